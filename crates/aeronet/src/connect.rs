@@ -0,0 +1,166 @@
+//! Connecting to a URL, using whichever IO layer its scheme calls for.
+//!
+//! See [`connect_url`].
+
+use {
+    bevy_ecs::{prelude::*, system::EntityCommands},
+    derive_more::{Display, Error},
+};
+
+/// IO layer which [`connect_url`] uses to connect to a URL.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ConnectBackend {
+    /// [`aeronet_websocket`], used for `ws://` and `wss://` URLs.
+    #[cfg(feature = "websocket")]
+    WebSocket,
+    /// [`aeronet_webtransport`], used for `https://` URLs.
+    #[cfg(feature = "webtransport")]
+    WebTransport,
+}
+
+impl ConnectBackend {
+    /// Picks the IO layer to use for connecting to `url`, based on its scheme.
+    ///
+    /// The scheme is matched case-insensitively.
+    ///
+    /// # Errors
+    ///
+    /// Errors if `url` has no scheme, or if no enabled IO layer handles the
+    /// scheme. `steam://` URLs are always rejected, since there is no Steam IO
+    /// layer to dispatch to.
+    pub fn for_url(url: &str) -> Result<Self, ConnectUrlError> {
+        let (scheme, _) = url.split_once("://").ok_or(ConnectUrlError::NoScheme)?;
+        match scheme.to_ascii_lowercase().as_str() {
+            #[cfg(feature = "websocket")]
+            "ws" | "wss" => Ok(Self::WebSocket),
+            #[cfg(feature = "webtransport")]
+            "https" => Ok(Self::WebTransport),
+            _ => Err(ConnectUrlError::UnsupportedScheme {
+                scheme: scheme.to_owned(),
+            }),
+        }
+    }
+}
+
+/// Configuration for each IO layer which [`connect_url`] may connect with.
+///
+/// Only the configuration for the IO layer picked by
+/// [`ConnectBackend::for_url`] is used.
+pub struct ConnectConfig {
+    /// Configuration used for [`ConnectBackend::WebSocket`].
+    #[cfg(feature = "websocket")]
+    pub websocket: aeronet_websocket::client::ClientConfig,
+    /// Configuration used for [`ConnectBackend::WebTransport`].
+    #[cfg(feature = "webtransport")]
+    pub webtransport: aeronet_webtransport::client::ClientConfig,
+}
+
+/// Failed to start connecting via [`connect_url`].
+#[derive(Debug, Clone, PartialEq, Eq, Display, Error)]
+pub enum ConnectUrlError {
+    /// URL does not start with a `scheme://`.
+    #[display("URL has no scheme")]
+    NoScheme,
+    /// No enabled IO layer can connect to URLs with this scheme.
+    #[display("unsupported URL scheme `{scheme}`")]
+    UnsupportedScheme {
+        /// Scheme of the URL, without the `://`.
+        scheme: String,
+    },
+}
+
+/// Spawns a new session entity, and starts connecting it to `url` using the IO
+/// layer that the URL's scheme calls for.
+///
+/// This lets you have a single code path for connecting, instead of one per IO
+/// layer. Which schemes are supported depends on the enabled features:
+///
+/// | Scheme            | IO layer                 | Feature        |
+/// |-------------------|--------------------------|----------------|
+/// | `ws://`, `wss://` | [`aeronet_websocket`]    | `websocket`    |
+/// | `https://`        | [`aeronet_webtransport`] | `webtransport` |
+///
+/// The IO layer's client plugin must be added to the app, as if you were
+/// connecting with that IO layer directly.
+///
+/// The returned [`EntityCommands`] can be used to insert your own components
+/// into the session entity.
+///
+/// # Errors
+///
+/// Errors if no IO layer can handle the URL, in which case no entity is
+/// spawned. See [`ConnectBackend::for_url`].
+///
+/// # Examples
+///
+/// ```
+/// use {
+///     aeronet::connect::{ConnectConfig, connect_url},
+///     bevy_ecs::prelude::*,
+/// };
+///
+/// #[derive(Component)]
+/// struct MyClient;
+///
+/// fn connect(mut commands: Commands, config: ConnectConfig) {
+///     match connect_url(&mut commands, "wss://[::1]:1234", config) {
+///         Ok(mut session) => {
+///             session.insert(MyClient);
+///         }
+///         Err(err) => {
+///             eprintln!("failed to connect: {err}");
+///         }
+///     }
+/// }
+/// ```
+pub fn connect_url<'a>(
+    commands: &'a mut Commands,
+    url: impl Into<String>,
+    config: ConnectConfig,
+) -> Result<EntityCommands<'a>, ConnectUrlError> {
+    let url = url.into();
+    let backend = ConnectBackend::for_url(&url)?;
+    let mut session = commands.spawn_empty();
+    match backend {
+        #[cfg(feature = "websocket")]
+        ConnectBackend::WebSocket => {
+            session.queue(aeronet_websocket::client::WebSocketClient::connect(
+                config.websocket,
+                url,
+            ));
+        }
+        #[cfg(feature = "webtransport")]
+        ConnectBackend::WebTransport => {
+            session.queue(aeronet_webtransport::client::WebTransportClient::connect(
+                config.webtransport,
+                url,
+            ));
+        }
+    }
+    Ok(session)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unsupported_schemes() {
+        assert_eq!(
+            Err(ConnectUrlError::NoScheme),
+            ConnectBackend::for_url("[::1]:1234")
+        );
+        for (url, scheme) in [
+            ("steam://76561197960287930", "steam"),
+            ("http://[::1]:1234", "http"),
+            ("ftp://[::1]:1234", "ftp"),
+        ] {
+            assert_eq!(
+                Err(ConnectUrlError::UnsupportedScheme {
+                    scheme: scheme.to_owned()
+                }),
+                ConnectBackend::for_url(url)
+            );
+        }
+    }
+}
@@ -0,0 +1,425 @@
+//! Discovering servers on the local network, without entering their address
+//! manually.
+//!
+//! Servers announce themselves by periodically broadcasting a small UDP packet
+//! containing a [`LanAnnounce`], and clients listen for these packets and
+//! collect them into [`DiscoveredServers`]. This is independent of the IO layer
+//! that the server actually uses - the announcement only tells clients where
+//! to connect to, via [`LanAnnounce::endpoint`].
+//!
+//! # Usage
+//!
+//! Add [`LanDiscoveryPlugin`] to your app.
+//!
+//! On the server, insert a [`LanAnnounce`] into your server entity. While it
+//! exists, it is sent out every [`LanDiscoveryConfig::announce_interval`], and
+//! as soon as it is changed.
+//!
+//! On the client, insert a [`DiscoveredServers`] created with
+//! [`DiscoveredServers::bind`]. While it exists, it collects the announcements
+//! it receives, and forgets servers which have not announced themselves in
+//! [`LanDiscoveryConfig::expire_after`].
+//!
+//! ```
+//! use {
+//!     aeronet::discovery::{DiscoveredServers, LanAnnounce},
+//!     bevy_ecs::prelude::*,
+//! };
+//!
+//! fn start_announcing(mut commands: Commands, server: Entity) {
+//!     commands.entity(server).insert(LanAnnounce {
+//!         name: "My Server".into(),
+//!         players: 0,
+//!         endpoint: "wss://192.168.0.10:25565".into(),
+//!     });
+//! }
+//!
+//! fn start_discovering(mut commands: Commands) {
+//!     let servers = DiscoveredServers::bind_default().expect("failed to bind socket");
+//!     commands.insert_resource(servers);
+//! }
+//!
+//! fn list_servers(servers: Res<DiscoveredServers>) {
+//!     for server in servers.iter() {
+//!         println!(
+//!             "{} ({} players) at {}",
+//!             server.info.name, server.info.players, server.info.endpoint
+//!         );
+//!     }
+//! }
+//! ```
+
+use {
+    aeronet_io::IoSet,
+    bevy_app::prelude::*,
+    bevy_ecs::prelude::*,
+    core::{
+        net::{Ipv4Addr, Ipv6Addr, SocketAddr},
+        time::Duration,
+    },
+    socket2::{Domain, Protocol, Socket, Type},
+    std::{io, net::UdpSocket},
+    tracing::{debug, trace},
+    web_time::Instant,
+};
+
+/// Port which servers announce themselves to, and clients listen on, by
+/// default.
+pub const DEFAULT_DISCOVERY_PORT: u16 = 25_588;
+
+/// Maximum length of an encoded [`LanAnnounce`] packet.
+///
+/// This is small enough to fit in a single datagram on any network.
+pub const MAX_ANNOUNCE_LEN: usize = 1024;
+
+/// Identifies a packet as a [`LanAnnounce`], and the version of its encoding.
+const HEADER: &[u8] = b"aeronet-lan\x00";
+
+/// Sends out [`LanAnnounce`]s and collects them into [`DiscoveredServers`].
+///
+/// See [`discovery`](crate::discovery).
+#[derive(Debug)]
+pub struct LanDiscoveryPlugin;
+
+impl Plugin for LanDiscoveryPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<LanDiscoveryConfig>()
+            .add_systems(PreUpdate, recv_announces.in_set(IoSet::Poll))
+            .add_systems(PostUpdate, send_announces.in_set(IoSet::Flush));
+    }
+}
+
+/// Configures how [`LanDiscoveryPlugin`] sends and forgets announcements.
+#[derive(Debug, Clone, Resource)]
+pub struct LanDiscoveryConfig {
+    /// Address which [`LanAnnounce`]s are sent to.
+    ///
+    /// By default, this is the IPv4 broadcast address on
+    /// [`DEFAULT_DISCOVERY_PORT`], so every client on the local network which
+    /// is listening on that port receives it. For IPv6 networks, set this to a
+    /// multicast address instead.
+    pub announce_to: SocketAddr,
+    /// How often each [`LanAnnounce`] is sent out.
+    ///
+    /// Announcements are also sent out as soon as they are changed.
+    ///
+    /// Defaults to 1 second.
+    pub announce_interval: Duration,
+    /// How long a server may go without announcing itself before it is
+    /// removed from [`DiscoveredServers`].
+    ///
+    /// This should be a few times longer than the server's
+    /// [`LanDiscoveryConfig::announce_interval`], so that a few lost
+    /// announcements don't cause the server to disappear.
+    ///
+    /// Defaults to 5 seconds.
+    pub expire_after: Duration,
+}
+
+impl Default for LanDiscoveryConfig {
+    fn default() -> Self {
+        Self {
+            announce_to: SocketAddr::from((Ipv4Addr::BROADCAST, DEFAULT_DISCOVERY_PORT)),
+            announce_interval: Duration::from_secs(1),
+            expire_after: Duration::from_secs(5),
+        }
+    }
+}
+
+/// Information about a server, which is announced to clients on the local
+/// network.
+///
+/// Insert this into an entity (usually your server entity) to start announcing
+/// it, and mutate it to update the information that clients see.
+///
+/// The encoded announcement must fit in [`MAX_ANNOUNCE_LEN`] bytes - if it
+/// doesn't, it is not sent.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Component)]
+pub struct LanAnnounce {
+    /// Human-readable name of the server.
+    pub name: String,
+    /// Number of players currently connected to the server.
+    pub players: u32,
+    /// Where clients should connect to, to join this server.
+    ///
+    /// This is opaque to the discovery logic - it may be a URL, a socket
+    /// address, or anything else your client knows how to connect to. Note
+    /// that the server may not know its own address on the local network; if
+    /// so, clients can use [`DiscoveredServer::source`] instead.
+    pub endpoint: String,
+}
+
+/// Servers on the local network which have announced themselves to us.
+///
+/// See [`discovery`](crate::discovery).
+#[derive(Debug, Resource)]
+pub struct DiscoveredServers {
+    socket: UdpSocket,
+    servers: Vec<DiscoveredServer>,
+}
+
+/// Server which has announced itself to [`DiscoveredServers`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiscoveredServer {
+    /// Address which the announcement was sent from.
+    ///
+    /// The IP address is the server's address on the local network, but the
+    /// port is the one that the server sends announcements from, not the one
+    /// that it accepts connections on.
+    pub source: SocketAddr,
+    /// Latest information that the server has announced.
+    pub info: LanAnnounce,
+    /// When we last received an announcement from this server.
+    pub last_seen: Instant,
+}
+
+impl DiscoveredServers {
+    /// Starts listening for announcements on `addr`.
+    ///
+    /// Multiple sockets on the same machine may listen on the same address,
+    /// so that multiple clients can discover servers at once.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the socket could not be created or bound.
+    pub fn bind(addr: SocketAddr) -> io::Result<Self> {
+        let socket = Socket::new(Domain::for_address(addr), Type::DGRAM, Some(Protocol::UDP))?;
+        socket.set_nonblocking(true)?;
+        socket.set_reuse_address(true)?;
+        #[cfg(all(unix, not(any(target_os = "solaris", target_os = "illumos"))))]
+        socket.set_reuse_port(true)?;
+        socket.bind(&addr.into())?;
+        Ok(Self {
+            socket: socket.into(),
+            servers: Vec::new(),
+        })
+    }
+
+    /// Starts listening for announcements on all IPv4 interfaces on
+    /// [`DEFAULT_DISCOVERY_PORT`].
+    ///
+    /// # Errors
+    ///
+    /// See [`DiscoveredServers::bind`].
+    pub fn bind_default() -> io::Result<Self> {
+        Self::bind(SocketAddr::from((
+            Ipv4Addr::UNSPECIFIED,
+            DEFAULT_DISCOVERY_PORT,
+        )))
+    }
+
+    /// Gets the local address that we are listening for announcements on.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the address could not be read from the socket.
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.socket.local_addr()
+    }
+
+    /// Gets the servers which have announced themselves to us, in the order
+    /// that they were first discovered.
+    #[must_use]
+    pub fn iter(&self) -> impl ExactSizeIterator<Item = &DiscoveredServer> {
+        self.servers.iter()
+    }
+
+    /// Gets how many servers have announced themselves to us.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.servers.len()
+    }
+
+    /// Gets if no servers have announced themselves to us.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.servers.is_empty()
+    }
+}
+
+fn encode(announce: &LanAnnounce) -> Option<Vec<u8>> {
+    let mut packet = Vec::with_capacity(MAX_ANNOUNCE_LEN);
+    packet.extend_from_slice(HEADER);
+    packet.extend_from_slice(&announce.players.to_be_bytes());
+    for field in [&announce.name, &announce.endpoint] {
+        let len = u16::try_from(field.len()).ok()?;
+        packet.extend_from_slice(&len.to_be_bytes());
+        packet.extend_from_slice(field.as_bytes());
+    }
+    (packet.len() <= MAX_ANNOUNCE_LEN).then_some(packet)
+}
+
+fn decode(packet: &[u8]) -> Option<LanAnnounce> {
+    fn read_str(packet: &mut &[u8]) -> Option<String> {
+        let (len, rest) = packet.split_first_chunk::<2>()?;
+        let (str, rest) = rest.split_at_checked(usize::from(u16::from_be_bytes(*len)))?;
+        *packet = rest;
+        String::from_utf8(str.to_vec()).ok()
+    }
+
+    let mut packet = packet.strip_prefix(HEADER)?;
+    let (players, rest) = packet.split_first_chunk::<4>()?;
+    packet = rest;
+    let name = read_str(&mut packet)?;
+    let endpoint = read_str(&mut packet)?;
+    Some(LanAnnounce {
+        name,
+        players: u32::from_be_bytes(*players),
+        endpoint,
+    })
+}
+
+fn bind_announce_socket(announce_to: SocketAddr) -> io::Result<UdpSocket> {
+    let bind_addr = match announce_to {
+        SocketAddr::V4(_) => SocketAddr::from((Ipv4Addr::UNSPECIFIED, 0)),
+        SocketAddr::V6(_) => SocketAddr::from((Ipv6Addr::UNSPECIFIED, 0)),
+    };
+    let socket = UdpSocket::bind(bind_addr)?;
+    socket.set_nonblocking(true)?;
+    if announce_to.is_ipv4() {
+        socket.set_broadcast(true)?;
+    }
+    Ok(socket)
+}
+
+#[derive(Debug, Default)]
+struct AnnounceState {
+    socket: Option<UdpSocket>,
+    last_sent: Option<Instant>,
+}
+
+fn send_announces(
+    mut state: Local<AnnounceState>,
+    config: Res<LanDiscoveryConfig>,
+    announces: Query<(Entity, Ref<LanAnnounce>)>,
+) {
+    if announces.is_empty() {
+        return;
+    }
+
+    let now = Instant::now();
+    let due = state.last_sent.is_none_or(|last_sent| {
+        now.saturating_duration_since(last_sent) >= config.announce_interval
+    });
+    if due {
+        state.last_sent = Some(now);
+    }
+
+    if config.is_changed() {
+        state.socket = None;
+    }
+    let socket = match state.socket.take() {
+        Some(socket) => socket,
+        None => match bind_announce_socket(config.announce_to) {
+            Ok(socket) => socket,
+            Err(err) => {
+                debug!("Failed to bind announce socket: {err}");
+                return;
+            }
+        },
+    };
+
+    for (entity, announce) in &announces {
+        if !due && !announce.is_changed() {
+            continue;
+        }
+
+        let Some(packet) = encode(&announce) else {
+            debug!("Announcement for {entity} is longer than {MAX_ANNOUNCE_LEN} bytes");
+            continue;
+        };
+        if let Err(err) = socket.send_to(&packet, config.announce_to) {
+            debug!(
+                "Failed to send announcement for {entity} to {}: {err}",
+                config.announce_to
+            );
+        }
+    }
+    state.socket = Some(socket);
+}
+
+fn recv_announces(config: Res<LanDiscoveryConfig>, discovered: Option<ResMut<DiscoveredServers>>) {
+    let Some(mut discovered) = discovered else {
+        return;
+    };
+
+    let now = Instant::now();
+    // only trigger change detection if the servers actually changed
+    let servers = discovered.bypass_change_detection();
+    let mut changed = false;
+    let mut buf = [0; MAX_ANNOUNCE_LEN];
+    loop {
+        let (len, source) = match servers.socket.recv_from(&mut buf) {
+            Ok(recv) => recv,
+            Err(err) if err.kind() == io::ErrorKind::WouldBlock => break,
+            Err(err) => {
+                debug!("Failed to receive announcement: {err}");
+                break;
+            }
+        };
+        let Some(info) = decode(&buf[..len]) else {
+            trace!(%source, "Received invalid announcement");
+            continue;
+        };
+
+        if let Some(server) = servers
+            .servers
+            .iter_mut()
+            .find(|server| server.source == source && server.info.endpoint == info.endpoint)
+        {
+            server.last_seen = now;
+            if server.info != info {
+                server.info = info;
+                changed = true;
+            }
+        } else {
+            debug!(%source, endpoint = info.endpoint, "Discovered server");
+            servers.servers.push(DiscoveredServer {
+                source,
+                info,
+                last_seen: now,
+            });
+            changed = true;
+        }
+    }
+
+    let num_servers = servers.servers.len();
+    servers
+        .servers
+        .retain(|server| now.saturating_duration_since(server.last_seen) < config.expire_after);
+    if changed || servers.servers.len() != num_servers {
+        discovered.set_changed();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![expect(clippy::unwrap_used, reason = "testing")]
+
+    use super::*;
+
+    #[test]
+    fn encode_decode() {
+        let announce = LanAnnounce {
+            name: "My Server".into(),
+            players: 12,
+            endpoint: "wss://192.168.0.10:25565".into(),
+        };
+        let packet = encode(&announce).unwrap();
+        assert_eq!(Some(announce), decode(&packet));
+
+        // truncated packets are rejected instead of panicking
+        for len in 0..packet.len() {
+            assert_eq!(None, decode(&packet[..len]));
+        }
+    }
+
+    #[test]
+    fn encode_too_long() {
+        let announce = LanAnnounce {
+            endpoint: "a".repeat(MAX_ANNOUNCE_LEN),
+            ..Default::default()
+        };
+        assert_eq!(None, encode(&announce));
+    }
+}
@@ -4,8 +4,20 @@
 //! ## Feature flags
 #![cfg_attr(feature = "document-features", doc = document_features::document_features!())]
 
+#[cfg(any(feature = "websocket", feature = "webtransport"))]
+pub mod connect;
+#[cfg(feature = "discovery")]
+pub mod discovery;
+
+#[cfg(feature = "websocket")]
+pub use aeronet_websocket as websocket;
+#[cfg(feature = "webtransport")]
+pub use aeronet_webtransport as webtransport;
 use bevy_app::{PluginGroupBuilder, prelude::*};
-pub use {aeronet_io as io, aeronet_transport as transport};
+pub use {
+    aeronet_io::{self as io, now},
+    aeronet_transport as transport,
+};
 
 /// Adds the default networking plugins.
 ///
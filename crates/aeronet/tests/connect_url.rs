@@ -0,0 +1,49 @@
+#![expect(missing_docs, clippy::unwrap_used, reason = "testing")]
+
+use {
+    aeronet::{
+        connect::{ConnectBackend, ConnectConfig, connect_url},
+        websocket::client::{WebSocketClient, WebSocketClientPlugin},
+        webtransport::client::{WebTransportClient, WebTransportClientPlugin},
+    },
+    bevy_app::prelude::*,
+};
+
+fn config() -> ConnectConfig {
+    ConnectConfig {
+        websocket: aeronet::websocket::client::ClientConfig::builder().with_native_certs(),
+        webtransport: aeronet::webtransport::client::ClientConfig::builder()
+            .with_bind_default()
+            .with_native_certs()
+            .build(),
+    }
+}
+
+#[test]
+fn schemes_route_to_backend() {
+    let mut app = App::new();
+    app.add_plugins((WebSocketClientPlugin, WebTransportClientPlugin));
+
+    for (url, backend) in [
+        ("ws://[::1]:1", ConnectBackend::WebSocket),
+        ("wss://[::1]:1", ConnectBackend::WebSocket),
+        ("WSS://[::1]:1", ConnectBackend::WebSocket),
+        ("https://[::1]:1", ConnectBackend::WebTransport),
+    ] {
+        assert_eq!(Ok(backend), ConnectBackend::for_url(url));
+
+        let world = app.world_mut();
+        let session = connect_url(&mut world.commands(), url, config())
+            .unwrap()
+            .id();
+        world.flush();
+        assert_eq!(
+            backend == ConnectBackend::WebSocket,
+            world.get::<WebSocketClient>(session).is_some()
+        );
+        assert_eq!(
+            backend == ConnectBackend::WebTransport,
+            world.get::<WebTransportClient>(session).is_some()
+        );
+    }
+}
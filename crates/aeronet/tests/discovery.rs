@@ -0,0 +1,64 @@
+#![expect(missing_docs, clippy::unwrap_used, reason = "testing")]
+
+use {
+    aeronet::discovery::{DiscoveredServers, LanAnnounce, LanDiscoveryConfig, LanDiscoveryPlugin},
+    bevy_app::prelude::*,
+    core::{net::Ipv4Addr, time::Duration},
+    std::thread,
+};
+
+const MAX_UPDATES: usize = 1000;
+
+fn update_until(app: &mut App, mut f: impl FnMut(&DiscoveredServers) -> bool) {
+    for _ in 0..MAX_UPDATES {
+        app.update();
+        if f(app.world().resource::<DiscoveredServers>()) {
+            return;
+        }
+        thread::sleep(Duration::from_millis(5));
+    }
+    panic!("condition not met after {MAX_UPDATES} updates");
+}
+
+#[test]
+fn discover_announced_server() {
+    let mut app = App::new();
+    app.add_plugins(LanDiscoveryPlugin);
+
+    let discovered = DiscoveredServers::bind((Ipv4Addr::LOCALHOST, 0).into()).unwrap();
+    let listen_addr = discovered.local_addr().unwrap();
+    app.insert_resource(discovered)
+        .insert_resource(LanDiscoveryConfig {
+            announce_to: listen_addr,
+            expire_after: Duration::from_millis(200),
+            ..Default::default()
+        });
+
+    let announce = LanAnnounce {
+        name: "My Server".into(),
+        players: 3,
+        endpoint: "wss://127.0.0.1:25565".into(),
+    };
+    let server = app.world_mut().spawn(announce.clone()).id();
+    update_until(&mut app, |servers| !servers.is_empty());
+
+    let servers = app.world().resource::<DiscoveredServers>();
+    assert_eq!(1, servers.len());
+    let server_info = servers.iter().next().unwrap();
+    assert_eq!(announce, server_info.info);
+    assert_eq!(Ipv4Addr::LOCALHOST, server_info.source.ip());
+
+    // changes are announced straight away, instead of on the next interval
+    app.world_mut()
+        .get_mut::<LanAnnounce>(server)
+        .unwrap()
+        .players = 4;
+    update_until(&mut app, |servers| {
+        servers.iter().next().unwrap().info.players == 4
+    });
+    assert_eq!(1, app.world().resource::<DiscoveredServers>().len());
+
+    // once the server stops announcing itself, it's forgotten
+    app.world_mut().despawn(server);
+    update_until(&mut app, DiscoveredServers::is_empty);
+}
@@ -4,7 +4,10 @@
 use {
     aeronet_io::{
         AeronetIoPlugin, IoSet, Session, SessionEndpoint,
-        connection::{DROP_DISCONNECT_REASON, Disconnect, DisconnectReason, Disconnected},
+        connection::{
+            DROP_DISCONNECT_REASON, Disconnect, DisconnectReason, Disconnected,
+            DisconnectedRetained,
+        },
         packet::RecvPacket,
     },
     bevy_app::prelude::*,
@@ -148,7 +151,7 @@ fn on_io_added(trigger: Trigger<OnAdd, ChannelIo>, mut commands: Commands) {
 
 fn on_disconnect(trigger: Trigger<Disconnect>, mut sessions: Query<&mut ChannelIo>) {
     let entity = trigger.entity();
-    let Disconnect { reason } = trigger.event();
+    let Disconnect { reason, .. } = trigger.event();
     let Ok(mut io) = sessions.get_mut(entity) else {
         return;
     };
@@ -158,7 +161,11 @@ fn on_disconnect(trigger: Trigger<Disconnect>, mut sessions: Query<&mut ChannelI
     }
 }
 
-fn poll(mut commands: Commands, mut sessions: Query<(Entity, &mut Session, &mut ChannelIo)>) {
+// a retained session has already seen its disconnect, so stop polling it
+fn poll(
+    mut commands: Commands,
+    mut sessions: Query<(Entity, &mut Session, &mut ChannelIo), Without<DisconnectedRetained>>,
+) {
     for (entity, mut session, mut io) in &mut sessions {
         let span = trace_span!("poll", %entity);
         let _span = span.enter();
@@ -171,7 +178,7 @@ fn poll(mut commands: Commands, mut sessions: Query<(Entity, &mut Session, &mut
             Err(oneshot::TryRecvError::Empty) => None,
         };
         if let Some(reason) = dc_reason {
-            commands.trigger_targets(Disconnected { reason }, entity);
+            commands.trigger_targets(Disconnected { reason, code: None }, entity);
             continue;
         }
 
@@ -198,7 +205,7 @@ fn poll(mut commands: Commands, mut sessions: Query<(Entity, &mut Session, &mut
     }
 }
 
-fn flush(mut sessions: Query<(Entity, &mut Session, &ChannelIo)>) {
+fn flush(mut sessions: Query<(Entity, &mut Session, &ChannelIo), Without<DisconnectedRetained>>) {
     for (entity, mut session, io) in &mut sessions {
         let span = trace_span!("flush", %entity);
         let _span = span.enter();
@@ -3,8 +3,8 @@
 use {
     aeronet_channel::{ChannelIo, ChannelIoPlugin},
     aeronet_io::{
-        Session,
-        connection::{Disconnect, DisconnectReason, Disconnected},
+        Session, conformance,
+        connection::{APP_EXIT_DISCONNECT_REASON, Disconnect, DisconnectReason, Disconnected},
     },
     bevy::{log::LogPlugin, prelude::*},
 };
@@ -31,6 +31,11 @@ fn setup() -> (App, Entity, Entity) {
     (app, a, b)
 }
 
+#[test]
+fn conformance() {
+    conformance::run(setup);
+}
+
 #[test]
 fn events_connect() {
     #[derive(Default, Resource)]
@@ -116,3 +121,38 @@ fn events_disconnect() {
         app.world().resource::<WhoDisconnected>().0
     );
 }
+
+#[test]
+fn disconnect_on_app_exit() {
+    #[derive(Default, Resource)]
+    struct PeerReason(Option<String>);
+
+    // the two sides live in separate apps, like a client and server would
+    let (io_a, io_b) = ChannelIo::new();
+    let mut app_a = app();
+    let mut app_b = app();
+    app_a.world_mut().spawn(io_a);
+    app_b.world_mut().spawn(io_b);
+    app_a.update();
+    app_b.update();
+
+    app_b.init_resource::<PeerReason>().add_observer(
+        |trigger: Trigger<Disconnected>, mut peer_reason: ResMut<PeerReason>| {
+            let DisconnectReason::Peer(reason) = &trigger.event().reason else {
+                panic!("should be disconnected by the peer");
+            };
+            peer_reason.0 = Some(reason.clone());
+        },
+    );
+
+    app_a.world_mut().send_event(AppExit::Success);
+    app_a.update();
+    assert!(app_a.should_exit().is_some());
+    drop(app_a);
+    app_b.update();
+
+    assert_eq!(
+        Some(APP_EXIT_DISCONNECT_REASON),
+        app_b.world().resource::<PeerReason>().0.as_deref()
+    );
+}
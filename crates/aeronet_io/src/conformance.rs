@@ -0,0 +1,210 @@
+//! Conformance suite which checks that an IO layer implementation upholds the
+//! [`Session`] contract.
+//!
+//! IO layers should run this suite in their integration tests, to make sure
+//! that code written against [`Session`] behaves the same no matter which IO
+//! layer it runs on. To run it, write a function which creates an [`App`] with
+//! two sessions connected to each other (e.g. a client and the server's session
+//! for that client) over a local connection, and pass it to [`run`]. Each check
+//! creates its own pair of sessions, and may also be run individually.
+//!
+//! The checks assume that the connection between the two sessions does not
+//! lose packets, which is the case for loopback connections in practice, even
+//! for IO layers which are unreliable in general.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use {aeronet_io::conformance, bevy_app::prelude::*, bevy_ecs::prelude::*};
+//!
+//! fn setup() -> (App, Entity, Entity) {
+//!     // open a server, connect a client to it, and wait until both are
+//!     // connected...
+//!     # unimplemented!()
+//! }
+//!
+//! #[test]
+//! fn conformance() {
+//!     conformance::run(setup);
+//! }
+//! ```
+
+use {
+    crate::{
+        Session,
+        connection::{Disconnect, DisconnectReason, Disconnected},
+    },
+    bevy_app::prelude::*,
+    bevy_ecs::prelude::*,
+    bytes::Bytes,
+    core::time::Duration,
+    std::thread,
+};
+
+/// Maximum number of updates that a check waits for something to happen
+/// before failing.
+pub const MAX_UPDATES: usize = 1000;
+
+/// Maximum size of packet sent by [`mtu_sized_packets`].
+///
+/// Some IO layers, such as in-memory channels, have a practically unlimited
+/// MTU, so the check is capped to this size.
+pub const MAX_CHECKED_MTU: usize = 64 * 1024;
+
+/// Runs every check in this suite.
+///
+/// `setup` must return an [`App`] with two sessions which are connected to
+/// each other, i.e. both already have [`Session`].
+///
+/// # Panics
+///
+/// Panics if any check fails.
+pub fn run(mut setup: impl FnMut() -> (App, Entity, Entity)) {
+    let (mut app, a, b) = setup();
+    round_trip(&mut app, a, b);
+
+    let (mut app, a, b) = setup();
+    mtu_sized_packets(&mut app, a, b);
+
+    let (mut app, a, b) = setup();
+    disconnect_reason(&mut app, a, b);
+}
+
+/// Checks that packets sent by either session arrive at its peer unchanged,
+/// and are counted in both sessions' [`Session::stats`].
+///
+/// # Panics
+///
+/// Panics if the check fails.
+pub fn round_trip(app: &mut App, a: Entity, b: Entity) {
+    const PACKETS: [&[u8]; 3] = [b"packet 1", b"packet 2", b"\x00\xff\x80"];
+
+    for (from, to) in [(a, b), (b, a)] {
+        let sent_before = session(app, from).stats;
+        let recv_before = session(app, to).stats;
+
+        session_mut(app, from)
+            .send
+            .extend(PACKETS.map(Bytes::from_static));
+        let mut packets = recv_packets(app, to, PACKETS.len());
+
+        // the IO layer may not preserve the order of packets
+        packets.sort();
+        let mut expected = PACKETS.to_vec();
+        expected.sort_unstable();
+        assert_eq!(expected, packets, "{to} received wrong packets from {from}");
+
+        let num_bytes = PACKETS.iter().map(|packet| packet.len()).sum::<usize>();
+        let sent = session(app, from).stats - sent_before;
+        let recv = session(app, to).stats - recv_before;
+        assert_eq!(PACKETS.len(), sent.packets_sent.0, "{from} packets sent");
+        assert_eq!(num_bytes, sent.bytes_sent.0, "{from} bytes sent");
+        assert_eq!(PACKETS.len(), recv.packets_recv.0, "{to} packets received");
+        assert_eq!(num_bytes, recv.bytes_recv.0, "{to} bytes received");
+    }
+}
+
+/// Checks that a packet of exactly the sender's [`Session::mtu`] bytes (up to
+/// [`MAX_CHECKED_MTU`]) arrives at its peer unchanged.
+///
+/// # Panics
+///
+/// Panics if the check fails.
+pub fn mtu_sized_packets(app: &mut App, a: Entity, b: Entity) {
+    for (from, to) in [(a, b), (b, a)] {
+        let mtu = session(app, from).mtu().min(MAX_CHECKED_MTU);
+        #[expect(clippy::cast_possible_truncation, reason = "truncation is intended")]
+        let packet = (0..mtu).map(|i| i as u8).collect::<Bytes>();
+
+        session_mut(app, from).send.push(packet.clone());
+        let packets = recv_packets(app, to, 1);
+        assert_eq!(
+            vec![packet],
+            packets,
+            "{to} received wrong packet of {mtu} bytes from {from}"
+        );
+    }
+}
+
+/// Checks that when a session is disconnected with [`Disconnect`], it is
+/// [`Disconnected`] with [`DisconnectReason::User`], and its peer is
+/// [`Disconnected`] with [`DisconnectReason::Peer`] with the same reason.
+///
+/// # Panics
+///
+/// Panics if the check fails.
+pub fn disconnect_reason(app: &mut App, a: Entity, b: Entity) {
+    const REASON: &str = "conformance disconnect";
+
+    #[derive(Debug, Default, Resource)]
+    struct Reasons(Vec<(Entity, DisconnectReason<String>)>);
+
+    app.init_resource::<Reasons>().add_observer(
+        |trigger: Trigger<Disconnected>, mut reasons: ResMut<Reasons>| {
+            let reason = match &trigger.event().reason {
+                DisconnectReason::User(reason) => DisconnectReason::User(reason.clone()),
+                DisconnectReason::Peer(reason) => DisconnectReason::Peer(reason.clone()),
+                DisconnectReason::Error(err) => DisconnectReason::Error(format!("{err:#}")),
+            };
+            reasons.0.push((trigger.entity(), reason));
+        },
+    );
+
+    app.world_mut().trigger_targets(Disconnect::new(REASON), a);
+    update_until(app, |world| {
+        (world.resource::<Reasons>().0.len() >= 2).then_some(())
+    });
+
+    let reasons = &app.world().resource::<Reasons>().0;
+    let reason_of = |session: Entity| {
+        reasons
+            .iter()
+            .find(|(entity, _)| *entity == session)
+            .map(|(_, reason)| reason)
+    };
+    assert!(
+        matches!(reason_of(a), Some(DisconnectReason::User(reason)) if reason == REASON),
+        "{a} disconnected with {:?}",
+        reason_of(a)
+    );
+    assert!(
+        matches!(reason_of(b), Some(DisconnectReason::Peer(reason)) if reason == REASON),
+        "{b} disconnected with {:?}",
+        reason_of(b)
+    );
+}
+
+fn session(app: &App, entity: Entity) -> &Session {
+    app.world()
+        .get::<Session>(entity)
+        .unwrap_or_else(|| panic!("{entity} should have `Session`"))
+}
+
+fn session_mut(app: &mut App, entity: Entity) -> Mut<'_, Session> {
+    app.world_mut()
+        .get_mut::<Session>(entity)
+        .unwrap_or_else(|| panic!("{entity} should have `Session`"))
+}
+
+fn update_until<T>(app: &mut App, mut f: impl FnMut(&mut World) -> Option<T>) -> T {
+    for _ in 0..MAX_UPDATES {
+        app.update();
+        if let Some(value) = f(app.world_mut()) {
+            return value;
+        }
+        thread::sleep(Duration::from_millis(5));
+    }
+    panic!("condition not met after {MAX_UPDATES} updates");
+}
+
+fn recv_packets(app: &mut App, entity: Entity, count: usize) -> Vec<Bytes> {
+    let mut packets = Vec::new();
+    update_until(app, |world| {
+        let mut session = world
+            .get_mut::<Session>(entity)
+            .unwrap_or_else(|| panic!("{entity} should have `Session`"));
+        packets.extend(session.recv.drain(..).map(|packet| packet.payload));
+        (packets.len() >= count).then_some(())
+    });
+    packets
+}
@@ -1,13 +1,15 @@
 //! Logic for connection and disconnection of a [`Session`].
 
 use {
-    crate::{Session, SessionEndpoint},
+    crate::{IoSet, Session, SessionEndpoint, packet, server::ServerEndpoint},
     bevy_app::prelude::*,
     bevy_derive::Deref,
     bevy_ecs::prelude::*,
-    bevy_hierarchy::DespawnRecursiveExt,
-    core::{fmt::Debug, net::SocketAddr},
+    bevy_hierarchy::{DespawnRecursiveExt, Parent},
+    bevy_reflect::prelude::*,
+    core::{fmt::Debug, net::SocketAddr, time::Duration},
     tracing::debug,
+    web_time::Instant,
 };
 
 #[derive(Debug)]
@@ -15,10 +17,31 @@ pub(crate) struct ConnectionPlugin;
 
 impl Plugin for ConnectionPlugin {
     fn build(&self, app: &mut App) {
-        app.add_observer(on_connecting)
+        app.register_type::<ConnectTiming>()
+            .register_type::<IdleDetection>()
+            .register_type::<DisconnectDespawn>()
+            .register_type::<DisconnectedRetained>()
+            .register_type::<AppExitTimeout>()
+            .register_type::<Unflushed>()
+            .init_resource::<AppExitTimeout>()
+            .add_observer(on_connecting)
             .add_observer(on_connected)
             .add_observer(on_disconnect)
-            .add_observer(on_disconnected);
+            .add_observer(on_disconnected)
+            .add_observer(on_retained_removed)
+            .add_systems(
+                Last,
+                ((hold_exit, disconnect_on_exit).chain(), release_retained),
+            )
+            .add_systems(
+                PreUpdate,
+                detect_idle
+                    .after(IoSet::Poll)
+                    .after(packet::update_last_recv),
+            );
+
+        #[cfg(feature = "test-utils")]
+        app.add_observer(on_simulate_disconnect);
     }
 }
 
@@ -44,6 +67,9 @@ impl Plugin for ConnectionPlugin {
 /// commands.trigger_targets(Disconnect::new("show's over everyone, go home"), [
 ///     session1, session2,
 /// ]);
+///
+/// // disconnect with an application-defined code
+/// commands.trigger_targets(Disconnect::new("banned").with_code(403), session);
 /// # }
 /// ```
 #[derive(Debug, Clone, PartialEq, Eq, Event)]
@@ -52,6 +78,13 @@ pub struct Disconnect {
     ///
     /// Will be used as the reason in [`DisconnectReason::User`].
     pub reason: String,
+    /// Application-defined code to close the connection with.
+    ///
+    /// This lets the peer react to the disconnection programmatically, without
+    /// parsing the reason string. Only IO layers whose protocol has a notion of
+    /// close codes (i.e. WebTransport) will transmit this to the peer - other
+    /// IO layers ignore it. See [`Disconnected::code`].
+    pub code: Option<u32>,
 }
 
 impl Disconnect {
@@ -60,13 +93,57 @@ impl Disconnect {
     pub fn new(reason: impl Into<String>) -> Self {
         Self {
             reason: reason.into(),
+            code: None,
         }
     }
+
+    /// Sets the [`Disconnect::code`] to close the connection with.
+    #[must_use]
+    pub const fn with_code(mut self, code: u32) -> Self {
+        self.code = Some(code);
+        self
+    }
 }
 
+/// Triggered to make a [`Session`] disconnect as if its connection had failed,
+/// for testing how your app handles connection problems.
+///
+/// Unlike [`Disconnect`], which is a graceful disconnection requested by the
+/// user, this triggers [`Disconnected`] with a [`DisconnectReason::Error`]
+/// containing a [`SimulatedDisconnect`] - the same category of reason that an
+/// IO layer uses when e.g. the network drops.
+///
+/// This only simulates the failure on our side. The IO layer is not told about
+/// this, so the session is despawned in the same way as any other session
+/// entity being despawned. This drops the IO layer's components, and most IO
+/// layers send [`DROP_DISCONNECT_REASON`] to the peer when dropped, so the peer
+/// will usually see a graceful [`DisconnectReason::Peer`] rather than a
+/// connection failure.
+///
+/// # Examples
+///
+/// ```
+/// use {aeronet_io::connection::SimulateDisconnect, bevy_ecs::prelude::*};
+///
+/// # fn run(mut commands: Commands, session: Entity) {
+/// commands.trigger_targets(SimulateDisconnect, session);
+/// # }
+/// ```
+#[cfg(feature = "test-utils")]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Event)]
+pub struct SimulateDisconnect;
+
+/// Error used as the [`DisconnectReason::Error`] when a [`Session`] is
+/// disconnected via [`SimulateDisconnect`].
+#[cfg(feature = "test-utils")]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, derive_more::Display, derive_more::Error)]
+#[display("simulated connection failure")]
+pub struct SimulatedDisconnect;
+
 /// Triggered when a [`Session`] loses connection for any reason.
 ///
-/// Immediately after this, the session will be despawned.
+/// Immediately after this, the session will be despawned, unless it has a
+/// [`DisconnectDespawn`] which defers the despawn.
 ///
 /// This must only be triggered by the IO layer when it detects that the peer
 /// has disconnected from us, or when it detects a connection error.
@@ -77,6 +154,13 @@ impl Disconnect {
 pub struct Disconnected {
     /// Why the session was disconnected.
     pub reason: DisconnectReason<anyhow::Error>,
+    /// Application-defined code that the connection was closed with.
+    ///
+    /// For [`DisconnectReason::User`], this is the [`Disconnect::code`] that
+    /// we disconnected with. For [`DisconnectReason::Peer`], this is the code
+    /// that the peer disconnected us with, if the IO layer supports receiving
+    /// close codes. Otherwise, this is [`None`].
+    pub code: Option<u32>,
 }
 
 /// Why a [`Session`] was disconnected from its peer.
@@ -125,6 +209,139 @@ impl<E> From<E> for DisconnectReason<E> {
     }
 }
 
+/// Triggers [`SessionActivity`] on a [`Session`] when it stops receiving
+/// packets from its peer for a while, and when it starts receiving them again.
+///
+/// A session is idle if its [`Session::last_recv_at`] is at least
+/// [`IdleDetection::threshold`] ago. This only notifies you - the session is
+/// not disconnected when it goes idle.
+///
+/// # Examples
+///
+/// ```
+/// use {
+///     aeronet_io::connection::{IdleDetection, SessionActivity},
+///     bevy_ecs::prelude::*,
+///     core::time::Duration,
+/// };
+///
+/// # fn run(mut commands: Commands, session: Entity) {
+/// commands
+///     .entity(session)
+///     .insert(IdleDetection::new(Duration::from_secs(5)))
+///     .observe(|trigger: Trigger<SessionActivity>| {
+///         let session = trigger.entity();
+///         match trigger.event() {
+///             SessionActivity::Idle => println!("{session} went away"),
+///             SessionActivity::Active => println!("{session} is back"),
+///         }
+///     });
+/// # }
+/// ```
+#[derive(Debug, Clone, Component, Reflect)]
+#[reflect(Component)]
+pub struct IdleDetection {
+    /// How long a session may go without receiving any packets before it is
+    /// considered idle.
+    pub threshold: Duration,
+    idle: bool,
+}
+
+impl IdleDetection {
+    /// Creates a new [`IdleDetection`] with the given
+    /// [`IdleDetection::threshold`].
+    #[must_use]
+    pub const fn new(threshold: Duration) -> Self {
+        Self {
+            threshold,
+            idle: false,
+        }
+    }
+
+    /// Gets if the session is currently idle.
+    #[must_use]
+    pub const fn is_idle(&self) -> bool {
+        self.idle
+    }
+}
+
+/// Triggered on a [`Session`] with [`IdleDetection`] when it transitions
+/// between being active and idle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Event)]
+pub enum SessionActivity {
+    /// Session has not received any packets for at least
+    /// [`IdleDetection::threshold`].
+    Idle,
+    /// Session has received a packet after being [`SessionActivity::Idle`].
+    Active,
+}
+
+/// Controls when a [`Session`] is despawned after [`Disconnected`] is
+/// triggered on it.
+///
+/// By default, a session is despawned immediately after [`Disconnected`], so
+/// any code which wants to react to the disconnection must do so inside an
+/// observer for that event. If you want multiple systems to react to the
+/// disconnection, or read the session's final state (e.g. its stats) after the
+/// observers have run, insert this component on the session to defer the
+/// despawn.
+///
+/// When the despawn is deferred, [`DisconnectedRetained`] is inserted on the
+/// session instead of despawning it, and the session is despawned as soon as
+/// that marker is removed. While the session is retained, it is no longer
+/// connected: the IO layers and `aeronet_transport` provided by `aeronet` skip
+/// it when polling and flushing, so no more packets are sent or received, and
+/// further [`Disconnect`]s and [`Disconnected`]s are ignored. If you write your
+/// own IO layer, its systems must filter out sessions with
+/// [`DisconnectedRetained`] as well.
+///
+/// # Examples
+///
+/// ```
+/// use {
+///     aeronet_io::connection::{DisconnectDespawn, DisconnectedRetained},
+///     bevy_ecs::prelude::*,
+/// };
+///
+/// # fn run(mut commands: Commands, session: Entity) {
+/// commands.entity(session).insert(DisconnectDespawn::Retained);
+/// # }
+///
+/// fn finalize(sessions: Query<Entity, With<DisconnectedRetained>>, mut commands: Commands) {
+///     for session in &sessions {
+///         // read the session's final state here..
+///         commands.entity(session).remove::<DisconnectedRetained>();
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Component, Reflect)]
+#[reflect(Component)]
+pub enum DisconnectDespawn {
+    /// Session is despawned immediately after [`Disconnected`] is triggered.
+    #[default]
+    Immediate,
+    /// Session is retained until the end of the update after the one in which
+    /// it was disconnected, then despawned automatically.
+    ///
+    /// This guarantees that every system gets at least one chance to observe
+    /// the [`DisconnectedRetained`] session. You may still remove
+    /// [`DisconnectedRetained`] earlier to despawn the session sooner.
+    NextUpdate,
+    /// Session is retained until the app removes [`DisconnectedRetained`] from
+    /// it.
+    ///
+    /// If you never remove the marker, the session is never despawned.
+    Retained,
+}
+
+/// Marker inserted on a [`Session`] which has been [`Disconnected`], but whose
+/// despawn has been deferred by [`DisconnectDespawn`].
+///
+/// Remove this component to despawn the session.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Component, Reflect)]
+#[reflect(Component)]
+pub struct DisconnectedRetained;
+
 /// Disconnect reason to use when an IO layer component is dropped.
 ///
 /// IO layer implementations may use this as a default disconnection reason when
@@ -132,6 +349,61 @@ impl<E> From<E> for DisconnectReason<E> {
 /// [`Disconnect`].
 pub const DROP_DISCONNECT_REASON: &str = "dropped";
 
+/// Disconnect reason used when the app exits.
+///
+/// When an [`AppExit`] event is sent, all [`Session`]s are gracefully
+/// [`Disconnect`]ed with this reason, and all [`Server`]s are [`Close`]d with
+/// this reason. This happens in [`Last`], so the sessions have already flushed
+/// out their packets for this update (see [`IoSet::Flush`]), and the peer
+/// receives a graceful disconnect instead of seeing its connection dropped.
+///
+/// If any session is [`Unflushed`], the exit is held back for up to
+/// [`AppExitTimeout`] first, so that it can finish sending its data.
+///
+/// The IO layer will make a best-effort attempt to send the packets it was
+/// given before sending the disconnection, but if the app process exits
+/// before the IO layer has finished, the peer may not receive them.
+///
+/// [`Server`]: crate::server::Server
+/// [`Close`]: crate::server::Close
+/// [`IoSet::Flush`]: crate::IoSet::Flush
+pub const APP_EXIT_DISCONNECT_REASON: &str = "app exited";
+
+/// How long an [`AppExit`] is held back for, while waiting for [`Unflushed`]
+/// sessions to finish sending their data.
+///
+/// When an [`AppExit`] event is sent while any session is [`Unflushed`], the
+/// event is taken out, and the app keeps updating as normal. This gives the
+/// layers on top of the IO layer a chance to e.g. resend lost data, and receive
+/// acknowledgements for it. Once no session is [`Unflushed`], or this timeout
+/// passes, the [`AppExit`] is sent again, and sessions and servers are
+/// disconnected with [`APP_EXIT_DISCONNECT_REASON`] in that same update.
+///
+/// If another [`AppExit`] is sent while waiting, it is also held back.
+///
+/// Set this to [`Duration::ZERO`] to never hold back the exit.
+///
+/// Defaults to 1 second.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deref, Resource, Reflect)]
+#[reflect(Resource)]
+pub struct AppExitTimeout(pub Duration);
+
+impl Default for AppExitTimeout {
+    fn default() -> Self {
+        Self(Duration::from_secs(1))
+    }
+}
+
+/// Marker for a [`Session`] which still has data that it wants to send before
+/// it is disconnected when the app exits.
+///
+/// IO layers, or layers on top of them, should insert this on a session while
+/// they have data which has not been fully sent, and remove it once it has.
+/// See [`AppExitTimeout`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Component, Reflect)]
+#[reflect(Component)]
+pub struct Unflushed;
+
 /// Local socket address that this entity uses for connections.
 ///
 /// Sessions or servers which use a network will use an OS socket for
@@ -158,6 +430,89 @@ pub struct LocalAddr(pub SocketAddr);
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Deref, Component)]
 pub struct PeerAddr(pub SocketAddr);
 
+/// Breakdown of how long each phase of establishing a [`Session`]'s
+/// connection took.
+///
+/// IO layers which support this insert it alongside [`Session`] once the
+/// session is connected. Phases which the IO layer cannot observe separately
+/// (e.g. DNS resolution performed internally by a library, or any phase in a
+/// browser) are left as [`None`], and their time is folded into the next
+/// phase that was observed.
+///
+/// The phases are, in order:
+/// - `started_at` - the IO layer started connecting
+/// - `resolved_at` - the target address was resolved, e.g. via DNS
+/// - `handshake_at` - the underlying secure channel (TCP + TLS, or QUIC) was
+///   established
+/// - `connected_at` - the IO-layer-specific protocol (e.g. the WebSocket
+///   upgrade or WebTransport session) was negotiated, and the session is ready
+///   to send and receive packets
+///
+/// # Examples
+///
+/// ```
+/// use {aeronet_io::connection::ConnectTiming, bevy_ecs::prelude::*};
+///
+/// fn log_timing(sessions: Query<(Entity, &ConnectTiming), Added<ConnectTiming>>) {
+///     for (session, timing) in &sessions {
+///         let total = timing.total();
+///         let handshake = timing.handshake();
+///         info!("{session} connected in {total:?} (handshake: {handshake:?})");
+///     }
+/// }
+/// # use tracing::info;
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Component, Reflect)]
+#[reflect(from_reflect = false, Component)]
+pub struct ConnectTiming {
+    /// Instant at which the IO layer started connecting.
+    pub started_at: Instant,
+    /// Instant at which the target address was resolved.
+    pub resolved_at: Option<Instant>,
+    /// Instant at which the underlying secure channel was established.
+    pub handshake_at: Option<Instant>,
+    /// Instant at which the session was fully connected.
+    pub connected_at: Instant,
+}
+
+impl ConnectTiming {
+    /// Gets how long resolving the target address took, if it was observed.
+    #[must_use]
+    pub fn dns(&self) -> Option<Duration> {
+        self.resolved_at
+            .map(|resolved_at| resolved_at.saturating_duration_since(self.started_at))
+    }
+
+    /// Gets how long establishing the underlying secure channel took, if it
+    /// was observed.
+    ///
+    /// If [`ConnectTiming::resolved_at`] was not observed, this is measured
+    /// from [`ConnectTiming::started_at`].
+    #[must_use]
+    pub fn handshake(&self) -> Option<Duration> {
+        self.handshake_at.map(|handshake_at| {
+            handshake_at.saturating_duration_since(self.resolved_at.unwrap_or(self.started_at))
+        })
+    }
+
+    /// Gets how long negotiating the IO-layer-specific protocol took, measured
+    /// from the last phase which was observed.
+    #[must_use]
+    pub fn negotiation(&self) -> Duration {
+        let from = self
+            .handshake_at
+            .or(self.resolved_at)
+            .unwrap_or(self.started_at);
+        self.connected_at.saturating_duration_since(from)
+    }
+
+    /// Gets how long the entire connection process took.
+    #[must_use]
+    pub fn total(&self) -> Duration {
+        self.connected_at.saturating_duration_since(self.started_at)
+    }
+}
+
 fn on_connecting(trigger: Trigger<OnAdd, SessionEndpoint>) {
     let entity = trigger.entity();
     debug!("{entity} connecting");
@@ -168,14 +523,152 @@ fn on_connected(trigger: Trigger<OnAdd, Session>) {
     debug!("{entity} connected");
 }
 
-fn on_disconnect(trigger: Trigger<Disconnect>, mut commands: Commands) {
+fn on_disconnect(
+    trigger: Trigger<Disconnect>,
+    retained: Query<(), With<DisconnectedRetained>>,
+    mut commands: Commands,
+) {
     let entity = trigger.entity();
-    let reason = DisconnectReason::User(trigger.event().reason.clone());
-    commands.trigger_targets(Disconnected { reason }, entity);
+    if retained.contains(entity) {
+        return;
+    }
+    let Disconnect { reason, code } = trigger.event();
+    let reason = DisconnectReason::User(reason.clone());
+    commands.trigger_targets(
+        Disconnected {
+            reason,
+            code: *code,
+        },
+        entity,
+    );
 }
 
-fn on_disconnected(trigger: Trigger<Disconnected>, mut commands: Commands) {
+#[cfg(feature = "test-utils")]
+fn on_simulate_disconnect(trigger: Trigger<SimulateDisconnect>, mut commands: Commands) {
     let entity = trigger.entity();
+    commands.trigger_targets(
+        Disconnected {
+            reason: DisconnectReason::Error(anyhow::Error::new(SimulatedDisconnect)),
+            code: None,
+        },
+        entity,
+    );
+}
+
+fn detect_idle(
+    mut sessions: Query<(Entity, &Session, &mut IdleDetection)>,
+    mut commands: Commands,
+) {
+    let now = Instant::now();
+    for (entity, session, mut detection) in &mut sessions {
+        let idle = now.saturating_duration_since(session.last_recv_at()) >= detection.threshold;
+        if idle == detection.idle {
+            continue;
+        }
+
+        detection.idle = idle;
+        let activity = if idle {
+            debug!("{entity} is idle");
+            SessionActivity::Idle
+        } else {
+            debug!("{entity} is active");
+            SessionActivity::Active
+        };
+        commands.trigger_targets(activity, entity);
+    }
+}
+
+#[derive(Debug, Default)]
+pub(crate) enum PendingExit {
+    #[default]
+    None,
+    Waiting {
+        exit: AppExit,
+        deadline: Instant,
+    },
+    Released,
+}
+
+pub(crate) fn hold_exit(
+    mut exits: ResMut<Events<AppExit>>,
+    mut pending: Local<PendingExit>,
+    timeout: Res<AppExitTimeout>,
+    unflushed: Query<(), With<Unflushed>>,
+) {
+    fn take_exit(exits: &mut Events<AppExit>) -> Option<AppExit> {
+        // prefer reporting an error over a success
+        exits
+            .drain()
+            .reduce(|exit, next| if exit.is_error() { exit } else { next })
+    }
+
+    let now = Instant::now();
+    match &mut *pending {
+        PendingExit::Released => return,
+        PendingExit::None => {
+            let Some(exit) = take_exit(&mut exits) else {
+                return;
+            };
+            *pending = PendingExit::Waiting {
+                exit,
+                deadline: now + **timeout,
+            };
+        }
+        PendingExit::Waiting { exit, .. } => {
+            if let Some(next) = take_exit(&mut exits).filter(|_| !exit.is_error()) {
+                *exit = next;
+            }
+        }
+    }
+
+    let PendingExit::Waiting { exit, deadline } = &*pending else {
+        return;
+    };
+    let num_unflushed = unflushed.iter().count();
+    if num_unflushed > 0 {
+        if now < *deadline {
+            return;
+        }
+        debug!("Timed out waiting for {num_unflushed} sessions to flush before exiting");
+    }
+    exits.send(exit.clone());
+    *pending = PendingExit::Released;
+}
+
+fn disconnect_on_exit(
+    mut exits: EventReader<AppExit>,
+    sessions: Query<(Entity, Option<&Parent>), With<SessionEndpoint>>,
+    servers: Query<(), With<ServerEndpoint>>,
+    mut commands: Commands,
+) {
+    if exits.is_empty() {
+        return;
+    }
+    exits.clear();
+
+    // sessions belonging to a server will be disconnected when the server closes
+    let sessions = sessions
+        .iter()
+        .filter(|(_, parent)| !parent.is_some_and(|parent| servers.contains(parent.get())))
+        .map(|(session, _)| session)
+        .collect::<Vec<_>>();
+    if !sessions.is_empty() {
+        debug!("App exiting, disconnecting {} sessions", sessions.len());
+        commands.trigger_targets(Disconnect::new(APP_EXIT_DISCONNECT_REASON), sessions);
+    }
+}
+
+fn on_disconnected(
+    trigger: Trigger<Disconnected>,
+    sessions: Query<(Option<&DisconnectDespawn>, Has<DisconnectedRetained>)>,
+    mut commands: Commands,
+) {
+    let entity = trigger.entity();
+    let (despawn, retained) = sessions.get(entity).unwrap_or_default();
+    if retained {
+        return;
+    }
+
     match &trigger.event().reason {
         DisconnectReason::User(reason) => {
             debug!("{entity} disconnected by user: {reason}");
@@ -188,14 +681,51 @@ fn on_disconnected(trigger: Trigger<Disconnected>, mut commands: Commands) {
         }
     }
 
-    if let Some(entity) = commands.get_entity(entity) {
-        entity.despawn_recursive();
+    let Some(mut entity) = commands.get_entity(entity) else {
+        return;
+    };
+    match despawn.copied().unwrap_or_default() {
+        DisconnectDespawn::Immediate => entity.despawn_recursive(),
+        DisconnectDespawn::NextUpdate | DisconnectDespawn::Retained => {
+            entity.insert(DisconnectedRetained);
+        }
     }
 }
 
+fn release_retained(
+    sessions: Query<(Entity, &DisconnectDespawn, Ref<DisconnectedRetained>)>,
+    mut commands: Commands,
+) {
+    for (entity, despawn, retained) in &sessions {
+        // give every system one full update to see the retained session
+        if *despawn == DisconnectDespawn::NextUpdate && !retained.is_added() {
+            commands.entity(entity).remove::<DisconnectedRetained>();
+        }
+    }
+}
+
+fn on_retained_removed(trigger: Trigger<OnRemove, DisconnectedRetained>, mut commands: Commands) {
+    let entity = trigger.entity();
+    debug!("{entity} released after disconnect");
+    // the marker is also removed when the entity is despawned by someone else
+    commands.queue(move |world: &mut World| {
+        if let Ok(entity) = world.get_entity_mut(entity) {
+            entity.despawn_recursive();
+        }
+    });
+}
+
 #[cfg(test)]
 mod tests {
-    use {super::*, crate::AeronetIoPlugin};
+    #![expect(clippy::unwrap_used, reason = "testing")]
+
+    use {
+        super::*,
+        crate::{AeronetIoPlugin, packet::RecvPacket},
+        bytes::Bytes,
+        core::mem,
+        std::thread,
+    };
 
     #[test]
     fn remove_entity_on_disconnect() {
@@ -227,4 +757,245 @@ mod tests {
         assert!(app.world().get_entity(entity).is_err());
         assert!(app.world().resource::<HasDisconnected>().0);
     }
+
+    #[test]
+    fn defer_despawn_next_update() {
+        #[derive(Debug, Default, Resource)]
+        struct FinalStats(Vec<usize>);
+
+        fn read_final_stats(
+            sessions: Query<&Session, With<DisconnectedRetained>>,
+            mut final_stats: ResMut<FinalStats>,
+        ) {
+            for session in &sessions {
+                final_stats.0.push(session.stats.packets_sent.0);
+            }
+        }
+
+        let mut app = App::new();
+        app.add_plugins(AeronetIoPlugin)
+            .init_resource::<FinalStats>()
+            .add_systems(Update, read_final_stats);
+
+        let mut session = Session::new(Instant::now(), 1000);
+        session.stats.packets_sent.0 = 3;
+        let entity = app
+            .world_mut()
+            .spawn((session, DisconnectDespawn::NextUpdate))
+            .id();
+
+        app.world_mut()
+            .trigger_targets(Disconnect::new("disconnect reason"), entity);
+        app.world_mut().flush();
+        assert!(app.world().get::<DisconnectedRetained>(entity).is_some());
+
+        // update in which the session was disconnected
+        app.update();
+        assert!(app.world().get_entity(entity).is_ok());
+
+        // the next update
+        app.update();
+        assert!(app.world().get_entity(entity).is_err());
+        assert_eq!(vec![3, 3], app.world().resource::<FinalStats>().0);
+    }
+
+    #[test]
+    fn defer_despawn_until_released() {
+        let mut app = App::new();
+        app.add_plugins(AeronetIoPlugin);
+
+        let entity = app
+            .world_mut()
+            .spawn((
+                Session::new(Instant::now(), 1000),
+                DisconnectDespawn::Retained,
+            ))
+            .id();
+
+        app.world_mut()
+            .trigger_targets(Disconnect::new("disconnect reason"), entity);
+        for _ in 0..3 {
+            app.update();
+            assert!(app.world().get::<DisconnectedRetained>(entity).is_some());
+            assert!(app.world().get::<Session>(entity).is_some());
+        }
+
+        // disconnecting again while retained does nothing
+        app.world_mut()
+            .trigger_targets(Disconnect::new("disconnect reason"), entity);
+        app.update();
+        assert!(app.world().get_entity(entity).is_ok());
+
+        app.world_mut()
+            .entity_mut(entity)
+            .remove::<DisconnectedRetained>();
+        app.update();
+        assert!(app.world().get_entity(entity).is_err());
+    }
+
+    #[test]
+    fn hold_exit_until_flushed() {
+        let mut app = App::new();
+        app.add_plugins(AeronetIoPlugin)
+            .insert_resource(AppExitTimeout(Duration::from_secs(60)));
+
+        let session = app
+            .world_mut()
+            .spawn((Session::new(Instant::now(), 1000), Unflushed))
+            .id();
+
+        app.world_mut().send_event(AppExit::Success);
+        for _ in 0..3 {
+            app.update();
+            assert_eq!(None, app.should_exit());
+            assert!(app.world().get_entity(session).is_ok());
+        }
+
+        // once the session has sent everything, it's disconnected,
+        // and the app exits in the same update
+        app.world_mut().entity_mut(session).remove::<Unflushed>();
+        app.update();
+        assert_eq!(Some(AppExit::Success), app.should_exit());
+        assert!(app.world().get_entity(session).is_err());
+    }
+
+    #[test]
+    fn hold_exit_times_out() {
+        const TIMEOUT: Duration = Duration::from_millis(50);
+
+        let mut app = App::new();
+        app.add_plugins(AeronetIoPlugin)
+            .insert_resource(AppExitTimeout(TIMEOUT));
+
+        let session = app
+            .world_mut()
+            .spawn((Session::new(Instant::now(), 1000), Unflushed))
+            .id();
+
+        app.world_mut().send_event(AppExit::error());
+        app.update();
+        assert_eq!(None, app.should_exit());
+
+        // the session never finishes flushing
+        thread::sleep(TIMEOUT);
+        app.update();
+        assert_eq!(Some(AppExit::error()), app.should_exit());
+        assert!(app.world().get_entity(session).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "test-utils")]
+    fn simulate_disconnect() {
+        #[derive(Resource)]
+        struct HasDisconnected(bool);
+
+        let mut app = App::new();
+        app.add_plugins(AeronetIoPlugin)
+            .insert_resource(HasDisconnected(false));
+
+        let entity = app.world_mut().spawn_empty().id();
+        app.world_mut().entity_mut(entity).observe(
+            |trigger: Trigger<Disconnected>, mut has_disconnected: ResMut<HasDisconnected>| {
+                let DisconnectReason::Error(err) = &trigger.event().reason else {
+                    panic!("expected an error, got {:?}", trigger.event().reason);
+                };
+                assert!(err.downcast_ref::<SimulatedDisconnect>().is_some());
+                assert_eq!(None, trigger.event().code);
+
+                has_disconnected.0 = true;
+            },
+        );
+
+        app.world_mut().trigger_targets(SimulateDisconnect, entity);
+        app.update();
+
+        assert!(app.world().get_entity(entity).is_err());
+        assert!(app.world().resource::<HasDisconnected>().0);
+    }
+
+    #[test]
+    fn idle_detection() {
+        const THRESHOLD: Duration = Duration::from_millis(50);
+
+        #[derive(Debug, Default, Resource)]
+        struct Incoming(bool);
+
+        #[derive(Debug, Default, Resource)]
+        struct Activity(Vec<SessionActivity>);
+
+        fn recv(mut incoming: ResMut<Incoming>, mut sessions: Query<&mut Session>) {
+            if !mem::take(&mut incoming.0) {
+                return;
+            }
+            for mut session in &mut sessions {
+                session.recv.push(RecvPacket {
+                    recv_at: Instant::now(),
+                    payload: Bytes::new(),
+                });
+            }
+        }
+
+        fn consume(mut sessions: Query<&mut Session>) {
+            for mut session in &mut sessions {
+                session.recv.clear();
+            }
+        }
+
+        let mut app = App::new();
+        app.add_plugins(AeronetIoPlugin)
+            .init_resource::<Incoming>()
+            .init_resource::<Activity>()
+            .add_systems(
+                PreUpdate,
+                (
+                    recv.in_set(IoSet::Poll),
+                    consume.after(packet::update_last_recv),
+                ),
+            );
+
+        let connected_at = Instant::now();
+        let session = app
+            .world_mut()
+            .spawn((
+                Session::new(connected_at, 1000),
+                IdleDetection::new(THRESHOLD),
+            ))
+            .observe(
+                |trigger: Trigger<SessionActivity>, mut activity: ResMut<Activity>| {
+                    activity.0.push(*trigger.event());
+                },
+            )
+            .id();
+        let last_recv_at = |app: &App| app.world().get::<Session>(session).unwrap().last_recv_at();
+        assert_eq!(connected_at, last_recv_at(&app));
+
+        app.world_mut().resource_mut::<Incoming>().0 = true;
+        app.update();
+        let first_recv_at = last_recv_at(&app);
+        assert!(first_recv_at > connected_at);
+
+        // no packets, but we haven't hit the threshold yet
+        app.update();
+        assert_eq!(first_recv_at, last_recv_at(&app));
+        assert_eq!(
+            Vec::<SessionActivity>::new(),
+            app.world().resource::<Activity>().0
+        );
+
+        thread::sleep(THRESHOLD);
+        app.update();
+        app.update();
+        assert_eq!(
+            vec![SessionActivity::Idle],
+            app.world().resource::<Activity>().0
+        );
+
+        app.world_mut().resource_mut::<Incoming>().0 = true;
+        app.update();
+        assert!(last_recv_at(&app) > first_recv_at);
+        assert_eq!(
+            vec![SessionActivity::Idle, SessionActivity::Active],
+            app.world().resource::<Activity>().0
+        );
+    }
 }
@@ -1,6 +1,10 @@
 #![cfg_attr(docsrs, feature(doc_cfg, doc_auto_cfg))]
 #![doc = include_str!("../README.md")]
 
+extern crate alloc;
+
+#[cfg(feature = "test-utils")]
+pub mod conformance;
 pub mod connection;
 pub mod packet;
 pub mod server;
@@ -84,8 +88,9 @@ pub struct SessionEndpoint;
 /// If the session fails to connect, or loses connection after successfully
 /// connecting (this may be a graceful disconnect or a connection error),
 /// [`Disconnected`] is [triggered][trigger] on the session entity, and the
-/// session is despawned immediately afterwards. You may also [trigger] your own
-/// disconnection with a string reason by triggering [`Disconnect`].
+/// session is despawned immediately afterwards (see [`DisconnectDespawn`] to
+/// defer this). You may also [trigger] your own disconnection with a string
+/// reason by triggering [`Disconnect`].
 ///
 /// # Packet buffers
 ///
@@ -109,11 +114,13 @@ pub struct SessionEndpoint;
 /// [packets]: packet
 /// [`Disconnected`]: connection::Disconnected
 /// [`Disconnect`]: connection::Disconnect
+/// [`DisconnectDespawn`]: connection::DisconnectDespawn
 #[derive(Debug, Component, Reflect)]
 #[reflect(from_reflect = false, Component)]
 #[require(SessionEndpoint)]
 pub struct Session {
     connected_at: Instant,
+    last_recv_at: Instant,
     min_mtu: usize,
     mtu: usize,
     /// Total packet statistics of this session up to now.
@@ -155,6 +162,7 @@ impl Session {
     pub fn new(connected_at: Instant, min_mtu: usize) -> Self {
         Self {
             connected_at,
+            last_recv_at: connected_at,
             min_mtu,
             mtu: min_mtu,
             stats: PacketStats::default(),
@@ -179,6 +187,30 @@ impl Session {
         self.connected_at
     }
 
+    /// Returns when this session last received a packet from its peer.
+    ///
+    /// This is updated after [`IoSet::Poll`] from the [`RecvPacket::recv_at`]
+    /// of every packet that the IO layer received - including packets which
+    /// carry no data, such as keep-alives - so it can be used to tell when the
+    /// peer was last seen. Before any packets are received, this is
+    /// [`Session::connected_at`].
+    ///
+    /// See [`connection::IdleDetection`] to be notified when this gets too old.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use {aeronet_io::Session, web_time::Instant};
+    ///
+    /// let now = Instant::now();
+    /// let session = Session::new(now, 1000);
+    /// assert_eq!(now, session.last_recv_at());
+    /// ```
+    #[must_use]
+    pub const fn last_recv_at(&self) -> Instant {
+        self.last_recv_at
+    }
+
     /// Returns the smallest value that [`Session::mtu`] will ever report on
     /// this session.
     ///
@@ -251,6 +283,27 @@ impl Session {
     }
 }
 
+/// Gets the current instant, using the correct clock for this platform.
+///
+/// All APIs in `aeronet` which take a `now` parameter use
+/// [`web_time::Instant`]. On native platforms, this is the same type as
+/// [`std::time::Instant`], so it is easy to accidentally call
+/// [`std::time::Instant::now`] instead - which compiles fine, but panics on
+/// WASM. Use this function whenever you need to pass `now`, so that your code
+/// works on every platform.
+///
+/// # Examples
+///
+/// ```
+/// let before = aeronet_io::now();
+/// let after = aeronet_io::now();
+/// assert!(after >= before);
+/// ```
+#[must_use]
+pub fn now() -> Instant {
+    Instant::now()
+}
+
 /// Set for scheduling IO layer systems.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, SystemSet)]
 pub enum IoSet {
@@ -259,3 +312,18 @@ pub enum IoSet {
     /// Sending buffered packets.
     Flush,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn now_is_monotonic() {
+        let mut last = now();
+        for _ in 0..1000 {
+            let next = now();
+            assert!(next >= last);
+            last = next;
+        }
+    }
+}
@@ -35,6 +35,12 @@
 //!
 //! A networked transport may choose to use [`IP_MTU`] as a base MTU value,
 //! minus protocol overhead.
+//!
+//! # Tracing
+//!
+//! For debugging, you can record every packet sent and received by a session
+//! into a file (or any other writer) by adding a [`PacketTrace`] to it. See
+//! its docs for the format of the trace.
 
 use {
     crate::{IoSet, Session},
@@ -43,8 +49,9 @@ use {
     bevy_ecs::prelude::*,
     bevy_reflect::prelude::*,
     bytes::Bytes,
-    core::{num::Saturating, time::Duration},
+    core::{fmt, num::Saturating, time::Duration},
     derive_more::{Add, AddAssign, Display, Error, Sub, SubAssign},
+    std::io,
     tracing::warn,
     web_time::Instant,
 };
@@ -56,8 +63,20 @@ impl Plugin for PacketPlugin {
     fn build(&self, app: &mut App) {
         app.register_type::<PacketRtt>()
             .register_type::<PacketStats>()
-            .add_systems(PreUpdate, clear_recv_buffers.before(IoSet::Poll))
-            .add_systems(PostUpdate, clear_send_buffers.after(IoSet::Flush));
+            .add_systems(
+                PreUpdate,
+                (
+                    clear_recv_buffers.before(IoSet::Poll),
+                    (update_last_recv, trace_recv_packets).after(IoSet::Poll),
+                ),
+            )
+            .add_systems(
+                PostUpdate,
+                (
+                    trace_send_packets.before(IoSet::Flush),
+                    clear_send_buffers.after(IoSet::Flush),
+                ),
+            );
     }
 }
 
@@ -143,6 +162,18 @@ pub fn clear_recv_buffers(mut sessions: Query<(Entity, &mut Session)>) {
     }
 }
 
+/// Updates [`Session::last_recv_at`] from the packets in [`Session::recv`].
+pub fn update_last_recv(mut sessions: Query<&mut Session>) {
+    for mut session in &mut sessions {
+        let Some(recv_at) = session.recv.iter().map(|packet| packet.recv_at).max() else {
+            continue;
+        };
+        if recv_at > session.last_recv_at {
+            session.last_recv_at = recv_at;
+        }
+    }
+}
+
 /// Clears all [`Session::send`] buffers, emitting warnings if there were any
 /// packets left in the buffer.
 pub fn clear_send_buffers(mut sessions: Query<(Entity, &mut Session)>) {
@@ -157,3 +188,322 @@ pub fn clear_send_buffers(mut sessions: Query<(Entity, &mut Session)>) {
         }
     }
 }
+
+/// Magic bytes written at the start of a [`PacketTrace`].
+///
+/// The last byte is the version of the trace format.
+pub const PACKET_TRACE_MAGIC: [u8; 8] = *b"AERNTRC1";
+
+/// Records every packet sent and received by a [`Session`] into a writer.
+///
+/// When this component is present on a session, all packets in
+/// [`Session::recv`] are recorded after [`IoSet::Poll`], and all packets in
+/// [`Session::send`] are recorded before [`IoSet::Flush`]. If you read from
+/// or write to these buffers directly (instead of via a higher layer such as
+/// `aeronet_transport`, which is already ordered correctly), make sure your
+/// systems run after [`trace_recv_packets`] and before
+/// [`trace_send_packets`], otherwise some packets will not be recorded.
+///
+/// This is intended for debugging, e.g. to reproduce a bug by capturing the
+/// exact packets which caused it. Writes are performed synchronously in the
+/// system which records the packets, so you should use a buffered writer such
+/// as [`io::BufWriter`]. If writing fails, a warning is logged and no more
+/// packets are recorded.
+///
+/// # Format
+///
+/// The trace starts with [`PACKET_TRACE_MAGIC`], followed by one record per
+/// packet. Each record consists of:
+/// - direction: 1 byte, `0` for received and `1` for sent (see
+///   [`PacketDirection`])
+/// - timestamp: 8 bytes, little-endian microseconds since the trace was created
+/// - payload length: 4 bytes, little-endian
+/// - payload: the raw bytes of the packet
+///
+/// Use [`read_packet_trace`] to parse a trace back into packets.
+///
+/// # Examples
+///
+/// ```
+/// use {
+///     aeronet_io::packet::PacketTrace,
+///     bevy_ecs::prelude::*,
+///     std::{fs::File, io::BufWriter},
+/// };
+///
+/// # fn run(mut commands: Commands, session: Entity) -> std::io::Result<()> {
+/// let file = File::create("session.trace")?;
+/// commands
+///     .entity(session)
+///     .insert(PacketTrace::new(BufWriter::new(file))?);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Component)]
+pub struct PacketTrace {
+    writer: Option<Box<dyn io::Write + Send + Sync>>,
+    started_at: Instant,
+}
+
+/// Whether a packet recorded in a [`PacketTrace`] was received or sent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PacketDirection {
+    /// Packet was received from the peer, via [`Session::recv`].
+    Recv,
+    /// Packet was sent to the peer, via [`Session::send`].
+    Send,
+}
+
+/// Packet read from a [`PacketTrace`] by [`read_packet_trace`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TracedPacket {
+    /// Whether this packet was received or sent.
+    pub direction: PacketDirection,
+    /// Time since the trace was created until the packet was received or sent.
+    pub elapsed: Duration,
+    /// Raw byte data of this packet.
+    pub payload: Bytes,
+}
+
+impl PacketTrace {
+    /// Creates a new trace which records packets into the given writer.
+    ///
+    /// # Errors
+    ///
+    /// Errors if [`PACKET_TRACE_MAGIC`] could not be written.
+    pub fn new(mut writer: impl io::Write + Send + Sync + 'static) -> io::Result<Self> {
+        writer.write_all(&PACKET_TRACE_MAGIC)?;
+        Ok(Self {
+            writer: Some(Box::new(writer)),
+            started_at: Instant::now(),
+        })
+    }
+
+    /// Gets the instant at which this trace was created.
+    ///
+    /// Timestamps in the trace are relative to this instant.
+    #[must_use]
+    pub const fn started_at(&self) -> Instant {
+        self.started_at
+    }
+
+    /// Gets if this trace is still recording packets.
+    ///
+    /// This is `false` if writing to the writer has failed.
+    #[must_use]
+    pub const fn is_recording(&self) -> bool {
+        self.writer.is_some()
+    }
+
+    fn record(&mut self, entity: Entity, direction: PacketDirection, at: Instant, payload: &[u8]) {
+        let Some(writer) = &mut self.writer else {
+            return;
+        };
+        let elapsed = at.saturating_duration_since(self.started_at);
+        if let Err(err) = write_record(writer, direction, elapsed, payload) {
+            warn!("{entity} failed to write to packet trace, no longer recording: {err:?}");
+            self.writer = None;
+        }
+    }
+}
+
+impl fmt::Debug for PacketTrace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PacketTrace")
+            .field("recording", &self.is_recording())
+            .field("started_at", &self.started_at)
+            .finish_non_exhaustive()
+    }
+}
+
+impl PacketDirection {
+    const fn to_byte(self) -> u8 {
+        match self {
+            Self::Recv => 0,
+            Self::Send => 1,
+        }
+    }
+
+    const fn from_byte(byte: u8) -> Option<Self> {
+        match byte {
+            0 => Some(Self::Recv),
+            1 => Some(Self::Send),
+            _ => None,
+        }
+    }
+}
+
+fn write_record(
+    writer: &mut dyn io::Write,
+    direction: PacketDirection,
+    elapsed: Duration,
+    payload: &[u8],
+) -> io::Result<()> {
+    let timestamp = u64::try_from(elapsed.as_micros()).unwrap_or(u64::MAX);
+    let len = u32::try_from(payload.len())
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "packet too large"))?;
+    writer.write_all(&[direction.to_byte()])?;
+    writer.write_all(&timestamp.to_le_bytes())?;
+    writer.write_all(&len.to_le_bytes())?;
+    writer.write_all(payload)
+}
+
+/// Reads all packets recorded by a [`PacketTrace`].
+///
+/// # Errors
+///
+/// Errors if the reader fails, or if the data is not a valid trace.
+pub fn read_packet_trace(mut reader: impl io::Read) -> io::Result<Vec<TracedPacket>> {
+    let invalid = |msg| io::Error::new(io::ErrorKind::InvalidData, msg);
+
+    let mut magic = [0; PACKET_TRACE_MAGIC.len()];
+    reader.read_exact(&mut magic)?;
+    if magic != PACKET_TRACE_MAGIC {
+        return Err(invalid("invalid packet trace magic"));
+    }
+
+    let mut packets = Vec::new();
+    loop {
+        let mut direction = [0; 1];
+        match reader.read_exact(&mut direction) {
+            Ok(()) => {}
+            Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => return Ok(packets),
+            Err(err) => return Err(err),
+        }
+        let direction =
+            PacketDirection::from_byte(direction[0]).ok_or_else(|| invalid("invalid direction"))?;
+
+        let mut timestamp = [0; 8];
+        reader.read_exact(&mut timestamp)?;
+        let elapsed = Duration::from_micros(u64::from_le_bytes(timestamp));
+
+        let mut len = [0; 4];
+        reader.read_exact(&mut len)?;
+        let len =
+            usize::try_from(u32::from_le_bytes(len)).map_err(|_| invalid("packet too large"))?;
+
+        let mut payload = vec![0; len];
+        reader.read_exact(&mut payload)?;
+        packets.push(TracedPacket {
+            direction,
+            elapsed,
+            payload: Bytes::from(payload),
+        });
+    }
+}
+
+/// Records all packets in [`Session::recv`] into the session's
+/// [`PacketTrace`].
+pub fn trace_recv_packets(mut sessions: Query<(Entity, &Session, &mut PacketTrace)>) {
+    for (entity, session, mut trace) in &mut sessions {
+        for packet in &session.recv {
+            trace.record(
+                entity,
+                PacketDirection::Recv,
+                packet.recv_at,
+                &packet.payload,
+            );
+        }
+    }
+}
+
+/// Records all packets in [`Session::send`] into the session's
+/// [`PacketTrace`].
+pub fn trace_send_packets(mut sessions: Query<(Entity, &Session, &mut PacketTrace)>) {
+    let now = Instant::now();
+    for (entity, session, mut trace) in &mut sessions {
+        for packet in &session.send {
+            trace.record(entity, PacketDirection::Send, now, packet);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![expect(clippy::unwrap_used, reason = "testing")]
+
+    use {super::*, crate::AeronetIoPlugin, alloc::sync::Arc, std::sync::Mutex};
+
+    #[derive(Clone, Default)]
+    struct SharedBuf(Arc<Mutex<Vec<u8>>>);
+
+    impl io::Write for SharedBuf {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn trace_exchange() {
+        let buf = SharedBuf::default();
+        let mut app = App::new();
+        app.add_plugins(AeronetIoPlugin)
+            .add_systems(
+                PreUpdate,
+                (|mut sessions: Query<&mut Session>| {
+                    for mut session in &mut sessions {
+                        session.recv.push(RecvPacket {
+                            recv_at: Instant::now(),
+                            payload: Bytes::from_static(b"request"),
+                        });
+                    }
+                })
+                .in_set(IoSet::Poll),
+            )
+            .add_systems(Update, |mut sessions: Query<&mut Session>| {
+                for mut session in &mut sessions {
+                    session.recv.clear();
+                    session.send.push(Bytes::from_static(b"response 1"));
+                    session.send.push(Bytes::from_static(b"response 2"));
+                }
+            })
+            .add_systems(
+                PostUpdate,
+                (|mut sessions: Query<&mut Session>| {
+                    for mut session in &mut sessions {
+                        session.send.clear();
+                    }
+                })
+                .in_set(IoSet::Flush),
+            );
+        app.world_mut().spawn((
+            Session::new(Instant::now(), IP_MTU),
+            PacketTrace::new(buf.clone()).unwrap(),
+        ));
+
+        app.update();
+        app.update();
+
+        let packets = read_packet_trace(&buf.0.lock().unwrap()[..]).unwrap();
+        assert_eq!(
+            [
+                PacketDirection::Recv,
+                PacketDirection::Send,
+                PacketDirection::Send,
+                PacketDirection::Recv,
+                PacketDirection::Send,
+                PacketDirection::Send,
+            ],
+            packets
+                .iter()
+                .map(|packet| packet.direction)
+                .collect::<Vec<_>>()
+                .as_slice()
+        );
+        assert_eq!(b"request", &*packets[0].payload);
+        assert_eq!(b"response 1", &*packets[1].payload);
+        assert_eq!(b"response 2", &*packets[2].payload);
+    }
+
+    #[test]
+    fn invalid_magic() {
+        assert_eq!(
+            io::ErrorKind::InvalidData,
+            read_packet_trace(&b"NOTATRACE"[..]).unwrap_err().kind()
+        );
+    }
+}
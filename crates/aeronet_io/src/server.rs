@@ -8,11 +8,16 @@
 //! [`Session`]: crate::Session
 
 use {
-    crate::connection::Disconnect,
+    crate::{
+        Session, SessionEndpoint,
+        connection::{self, APP_EXIT_DISCONNECT_REASON, Disconnect, DisconnectedRetained},
+    },
+    alloc::collections::BTreeMap,
     bevy_app::prelude::*,
     bevy_ecs::prelude::*,
-    bevy_hierarchy::{Children, DespawnRecursiveExt},
+    bevy_hierarchy::{Children, DespawnRecursiveExt, Parent},
     bevy_reflect::prelude::*,
+    core::{net::IpAddr, time::Duration},
     tracing::debug,
     web_time::Instant,
 };
@@ -24,10 +29,18 @@ impl Plugin for ServerPlugin {
     fn build(&self, app: &mut App) {
         app.register_type::<ServerEndpoint>()
             .register_type::<Server>()
+            .register_type::<Draining>()
+            .register_type::<PeerRateLimit>()
             .add_observer(on_opening)
             .add_observer(on_opened)
             .add_observer(on_close)
-            .add_observer(on_closed);
+            .add_observer(on_closed)
+            .add_observer(on_draining)
+            .add_observer(reject_while_draining)
+            .add_systems(
+                Last,
+                (close_drained, close_on_exit.after(connection::hold_exit)).chain(),
+            );
     }
 }
 
@@ -86,6 +99,65 @@ impl Server {
     }
 }
 
+/// Marks a [`Server`] as draining: it stops accepting new clients, but lets
+/// already connected clients finish what they are doing, before closing.
+///
+/// Insert this component on a server entity to start draining it, e.g. before
+/// shutting the server down for maintenance. While a server is draining:
+/// - sessions which were already connected are left alone
+/// - any session which finishes connecting is immediately disconnected with
+///   [`Draining::reason`]
+///   - IO layers which can reject clients before they connect may do so
+///     instead, e.g. `aeronet_webtransport` rejects session requests
+/// - once [`Draining::grace_period`] has elapsed, or all sessions have
+///   disconnected, the server is [`Close`]d with [`Draining::reason`], which
+///   disconnects any remaining sessions with the same reason
+///
+/// Removing this component stops draining, and the server will accept new
+/// clients again.
+///
+/// # Examples
+///
+/// ```
+/// use {aeronet_io::server::Draining, bevy_ecs::prelude::*, core::time::Duration};
+///
+/// # fn run(mut commands: Commands, server: Entity) {
+/// commands.entity(server).insert(Draining::new(
+///     Duration::from_secs(60),
+///     "server shutting down for maintenance",
+/// ));
+/// # }
+/// ```
+#[derive(Debug, Clone, Component, Reflect)]
+#[reflect(from_reflect = false, Component)]
+pub struct Draining {
+    /// Instant at which the server started draining.
+    pub started_at: Instant,
+    /// How long connected sessions are given to finish before the server is
+    /// closed.
+    pub grace_period: Duration,
+    /// Reason used when rejecting new sessions, and when closing the server.
+    pub reason: String,
+}
+
+impl Draining {
+    /// Creates a new [`Draining`] which starts now.
+    #[must_use]
+    pub fn new(grace_period: Duration, reason: impl Into<String>) -> Self {
+        Self {
+            started_at: Instant::now(),
+            grace_period,
+            reason: reason.into(),
+        }
+    }
+
+    /// Gets the instant at which the server will be closed.
+    #[must_use]
+    pub fn deadline(&self) -> Instant {
+        self.started_at + self.grace_period
+    }
+}
+
 /// Triggered when a user requests a [`Server`] to gracefully shut down and
 /// disconnect all of its connected clients.
 ///
@@ -181,6 +253,137 @@ impl<E> From<E> for CloseReason<E> {
     }
 }
 
+/// Limits how many connection attempts a server accepts from a single IP
+/// address, before any handshake is performed.
+///
+/// A flood of connection attempts from one IP address can use up server
+/// resources long before the server knows anything about the client. IO layer
+/// implementations which support this check each incoming connection's source
+/// IP against the limit as soon as it arrives, and cheaply drop any attempt
+/// which goes over the limit - no handshake is performed, and no session
+/// entity is spawned for it.
+///
+/// At most [`PeerRateLimit::max_attempts`] connection attempts from the same IP
+/// address are accepted within each [`PeerRateLimit::interval`]. IPv4 addresses
+/// mapped into IPv6 are treated the same as their IPv4 counterparts.
+///
+/// This is a fixed window limit: an IP address's window starts at its first
+/// attempt, and its count resets once the window has elapsed. An IP address
+/// which uses up its attempts at the end of one window can immediately use up
+/// the next window's attempts as well, so up to twice
+/// [`PeerRateLimit::max_attempts`] attempts may be accepted in a short burst
+/// around a window boundary.
+///
+/// Note that a single IPv6 client may have many addresses available to it,
+/// so this is only a first line of defense.
+///
+/// To use this, insert it on a server entity before opening the server. The
+/// limit is read once when the server starts opening, so changing or removing
+/// this component afterwards has no effect.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Component, Reflect)]
+#[reflect(Component)]
+pub struct PeerRateLimit {
+    /// Maximum number of connection attempts accepted from a single IP address
+    /// within each [`PeerRateLimit::interval`].
+    pub max_attempts: usize,
+    /// Length of the window which [`PeerRateLimit::max_attempts`] applies to.
+    pub interval: Duration,
+}
+
+impl PeerRateLimit {
+    /// Creates a [`PeerRateLimit`] which accepts up to `max_attempts`
+    /// connection attempts from a single IP address per `interval`.
+    #[must_use]
+    pub const fn new(max_attempts: usize, interval: Duration) -> Self {
+        Self {
+            max_attempts,
+            interval,
+        }
+    }
+}
+
+/// Tracks connection attempts per IP address to enforce a [`PeerRateLimit`].
+///
+/// This is intended for use by IO layer implementations, in the task which
+/// accepts incoming connections.
+#[derive(Debug)]
+pub struct PeerRateLimiter {
+    limit: PeerRateLimit,
+    windows: BTreeMap<IpAddr, AttemptWindow>,
+    last_pruned_at: Option<Instant>,
+}
+
+#[derive(Debug)]
+struct AttemptWindow {
+    started_at: Instant,
+    attempts: usize,
+}
+
+impl PeerRateLimiter {
+    /// Creates a limiter which enforces `limit`.
+    #[must_use]
+    pub const fn new(limit: PeerRateLimit) -> Self {
+        Self {
+            limit,
+            windows: BTreeMap::new(),
+            last_pruned_at: None,
+        }
+    }
+
+    /// Gets the limit that this enforces.
+    #[must_use]
+    pub const fn limit(&self) -> PeerRateLimit {
+        self.limit
+    }
+
+    /// Records a connection attempt from `ip` at `now`, and returns whether
+    /// the attempt should be accepted.
+    ///
+    /// If this returns `false`, the connection should be dropped immediately.
+    pub fn try_acquire(&mut self, ip: IpAddr, now: Instant) -> bool {
+        self.prune(now);
+
+        let interval = self.limit.interval;
+        let window = self
+            .windows
+            .entry(ip.to_canonical())
+            .or_insert(AttemptWindow {
+                started_at: now,
+                attempts: 0,
+            });
+        if now.saturating_duration_since(window.started_at) >= interval {
+            window.started_at = now;
+            window.attempts = 0;
+        }
+
+        if window.attempts < self.limit.max_attempts {
+            window.attempts += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Gets the number of IP addresses which are currently being tracked.
+    #[must_use]
+    pub fn num_tracked(&self) -> usize {
+        self.windows.len()
+    }
+
+    fn prune(&mut self, now: Instant) {
+        let interval = self.limit.interval;
+        if self
+            .last_pruned_at
+            .is_some_and(|at| now.saturating_duration_since(at) < interval)
+        {
+            return;
+        }
+        self.last_pruned_at = Some(now);
+        self.windows
+            .retain(|_, window| now.saturating_duration_since(window.started_at) < interval);
+    }
+}
+
 fn on_opening(trigger: Trigger<OnAdd, ServerEndpoint>) {
     let server = trigger.entity();
     debug!("{server} opening");
@@ -197,6 +400,72 @@ fn on_close(trigger: Trigger<Close>, mut commands: Commands) {
     commands.trigger_targets(Closed { reason }, server);
 }
 
+fn on_draining(trigger: Trigger<OnAdd, Draining>, servers: Query<&Draining>) {
+    let server = trigger.entity();
+    let Ok(draining) = servers.get(server) else {
+        return;
+    };
+    debug!(
+        "{server} draining with grace period of {:?}: {}",
+        draining.grace_period, draining.reason
+    );
+}
+
+fn reject_while_draining(
+    trigger: Trigger<OnAdd, Session>,
+    parents: Query<&Parent>,
+    servers: Query<&Draining>,
+    mut commands: Commands,
+) {
+    let session = trigger.entity();
+    let Ok(draining) = parents
+        .get(session)
+        .and_then(|parent| servers.get(parent.get()))
+    else {
+        return;
+    };
+    debug!("{session} connected while server is draining, disconnecting");
+    commands.trigger_targets(Disconnect::new(&draining.reason), session);
+}
+
+fn close_drained(
+    servers: Query<(Entity, &Draining, Option<&Children>)>,
+    // retained sessions are already disconnected, and will never close
+    sessions: Query<(), (With<SessionEndpoint>, Without<DisconnectedRetained>)>,
+    mut commands: Commands,
+) {
+    let now = Instant::now();
+    for (server, draining, children) in &servers {
+        let all_disconnected = children
+            .is_none_or(|children| children.iter().all(|&child| sessions.get(child).is_err()));
+        if now >= draining.deadline() {
+            debug!("{server} grace period elapsed, closing");
+        } else if all_disconnected {
+            debug!("{server} has no more sessions, closing");
+        } else {
+            continue;
+        }
+        commands.trigger_targets(Close::new(&draining.reason), server);
+    }
+}
+
+fn close_on_exit(
+    mut exits: EventReader<AppExit>,
+    servers: Query<Entity, With<ServerEndpoint>>,
+    mut commands: Commands,
+) {
+    if exits.is_empty() {
+        return;
+    }
+    exits.clear();
+
+    let servers = servers.iter().collect::<Vec<_>>();
+    if !servers.is_empty() {
+        debug!("App exiting, closing {} servers", servers.len());
+        commands.trigger_targets(Close::new(APP_EXIT_DISCONNECT_REASON), servers);
+    }
+}
+
 fn on_closed(trigger: Trigger<Closed>, children: Query<&Children>, mut commands: Commands) {
     let server = trigger.entity();
     let children = children
@@ -220,6 +489,8 @@ fn on_closed(trigger: Trigger<Closed>, children: Query<&Children>, mut commands:
 
 #[cfg(test)]
 mod tests {
+    #![expect(clippy::unwrap_used, reason = "testing")]
+
     use {
         super::*,
         crate::{
@@ -227,6 +498,7 @@ mod tests {
             connection::{DisconnectReason, Disconnected},
         },
         bevy_hierarchy::BuildChildren,
+        core::net::Ipv4Addr,
     };
 
     #[test]
@@ -280,4 +552,105 @@ mod tests {
         assert!(app.world().get_entity(server).is_err());
         assert!(app.world().resource::<HasClosed>().0);
     }
+
+    #[test]
+    fn drain() {
+        const REASON: &str = "server shutting down";
+
+        #[derive(Resource, Default)]
+        struct Disconnects(Vec<(Entity, String)>);
+
+        let mut app = App::new();
+        app.add_plugins(AeronetIoPlugin)
+            .init_resource::<Disconnects>()
+            .add_observer(
+                |trigger: Trigger<Disconnected>, mut disconnects: ResMut<Disconnects>| {
+                    let DisconnectReason::User(reason) = &trigger.event().reason else {
+                        panic!("should be disconnected by the user");
+                    };
+                    disconnects.0.push((trigger.entity(), reason.clone()));
+                },
+            );
+
+        let server = app.world_mut().spawn(Server::new(Instant::now())).id();
+        let existing = app
+            .world_mut()
+            .spawn(Session::new(Instant::now(), 1000))
+            .set_parent(server)
+            .id();
+        app.update();
+
+        app.world_mut()
+            .entity_mut(server)
+            .insert(Draining::new(Duration::from_secs(60), REASON));
+        let new = app.world_mut().spawn_empty().set_parent(server).id();
+        app.world_mut()
+            .entity_mut(new)
+            .insert(Session::new(Instant::now(), 1000));
+        app.update();
+
+        // new session is rejected, existing session is left alone
+        assert_eq!(
+            vec![(new, REASON.to_owned())],
+            app.world().resource::<Disconnects>().0
+        );
+        assert!(app.world().get_entity(new).is_err());
+        assert!(app.world().get_entity(existing).is_ok());
+        assert!(app.world().get_entity(server).is_ok());
+
+        // grace period elapses
+        app.world_mut()
+            .get_mut::<Draining>(server)
+            .unwrap()
+            .grace_period = Duration::ZERO;
+        app.update();
+
+        assert_eq!(
+            vec![(new, REASON.to_owned()), (existing, REASON.to_owned())],
+            app.world().resource::<Disconnects>().0
+        );
+        assert!(app.world().get_entity(existing).is_err());
+        assert!(app.world().get_entity(server).is_err());
+    }
+
+    #[test]
+    fn drain_ignores_retained() {
+        let mut app = App::new();
+        app.add_plugins(AeronetIoPlugin);
+
+        let server = app.world_mut().spawn(Server::new(Instant::now())).id();
+        app.world_mut()
+            .spawn((Session::new(Instant::now(), 1000), DisconnectedRetained))
+            .set_parent(server);
+        app.world_mut().entity_mut(server).insert(Draining::new(
+            Duration::from_secs(60),
+            "server shutting down",
+        ));
+        app.update();
+
+        assert!(app.world().get_entity(server).is_err());
+    }
+
+    #[test]
+    fn peer_rate_limit() {
+        const INTERVAL: Duration = Duration::from_secs(1);
+
+        let a = IpAddr::from([127, 0, 0, 1]);
+        let b = IpAddr::from([127, 0, 0, 2]);
+        let mut limiter = PeerRateLimiter::new(PeerRateLimit::new(2, INTERVAL));
+        let now = Instant::now();
+
+        assert!(limiter.try_acquire(a, now));
+        assert!(limiter.try_acquire(a, now));
+        assert!(!limiter.try_acquire(a, now));
+        // IPv4-mapped IPv6 address counts as the same peer
+        assert!(!limiter.try_acquire(IpAddr::V6(Ipv4Addr::LOCALHOST.to_ipv6_mapped()), now));
+        // other peers are unaffected
+        assert!(limiter.try_acquire(b, now));
+
+        // window elapses
+        let later = now + INTERVAL;
+        assert!(limiter.try_acquire(a, later));
+        assert_eq!(1, limiter.num_tracked());
+    }
 }
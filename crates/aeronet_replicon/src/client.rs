@@ -1,7 +1,7 @@
 //! Client-side [`bevy_replicon`] support.
 
 use {
-    crate::convert,
+    crate::{convert, framing::RepliconFraming},
     aeronet_io::{Session, SessionEndpoint, connection::Disconnect, web_time::Instant},
     aeronet_transport::{
         AeronetTransportPlugin, Transport, TransportSet,
@@ -12,7 +12,7 @@ use {
     bevy_reflect::prelude::*,
     bevy_replicon::prelude::*,
     core::{num::Saturating, time::Duration},
-    tracing::warn,
+    tracing::{trace, warn},
 };
 
 /// Provides a [`bevy_replicon`] client backend using [`Session`]s for
@@ -20,6 +20,17 @@ use {
 ///
 /// To make a [`Session`] be used by [`bevy_replicon`], add the
 /// [`AeronetRepliconClient`] component.
+///
+/// The network statistics of the [`RepliconClient`] are the transport layer's
+/// own measurements, averaged across all connected sessions:
+/// - [`RepliconClient::rtt`] is the [`Transport::rtt`] estimate
+/// - [`RepliconClient::packet_loss`] is the most recently sampled
+///   [`SessionStatsSample::loss`]
+/// - [`RepliconClient::received_bps`] and [`RepliconClient::sent_bps`] are
+///   computed from the most recent [`SessionStatsSample`]
+///
+/// [`SessionStatsSample`]: aeronet_transport::sampling::SessionStatsSample
+/// [`SessionStatsSample::loss`]: aeronet_transport::sampling::SessionStatsSample::loss
 #[derive(Debug)]
 pub struct AeronetRepliconClientPlugin;
 
@@ -33,6 +44,7 @@ impl Plugin for AeronetRepliconClientPlugin {
         }
 
         app.register_type::<AeronetRepliconClient>()
+            .init_resource::<RepliconFraming>()
             .configure_sets(
                 PreUpdate,
                 (
@@ -177,13 +189,15 @@ fn update_state(
     for (session, transport, stats) in &clients {
         endpoint_exists = true;
 
-        let (Some(_), Some(_), Some(stats)) = (session, transport, stats) else {
+        let (Some(_), Some(transport), Some(stats)) = (session, transport, stats) else {
             continue;
         };
         let stats = stats.last().copied().unwrap_or_default();
 
         num_connected += 1;
-        sum_rtt += stats.msg_rtt;
+        // read the RTT estimate directly instead of the last sample,
+        // so that it's up to date even before the first sample is taken
+        sum_rtt += transport.rtt().get();
         sum_packet_loss += stats.loss;
         sum_bytes_recv += stats.packets_delta.bytes_recv;
         sum_bytes_sent += stats.packets_delta.bytes_sent;
@@ -225,14 +239,22 @@ fn update_state(
 
 fn poll(
     mut replicon_client: ResMut<RepliconClient>,
-    mut clients: Query<&mut Transport, With<AeronetRepliconClient>>,
+    mut clients: Query<(Entity, &mut Transport), With<AeronetRepliconClient>>,
+    framing: Res<RepliconFraming>,
 ) {
-    for mut transport in &mut clients {
+    for (client, mut transport) in &mut clients {
         for msg in transport.recv.msgs.drain() {
             let Some(channel_id) = convert::to_channel_id(msg.lane) else {
                 continue;
             };
-            replicon_client.insert_received(channel_id, msg.payload);
+            let msg = match framing.0.decode(channel_id, msg.payload.into()) {
+                Ok(msg) => msg,
+                Err(err) => {
+                    trace!("{client} received invalid message on channel {channel_id}: {err}");
+                    continue;
+                }
+            };
+            replicon_client.insert_received(channel_id, msg);
         }
 
         for _ in transport.recv.acks.drain() {
@@ -244,12 +266,218 @@ fn poll(
 fn flush(
     mut replicon_client: ResMut<RepliconClient>,
     mut clients: Query<&mut Transport, With<AeronetRepliconClient>>,
+    framing: Res<RepliconFraming>,
 ) {
     let now = Instant::now();
     for (channel_id, msg) in replicon_client.drain_sent() {
+        let msg = framing.0.encode(channel_id, msg);
         let lane_index = convert::to_lane_index(channel_id);
         for mut transport in &mut clients {
-            transport.send.push(lane_index, msg.clone(), now);
+            _ = transport.send.push(lane_index, msg.clone(), now);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![expect(clippy::unwrap_used, reason = "testing")]
+
+    use {
+        super::*,
+        crate::framing::{Framing, InvalidFrame},
+        aeronet_io::{
+            IoSet,
+            bytes::Bytes,
+            packet::{IP_MTU, RecvPacket},
+        },
+        bevy_replicon::{client::ClientPlugin, core::RepliconCorePlugin},
+        bevy_time::TimePlugin,
+        std::thread,
+    };
+
+    /// Peer which the client is connected to.
+    #[derive(Debug, Component)]
+    struct Peer;
+
+    /// Packets in flight between the client and the peer.
+    #[derive(Debug, Default, Resource)]
+    struct Wire {
+        to_client: Vec<Bytes>,
+        to_peer: Vec<Bytes>,
+    }
+
+    fn wire_poll(mut wire: ResMut<Wire>, mut sessions: Query<(&mut Session, Has<Peer>)>) {
+        let now = Instant::now();
+        for (mut session, is_peer) in &mut sessions {
+            let packets = if is_peer {
+                &mut wire.to_peer
+            } else {
+                &mut wire.to_client
+            };
+            session
+                .recv
+                .extend(packets.drain(..).map(|payload| RecvPacket {
+                    recv_at: now,
+                    payload,
+                }));
+        }
+    }
+
+    fn wire_flush(mut wire: ResMut<Wire>, mut sessions: Query<(&mut Session, Has<Peer>)>) {
+        for (mut session, is_peer) in &mut sessions {
+            let packets = if is_peer {
+                &mut wire.to_client
+            } else {
+                &mut wire.to_peer
+            };
+            packets.append(&mut session.send);
+        }
+    }
+
+    #[test]
+    fn stats_use_transport_rtt() {
+        let mut app = App::new();
+        app.add_plugins((TimePlugin, AeronetRepliconClientPlugin))
+            .init_resource::<RepliconChannels>()
+            .init_resource::<RepliconClient>()
+            .init_resource::<Wire>()
+            .add_systems(PreUpdate, wire_poll.in_set(IoSet::Poll))
+            .add_systems(PostUpdate, wire_flush.in_set(IoSet::Flush));
+
+        let now = Instant::now();
+        let channels = app.world().resource::<RepliconChannels>();
+        let recv_lanes = channels
+            .client_channels()
+            .iter()
+            .map(|channel| convert::to_lane_kind(channel.kind))
+            .collect::<Vec<_>>();
+        let send_lanes = channels
+            .server_channels()
+            .iter()
+            .map(|channel| convert::to_lane_kind(channel.kind))
+            .collect::<Vec<_>>();
+        let session = Session::new(now, IP_MTU);
+        let transport = Transport::new(&session, recv_lanes, send_lanes, now).unwrap();
+        app.world_mut().spawn((Peer, session, transport));
+
+        let client = app
+            .world_mut()
+            .spawn((
+                AeronetRepliconClient,
+                SessionEndpoint,
+                Session::new(now, IP_MTU),
+            ))
+            .id();
+
+        for _ in 0..10 {
+            app.update();
+            thread::sleep(Duration::from_millis(5));
+        }
+
+        let transport = app.world().get::<Transport>(client).unwrap();
+        assert!(transport.stats().packet_acks_recv.0 > 0);
+        let rtt = transport.rtt().get().as_secs_f64();
+        let replicon_client = app.world().resource::<RepliconClient>();
+        assert!(replicon_client.is_connected());
+        assert!((replicon_client.rtt() - rtt).abs() < f64::EPSILON);
+    }
+
+    /// Prefixes each message with a version byte.
+    #[derive(Debug)]
+    struct VersionedFraming;
+
+    const VERSION: u8 = 3;
+
+    impl Framing for VersionedFraming {
+        fn encode(&self, _: u8, msg: Bytes) -> Bytes {
+            [&[VERSION][..], &msg].concat().into()
+        }
+
+        fn decode(&self, _: u8, payload: Bytes) -> Result<Bytes, InvalidFrame> {
+            match payload.first() {
+                Some(&VERSION) => Ok(payload.slice(1..)),
+                _ => Err(InvalidFrame),
+            }
         }
     }
+
+    #[test]
+    fn custom_framing() {
+        let mut app = App::new();
+        app.add_plugins((
+            TimePlugin,
+            RepliconCorePlugin,
+            ClientPlugin,
+            AeronetRepliconClientPlugin,
+        ))
+        .insert_resource(RepliconFraming::new(VersionedFraming))
+        .init_resource::<Wire>()
+        .add_systems(PreUpdate, wire_poll.in_set(IoSet::Poll))
+        .add_systems(PostUpdate, wire_flush.in_set(IoSet::Flush));
+
+        let mut channels = app.world_mut().resource_mut::<RepliconChannels>();
+        let server_channel = channels.create_server_channel(ChannelKind::Ordered.into());
+        let client_channel = channels.create_client_channel(ChannelKind::Ordered.into());
+        app.finish();
+
+        let now = Instant::now();
+        let channels = app.world().resource::<RepliconChannels>();
+        let recv_lanes = channels
+            .client_channels()
+            .iter()
+            .map(|channel| convert::to_lane_kind(channel.kind))
+            .collect::<Vec<_>>();
+        let send_lanes = channels
+            .server_channels()
+            .iter()
+            .map(|channel| convert::to_lane_kind(channel.kind))
+            .collect::<Vec<_>>();
+        let session = Session::new(now, IP_MTU);
+        let mut transport = Transport::new(&session, recv_lanes, send_lanes, now).unwrap();
+        transport
+            .send
+            .push(
+                convert::to_lane_index(server_channel),
+                VersionedFraming.encode(server_channel, Bytes::from_static(b"from server")),
+                now,
+            )
+            .unwrap();
+        // messages with a different version are dropped
+        transport
+            .send
+            .push(
+                convert::to_lane_index(server_channel),
+                Bytes::from_static(b"\x00invalid"),
+                now,
+            )
+            .unwrap();
+        let peer = app.world_mut().spawn((Peer, session, transport)).id();
+
+        app.world_mut().spawn((
+            AeronetRepliconClient,
+            SessionEndpoint,
+            Session::new(now, IP_MTU),
+        ));
+
+        let mut received = Vec::new();
+        for _ in 0..10 {
+            app.update();
+            let mut replicon_client = app.world_mut().resource_mut::<RepliconClient>();
+            if replicon_client.is_connected() {
+                received.extend(replicon_client.receive(server_channel));
+                replicon_client.send(client_channel, Bytes::from_static(b"from client"));
+            }
+        }
+        assert_eq!(vec![Bytes::from_static(b"from server")], received);
+
+        let mut transport = app.world_mut().get_mut::<Transport>(peer).unwrap();
+        let msg = transport.recv.msgs.drain().next().unwrap();
+        assert_eq!(convert::to_lane_index(client_channel), msg.lane);
+        assert_eq!(
+            Bytes::from_static(b"from client"),
+            VersionedFraming
+                .decode(client_channel, msg.payload.into())
+                .unwrap()
+        );
+    }
 }
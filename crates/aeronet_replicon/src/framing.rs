@@ -0,0 +1,80 @@
+//! Customizing how [`bevy_replicon`] messages are framed on the wire.
+//!
+//! By default, each message which [`bevy_replicon`] sends on a channel is sent
+//! as-is as a single message on the lane with the same index (see
+//! [`convert`]). If you need a different wire format - for example, to add a
+//! version byte so that a custom client can tell which protocol it's talking
+//! to - implement [`Framing`] and insert it as a [`RepliconFraming`] resource.
+//!
+//! Both sides of a connection must use the same framing.
+//!
+//! [`convert`]: crate::convert
+
+use {
+    aeronet_io::bytes::Bytes,
+    bevy_ecs::prelude::*,
+    core::fmt::Debug,
+    derive_more::{Display, Error},
+};
+
+/// Encodes [`bevy_replicon`] messages into transport messages, and decodes
+/// them back.
+///
+/// See [`framing`](crate::framing).
+pub trait Framing: Debug + Send + Sync + 'static {
+    /// Encodes a message which [`bevy_replicon`] wants to send on
+    /// `channel_id` into the payload of a transport message.
+    fn encode(&self, channel_id: u8, msg: Bytes) -> Bytes;
+
+    /// Decodes the payload of a transport message received on `channel_id`
+    /// into a message for [`bevy_replicon`].
+    ///
+    /// # Errors
+    ///
+    /// Errors if the payload is not validly framed, in which case the message
+    /// is dropped.
+    fn decode(&self, channel_id: u8, payload: Bytes) -> Result<Bytes, InvalidFrame>;
+}
+
+/// [`Framing`] which sends [`bevy_replicon`] messages as-is, without any extra
+/// framing.
+///
+/// This is the default [`RepliconFraming`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RawFraming;
+
+impl Framing for RawFraming {
+    fn encode(&self, _: u8, msg: Bytes) -> Bytes {
+        msg
+    }
+
+    fn decode(&self, _: u8, payload: Bytes) -> Result<Bytes, InvalidFrame> {
+        Ok(payload)
+    }
+}
+
+/// [`Framing`] used by the client and server plugins to encode and decode
+/// [`bevy_replicon`] messages.
+///
+/// Defaults to [`RawFraming`].
+#[derive(Debug, Resource)]
+pub struct RepliconFraming(pub Box<dyn Framing>);
+
+impl RepliconFraming {
+    /// Creates a new [`RepliconFraming`] from a [`Framing`].
+    #[must_use]
+    pub fn new(framing: impl Framing) -> Self {
+        Self(Box::new(framing))
+    }
+}
+
+impl Default for RepliconFraming {
+    fn default() -> Self {
+        Self::new(RawFraming)
+    }
+}
+
+/// Failed to decode a message using a [`Framing`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Display, Error)]
+#[display("invalid frame")]
+pub struct InvalidFrame;
@@ -7,5 +7,6 @@
 #[cfg(feature = "client")]
 pub mod client;
 pub mod convert;
+pub mod framing;
 #[cfg(feature = "server")]
 pub mod server;
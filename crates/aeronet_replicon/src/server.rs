@@ -1,7 +1,7 @@
 //! Server-side [`bevy_replicon`] support.
 
 use {
-    crate::convert,
+    crate::{convert, framing::RepliconFraming},
     aeronet_io::{
         Session,
         connection::{DisconnectReason, Disconnected},
@@ -20,7 +20,7 @@ use {
         prelude::{ConnectedClients, RepliconChannels, RepliconServer},
         server::{ServerEvent, ServerSet},
     },
-    tracing::warn,
+    tracing::{trace, warn},
 };
 
 /// Provides a [`bevy_replicon`] server backend using [`Server`]s and
@@ -71,6 +71,7 @@ impl Plugin for AeronetRepliconServerPlugin {
                 .in_set(ServerTransportSet::Flush)
                 .run_if(resource_exists::<RepliconServer>),
         )
+        .init_resource::<RepliconFraming>()
         .add_observer(on_connected)
         .add_observer(on_disconnected);
     }
@@ -213,6 +214,7 @@ fn poll(
     mut replicon_server: ResMut<RepliconServer>,
     mut clients: Query<(Entity, &mut Transport, &Parent)>,
     open_servers: Query<(), OpenedServer>,
+    framing: Res<RepliconFraming>,
 ) {
     for (client, mut transport, server) in &mut clients {
         if open_servers.get(server.get()).is_err() {
@@ -224,7 +226,14 @@ fn poll(
             let Some(channel_id) = convert::to_channel_id(msg.lane) else {
                 continue;
             };
-            replicon_server.insert_received(client_id, channel_id, msg.payload);
+            let msg = match framing.0.decode(channel_id, msg.payload.into()) {
+                Ok(msg) => msg,
+                Err(err) => {
+                    trace!("{client} received invalid message on channel {channel_id}: {err}");
+                    continue;
+                }
+            };
+            replicon_server.insert_received(client_id, channel_id, msg);
         }
 
         for _ in transport.recv.acks.drain() {
@@ -259,7 +268,11 @@ fn update_client_data(
     }
 }
 
-fn flush(mut replicon_server: ResMut<RepliconServer>, mut clients: Query<&mut Transport>) {
+fn flush(
+    mut replicon_server: ResMut<RepliconServer>,
+    mut clients: Query<&mut Transport>,
+    framing: Res<RepliconFraming>,
+) {
     let now = Instant::now();
     for (client_id, channel_id, msg) in replicon_server.drain_sent() {
         let Some(mut transport) =
@@ -269,6 +282,7 @@ fn flush(mut replicon_server: ResMut<RepliconServer>, mut clients: Query<&mut Tr
         };
         let lane_index = convert::to_lane_index(channel_id);
 
-        transport.send.push(lane_index, msg, now);
+        let msg = framing.0.encode(channel_id, msg);
+        _ = transport.send.push(lane_index, msg, now);
     }
 }
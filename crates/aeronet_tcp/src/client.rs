@@ -0,0 +1,218 @@
+//! See [`TcpClient`].
+
+use {
+    crate::{
+        TcpRuntime,
+        session::{self, SessionError, SessionFrontend, TcpIo, TcpSessionPlugin, backend},
+    },
+    aeronet_io::{
+        IoSet, SessionEndpoint,
+        connection::{DisconnectReason, Disconnected, LocalAddr, PeerAddr},
+    },
+    bevy_app::prelude::*,
+    bevy_ecs::{prelude::*, system::EntityCommand},
+    core::net::SocketAddr,
+    derive_more::{Display, Error, From},
+    futures::{channel::oneshot, never::Never},
+    std::io,
+    tokio::net::{TcpStream, ToSocketAddrs},
+    tracing::{Instrument, debug, debug_span},
+};
+
+/// Allows using [`TcpClient`].
+#[derive(Debug)]
+pub struct TcpClientPlugin;
+
+impl Plugin for TcpClientPlugin {
+    fn build(&self, app: &mut App) {
+        if !app.is_plugin_added::<TcpSessionPlugin>() {
+            app.add_plugins(TcpSessionPlugin);
+        }
+
+        app.add_systems(
+            PreUpdate,
+            poll_clients.in_set(IoSet::Poll).before(session::poll),
+        );
+    }
+}
+
+/// TCP session implementation which acts as a dedicated client, connecting to
+/// a target socket.
+///
+/// Use [`TcpClient::connect`] to start a connection.
+#[derive(Debug, Component)]
+#[require(SessionEndpoint)]
+pub struct TcpClient(ClientFrontend);
+
+impl TcpClient {
+    /// Creates an [`EntityCommand`] to set up a session and connect it to the
+    /// `target`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use {
+    ///     aeronet_tcp::client::TcpClient,
+    ///     bevy_ecs::{prelude::*, system::EntityCommand},
+    /// };
+    ///
+    /// # fn run(mut commands: Commands, world: &mut World) {
+    /// let target = "[::1]:1234";
+    ///
+    /// // using `Commands`
+    /// commands.spawn_empty().queue(TcpClient::connect(target));
+    ///
+    /// // using mutable `World` access
+    /// let session = world.spawn_empty().id();
+    /// TcpClient::connect(target).apply(session, world);
+    /// # }
+    /// ```
+    #[must_use]
+    pub fn connect(target: impl ToSocketAddrs + Send + 'static) -> impl EntityCommand {
+        move |session: Entity, world: &mut World| connect(session, world, target)
+    }
+}
+
+fn connect(session: Entity, world: &mut World, target: impl ToSocketAddrs + Send + 'static) {
+    let runtime = world.resource::<TcpRuntime>().clone();
+
+    let (send_dc, recv_dc) = oneshot::channel::<DisconnectReason<ClientError>>();
+    let (send_next, recv_next) = oneshot::channel::<ToConnected>();
+    runtime.spawn_on_self(
+        async move {
+            let Err(reason) = start(target, send_next).await;
+            _ = send_dc.send(reason);
+        }
+        .instrument(debug_span!("client", %session)),
+    );
+
+    world
+        .entity_mut(session)
+        .insert(TcpClient(ClientFrontend::Connecting { recv_dc, recv_next }));
+}
+
+/// [`TcpClient`] error.
+#[derive(Debug, Display, Error, From)]
+#[non_exhaustive]
+pub enum ClientError {
+    /// Failed to connect to the target.
+    #[display("failed to connect")]
+    Connect(io::Error),
+    /// Generic session error.
+    #[from]
+    Session(SessionError),
+}
+
+#[derive(Debug)]
+enum ClientFrontend {
+    Connecting {
+        recv_dc: oneshot::Receiver<DisconnectReason<ClientError>>,
+        recv_next: oneshot::Receiver<ToConnected>,
+    },
+    Connected {
+        recv_dc: oneshot::Receiver<DisconnectReason<ClientError>>,
+    },
+    Disconnected,
+}
+
+#[derive(Debug)]
+struct ToConnected {
+    local_addr: SocketAddr,
+    peer_addr: SocketAddr,
+    frontend: SessionFrontend,
+}
+
+async fn start(
+    target: impl ToSocketAddrs,
+    send_connected: oneshot::Sender<ToConnected>,
+) -> Result<Never, DisconnectReason<ClientError>> {
+    debug!("Spawning backend task to connect");
+
+    let stream = TcpStream::connect(target)
+        .await
+        .map_err(ClientError::Connect)?;
+    stream.set_nodelay(true).map_err(ClientError::Connect)?;
+    let local_addr = stream
+        .local_addr()
+        .map_err(SessionError::GetLocalAddr)
+        .map_err(ClientError::Session)?;
+    let peer_addr = stream
+        .peer_addr()
+        .map_err(SessionError::GetPeerAddr)
+        .map_err(ClientError::Session)?;
+    debug!("Connected to {peer_addr}");
+
+    let (reader, writer) = stream.into_split();
+    let (frontend, backend) = backend::split(reader, writer);
+    send_connected
+        .send(ToConnected {
+            local_addr,
+            peer_addr,
+            frontend,
+        })
+        .map_err(|_| SessionError::FrontendClosed)
+        .map_err(ClientError::Session)?;
+
+    debug!("Starting session loop");
+    backend
+        .start()
+        .await
+        .map_err(|reason| reason.map_err(ClientError::Session))
+}
+
+fn poll_clients(mut commands: Commands, mut frontends: Query<(Entity, &mut TcpClient)>) {
+    for (session, mut frontend) in &mut frontends {
+        replace_with::replace_with_or_abort(&mut frontend.0, |state| match state {
+            ClientFrontend::Connecting { recv_dc, recv_next } => {
+                poll_connecting(&mut commands, session, recv_dc, recv_next)
+            }
+            ClientFrontend::Connected { mut recv_dc } => {
+                if should_disconnect(&mut commands, session, &mut recv_dc) {
+                    ClientFrontend::Disconnected
+                } else {
+                    ClientFrontend::Connected { recv_dc }
+                }
+            }
+            ClientFrontend::Disconnected => state,
+        });
+    }
+}
+
+fn poll_connecting(
+    commands: &mut Commands,
+    session: Entity,
+    mut recv_dc: oneshot::Receiver<DisconnectReason<ClientError>>,
+    mut recv_next: oneshot::Receiver<ToConnected>,
+) -> ClientFrontend {
+    if should_disconnect(commands, session, &mut recv_dc) {
+        return ClientFrontend::Disconnected;
+    }
+
+    let Ok(Some(next)) = recv_next.try_recv() else {
+        return ClientFrontend::Connecting { recv_dc, recv_next };
+    };
+
+    commands.entity(session).insert((
+        TcpIo::from(next.frontend),
+        LocalAddr(next.local_addr),
+        PeerAddr(next.peer_addr),
+    ));
+    ClientFrontend::Connected { recv_dc }
+}
+
+fn should_disconnect(
+    commands: &mut Commands,
+    session: Entity,
+    recv_dc: &mut oneshot::Receiver<DisconnectReason<ClientError>>,
+) -> bool {
+    let dc_reason = match recv_dc.try_recv() {
+        Ok(None) => None,
+        Ok(Some(dc_reason)) => Some(dc_reason),
+        Err(_) => Some(ClientError::Session(SessionError::BackendClosed).into()),
+    };
+    dc_reason.is_some_and(|reason| {
+        let reason = reason.map_err(anyhow::Error::new);
+        commands.trigger_targets(Disconnected { reason, code: None }, session);
+        true
+    })
+}
@@ -0,0 +1,45 @@
+use {bevy_ecs::prelude::*, core::future::Future};
+
+/// Holds a handle to the `tokio` runtime used for driving the TCP IO layer.
+///
+/// Use the [`Default`] impl to create and leak a new `tokio` runtime, and use
+/// that as the [`TcpRuntime`] handle.
+///
+/// If you already have a runtime handle, you can use
+/// `TcpRuntime::from(handle)` to create a runtime from that handle.
+#[derive(Debug, Clone, Resource)]
+pub struct TcpRuntime {
+    handle: tokio::runtime::Handle,
+}
+
+impl Default for TcpRuntime {
+    fn default() -> Self {
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .enable_all()
+            .build()
+            .expect("failed to create tokio runtime");
+        let runtime = Box::leak(Box::new(runtime));
+        Self {
+            handle: runtime.handle().clone(),
+        }
+    }
+}
+
+impl From<tokio::runtime::Handle> for TcpRuntime {
+    fn from(value: tokio::runtime::Handle) -> Self {
+        Self { handle: value }
+    }
+}
+
+impl TcpRuntime {
+    /// Spawns a future on the task runtime `self`.
+    ///
+    /// If you are already in a task context, use [`tokio::spawn`] to avoid
+    /// having to pass around [`TcpRuntime`].
+    pub fn spawn_on_self<F>(&self, future: F)
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        self.handle.spawn(future);
+    }
+}
@@ -0,0 +1,354 @@
+//! See [`TcpServer`].
+
+use {
+    crate::{
+        TcpRuntime,
+        session::{self, SessionError, SessionFrontend, TcpIo, TcpSessionPlugin, backend},
+    },
+    aeronet_io::{
+        IoSet, SessionEndpoint,
+        connection::{DisconnectReason, Disconnected, LocalAddr, PeerAddr},
+        server::{CloseReason, Closed, Server, ServerEndpoint},
+    },
+    bevy_app::prelude::*,
+    bevy_ecs::{prelude::*, system::EntityCommand},
+    bevy_hierarchy::BuildChildren,
+    core::net::SocketAddr,
+    derive_more::{Display, Error, From},
+    futures::{
+        SinkExt,
+        channel::{mpsc, oneshot},
+        never::Never,
+    },
+    std::io,
+    tokio::net::{TcpListener, TcpStream},
+    tracing::{Instrument, debug, debug_span},
+    web_time::Instant,
+};
+
+/// Allows using [`TcpServer`].
+#[derive(Debug)]
+pub struct TcpServerPlugin;
+
+impl Plugin for TcpServerPlugin {
+    fn build(&self, app: &mut App) {
+        if !app.is_plugin_added::<TcpSessionPlugin>() {
+            app.add_plugins(TcpSessionPlugin);
+        }
+
+        app.add_systems(
+            PreUpdate,
+            (poll_servers, poll_clients)
+                .in_set(IoSet::Poll)
+                .before(session::poll),
+        );
+    }
+}
+
+/// TCP server implementation which listens for client connections, and
+/// coordinates messaging between multiple clients.
+///
+/// Use [`TcpServer::open`] to start opening a server.
+#[derive(Debug, Component)]
+#[require(ServerEndpoint)]
+pub struct TcpServer(Frontend);
+
+impl TcpServer {
+    /// Creates an [`EntityCommand`] to set up a server and have it start
+    /// listening for connections on `bind_address`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use {
+    ///     aeronet_tcp::server::TcpServer,
+    ///     bevy_ecs::{prelude::*, system::EntityCommand},
+    ///     core::net::{Ipv6Addr, SocketAddr},
+    /// };
+    ///
+    /// # fn run(mut commands: Commands, world: &mut World) {
+    /// let bind_address = SocketAddr::new(Ipv6Addr::UNSPECIFIED.into(), 12345);
+    ///
+    /// // using `Commands`
+    /// commands.spawn_empty().queue(TcpServer::open(bind_address));
+    ///
+    /// // using mutable `World` access
+    /// let server = world.spawn_empty().id();
+    /// TcpServer::open(bind_address).apply(server, world);
+    /// # }
+    /// ```
+    #[must_use]
+    pub fn open(bind_address: impl Into<SocketAddr>) -> impl EntityCommand {
+        let bind_address = bind_address.into();
+        move |server: Entity, world: &mut World| open(server, world, bind_address)
+    }
+}
+
+fn open(server: Entity, world: &mut World, bind_address: SocketAddr) {
+    let runtime = world.resource::<TcpRuntime>().clone();
+
+    let (send_closed, recv_closed) = oneshot::channel::<CloseReason<ServerError>>();
+    let (send_next, recv_next) = oneshot::channel::<ToOpen>();
+    runtime.spawn_on_self(
+        async move {
+            let Err(err) = start(bind_address, send_next).await;
+            _ = send_closed.send(CloseReason::Error(err));
+        }
+        .instrument(debug_span!("server", %server)),
+    );
+
+    world
+        .entity_mut(server)
+        .insert(TcpServer(Frontend::Opening {
+            recv_closed,
+            recv_next,
+        }));
+}
+
+/// [`TcpServer`] error.
+#[derive(Debug, Display, Error, From)]
+#[non_exhaustive]
+pub enum ServerError {
+    /// Failed to bind a socket to the address given in [`TcpServer::open`].
+    #[display("failed to bind socket")]
+    BindSocket(io::Error),
+    /// Failed to accept a connection.
+    #[display("failed to accept connection")]
+    AcceptConnection(io::Error),
+    /// Generic session error.
+    #[from]
+    Session(SessionError),
+}
+
+#[derive(Debug)]
+enum Frontend {
+    Opening {
+        recv_closed: oneshot::Receiver<CloseReason<ServerError>>,
+        recv_next: oneshot::Receiver<ToOpen>,
+    },
+    Open {
+        recv_closed: oneshot::Receiver<CloseReason<ServerError>>,
+        recv_connected: mpsc::Receiver<ToConnected>,
+    },
+    Closed,
+}
+
+#[derive(Debug, Component)]
+#[require(SessionEndpoint)]
+enum ClientFrontend {
+    Connected {
+        recv_dc: oneshot::Receiver<DisconnectReason<ServerError>>,
+    },
+    Disconnected,
+}
+
+#[derive(Debug)]
+struct ToOpen {
+    local_addr: SocketAddr,
+    recv_connected: mpsc::Receiver<ToConnected>,
+}
+
+#[derive(Debug)]
+struct ToConnected {
+    peer_addr: SocketAddr,
+    frontend: SessionFrontend,
+    send_session_entity: oneshot::Sender<Entity>,
+    recv_dc: oneshot::Receiver<DisconnectReason<ServerError>>,
+}
+
+async fn start(
+    bind_address: SocketAddr,
+    send_next: oneshot::Sender<ToOpen>,
+) -> Result<Never, ServerError> {
+    let listener = TcpListener::bind(bind_address)
+        .await
+        .map_err(ServerError::BindSocket)?;
+    debug!("Listening on {bind_address}");
+
+    let (send_connected, recv_connected) = mpsc::channel::<ToConnected>(1);
+
+    let local_addr = listener.local_addr().map_err(SessionError::GetLocalAddr)?;
+    let next = ToOpen {
+        local_addr,
+        recv_connected,
+    };
+    send_next
+        .send(next)
+        .map_err(|_| SessionError::FrontendClosed)?;
+
+    debug!("Starting server loop");
+    loop {
+        let (stream, peer_addr) = listener
+            .accept()
+            .await
+            .map_err(ServerError::AcceptConnection)?;
+        tokio::spawn({
+            let send_connected = send_connected.clone();
+            async move {
+                if let Err(err) = accept_session(stream, peer_addr, send_connected).await {
+                    debug!("Failed to accept session: {err:?}");
+                }
+            }
+        });
+    }
+}
+
+async fn accept_session(
+    stream: TcpStream,
+    peer_addr: SocketAddr,
+    mut send_connected: mpsc::Sender<ToConnected>,
+) -> Result<(), ServerError> {
+    stream
+        .set_nodelay(true)
+        .map_err(ServerError::AcceptConnection)?;
+
+    let (reader, writer) = stream.into_split();
+    let (frontend, backend) = backend::split(reader, writer);
+    let (send_session_entity, recv_session_entity) = oneshot::channel::<Entity>();
+    let (send_dc, recv_dc) = oneshot::channel::<DisconnectReason<ServerError>>();
+    send_connected
+        .send(ToConnected {
+            peer_addr,
+            frontend,
+            send_session_entity,
+            recv_dc,
+        })
+        .await
+        .map_err(|_| SessionError::FrontendClosed)?;
+    let session = recv_session_entity
+        .await
+        .map_err(|_| SessionError::FrontendClosed)?;
+
+    debug!("Starting session loop");
+    let Err(dc_reason) = backend
+        .start()
+        .instrument(debug_span!("session", %session))
+        .await;
+    _ = send_dc.send(dc_reason.map_err(ServerError::Session));
+    Ok(())
+}
+
+fn poll_servers(mut commands: Commands, mut servers: Query<(Entity, &mut TcpServer)>) {
+    for (server, mut frontend) in &mut servers {
+        replace_with::replace_with_or_abort(&mut frontend.0, |state| match state {
+            Frontend::Opening {
+                recv_closed,
+                recv_next,
+            } => poll_opening(&mut commands, server, recv_closed, recv_next),
+            Frontend::Open {
+                recv_closed,
+                recv_connected,
+            } => poll_open(&mut commands, server, recv_closed, recv_connected),
+            Frontend::Closed => state,
+        });
+    }
+}
+
+fn poll_opening(
+    commands: &mut Commands,
+    server: Entity,
+    mut recv_closed: oneshot::Receiver<CloseReason<ServerError>>,
+    mut recv_next: oneshot::Receiver<ToOpen>,
+) -> Frontend {
+    if should_close(commands, server, &mut recv_closed) {
+        return Frontend::Closed;
+    }
+
+    let Ok(Some(next)) = recv_next.try_recv() else {
+        return Frontend::Opening {
+            recv_closed,
+            recv_next,
+        };
+    };
+
+    let now = Instant::now();
+    commands
+        .entity(server)
+        .insert((Server::new(now), LocalAddr(next.local_addr)));
+    Frontend::Open {
+        recv_closed,
+        recv_connected: next.recv_connected,
+    }
+}
+
+fn poll_open(
+    commands: &mut Commands,
+    server: Entity,
+    mut recv_closed: oneshot::Receiver<CloseReason<ServerError>>,
+    mut recv_connected: mpsc::Receiver<ToConnected>,
+) -> Frontend {
+    if should_close(commands, server, &mut recv_closed) {
+        return Frontend::Closed;
+    }
+
+    while let Ok(Some(connected)) = recv_connected.try_next() {
+        let session = commands
+            // spawn -> parent -> insert, so that Parent is available
+            // as soon as other components are added
+            .spawn_empty()
+            .set_parent(server)
+            .insert((
+                ClientFrontend::Connected {
+                    recv_dc: connected.recv_dc,
+                },
+                PeerAddr(connected.peer_addr),
+                TcpIo::from(connected.frontend),
+            ))
+            .id();
+        _ = connected.send_session_entity.send(session);
+    }
+
+    Frontend::Open {
+        recv_closed,
+        recv_connected,
+    }
+}
+
+fn should_close(
+    commands: &mut Commands,
+    server: Entity,
+    recv_closed: &mut oneshot::Receiver<CloseReason<ServerError>>,
+) -> bool {
+    let close_reason = match recv_closed.try_recv() {
+        Ok(None) => None,
+        Ok(Some(close_reason)) => Some(close_reason),
+        Err(_) => Some(ServerError::Session(SessionError::BackendClosed).into()),
+    };
+    close_reason.is_some_and(|reason| {
+        let reason = reason.map_err(anyhow::Error::new);
+        commands.trigger_targets(Closed { reason }, server);
+        true
+    })
+}
+
+fn poll_clients(mut commands: Commands, mut clients: Query<(Entity, &mut ClientFrontend)>) {
+    for (client, mut frontend) in &mut clients {
+        replace_with::replace_with_or_abort(&mut *frontend, |state| match state {
+            ClientFrontend::Connected { mut recv_dc } => {
+                if should_disconnect(&mut commands, client, &mut recv_dc) {
+                    ClientFrontend::Disconnected
+                } else {
+                    ClientFrontend::Connected { recv_dc }
+                }
+            }
+            ClientFrontend::Disconnected => state,
+        });
+    }
+}
+
+fn should_disconnect(
+    commands: &mut Commands,
+    client: Entity,
+    recv_dc: &mut oneshot::Receiver<DisconnectReason<ServerError>>,
+) -> bool {
+    let dc_reason = match recv_dc.try_recv() {
+        Ok(None) => None,
+        Ok(Some(dc_reason)) => Some(dc_reason),
+        Err(_) => Some(ServerError::Session(SessionError::BackendClosed).into()),
+    };
+    dc_reason.is_some_and(|reason| {
+        let reason = reason.map_err(anyhow::Error::new);
+        commands.trigger_targets(Disconnected { reason, code: None }, client);
+        true
+    })
+}
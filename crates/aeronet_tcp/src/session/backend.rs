@@ -0,0 +1,173 @@
+use {
+    super::{MAX_FRAME_LEN, SessionError, SessionFrontend},
+    aeronet_io::{connection::DisconnectReason, packet::RecvPacket},
+    bytes::Bytes,
+    core::pin::pin,
+    futures::{
+        StreamExt,
+        channel::{mpsc, oneshot},
+        future::{self, Either},
+        never::Never,
+    },
+    std::io,
+    tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt},
+    web_time::Instant,
+};
+
+const FRAME_KIND_PACKET: u8 = 0;
+const FRAME_KIND_DISCONNECT: u8 = 1;
+
+#[derive(Debug)]
+pub struct SessionBackend<R, W> {
+    reader: R,
+    writer: W,
+    send_packet_b2f: mpsc::UnboundedSender<RecvPacket>,
+    recv_packet_f2b: mpsc::UnboundedReceiver<Bytes>,
+    recv_user_dc: oneshot::Receiver<String>,
+}
+
+pub fn split<R, W>(reader: R, writer: W) -> (SessionFrontend, SessionBackend<R, W>) {
+    let (send_packet_b2f, recv_packet_b2f) = mpsc::unbounded::<RecvPacket>();
+    let (send_packet_f2b, recv_packet_f2b) = mpsc::unbounded::<Bytes>();
+    let (send_user_dc, recv_user_dc) = oneshot::channel::<String>();
+
+    (
+        SessionFrontend {
+            recv_packet_b2f,
+            send_packet_f2b,
+            send_user_dc,
+        },
+        SessionBackend {
+            reader,
+            writer,
+            send_packet_b2f,
+            recv_packet_f2b,
+            recv_user_dc,
+        },
+    )
+}
+
+impl<R, W> SessionBackend<R, W>
+where
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    pub async fn start(self) -> Result<Never, DisconnectReason<SessionError>> {
+        let Self {
+            reader,
+            writer,
+            send_packet_b2f,
+            recv_packet_f2b,
+            recv_user_dc,
+        } = self;
+
+        // reading a frame is not cancel-safe, so we can't `select!` between
+        // reading and sending in a single loop - instead, each half of the
+        // stream gets its own loop
+        let recv = pin!(recv_loop(reader, send_packet_b2f));
+        let send = pin!(send_loop(writer, recv_packet_f2b, recv_user_dc));
+        match future::select(recv, send).await {
+            Either::Left((Err(reason), _)) | Either::Right((Err(reason), _)) => Err(reason),
+        }
+    }
+}
+
+async fn recv_loop(
+    mut reader: impl AsyncRead + Unpin,
+    send_packet_b2f: mpsc::UnboundedSender<RecvPacket>,
+) -> Result<Never, DisconnectReason<SessionError>> {
+    loop {
+        let kind = match reader.read_u8().await {
+            Ok(kind) => kind,
+            Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => {
+                return Err(SessionError::RecvStreamClosed.into());
+            }
+            Err(err) => return Err(SessionError::Recv(err).into()),
+        };
+        let len = reader.read_u32().await.map_err(SessionError::Recv)?;
+        let len = usize::try_from(len).unwrap_or(usize::MAX);
+        if len > MAX_FRAME_LEN {
+            return Err(SessionError::FrameTooLong { len }.into());
+        }
+
+        let mut payload = vec![0; len];
+        reader
+            .read_exact(&mut payload)
+            .await
+            .map_err(SessionError::Recv)?;
+        let now = Instant::now();
+
+        match kind {
+            FRAME_KIND_PACKET => {
+                send_packet_b2f
+                    .unbounded_send(RecvPacket {
+                        recv_at: now,
+                        payload: Bytes::from(payload),
+                    })
+                    .map_err(|_| SessionError::BackendClosed)?;
+            }
+            FRAME_KIND_DISCONNECT => {
+                let reason = String::from_utf8_lossy(&payload).into_owned();
+                return Err(DisconnectReason::Peer(reason));
+            }
+            kind => return Err(SessionError::InvalidFrameKind(kind).into()),
+        }
+    }
+}
+
+async fn send_loop(
+    mut writer: impl AsyncWrite + Unpin,
+    mut recv_packet_f2b: mpsc::UnboundedReceiver<Bytes>,
+    mut recv_user_dc: oneshot::Receiver<String>,
+) -> Result<Never, DisconnectReason<SessionError>> {
+    loop {
+        // if the frontend is dropped right after disconnecting, both of these
+        // are ready at once - make sure we still send the disconnect frame
+        futures::select_biased! {
+            reason = recv_user_dc => {
+                let reason = reason.map_err(|_| SessionError::FrontendClosed)?;
+                // send out packets which were flushed before the user
+                // disconnected, so they arrive before the disconnect frame
+                while let Ok(Some(packet)) = recv_packet_f2b.try_next() {
+                    write_frame(&mut writer, FRAME_KIND_PACKET, &packet).await?;
+                }
+                write_frame(&mut writer, FRAME_KIND_DISCONNECT, reason.as_bytes()).await?;
+                writer.flush().await.map_err(SessionError::Send)?;
+                // we're disconnecting anyway, so if this fails, it doesn't matter
+                _ = writer.shutdown().await;
+                return Err(DisconnectReason::User(reason));
+            }
+            packet = recv_packet_f2b.next() => {
+                let packet = packet.ok_or(SessionError::FrontendClosed)?;
+                write_frame(&mut writer, FRAME_KIND_PACKET, &packet).await?;
+                // write out the rest of the packets flushed alongside this one
+                // before flushing, so a buffered writer can batch them up
+                while let Ok(Some(packet)) = recv_packet_f2b.try_next() {
+                    write_frame(&mut writer, FRAME_KIND_PACKET, &packet).await?;
+                }
+                // the writer may be buffered, e.g. a `BufWriter` or TLS stream,
+                // so make sure the frames actually go out
+                writer.flush().await.map_err(SessionError::Send)?;
+            }
+        }
+    }
+}
+
+async fn write_frame(
+    writer: &mut (impl AsyncWrite + Unpin),
+    kind: u8,
+    payload: &[u8],
+) -> Result<(), SessionError> {
+    let len = u32::try_from(payload.len()).map_err(|_| {
+        SessionError::Send(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "frame payload too long",
+        ))
+    })?;
+
+    let mut frame = Vec::with_capacity(super::FRAME_HEADER_LEN + payload.len());
+    frame.push(kind);
+    frame.extend_from_slice(&len.to_be_bytes());
+    frame.extend_from_slice(payload);
+    writer.write_all(&frame).await.map_err(SessionError::Send)
+}
@@ -0,0 +1,214 @@
+//! Implementation for TCP sessions.
+//!
+//! This logic is shared between clients, servers, and [`StreamIo`] sessions.
+//!
+//! [`StreamIo`]: crate::stream::StreamIo
+
+pub(crate) mod backend;
+
+use {
+    crate::TcpRuntime,
+    aeronet_io::{
+        AeronetIoPlugin, IoSet, Session,
+        connection::{DROP_DISCONNECT_REASON, Disconnect, DisconnectedRetained},
+        packet::{IP_MTU, RecvPacket},
+    },
+    bevy_app::prelude::*,
+    bevy_ecs::prelude::*,
+    bytes::Bytes,
+    core::num::Saturating,
+    derive_more::{Display, Error},
+    futures::channel::{mpsc, oneshot},
+    std::io,
+    tracing::{trace, trace_span},
+    web_time::Instant,
+};
+
+#[derive(Debug)]
+pub(crate) struct TcpSessionPlugin;
+
+impl Plugin for TcpSessionPlugin {
+    fn build(&self, app: &mut App) {
+        if !app.is_plugin_added::<AeronetIoPlugin>() {
+            app.add_plugins(AeronetIoPlugin);
+        }
+
+        app.init_resource::<TcpRuntime>()
+            .add_systems(PreUpdate, poll.in_set(IoSet::Poll))
+            .add_systems(PostUpdate, flush.in_set(IoSet::Flush))
+            .add_observer(on_disconnect);
+    }
+}
+
+/// Manages a TCP session's connection.
+///
+/// This may represent either an outgoing client connection (this session is
+/// connecting to a server), an incoming client connection (this session is a
+/// child of a server that the user has spawned), or a session over an arbitrary
+/// byte stream (see [`StreamIo`]).
+///
+/// You should not add or remove this component directly - it is managed
+/// entirely by the client and server implementations.
+///
+/// [`StreamIo`]: crate::stream::StreamIo
+#[derive(Debug, Component)]
+#[require(Session(new_session))]
+pub struct TcpIo {
+    pub(crate) recv_packet_b2f: mpsc::UnboundedReceiver<RecvPacket>,
+    pub(crate) send_packet_f2b: mpsc::UnboundedSender<Bytes>,
+    pub(crate) send_user_dc: Option<oneshot::Sender<String>>,
+}
+
+fn new_session() -> Session {
+    Session::new(Instant::now(), MTU)
+}
+
+/// Error that occurs when polling a session using the [`TcpIo`] IO layer.
+#[derive(Debug, Display, Error)]
+#[non_exhaustive]
+pub enum SessionError {
+    /// Frontend ([`TcpIo`]) was dropped.
+    #[display("frontend closed")]
+    FrontendClosed,
+    /// Backend async task was unexpectedly cancelled and dropped.
+    #[display("backend closed")]
+    BackendClosed,
+    /// Failed to read the local socket address of the endpoint.
+    #[display("failed to get local socket address")]
+    GetLocalAddr(io::Error),
+    /// Failed to read the peer socket address of the endpoint.
+    #[display("failed to get peer socket address")]
+    GetPeerAddr(io::Error),
+    /// Peer closed the stream without sending a disconnect reason.
+    #[display("receiver stream closed")]
+    RecvStreamClosed,
+    /// Failed to read data from the stream.
+    #[display("failed to receive data")]
+    Recv(io::Error),
+    /// Failed to write data to the stream.
+    #[display("failed to send data")]
+    Send(io::Error),
+    /// Peer sent a frame which was longer than [`MAX_FRAME_LEN`].
+    #[display("received frame of length {len}, but maximum is {MAX_FRAME_LEN}")]
+    FrameTooLong {
+        /// Length of the frame, as declared by the peer.
+        len: usize,
+    },
+    /// Peer sent a frame with an unknown kind.
+    #[display("received frame of unknown kind {_0}")]
+    InvalidFrameKind(#[error(not(source))] u8),
+}
+
+impl Drop for TcpIo {
+    fn drop(&mut self) {
+        if let Some(send_dc) = self.send_user_dc.take() {
+            _ = send_dc.send(DROP_DISCONNECT_REASON.to_owned());
+        }
+    }
+}
+
+/// Length of the header prepended to each frame sent over the stream.
+///
+/// This is made up of a single byte for the frame kind, followed by a
+/// big-endian [`u32`] for the length of the frame payload.
+pub const FRAME_HEADER_LEN: usize = 1 + 4;
+
+/// Maximum length of a single frame's payload that a peer may send.
+///
+/// If the peer declares a frame longer than this, the session is disconnected,
+/// to avoid a malicious peer forcing us to allocate a large buffer.
+pub const MAX_FRAME_LEN: usize = 64 * 1024;
+
+/// Packet MTU of [`TcpIo`] sessions.
+///
+/// This is made up of the [`IP_MTU`] minus:
+/// - maximum TCP header size
+///   - <https://en.wikipedia.org/wiki/Transmission_Control_Protocol#TCP_segment_structure>
+/// - IPv6 header size without extensions
+///   - <https://en.wikipedia.org/wiki/IPv6_packet#Fixed_header>
+/// - [`FRAME_HEADER_LEN`]
+pub const MTU: usize = IP_MTU - 60 - 40 - FRAME_HEADER_LEN;
+
+#[derive(Debug)]
+pub(crate) struct SessionFrontend {
+    pub recv_packet_b2f: mpsc::UnboundedReceiver<RecvPacket>,
+    pub send_packet_f2b: mpsc::UnboundedSender<Bytes>,
+    pub send_user_dc: oneshot::Sender<String>,
+}
+
+impl From<SessionFrontend> for TcpIo {
+    fn from(value: SessionFrontend) -> Self {
+        Self {
+            recv_packet_b2f: value.recv_packet_b2f,
+            send_packet_f2b: value.send_packet_f2b,
+            send_user_dc: Some(value.send_user_dc),
+        }
+    }
+}
+
+fn on_disconnect(trigger: Trigger<Disconnect>, mut sessions: Query<&mut TcpIo>) {
+    let session = trigger.entity();
+    let Disconnect { reason, .. } = trigger.event();
+    let Ok(mut io) = sessions.get_mut(session) else {
+        return;
+    };
+
+    if let Some(send_dc) = io.send_user_dc.take() {
+        _ = send_dc.send(reason.clone());
+    }
+}
+
+pub(crate) fn poll(
+    mut sessions: Query<(Entity, &mut Session, &mut TcpIo), Without<DisconnectedRetained>>,
+) {
+    for (entity, mut session, mut io) in &mut sessions {
+        let span = trace_span!("poll", %entity);
+        let _span = span.enter();
+
+        let mut num_packets = Saturating(0);
+        let mut num_bytes = Saturating(0);
+        while let Ok(Some(packet)) = io.recv_packet_b2f.try_next() {
+            num_packets += 1;
+            session.stats.packets_recv += 1;
+
+            num_bytes += packet.payload.len();
+            session.stats.bytes_recv += packet.payload.len();
+
+            session.recv.push(packet);
+        }
+
+        trace!(
+            num_packets = num_packets.0,
+            num_bytes = num_bytes.0,
+            "Received packets",
+        );
+    }
+}
+
+fn flush(mut sessions: Query<(Entity, &mut Session, &TcpIo), Without<DisconnectedRetained>>) {
+    for (entity, mut session, io) in &mut sessions {
+        let span = trace_span!("flush", %entity);
+        let _span = span.enter();
+
+        // explicit deref so we can access disjoint fields
+        let session = &mut *session;
+        let mut num_packets = Saturating(0);
+        let mut num_bytes = Saturating(0);
+        for packet in session.send.drain(..) {
+            num_packets += 1;
+            session.stats.packets_sent += 1;
+
+            num_bytes += packet.len();
+            session.stats.bytes_sent += packet.len();
+
+            // handle connection errors in `poll`
+            _ = io.send_packet_f2b.unbounded_send(packet);
+        }
+
+        trace!(
+            num_packets = num_packets.0,
+            num_bytes = num_bytes.0,
+            "Flushed packets",
+        );
+    }
+}
@@ -0,0 +1,152 @@
+//! See [`StreamIo`].
+
+use {
+    crate::{
+        TcpRuntime,
+        session::{self, SessionError, TcpIo, TcpSessionPlugin, backend},
+    },
+    aeronet_io::{
+        IoSet, SessionEndpoint,
+        connection::{DisconnectReason, Disconnected},
+    },
+    bevy_app::prelude::*,
+    bevy_ecs::{prelude::*, system::EntityCommand},
+    futures::channel::oneshot,
+    tokio::io::{AsyncRead, AsyncWrite},
+    tracing::{Instrument, debug, debug_span},
+};
+
+/// Allows using [`StreamIo`].
+#[derive(Debug)]
+pub struct StreamIoPlugin;
+
+impl Plugin for StreamIoPlugin {
+    fn build(&self, app: &mut App) {
+        if !app.is_plugin_added::<TcpSessionPlugin>() {
+            app.add_plugins(TcpSessionPlugin);
+        }
+
+        app.add_systems(
+            PreUpdate,
+            poll_streams.in_set(IoSet::Poll).before(session::poll),
+        );
+    }
+}
+
+/// Session implementation which runs over an arbitrary, already-connected
+/// byte stream, such as a serial link, a named pipe, or an SSH tunnel.
+///
+/// Packets are framed over the stream in exactly the same way as for TCP
+/// sessions, so the peer must also be using [`StreamIo`] (or a [`TcpClient`] or
+/// [`TcpServer`], if the stream is a TCP connection).
+///
+/// Since the stream is already connected, the [`Session`] is available as soon
+/// as this is set up. Sessions use the same [`MTU`] as TCP sessions.
+///
+/// Use [`StreamIo::from_async`] to start a session.
+///
+/// [`TcpClient`]: crate::client::TcpClient
+/// [`TcpServer`]: crate::server::TcpServer
+/// [`Session`]: aeronet_io::Session
+/// [`MTU`]: crate::session::MTU
+#[derive(Debug, Component)]
+#[require(SessionEndpoint)]
+pub struct StreamIo(StreamFrontend);
+
+impl StreamIo {
+    /// Creates an [`EntityCommand`] to set up a session which reads packets
+    /// from `reader`, and writes packets to `writer`.
+    ///
+    /// The stream is driven on the [`TcpRuntime`]. If you have a single value
+    /// which is both [`AsyncRead`] and [`AsyncWrite`], use [`tokio::io::split`]
+    /// to split it into two halves.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use {
+    ///     aeronet_tcp::{stream::StreamIo, tokio},
+    ///     bevy_ecs::prelude::*,
+    /// };
+    ///
+    /// # fn run(mut commands: Commands) {
+    /// let (stream, _peer) = tokio::io::duplex(64 * 1024);
+    /// let (reader, writer) = tokio::io::split(stream);
+    /// commands
+    ///     .spawn_empty()
+    ///     .queue(StreamIo::from_async(reader, writer));
+    /// # }
+    /// ```
+    #[must_use]
+    pub fn from_async<R, W>(reader: R, writer: W) -> impl EntityCommand
+    where
+        R: AsyncRead + Unpin + Send + 'static,
+        W: AsyncWrite + Unpin + Send + 'static,
+    {
+        move |session: Entity, world: &mut World| open(session, world, reader, writer)
+    }
+}
+
+fn open<R, W>(session: Entity, world: &mut World, reader: R, writer: W)
+where
+    R: AsyncRead + Unpin + Send + 'static,
+    W: AsyncWrite + Unpin + Send + 'static,
+{
+    let runtime = world.resource::<TcpRuntime>().clone();
+
+    let (frontend, backend) = backend::split(reader, writer);
+    let (send_dc, recv_dc) = oneshot::channel::<DisconnectReason<SessionError>>();
+    runtime.spawn_on_self(
+        async move {
+            debug!("Starting session loop");
+            let Err(reason) = backend.start().await;
+            _ = send_dc.send(reason);
+        }
+        .instrument(debug_span!("stream", %session)),
+    );
+
+    world.entity_mut(session).insert((
+        StreamIo(StreamFrontend::Connected { recv_dc }),
+        TcpIo::from(frontend),
+    ));
+}
+
+#[derive(Debug)]
+enum StreamFrontend {
+    Connected {
+        recv_dc: oneshot::Receiver<DisconnectReason<SessionError>>,
+    },
+    Disconnected,
+}
+
+fn poll_streams(mut commands: Commands, mut frontends: Query<(Entity, &mut StreamIo)>) {
+    for (session, mut frontend) in &mut frontends {
+        replace_with::replace_with_or_abort(&mut frontend.0, |state| match state {
+            StreamFrontend::Connected { mut recv_dc } => {
+                if should_disconnect(&mut commands, session, &mut recv_dc) {
+                    StreamFrontend::Disconnected
+                } else {
+                    StreamFrontend::Connected { recv_dc }
+                }
+            }
+            StreamFrontend::Disconnected => state,
+        });
+    }
+}
+
+fn should_disconnect(
+    commands: &mut Commands,
+    session: Entity,
+    recv_dc: &mut oneshot::Receiver<DisconnectReason<SessionError>>,
+) -> bool {
+    let dc_reason = match recv_dc.try_recv() {
+        Ok(None) => None,
+        Ok(Some(dc_reason)) => Some(dc_reason),
+        Err(_) => Some(SessionError::BackendClosed.into()),
+    };
+    dc_reason.is_some_and(|reason| {
+        let reason = reason.map_err(anyhow::Error::new);
+        commands.trigger_targets(Disconnected { reason, code: None }, session);
+        true
+    })
+}
@@ -0,0 +1,34 @@
+//! Helpers shared between the integration tests of this crate.
+
+use {
+    aeronet_io::Session, bevy_app::prelude::*, bevy_ecs::prelude::*, bytes::Bytes,
+    core::time::Duration, std::thread,
+};
+
+/// Maximum number of updates that [`update_until`] waits for.
+pub const MAX_UPDATES: usize = 1000;
+
+/// Updates the app until `f` returns [`Some`], panicking if this takes more
+/// than [`MAX_UPDATES`] updates.
+pub fn update_until<T>(app: &mut App, mut f: impl FnMut(&mut World) -> Option<T>) -> T {
+    for _ in 0..MAX_UPDATES {
+        app.update();
+        if let Some(value) = f(app.world_mut()) {
+            return value;
+        }
+        thread::sleep(Duration::from_millis(5));
+    }
+    panic!("condition not met after {MAX_UPDATES} updates");
+}
+
+/// Updates the app until `session` has received at least `count` packets,
+/// returning their payloads.
+pub fn recv_all(app: &mut App, session: Entity, count: usize) -> Vec<Bytes> {
+    let mut packets = Vec::new();
+    update_until(app, |world| {
+        let mut session = world.get_mut::<Session>(session).unwrap();
+        packets.extend(session.recv.drain(..).map(|packet| packet.payload));
+        (packets.len() >= count).then_some(())
+    });
+    packets
+}
@@ -0,0 +1,101 @@
+#![expect(missing_docs, clippy::unwrap_used, reason = "testing")]
+
+mod common;
+
+use {
+    aeronet_io::{
+        Session, conformance,
+        connection::{Disconnect, DisconnectReason, Disconnected, LocalAddr},
+    },
+    aeronet_tcp::{
+        client::{TcpClient, TcpClientPlugin},
+        server::{TcpServer, TcpServerPlugin},
+    },
+    bevy_app::prelude::*,
+    bevy_ecs::prelude::*,
+    bevy_hierarchy::Parent,
+    bytes::Bytes,
+    common::{recv_all, update_until},
+    core::net::SocketAddr,
+};
+
+fn setup() -> (App, Entity, Entity) {
+    let mut app = App::new();
+    app.add_plugins((TcpClientPlugin, TcpServerPlugin));
+
+    let server = app
+        .world_mut()
+        .commands()
+        .spawn_empty()
+        .queue(TcpServer::open(SocketAddr::from(([127, 0, 0, 1], 0))))
+        .id();
+    app.world_mut().flush();
+    let server_addr = update_until(&mut app, |world| {
+        world.get::<LocalAddr>(server).map(|a| a.0)
+    });
+
+    let client = app
+        .world_mut()
+        .commands()
+        .spawn_empty()
+        .queue(TcpClient::connect(server_addr))
+        .id();
+    app.world_mut().flush();
+    update_until(&mut app, |world| world.get::<Session>(client).map(drop));
+
+    let server_session = update_until(&mut app, |world| {
+        world
+            .query_filtered::<(Entity, &Parent), With<Session>>()
+            .iter(world)
+            .find(|(_, parent)| parent.get() == server)
+            .map(|(entity, _)| entity)
+    });
+    (app, client, server_session)
+}
+
+#[test]
+fn conformance() {
+    conformance::run(setup);
+}
+
+#[test]
+fn round_trip() {
+    const MSG1: &[u8] = b"message 1";
+    const MSG2: &[u8] = b"message 2";
+
+    let (mut app, client, server_session) = setup();
+
+    let mut session = app.world_mut().get_mut::<Session>(client).unwrap();
+    session.send.push(Bytes::from_static(MSG1));
+    session.send.push(Bytes::from_static(MSG2));
+    assert_eq!(vec![MSG1, MSG2], recv_all(&mut app, server_session, 2));
+
+    let mut session = app.world_mut().get_mut::<Session>(server_session).unwrap();
+    session.send.push(Bytes::from_static(MSG2));
+    assert_eq!(vec![MSG2], recv_all(&mut app, client, 1));
+}
+
+#[test]
+fn disconnect_reason() {
+    const REASON: &str = "disconnect reason";
+
+    #[derive(Default, Resource)]
+    struct PeerReason(Option<String>);
+
+    let (mut app, client, server_session) = setup();
+    app.init_resource::<PeerReason>().add_observer(
+        move |trigger: Trigger<Disconnected>, mut peer_reason: ResMut<PeerReason>| {
+            if trigger.entity() != server_session {
+                return;
+            }
+            if let DisconnectReason::Peer(reason) = &trigger.event().reason {
+                peer_reason.0 = Some(reason.clone());
+            }
+        },
+    );
+
+    app.world_mut()
+        .trigger_targets(Disconnect::new(REASON), client);
+    let reason = update_until(&mut app, |world| world.resource::<PeerReason>().0.clone());
+    assert_eq!(REASON, reason);
+}
@@ -0,0 +1,149 @@
+#![expect(missing_docs, clippy::unwrap_used, reason = "testing")]
+
+mod common;
+
+use {
+    aeronet_io::{
+        Session,
+        connection::{Disconnect, DisconnectReason, Disconnected},
+    },
+    aeronet_tcp::{
+        stream::{StreamIo, StreamIoPlugin},
+        tokio::{self, io::BufWriter},
+    },
+    bevy_app::prelude::*,
+    bevy_ecs::prelude::*,
+    bytes::Bytes,
+    common::{recv_all, update_until},
+};
+
+/// Connects two sessions over an in-memory duplex stream.
+fn setup() -> (App, Entity, Entity) {
+    let mut app = App::new();
+    app.add_plugins(StreamIoPlugin);
+
+    let (stream_a, stream_b) = tokio::io::duplex(64 * 1024);
+    let (reader_a, writer_a) = tokio::io::split(stream_a);
+    let (reader_b, writer_b) = tokio::io::split(stream_b);
+    let mut commands = app.world_mut().commands();
+    let a = commands
+        .spawn_empty()
+        .queue(StreamIo::from_async(reader_a, writer_a))
+        .id();
+    let b = commands
+        .spawn_empty()
+        .queue(StreamIo::from_async(reader_b, writer_b))
+        .id();
+    app.world_mut().flush();
+
+    assert!(app.world().get::<Session>(a).is_some());
+    assert!(app.world().get::<Session>(b).is_some());
+    (app, a, b)
+}
+
+#[derive(Default, Resource)]
+struct PeerReason(Option<(Entity, String)>);
+
+fn record_peer_reason(trigger: Trigger<Disconnected>, mut peer_reason: ResMut<PeerReason>) {
+    if let DisconnectReason::Peer(reason) = &trigger.event().reason {
+        peer_reason.0 = Some((trigger.entity(), reason.clone()));
+    }
+}
+
+#[test]
+fn round_trip() {
+    const MSG1: &[u8] = b"message 1";
+    const MSG2: &[u8] = b"message 2";
+
+    let (mut app, a, b) = setup();
+
+    let mut session = app.world_mut().get_mut::<Session>(a).unwrap();
+    session.send.push(Bytes::from_static(MSG1));
+    session.send.push(Bytes::from_static(MSG2));
+    assert_eq!(vec![MSG1, MSG2], recv_all(&mut app, b, 2));
+
+    let mut session = app.world_mut().get_mut::<Session>(b).unwrap();
+    session.send.push(Bytes::from_static(MSG2));
+    assert_eq!(vec![MSG2], recv_all(&mut app, a, 1));
+}
+
+#[test]
+fn buffered_writer() {
+    const MSG: &[u8] = b"message";
+
+    let mut app = App::new();
+    app.add_plugins(StreamIoPlugin);
+
+    // the buffers are large enough that nothing is written out unless the
+    // session flushes its writer
+    let (stream_a, stream_b) = tokio::io::duplex(64 * 1024);
+    let (reader_a, writer_a) = tokio::io::split(stream_a);
+    let (reader_b, writer_b) = tokio::io::split(stream_b);
+    let mut commands = app.world_mut().commands();
+    let a = commands
+        .spawn_empty()
+        .queue(StreamIo::from_async(
+            reader_a,
+            BufWriter::with_capacity(64 * 1024, writer_a),
+        ))
+        .id();
+    let b = commands
+        .spawn_empty()
+        .queue(StreamIo::from_async(reader_b, writer_b))
+        .id();
+    app.world_mut().flush();
+
+    let mut session = app.world_mut().get_mut::<Session>(a).unwrap();
+    session.send.push(Bytes::from_static(MSG));
+    assert_eq!(vec![MSG], recv_all(&mut app, b, 1));
+}
+
+#[test]
+fn disconnect_reason() {
+    const REASON: &str = "disconnect reason";
+
+    let (mut app, a, b) = setup();
+    app.init_resource::<PeerReason>()
+        .add_observer(record_peer_reason);
+
+    app.world_mut().trigger_targets(Disconnect::new(REASON), a);
+    let peer_reason = update_until(&mut app, |world| world.resource::<PeerReason>().0.clone());
+    assert_eq!((b, REASON.to_owned()), peer_reason);
+}
+
+#[test]
+fn stream_closed() {
+    #[derive(Default, Resource)]
+    struct Errored(Vec<Entity>);
+
+    let mut app = App::new();
+    app.add_plugins(StreamIoPlugin)
+        .init_resource::<Errored>()
+        .add_observer(
+            |trigger: Trigger<Disconnected>, mut errored: ResMut<Errored>| {
+                if matches!(trigger.event().reason, DisconnectReason::Error(_)) {
+                    errored.0.push(trigger.entity());
+                }
+            },
+        );
+
+    // the other end of the stream goes away without sending a disconnect
+    let (stream, peer) = tokio::io::duplex(64 * 1024);
+    let (reader, writer) = tokio::io::split(stream);
+    let session = app
+        .world_mut()
+        .commands()
+        .spawn_empty()
+        .queue(StreamIo::from_async(reader, writer))
+        .id();
+    app.world_mut().flush();
+    drop(peer);
+
+    update_until(&mut app, |world| {
+        world
+            .resource::<Errored>()
+            .0
+            .contains(&session)
+            .then_some(())
+    });
+}
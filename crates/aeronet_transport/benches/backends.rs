@@ -0,0 +1,272 @@
+//! Runs the same fixed workload through the full [`Session`] and [`Transport`]
+//! stack of each IO backend, and reports throughput, delivery latency, and CPU
+//! time, so that backends can be compared against each other.
+//!
+//! Run with:
+//!
+//! ```sh
+//! cargo bench -p aeronet_transport --bench backends --features cpu-time
+//! ```
+//!
+//! Each workload pushes a fixed number of messages per app update from one
+//! session to its peer on a [`LaneKind::ReliableOrdered`] lane, until all of
+//! the messages have been received. Every backend uses the same
+//! [`TransportConfig`], so any difference in the results comes from the IO
+//! layer.
+//!
+//! To add a backend, implement [`Backend`] for it and add it to [`main`].
+
+use {
+    aeronet_channel::{ChannelIo, ChannelIoPlugin},
+    aeronet_io::Session,
+    aeronet_transport::{
+        AeronetTransportPlugin, Transport, TransportConfig,
+        lane::{LaneIndex, LaneKind},
+    },
+    bevy_app::prelude::*,
+    bevy_ecs::prelude::*,
+    bevy_time::TimePlugin,
+    core::time::Duration,
+    octs::Bytes,
+    web_time::Instant,
+};
+
+const LANES: [LaneKind; 1] = [LaneKind::ReliableOrdered];
+const LANE: LaneIndex = LaneIndex(0);
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+/// IO backend which can be benchmarked.
+trait Backend {
+    /// Name of this backend in the report.
+    const NAME: &'static str;
+
+    /// Adds the plugins required by this backend to `app`.
+    fn build(app: &mut App);
+
+    /// Connects two sessions to each other, returning `(sender, receiver)`.
+    ///
+    /// This may update `app` as many times as it needs to, but must only
+    /// return once both entities have a [`Session`].
+    fn connect(app: &mut App) -> (Entity, Entity);
+}
+
+/// [`aeronet_channel`] backend.
+struct Channel;
+
+impl Backend for Channel {
+    const NAME: &'static str = "channel";
+
+    fn build(app: &mut App) {
+        app.add_plugins(ChannelIoPlugin);
+    }
+
+    fn connect(app: &mut App) -> (Entity, Entity) {
+        let world = app.world_mut();
+        let sender = world.spawn_empty().id();
+        let receiver = world.spawn_empty().id();
+        world.commands().queue(ChannelIo::open(sender, receiver));
+        app.update();
+        (sender, receiver)
+    }
+}
+
+/// Fixed amount of work which is run through each backend.
+struct Workload {
+    name: &'static str,
+    msg_len: usize,
+    msgs_per_update: usize,
+    total_msgs: usize,
+}
+
+const WORKLOADS: [Workload; 3] = [
+    Workload {
+        name: "small",
+        msg_len: 32,
+        msgs_per_update: 256,
+        total_msgs: 100_000,
+    },
+    Workload {
+        name: "medium",
+        msg_len: 1024,
+        msgs_per_update: 64,
+        total_msgs: 20_000,
+    },
+    Workload {
+        name: "fragmented",
+        msg_len: 16 * 1024,
+        msgs_per_update: 8,
+        total_msgs: 2_000,
+    },
+];
+
+fn transport_config() -> TransportConfig {
+    TransportConfig {
+        max_memory_usage: 64 * 1024 * 1024,
+        ..Default::default()
+    }
+}
+
+fn add_transport(
+    trigger: Trigger<OnAdd, Session>,
+    sessions: Query<&Session>,
+    mut commands: Commands,
+) {
+    let entity = trigger.entity();
+    let session = sessions.get(entity).expect("session should exist");
+    let transport = Transport::new(session, LANES, LANES, Instant::now())
+        .expect("should be able to create transport");
+    commands
+        .entity(entity)
+        .insert((transport, transport_config()));
+}
+
+struct Report {
+    elapsed: Duration,
+    updates: usize,
+    update_time: Duration,
+    latencies: Vec<Duration>,
+    #[cfg(feature = "cpu-time")]
+    cpu_time: Duration,
+}
+
+fn run<B: Backend>(workload: &Workload) -> Report {
+    let mut app = App::new();
+    app.add_plugins((TimePlugin, AeronetTransportPlugin))
+        .add_observer(add_transport);
+    B::build(&mut app);
+    let (sender, receiver) = B::connect(&mut app);
+
+    let mut pushed_at = Vec::with_capacity(workload.total_msgs);
+    let mut latencies = Vec::with_capacity(workload.total_msgs);
+    let mut updates = 0;
+    let mut update_time = Duration::ZERO;
+    let start = Instant::now();
+    while latencies.len() < workload.total_msgs {
+        assert!(
+            start.elapsed() < TIMEOUT,
+            "{} did not receive all messages within {TIMEOUT:?}",
+            B::NAME
+        );
+
+        let mut transport = app
+            .world_mut()
+            .get_mut::<Transport>(sender)
+            .expect("sender should have a transport");
+        let to_push = workload
+            .msgs_per_update
+            .min(workload.total_msgs - pushed_at.len());
+        for _ in 0..to_push {
+            let now = Instant::now();
+            let msg = message(pushed_at.len(), workload.msg_len);
+            transport
+                .send
+                .push(LANE, msg, now)
+                .expect("should be able to push message");
+            pushed_at.push(now);
+        }
+
+        let update_start = Instant::now();
+        app.update();
+        update_time += update_start.elapsed();
+        updates += 1;
+
+        let world = app.world_mut();
+        let mut transport = world
+            .get_mut::<Transport>(receiver)
+            .expect("receiver should have a transport");
+        for msg in transport.recv.msgs.drain() {
+            let index = message_index(&msg.payload);
+            latencies.push(msg.recv_at.saturating_duration_since(pushed_at[index]));
+        }
+        for entity in [sender, receiver] {
+            let mut transport = world
+                .get_mut::<Transport>(entity)
+                .expect("session should have a transport");
+            transport.recv.acks.drain().for_each(drop);
+            transport.recv.msgs.drain().for_each(drop);
+        }
+    }
+    let elapsed = start.elapsed();
+
+    #[cfg(feature = "cpu-time")]
+    let cpu_time = [sender, receiver]
+        .into_iter()
+        .map(|entity| {
+            let cpu_time = app
+                .world()
+                .get::<Transport>(entity)
+                .expect("session should have a transport")
+                .cpu_time();
+            cpu_time.fragment + cpu_time.reassemble
+        })
+        .sum();
+
+    latencies.sort_unstable();
+    Report {
+        elapsed,
+        updates,
+        update_time,
+        latencies,
+        #[cfg(feature = "cpu-time")]
+        cpu_time,
+    }
+}
+
+/// Creates a message of `len` bytes which starts with its `index`.
+fn message(index: usize, len: usize) -> Bytes {
+    let mut msg = vec![0; len.max(size_of::<u64>())];
+    msg[..size_of::<u64>()].copy_from_slice(&(index as u64).to_le_bytes());
+    Bytes::from(msg)
+}
+
+fn message_index(msg: &[u8]) -> usize {
+    let mut index = [0; size_of::<u64>()];
+    index.copy_from_slice(&msg[..size_of::<u64>()]);
+    usize::try_from(u64::from_le_bytes(index)).expect("index should fit in a usize")
+}
+
+fn percentile(sorted: &[Duration], percent: usize) -> Duration {
+    sorted[(sorted.len() - 1) * percent / 100]
+}
+
+#[expect(clippy::cast_precision_loss, reason = "only used for display")]
+fn print_report<B: Backend>(workload: &Workload, report: &Report) {
+    let secs = report.elapsed.as_secs_f64();
+    let msgs_per_sec = workload.total_msgs as f64 / secs;
+    let mib_per_sec = (workload.total_msgs * workload.msg_len) as f64 / secs / (1024.0 * 1024.0);
+    let mean_update = report.update_time / u32::try_from(report.updates).unwrap_or(u32::MAX);
+
+    print!(
+        "{:<10} {:<12} {:>12.0} {:>10.2} {:>10.2?} {:>10.2?} {:>10.2?} {:>12.2?}",
+        B::NAME,
+        workload.name,
+        msgs_per_sec,
+        mib_per_sec,
+        percentile(&report.latencies, 50),
+        percentile(&report.latencies, 90),
+        percentile(&report.latencies, 99),
+        mean_update,
+    );
+    #[cfg(feature = "cpu-time")]
+    print!(" {:>12.2?}", report.cpu_time);
+    println!();
+}
+
+fn bench<B: Backend>() {
+    for workload in &WORKLOADS {
+        let report = run::<B>(workload);
+        print_report::<B>(workload, &report);
+    }
+}
+
+fn main() {
+    print!(
+        "{:<10} {:<12} {:>12} {:>10} {:>10} {:>10} {:>10} {:>12}",
+        "backend", "workload", "msgs/s", "MiB/s", "p50", "p90", "p99", "update"
+    );
+    #[cfg(feature = "cpu-time")]
+    print!(" {:>12}", "transport");
+    println!();
+
+    bench::<Channel>();
+}
@@ -13,9 +13,10 @@ fuzz_target!(|input: (LaneKind, &[u8])| {
     let (lane_kind, msg) = input;
 
     const MTU: usize = 128;
-    const LANES: [LaneKind; 4] = [
+    const LANES: [LaneKind; 5] = [
         LaneKind::UnreliableUnordered,
         LaneKind::UnreliableSequenced,
+        LaneKind::UnreliableLatest,
         LaneKind::ReliableUnordered,
         LaneKind::ReliableOrdered,
     ];
@@ -26,10 +27,13 @@ fuzz_target!(|input: (LaneKind, &[u8])| {
 
     let lane_index = LaneIndex::try_from(lane_kind as usize).unwrap();
     let msg = Bytes::from(msg.to_vec());
-    _ = transport.send.push(lane_index, msg, now).unwrap();
+    if transport.send.push(lane_index, msg, now).is_err() {
+        // rejected messages, e.g. ones too large to fragment, are never sent
+        return;
+    }
 
     let packets = aeronet_transport::send::fuzz_flush_on(&mut transport, MTU).collect::<Vec<_>>();
     for packet in packets {
-        aeronet_transport::recv::fuzz_recv_on(&mut transport, &packet).unwrap();
+        transport.recv_fuzz_input(&packet, now).unwrap();
     }
 });
@@ -9,11 +9,12 @@ use {
     libfuzzer_sys::fuzz_target,
 };
 
-fuzz_target!(|packet: &[u8]| {
+fuzz_target!(|packets: Vec<&[u8]>| {
     const MTU: usize = 128;
-    const RECV_LANES: [LaneKind; 4] = [
+    const RECV_LANES: [LaneKind; 5] = [
         LaneKind::UnreliableUnordered,
         LaneKind::UnreliableSequenced,
+        LaneKind::UnreliableLatest,
         LaneKind::ReliableUnordered,
         LaneKind::ReliableOrdered,
     ];
@@ -21,5 +22,9 @@ fuzz_target!(|packet: &[u8]| {
     let now = Instant::now();
     let session = Session::new(now, MTU);
     let mut transport = Transport::new(&session, RECV_LANES, [], now).unwrap();
-    _ = aeronet_transport::recv::fuzz_recv_on(&mut transport, packet);
+    // feed in multiple packets, so that fragments of one message can be
+    // reassembled across packets
+    for packet in packets {
+        _ = transport.recv_fuzz_input(packet, now);
+    }
 });
@@ -0,0 +1,393 @@
+//! See [`AuthPlugin`].
+
+use {
+    crate::TransportSet,
+    aeronet_io::{IoSet, Session, connection::Disconnect},
+    bevy_app::prelude::*,
+    bevy_ecs::prelude::*,
+    core::{fmt, time::Duration},
+    derive_more::{Display, Error},
+    octs::Bytes,
+    tracing::{debug, trace_span},
+    web_time::Instant,
+};
+
+/// Adds an optional authentication handshake which [`Session`]s perform
+/// before a [`Transport`] is created for them.
+///
+/// This is not a replacement for an encrypted and authenticated connection,
+/// which your IO layer may provide. Instead, it lets a server check that a
+/// client is allowed to join (e.g. by checking a token issued by a matchmaking
+/// service), before committing any more resources to that client.
+///
+/// # Handshake
+///
+/// - The challenging side (e.g. a client of a server) has an
+///   [`AuthChallenger`], which generates a challenge and sends it to the peer
+/// - The responding side has an [`AuthResponder`], which replies to that
+///   challenge with a response
+/// - The challenger validates the response:
+///   - if it is valid, both sides are marked as [`Authenticated`]
+///   - if it is invalid, or the peer does not respond in time, [`AuthFailed`]
+///     is triggered on the session, and the session is disconnected with the
+///     [`AuthError`] as the reason
+///
+/// Handshake packets are resent until they are acknowledged, so the handshake
+/// works over unreliable IO layers. The challenge and response must each fit
+/// into a single packet alongside a small header.
+///
+/// While a session is not [`Authenticated`] yet, all other packets it receives
+/// are dropped. You should only create the [`Transport`] for a session once it
+/// is [`Authenticated`] - use a [`Trigger<OnAdd, Authenticated>`] instead of a
+/// [`Trigger<OnAdd, Session>`] for this.
+///
+/// # Examples
+///
+/// ```
+/// use {
+///     aeronet_transport::auth::{AuthChallenger, AuthResponder},
+///     bevy_ecs::prelude::*,
+///     octs::Bytes,
+/// };
+///
+/// # fn run(mut commands: Commands, client_on_server: Entity, client: Entity) {
+/// // on the server, challenge the client
+/// commands
+///     .entity(client_on_server)
+///     .insert(AuthChallenger::new(
+///         || Bytes::from_static(b"nonce"),
+///         |_challenge, response| {
+///             if response == b"secret token" {
+///                 Ok(())
+///             } else {
+///                 Err("invalid token".into())
+///             }
+///         },
+///     ));
+///
+/// // on the client, respond to the server's challenge
+/// commands
+///     .entity(client)
+///     .insert(AuthResponder::new(|_challenge| {
+///         Bytes::from_static(b"secret token")
+///     }));
+/// # }
+/// ```
+///
+/// [`Transport`]: crate::Transport
+#[derive(Debug, Clone, Default)]
+pub struct AuthPlugin;
+
+impl Plugin for AuthPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            PreUpdate,
+            (poll_challengers, poll_responders)
+                .after(IoSet::Poll)
+                .before(TransportSet::Poll),
+        );
+    }
+}
+
+/// Challenges the peer of this [`Session`] to authenticate itself.
+///
+/// See [`AuthPlugin`].
+#[derive(Component)]
+pub struct AuthChallenger {
+    make_challenge: Box<dyn Fn() -> Bytes + Send + Sync>,
+    validate: Box<dyn Fn(&[u8], &[u8]) -> Result<(), String> + Send + Sync>,
+    /// How long the peer has to respond with a valid response, starting from
+    /// when the handshake starts.
+    ///
+    /// By default, this is 10 seconds.
+    pub timeout: Duration,
+    /// How often to resend the challenge while the peer has not responded.
+    ///
+    /// By default, this is 250 milliseconds.
+    pub resend_interval: Duration,
+    challenge: Option<Bytes>,
+    started_at: Option<Instant>,
+    last_sent_at: Option<Instant>,
+}
+
+/// Responds to authentication challenges sent by the peer of this
+/// [`Session`].
+///
+/// See [`AuthPlugin`].
+#[derive(Component)]
+pub struct AuthResponder {
+    respond: Box<dyn Fn(&[u8]) -> Bytes + Send + Sync>,
+    /// How often to resend the response while the peer has not accepted it.
+    ///
+    /// By default, this is 250 milliseconds.
+    pub resend_interval: Duration,
+    challenge: Option<Bytes>,
+    response: Option<Bytes>,
+    last_sent_at: Option<Instant>,
+}
+
+/// Marks a [`Session`] which has completed the authentication handshake.
+///
+/// See [`AuthPlugin`].
+#[derive(Debug, Clone, Copy, Default, Component)]
+pub struct Authenticated;
+
+/// Triggered on a [`Session`] with an [`AuthChallenger`] when its peer fails
+/// to authenticate.
+///
+/// Immediately after this, the session is disconnected with the
+/// [`AuthFailed::error`] as the reason.
+#[derive(Debug, Clone, PartialEq, Eq, Event)]
+pub struct AuthFailed {
+    /// Why the peer failed to authenticate.
+    pub error: AuthError,
+}
+
+/// Why a peer failed to authenticate.
+#[derive(Debug, Clone, PartialEq, Eq, Display, Error)]
+pub enum AuthError {
+    /// Peer responded to the challenge, but the response was rejected.
+    #[display("authentication rejected: {reason}")]
+    Rejected {
+        /// Reason given by the validation function.
+        reason: String,
+    },
+    /// Peer did not respond to the challenge in time.
+    #[display("authentication timed out")]
+    TimedOut,
+}
+
+impl AuthChallenger {
+    /// Creates a new [`AuthChallenger`].
+    ///
+    /// - `make_challenge` creates the challenge to send to the peer
+    /// - `validate` checks the peer's response against the challenge, returning
+    ///   the reason for rejecting it if it is invalid
+    #[must_use]
+    pub fn new(
+        make_challenge: impl Fn() -> Bytes + Send + Sync + 'static,
+        validate: impl Fn(&[u8], &[u8]) -> Result<(), String> + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            make_challenge: Box::new(make_challenge),
+            validate: Box::new(validate),
+            timeout: Duration::from_secs(10),
+            resend_interval: Duration::from_millis(250),
+            challenge: None,
+            started_at: None,
+            last_sent_at: None,
+        }
+    }
+}
+
+impl AuthResponder {
+    /// Creates a new [`AuthResponder`].
+    ///
+    /// `respond` creates the response to a challenge sent by the peer.
+    #[must_use]
+    pub fn new(respond: impl Fn(&[u8]) -> Bytes + Send + Sync + 'static) -> Self {
+        Self {
+            respond: Box::new(respond),
+            resend_interval: Duration::from_millis(250),
+            challenge: None,
+            response: None,
+            last_sent_at: None,
+        }
+    }
+}
+
+impl fmt::Debug for AuthChallenger {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AuthChallenger")
+            .field("timeout", &self.timeout)
+            .field("resend_interval", &self.resend_interval)
+            .field("challenge", &self.challenge)
+            .field("started_at", &self.started_at)
+            .field("last_sent_at", &self.last_sent_at)
+            .finish_non_exhaustive()
+    }
+}
+
+impl fmt::Debug for AuthResponder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AuthResponder")
+            .field("resend_interval", &self.resend_interval)
+            .field("challenge", &self.challenge)
+            .field("response", &self.response)
+            .field("last_sent_at", &self.last_sent_at)
+            .finish_non_exhaustive()
+    }
+}
+
+// we share the packet stream with the transport, so handshake packets start
+// with a prefix that a transport packet is practically never going to start
+// with
+const MAGIC: [u8; 12] = *b"aeronet:auth";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PacketKind {
+    Challenge,
+    Response,
+    Accepted,
+}
+
+impl PacketKind {
+    const fn to_byte(self) -> u8 {
+        match self {
+            Self::Challenge => 0,
+            Self::Response => 1,
+            Self::Accepted => 2,
+        }
+    }
+
+    const fn from_byte(byte: u8) -> Option<Self> {
+        match byte {
+            0 => Some(Self::Challenge),
+            1 => Some(Self::Response),
+            2 => Some(Self::Accepted),
+            _ => None,
+        }
+    }
+}
+
+fn encode(kind: PacketKind, payload: &[u8]) -> Bytes {
+    let mut packet = Vec::with_capacity(MAGIC.len() + 1 + payload.len());
+    packet.extend_from_slice(&MAGIC);
+    packet.push(kind.to_byte());
+    packet.extend_from_slice(payload);
+    Bytes::from(packet)
+}
+
+fn decode(packet: &[u8]) -> Option<(PacketKind, &[u8])> {
+    let rest = packet.strip_prefix(&MAGIC)?;
+    let (&kind, payload) = rest.split_first()?;
+    Some((PacketKind::from_byte(kind)?, payload))
+}
+
+fn should_send(last_sent_at: Option<Instant>, resend_interval: Duration, now: Instant) -> bool {
+    last_sent_at.is_none_or(|last| now.saturating_duration_since(last) >= resend_interval)
+}
+
+fn poll_challengers(
+    mut sessions: Query<(
+        Entity,
+        &mut Session,
+        &mut AuthChallenger,
+        Has<Authenticated>,
+    )>,
+    mut commands: Commands,
+) {
+    let now = Instant::now();
+    for (entity, mut session, mut auth, authenticated) in &mut sessions {
+        let span = trace_span!("auth", %entity);
+        let _span = span.enter();
+
+        let mut responses = Vec::new();
+        session.recv.retain(|packet| match decode(&packet.payload) {
+            Some((PacketKind::Response, payload)) => {
+                responses.push(payload.to_vec());
+                false
+            }
+            Some(_) => false,
+            None => authenticated,
+        });
+
+        if authenticated {
+            // the peer didn't get our acceptance yet
+            if !responses.is_empty() {
+                session.send.push(encode(PacketKind::Accepted, &[]));
+            }
+            continue;
+        }
+
+        let auth = &mut *auth;
+        let started_at = *auth.started_at.get_or_insert(now);
+        let challenge = auth
+            .challenge
+            .get_or_insert_with(|| (auth.make_challenge)())
+            .clone();
+
+        if let Some(response) = responses.first() {
+            match (auth.validate)(&challenge, response) {
+                Ok(()) => {
+                    debug!("{entity} authenticated");
+                    session.send.push(encode(PacketKind::Accepted, &[]));
+                    commands.entity(entity).insert(Authenticated);
+                }
+                Err(reason) => {
+                    fail(&mut commands, entity, AuthError::Rejected { reason });
+                }
+            }
+            continue;
+        }
+
+        if now.saturating_duration_since(started_at) >= auth.timeout {
+            fail(&mut commands, entity, AuthError::TimedOut);
+            continue;
+        }
+
+        if should_send(auth.last_sent_at, auth.resend_interval, now) {
+            session.send.push(encode(PacketKind::Challenge, &challenge));
+            auth.last_sent_at = Some(now);
+        }
+    }
+}
+
+fn fail(commands: &mut Commands, entity: Entity, error: AuthError) {
+    debug!("{entity} failed to authenticate: {error}");
+    let reason = error.to_string();
+    commands.trigger_targets(AuthFailed { error }, entity);
+    commands.trigger_targets(Disconnect::new(reason), entity);
+}
+
+fn poll_responders(
+    mut sessions: Query<(Entity, &mut Session, &mut AuthResponder, Has<Authenticated>)>,
+    mut commands: Commands,
+) {
+    let now = Instant::now();
+    for (entity, mut session, mut auth, authenticated) in &mut sessions {
+        let span = trace_span!("auth", %entity);
+        let _span = span.enter();
+
+        let mut challenge = None;
+        let mut accepted = false;
+        session.recv.retain(|packet| match decode(&packet.payload) {
+            Some((PacketKind::Challenge, payload)) => {
+                challenge = Some(payload.to_vec());
+                false
+            }
+            Some((PacketKind::Accepted, _)) => {
+                accepted = true;
+                false
+            }
+            Some(_) => false,
+            None => authenticated,
+        });
+
+        if authenticated {
+            continue;
+        }
+
+        if accepted {
+            debug!("{entity} authenticated");
+            commands.entity(entity).insert(Authenticated);
+            continue;
+        }
+
+        let auth = &mut *auth;
+        if let Some(challenge) = challenge {
+            if auth.challenge.as_deref() != Some(challenge.as_slice()) {
+                auth.response = Some((auth.respond)(&challenge));
+                auth.challenge = Some(Bytes::from(challenge));
+                auth.last_sent_at = None;
+            }
+        }
+
+        if let Some(response) = &auth.response {
+            if should_send(auth.last_sent_at, auth.resend_interval, now) {
+                session.send.push(encode(PacketKind::Response, response));
+                auth.last_sent_at = Some(now);
+            }
+        }
+    }
+}
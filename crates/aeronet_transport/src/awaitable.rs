@@ -0,0 +1,167 @@
+//! Awaiting the delivery of a single message as a [`Future`].
+//!
+//! See [`Transport::send_awaitable`].
+//!
+//! [`Transport::send_awaitable`]: crate::Transport::send_awaitable
+
+use {
+    crate::{
+        MessageKey, Transport,
+        hash::HashMap,
+        send::{Delivery, DeliveryReport},
+    },
+    alloc::sync::Arc,
+    bevy_ecs::prelude::*,
+    core::{
+        future::Future,
+        pin::Pin,
+        task::{Context, Poll, Waker},
+    },
+    std::sync::{Mutex, PoisonError},
+};
+
+/// What happened to a message sent via [`Transport::send_awaitable`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DeliveryOutcome {
+    /// The peer acknowledged that it received the whole message.
+    ///
+    /// See [`Delivery::Delivered`].
+    Delivered,
+    /// The message was not delivered, and never will be.
+    ///
+    /// See [`Delivery::Lost`].
+    Lost,
+    /// The transport does not know if the message was delivered.
+    ///
+    /// See [`Delivery::Unknown`].
+    Unknown,
+    /// The [`Transport`] was removed, or its session was despawned, before the
+    /// transport knew what happened to the message.
+    Closed,
+}
+
+impl From<Delivery> for DeliveryOutcome {
+    fn from(value: Delivery) -> Self {
+        match value {
+            Delivery::Delivered => Self::Delivered,
+            Delivery::Lost => Self::Lost,
+            Delivery::Unknown => Self::Unknown,
+        }
+    }
+}
+
+/// Future returned by [`Transport::send_awaitable`], which resolves once the
+/// transport knows what happened to the message.
+///
+/// This does not depend on any specific async runtime. The transport wakes the
+/// task polling this future from its own systems, so the app must keep
+/// updating for this future to make progress.
+#[derive(Debug)]
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct DeliveryFuture {
+    key: MessageKey,
+    waiter: Arc<Mutex<Waiter>>,
+}
+
+impl DeliveryFuture {
+    /// Gets the key of the message whose delivery this future is waiting for.
+    #[must_use]
+    pub const fn key(&self) -> MessageKey {
+        self.key
+    }
+}
+
+impl Future for DeliveryFuture {
+    type Output = DeliveryOutcome;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut waiter = self.waiter.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(outcome) = waiter.outcome {
+            return Poll::Ready(outcome);
+        }
+
+        if !waiter
+            .waker
+            .as_ref()
+            .is_some_and(|waker| waker.will_wake(cx.waker()))
+        {
+            waiter.waker = Some(cx.waker().clone());
+        }
+        Poll::Pending
+    }
+}
+
+#[derive(Debug, Default)]
+struct Waiter {
+    outcome: Option<DeliveryOutcome>,
+    waker: Option<Waker>,
+}
+
+impl Waiter {
+    fn resolve(waiter: &Mutex<Self>, outcome: DeliveryOutcome) {
+        let mut waiter = waiter.lock().unwrap_or_else(PoisonError::into_inner);
+        waiter.outcome = Some(outcome);
+        if let Some(waker) = waiter.waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+/// Messages being awaited via [`DeliveryFuture`]s.
+///
+/// When this is dropped - i.e. when the [`Transport`] is dropped - all of the
+/// remaining futures resolve with [`DeliveryOutcome::Closed`].
+#[derive(Debug, Default)]
+pub(crate) struct DeliveryWaiters(HashMap<MessageKey, Arc<Mutex<Waiter>>>);
+
+impl DeliveryWaiters {
+    pub(crate) fn insert(&mut self, key: MessageKey) -> DeliveryFuture {
+        let waiter = Arc::new(Mutex::new(Waiter::default()));
+        self.0.insert(key, waiter.clone());
+        DeliveryFuture { key, waiter }
+    }
+
+    /// Resolves the future waiting for the message in this report, if there is
+    /// one.
+    ///
+    /// Returns `true` if the report was consumed by a future.
+    fn resolve(&mut self, report: DeliveryReport) -> bool {
+        let Some(waiter) = self.0.remove(&report.key) else {
+            return false;
+        };
+        Waiter::resolve(&waiter, report.delivery.into());
+        true
+    }
+}
+
+impl Drop for DeliveryWaiters {
+    fn drop(&mut self) {
+        for (_, waiter) in self.0.drain() {
+            Waiter::resolve(&waiter, DeliveryOutcome::Closed);
+        }
+    }
+}
+
+impl Transport {
+    /// Resolves the futures of awaited messages which have a report in
+    /// [`TransportRecv::deliveries`], removing those reports from the buffer.
+    ///
+    /// [`TransportRecv::deliveries`]: crate::recv::TransportRecv::deliveries
+    pub(crate) fn resolve_awaited(&mut self) {
+        if self.awaiting.0.is_empty() {
+            return;
+        }
+
+        let awaiting = &mut self.awaiting;
+        self.recv
+            .deliveries
+            .0
+            .retain(|report| !awaiting.resolve(*report));
+    }
+}
+
+pub(crate) fn resolve(mut transports: Query<&mut Transport>) {
+    for mut transport in &mut transports {
+        transport.resolve_awaited();
+    }
+}
@@ -0,0 +1,89 @@
+//! Rough estimation of the offset between our and the peer's wall clocks.
+//!
+//! See [`Transport::peer_clock_offset`].
+//!
+//! [`Transport::peer_clock_offset`]: crate::Transport::peer_clock_offset
+
+use {
+    core::time::Duration,
+    typesize::derive::TypeSize,
+    web_time::{Instant, SystemTime, UNIX_EPOCH},
+};
+
+/// How much weight a new offset sample has when added to the smoothed offset,
+/// as a fraction `1 / N`.
+const OFFSET_SMOOTHING: i64 = 8;
+
+#[derive(Debug, Default, TypeSize)]
+pub(crate) struct ClockSync {
+    interval: Option<Duration>,
+    last_sent: Option<Instant>,
+    offset_micros: Option<i64>,
+}
+
+impl ClockSync {
+    pub(crate) fn set_interval(&mut self, interval: Option<Duration>) {
+        self.interval = interval;
+    }
+
+    /// Gets our wall clock time to include in a packet flushed at `now`, if
+    /// we're due to share it with the peer.
+    ///
+    /// If the packet is sent, [`ClockSync::sent`] must be called.
+    pub(crate) fn timestamp_to_send(&self, now: Instant) -> Option<u64> {
+        let interval = self.interval?;
+        if self
+            .last_sent
+            .is_some_and(|last_sent| now.saturating_duration_since(last_sent) < interval)
+        {
+            return None;
+        }
+        Some(wall_clock_micros(now))
+    }
+
+    pub(crate) fn sent(&mut self, now: Instant) {
+        self.last_sent = Some(now);
+    }
+
+    /// Adds a sample of the peer's wall clock, which it sent at
+    /// `peer_micros` and we received at `recv_at`.
+    ///
+    /// `rtt` must be [`None`] if we don't have a real RTT estimate yet.
+    pub(crate) fn recv(&mut self, peer_micros: u64, recv_at: Instant, rtt: Option<Duration>) {
+        let Some(rtt) = rtt else {
+            return;
+        };
+
+        // assume the packet took half of the RTT to reach us
+        let one_way = i64::try_from(rtt.as_micros() / 2).unwrap_or(i64::MAX);
+        let peer_at_recv = i64::try_from(peer_micros)
+            .unwrap_or(i64::MAX)
+            .saturating_add(one_way);
+        let ours_at_recv = i64::try_from(wall_clock_micros(recv_at)).unwrap_or(i64::MAX);
+        let sample = peer_at_recv.saturating_sub(ours_at_recv);
+
+        self.offset_micros = Some(self.offset_micros.map_or(sample, |offset| {
+            offset.saturating_add(sample.saturating_sub(offset) / OFFSET_SMOOTHING)
+        }));
+    }
+
+    pub(crate) const fn offset_micros(&self) -> Option<i64> {
+        self.offset_micros
+    }
+}
+
+/// Gets the wall clock time at the instant `at`, in microseconds since the
+/// UNIX epoch.
+fn wall_clock_micros(at: Instant) -> u64 {
+    let (wall_now, now) = (SystemTime::now(), Instant::now());
+    let wall_at = if at <= now {
+        wall_now.checked_sub(now.saturating_duration_since(at))
+    } else {
+        wall_now.checked_add(at.saturating_duration_since(now))
+    }
+    .unwrap_or(wall_now);
+
+    wall_at.duration_since(UNIX_EPOCH).map_or(0, |since_epoch| {
+        u64::try_from(since_epoch.as_micros()).unwrap_or(u64::MAX)
+    })
+}
@@ -9,13 +9,16 @@
 //! [`FragmentReceiver`]).
 
 use {
-    crate::packet::{FragmentIndex, FragmentPosition, MessageSeq},
-    ahash::HashMap,
+    crate::{
+        hash::HashMap,
+        packet::{FragmentIndex, FragmentPosition, MessageSeq, PacketSeq},
+    },
     bitvec::vec::BitVec,
     core::{fmt, iter::FusedIterator},
     derive_more::{Display, Error},
     octs::{Bytes, chunks::ByteChunksExt},
     typesize::derive::TypeSize,
+    web_time::Instant,
 };
 
 /// Splits a message up into smaller fragments, ready to be reassembled by a
@@ -40,10 +43,8 @@ use {
 ///
 /// Panics if `max_frag_len == 0`.
 ///
-/// Panics if `msg` is too large, and is split into too many fragments for
-/// [`FragmentPosition`] to be able to encode the fragment index.
-/// The range of [`FragmentIndex`] should be large enough so that you never run
-/// into this.
+/// Panics if `msg` is too large, and is split into more than [`MAX_FRAGS`]
+/// fragments.
 ///
 /// [last fragment]: FragmentPosition::is_last
 pub fn split(
@@ -80,11 +81,17 @@ pub fn split(
     })
 }
 
+/// Maximum number of fragments that a single message can be [`split`] into.
+///
+/// This is limited by the range of fragment indices that [`FragmentPosition`]
+/// can encode.
+pub const MAX_FRAGS: usize = 1 << (FragmentIndex::BITS - 1);
+
 /// Receives fragments created by [`split`] and reassembles them into full
 /// messages.
 #[derive(Default, Clone, TypeSize)]
 pub struct FragmentReceiver {
-    msgs: HashMap<MessageSeq, MessageBuf>,
+    pub(crate) msgs: HashMap<MessageSeq, MessageBuf>,
 }
 
 /// Received an invalid fragment when reassembling fragments into a message.
@@ -140,12 +147,19 @@ pub enum ReassembleError {
 }
 
 #[derive(Default, Clone, TypeSize)]
-struct MessageBuf {
-    last_frag_index: Option<usize>,
-    max_frag_index: usize,
-    num_frags_recv: usize,
-    frag_indices_recv: BitVec,
-    payload: Vec<u8>,
+pub(crate) struct MessageBuf {
+    pub(crate) last_frag_index: Option<usize>,
+    pub(crate) max_frag_index: usize,
+    pub(crate) num_frags_recv: usize,
+    pub(crate) frag_indices_recv: BitVec,
+    pub(crate) payload: Vec<u8>,
+    pub(crate) first_recv_at: Option<Instant>,
+    /// Packets which carried the first and the latest fragment of this message
+    /// that we received.
+    pub(crate) recv_packets: Option<(PacketSeq, PacketSeq)>,
+    /// Whether we have already queued NACKs for the missing fragments of this
+    /// message.
+    pub(crate) nacked: bool,
 }
 
 impl fmt::Debug for FragmentReceiver {
@@ -195,7 +209,9 @@ impl FragmentReceiver {
     /// packet will result in using too much memory, this returns a
     /// [`ReassembleError::OutOfMemory`]. Note that this will just return an
     /// error on out-of-memory, unlike [`TransportConfig::max_memory_usage`],
-    /// which will forcibly disconnect the session.
+    /// which will forcibly disconnect the session. A [`Transport`] may then
+    /// evict another message to make room, depending on its
+    /// [`TransportConfig::unreliable_drop_policy`].
     ///
     /// # Errors
     ///
@@ -207,6 +223,8 @@ impl FragmentReceiver {
     /// invalid state.
     ///
     /// [`TransportConfig::max_memory_usage`]: crate::TransportConfig::max_memory_usage
+    /// [`Transport`]: crate::Transport
+    /// [`TransportConfig::unreliable_drop_policy`]: crate::TransportConfig::unreliable_drop_policy
     #[expect(clippy::missing_panics_doc, reason = "shouldn't panic")]
     pub fn reassemble(
         &mut self,
@@ -0,0 +1,205 @@
+//! See [`SessionHandoffPlugin`].
+
+use {
+    crate::{Transport, TransportSet, lane::LaneIndex},
+    aeronet_io::connection::Disconnect,
+    bevy_app::prelude::*,
+    bevy_ecs::prelude::*,
+    core::time::Duration,
+    octs::Bytes,
+    tracing::{debug, warn},
+    web_time::Instant,
+};
+
+/// Allows gracefully migrating from one [`Session`] to another in the middle
+/// of a connection, e.g. to move a client from one server to another during a
+/// world transition.
+///
+/// # Handoff
+///
+/// - Start connecting the new session as usual, and insert a [`SessionHandoff`]
+///   on the old session, targeting the new one
+/// - Until the new session has a [`Transport`], the old session keeps working
+///   as usual
+/// - Once the new session has a [`Transport`], the old session starts
+///   *draining* (see [`SessionHandoff::is_draining`]) - you should send any new
+///   messages on the new session, while the old session finishes delivering the
+///   reliable messages it has already been given
+/// - Once the peer of the old session has acknowledged all of its reliable
+///   messages, or [`SessionHandoff::drain_timeout`] has elapsed, the handoff
+///   *cuts over*: [`HandoffComplete`] is triggered on the old session, and the
+///   old session is disconnected with [`HANDOFF_DISCONNECT_REASON`]
+///
+/// Cutover happens in [`PostUpdate`] before [`TransportSet::Flush`], so any
+/// messages received on the old session up to then have already been
+/// available to drain in [`Update`]. Any reliable messages which the peer did
+/// not acknowledge - including ones pushed in the same update as the cutover -
+/// are given back in [`HandoffComplete::unacked`], so that you can re-send
+/// them on the new session.
+///
+/// If the new session is despawned before the cutover (e.g. because it failed
+/// to connect), the handoff is cancelled: [`SessionHandoff`] is removed, and
+/// the old session keeps working as usual.
+///
+/// # Examples
+///
+/// ```
+/// use {
+///     aeronet_transport::{
+///         Transport,
+///         handoff::{HandoffComplete, SessionHandoff},
+///     },
+///     bevy_ecs::prelude::*,
+///     web_time::Instant,
+/// };
+///
+/// # fn run(mut commands: Commands, old_session: Entity, new_session: Entity) {
+/// commands
+///     .entity(old_session)
+///     .insert(SessionHandoff::new(new_session))
+///     .observe(
+///         |trigger: Trigger<HandoffComplete>, mut transports: Query<&mut Transport>| {
+///             let handoff = trigger.event();
+///             let mut transport = transports.get_mut(handoff.target).unwrap();
+///             for (lane_index, msg) in handoff.unacked.iter().cloned() {
+///                 _ = transport.send.push(lane_index, msg, Instant::now());
+///             }
+///         },
+///     );
+/// # }
+/// ```
+///
+/// [`Session`]: aeronet_io::Session
+#[derive(Debug, Clone, Default)]
+pub struct SessionHandoffPlugin;
+
+impl Plugin for SessionHandoffPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(PostUpdate, update_handoffs.before(TransportSet::Flush));
+    }
+}
+
+/// Disconnect reason used when a session is disconnected because it has been
+/// handed off to another session.
+///
+/// See [`SessionHandoffPlugin`].
+pub const HANDOFF_DISCONNECT_REASON: &str = "handed off to another session";
+
+/// Hands this session off to another session.
+///
+/// See [`SessionHandoffPlugin`].
+#[derive(Debug, Clone, Component)]
+pub struct SessionHandoff {
+    target: Entity,
+    /// How long to wait for the peer to acknowledge the reliable messages of
+    /// this session, after the target session is ready, before cutting over
+    /// anyway.
+    ///
+    /// Set this to [`Duration::ZERO`] to cut over as soon as the target session
+    /// is ready.
+    ///
+    /// By default, this is 5 seconds.
+    pub drain_timeout: Duration,
+    drain_started_at: Option<Instant>,
+}
+
+impl SessionHandoff {
+    /// Creates a new handoff from this session to `target`.
+    #[must_use]
+    pub const fn new(target: Entity) -> Self {
+        Self {
+            target,
+            drain_timeout: Duration::from_secs(5),
+            drain_started_at: None,
+        }
+    }
+
+    /// Sets [`SessionHandoff::drain_timeout`].
+    #[must_use]
+    pub const fn with_drain_timeout(self, drain_timeout: Duration) -> Self {
+        Self {
+            drain_timeout,
+            ..self
+        }
+    }
+
+    /// Gets the session which this session is being handed off to.
+    #[must_use]
+    pub const fn target(&self) -> Entity {
+        self.target
+    }
+
+    /// Gets if the target session is ready, and this session is now waiting
+    /// for its reliable messages to be acknowledged before cutting over.
+    ///
+    /// While this is `true`, you should send new messages on the target
+    /// session instead of this one.
+    #[must_use]
+    pub const fn is_draining(&self) -> bool {
+        self.drain_started_at.is_some()
+    }
+}
+
+/// Triggered on a session when it has been handed off to another session,
+/// right before it is disconnected.
+///
+/// See [`SessionHandoffPlugin`].
+#[derive(Debug, Clone, Event)]
+pub struct HandoffComplete {
+    /// Session which this session was handed off to.
+    pub target: Entity,
+    /// Messages on reliable lanes which the peer did not fully acknowledge
+    /// before the cutover, from oldest to newest on each lane.
+    ///
+    /// The peer may have received some of these, but there is no way to know
+    /// for sure.
+    pub unacked: Vec<(LaneIndex, Bytes)>,
+}
+
+fn update_handoffs(
+    mut sessions: Query<(Entity, &mut SessionHandoff, &Transport)>,
+    targets: Query<Has<Transport>>,
+    mut commands: Commands,
+) {
+    let now = Instant::now();
+    for (entity, mut handoff, transport) in &mut sessions {
+        let target = handoff.target;
+        let Ok(target_ready) = targets.get(target) else {
+            warn!("{entity} handoff target {target} no longer exists, cancelling handoff");
+            commands.entity(entity).remove::<SessionHandoff>();
+            continue;
+        };
+        if !target_ready {
+            continue;
+        }
+
+        let drain_started_at = *handoff.drain_started_at.get_or_insert_with(|| {
+            debug!("{entity} draining for handoff to {target}");
+            now
+        });
+
+        let unacked = transport
+            .send
+            .lanes()
+            .iter()
+            .enumerate()
+            .flat_map(|(lane_index, lane)| {
+                let lane_index = LaneIndex::try_from(lane_index).expect("lane index too large");
+                lane.unacked_msgs().map(move |(_, msg)| (lane_index, msg))
+            })
+            .collect::<Vec<_>>();
+        let drained = unacked.is_empty();
+        let timed_out = now.saturating_duration_since(drain_started_at) >= handoff.drain_timeout;
+        if !drained && !timed_out {
+            continue;
+        }
+
+        debug!(
+            "{entity} handed off to {target} with {} unacked messages",
+            unacked.len()
+        );
+        commands.entity(entity).remove::<SessionHandoff>();
+        commands.trigger_targets(HandoffComplete { target, unacked }, entity);
+        commands.trigger_targets(Disconnect::new(HANDOFF_DISCONNECT_REASON), entity);
+    }
+}
@@ -0,0 +1,95 @@
+//! Hash maps used for tracking sent messages and reassembling received
+//! messages.
+//!
+//! The keys of these maps are message sequence numbers, which are controlled
+//! by the peer. By default, maps are hashed using [`ahash`], which is fast and
+//! randomly keyed per map. If you want a hasher with stronger guarantees
+//! against hash flooding, enable the `std-hasher` feature to use the standard
+//! library's SipHash-based [`RandomState`] instead.
+//!
+//! [`RandomState`]: std::hash::RandomState
+
+/// Hasher builder used for all maps in this crate.
+#[cfg(not(feature = "std-hasher"))]
+pub type RandomState = ahash::RandomState;
+
+/// Hasher builder used for all maps in this crate.
+#[cfg(feature = "std-hasher")]
+pub type RandomState = std::hash::RandomState;
+
+/// [`std::collections::HashMap`] using [`RandomState`].
+pub type HashMap<K, V> = std::collections::HashMap<K, V, RandomState>;
+
+/// [`std::collections::HashSet`] using [`RandomState`].
+pub type HashSet<T> = std::collections::HashSet<T, RandomState>;
+
+#[cfg(test)]
+mod tests {
+    #![expect(clippy::unwrap_used, reason = "testing")]
+
+    use {
+        super::*,
+        crate::{
+            Transport, TransportConfig,
+            lane::{LaneIndex, LaneKind},
+            limit::TokenBucket,
+            recv::recv_on,
+            send::flush_on,
+        },
+        aeronet_io::{Session, packet::IP_MTU},
+        core::any::type_name,
+        octs::Bytes,
+        web_time::Instant,
+    };
+
+    const LANES: [LaneKind; 2] = [LaneKind::UnreliableUnordered, LaneKind::ReliableOrdered];
+
+    #[test]
+    fn selected_hasher() {
+        #[cfg(not(feature = "std-hasher"))]
+        assert_eq!(
+            type_name::<ahash::RandomState>(),
+            type_name::<RandomState>()
+        );
+        #[cfg(feature = "std-hasher")]
+        assert_eq!(
+            type_name::<std::hash::RandomState>(),
+            type_name::<RandomState>()
+        );
+    }
+
+    // run with and without `std-hasher` to check both hashers
+    #[test]
+    fn round_trip_fragmented() {
+        let now = Instant::now();
+        let session = Session::new(now, IP_MTU);
+        let config = TransportConfig::default();
+        let mut sender = Transport::new_symmetric(&session, LANES, now).unwrap();
+        let mut receiver = Transport::new_symmetric(&session, LANES, now).unwrap();
+        sender.send.bytes_bucket = TokenBucket::new(usize::MAX);
+
+        let msgs = (0..8_u8).map(|i| vec![i; IP_MTU * 3]).collect::<Vec<_>>();
+        for (i, msg) in msgs.iter().enumerate() {
+            let lane = LaneIndex::try_from(i % LANES.len()).unwrap();
+            sender
+                .send
+                .push(lane, Bytes::from(msg.clone()), now)
+                .unwrap();
+        }
+        // deliver packets in reverse, so that all messages are being
+        // reassembled at the same time
+        let packets = flush_on(&mut sender, now, IP_MTU).collect::<Vec<_>>();
+        for packet in packets.iter().rev() {
+            recv_on(&mut receiver, &config, now, packet).unwrap();
+        }
+
+        let mut recv = receiver
+            .recv
+            .collect_msgs()
+            .into_iter()
+            .map(|msg| msg.payload)
+            .collect::<Vec<_>>();
+        recv.sort_unstable();
+        assert_eq!(msgs, recv);
+    }
+}
@@ -32,6 +32,8 @@
 use {
     arbitrary::Arbitrary,
     bevy_reflect::prelude::*,
+    core::fmt,
+    derive_more::Error,
     octs::{BufTooShortOr, Decode, Encode, EncodeLen, FixedEncodeLenHint, Read, VarInt, Write},
     static_assertions::const_assert,
     typesize::derive::TypeSize,
@@ -75,6 +77,24 @@ pub enum LaneKind {
     /// updates should not make the player go back in time - so any messages
     /// older than the most recent ones are dropped.
     UnreliableSequenced,
+    /// Messages are *unreliable*, and only the latest message matters.
+    ///
+    /// Similar to [`LaneKind::UnreliableSequenced`], but stale messages are
+    /// also dropped before they are sent, and after they are received:
+    /// - when a message is pushed onto this lane, any previous messages on this
+    ///   lane which have not been fully flushed yet are dropped, and will not
+    ///   be sent
+    /// - when multiple messages on this lane are received in the same update,
+    ///   only the newest one is made available to the user
+    ///
+    /// This lane kind never blocks, and can dramatically reduce bandwidth for
+    /// a single value which is updated many times per frame, since only the
+    /// last update in a frame is sent out.
+    ///
+    /// An example of a message using this lane kind is a client's cursor or
+    /// camera position. Only its current value is useful to the peer, so any
+    /// older values can be thrown away as soon as a newer value exists.
+    UnreliableLatest,
     /// Messages are sent *reliably* but the *ordering* is not guaranteed.
     ///
     /// This is useful for important one-off events where you need a guarantee
@@ -117,7 +137,9 @@ impl LaneKind {
     #[must_use]
     pub const fn reliability(&self) -> LaneReliability {
         match self {
-            Self::UnreliableUnordered | Self::UnreliableSequenced => LaneReliability::Unreliable,
+            Self::UnreliableUnordered | Self::UnreliableSequenced | Self::UnreliableLatest => {
+                LaneReliability::Unreliable
+            }
             Self::ReliableUnordered | Self::ReliableOrdered => LaneReliability::Reliable,
         }
     }
@@ -204,3 +226,105 @@ impl Decode for LaneIndex {
         Ok(Self(src.read::<VarInt<RawLaneIndex>>()?.0))
     }
 }
+
+/// Single lane on which the peer sends messages of a kind that we do not
+/// receive in the same way.
+///
+/// See [`check_lane_pairing`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LaneMismatch {
+    /// Index of the mismatched lane.
+    pub lane: LaneIndex,
+    /// Kind of lane that the peer sends on.
+    pub peer_send: LaneKind,
+    /// Kind of lane that we receive on, or [`None`] if we do not receive on
+    /// this lane at all.
+    pub our_recv: Option<LaneKind>,
+}
+
+impl fmt::Display for LaneMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let Self {
+            lane,
+            peer_send,
+            our_recv,
+        } = self;
+        match our_recv {
+            Some(our_recv) => write!(
+                f,
+                "lane {}: peer sends {peer_send:?}, but we receive {our_recv:?}",
+                lane.0
+            ),
+            None => write!(
+                f,
+                "lane {}: peer sends {peer_send:?}, but we do not receive on this lane",
+                lane.0
+            ),
+        }
+    }
+}
+
+/// The lanes that the peer sends on do not pair up with the lanes that we
+/// receive on.
+///
+/// See [`check_lane_pairing`].
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub struct LanePairingError {
+    /// Every lane which does not pair up, in order of lane index.
+    pub mismatches: Vec<LaneMismatch>,
+}
+
+impl fmt::Display for LanePairingError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "lane pairing mismatch")?;
+        for (i, mismatch) in self.mismatches.iter().enumerate() {
+            let sep = if i == 0 { " - " } else { "; " };
+            write!(f, "{sep}{mismatch}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Checks that each lane the peer sends on pairs up with the lane that we
+/// receive on at the same index.
+///
+/// Lanes are identified purely by their index on the wire, so if the peer's
+/// send lane `i` is e.g. [`LaneKind::ReliableOrdered`], but our receive lane
+/// `i` is [`LaneKind::UnreliableUnordered`], messages will still be received,
+/// but without the guarantees the sender expects. This silently breaks the
+/// app instead of failing loudly, so this function finds such lanes.
+///
+/// A pair of lanes is compatible if both sides have the same [`LaneKind`]. We
+/// may receive on more lanes than the peer sends on, but not the other way
+/// round.
+///
+/// # Errors
+///
+/// Errors with every lane which is not compatible.
+pub fn check_lane_pairing(
+    peer_send: &[LaneKind],
+    our_recv: &[LaneKind],
+) -> Result<(), LanePairingError> {
+    let mismatches = peer_send
+        .iter()
+        .enumerate()
+        .filter_map(|(index, &peer_send)| {
+            let our_recv = our_recv.get(index).copied();
+            if our_recv == Some(peer_send) {
+                return None;
+            }
+            let lane = LaneIndex::try_from(index).ok()?;
+            Some(LaneMismatch {
+                lane,
+                peer_send,
+                our_recv,
+            })
+        })
+        .collect::<Vec<_>>();
+
+    if mismatches.is_empty() {
+        Ok(())
+    } else {
+        Err(LanePairingError { mismatches })
+    }
+}
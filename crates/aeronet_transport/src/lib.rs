@@ -6,36 +6,59 @@
 
 extern crate alloc;
 
+pub mod auth;
+pub mod awaitable;
+pub mod clock;
 pub mod frag;
+pub mod handoff;
+mod hash;
 pub mod lane;
 pub mod limit;
+#[cfg(debug_assertions)]
+mod order;
 pub mod packet;
+pub mod ping;
 pub mod recv;
+pub mod recv_log;
 pub mod rtt;
 pub mod sampling;
 pub mod send;
 pub mod seq_buf;
+pub mod snapshot;
+pub mod summary;
+pub mod trace_context;
 
 #[cfg(feature = "visualizer")]
 pub mod visualizer;
 
 pub use aeronet_io as io;
 use {
-    aeronet_io::{IoSet, Session, connection::Disconnect, packet::MtuTooSmall},
+    aeronet_io::{
+        IoSet, Session,
+        connection::{Disconnect, Unflushed},
+        packet::MtuTooSmall,
+    },
+    alloc::collections::VecDeque,
     arbitrary::Arbitrary,
     bevy_app::prelude::*,
-    bevy_ecs::{prelude::*, schedule::SystemSet},
+    bevy_ecs::{component::ComponentId, prelude::*, schedule::SystemSet, world::DeferredWorld},
     bevy_reflect::Reflect,
-    core::num::Saturating,
+    core::{cell::Cell, mem, num::Saturating, time::Duration},
     derive_more::{Add, AddAssign, Sub, SubAssign},
-    lane::{LaneIndex, LaneKind},
-    octs::FixedEncodeLenHint,
-    packet::{Acknowledge, FragmentHeader, FragmentIndex, MessageSeq, PacketHeader},
-    recv::TransportRecv,
+    lane::{LaneIndex, LaneKind, LaneReliability},
+    octs::{Bytes, FixedEncodeLenHint},
+    packet::{
+        Acknowledge, FragmentHeader, FragmentIndex, FragmentNack, MessageSeq, PacketHeader,
+        PacketSeq,
+    },
+    recv::{RecvError, TransportRecv},
     rtt::RttEstimator,
-    send::TransportSend,
+    send::{
+        Delivery, DeliveryReport, MessageContext, MessageOutcome, PushError, SendError,
+        SentMessage, TransportSend,
+    },
     seq_buf::SeqBuf,
-    tracing::warn,
+    tracing::{error, trace, warn},
     typesize::{TypeSize, derive::TypeSize},
     web_time::Instant,
 };
@@ -50,13 +73,30 @@ impl Plugin for AeronetTransportPlugin {
     fn build(&self, app: &mut App) {
         app.configure_sets(PreUpdate, (IoSet::Poll, TransportSet::Poll).chain())
             .configure_sets(PostUpdate, (TransportSet::Flush, IoSet::Flush).chain())
+            // so that packet traces and activity tracking see packets
+            // before we consume them
+            .configure_sets(
+                PreUpdate,
+                TransportSet::Poll
+                    .after(aeronet_io::packet::trace_recv_packets)
+                    .after(aeronet_io::packet::update_last_recv),
+            )
+            .configure_sets(
+                PostUpdate,
+                TransportSet::Flush.before(aeronet_io::packet::trace_send_packets),
+            )
             .add_systems(
                 PreUpdate,
                 (
                     recv::clear_buffers.before(TransportSet::Poll),
                     (
                         recv::poll,
-                        send::update_send_bytes_config,
+                        recv_log::log_recv_msgs,
+                        awaitable::resolve,
+                        check_peer_lanes,
+                        check_protocol_version,
+                        check_seq_desync,
+                        send::update_send_config,
                         send::refill_send_bytes,
                         check_memory_limit,
                     )
@@ -64,7 +104,21 @@ impl Plugin for AeronetTransportPlugin {
                         .in_set(TransportSet::Poll),
                 ),
             )
-            .add_systems(PostUpdate, send::flush.in_set(TransportSet::Flush));
+            .add_systems(
+                PostUpdate,
+                (
+                    send::flush,
+                    awaitable::resolve,
+                    send::detect_congestion,
+                    check_unacked_packets,
+                    update_unflushed,
+                )
+                    .chain()
+                    .in_set(TransportSet::Flush),
+            );
+
+        #[cfg(debug_assertions)]
+        order::build(app);
     }
 }
 
@@ -87,6 +141,14 @@ impl Plugin for AeronetTransportPlugin {
 /// The [`Transport`] itself may disconnect the session prematurely if there
 /// is an error such as running out of memory or buffering too many messages.
 ///
+/// While the transport has messages which have not been fully flushed or
+/// acknowledged, the session is marked as [`Unflushed`]. When the app exits,
+/// this holds back the exit for up to [`AppExitTimeout`], so that these
+/// messages can still be resent and acknowledged, before the IO layer
+/// gracefully disconnects the session (see [`APP_EXIT_DISCONNECT_REASON`]).
+/// Reliable messages which are still unacknowledged once the timeout passes
+/// are not guaranteed to arrive.
+///
 /// # Usage
 ///
 /// - Use [`Transport::send`] to enqueue messages for sending, and to get a key
@@ -99,14 +161,50 @@ impl Plugin for AeronetTransportPlugin {
 ///
 /// The `recv` buffers must be drained on every update, otherwise some may be
 /// lost, leading to incorrect behavior, and a warning will be logged.
+///
+/// [`AppExitTimeout`]: aeronet_io::connection::AppExitTimeout
+/// [`APP_EXIT_DISCONNECT_REASON`]: aeronet_io::connection::APP_EXIT_DISCONNECT_REASON
 #[derive(Debug, Component, TypeSize)]
-#[require(TransportConfig)]
+#[expect(clippy::struct_excessive_bools, reason = "independent state flags")]
+#[require(TransportConfig(implicit_config))]
+#[component(on_insert = check_implicit_config)]
 pub struct Transport {
     // shared
-    flushed_packets: SeqBuf<FlushedPacket, 1024>,
+    flushed_packets: SeqBuf<FlushedPacket, MAX_UNACKED_PACKETS>,
+    /// Number of unacknowledged packets when we last crossed
+    /// [`UNACKED_PACKETS_WARN_THRESHOLD`].
+    unacked_packets_warned_at: Option<usize>,
+    unacked_packets_warning_pending: bool,
+    peak_memory_used: usize,
+    memory_pressure_high: bool,
     stats: MessageStats,
     peer_acks: Acknowledge,
+    acks_pending: bool,
+    ack_eliciting_pending: bool,
+    pending_nacks: VecDeque<FragmentNack>,
+    /// Partially-received messages on reliable lanes which we may still NACK.
+    nack_candidates: Vec<(LaneIndex, MessageSeq)>,
+    pings: ping::Pings,
     rtt: RttEstimator,
+    peer_lanes: Option<Box<[LaneKind]>>,
+    peer_lanes_checked: bool,
+    peer_mtu: Option<usize>,
+    /// MTU that we are advertising to the peer, and the first packet which
+    /// carried it.
+    sent_mtu: Option<(usize, PacketSeq)>,
+    /// Whether the peer has received a packet carrying `sent_mtu`.
+    sent_mtu_acked: bool,
+    peer_protocol_version: Option<u32>,
+    protocol_mismatches: usize,
+    protocol_mismatch_checked: bool,
+    seq_desyncs: usize,
+    seq_desync_checked: bool,
+    adapted_lost_threshold_factor: Option<f64>,
+    clock: clock::ClockSync,
+    #[cfg(feature = "cpu-time")]
+    cpu_time: CpuTime,
+    #[typesize(skip)]
+    awaiting: awaitable::DeliveryWaiters,
     /// Interface to the receiving half of this transport.
     ///
     /// Use this to read received messages and acknowledgements.
@@ -120,11 +218,13 @@ pub struct Transport {
 /// User-configurable properties of a [`Transport`].
 ///
 /// If you do not provide this component explicitly, a default config will
-/// be created and inserted into the [`Session`].
+/// be created and inserted into the [`Session`] - unless the
+/// [`ExplicitTransportConfig`] resource is present.
 ///
 /// This component may be modified over the lifetime of a [`Session`] and the
 /// [`Transport`] will be updated accordingly.
 #[derive(Debug, Clone, Component, TypeSize, Reflect)]
+#[expect(clippy::struct_excessive_bools, reason = "independent config options")]
 #[reflect(Component)]
 pub struct TransportConfig {
     /// Maximum amount of memory, in bytes, that this [`Transport`] may use for
@@ -134,45 +234,497 @@ pub struct TransportConfig {
     /// connections fail with an out-of-memory error, or you see
     /// [`Transport::memory_used`] is too high (you can use the [`visualizer`]
     /// to see real-time statistics).
+    ///
+    /// To limit the memory used by all sessions combined, see
+    /// [`TransportMemoryBudget`].
     pub max_memory_usage: usize,
+    /// Amount of memory, in bytes, above which this [`Transport`] is under
+    /// memory pressure.
+    ///
+    /// Unlike [`TransportConfig::max_memory_usage`], going over this limit does
+    /// not disconnect the session. Instead, [`MemoryPressureHigh`] is triggered
+    /// on the session, so that you can reduce how much you are sending before
+    /// the hard limit is reached. Once [`Transport::memory_used`] drops back to
+    /// three quarters of this limit or below, [`MemoryPressureNormal`] is
+    /// triggered.
+    ///
+    /// Memory usage is checked once per update, in [`TransportSet::Poll`].
+    /// Use [`Transport::is_memory_pressure_high`] to read the current state.
+    ///
+    /// This should be lower than [`TransportConfig::max_memory_usage`].
+    ///
+    /// By default, this is [`None`].
+    pub soft_memory_limit: Option<usize>,
     /// How many packet bytes we can flush out to the IO layer per second.
     ///
     /// This can be used to limit the outgoing bandwidth of this transport.
     ///
     /// By default, this is [`usize::MAX`].
     pub send_bytes_per_sec: usize,
+    /// If set, enables *slow start*: right after the [`Transport`] is created,
+    /// we only send this many bytes per second, and ramp up to
+    /// [`TransportConfig::send_bytes_per_sec`] over the next few round trips.
+    ///
+    /// Sending at the full rate straight away can overwhelm the network path
+    /// before we have any idea of its RTT or packet loss. With slow start, the
+    /// send rate doubles every [`Transport::rtt`] (using the smoothed RTT
+    /// estimate at that time), until it reaches
+    /// [`TransportConfig::send_bytes_per_sec`]. If any packets were declared
+    /// lost (see [`MessageStats::packets_lost`]) since the rate was last
+    /// doubled, the rate is held for another round trip instead.
+    ///
+    /// The current send rate is the capacity of
+    /// [`TransportSend::bytes_bucket`].
+    ///
+    /// Slow start only happens once, when the [`Transport`] is created.
+    /// Setting this later in the session has no effect, but clearing it ends
+    /// slow start early.
+    ///
+    /// By default, this is [`None`].
+    pub slow_start_bytes_per_sec: Option<usize>,
     /// Multiplier for how long it takes for an unacknowledged packet to be
     /// marked as lost on our side.
     ///
-    /// Packet loss is computed by calculating how many packets, that we have
-    /// sent out, should have been acknowledged by the peer by now (see
-    /// [`SessionStatsSample::loss`]). "By now" is defined as
-    /// [`Transport::rtt`]'s [`RttEstimator::pto`], multiplied by this config
-    /// value.
+    /// A packet is declared lost if it has not been acknowledged by the peer
+    /// within [`Transport::rtt`]'s [`RttEstimator::pto`], multiplied by this
+    /// config value (see [`SessionStatsSample::loss`]). If the peer does
+    /// acknowledge the packet after it was declared lost, the loss was
+    /// spurious, and is not counted.
     ///
     /// If this value is set too low, or below 1, the packet loss value will be
     /// an overestimate, as the peer will not have enough time to respond with
-    /// an acknowledgement.
+    /// an acknowledgement. Spurious losses are corrected once their late
+    /// acknowledgements arrive, but until then, they are counted as lost.
     /// If this value is set too high, the packet loss value will be an
     /// underestimate, as packets which should be reasonably considered "lost"
     /// will not be marked as such.
     ///
     /// By default, this is 1.5.
     ///
+    /// If [`TransportConfig::adaptive_loss_threshold`] is enabled, this is
+    /// only the starting value.
+    ///
     /// [`SessionStatsSample::loss`]: crate::sampling::SessionStatsSample::loss
     pub packet_lost_threshold_factor: f64,
+    /// Whether to adjust [`TransportConfig::packet_lost_threshold_factor`]
+    /// over the lifetime of the session, based on how accurately packets have
+    /// been declared lost so far.
+    ///
+    /// A single fixed factor can't suit every network: on a connection with
+    /// frequent delay spikes, it will cause many spurious losses, but on a
+    /// stable connection, it will be slow to notice real losses. In adaptive
+    /// mode, the factor starts at
+    /// [`TransportConfig::packet_lost_threshold_factor`], and:
+    /// - whenever a packet is acknowledged after it was declared lost (a
+    ///   spurious loss - see [`MessageStats::spurious_losses`]), the factor is
+    ///   raised, so that we wait longer before declaring packets lost
+    /// - whenever a packet which was declared lost is confirmed to really be
+    ///   lost, because the peer has acknowledged several packets sent after it
+    ///   but not this one, the factor is lowered, so that we notice losses
+    ///   sooner
+    ///
+    /// The factor is never lowered below 1 (or the configured value, if that is
+    /// lower), and never raised above 4 times the configured value. Use
+    /// [`Transport::packet_lost_threshold_factor`] to read the factor
+    /// currently in use.
+    ///
+    /// By default, this is `false`.
+    pub adaptive_loss_threshold: bool,
+    /// How often to share our wall clock time with the peer, so that it can
+    /// estimate the offset between our clocks.
+    ///
+    /// If this is set, our wall clock time is included in a packet flushed to
+    /// the peer at most once per interval, which costs a few extra bytes in
+    /// that packet. The peer can then read the estimated offset via
+    /// [`Transport::peer_clock_offset`]. For both sides to know the offset,
+    /// both sides must set this.
+    ///
+    /// The clock time piggybacks on packets which are already being sent, so
+    /// the peer only receives it while we're sending messages or acks.
+    ///
+    /// By default, this is [`None`].
+    pub clock_sync_interval: Option<Duration>,
+    /// Whether to flush out fragments which are being retransmitted before any
+    /// fragments which have never been sent yet.
+    ///
+    /// By default, fragments are flushed in the order that their messages were
+    /// pushed, oldest first. When the connection recovers after a period of
+    /// packet loss, or when [`TransportConfig::send_bytes_per_sec`] is limiting
+    /// how much we can send, enabling this minimizes the delivery latency of
+    /// data which the peer is already waiting on.
+    ///
+    /// This only changes the order in which fragments are flushed - it does not
+    /// affect the guarantees that any [`LaneKind`] provides, since the receiver
+    /// orders messages using their sequence numbers. On an ordered lane, the
+    /// receiver holds back newer messages until the retransmitted ones arrive
+    /// anyway, so prioritizing retransmissions means those newer messages can
+    /// be delivered sooner.
+    ///
+    /// This takes precedence over lane weights set with
+    /// [`TransportSend::set_lane_weight`], so a retransmission goes before any
+    /// fragment which is being sent for the first time, no matter which lane
+    /// it is on.
+    ///
+    /// By default, this is `false`.
+    pub retransmit_priority: bool,
+    /// Minimum time between two flushes of this transport.
+    ///
+    /// By default, a transport flushes on every [`PostUpdate`], so at a high
+    /// update rate, each packet only carries the few messages pushed since the
+    /// last update. If the last flush was less than this long ago,
+    /// [`TransportSet::Flush`] skips flushing this transport, and messages keep
+    /// being buffered until the next flush, so that they are batched into
+    /// fewer, larger packets. This trades latency for less packet overhead.
+    ///
+    /// Fragments which are due to be retransmitted are never held back: if any
+    /// are due, the transport is flushed as usual, along with everything else
+    /// which is buffered. Since every flush sends out at least one packet, the
+    /// peer still receives acks and keep-alives at least once per interval. To
+    /// send acks sooner than that, see [`TransportConfig::ack_policy`].
+    ///
+    /// By default, this is [`Duration::ZERO`].
+    pub min_flush_interval: Duration,
+    /// When acknowledgements for received packets are sent to the peer.
+    ///
+    /// Acks are carried in the header of every packet we send, so by default,
+    /// they piggyback on the next flush. If that flush is held back, e.g. by
+    /// [`TransportConfig::min_flush_interval`], the peer only learns that its
+    /// packets arrived once it happens, which delays its RTT estimate and loss
+    /// detection. Other policies may instead send acks out immediately, in a
+    /// dedicated packet containing only the header.
+    ///
+    /// By default, this is [`AckPolicy::Piggyback`].
+    pub ack_policy: AckPolicy,
+    /// Maximum number of packets which a single flush of this transport may
+    /// send out.
+    ///
+    /// [`TransportConfig::send_bytes_per_sec`] limits how many bytes are sent
+    /// over time, but if a large backlog builds up and the send rate allows
+    /// it, a single flush may still send out hundreds of packets at once. This
+    /// caps the size of that burst. Any fragments which don't fit into these
+    /// packets stay buffered, and are sent in later flushes.
+    ///
+    /// Packets sent for pings also count towards this limit.
+    ///
+    /// This must be at least 1 - a value of 0 is treated as 1, and a warning is
+    /// logged.
+    ///
+    /// By default, this is [`usize::MAX`].
+    pub max_packets_per_flush: usize,
+    /// Maximum number of messages which are made available in
+    /// [`TransportRecv::msgs`] per update.
+    ///
+    /// If many messages are received at once, e.g. because a large backlog of
+    /// messages on an ordered lane is unblocked, processing all of them in a
+    /// single update may cause a frame spike. Any messages over this limit are
+    /// held back (see [`TransportRecv::msgs_backlog_len`]) instead of being
+    /// dropped, and are made available in later updates.
+    ///
+    /// Messages are always made available in the same order that they would be
+    /// without this limit, and messages held back from a previous update are
+    /// always made available before any newer messages.
+    ///
+    /// Held back messages count towards [`Transport::memory_used`].
+    ///
+    /// By default, this is [`usize::MAX`].
+    pub max_msgs_per_drain: usize,
+    /// Which partially-received unreliable message is evicted when there is
+    /// not enough memory left to reassemble an incoming fragment.
+    ///
+    /// By default, this is [`UnreliableDropPolicy::DropIncoming`].
+    pub unreliable_drop_policy: UnreliableDropPolicy,
+    /// Maximum number of distinct partially-received messages, across all
+    /// lanes, which may be reassembled at the same time.
+    ///
+    /// [`TransportConfig::max_memory_usage`] limits how many bytes partial
+    /// messages may use, but a peer could still send the first fragment of
+    /// many different messages to make us track a huge number of them. Once
+    /// this limit is reached, a fragment which would start reassembling a new
+    /// message first tries to evict an existing unreliable message, following
+    /// [`TransportConfig::unreliable_drop_policy`]. If nothing can be evicted,
+    /// the fragment is dropped - if it was on a reliable lane, the peer will
+    /// resend it later.
+    ///
+    /// Messages which fit into a single fragment are never tracked, so they
+    /// are not affected by this limit.
+    ///
+    /// By default, this is 1024.
+    pub max_concurrent_reassemblies: usize,
+    /// Whether to ask the peer to resend fragments of reliable messages which
+    /// we think were lost, instead of waiting for the peer to notice.
+    ///
+    /// Normally, the peer only resends a fragment once it has not been
+    /// acknowledged for its [`RttEstimator::pto`]. With this enabled, once a
+    /// partially-received message on a reliable lane stops receiving fragments,
+    /// and we have missed a packet since its first fragment arrived, we send a
+    /// negative acknowledgement ([`FragmentNack`]) for its missing fragments.
+    /// The peer then resends those fragments on its next flush, which reduces
+    /// the delivery latency of large messages on lossy connections.
+    ///
+    /// A single NACK covers all of a message's missing fragments. NACKs are
+    /// carried in [`PacketHeader`]s, one per packet, and only ride along on
+    /// packets which are being sent anyway, so they never cause extra packets
+    /// to be sent - if several messages are waiting to be NACKed, the later
+    /// NACKs go out in later packets. A NACK may be sent for a fragment which
+    /// was only reordered rather than lost, in which case the peer resends it
+    /// needlessly.
+    ///
+    /// Peers always act on NACKs they receive, so this only needs to be set on
+    /// the side which receives the messages.
+    ///
+    /// By default, this is `false`.
+    pub fragment_nacks: bool,
+    /// Whether to log a hex dump of messages which were left in
+    /// [`TransportRecv::msgs`] without being drained.
+    ///
+    /// Undrained messages are always cleared before the next
+    /// [`TransportSet::Poll`], along with a warning saying how many there were.
+    /// With this enabled, that warning is accompanied by the lanes and payloads
+    /// of a few of those messages, which helps to find out where unexpected
+    /// traffic is coming from when debugging a protocol. To avoid flooding the
+    /// logs, this is logged at most once per second per session.
+    ///
+    /// By default, this is `false`.
+    pub log_undrained: bool,
 }
 
 impl Default for TransportConfig {
     fn default() -> Self {
         Self {
             max_memory_usage: 4 * 1024 * 1024,
+            soft_memory_limit: None,
             send_bytes_per_sec: usize::MAX,
+            slow_start_bytes_per_sec: None,
             packet_lost_threshold_factor: 1.5,
+            adaptive_loss_threshold: false,
+            clock_sync_interval: None,
+            retransmit_priority: false,
+            min_flush_interval: Duration::ZERO,
+            ack_policy: AckPolicy::default(),
+            max_packets_per_flush: usize::MAX,
+            max_msgs_per_drain: usize::MAX,
+            unreliable_drop_policy: UnreliableDropPolicy::default(),
+            max_concurrent_reassemblies: 1024,
+            fragment_nacks: false,
+            log_undrained: false,
         }
     }
 }
 
+/// How a [`Transport`] chooses which partially-received message to drop when
+/// there is not enough memory left under [`TransportConfig::max_memory_usage`]
+/// to reassemble an incoming fragment.
+///
+/// This is also used when there are already
+/// [`TransportConfig::max_concurrent_reassemblies`] messages being reassembled.
+///
+/// Only messages on unreliable lanes are ever evicted - partially-received
+/// reliable messages are always kept. If there are no unreliable messages left
+/// to evict, the incoming fragment is dropped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, TypeSize, Reflect)]
+pub enum UnreliableDropPolicy {
+    /// Drops the incoming fragment, keeping all messages which are already
+    /// being reassembled.
+    #[default]
+    DropIncoming,
+    /// Evicts the message which started being reassembled the longest time
+    /// ago.
+    ///
+    /// Old incomplete messages are the most likely to have lost a fragment, and
+    /// never be completed.
+    DropOldest,
+    /// Evicts the message which is using the most memory.
+    ///
+    /// This frees up memory with as few evictions as possible.
+    DropLargest,
+    /// Evicts a message on the lowest priority unreliable lane, picking the
+    /// oldest message on that lane.
+    ///
+    /// Lanes are prioritized by their [`LaneIndex`] - the lane with the highest
+    /// index has the lowest priority.
+    DropLowestPriorityLane,
+}
+
+/// When a [`Transport`] sends acknowledgements for the packets it has
+/// received.
+///
+/// Whenever the transport is flushed, acks are written into the header of the
+/// packets sent, so a policy only changes what happens on updates where the
+/// transport is *not* flushed (see [`TransportConfig::min_flush_interval`]).
+/// A dedicated ack packet is only sent if we have received a packet carrying
+/// messages or a [`FragmentNack`] whose ack has not been sent to the peer yet,
+/// and it counts towards [`TransportConfig::send_bytes_per_sec`] like any other
+/// packet. Packets which carry nothing but acks themselves (including
+/// keep-alives and [pings]) are never answered by a dedicated ack packet -
+/// otherwise, two idle peers would keep acking each other's acks forever. Their
+/// acks always wait for the next flush.
+///
+/// [pings]: Transport::ping
+///
+/// See [`TransportConfig::ack_policy`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, TypeSize, Reflect)]
+#[reflect(opaque, Debug)]
+pub enum AckPolicy {
+    /// Acks wait for the next flush, and are sent along with any messages or
+    /// keep-alive in that flush.
+    ///
+    /// This sends the fewest packets, but acks may be delayed by up to
+    /// [`TransportConfig::min_flush_interval`].
+    #[default]
+    Piggyback,
+    /// Acks are sent in a dedicated packet in the same update that their
+    /// packets were received, if the transport is not flushed in that update.
+    ///
+    /// This gives the peer the most accurate RTT estimate and loss detection,
+    /// at the cost of sending an extra packet on every update where we receive
+    /// messages but don't flush.
+    Standalone,
+    /// Acks wait for the next flush if it will carry messages within
+    /// `max_delay`, otherwise they are sent like [`AckPolicy::Standalone`].
+    ///
+    /// This avoids sending a dedicated ack packet right before a packet which
+    /// would have carried the acks anyway, while still not holding acks back
+    /// for a long time when there is no data to send.
+    Hybrid {
+        /// Maximum time which acks may wait for messages to piggyback on.
+        max_delay: Duration,
+    },
+}
+
+/// Requires every [`Transport`] to be added together with an explicit
+/// [`TransportConfig`].
+///
+/// By default, if a [`Transport`] is added without a [`TransportConfig`], a
+/// default config is inserted for it. If you want to make sure that you never
+/// accidentally ship with default configuration values, insert this resource:
+/// a [`Transport`] which is added without a [`TransportConfig`] will then log
+/// an error and disconnect its session, instead of running with the defaults.
+///
+/// The [`TransportConfig`] must be inserted in the same bundle as the
+/// [`Transport`] (or already be present on the entity), not in a later
+/// command.
+///
+/// By default, this resource is not present.
+#[derive(Debug, Clone, Copy, Default, Resource, Reflect)]
+#[reflect(Resource)]
+pub struct ExplicitTransportConfig;
+
+/// Limit on the combined memory that all [`Transport`]s in the app may use.
+///
+/// [`TransportConfig::max_memory_usage`] only limits the memory used by a
+/// single session. On a server with many clients, all of them combined may
+/// still exhaust the process's memory, even if each client stays under its own
+/// limit. Insert this resource to also limit the combined memory usage.
+///
+/// Whenever the total [`Transport::memory_used`] across all sessions goes over
+/// [`TransportMemoryBudget::max_memory_usage`], the [`MemoryBudgetPolicy`]
+/// decides which sessions are disconnected to bring memory usage back under
+/// the budget. Sessions which are already over their own
+/// [`TransportConfig::max_memory_usage`] are disconnected anyway, and do not
+/// count towards the total.
+///
+/// By default, this resource is not present, so there is no combined limit.
+#[derive(Debug, Clone, Resource, Reflect)]
+#[reflect(Resource)]
+pub struct TransportMemoryBudget {
+    /// Maximum amount of memory, in bytes, that all [`Transport`]s may use
+    /// combined.
+    pub max_memory_usage: usize,
+    /// Which sessions are disconnected when the budget is exceeded.
+    pub policy: MemoryBudgetPolicy,
+}
+
+/// How a [`TransportMemoryBudget`] chooses which sessions to disconnect when
+/// the budget is exceeded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Reflect)]
+pub enum MemoryBudgetPolicy {
+    /// Disconnects the sessions using the most memory, one at a time, until the
+    /// remaining sessions fit into the budget.
+    ///
+    /// This disconnects as few sessions as possible.
+    #[default]
+    DisconnectLargest,
+    /// Gives each session an equal share of the budget, and disconnects all
+    /// sessions using more than their share.
+    ///
+    /// This effectively tightens each session's memory limit while the budget
+    /// is exceeded, and may disconnect more sessions than
+    /// [`MemoryBudgetPolicy::DisconnectLargest`] would.
+    FairShare,
+}
+
+impl TransportMemoryBudget {
+    /// Given the memory used by each session, returns the sessions which must
+    /// be disconnected to stay within this budget.
+    fn sessions_over_budget(&self, mut sessions: Vec<(Entity, usize)>) -> Vec<(Entity, usize)> {
+        let mut total = sessions.iter().map(|(_, mem_used)| mem_used).sum::<usize>();
+        if total <= self.max_memory_usage {
+            return Vec::new();
+        }
+
+        match self.policy {
+            MemoryBudgetPolicy::DisconnectLargest => {
+                sessions.sort_unstable_by_key(|(session, mem_used)| (*mem_used, *session));
+                let mut over_budget = Vec::new();
+                while total > self.max_memory_usage {
+                    let Some((session, mem_used)) = sessions.pop() else {
+                        break;
+                    };
+                    total -= mem_used;
+                    over_budget.push((session, mem_used));
+                }
+                over_budget
+            }
+            MemoryBudgetPolicy::FairShare => {
+                let share = self.max_memory_usage / sessions.len();
+                sessions.retain(|(_, mem_used)| *mem_used > share);
+                sessions
+            }
+        }
+    }
+}
+
+/// Triggered on a session when its [`Transport::memory_used`] goes over
+/// [`TransportConfig::soft_memory_limit`], in [`TransportSet::Poll`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Event)]
+pub struct MemoryPressureHigh {
+    /// [`Transport::memory_used`] when the limit was crossed.
+    pub memory_used: usize,
+}
+
+/// Triggered on a session which was under memory pressure when its
+/// [`Transport::memory_used`] drops back to three quarters of
+/// [`TransportConfig::soft_memory_limit`] or below, in [`TransportSet::Poll`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Event)]
+pub struct MemoryPressureNormal {
+    /// [`Transport::memory_used`] when the pressure was relieved.
+    pub memory_used: usize,
+}
+
+/// Triggered on a session when its peer speaks a different
+/// [`PROTOCOL_VERSION`] to ours, in [`TransportSet::Poll`].
+///
+/// This is only triggered once the peer has sent
+/// [`MAX_PROTOCOL_MISMATCHES`] packets in a row with the same different
+/// version. The two sides can't interpret each other's packets, so after this
+/// is triggered, the session is disconnected.
+///
+/// See [`Transport::peer_protocol_version`].
+///
+/// [`PROTOCOL_VERSION`]: packet::PROTOCOL_VERSION
+/// [`MAX_PROTOCOL_MISMATCHES`]: recv::MAX_PROTOCOL_MISMATCHES
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Event)]
+pub struct ProtocolVersionMismatch {
+    /// Session whose peer speaks a different protocol version.
+    pub entity: Entity,
+    /// Our [`PROTOCOL_VERSION`].
+    ///
+    /// [`PROTOCOL_VERSION`]: packet::PROTOCOL_VERSION
+    pub ours: u32,
+    /// Protocol version that the peer speaks.
+    pub peer: u32,
+}
+
 /// Incoming message that a [`Transport`] created from packets received by the
 /// IO layer.
 #[derive(Debug, TypeSize)]
@@ -185,8 +737,60 @@ pub struct RecvMessage {
     pub payload: Vec<u8>,
 }
 
+/// If one side of a connection has an MTU this many times larger than the
+/// other side's, a warning is logged.
+///
+/// See [`Transport::peer_mtu`].
+pub const MTU_ASYMMETRY_WARN_RATIO: usize = 2;
+
+/// Maximum number of packets which a [`Transport`] can keep track of while
+/// waiting for the peer to acknowledge them.
+///
+/// Packets are tracked in a window indexed by their sequence number. Once more
+/// than this many packets are in flight, flushing a new packet makes us forget
+/// about the oldest unacknowledged one: if the peer acknowledges it later, the
+/// acknowledgement is ignored, so its fragments are resent unnecessarily, and
+/// it is never counted as lost. On a connection with a very high packet rate
+/// or a very high RTT, you should reduce your send rate (see
+/// [`TransportConfig::send_bytes_per_sec`]) to stay under this limit.
+///
+/// Once [`Transport::num_unacked_packets`] reaches
+/// [`UNACKED_PACKETS_WARN_THRESHOLD`], a warning is logged.
+pub const MAX_UNACKED_PACKETS: usize = 1024;
+
+/// Once this many packets are waiting to be acknowledged, a warning is logged,
+/// since the transport is close to forgetting about unacknowledged packets.
+///
+/// The warning is logged again if the number of unacknowledged packets drops
+/// below half of [`MAX_UNACKED_PACKETS`], then rises back up to this threshold.
+///
+/// See [`MAX_UNACKED_PACKETS`].
+pub const UNACKED_PACKETS_WARN_THRESHOLD: usize = MAX_UNACKED_PACKETS * 3 / 4;
+
 const FRAG_OVERHEAD: usize = PacketHeader::MAX_ENCODE_LEN + FragmentHeader::MAX_ENCODE_LEN;
 
+/// Checks if a transport's recv and send lanes look like they were meant to be
+/// the same, but were accidentally configured differently.
+///
+/// Intentionally asymmetric configs usually have different lanes on each side,
+/// so we only flag configs which have the exact same lane kinds, but in a
+/// different order.
+#[cfg_attr(
+    not(any(debug_assertions, test)),
+    expect(dead_code, reason = "only checked in debug builds")
+)]
+fn likely_accidental_asymmetry(recv_lanes: &[LaneKind], send_lanes: &[LaneKind]) -> bool {
+    if recv_lanes == send_lanes {
+        return false;
+    }
+
+    let mut recv_lanes = recv_lanes.to_vec();
+    let mut send_lanes = send_lanes.to_vec();
+    recv_lanes.sort_unstable_by_key(|kind| *kind as u8);
+    send_lanes.sort_unstable_by_key(|kind| *kind as u8);
+    recv_lanes == send_lanes
+}
+
 impl Transport {
     /// Creates a new transport from an existing [`Session`].
     ///
@@ -195,6 +799,14 @@ impl Transport {
     ///
     /// Also see [`TransportConfig`] for configuration options.
     ///
+    /// `recv_lanes` must match the peer's `send_lanes`, and vice versa. If both
+    /// sides send and receive on the same lanes, use
+    /// [`Transport::new_symmetric`] instead. Lane configs may be intentionally
+    /// asymmetric, e.g. a server may send on lanes which the client only
+    /// receives on. However, if the recv and send lanes have the same kinds,
+    /// but in a different order, the lanes were likely swapped by mistake, and
+    /// a warning is logged in debug builds.
+    ///
     /// # Errors
     ///
     /// Errors if the [`Session::min_mtu`] is too small to support messages.
@@ -242,22 +854,85 @@ impl Transport {
             mtu: min_mtu,
             min: FRAG_OVERHEAD,
         })?;
+
+        let recv_lanes = recv_lanes.into_iter().collect::<Vec<_>>();
+        let send_lanes = send_lanes.into_iter().collect::<Vec<_>>();
+        #[cfg(debug_assertions)]
+        if likely_accidental_asymmetry(&recv_lanes, &send_lanes) {
+            warn!(
+                "Creating transport with {} lanes, but recv lanes {recv_lanes:?} do not match \
+                 send lanes {send_lanes:?} - if this is intentional, you can ignore this warning, \
+                 otherwise consider using `Transport::new_symmetric`",
+                recv_lanes.len()
+            );
+        }
+
         Ok(Self {
             flushed_packets: SeqBuf::new_from_fn(|_| FlushedPacket::new(now)),
+            unacked_packets_warned_at: None,
+            unacked_packets_warning_pending: false,
+            peak_memory_used: 0,
+            memory_pressure_high: false,
             stats: MessageStats::default(),
             peer_acks: Acknowledge::default(),
+            acks_pending: false,
+            ack_eliciting_pending: false,
+            pending_nacks: VecDeque::new(),
+            nack_candidates: Vec::new(),
+            pings: ping::Pings::default(),
             rtt: RttEstimator::default(),
+            peer_lanes: None,
+            peer_lanes_checked: false,
+            peer_mtu: None,
+            sent_mtu: None,
+            sent_mtu_acked: false,
+            peer_protocol_version: None,
+            protocol_mismatches: 0,
+            protocol_mismatch_checked: false,
+            seq_desyncs: 0,
+            seq_desync_checked: false,
+            adapted_lost_threshold_factor: None,
+            clock: clock::ClockSync::default(),
+            #[cfg(feature = "cpu-time")]
+            cpu_time: CpuTime::default(),
+            awaiting: awaitable::DeliveryWaiters::default(),
             recv: TransportRecv::new(recv_lanes),
             send: TransportSend::new(max_frag_len, send_lanes),
         })
     }
 
+    /// Creates a new transport from an existing [`Session`], which sends and
+    /// receives on the same lanes.
+    ///
+    /// This is the common case where both sides of the connection use the same
+    /// lane config. See [`Transport::new`].
+    ///
+    /// # Errors
+    ///
+    /// See [`Transport::new`].
+    pub fn new_symmetric(
+        session: &Session,
+        lanes: impl IntoIterator<Item = LaneKind>,
+        now: Instant,
+    ) -> Result<Self, MtuTooSmall> {
+        let lanes = lanes.into_iter().collect::<Vec<_>>();
+        Self::new(session, lanes.clone(), lanes, now)
+    }
+
     /// Gets the total stats gathered up to now.
     #[must_use]
     pub const fn stats(&self) -> MessageStats {
         self.stats
     }
 
+    /// Gets the total CPU time spent fragmenting and reassembling messages up
+    /// to now.
+    #[cfg(feature = "cpu-time")]
+    #[must_use]
+    pub const fn cpu_time(&self) -> CpuTime {
+        self.cpu_time
+    }
+
     /// Gets access to the RTT estimator, allowing you to read the current RTT
     /// estimates.
     #[must_use]
@@ -267,22 +942,751 @@ impl Transport {
 
     /// Gets the number of packets that have been flushed out to the peer, but
     /// we have not received an acknowledgement from the peer for them yet.
+    ///
+    /// This is at most [`MAX_UNACKED_PACKETS`].
     #[must_use]
     pub const fn num_unacked_packets(&self) -> usize {
         self.flushed_packets.len()
     }
 
+    /// Checks if the number of unacknowledged packets has just reached
+    /// [`UNACKED_PACKETS_WARN_THRESHOLD`].
+    ///
+    /// This is called every time a packet is flushed, since a single flush may
+    /// flush enough packets to wrap the whole window.
+    fn track_unacked_packets(&mut self) {
+        let num_unacked = self.num_unacked_packets();
+        if num_unacked >= UNACKED_PACKETS_WARN_THRESHOLD {
+            if self.unacked_packets_warned_at.is_none() {
+                self.unacked_packets_warned_at = Some(num_unacked);
+                self.unacked_packets_warning_pending = true;
+            }
+        } else if num_unacked < MAX_UNACKED_PACKETS / 2 {
+            self.unacked_packets_warned_at = None;
+        }
+    }
+
+    /// If the number of unacknowledged packets has reached
+    /// [`UNACKED_PACKETS_WARN_THRESHOLD`] since the last call, gets that
+    /// number.
+    fn take_unacked_packets_warning(&mut self) -> Option<usize> {
+        if mem::take(&mut self.unacked_packets_warning_pending) {
+            self.unacked_packets_warned_at
+        } else {
+            None
+        }
+    }
+
+    /// Immediately sends a packet containing only acknowledgements to the
+    /// peer, if we have received packets whose acknowledgements have not been
+    /// flushed out yet.
+    ///
+    /// Normally, acknowledgements are only sent out in [`TransportSet::Flush`],
+    /// piggybacking on any messages sent in that update. If you know that you
+    /// won't be sending a response to the peer for a while (e.g. in a
+    /// request/response pattern where the response takes time to prepare), you
+    /// can call this to let the peer know that its packets have arrived as soon
+    /// as possible. This improves the peer's RTT estimate and packet loss
+    /// detection.
+    ///
+    /// The packet is pushed into [`Session::send`], so it is sent out by the IO
+    /// layer in the next [`IoSet::Flush`]. It still counts towards
+    /// [`TransportConfig::send_bytes_per_sec`] - if there is not enough budget
+    /// left, no packet is sent.
+    ///
+    /// Returns whether a packet was sent.
+    pub fn flush_acks_now(&mut self, session: &mut Session, now: Instant) -> bool {
+        let mtu = session.mtu();
+        send::flush_acks_on(self, now, mtu)
+            .map(|packet| session.send.push(packet))
+            .is_some()
+    }
+
+    /// Runs an untrusted packet through the full receive path, as if it had
+    /// been received from the IO layer at `now`.
+    ///
+    /// This parses the packet header, processes the acknowledgements in it,
+    /// and reads and reassembles its fragments into [`TransportRecv::msgs`] -
+    /// the same path which [`TransportSet::Poll`] runs for every packet in
+    /// [`Session::recv`], using the default [`TransportConfig`]. It is intended
+    /// for fuzz targets and tests which want to feed arbitrary bytes into the
+    /// transport.
+    ///
+    /// This never panics, no matter how malformed `packet` is. If the packet
+    /// header can't be read, this returns an error and the packet is ignored.
+    /// Invalid fragments after a valid header are dropped individually, like
+    /// in [`TransportSet::Poll`], without failing the whole packet.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the packet header can't be read.
+    pub fn recv_fuzz_input(&mut self, packet: &[u8], now: Instant) -> Result<(), RecvError> {
+        recv::recv_on(self, &TransportConfig::default(), now, packet)
+    }
+
+    /// Requests an immediate round-trip time sample from the peer.
+    ///
+    /// [`Transport::rtt`] is estimated from the acknowledgements of packets
+    /// carrying messages, so it may be out of date if little traffic is being
+    /// sent. A ping instead sends a dedicated probe packet in the next
+    /// [`TransportSet::Flush`], which the peer acknowledges like any other
+    /// packet. Once that acknowledgement arrives, a [`Pong`] carrying the
+    /// returned token and the measured RTT is made available in
+    /// [`TransportRecv::pongs`]. This sample also feeds into
+    /// [`Transport::rtt`].
+    ///
+    /// The probe does not use any lane, and is not resent if it is lost - if
+    /// the probe or its acknowledgement is lost, no [`Pong`] is ever received
+    /// for this token.
+    ///
+    /// `now` is the instant at which the ping was requested, which is given
+    /// back in [`Pong::pinged_at`]. The RTT itself is measured from when the
+    /// probe is actually flushed.
+    ///
+    /// [`Pong`]: ping::Pong
+    /// [`Pong::pinged_at`]: ping::Pong::pinged_at
+    /// [`TransportRecv::pongs`]: recv::TransportRecv::pongs
+    pub fn ping(&mut self, now: Instant) -> ping::PingToken {
+        self.pings.queue(now)
+    }
+
+    /// Gets the maximum length in bytes of a single message that can be
+    /// enqueued with [`TransportSend::push`].
+    ///
+    /// Messages are split into fragments of at most the [`Session::min_mtu`]
+    /// (minus some overhead), and a message can be split into at most
+    /// [`frag::MAX_FRAGS`] fragments. Pushing a message longer than this
+    /// returns [`PushError::TooLarge`].
+    ///
+    /// This is a hard ceiling, but a message under this size may still be too
+    /// large in practice. While a message is being sent, it must fit in our
+    /// [`TransportConfig::max_memory_usage`] alongside everything else we are
+    /// sending, and while it is being reassembled, it must fit in the peer's
+    /// memory limit.
+    ///
+    /// [`PushError::TooLarge`]: send::PushError::TooLarge
+    #[must_use]
+    pub const fn max_message_size(&self) -> usize {
+        self.send.max_msg_len()
+    }
+
+    /// Gets how long the oldest packet which we have flushed out, but which the
+    /// peer has not acknowledged yet, has been waiting for an acknowledgement.
+    ///
+    /// This is [`None`] if there are no unacknowledged packets.
+    ///
+    /// Since we flush at least one packet on every update, and the peer
+    /// acknowledges packets as soon as it receives them, this should stay
+    /// around the [RTT] on a healthy connection. If this keeps growing, the
+    /// peer has likely gone silent, and you may want to raise an alarm or
+    /// disconnect the peer before the IO layer times out.
+    ///
+    /// [RTT]: Transport::rtt
+    #[must_use]
+    pub fn oldest_unacked_age(&self, now: Instant) -> Option<Duration> {
+        self.flushed_packets
+            .iter()
+            .map(|(_, packet)| packet.flushed_at)
+            .min()
+            .map(|flushed_at| now.saturating_duration_since(flushed_at))
+    }
+
+    /// Gets how many more bytes of packets can be flushed out to the IO layer
+    /// before [`TransportConfig::send_bytes_per_sec`] starts holding back
+    /// sends.
+    ///
+    /// The budget is refilled continuously over time, up to
+    /// [`TransportConfig::send_bytes_per_sec`]. If messages are not being sent
+    /// out, and this is close to 0, then the send rate limit is what is
+    /// throttling them.
+    ///
+    /// This is a shorthand for [`TransportSend::bytes_bucket`]'s
+    /// [`TokenBucket::rem`].
+    ///
+    /// [`TokenBucket::rem`]: limit::TokenBucket::rem
+    #[must_use]
+    pub const fn send_budget_remaining(&self) -> usize {
+        self.send.bytes_bucket.rem()
+    }
+
+    /// Gets why the last flush of this transport in [`TransportSet::Flush`]
+    /// stopped sending packets.
+    ///
+    /// If messages are queued, but are not being sent out, this tells you what
+    /// is holding them back.
+    #[must_use]
+    pub const fn last_flush_outcome(&self) -> send::FlushOutcome {
+        self.send.last_flush_outcome
+    }
+
+    /// Gets the MTU of the peer's [`Session`], as of the last packet received
+    /// from the peer.
+    ///
+    /// This is [`None`] until the first packet from the peer is received. Each
+    /// side sends its current MTU in its packet headers until the other side
+    /// has received it, and again whenever it changes, so this is kept up to
+    /// date automatically. Both sides of a connection may have different MTUs -
+    /// if the asymmetry is large (see [`MTU_ASYMMETRY_WARN_RATIO`]), a warning
+    /// is logged, since one side may end up fragmenting messages much more
+    /// heavily than the other.
+    #[must_use]
+    pub const fn peer_mtu(&self) -> Option<usize> {
+        self.peer_mtu
+    }
+
+    /// Gets the [`PROTOCOL_VERSION`] that the peer speaks, as of the last
+    /// packet received from the peer.
+    ///
+    /// This is [`None`] until the first packet from the peer is received. If
+    /// this is different to our own [`PROTOCOL_VERSION`], packets from the
+    /// peer are dropped with [`RecvError::ProtocolMismatch`]. Once
+    /// [`MAX_PROTOCOL_MISMATCHES`] packets in a row have been sent with the
+    /// same different version, the session is disconnected in
+    /// [`TransportSet::Poll`] after triggering [`ProtocolVersionMismatch`].
+    ///
+    /// [`PROTOCOL_VERSION`]: packet::PROTOCOL_VERSION
+    /// [`MAX_PROTOCOL_MISMATCHES`]: recv::MAX_PROTOCOL_MISMATCHES
+    #[must_use]
+    pub const fn peer_protocol_version(&self) -> Option<u32> {
+        self.peer_protocol_version
+    }
+
+    /// Gets if we have been unable to place the sequence numbers of what the
+    /// peer sends for long enough that the session can't recover.
+    ///
+    /// See [`recv::MAX_SEQ_DESYNCS`].
+    fn is_seq_desynced(&self) -> bool {
+        self.seq_desyncs >= recv::MAX_SEQ_DESYNCS
+            || self
+                .recv
+                .lanes
+                .iter()
+                .any(|lane| lane.seq_desyncs >= recv::MAX_SEQ_DESYNCS)
+    }
+
+    /// Gets the [`TransportConfig::packet_lost_threshold_factor`] which is
+    /// currently used to declare packets lost.
+    ///
+    /// If [`TransportConfig::adaptive_loss_threshold`] is enabled, this may
+    /// differ from the configured value.
+    #[must_use]
+    pub fn packet_lost_threshold_factor(&self, config: &TransportConfig) -> f64 {
+        self.adapted_lost_threshold_factor
+            .filter(|_| config.adaptive_loss_threshold)
+            .unwrap_or(config.packet_lost_threshold_factor)
+    }
+
+    /// Gets a rough estimate of how far the peer's wall clock is from ours.
+    ///
+    /// This is [`None`] until the peer has shared its clock with us, which it
+    /// only does if it has [`TransportConfig::clock_sync_interval`] set, and
+    /// until we have measured the RTT to the peer. Use
+    /// [`Transport::peer_clock_ahead`] to find out which direction the offset
+    /// is in.
+    ///
+    /// The peer includes its wall clock time in some of its packets. When we
+    /// receive one, we assume that it took half of the RTT to reach us, and
+    /// compare the peer's time plus that delay to our own time. This is
+    /// smoothed over multiple samples.
+    ///
+    /// # Accuracy
+    ///
+    /// This is only a rough estimate, suitable for e.g. lag compensation or
+    /// interpolation, but not for anything which needs precise time:
+    /// - if the path to the peer is slower in one direction than the other, the
+    ///   estimate is off by up to half of the difference - this is a
+    ///   fundamental limit of any estimate based on the RTT
+    /// - the time between the IO layer receiving a packet and the transport
+    ///   processing it, and between flushing a packet and the IO layer sending
+    ///   it, is counted as part of the latency, which adds up to a few update
+    ///   ticks of error
+    /// - if either side's wall clock is adjusted (e.g. by NTP), the estimate
+    ///   takes a few samples to catch up
+    #[must_use]
+    pub fn peer_clock_offset(&self) -> Option<Duration> {
+        self.clock
+            .offset_micros()
+            .map(|offset| Duration::from_micros(offset.unsigned_abs()))
+    }
+
+    /// Gets whether the peer's wall clock is ahead of ours, rather than
+    /// behind it.
+    ///
+    /// See [`Transport::peer_clock_offset`].
+    #[must_use]
+    pub fn peer_clock_ahead(&self) -> Option<bool> {
+        self.clock.offset_micros().map(|offset| offset > 0)
+    }
+
+    /// Gets the number of lanes which this transport can send messages on.
+    ///
+    /// This is the number of send lanes passed to [`Transport::new`], and is
+    /// a shorthand for the length of [`TransportSend::lanes`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use {
+    /// #     aeronet_io::{Session, packet::IP_MTU},
+    /// #     aeronet_transport::{Transport, lane::LaneKind},
+    /// #     web_time::Instant,
+    /// # };
+    /// # let now = Instant::now();
+    /// # let session = Session::new(now, IP_MTU);
+    /// let recv_lanes = [LaneKind::ReliableOrdered];
+    /// let send_lanes = [LaneKind::ReliableOrdered, LaneKind::UnreliableUnordered];
+    /// let transport = Transport::new(&session, recv_lanes, send_lanes, now).unwrap();
+    ///
+    /// assert_eq!(2, transport.num_send_lanes());
+    /// assert_eq!(1, transport.num_recv_lanes());
+    /// ```
+    #[must_use]
+    pub const fn num_send_lanes(&self) -> usize {
+        self.send.lanes().len()
+    }
+
+    /// Gets the number of lanes which this transport can receive messages on.
+    ///
+    /// This is the number of receive lanes passed to [`Transport::new`], and
+    /// is a shorthand for the length of [`TransportRecv::lanes`].
+    ///
+    /// See [`Transport::num_send_lanes`] for an example.
+    #[must_use]
+    pub const fn num_recv_lanes(&self) -> usize {
+        self.recv.lanes().len()
+    }
+
+    /// Gets the lanes which the peer declared that it will send messages on.
+    ///
+    /// This is [`None`] until the peer's lane configuration has been received
+    /// and set via [`Transport::set_peer_lanes`]. The transport itself does not
+    /// exchange lane configurations with the peer - this must be done by
+    /// whatever is responsible for negotiating the connection, e.g. an
+    /// app-level handshake.
+    ///
+    /// You can compare this against our own [`TransportRecv::lanes`] to find
+    /// exactly where the client and server lane configurations diverge. Once
+    /// the peer lanes are set, this is done automatically in
+    /// [`TransportSet::Poll`] - see [`Transport::check_peer_lanes`].
+    #[must_use]
+    pub fn peer_lanes(&self) -> Option<&[LaneKind]> {
+        self.peer_lanes.as_deref()
+    }
+
+    /// Sets the lanes which the peer declared that it will send messages on.
+    ///
+    /// See [`Transport::peer_lanes`].
+    pub fn set_peer_lanes(&mut self, lanes: impl IntoIterator<Item = impl Into<LaneKind>>) {
+        self.peer_lanes = Some(lanes.into_iter().map(Into::into).collect());
+        self.peer_lanes_checked = false;
+    }
+
+    /// Checks that the lanes which the peer sends on pair up with the lanes
+    /// that we receive on.
+    ///
+    /// If the [`Transport::peer_lanes`] are not known yet, this always
+    /// succeeds.
+    ///
+    /// After the peer lanes are set, this is checked once in
+    /// [`TransportSet::Poll`]. If the lanes do not pair up, the session is
+    /// disconnected, using the [`LanePairingError`] as the reason.
+    ///
+    /// # Errors
+    ///
+    /// See [`lane::check_lane_pairing`].
+    ///
+    /// [`LanePairingError`]: lane::LanePairingError
+    pub fn check_peer_lanes(&self) -> Result<(), lane::LanePairingError> {
+        let Some(peer_lanes) = &self.peer_lanes else {
+            return Ok(());
+        };
+        let our_lanes = self
+            .recv
+            .lanes()
+            .iter()
+            .map(recv::RecvLane::kind)
+            .collect::<Vec<_>>();
+        lane::check_lane_pairing(peer_lanes, &our_lanes)
+    }
+
+    /// Resets the message ordering of the lane at index `lane`, on both the
+    /// sending and receiving halves of this transport.
+    ///
+    /// On the receiving side, all messages on this lane which are buffered
+    /// waiting for an earlier message to arrive are dropped, and the lane
+    /// starts expecting messages from the first sequence number again. On
+    /// the sending side, all messages queued on this lane are dropped (even
+    /// if they have not been acknowledged yet), and new messages are sent
+    /// starting from the first sequence number again.
+    ///
+    /// This is useful after a logical "scene change", where new messages should
+    /// not be blocked behind messages sent before the change which may never
+    /// arrive.
+    ///
+    /// If only one half of this transport has a lane at index `lane`, only
+    /// that half is reset.
+    ///
+    /// # Coordination
+    ///
+    /// Both peers **must** reset the same lane at the same logical point in the
+    /// message stream, since both sides' sequence numbers restart from the
+    /// beginning. The transport does not coordinate this for you. For example,
+    /// you may send a "scene change" message on a separate reliable lane, reset
+    /// the lane right after sending it, and have the peer reset the lane as
+    /// soon as it receives it.
+    ///
+    /// If the peer still has messages in flight which were sent before it reset
+    /// its lane, they may be received as if they were sent after the reset.
+    pub fn reset_lane_ordering(&mut self, lane: LaneIndex) {
+        let lane_index = usize::from(lane);
+        if let Some(recv_lane) = self.recv.lanes.get_mut(lane_index) {
+            recv_lane.reset();
+        }
+
+        if let Some(send_lane) = self.send.lanes.get_mut(lane_index) {
+            send_lane.reset(lane, &mut self.recv.contexts.0, &mut self.recv.deliveries.0);
+            // packets which are still in flight may reference messages sent
+            // before the reset, which would share sequence numbers with
+            // messages sent after the reset - forget about those fragments so
+            // that their acks aren't applied to the new messages
+            for (_, packet) in self.flushed_packets.iter_mut() {
+                if packet.frags.iter().any(|path| path.lane_index == lane) {
+                    packet.frags = packet
+                        .frags
+                        .iter()
+                        .filter(|path| path.lane_index != lane)
+                        .copied()
+                        .collect();
+                }
+            }
+        }
+    }
+
+    /// Enqueues a batch of messages, potentially on different lanes, so that
+    /// either all of them are enqueued, or none of them are.
+    ///
+    /// This is useful for composite updates, where enqueuing only some of the
+    /// messages would leave the peer with a partial update. Every message is
+    /// validated up front, and only once all of them would be enqueued
+    /// successfully are they actually enqueued, in order, with
+    /// [`TransportSend::push`]. All messages enqueued by a single call will be
+    /// sent out in the same flush, as long as there are enough bytes left in
+    /// [`TransportConfig::send_bytes_per_sec`].
+    ///
+    /// On success, returns the [`MessageKey`] of each message, in the same
+    /// order as `msgs`.
+    ///
+    /// # Errors
+    ///
+    /// Errors if any message in the batch could not be enqueued, in which case
+    /// none of the messages are enqueued. Unlike [`TransportSend::push`], this
+    /// never causes the transport to be disconnected.
+    #[expect(clippy::missing_panics_doc, reason = "shouldn't panic")]
+    pub fn send_batch(
+        &mut self,
+        msgs: &[(LaneIndex, Bytes)],
+        now: Instant,
+    ) -> Result<Vec<MessageKey>, SendError> {
+        let max_msg_len = self.send.max_msg_len();
+        let mut num_queued = vec![0usize; self.send.lanes.len()];
+        for (index, (lane, msg)) in msgs.iter().enumerate() {
+            if msg.len() > max_msg_len {
+                return Err(SendError::TooLarge {
+                    index,
+                    len: msg.len(),
+                    max: max_msg_len,
+                });
+            }
+
+            let lane = *lane;
+            let lane_index = usize::from(lane);
+            let send_lane = self
+                .send
+                .lanes
+                .get(lane_index)
+                .ok_or(SendError::InvalidLane { index, lane })?;
+            let num_queued = &mut num_queued[lane_index];
+
+            if let Some(threshold) = send_lane.backpressure_threshold() {
+                if send_lane.sent_msgs.len() + *num_queued >= threshold {
+                    return Err(SendError::Backpressured {
+                        index,
+                        lane,
+                        threshold,
+                    });
+                }
+            }
+
+            if let Some((_, max)) = send_lane.full_in_flight_window() {
+                return Err(SendError::InFlightLimit { index, lane, max });
+            }
+
+            let offset = u16::try_from(*num_queued)
+                .map_err(|_| SendError::TooManyMessages { index, lane })?;
+            let msg_seq = send_lane.next_msg_seq + MessageSeq::new(offset);
+            if send_lane.sent_msgs.contains_key(&msg_seq) {
+                return Err(SendError::TooManyMessages { index, lane });
+            }
+
+            *num_queued += 1;
+        }
+
+        Ok(msgs
+            .iter()
+            .map(|(lane, msg)| {
+                self.send
+                    .push(*lane, msg.clone(), now)
+                    .expect("batch was validated, so push should succeed")
+            })
+            .collect())
+    }
+
+    /// Cancels sending a message which was previously enqueued with
+    /// [`TransportSend::push`], before it has been fully acknowledged.
+    ///
+    /// Any fragments of this message which have not been flushed yet are never
+    /// sent, and fragments which were already flushed are no longer resent.
+    /// The buffers used by the message are freed immediately. If the message
+    /// had a context attached via [`TransportSend::push_with_context`], it is
+    /// given back with [`MessageOutcome::Dropped`]. If the message was tracked
+    /// via [`Transport::track_delivery`], it is reported as [`Delivery::Lost`].
+    ///
+    /// Returns `true` if the message was still being tracked and is now
+    /// canceled, or `false` if there is no such message - it may have already
+    /// been fully acknowledged, or dropped after being flushed on an unreliable
+    /// lane.
+    ///
+    /// # Ordering
+    ///
+    /// On unreliable lanes, this is always safe, since the peer already has to
+    /// cope with messages which never arrive.
+    ///
+    /// On reliable lanes, canceling a message creates a gap in the message
+    /// sequence which is never filled. On a [`LaneKind::ReliableOrdered`] lane,
+    /// the peer will hold back every message sent after the canceled one,
+    /// waiting for a message which will never arrive. The transport does not
+    /// tell the peer to skip over the gap - you are responsible for this, e.g.
+    /// by having both sides [reset the lane's ordering] afterwards.
+    ///
+    /// [reset the lane's ordering]: Transport::reset_lane_ordering
+    pub fn cancel(&mut self, key: MessageKey) -> bool {
+        let Some(send_lane) = self.send.lanes.get_mut(usize::from(key.lane)) else {
+            return false;
+        };
+        let Some(msg) = send_lane.sent_msgs.remove(&key.seq) else {
+            return false;
+        };
+
+        if let Some(context) = msg.context {
+            self.recv.contexts.0.push(MessageContext::new(
+                key.lane,
+                key.seq,
+                context,
+                MessageOutcome::Dropped,
+            ));
+        }
+        if msg.track_delivery {
+            self.recv.deliveries.0.push(DeliveryReport {
+                key,
+                delivery: Delivery::Lost,
+            });
+        }
+
+        // forget about fragments of this message in packets which are still in
+        // flight, so that a later message with the same key isn't acked by them
+        for (_, packet) in self.flushed_packets.iter_mut() {
+            let is_canceled =
+                |path: &FragmentPath| path.lane_index == key.lane && path.msg_seq == key.seq;
+            if packet.frags.iter().any(is_canceled) {
+                packet.frags = packet
+                    .frags
+                    .iter()
+                    .filter(|path| !is_canceled(path))
+                    .copied()
+                    .collect();
+            }
+        }
+        true
+    }
+
+    /// Starts tracking whether a message previously enqueued with
+    /// [`TransportSend::push`] is delivered to the peer.
+    ///
+    /// Once the transport knows what happened to the message, a
+    /// [`DeliveryReport`] for it is made available in
+    /// [`TransportRecv::deliveries`]. Each tracked message is reported exactly
+    /// once, as either:
+    /// - [`Delivery::Delivered`] once the peer acknowledges it
+    /// - [`Delivery::Lost`] once the transport knows it was not delivered
+    /// - [`Delivery::Unknown`] if the transport stops tracking it without
+    ///   finding out (e.g. on an unreliable lane without [receipts])
+    ///
+    /// You should call this right after pushing the message. If `key` does not
+    /// refer to a message which is still being sent - e.g. it was already
+    /// acknowledged or dropped - it is reported as [`Delivery::Unknown`]
+    /// straight away.
+    ///
+    /// Unlike [`TransportRecv::acks`], this also tells you about messages which
+    /// were not delivered, which is useful for diagnostics.
+    ///
+    /// [receipts]: TransportSend::set_unreliable_receipts
+    pub fn track_delivery(&mut self, key: MessageKey) {
+        let msg = self
+            .send
+            .lanes
+            .get_mut(usize::from(key.lane))
+            .and_then(|lane| lane.sent_msgs.get_mut(&key.seq))
+            .filter(|msg| msg.frags.iter().any(Option::is_some));
+        if let Some(msg) = msg {
+            msg.track_delivery = true;
+        } else {
+            self.recv.deliveries.0.push(DeliveryReport {
+                key,
+                delivery: Delivery::Unknown,
+            });
+        }
+    }
+
+    /// Enqueues a message to be sent, and returns a [`Future`] which resolves
+    /// once the transport knows what happened to it.
+    ///
+    /// This is a convenience over [`TransportSend::push`] and
+    /// [`Transport::track_delivery`], for scripts and tests which want to wait
+    /// for a specific message to arrive before doing something else. The future
+    /// resolves with:
+    /// - the message's [`Delivery`], once it is known - see
+    ///   [`Transport::track_delivery`] for when each one is reported
+    /// - [`DeliveryOutcome::Closed`] if this transport is dropped first, e.g.
+    ///   because its session was disconnected
+    ///
+    /// The [`DeliveryReport`] for this message is consumed by the future, so it
+    /// is never given to you in [`TransportRecv::deliveries`].
+    ///
+    /// The future does not depend on any async runtime, but it is only woken
+    /// from this transport's systems in [`TransportSet::Poll`] and
+    /// [`TransportSet::Flush`], so the app must keep updating for it to make
+    /// progress.
+    ///
+    /// This is intended for low-volume use. Every awaited message needs its
+    /// own allocation and lock, and waking a task per message is expensive
+    /// compared to draining [`TransportRecv::acks`] once per update. If you
+    /// are sending many messages, use [`TransportRecv::acks`] or
+    /// [`Transport::track_delivery`] instead.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the message could not be enqueued. See
+    /// [`TransportSend::push`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `lane_index` is outside the range of send lanes configured
+    /// on this transport when it was created.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use aeronet_transport::{Transport, awaitable::DeliveryOutcome, lane::LaneIndex};
+    ///
+    /// async fn send_and_wait(transport: &mut Transport) {
+    ///     let delivery = transport
+    ///         .send_awaitable(
+    ///             LaneIndex(0),
+    ///             b"hello world".to_vec().into(),
+    ///             aeronet_io::now(),
+    ///         )
+    ///         .unwrap();
+    ///
+    ///     // keep updating the app while we wait..
+    ///
+    ///     assert_eq!(DeliveryOutcome::Delivered, delivery.await);
+    /// }
+    /// ```
+    ///
+    /// [`DeliveryOutcome::Closed`]: awaitable::DeliveryOutcome::Closed
+    pub fn send_awaitable(
+        &mut self,
+        lane_index: LaneIndex,
+        msg: Bytes,
+        now: Instant,
+    ) -> Result<awaitable::DeliveryFuture, PushError> {
+        let key = self.send.push(lane_index, msg, now)?;
+        self.track_delivery(key);
+        Ok(self.awaiting.insert(key))
+    }
+
+    /// Gets what fraction of a reliable message's fragments the peer has
+    /// acknowledged so far, from 0.0 to 1.0.
+    ///
+    /// This is useful for showing the progress of a large transfer - unlike
+    /// the number of fragments flushed, this only increases once the peer has
+    /// actually received a fragment.
+    ///
+    /// Returns [`None`] if `key` is not a message on a reliable lane which is
+    /// still being tracked. Once a message is fully acknowledged, this returns
+    /// 1.0 until the next [`TransportSet::Flush`], after which the transport
+    /// forgets about the message and this returns [`None`] - use
+    /// [`TransportRecv::acks`] to find out exactly when it was acknowledged.
+    #[must_use]
+    pub fn message_ack_progress(&self, key: MessageKey) -> Option<f32> {
+        let lane = self.send.lanes.get(usize::from(key.lane))?;
+        if lane.kind().reliability() != LaneReliability::Reliable {
+            return None;
+        }
+        let msg = lane.sent_msgs.get(&key.seq)?;
+
+        // acked frags are taken out of their slots
+        let num_acked = msg.frags.iter().filter(|frag| frag.is_none()).count();
+        #[expect(clippy::cast_precision_loss, reason = "precision loss is acceptable")]
+        Some(num_acked as f32 / msg.frags.len() as f32)
+    }
+
     /// Gets how many total bytes of memory this transport is using.
     ///
     /// This call is potentially expensive. You should cache this where
     /// possible.
     #[must_use]
     pub fn memory_used(&self) -> usize {
-        self.get_size()
+        let retained_msgs_len = self
+            .send
+            .lanes
+            .iter()
+            .flat_map(|lane| lane.sent_msgs.values())
+            .map(SentMessage::retained_len)
+            .sum::<usize>();
+        self.get_size() + retained_msgs_len
+    }
+
+    /// Gets the highest [`Transport::memory_used`] seen over the lifetime of
+    /// this transport.
+    ///
+    /// Use this for capacity planning, e.g. to size
+    /// [`TransportConfig::max_memory_usage`] based on how much memory sessions
+    /// actually need during bursts of messages.
+    ///
+    /// Memory usage is sampled once per update, in [`TransportSet::Poll`],
+    /// after received packets have been read into messages. Memory which is
+    /// only used in between two samples is not reflected here.
+    #[must_use]
+    pub const fn peak_memory_used(&self) -> usize {
+        self.peak_memory_used
+    }
+
+    /// Gets if this transport is currently under memory pressure.
+    ///
+    /// See [`TransportConfig::soft_memory_limit`].
+    #[must_use]
+    pub const fn is_memory_pressure_high(&self) -> bool {
+        self.memory_pressure_high
     }
 }
 
 /// Set for scheduling transport layer systems.
+///
+/// In debug builds, a warning is logged if the IO layer pushes received
+/// packets after [`TransportSet::Poll`], or does not send out the packets
+/// flushed in [`TransportSet::Flush`] by the end of [`IoSet::Flush`]. This
+/// usually means that the IO layer's systems are not in the right [`IoSet`].
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, SystemSet)]
 pub enum TransportSet {
     /// Draining packets from the IO layer and reading out messages; updating
@@ -339,9 +1743,44 @@ pub struct MessageStats {
     pub msgs_sent: Saturating<usize>,
     /// Number of packet acknowledgements received.
     pub packet_acks_recv: Saturating<usize>,
+    /// Number of packets which were declared lost, since the peer did not
+    /// acknowledge them in time.
+    ///
+    /// See [`TransportConfig::packet_lost_threshold_factor`].
+    pub packets_lost: Saturating<usize>,
+    /// Number of packets which were declared lost, but which the peer
+    /// acknowledged afterwards anyway.
+    ///
+    /// These packets are also counted in [`MessageStats::packets_lost`] and
+    /// [`MessageStats::packet_acks_recv`].
+    pub spurious_losses: Saturating<usize>,
     /// Number of message acknowledgements received into
     /// [`TransportRecv::acks`].
     pub msg_acks_recv: Saturating<usize>,
+    /// Number of packet acknowledgements received for packets which we have
+    /// not sent yet.
+    ///
+    /// A well-behaved peer never sends these, so if this is non-zero, the peer
+    /// is either buggy or malicious. These acknowledgements are ignored, and
+    /// are not counted in [`MessageStats::packet_acks_recv`].
+    pub unknown_packet_acks_recv: Saturating<usize>,
+}
+
+/// Total CPU time that a [`Transport`] has spent processing messages.
+///
+/// This measures wall-clock time around the processing, so it also includes
+/// any time that the thread was preempted for.
+///
+/// See [`Transport::cpu_time`].
+#[cfg(feature = "cpu-time")]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, TypeSize)]
+pub struct CpuTime {
+    /// Time spent splitting messages into fragments and writing them into
+    /// packets, in [`TransportSet::Flush`].
+    pub fragment: Duration,
+    /// Time spent reading fragments out of packets and reassembling them into
+    /// messages, in [`TransportSet::Poll`].
+    pub reassemble: Duration,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, TypeSize)]
@@ -354,6 +1793,8 @@ struct FragmentPath {
 #[derive(Debug, Clone, TypeSize)]
 struct FlushedPacket {
     flushed_at: Instant,
+    lost: bool,
+    loss_confirmed: bool,
     frags: Box<[FragmentPath]>,
 }
 
@@ -361,21 +1802,970 @@ impl FlushedPacket {
     fn new(flushed_at: Instant) -> Self {
         Self {
             flushed_at,
+            lost: false,
+            loss_confirmed: false,
             frags: Box::new([]),
         }
     }
 }
 
+std::thread_local! {
+    /// Set when [`implicit_config`] creates a [`TransportConfig`] for a
+    /// [`Transport`] which was added without one, and taken by
+    /// [`check_implicit_config`] as part of the same insertion.
+    ///
+    /// Hooks can't tell if a required component was inserted explicitly or
+    /// not, and the required component constructor doesn't have access to the
+    /// world, so this is how the constructor tells the hook.
+    static IMPLICIT_CONFIG: Cell<bool> = const { Cell::new(false) };
+}
+
+fn implicit_config() -> TransportConfig {
+    IMPLICIT_CONFIG.set(true);
+    TransportConfig::default()
+}
+
+fn check_implicit_config(mut world: DeferredWorld, entity: Entity, _: ComponentId) {
+    if !IMPLICIT_CONFIG.take() || !world.contains_resource::<ExplicitTransportConfig>() {
+        return;
+    }
+
+    error!(
+        "{entity} added a `Transport` without a `TransportConfig`, but `ExplicitTransportConfig` \
+         is set, disconnecting"
+    );
+    world
+        .commands()
+        .trigger_targets(Disconnect::new("missing transport config"), entity);
+}
+
+fn check_peer_lanes(mut commands: Commands, mut sessions: Query<(Entity, &mut Transport)>) {
+    for (session, mut transport) in &mut sessions {
+        if transport.peer_lanes.is_none() || transport.peer_lanes_checked {
+            continue;
+        }
+        transport.peer_lanes_checked = true;
+
+        if let Err(err) = transport.check_peer_lanes() {
+            warn!("{session} has mismatched lanes with its peer, disconnecting - {err}");
+            commands.trigger_targets(Disconnect::new(err.to_string()), session);
+        }
+    }
+}
+
+fn check_protocol_version(mut commands: Commands, mut sessions: Query<(Entity, &mut Transport)>) {
+    for (session, mut transport) in &mut sessions {
+        let Some(peer) = transport.peer_protocol_version else {
+            continue;
+        };
+        if peer == packet::PROTOCOL_VERSION
+            || transport.protocol_mismatches < recv::MAX_PROTOCOL_MISMATCHES
+            || transport.protocol_mismatch_checked
+        {
+            continue;
+        }
+        transport.protocol_mismatch_checked = true;
+
+        let ours = packet::PROTOCOL_VERSION;
+        warn!(
+            "{session} speaks a different protocol version to its peer, disconnecting - we speak \
+             {ours}, peer speaks {peer}"
+        );
+        commands.trigger_targets(
+            ProtocolVersionMismatch {
+                entity: session,
+                ours,
+                peer,
+            },
+            session,
+        );
+        commands.trigger_targets(
+            Disconnect::new(format!(
+                "protocol version mismatch - we speak {ours}, peer speaks {peer}"
+            )),
+            session,
+        );
+    }
+}
+
+fn check_seq_desync(mut commands: Commands, mut sessions: Query<(Entity, &mut Transport)>) {
+    for (session, mut transport) in &mut sessions {
+        if transport.seq_desync_checked || !transport.is_seq_desynced() {
+            continue;
+        }
+        transport.seq_desync_checked = true;
+
+        warn!("{session} can't place the sequence numbers of packets from its peer, disconnecting");
+        commands.trigger_targets(
+            Disconnect::new("sequence numbers desynchronized with peer"),
+            session,
+        );
+    }
+}
+
+fn check_unacked_packets(mut sessions: Query<(Entity, &mut Transport)>) {
+    for (session, mut transport) in &mut sessions {
+        if let Some(num_unacked) = transport.take_unacked_packets_warning() {
+            warn!(
+                "{session} has {num_unacked} unacknowledged packets, close to the maximum of \
+                 {MAX_UNACKED_PACKETS} - older packets will start being forgotten, consider \
+                 reducing the send rate"
+            );
+        }
+    }
+}
+
+fn update_unflushed(mut commands: Commands, sessions: Query<(Entity, &Transport, Has<Unflushed>)>) {
+    for (session, transport, was_unflushed) in &sessions {
+        let unflushed = transport
+            .send
+            .lanes()
+            .iter()
+            .any(|lane| lane.num_queued_msgs() > 0);
+        if unflushed && !was_unflushed {
+            commands.entity(session).try_insert(Unflushed);
+        } else if !unflushed && was_unflushed {
+            commands.entity(session).remove::<Unflushed>();
+        }
+    }
+}
+
+/// Gets if a state which switches between two thresholds is now active.
+///
+/// An inactive state becomes active once `value` reaches `high`, and an active
+/// state only becomes inactive once `value` drops to `low` or below. The gap
+/// between the two stops the state from flipping on every update while
+/// `value` hovers around a single threshold.
+pub(crate) const fn hysteresis(active: bool, value: usize, low: usize, high: usize) -> bool {
+    if active { value > low } else { value >= high }
+}
+
 fn check_memory_limit(
     mut commands: Commands,
-    sessions: Query<(Entity, &Transport, &TransportConfig)>,
+    mut sessions: Query<(Entity, &mut Transport, &TransportConfig)>,
+    budget: Option<Res<TransportMemoryBudget>>,
 ) {
-    for (session, transport, config) in &sessions {
+    let mut within_limit = budget.as_ref().map(|_| Vec::new());
+    for (session, mut transport, config) in &mut sessions {
         let mem_used = transport.memory_used();
+        transport.peak_memory_used = transport.peak_memory_used.max(mem_used);
         let mem_max = config.max_memory_usage;
         if mem_used > mem_max {
             warn!("{session} exceeded memory limit, disconnecting - {mem_used} / {mem_max} bytes");
             commands.trigger_targets(Disconnect::new("memory limit exceeded"), session);
+            continue;
+        }
+
+        let pressure_high = config.soft_memory_limit.is_some_and(|limit| {
+            hysteresis(
+                transport.memory_pressure_high,
+                mem_used,
+                limit - limit / 4,
+                limit.saturating_add(1),
+            )
+        });
+        if pressure_high != transport.memory_pressure_high {
+            transport.memory_pressure_high = pressure_high;
+            // a limit which was just unset doesn't count as relieving pressure
+            if config.soft_memory_limit.is_some() {
+                trace!(%session, pressure_high, mem_used, "Memory pressure changed");
+                if pressure_high {
+                    commands.trigger_targets(
+                        MemoryPressureHigh {
+                            memory_used: mem_used,
+                        },
+                        session,
+                    );
+                } else {
+                    commands.trigger_targets(
+                        MemoryPressureNormal {
+                            memory_used: mem_used,
+                        },
+                        session,
+                    );
+                }
+            }
+        }
+
+        if let Some(within_limit) = &mut within_limit {
+            within_limit.push((session, mem_used));
+        }
+    }
+
+    let (Some(budget), Some(within_limit)) = (budget, within_limit) else {
+        return;
+    };
+    let budget_max = budget.max_memory_usage;
+    for (session, mem_used) in budget.sessions_over_budget(within_limit) {
+        warn!(
+            "{session} exceeded transport memory budget, disconnecting - using {mem_used} bytes, \
+             budget is {budget_max} bytes"
+        );
+        commands.trigger_targets(Disconnect::new("memory budget exceeded"), session);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![expect(clippy::unwrap_used, reason = "testing")]
+
+    use {
+        super::*, aeronet_io::packet::IP_MTU, bevy_ecs::system::RunSystemOnce, limit::TokenBucket,
+        octs::Write, recv::RecvLane, send::SendLane,
+    };
+
+    fn lane_kinds(transport: &Transport) -> (Vec<LaneKind>, Vec<LaneKind>) {
+        let recv = transport.recv.lanes().iter().map(RecvLane::kind).collect();
+        let send = transport.send.lanes().iter().map(SendLane::kind).collect();
+        (recv, send)
+    }
+
+    #[test]
+    fn num_lanes() {
+        const RECV_LANES: [LaneKind; 3] = [
+            LaneKind::ReliableOrdered,
+            LaneKind::UnreliableUnordered,
+            LaneKind::UnreliableSequenced,
+        ];
+        const SEND_LANES: [LaneKind; 1] = [LaneKind::ReliableUnordered];
+
+        let now = Instant::now();
+        let session = Session::new(now, IP_MTU);
+        let transport = Transport::new(&session, RECV_LANES, SEND_LANES, now).unwrap();
+        assert_eq!(RECV_LANES.len(), transport.num_recv_lanes());
+        assert_eq!(SEND_LANES.len(), transport.num_send_lanes());
+
+        let transport = Transport::new(&session, [] as [LaneKind; 0], SEND_LANES, now).unwrap();
+        assert_eq!(0, transport.num_recv_lanes());
+        assert_eq!(SEND_LANES.len(), transport.num_send_lanes());
+    }
+
+    #[test]
+    fn oldest_unacked_age() {
+        const LANES: [LaneKind; 1] = [LaneKind::ReliableOrdered];
+
+        let start = Instant::now();
+        let session = Session::new(start, IP_MTU);
+        let mut transport = Transport::new_symmetric(&session, LANES, start).unwrap();
+        transport.send.bytes_bucket = TokenBucket::new(usize::MAX);
+        assert_eq!(None, transport.oldest_unacked_age(start));
+
+        transport
+            .send
+            .push(LaneIndex(0), Bytes::from_static(b"hi"), start)
+            .unwrap();
+        assert_eq!(1, send::flush_on(&mut transport, start, IP_MTU).count());
+        assert_eq!(Some(Duration::ZERO), transport.oldest_unacked_age(start));
+
+        // the peer never acks anything, and we keep flushing
+        let later = start + Duration::from_millis(100);
+        assert_eq!(1, send::flush_on(&mut transport, later, IP_MTU).count());
+        assert_eq!(
+            Some(Duration::from_millis(100)),
+            transport.oldest_unacked_age(later)
+        );
+
+        let even_later = start + Duration::from_millis(500);
+        assert_eq!(
+            Some(Duration::from_millis(500)),
+            transport.oldest_unacked_age(even_later)
+        );
+    }
+
+    #[test]
+    fn unacked_packets_warning() {
+        const LANES: [LaneKind; 1] = [LaneKind::ReliableOrdered];
+
+        let now = Instant::now();
+        let session = Session::new(now, IP_MTU);
+        let mut transport = Transport::new_symmetric(&session, LANES, now).unwrap();
+        transport.send.bytes_bucket = TokenBucket::new(usize::MAX);
+
+        // the peer never acks anything, and we keep flushing keep-alives
+        let mut warnings = Vec::new();
+        for num_flushed in 1..=MAX_UNACKED_PACKETS * 2 {
+            assert_eq!(1, send::flush_on(&mut transport, now, IP_MTU).count());
+            if let Some(num_unacked) = transport.take_unacked_packets_warning() {
+                warnings.push((num_flushed, num_unacked));
+            }
+        }
+
+        // we warn once, while we still remember every packet we've flushed
+        assert_eq!(
+            vec![(
+                UNACKED_PACKETS_WARN_THRESHOLD,
+                UNACKED_PACKETS_WARN_THRESHOLD
+            )],
+            warnings
+        );
+        assert_eq!(MAX_UNACKED_PACKETS, transport.num_unacked_packets());
+    }
+
+    #[test]
+    fn unacked_packets_warning_within_single_flush() {
+        const LANES: [LaneKind; 1] = [LaneKind::ReliableOrdered];
+
+        let mut app = App::new();
+        app.add_plugins((aeronet_io::AeronetIoPlugin, AeronetTransportPlugin));
+
+        let now = Instant::now();
+        let session = Session::new(now, IP_MTU);
+        let mut transport = Transport::new_symmetric(&session, LANES, now).unwrap();
+        // a single message which is split into more packets than the window
+        // can hold, so it wraps the window within a single flush
+        let msg = Bytes::from(vec![
+            0;
+            transport.send.max_frag_len * (MAX_UNACKED_PACKETS + 16)
+        ]);
+        transport.send.push(LaneIndex(0), msg, now).unwrap();
+        let entity = app.world_mut().spawn((session, transport)).id();
+        // no time passes between updates, so make sure the transport can flush
+        app.add_systems(Update, |mut transports: Query<&mut Transport>| {
+            for mut transport in &mut transports {
+                transport.send.bytes_bucket.refill_portion(1.0);
+            }
+        });
+
+        app.update();
+        let transport = app.world().get::<Transport>(entity).unwrap();
+        assert_eq!(MAX_UNACKED_PACKETS, transport.num_unacked_packets());
+        // we warned as soon as we crossed the threshold, not once the window
+        // had already wrapped
+        assert_eq!(
+            Some(UNACKED_PACKETS_WARN_THRESHOLD),
+            transport.unacked_packets_warned_at
+        );
+        assert!(!transport.unacked_packets_warning_pending);
+    }
+
+    #[test]
+    fn symmetric_lanes() {
+        const LANES: [LaneKind; 2] = [LaneKind::ReliableOrdered, LaneKind::UnreliableUnordered];
+
+        let now = Instant::now();
+        let session = Session::new(now, IP_MTU);
+        let transport = Transport::new_symmetric(&session, LANES, now).unwrap();
+        assert_eq!((LANES.to_vec(), LANES.to_vec()), lane_kinds(&transport));
+    }
+
+    #[test]
+    fn asymmetric_lanes() {
+        // the server also broadcasts on a lane which the client never sends on
+        const RECV_LANES: [LaneKind; 1] = [LaneKind::ReliableOrdered];
+        const SEND_LANES: [LaneKind; 2] = [LaneKind::ReliableOrdered, LaneKind::UnreliableLatest];
+
+        let now = Instant::now();
+        let session = Session::new(now, IP_MTU);
+        let transport = Transport::new(&session, RECV_LANES, SEND_LANES, now).unwrap();
+        assert_eq!(
+            (RECV_LANES.to_vec(), SEND_LANES.to_vec()),
+            lane_kinds(&transport)
+        );
+        assert!(!likely_accidental_asymmetry(&RECV_LANES, &SEND_LANES));
+    }
+
+    #[test]
+    fn accidental_asymmetry() {
+        // lanes were swapped on one side
+        let recv_lanes = [LaneKind::ReliableOrdered, LaneKind::UnreliableUnordered];
+        let send_lanes = [LaneKind::UnreliableUnordered, LaneKind::ReliableOrdered];
+        assert!(likely_accidental_asymmetry(&recv_lanes, &send_lanes));
+        assert!(!likely_accidental_asymmetry(&recv_lanes, &recv_lanes));
+        // same number of lanes, but different kinds
+        let send_lanes = [LaneKind::ReliableOrdered, LaneKind::ReliableOrdered];
+        assert!(!likely_accidental_asymmetry(&recv_lanes, &send_lanes));
+    }
+
+    #[test]
+    fn peer_lanes_mismatch() {
+        const OUR_LANES: [LaneKind; 2] = [LaneKind::ReliableOrdered, LaneKind::UnreliableUnordered];
+        const PEER_LANES: [LaneKind; 1] = [LaneKind::ReliableOrdered];
+
+        let now = Instant::now();
+        let session = Session::new(now, IP_MTU);
+        let mut transport = Transport::new(&session, OUR_LANES, OUR_LANES, now).unwrap();
+        assert_eq!(None, transport.peer_lanes());
+
+        transport.set_peer_lanes(PEER_LANES);
+        let peer_lanes = transport.peer_lanes().unwrap();
+        assert_eq!(&PEER_LANES, peer_lanes);
+
+        let our_lanes = transport
+            .recv
+            .lanes()
+            .iter()
+            .map(RecvLane::kind)
+            .collect::<Vec<_>>();
+        assert_ne!(our_lanes, peer_lanes);
+    }
+
+    #[test]
+    fn peer_lanes_reliability_mismatch() {
+        #[derive(Default, Resource)]
+        struct Disconnected(Vec<String>);
+
+        const OUR_LANES: [LaneKind; 2] = [LaneKind::ReliableOrdered, LaneKind::UnreliableUnordered];
+        // peer expects lane 1 to be reliable
+        const PEER_LANES: [LaneKind; 2] = [LaneKind::ReliableOrdered, LaneKind::ReliableOrdered];
+
+        let now = Instant::now();
+        let session = Session::new(now, IP_MTU);
+        let mut world = World::new();
+        world.init_resource::<Disconnected>();
+        world.add_observer(
+            |trigger: Trigger<Disconnect>, mut dc: ResMut<Disconnected>| {
+                dc.0.push(trigger.event().reason.clone());
+            },
+        );
+
+        let mut transport = Transport::new(&session, OUR_LANES, OUR_LANES, now).unwrap();
+        assert_eq!(Ok(()), transport.check_peer_lanes());
+        transport.set_peer_lanes(PEER_LANES);
+        assert_eq!(
+            Err(lane::LanePairingError {
+                mismatches: vec![lane::LaneMismatch {
+                    lane: LaneIndex(1),
+                    peer_send: LaneKind::ReliableOrdered,
+                    our_recv: Some(LaneKind::UnreliableUnordered),
+                }],
+            }),
+            transport.check_peer_lanes()
+        );
+        world.spawn(transport);
+
+        // only checked once
+        for _ in 0..2 {
+            world.run_system_once(check_peer_lanes).unwrap();
+            world.flush();
+        }
+        assert_eq!(
+            vec![
+                "lane pairing mismatch - lane 1: peer sends ReliableOrdered, but we receive \
+                 UnreliableUnordered"
+                    .to_owned()
+            ],
+            world.resource::<Disconnected>().0
+        );
+    }
+
+    #[test]
+    fn disconnect_on_seq_desync() {
+        #[derive(Default, Resource)]
+        struct Disconnects(Vec<String>);
+
+        const LANES: [LaneKind; 1] = [LaneKind::ReliableOrdered];
+
+        fn header_packet(seq: u16) -> Vec<u8> {
+            let mut packet = Vec::new();
+            packet
+                .write(PacketHeader {
+                    seq: packet::PacketSeq::new(seq),
+                    ..Default::default()
+                })
+                .unwrap();
+            packet
+        }
+
+        let now = Instant::now();
+        let session = Session::new(now, IP_MTU);
+        let mut world = World::new();
+        world.init_resource::<Disconnects>();
+        world.add_observer(
+            |trigger: Trigger<Disconnect>, mut disconnects: ResMut<Disconnects>| {
+                disconnects.0.push(trigger.event().reason.clone());
+            },
+        );
+
+        let mut transport = Transport::new(&session, LANES, LANES, now).unwrap();
+        transport.recv_fuzz_input(&header_packet(0), now).unwrap();
+
+        // the next `MAX_SEQ_GAP + 1` packets are lost, so we can't place any
+        // packets after them
+        let first_seq = recv::MAX_SEQ_GAP + 2;
+        let num_desyncs = u16::try_from(recv::MAX_SEQ_DESYNCS).unwrap();
+        for seq in first_seq..first_seq + num_desyncs - 1 {
+            assert!(matches!(
+                transport.recv_fuzz_input(&header_packet(seq), now),
+                Err(RecvError::SequenceDesync { lane: None, .. })
+            ));
+        }
+
+        // a few packets we can't place aren't enough to give up on the session
+        let entity = world.spawn(transport).id();
+        world.run_system_once(check_seq_desync).unwrap();
+        world.flush();
+        assert!(world.resource::<Disconnects>().0.is_empty());
+
+        // but if we can never place anything the peer sends, we disconnect
+        world
+            .get_mut::<Transport>(entity)
+            .unwrap()
+            .recv_fuzz_input(&header_packet(first_seq + num_desyncs - 1), now)
+            .unwrap_err();
+        for _ in 0..2 {
+            world.run_system_once(check_seq_desync).unwrap();
+            world.flush();
+        }
+        assert_eq!(
+            vec!["sequence numbers desynchronized with peer".to_owned()],
+            world.resource::<Disconnects>().0
+        );
+    }
+
+    #[test]
+    fn protocol_version_mismatch() {
+        #[derive(Default, Resource)]
+        struct Reported {
+            mismatches: Vec<ProtocolVersionMismatch>,
+            disconnects: Vec<String>,
+        }
+
+        const LANES: [LaneKind; 1] = [LaneKind::ReliableOrdered];
+
+        fn header_packet(protocol_version: u32) -> Vec<u8> {
+            let mut packet = Vec::new();
+            packet
+                .write(PacketHeader {
+                    protocol_version,
+                    ..Default::default()
+                })
+                .unwrap();
+            packet
+        }
+
+        let now = Instant::now();
+        let session = Session::new(now, IP_MTU);
+        let mut world = World::new();
+        world.init_resource::<Reported>();
+        world.add_observer(
+            |trigger: Trigger<ProtocolVersionMismatch>, mut reported: ResMut<Reported>| {
+                reported.mismatches.push(*trigger.event());
+            },
+        );
+        world.add_observer(
+            |trigger: Trigger<Disconnect>, mut reported: ResMut<Reported>| {
+                reported.disconnects.push(trigger.event().reason.clone());
+            },
+        );
+
+        // matching versions
+        let mut transport = Transport::new(&session, LANES, LANES, now).unwrap();
+        assert_eq!(None, transport.peer_protocol_version());
+        transport
+            .recv_fuzz_input(&header_packet(packet::PROTOCOL_VERSION), now)
+            .unwrap();
+        assert_eq!(
+            Some(packet::PROTOCOL_VERSION),
+            transport.peer_protocol_version()
+        );
+        let matching = world.spawn(transport).id();
+        world.run_system_once(check_protocol_version).unwrap();
+        world.flush();
+        assert!(world.resource::<Reported>().mismatches.is_empty());
+        assert!(world.resource::<Reported>().disconnects.is_empty());
+        world.despawn(matching);
+
+        // a stray packet with a different version is only dropped
+        let peer = packet::PROTOCOL_VERSION + 1;
+        let mut transport = Transport::new(&session, LANES, LANES, now).unwrap();
+        for _ in 0..recv::MAX_PROTOCOL_MISMATCHES - 1 {
+            assert!(matches!(
+                transport.recv_fuzz_input(&header_packet(peer), now),
+                Err(RecvError::ProtocolMismatch { ours, peer: p })
+                    if ours == packet::PROTOCOL_VERSION && p == peer
+            ));
+        }
+        assert_eq!(Some(peer), transport.peer_protocol_version());
+        transport
+            .recv_fuzz_input(&header_packet(packet::PROTOCOL_VERSION), now)
+            .unwrap();
+        transport
+            .recv_fuzz_input(&header_packet(peer), now)
+            .unwrap_err();
+        let entity = world.spawn(transport).id();
+        world.run_system_once(check_protocol_version).unwrap();
+        world.flush();
+        assert!(world.resource::<Reported>().mismatches.is_empty());
+        assert!(world.resource::<Reported>().disconnects.is_empty());
+
+        // but if the peer keeps sending it, it really speaks that version
+        let mut transport = world.get_mut::<Transport>(entity).unwrap();
+        for _ in 1..recv::MAX_PROTOCOL_MISMATCHES {
+            transport
+                .recv_fuzz_input(&header_packet(peer), now)
+                .unwrap_err();
+        }
+
+        // only reported once
+        for _ in 0..2 {
+            world.run_system_once(check_protocol_version).unwrap();
+            world.flush();
+        }
+        let reported = world.resource::<Reported>();
+        assert_eq!(
+            vec![ProtocolVersionMismatch {
+                entity,
+                ours: packet::PROTOCOL_VERSION,
+                peer,
+            }],
+            reported.mismatches
+        );
+        assert_eq!(
+            vec![format!(
+                "protocol version mismatch - we speak {}, peer speaks {peer}",
+                packet::PROTOCOL_VERSION
+            )],
+            reported.disconnects
+        );
+    }
+
+    #[test]
+    fn peer_lanes_extra_send_lane() {
+        let err = lane::check_lane_pairing(
+            &[LaneKind::ReliableOrdered, LaneKind::UnreliableSequenced],
+            &[LaneKind::ReliableOrdered],
+        )
+        .unwrap_err();
+        assert_eq!(
+            "lane pairing mismatch - lane 1: peer sends UnreliableSequenced, but we do not \
+             receive on this lane",
+            err.to_string()
+        );
+
+        // receiving on lanes which the peer never sends on is fine
+        lane::check_lane_pairing(
+            &[LaneKind::ReliableOrdered],
+            &[LaneKind::ReliableOrdered, LaneKind::UnreliableSequenced],
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn explicit_transport_config() {
+        #[derive(Default, Resource)]
+        struct Disconnected(Vec<Entity>);
+
+        const LANES: [LaneKind; 1] = [LaneKind::ReliableOrdered];
+
+        let now = Instant::now();
+        let session = Session::new(now, IP_MTU);
+        let mut world = World::new();
+        world.init_resource::<Disconnected>();
+        world.add_observer(
+            |trigger: Trigger<Disconnect>, mut dc: ResMut<Disconnected>| {
+                dc.0.push(trigger.entity());
+            },
+        );
+        let transport = || Transport::new(&session, LANES, LANES, now).unwrap();
+
+        // by default, a missing config is filled in
+        let defaulted = world.spawn(transport()).id();
+        world.flush();
+        assert!(world.entity(defaulted).contains::<TransportConfig>());
+        assert!(world.resource::<Disconnected>().0.is_empty());
+
+        // in strict mode, it's an error
+        world.insert_resource(ExplicitTransportConfig);
+        let explicit = world.spawn((transport(), TransportConfig::default())).id();
+        let missing = world.spawn(transport()).id();
+        world.flush();
+        assert_eq!(vec![missing], world.resource::<Disconnected>().0);
+        assert!(world.entity(explicit).contains::<TransportConfig>());
+    }
+
+    #[test]
+    fn send_budget_drains_and_refills() {
+        const LANES: [LaneKind; 1] = [LaneKind::UnreliableUnordered];
+
+        let now = Instant::now();
+        let session = Session::new(now, IP_MTU);
+        let msg = Bytes::from_static(&[0; 64]);
+
+        // find out how many bytes of budget sending this message takes up
+        let mut transport = Transport::new(&session, LANES, LANES, now).unwrap();
+        transport.send.bytes_bucket = TokenBucket::new(usize::MAX);
+        transport.send.push(LaneIndex(0), msg.clone(), now).unwrap();
+        let packet_len = send::flush_on(&mut transport, now, IP_MTU)
+            .map(|packet| packet.len())
+            .sum::<usize>();
+
+        let mut transport = Transport::new(&session, LANES, LANES, now).unwrap();
+        transport.send.bytes_bucket = TokenBucket::new(packet_len);
+        assert_eq!(packet_len, transport.send_budget_remaining());
+
+        transport.send.push(LaneIndex(0), msg, now).unwrap();
+        assert_eq!(1, send::flush_on(&mut transport, now, IP_MTU).count());
+        assert_eq!(0, transport.send_budget_remaining());
+
+        // refilled at a rate of `packet_len` bytes per second,
+        // without going over the capacity
+        transport.send.bytes_bucket.refill_portion(0.5);
+        let half_refilled = transport.send_budget_remaining();
+        assert!(half_refilled > 0 && half_refilled < packet_len);
+        transport.send.bytes_bucket.refill_portion(1.0);
+        assert_eq!(packet_len, transport.send_budget_remaining());
+    }
+
+    #[test]
+    fn peak_memory_used() {
+        const LANES: [LaneKind; 1] = [LaneKind::ReliableOrdered];
+
+        let now = Instant::now();
+        let session = Session::new(now, IP_MTU);
+        let mut world = World::new();
+        let transport = Transport::new(&session, LANES, LANES, now).unwrap();
+        let entity = world.spawn(transport).id();
+        world.run_system_once(check_memory_limit).unwrap();
+        let idle = world.get::<Transport>(entity).unwrap().memory_used();
+        assert_eq!(
+            idle,
+            world.get::<Transport>(entity).unwrap().peak_memory_used()
+        );
+
+        let mut transport = world.get_mut::<Transport>(entity).unwrap();
+        for _ in 0..64 {
+            transport
+                .send
+                .push(LaneIndex(0), Bytes::from_static(&[0; 4096]), now)
+                .unwrap();
+        }
+        world.run_system_once(check_memory_limit).unwrap();
+        let burst = world.get::<Transport>(entity).unwrap().memory_used();
+        assert!(burst > idle + 64 * 4096);
+
+        // the peer acks the whole burst
+        let mut transport = world.get_mut::<Transport>(entity).unwrap();
+        transport.send.lanes[0].sent_msgs.clear();
+        world.run_system_once(check_memory_limit).unwrap();
+
+        let transport = world.get::<Transport>(entity).unwrap();
+        assert!(transport.memory_used() < burst);
+        assert_eq!(burst, transport.peak_memory_used());
+    }
+
+    #[test]
+    fn hysteresis_transitions() {
+        const LOW: usize = 1;
+        const HIGH: usize = 3;
+
+        assert!(!hysteresis(false, 2, LOW, HIGH));
+        assert!(hysteresis(false, 3, LOW, HIGH));
+        // between the thresholds, keeps the current state
+        assert!(hysteresis(true, 2, LOW, HIGH));
+        assert!(!hysteresis(true, 1, LOW, HIGH));
+        assert!(!hysteresis(false, 2, LOW, HIGH));
+    }
+
+    #[test]
+    fn memory_pressure_events() {
+        #[derive(Default, Resource)]
+        struct Events(Vec<(Entity, bool, usize)>);
+
+        const LANES: [LaneKind; 1] = [LaneKind::ReliableOrdered];
+        const KIB: usize = 1024;
+
+        let now = Instant::now();
+        let session = Session::new(now, IP_MTU);
+        let mut world = World::new();
+        world.init_resource::<Events>();
+        world.add_observer(
+            |trigger: Trigger<MemoryPressureHigh>, mut events: ResMut<Events>| {
+                events
+                    .0
+                    .push((trigger.entity(), true, trigger.event().memory_used));
+            },
+        );
+        world.add_observer(
+            |trigger: Trigger<MemoryPressureNormal>, mut events: ResMut<Events>| {
+                events
+                    .0
+                    .push((trigger.entity(), false, trigger.event().memory_used));
+            },
+        );
+        let transport = Transport::new(&session, LANES, LANES, now).unwrap();
+        let config = TransportConfig {
+            soft_memory_limit: Some(transport.memory_used() + 100 * KIB),
+            ..Default::default()
+        };
+        let entity = world.spawn((transport, config)).id();
+
+        let run = |world: &mut World, buffered: usize| {
+            let mut transport = world.get_mut::<Transport>(entity).unwrap();
+            transport.send.lanes[0].sent_msgs.clear();
+            transport
+                .send
+                .push(LaneIndex(0), Bytes::from(vec![0; buffered]), now)
+                .unwrap();
+            let mem_used = transport.memory_used();
+            world.run_system_once(check_memory_limit).unwrap();
+            let events = core::mem::take(&mut world.resource_mut::<Events>().0);
+            (events, mem_used)
+        };
+
+        let (events, mem_used) = run(&mut world, 120 * KIB);
+        assert_eq!(vec![(entity, true, mem_used)], events);
+        // drops below three quarters of the limit
+        let (events, mem_used) = run(&mut world, 50 * KIB);
+        assert_eq!(vec![(entity, false, mem_used)], events);
+        run(&mut world, 120 * KIB);
+
+        // unsetting the limit resets the state without an event
+        world
+            .get_mut::<TransportConfig>(entity)
+            .unwrap()
+            .soft_memory_limit = None;
+        assert_eq!(
+            Vec::<(Entity, bool, usize)>::new(),
+            run(&mut world, 120 * KIB).0
+        );
+        assert!(
+            !world
+                .get::<Transport>(entity)
+                .unwrap()
+                .is_memory_pressure_high()
+        );
+    }
+
+    /// Returns the indices of the sessions which were disconnected.
+    fn memory_budget_disconnects(policy: MemoryBudgetPolicy) -> Vec<usize> {
+        #[derive(Default, Resource)]
+        struct Disconnected(Vec<Entity>);
+
+        const LANES: [LaneKind; 1] = [LaneKind::ReliableOrdered];
+
+        let now = Instant::now();
+        let session = Session::new(now, IP_MTU);
+        let mut world = World::new();
+        world.init_resource::<Disconnected>();
+        world.add_observer(
+            |trigger: Trigger<Disconnect>, mut dc: ResMut<Disconnected>| {
+                dc.0.push(trigger.entity());
+            },
+        );
+
+        // sessions with increasing amounts of buffered messages
+        let mut sessions = Vec::new();
+        let mut mem_used = Vec::new();
+        for num_msgs in [1, 2, 3, 10] {
+            let mut transport = Transport::new(&session, LANES, LANES, now).unwrap();
+            for _ in 0..num_msgs {
+                transport
+                    .send
+                    .push(LaneIndex(0), Bytes::from_static(&[0; 4096]), now)
+                    .unwrap();
+            }
+            mem_used.push(transport.memory_used());
+            sessions.push(world.spawn(transport).id());
         }
+
+        // everyone fits into their own limit, but not into the budget;
+        // an equal share of the budget is what session 1 uses
+        world.insert_resource(TransportMemoryBudget {
+            max_memory_usage: mem_used[1] * sessions.len(),
+            policy,
+        });
+        world.run_system_once(check_memory_limit).unwrap();
+        world.flush();
+
+        let mut disconnected = world
+            .resource::<Disconnected>()
+            .0
+            .iter()
+            .map(|entity| sessions.iter().position(|e| e == entity).unwrap())
+            .collect::<Vec<_>>();
+        disconnected.sort_unstable();
+        disconnected
+    }
+
+    #[test]
+    fn memory_budget_disconnect_largest() {
+        assert_eq!(
+            vec![3],
+            memory_budget_disconnects(MemoryBudgetPolicy::DisconnectLargest)
+        );
+    }
+
+    #[test]
+    fn memory_budget_fair_share() {
+        assert_eq!(
+            vec![2, 3],
+            memory_budget_disconnects(MemoryBudgetPolicy::FairShare)
+        );
+    }
+
+    #[test]
+    fn send_batch_invalid_lane() {
+        const LANES: [LaneKind; 2] = [LaneKind::ReliableOrdered, LaneKind::UnreliableUnordered];
+
+        let now = Instant::now();
+        let session = Session::new(now, IP_MTU);
+        let mut transport = Transport::new(&session, LANES, LANES, now).unwrap();
+
+        let msgs = [
+            (LaneIndex(0), Bytes::from_static(b"a")),
+            (LaneIndex(1), Bytes::from_static(b"b")),
+            (LaneIndex(2), Bytes::from_static(b"c")),
+        ];
+        assert_eq!(
+            SendError::InvalidLane {
+                index: 2,
+                lane: LaneIndex(2)
+            },
+            transport.send_batch(&msgs, now).unwrap_err()
+        );
+        assert!(
+            transport
+                .send
+                .lanes()
+                .iter()
+                .all(|lane| lane.num_queued_msgs() == 0)
+        );
+
+        let keys = transport.send_batch(&msgs[..2], now).unwrap();
+        assert_eq!(
+            vec![LaneIndex(0), LaneIndex(1)],
+            keys.iter().map(|key| key.lane).collect::<Vec<_>>()
+        );
+        assert!(
+            transport
+                .send
+                .lanes()
+                .iter()
+                .all(|lane| lane.num_queued_msgs() == 1)
+        );
+    }
+
+    #[cfg(feature = "cpu-time")]
+    #[test]
+    fn cpu_time_measured() {
+        const LANES: [LaneKind; 1] = [LaneKind::ReliableOrdered];
+
+        let now = Instant::now();
+        let session = Session::new(now, IP_MTU);
+        let mut sender = Transport::new_symmetric(&session, LANES, now).unwrap();
+        let mut receiver = Transport::new_symmetric(&session, LANES, now).unwrap();
+        sender.send.bytes_bucket = TokenBucket::new(usize::MAX);
+        assert_eq!(CpuTime::default(), sender.cpu_time());
+        assert_eq!(CpuTime::default(), receiver.cpu_time());
+
+        let msg = Bytes::from(vec![0; 16 * 1024]);
+        sender.send.push(LaneIndex(0), msg.clone(), now).unwrap();
+        let packets = send::flush_on(&mut sender, now, IP_MTU).collect::<Vec<_>>();
+        assert!(packets.len() > 1);
+        for packet in &packets {
+            receiver.recv_fuzz_input(packet, now).unwrap();
+        }
+        assert_eq!(
+            vec![msg],
+            receiver
+                .recv
+                .msgs
+                .drain()
+                .map(|msg| msg.payload)
+                .collect::<Vec<_>>()
+        );
+
+        assert!(sender.cpu_time().fragment > Duration::ZERO);
+        assert!(receiver.cpu_time().reassemble > Duration::ZERO);
     }
 }
@@ -0,0 +1,176 @@
+//! Detects when the IO layer and transport layer systems run in the wrong
+//! order relative to each other.
+//!
+//! [`TransportSet::Poll`] must run after [`IoSet::Poll`], and
+//! [`TransportSet::Flush`] must run before [`IoSet::Flush`].
+//! [`AeronetTransportPlugin`] sets this ordering up, but only within
+//! [`PreUpdate`] and [`PostUpdate`] - if an IO layer adds its systems to a
+//! different schedule, or outside of the [`IoSet`]s, packets will silently be
+//! delayed by a frame or dropped.
+//!
+//! These checks are only performed in debug builds.
+//!
+//! [`AeronetTransportPlugin`]: crate::AeronetTransportPlugin
+
+use {
+    crate::{Transport, TransportSet, send},
+    aeronet_io::{IoSet, Session, packet::clear_send_buffers},
+    bevy_app::prelude::*,
+    bevy_ecs::prelude::*,
+    tracing::warn,
+};
+
+pub fn build(app: &mut App) {
+    app.init_resource::<OrderViolations>().add_systems(
+        PostUpdate,
+        (
+            check_poll_order
+                .in_set(TransportSet::Flush)
+                .before(send::flush),
+            check_flush_order
+                .after(IoSet::Flush)
+                .before(clear_send_buffers),
+        ),
+    );
+}
+
+/// How many times each kind of misordering has been detected.
+#[derive(Debug, Clone, Default, Resource)]
+pub struct OrderViolations {
+    /// Packets were pushed into [`Session::recv`] after [`TransportSet::Poll`]
+    /// had already drained them.
+    pub poll: usize,
+    /// Packets flushed by [`TransportSet::Flush`] were not consumed by
+    /// [`IoSet::Flush`].
+    pub flush: usize,
+}
+
+fn check_poll_order(
+    sessions: Query<(Entity, &Session), With<Transport>>,
+    mut violations: ResMut<OrderViolations>,
+) {
+    for (entity, session) in &sessions {
+        let len = session.recv.len();
+        if len == 0 {
+            continue;
+        }
+
+        if violations.poll == 0 {
+            warn!(
+                "{entity} received {len} packets after `TransportSet::Poll` ran - make sure the \
+                 IO layer pushes received packets in `IoSet::Poll` in `PreUpdate`, which runs \
+                 before `TransportSet::Poll`"
+            );
+        }
+        violations.poll += 1;
+    }
+}
+
+fn check_flush_order(
+    sessions: Query<(Entity, &Session), With<Transport>>,
+    mut violations: ResMut<OrderViolations>,
+) {
+    for (entity, session) in &sessions {
+        let len = session.send.len();
+        if len == 0 {
+            continue;
+        }
+
+        if violations.flush == 0 {
+            warn!(
+                "{entity} has {len} packets flushed by `TransportSet::Flush` which were not sent \
+                 by `IoSet::Flush` - make sure the IO layer sends packets in `IoSet::Flush` in \
+                 `PostUpdate`, which runs after `TransportSet::Flush`"
+            );
+        }
+        violations.flush += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![expect(clippy::unwrap_used, reason = "testing")]
+
+    use {
+        super::*,
+        crate::{AeronetTransportPlugin, lane::LaneKind},
+        aeronet_io::{
+            AeronetIoPlugin,
+            packet::{IP_MTU, RecvPacket},
+        },
+        octs::Bytes,
+        web_time::Instant,
+    };
+
+    const LANES: [LaneKind; 1] = [LaneKind::UnreliableUnordered];
+
+    fn app_with_session() -> App {
+        let mut app = App::new();
+        app.add_plugins((AeronetIoPlugin, AeronetTransportPlugin));
+
+        let now = Instant::now();
+        let session = Session::new(now, IP_MTU);
+        let transport = Transport::new(&session, LANES, LANES, now).unwrap();
+        app.world_mut().spawn((session, transport));
+        // no time passes between updates, so make sure the transport can flush
+        app.add_systems(Update, |mut transports: Query<&mut Transport>| {
+            for mut transport in &mut transports {
+                transport.send.bytes_bucket.refill_portion(1.0);
+            }
+        });
+        app
+    }
+
+    fn violations(app: &App) -> &OrderViolations {
+        app.world().resource::<OrderViolations>()
+    }
+
+    #[test]
+    fn correct_order() {
+        let mut app = app_with_session();
+        app.add_systems(
+            PostUpdate,
+            (|mut sessions: Query<&mut Session>| {
+                for mut session in &mut sessions {
+                    session.send.clear();
+                }
+            })
+            .in_set(IoSet::Flush),
+        );
+
+        app.update();
+        assert_eq!(0, violations(&app).poll);
+        assert_eq!(0, violations(&app).flush);
+    }
+
+    #[test]
+    fn io_poll_after_transport_poll() {
+        let mut app = app_with_session();
+        // misordered: pushes packets in `Update`, after `TransportSet::Poll`
+        app.add_systems(Update, |mut sessions: Query<&mut Session>| {
+            for mut session in &mut sessions {
+                session.recv.push(RecvPacket {
+                    recv_at: Instant::now(),
+                    payload: Bytes::from_static(b"packet"),
+                });
+            }
+        });
+
+        app.update();
+        assert_eq!(1, violations(&app).poll);
+    }
+
+    #[test]
+    fn io_flush_before_transport_flush() {
+        let mut app = app_with_session();
+        // misordered: sends packets in `Update`, before `TransportSet::Flush`
+        app.add_systems(Update, |mut sessions: Query<&mut Session>| {
+            for mut session in &mut sessions {
+                session.send.clear();
+            }
+        });
+
+        app.update();
+        assert_eq!(1, violations(&app).flush);
+    }
+}
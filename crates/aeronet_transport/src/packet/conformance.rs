@@ -0,0 +1,322 @@
+//! Golden test vectors for the wire encoding of packets.
+//!
+//! If you are writing an implementation of this protocol outside of this crate
+//! (i.e. in another language), you can use [`HEADER_VECTORS`] and
+//! [`FRAGMENT_VECTORS`] to validate that your implementation encodes and
+//! decodes values exactly the same way as this crate does. See the
+//! [module-level docs](super#wire-encoding) for a description of the encoding.
+//!
+//! The functions in this module encode and decode a single value, and (unlike
+//! the [`Decode`] implementations) reject any trailing bytes, so that they can
+//! be checked directly against the vectors.
+//!
+//! ```
+//! use aeronet_transport::packet::conformance::{
+//!     HEADER_VECTORS, decode_packet_header, encode_packet_header,
+//! };
+//!
+//! for vector in HEADER_VECTORS {
+//!     assert_eq!(vector.bytes, encode_packet_header(&vector.header));
+//!     assert_eq!(Ok(vector.header), decode_packet_header(vector.bytes));
+//! }
+//! ```
+
+use {
+    super::{
+        Acknowledge, Fragment, FragmentHeader, FragmentNack, FragmentPayload, FragmentPosition,
+        MessageSeq, PacketHeader, PacketSeq, PayloadTooLarge,
+    },
+    crate::lane::LaneIndex,
+    alloc::vec::Vec,
+    derive_more::{Display, Error},
+    octs::{BufTooShortOr, Bytes, Decode, EncodeLen, Read, VarIntTooLarge, Write},
+};
+
+/// Encoded bytes of a [`PacketHeader`], and the header they decode to.
+#[derive(Debug, Clone, Copy)]
+pub struct HeaderVector {
+    /// Short description of what this vector tests.
+    pub name: &'static str,
+    /// Encoded form of [`HeaderVector::header`].
+    pub bytes: &'static [u8],
+    /// Decoded form of [`HeaderVector::bytes`].
+    pub header: PacketHeader,
+}
+
+/// Encoded bytes of a [`Fragment`], and the fragment they decode to.
+///
+/// Use [`FragmentVector::fragment`] to get the decoded [`Fragment`].
+#[derive(Debug, Clone, Copy)]
+pub struct FragmentVector {
+    /// Short description of what this vector tests.
+    pub name: &'static str,
+    /// Encoded form of the fragment.
+    pub bytes: &'static [u8],
+    /// Decoded header of the fragment.
+    pub header: FragmentHeader,
+    /// Decoded payload of the fragment.
+    pub payload: &'static [u8],
+}
+
+impl FragmentVector {
+    /// Creates the [`Fragment`] which [`FragmentVector::bytes`] decodes to.
+    #[must_use]
+    pub const fn fragment(&self) -> Fragment {
+        Fragment {
+            header: self.header,
+            payload: FragmentPayload(Bytes::from_static(self.payload)),
+        }
+    }
+}
+
+/// Golden test vectors for [`PacketHeader`].
+pub const HEADER_VECTORS: &[HeaderVector] = &[
+    HeaderVector {
+        name: "empty",
+        bytes: &[
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        ],
+        header: PacketHeader {
+            protocol_version: 0,
+            seq: PacketSeq::new(0),
+            acks: Acknowledge {
+                last_recv: PacketSeq::new(0),
+                bits: 0,
+            },
+            mtu: None,
+            clock: None,
+            nack: None,
+        },
+    },
+    HeaderVector {
+        name: "typical",
+        bytes: &[
+            0x01, 0x01, 0x02, 0x01, 0x01, 0x00, 0x00, 0x00, 0x03, 0xb1, 0x09, 0x00, 0x00,
+        ],
+        header: PacketHeader {
+            protocol_version: 1,
+            seq: PacketSeq::new(0x0102),
+            acks: Acknowledge {
+                last_recv: PacketSeq::new(0x0101),
+                bits: 0b11,
+            },
+            mtu: Some(1200),
+            clock: None,
+            nack: None,
+        },
+    },
+    HeaderVector {
+        name: "max values with clock",
+        bytes: &[
+            0xff, 0xff, 0xff, 0xff, 0x0f, 0xff, 0xff, 0xff, 0xfe, 0x80, 0x00, 0x00, 0x01, 0xff,
+            0xff, 0xff, 0xff, 0x0f, 0x81, 0x80, 0xf9, 0xc0, 0xc1, 0xc4, 0x82, 0x03, 0x00,
+        ],
+        header: PacketHeader {
+            protocol_version: u32::MAX,
+            seq: PacketSeq::new(0xffff),
+            acks: Acknowledge {
+                last_recv: PacketSeq::new(0xfffe),
+                bits: 0x8000_0001,
+            },
+            mtu: Some(u32::MAX - 1),
+            clock: Some(1_700_000_000_000_000),
+            nack: None,
+        },
+    },
+    HeaderVector {
+        name: "with nack",
+        bytes: &[
+            0x01, 0x01, 0x02, 0x01, 0x01, 0x00, 0x00, 0x00, 0x03, 0xb1, 0x09, 0x00, 0x04, 0x12,
+            0x34, 0xc8, 0x01,
+        ],
+        header: PacketHeader {
+            protocol_version: 1,
+            seq: PacketSeq::new(0x0102),
+            acks: Acknowledge {
+                last_recv: PacketSeq::new(0x0101),
+                bits: 0b11,
+            },
+            mtu: Some(1200),
+            clock: None,
+            nack: Some(FragmentNack {
+                lane: LaneIndex(3),
+                seq: MessageSeq::new(0x1234),
+                index: 200,
+            }),
+        },
+    },
+];
+
+/// Golden test vectors for [`Fragment`].
+pub const FRAGMENT_VECTORS: &[FragmentVector] = &[
+    FragmentVector {
+        name: "empty last fragment",
+        bytes: &[0x00, 0x00, 0x00, 0x01, 0x00],
+        header: FragmentHeader {
+            lane: LaneIndex(0),
+            seq: MessageSeq::new(0),
+            position: FragmentPosition(1),
+        },
+        payload: &[],
+    },
+    FragmentVector {
+        name: "non-last fragment",
+        bytes: &[0x12, 0x34, 0x03, 0x04, 0x02, 0x68, 0x69],
+        header: FragmentHeader {
+            lane: LaneIndex(3),
+            seq: MessageSeq::new(0x1234),
+            position: FragmentPosition(4),
+        },
+        payload: b"hi",
+    },
+    FragmentVector {
+        name: "multi-byte varints",
+        bytes: &[0x00, 0x07, 0xac, 0x02, 0xc9, 0x01, 0x03, 0xaa, 0xaa, 0xaa],
+        header: FragmentHeader {
+            lane: LaneIndex(300),
+            seq: MessageSeq::new(7),
+            position: FragmentPosition(201),
+        },
+        payload: &[0xaa; 3],
+    },
+];
+
+/// Failed to decode a value using this module's functions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display, Error)]
+pub enum DecodeError {
+    /// Buffer ended before the value was fully decoded.
+    #[display("buffer too short")]
+    TooShort,
+    /// A varint in the buffer was too large for its integer type.
+    #[display("varint too large")]
+    VarIntTooLarge,
+    /// Buffer contained more bytes after the value was decoded.
+    #[display("{_0} trailing bytes")]
+    TrailingBytes(#[error(not(source))] usize),
+}
+
+/// Encodes a [`PacketHeader`] into its wire form.
+#[must_use]
+pub fn encode_packet_header(header: &PacketHeader) -> Vec<u8> {
+    let mut dst = Vec::<u8>::with_capacity(header.encode_len());
+    match dst.write(header) {
+        Ok(()) => dst,
+        Err(BufTooShortOr::TooShort) => {
+            unreachable!("should grow the buffer when writing over capacity")
+        }
+        Err(BufTooShortOr::Or(err)) => match err {},
+    }
+}
+
+/// Decodes a [`PacketHeader`] from its wire form.
+///
+/// # Errors
+///
+/// Errors if `src` is not exactly one validly encoded [`PacketHeader`].
+pub fn decode_packet_header(src: &[u8]) -> Result<PacketHeader, DecodeError> {
+    decode_exact(Bytes::copy_from_slice(src))
+}
+
+/// Encodes a [`Fragment`] into its wire form.
+///
+/// # Errors
+///
+/// Errors if the fragment's payload is too large to be encoded.
+pub fn encode_fragment(fragment: &Fragment) -> Result<Vec<u8>, PayloadTooLarge> {
+    let mut dst = Vec::<u8>::with_capacity(fragment.encode_len());
+    dst.write(fragment).map_err(|err| match err {
+        BufTooShortOr::TooShort => {
+            unreachable!("should grow the buffer when writing over capacity")
+        }
+        BufTooShortOr::Or(err) => err,
+    })?;
+    Ok(dst)
+}
+
+/// Decodes a [`Fragment`] from its wire form.
+///
+/// # Errors
+///
+/// Errors if `src` is not exactly one validly encoded [`Fragment`].
+pub fn decode_fragment(src: &[u8]) -> Result<Fragment, DecodeError> {
+    decode_exact(Bytes::copy_from_slice(src))
+}
+
+fn decode_exact<T: Decode<Error = VarIntTooLarge>>(mut src: Bytes) -> Result<T, DecodeError> {
+    let value = src.read::<T>().map_err(|err| match err {
+        BufTooShortOr::TooShort => DecodeError::TooShort,
+        BufTooShortOr::Or(VarIntTooLarge) => DecodeError::VarIntTooLarge,
+    })?;
+    if src.is_empty() {
+        Ok(value)
+    } else {
+        Err(DecodeError::TrailingBytes(src.len()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![expect(clippy::unwrap_used, reason = "testing")]
+
+    use super::*;
+
+    #[test]
+    fn header_vectors() {
+        for vector in HEADER_VECTORS {
+            assert_eq!(
+                vector.bytes,
+                encode_packet_header(&vector.header),
+                "{}",
+                vector.name
+            );
+            assert_eq!(
+                Ok(vector.header),
+                decode_packet_header(vector.bytes),
+                "{}",
+                vector.name
+            );
+        }
+    }
+
+    #[test]
+    fn fragment_vectors() {
+        for vector in FRAGMENT_VECTORS {
+            assert_eq!(
+                vector.bytes,
+                encode_fragment(&vector.fragment()).unwrap(),
+                "{}",
+                vector.name
+            );
+            assert_eq!(
+                Ok(vector.fragment()),
+                decode_fragment(vector.bytes),
+                "{}",
+                vector.name
+            );
+        }
+    }
+
+    #[test]
+    fn reject_malformed() {
+        let bytes = HEADER_VECTORS[1].bytes;
+        assert_eq!(
+            Err(DecodeError::TooShort),
+            decode_packet_header(&bytes[..bytes.len() - 1])
+        );
+
+        let mut trailing = bytes.to_vec();
+        trailing.push(0);
+        assert_eq!(
+            Err(DecodeError::TrailingBytes(1)),
+            decode_packet_header(&trailing)
+        );
+
+        // `mtu` varint with more than `u32`'s worth of bits
+        let mut too_large = bytes[..9].to_vec();
+        too_large.extend([0xff, 0xff, 0xff, 0xff, 0xff, 0x7f, 0x00]);
+        assert_eq!(
+            Err(DecodeError::VarIntTooLarge),
+            decode_packet_header(&too_large)
+        );
+    }
+}
@@ -1,19 +1,78 @@
 use {
-    super::{Acknowledge, PacketHeader, PacketSeq},
+    super::{Acknowledge, FragmentIndex, FragmentNack, MessageSeq, PacketHeader, PacketSeq},
+    crate::lane::{LaneIndex, RawLaneIndex},
     core::convert::Infallible,
-    octs::{BufTooShortOr, Decode, Encode, FixedEncodeLen, Read, VarIntTooLarge, Write},
+    octs::{
+        BufTooShortOr, Decode, Encode, EncodeLen, FixedEncodeLen, FixedEncodeLenHint, Read, VarInt,
+        VarIntTooLarge, Write,
+    },
 };
 
-impl FixedEncodeLen for PacketHeader {
-    const ENCODE_LEN: usize = PacketSeq::ENCODE_LEN + Acknowledge::ENCODE_LEN;
+impl FixedEncodeLenHint for PacketHeader {
+    const MIN_ENCODE_LEN: usize = <VarInt<u32> as FixedEncodeLenHint>::MIN_ENCODE_LEN
+        + PacketSeq::ENCODE_LEN
+        + Acknowledge::ENCODE_LEN
+        + <VarInt<u32> as FixedEncodeLenHint>::MIN_ENCODE_LEN
+        + <VarInt<u64> as FixedEncodeLenHint>::MIN_ENCODE_LEN
+        + <VarInt<u32> as FixedEncodeLenHint>::MIN_ENCODE_LEN;
+
+    const MAX_ENCODE_LEN: usize = <VarInt<u32> as FixedEncodeLenHint>::MAX_ENCODE_LEN
+        + PacketSeq::ENCODE_LEN
+        + Acknowledge::ENCODE_LEN
+        + <VarInt<u32> as FixedEncodeLenHint>::MAX_ENCODE_LEN
+        + <VarInt<u64> as FixedEncodeLenHint>::MAX_ENCODE_LEN
+        + <VarInt<u32> as FixedEncodeLenHint>::MAX_ENCODE_LEN
+        + MessageSeq::ENCODE_LEN
+        + <VarInt<FragmentIndex> as FixedEncodeLenHint>::MAX_ENCODE_LEN;
+}
+
+impl EncodeLen for PacketHeader {
+    fn encode_len(&self) -> usize {
+        VarInt(self.protocol_version).encode_len()
+            + PacketSeq::ENCODE_LEN
+            + Acknowledge::ENCODE_LEN
+            + VarInt(encode_mtu(self.mtu)).encode_len()
+            + VarInt(encode_clock(self.clock)).encode_len()
+            + VarInt(encode_nack_lane(self.nack)).encode_len()
+            + self.nack.map_or(0, |nack| {
+                MessageSeq::ENCODE_LEN + VarInt(nack.index).encode_len()
+            })
+    }
+}
+
+// `0` is reserved for "no clock", so that it only takes up a single byte
+fn encode_clock(clock: Option<u64>) -> u64 {
+    clock.map_or(0, |clock| clock.saturating_add(1))
+}
+
+const fn decode_clock(clock: u64) -> Option<u64> {
+    clock.checked_sub(1)
+}
+
+// same as the clock, `0` is reserved for "no MTU"
+fn encode_mtu(mtu: Option<u32>) -> u32 {
+    mtu.map_or(0, |mtu| mtu.saturating_add(1))
+}
+
+// same as the clock, `0` is reserved for "no nack"
+fn encode_nack_lane(nack: Option<FragmentNack>) -> u32 {
+    nack.map_or(0, |nack| u32::from(nack.lane.0) + 1)
 }
 
 impl Encode for PacketHeader {
     type Error = Infallible;
 
     fn encode(&self, mut dst: impl Write) -> Result<(), BufTooShortOr<Self::Error>> {
+        dst.write(VarInt(self.protocol_version))?;
         dst.write(&self.seq)?;
         dst.write(&self.acks)?;
+        dst.write(VarInt(encode_mtu(self.mtu)))?;
+        dst.write(VarInt(encode_clock(self.clock)))?;
+        dst.write(VarInt(encode_nack_lane(self.nack)))?;
+        if let Some(nack) = self.nack {
+            dst.write(nack.seq)?;
+            dst.write(VarInt(nack.index))?;
+        }
         Ok(())
     }
 }
@@ -22,25 +81,68 @@ impl Decode for PacketHeader {
     type Error = VarIntTooLarge;
 
     fn decode(mut src: impl Read) -> Result<Self, BufTooShortOr<Self::Error>> {
+        let protocol_version = src.read::<VarInt<u32>>()?.0;
+        let seq = src.read()?;
+        let acks = src.read()?;
+        let mtu = src.read::<VarInt<u32>>()?.0.checked_sub(1);
+        let clock = decode_clock(src.read::<VarInt<u64>>()?.0);
+        let nack = match src.read::<VarInt<u32>>()?.0.checked_sub(1) {
+            None => None,
+            Some(lane) => Some(FragmentNack {
+                lane: LaneIndex(
+                    RawLaneIndex::try_from(lane).map_err(|_| BufTooShortOr::Or(VarIntTooLarge))?,
+                ),
+                seq: src.read()?,
+                index: src.read::<VarInt<FragmentIndex>>()?.0,
+            }),
+        };
         Ok(Self {
-            seq: src.read()?,
-            acks: src.read()?,
+            protocol_version,
+            seq,
+            acks,
+            mtu,
+            clock,
+            nack,
         })
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use {super::*, octs::test::*};
+    use {super::*, crate::packet::PROTOCOL_VERSION, octs::test::*};
 
     #[test]
     fn encode_decode() {
         hint_round_trip(&PacketHeader {
+            protocol_version: PROTOCOL_VERSION,
             seq: PacketSeq::new(3),
             acks: Acknowledge {
                 last_recv: PacketSeq::new(2),
                 bits: 0b11,
             },
+            mtu: Some(1200),
+            clock: None,
+            nack: None,
+        });
+        hint_round_trip(&PacketHeader {
+            protocol_version: PROTOCOL_VERSION,
+            seq: PacketSeq::new(3),
+            acks: Acknowledge::default(),
+            mtu: None,
+            clock: Some(1_700_000_000_000_000),
+            nack: None,
+        });
+        hint_round_trip(&PacketHeader {
+            protocol_version: PROTOCOL_VERSION,
+            seq: PacketSeq::new(3),
+            acks: Acknowledge::default(),
+            mtu: Some(1200),
+            clock: None,
+            nack: Some(FragmentNack {
+                lane: LaneIndex(RawLaneIndex::MAX),
+                seq: MessageSeq::new(0xffff),
+                index: FragmentIndex::MAX,
+            }),
         });
     }
 }
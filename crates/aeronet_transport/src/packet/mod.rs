@@ -29,8 +29,53 @@
 //!     }
 //! }
 //! ```
+//!
+//! # Wire encoding
+//!
+//! Every value is encoded as one of two kinds of integer:
+//! - fixed-width integers, which are encoded in **big-endian** byte order
+//! - varints, which are encoded as unsigned [LEB128] (the same encoding as
+//!   Protocol Buffers varints): 7 bits of the value per byte, least significant
+//!   group first, with the top bit of each byte set if more bytes follow
+//!
+//! [`PacketHeader`] is encoded as:
+//!
+//! | Field              | Encoding                                        |
+//! |--------------------|-------------------------------------------------|
+//! | `protocol_version` | varint (`u32`)                                  |
+//! | `seq`              | `u16`                                           |
+//! | `acks.last_recv`   | `u16`                                           |
+//! | `acks.bits`        | `u32`                                           |
+//! | `mtu`              | varint (`u32`) - `0` if absent, or `mtu + 1`    |
+//! | `clock`            | varint (`u64`) - `0` if absent, or `clock + 1`  |
+//! | `nack.lane`        | varint (`u32`) - `0` if absent, or `lane + 1`   |
+//! | `nack.seq`         | `u16` - only if `nack` is present               |
+//! | `nack.index`       | varint (`u16`) - only if `nack` is present      |
+//!
+//! `protocol_version` always comes first, so that it can be read even if the
+//! rest of the header is encoded differently in the peer's version of the
+//! protocol.
+//!
+//! Each [`Fragment`] is encoded as:
+//!
+//! | Field             | Encoding                                         |
+//! |-------------------|--------------------------------------------------|
+//! | `header.seq`      | `u16`                                            |
+//! | `header.lane`     | varint (`u16`)                                   |
+//! | `header.position` | varint (`u16`) - see [`FragmentPosition`]        |
+//! | payload length    | varint ([`FragmentPayloadLen`])                  |
+//! | payload           | raw bytes                                        |
+//!
+//! Note that the fragment header's `seq` comes before `lane` on the wire.
+//!
+//! The [`conformance`] module contains golden test vectors of encoded headers
+//! and fragments, which other implementations of this protocol can validate
+//! against.
+//!
+//! [LEB128]: https://en.wikipedia.org/wiki/LEB128
 
 mod ack;
+pub mod conformance;
 mod frag;
 mod header;
 mod payload;
@@ -131,14 +176,94 @@ pub struct Acknowledge {
     pub bits: u32,
 }
 
+/// Version of the protocol defined in this module.
+///
+/// This is incremented whenever the wire encoding of packets changes, so that
+/// peers speaking different versions of the protocol, which would misinterpret
+/// each other's packets, can detect this. It is sent in every
+/// [`PacketHeader`], and the version that the peer speaks is available via
+/// [`Transport::peer_protocol_version`].
+///
+/// [`Transport::peer_protocol_version`]: crate::Transport::peer_protocol_version
+pub const PROTOCOL_VERSION: u32 = 1;
+
 /// Metadata for a single packet.
-#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Arbitrary, TypeSize, Reflect)]
+///
+/// The [`Default`] header is sent with our own [`PROTOCOL_VERSION`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Arbitrary, TypeSize, Reflect)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PacketHeader {
+    /// [`PROTOCOL_VERSION`] of the sender.
+    ///
+    /// This is encoded as a variable-length integer, so usually only takes up
+    /// a single byte.
+    pub protocol_version: u32,
     /// Monotonically increasing sequence number of this packet.
     pub seq: PacketSeq,
     /// Informs the receiver which packets the sender has already received.
     pub acks: Acknowledge,
+    /// Current MTU of the sender's [`Session`], saturating at [`u32::MAX`].
+    ///
+    /// This lets each side learn the MTU of its peer (see
+    /// [`Transport::peer_mtu`]), which may be different to its own. The sender
+    /// only includes this until the receiver has acknowledged a packet which
+    /// carried it, and again whenever its MTU changes. If included, this
+    /// usually takes up 2 bytes, and otherwise only takes up a single byte.
+    ///
+    /// [`Session`]: aeronet_io::Session
+    /// [`Transport::peer_mtu`]: crate::Transport::peer_mtu
+    pub mtu: Option<u32>,
+    /// Wall clock time of the sender when it flushed this packet, in
+    /// microseconds since the UNIX epoch.
+    ///
+    /// This is only included if the sender is sharing its clock with us (see
+    /// [`TransportConfig::clock_sync_interval`]), and otherwise only takes up
+    /// a single byte.
+    ///
+    /// [`TransportConfig::clock_sync_interval`]: crate::TransportConfig::clock_sync_interval
+    pub clock: Option<u64>,
+    /// Fragments of a message which the sender of this packet is missing, and
+    /// wants us to resend immediately.
+    ///
+    /// This is only included if the sender has detected missing fragments
+    /// (see [`TransportConfig::fragment_nacks`]), and otherwise only takes up a
+    /// single byte.
+    ///
+    /// [`TransportConfig::fragment_nacks`]: crate::TransportConfig::fragment_nacks
+    pub nack: Option<FragmentNack>,
+}
+
+impl Default for PacketHeader {
+    fn default() -> Self {
+        Self {
+            protocol_version: PROTOCOL_VERSION,
+            seq: PacketSeq::default(),
+            acks: Acknowledge::default(),
+            mtu: None,
+            clock: None,
+            nack: None,
+        }
+    }
+}
+
+/// Negative acknowledgement of the fragments of a message which the receiver
+/// has not received, and believes were lost.
+///
+/// The sender of the message resends the fragment at
+/// [`FragmentNack::index`], and every later fragment of the message which has
+/// not been acknowledged yet, on its next flush, instead of waiting for its
+/// usual resend timer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Arbitrary, TypeSize, Reflect)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FragmentNack {
+    /// Lane index of the fragment's message, relative to the send lanes of
+    /// the peer which will resend it.
+    pub lane: LaneIndex,
+    /// Sequence number of the fragment's message.
+    pub seq: MessageSeq,
+    /// Index of the first fragment of the message which the receiver is
+    /// missing.
+    pub index: FragmentIndex,
 }
 
 /// Marks the index and last state of a single fragment.
@@ -198,7 +323,7 @@ pub struct FragmentHeader {
 }
 
 /// Single fragment of a message.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Fragment {
     /// Fragment metadata.
     pub header: FragmentHeader,
@@ -0,0 +1,109 @@
+//! On-demand round-trip time measurement, independent of message traffic.
+//!
+//! See [`Transport::ping`].
+//!
+//! [`Transport::ping`]: crate::Transport::ping
+
+use {
+    crate::{FlushedPacket, hash::HashMap, packet::PacketSeq, seq_buf::SeqBuf},
+    alloc::collections::VecDeque,
+    core::time::Duration,
+    typesize::derive::TypeSize,
+    web_time::Instant,
+};
+
+/// Identifies a ping sent via [`Transport::ping`].
+///
+/// Once the ping is answered, a [`Pong`] with the same token is made available
+/// in [`TransportRecv::pongs`].
+///
+/// [`Transport::ping`]: crate::Transport::ping
+/// [`TransportRecv::pongs`]: crate::recv::TransportRecv::pongs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, TypeSize)]
+pub struct PingToken(u64);
+
+/// Answer to a ping sent via [`Transport::ping`].
+///
+/// [`Transport::ping`]: crate::Transport::ping
+#[derive(Debug, Clone, Copy, PartialEq, Eq, TypeSize)]
+pub struct Pong {
+    /// Token returned by [`Transport::ping`] when the ping was requested.
+    ///
+    /// [`Transport::ping`]: crate::Transport::ping
+    pub token: PingToken,
+    /// Instant passed into [`Transport::ping`] when the ping was requested.
+    ///
+    /// [`Transport::ping`]: crate::Transport::ping
+    pub pinged_at: Instant,
+    /// Round-trip time measured from when the probe packet was flushed, to
+    /// when the peer's acknowledgement of it was received.
+    pub rtt: Duration,
+}
+
+#[derive(Debug, Default, TypeSize)]
+pub(crate) struct Pings {
+    next_token: u64,
+    queued: VecDeque<(PingToken, Instant)>,
+    in_flight: HashMap<u16, InFlightPing>,
+}
+
+#[derive(Debug, Clone, Copy, TypeSize)]
+struct InFlightPing {
+    token: PingToken,
+    pinged_at: Instant,
+    flushed_at: Instant,
+}
+
+impl Pings {
+    pub(crate) fn queue(&mut self, now: Instant) -> PingToken {
+        let token = PingToken(self.next_token);
+        self.next_token = self.next_token.wrapping_add(1);
+        self.queued.push_back((token, now));
+        token
+    }
+
+    pub(crate) fn is_queued(&self) -> bool {
+        !self.queued.is_empty()
+    }
+
+    /// Marks the oldest queued ping as being sent in the packet `packet_seq`.
+    pub(crate) fn flushed(&mut self, packet_seq: PacketSeq, now: Instant) {
+        let Some((token, pinged_at)) = self.queued.pop_front() else {
+            return;
+        };
+        self.in_flight.insert(
+            packet_seq.0.0,
+            InFlightPing {
+                token,
+                pinged_at,
+                flushed_at: now,
+            },
+        );
+    }
+
+    /// Resolves the pings which were sent in the packets that the peer has
+    /// now acknowledged.
+    pub(crate) fn acked<'s>(
+        &'s mut self,
+        acked_seqs: impl Iterator<Item = PacketSeq> + 's,
+        recv_at: Instant,
+    ) -> impl Iterator<Item = Pong> + 's {
+        acked_seqs
+            .filter_map(|acked_seq| self.in_flight.remove(&acked_seq.0.0))
+            .map(move |ping| Pong {
+                token: ping.token,
+                pinged_at: ping.pinged_at,
+                rtt: recv_at.saturating_duration_since(ping.flushed_at),
+            })
+    }
+
+    /// Forgets about pings whose probe packets can no longer be acknowledged,
+    /// because we've stopped tracking them.
+    pub(crate) fn forget_lost<const N: usize>(
+        &mut self,
+        flushed_packets: &SeqBuf<FlushedPacket, N>,
+    ) {
+        self.in_flight
+            .retain(|packet_seq, _| flushed_packets.get(*packet_seq).is_some());
+    }
+}
@@ -2,22 +2,27 @@
 
 use {
     crate::{
-        FlushedPacket, MessageKey, RecvMessage, Transport, TransportConfig,
+        FlushedPacket, MTU_ASYMMETRY_WARN_RATIO, MessageKey, RecvMessage, Transport,
+        TransportConfig, UnreliableDropPolicy,
         frag::{FragmentReceiver, ReassembleError},
-        lane::{LaneIndex, LaneKind},
-        packet::{Fragment, MessageSeq, PacketHeader, PacketSeq},
+        hash::{HashMap, HashSet},
+        lane::{LaneIndex, LaneKind, LaneReliability},
+        packet::{
+            Acknowledge, Fragment, FragmentIndex, FragmentNack, MessageSeq, PROTOCOL_VERSION,
+            PacketHeader, PacketSeq,
+        },
+        ping::Pong,
         rtt::RttEstimator,
-        send::SendLane,
+        send::{Delivery, DeliveryReport, MessageContext, MessageOutcome, SendLane},
         seq_buf::SeqBuf,
     },
-    aeronet_io::Session,
-    ahash::{HashMap, HashSet},
+    aeronet_io::{Session, connection::DisconnectedRetained},
     bevy_ecs::prelude::*,
-    core::{iter, num::Saturating},
+    core::{fmt::Write as _, iter, mem, num::Saturating, time::Duration},
     derive_more::{Display, Error},
     either::Either,
-    octs::{Buf, Read},
-    tracing::{trace, trace_span, warn},
+    octs::{Buf, Read, VarInt},
+    tracing::{debug, trace, trace_span, warn},
     typesize::{TypeSize, derive::TypeSize},
     web_time::Instant,
 };
@@ -25,11 +30,15 @@ use {
 /// Access to the receiving half of a [`Transport`].
 #[derive(Debug, TypeSize)]
 pub struct TransportRecv {
-    lanes: Box<[RecvLane]>,
+    pub(crate) lanes: Box<[RecvLane]>,
     /// Buffer of received messages.
     ///
     /// This must be drained by the user on every update.
+    ///
+    /// See [`TransportConfig::max_msgs_per_drain`] for limiting how many
+    /// messages are made available here per update.
     pub msgs: RecvBuffer<RecvMessage>,
+    pub(crate) msgs_backlog: Vec<RecvMessage>,
     /// Buffer of received message acknowledgements for messages previously
     /// sent via [`TransportSend::push`].
     ///
@@ -37,6 +46,44 @@ pub struct TransportRecv {
     ///
     /// [`TransportSend::push`]: crate::send::TransportSend::push
     pub acks: RecvBuffer<MessageKey>,
+    /// Buffer of contexts of messages previously sent via
+    /// [`TransportSend::push_with_context`], given back once the transport is
+    /// done with those messages.
+    ///
+    /// This must be drained by the user on every update.
+    ///
+    /// [`TransportSend::push_with_context`]: crate::send::TransportSend::push_with_context
+    pub contexts: RecvBuffer<MessageContext>,
+    /// Buffer of answers to pings previously sent via [`Transport::ping`].
+    ///
+    /// This must be drained by the user on every update.
+    pub pongs: RecvBuffer<Pong>,
+    /// Buffer of the final delivery status of messages tracked via
+    /// [`Transport::track_delivery`].
+    ///
+    /// This must be drained by the user on every update.
+    pub deliveries: RecvBuffer<DeliveryReport>,
+    /// Buffer of keys of messages on reliable lanes, previously sent via
+    /// [`TransportSend::push_with_deadline`], which were not fully flushed
+    /// before their deadline.
+    ///
+    /// These messages are still sent as usual.
+    ///
+    /// This must be drained by the user on every update.
+    ///
+    /// [`TransportSend::push_with_deadline`]: crate::send::TransportSend::push_with_deadline
+    pub late: RecvBuffer<MessageKey>,
+    /// Buffer of keys of messages on ordered lanes which were skipped, because
+    /// they did not arrive within the lane's maximum reorder wait.
+    ///
+    /// If a skipped message arrives later, it is dropped.
+    ///
+    /// This must be drained by the user on every update.
+    ///
+    /// See [`TransportRecv::set_max_reorder_wait`].
+    pub skipped: RecvBuffer<MessageKey>,
+    errors: RecvErrorCounts,
+    last_undrained_dump_at: Option<Instant>,
 }
 
 /// Buffer storing data received by a [`Transport`].
@@ -44,7 +91,7 @@ pub struct TransportRecv {
 /// This is effectively a wrapper around [`Vec`] which only publicly allows
 /// draining elements from it.
 #[derive(Debug, TypeSize)]
-pub struct RecvBuffer<T: TypeSize>(Vec<T>);
+pub struct RecvBuffer<T: TypeSize>(pub(crate) Vec<T>);
 
 impl TransportRecv {
     pub(crate) fn new(lanes: impl IntoIterator<Item = impl Into<LaneKind>>) -> Self {
@@ -55,7 +102,15 @@ impl TransportRecv {
                 .map(RecvLane::new)
                 .collect(),
             msgs: RecvBuffer(Vec::new()),
+            msgs_backlog: Vec::new(),
             acks: RecvBuffer(Vec::new()),
+            contexts: RecvBuffer(Vec::new()),
+            pongs: RecvBuffer(Vec::new()),
+            deliveries: RecvBuffer(Vec::new()),
+            late: RecvBuffer(Vec::new()),
+            skipped: RecvBuffer(Vec::new()),
+            errors: RecvErrorCounts::default(),
+            last_undrained_dump_at: None,
         }
     }
 
@@ -64,6 +119,176 @@ impl TransportRecv {
     pub const fn lanes(&self) -> &[RecvLane] {
         &self.lanes
     }
+
+    /// Takes all buffered [`TransportRecv::msgs`] out into an owned [`Vec`],
+    /// leaving the buffer empty.
+    ///
+    /// Unlike [`RecvBuffer::drain`], the result does not borrow from this
+    /// value, so it can be stored and processed later.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use {
+    /// #     aeronet_io::{Session, packet::IP_MTU},
+    /// #     aeronet_transport::{Transport, lane::LaneKind},
+    /// #     web_time::Instant,
+    /// # };
+    /// # let now = Instant::now();
+    /// # let session = Session::new(now, IP_MTU);
+    /// # let lanes = [LaneKind::ReliableOrdered];
+    /// let mut transport = Transport::new(&session, lanes, lanes, now).unwrap();
+    ///
+    /// let msgs = transport.recv.collect_msgs();
+    /// for msg in &msgs {
+    ///     println!(
+    ///         "Received {} bytes on lane {:?}",
+    ///         msg.payload.len(),
+    ///         msg.lane
+    ///     );
+    /// }
+    /// assert_eq!(0, transport.recv.msgs.drain().count());
+    /// ```
+    #[must_use]
+    pub fn collect_msgs(&mut self) -> Vec<RecvMessage> {
+        mem::take(&mut self.msgs.0)
+    }
+
+    /// Drains all buffered [`TransportRecv::msgs`], grouped by the lane they
+    /// were received on.
+    ///
+    /// Each lane which received at least one message yields a single group, in
+    /// order of [`LaneIndex`]. Within a group, messages are in the same order
+    /// as they were in [`TransportRecv::msgs`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use {
+    /// #     aeronet_io::{Session, packet::IP_MTU},
+    /// #     aeronet_transport::{Transport, lane::LaneKind},
+    /// #     web_time::Instant,
+    /// # };
+    /// # let now = Instant::now();
+    /// # let session = Session::new(now, IP_MTU);
+    /// # let lanes = [LaneKind::ReliableOrdered, LaneKind::UnreliableUnordered];
+    /// let mut transport = Transport::new(&session, lanes, lanes, now).unwrap();
+    ///
+    /// for (lane, msgs) in transport.recv.drain_grouped() {
+    ///     println!("Received {} messages on lane {lane:?}", msgs.len());
+    /// }
+    /// ```
+    pub fn drain_grouped(&mut self) -> impl Iterator<Item = (LaneIndex, Vec<RecvMessage>)> {
+        let mut groups = iter::repeat_with(Vec::new)
+            .take(self.lanes.len())
+            .collect::<Vec<_>>();
+        for msg in self.msgs.0.drain(..) {
+            groups[usize::from(msg.lane)].push(msg);
+        }
+
+        groups
+            .into_iter()
+            .filter_map(|msgs| msgs.first().map(|msg| msg.lane).map(|lane| (lane, msgs)))
+    }
+
+    /// Creates a cursor over the buffered [`TransportRecv::msgs`], which lets
+    /// you inspect each message in place before deciding whether to consume
+    /// it.
+    ///
+    /// Use [`RecvCursor::peek`] to borrow the lane and payload of the next
+    /// message, then either [`RecvCursor::advance`] to consume it, or
+    /// [`RecvCursor::skip`] to leave it in the buffer and move on. Nothing is
+    /// copied or allocated - consumed messages are removed from the buffer
+    /// when the cursor is dropped, and the messages left in the buffer keep
+    /// their order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use {
+    /// #     aeronet_io::{Session, packet::IP_MTU},
+    /// #     aeronet_transport::{Transport, lane::{LaneIndex, LaneKind}},
+    /// #     web_time::Instant,
+    /// # };
+    /// # let now = Instant::now();
+    /// # let session = Session::new(now, IP_MTU);
+    /// # let lanes = [LaneKind::ReliableOrdered, LaneKind::UnreliableUnordered];
+    /// let mut transport = Transport::new(&session, lanes, lanes, now).unwrap();
+    ///
+    /// // only handle messages on lane 0 for now
+    /// let mut cursor = transport.recv.msgs_cursor();
+    /// while let Some((lane, payload)) = cursor.peek() {
+    ///     if lane == LaneIndex(0) {
+    ///         println!("Received {} bytes", payload.len());
+    ///         cursor.advance();
+    ///     } else {
+    ///         cursor.skip();
+    ///     }
+    /// }
+    /// ```
+    pub fn msgs_cursor(&mut self) -> RecvCursor<'_> {
+        RecvCursor {
+            msgs: &mut self.msgs.0,
+            kept: 0,
+            next: 0,
+        }
+    }
+
+    /// Sets how long an ordered lane may wait for a missing message before
+    /// skipping it.
+    ///
+    /// On a [`LaneKind::ReliableOrdered`] lane, if a message is lost, all
+    /// messages received after it are held back until it is resent and
+    /// received. If the peer never manages to resend it, the lane stays blocked
+    /// forever. With a maximum reorder wait, once the lane has been blocked on
+    /// the same missing message for this long, the missing messages up to the
+    /// next received one are skipped, and the held back messages are made
+    /// available in [`TransportRecv::msgs`]. The keys of the skipped messages
+    /// are made available in [`TransportRecv::skipped`].
+    ///
+    /// This trades strict ordering for bounded latency: messages on this lane
+    /// are still never received out of order, but some may never be received
+    /// at all.
+    ///
+    /// This has no effect on lanes of any other kind. Pass [`None`] to wait
+    /// forever, which is the default.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `lane_index` is outside the range of receive lanes
+    /// configured on this [`Transport`] when it was created.
+    pub fn set_max_reorder_wait(&mut self, lane_index: LaneIndex, wait: Option<Duration>) {
+        self.lanes[usize::from(lane_index)].max_reorder_wait = wait;
+    }
+
+    /// Gets how many received messages are being held back, and will be made
+    /// available in [`TransportRecv::msgs`] in later updates.
+    ///
+    /// See [`TransportConfig::max_msgs_per_drain`].
+    #[must_use]
+    pub fn msgs_backlog_len(&self) -> usize {
+        self.msgs_backlog.len()
+    }
+
+    fn skip_reorder_gaps(&mut self, now: Instant) {
+        for (lane_index, lane) in self.lanes.iter_mut().enumerate() {
+            let lane_index = LaneIndex::try_from(lane_index).expect("lane index too large");
+            lane.skip_reorder_gap(lane_index, now, &mut self.msgs.0, &mut self.skipped.0);
+        }
+    }
+
+    pub(crate) fn limit_msgs(&mut self, max: usize) {
+        if self.msgs_backlog.is_empty() && self.msgs.0.len() <= max {
+            return;
+        }
+
+        // messages held back from previous updates go first
+        self.msgs_backlog.append(&mut self.msgs.0);
+        let num_msgs = self.msgs_backlog.len().min(max);
+        let rest = self.msgs_backlog.split_off(num_msgs);
+        self.msgs.0.append(&mut self.msgs_backlog);
+        self.msgs_backlog = rest;
+    }
 }
 
 impl<T: TypeSize> RecvBuffer<T> {
@@ -73,19 +298,89 @@ impl<T: TypeSize> RecvBuffer<T> {
     }
 }
 
+/// Cursor over the received messages of a [`Transport`], created by
+/// [`TransportRecv::msgs_cursor`].
+#[derive(Debug)]
+pub struct RecvCursor<'a> {
+    msgs: &'a mut Vec<RecvMessage>,
+    // `msgs[..kept]` were skipped, `msgs[kept..next]` were consumed,
+    // and `msgs[next..]` have not been visited yet
+    kept: usize,
+    next: usize,
+}
+
+impl RecvCursor<'_> {
+    /// Gets the lane index and payload of the next message, without consuming
+    /// it.
+    ///
+    /// Returns [`None`] if there are no more messages to visit.
+    #[must_use]
+    pub fn peek(&self) -> Option<(LaneIndex, &[u8])> {
+        self.msgs
+            .get(self.next)
+            .map(|msg| (msg.lane, msg.payload.as_slice()))
+    }
+
+    /// Consumes the next message, removing it from the buffer.
+    ///
+    /// Returns `false` if there are no more messages to visit.
+    pub fn advance(&mut self) -> bool {
+        if self.next >= self.msgs.len() {
+            return false;
+        }
+        self.next += 1;
+        true
+    }
+
+    /// Moves past the next message, leaving it in the buffer.
+    ///
+    /// Returns `false` if there are no more messages to visit.
+    pub fn skip(&mut self) -> bool {
+        if self.next >= self.msgs.len() {
+            return false;
+        }
+        self.msgs.swap(self.kept, self.next);
+        self.kept += 1;
+        self.next += 1;
+        true
+    }
+
+    /// Gets how many messages have not been visited yet.
+    #[must_use]
+    pub fn remaining(&self) -> usize {
+        self.msgs.len() - self.next
+    }
+}
+
+impl Drop for RecvCursor<'_> {
+    fn drop(&mut self) {
+        self.msgs.drain(self.kept..self.next);
+    }
+}
+
 /// State of a lane used for receiving incoming messages on a [`Transport`].
 #[derive(Debug, Clone, TypeSize)]
 pub struct RecvLane {
-    frags: FragmentReceiver,
-    state: LaneState,
+    pub(crate) frags: FragmentReceiver,
+    pub(crate) state: LaneState,
+    pub(crate) max_reorder_wait: Option<Duration>,
+    /// Message that this lane is held back on, and since when.
+    reorder_blocked: Option<(MessageSeq, Instant)>,
+    /// Number of consecutive fragments rejected with
+    /// [`RecvError::SequenceDesync`] on this lane.
+    pub(crate) seq_desyncs: usize,
 }
 
 #[derive(Debug, Clone, TypeSize)]
-enum LaneState {
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub(crate) enum LaneState {
     UnreliableUnordered,
     UnreliableSequenced {
         pending: MessageSeq,
     },
+    UnreliableLatest {
+        pending: MessageSeq,
+    },
     ReliableUnordered {
         pending: MessageSeq,
         recv_buf: HashSet<MessageSeq>,
@@ -96,6 +391,20 @@ enum LaneState {
     },
 }
 
+impl LaneState {
+    /// Gets the sequence number of the next message this lane expects, if it
+    /// tracks one.
+    const fn pending(&self) -> Option<MessageSeq> {
+        match self {
+            Self::UnreliableUnordered => None,
+            Self::UnreliableSequenced { pending }
+            | Self::UnreliableLatest { pending }
+            | Self::ReliableUnordered { pending, .. }
+            | Self::ReliableOrdered { pending, .. } => Some(*pending),
+        }
+    }
+}
+
 impl RecvLane {
     fn new(kind: LaneKind) -> Self {
         Self {
@@ -105,6 +414,9 @@ impl RecvLane {
                 LaneKind::UnreliableSequenced => LaneState::UnreliableSequenced {
                     pending: MessageSeq::default(),
                 },
+                LaneKind::UnreliableLatest => LaneState::UnreliableLatest {
+                    pending: MessageSeq::default(),
+                },
                 LaneKind::ReliableUnordered => LaneState::ReliableUnordered {
                     pending: MessageSeq::default(),
                     recv_buf: HashSet::default(),
@@ -114,6 +426,9 @@ impl RecvLane {
                     recv_buf: HashMap::default(),
                 },
             },
+            max_reorder_wait: None,
+            reorder_blocked: None,
+            seq_desyncs: 0,
         }
     }
 
@@ -123,6 +438,7 @@ impl RecvLane {
         match self.state {
             LaneState::UnreliableUnordered => LaneKind::UnreliableUnordered,
             LaneState::UnreliableSequenced { .. } => LaneKind::UnreliableSequenced,
+            LaneState::UnreliableLatest { .. } => LaneKind::UnreliableLatest,
             LaneState::ReliableUnordered { .. } => LaneKind::ReliableUnordered,
             LaneState::ReliableOrdered { .. } => LaneKind::ReliableOrdered,
         }
@@ -135,88 +451,568 @@ impl RecvLane {
         self.frags.len()
     }
 
+    /// Clears all received messages which have not been forwarded to the user
+    /// yet, and resets the message sequence number that this lane expects to
+    /// receive next.
+    ///
+    /// See [`Transport::reset_lane_ordering`].
+    pub(crate) fn reset(&mut self) {
+        *self = Self {
+            max_reorder_wait: self.max_reorder_wait,
+            ..Self::new(self.kind())
+        };
+    }
+
+    /// Gets how long this lane may wait for a missing message before skipping
+    /// it.
+    ///
+    /// See [`TransportRecv::set_max_reorder_wait`].
+    #[must_use]
+    pub const fn max_reorder_wait(&self) -> Option<Duration> {
+        self.max_reorder_wait
+    }
+
+    /// If this lane has been held back on a missing message for longer than
+    /// its [`RecvLane::max_reorder_wait`], skips up to the next received
+    /// message, and forwards the messages which are no longer held back.
+    fn skip_reorder_gap(
+        &mut self,
+        lane_index: LaneIndex,
+        now: Instant,
+        msgs: &mut Vec<RecvMessage>,
+        skipped: &mut Vec<MessageKey>,
+    ) {
+        let (Some(max_wait), LaneState::ReliableOrdered { pending, recv_buf }) =
+            (self.max_reorder_wait, &mut self.state)
+        else {
+            return;
+        };
+        if recv_buf.is_empty() {
+            self.reorder_blocked = None;
+            return;
+        }
+
+        let blocked_since = match self.reorder_blocked {
+            // still blocked on the same message
+            Some((blocked_on, since)) if blocked_on == *pending => since,
+            _ => {
+                self.reorder_blocked = Some((*pending, now));
+                now
+            }
+        };
+        if now.saturating_duration_since(blocked_since) < max_wait {
+            return;
+        }
+
+        let Some(next_recv) = recv_buf
+            .keys()
+            .copied()
+            .min_by_key(|msg_seq| pending.0.dist_to(msg_seq.0))
+        else {
+            return;
+        };
+        trace!(
+            lane = lane_index.0,
+            from = pending.0.0,
+            to = next_recv.0.0,
+            "Skipping messages after max reorder wait"
+        );
+        while *pending != next_recv {
+            skipped.push(MessageKey {
+                lane: lane_index,
+                seq: *pending,
+            });
+            *pending += MessageSeq::new(1);
+        }
+        while let Some(payload) = recv_buf.remove(pending) {
+            msgs.push(RecvMessage {
+                lane: lane_index,
+                recv_at: now,
+                payload,
+            });
+            *pending += MessageSeq::new(1);
+        }
+
+        // if we're blocked on another message now, wait for it from scratch
+        self.reorder_blocked = if recv_buf.is_empty() {
+            None
+        } else {
+            Some((*pending, now))
+        };
+    }
+
     /// Gets the number of messages which have been received and fully
     /// reassembled, but have not been forwarded to the user yet because some
     /// previous message has not been received yet.
     #[must_use]
     pub fn num_unordered_msgs(&self) -> usize {
         match &self.state {
-            LaneState::UnreliableUnordered | LaneState::UnreliableSequenced { .. } => 0,
+            LaneState::UnreliableUnordered
+            | LaneState::UnreliableSequenced { .. }
+            | LaneState::UnreliableLatest { .. } => 0,
             LaneState::ReliableUnordered { recv_buf, .. } => recv_buf.len(),
             LaneState::ReliableOrdered { recv_buf, .. } => recv_buf.len(),
         }
     }
 }
 
-/// Clears all [`TransportRecv::msgs`] and [`TransportRecv::acks`] buffers,
-/// emitting warnings if there were any items left in the buffers.
+/// Clears all [`TransportRecv::msgs`], [`TransportRecv::acks`], and
+/// [`TransportRecv::contexts`] buffers, emitting warnings if there were any
+/// items left in the buffers.
+///
+/// If [`TransportConfig::log_undrained`] is enabled, the warning also includes
+/// a hex dump of some of the undrained messages.
 ///
 /// The equivalent for [`Transport::send`] does not exist, because the transport
 /// layer itself is responsible for draining that buffer.
-pub fn clear_buffers(mut sessions: Query<(Entity, &mut Transport)>) {
-    for (entity, mut transport) in &mut sessions {
-        let len = transport.recv.msgs.0.len();
-        if len > 0 {
-            warn!(
-                "{entity} has {len} received messages which have not been consumed - this \
-                 indicates a bug in code above the transport layer"
-            );
-            transport.recv.msgs.0.clear();
+pub fn clear_buffers(mut sessions: Query<(Entity, &mut Transport, Option<&TransportConfig>)>) {
+    let now = Instant::now();
+    for (entity, mut transport, config) in &mut sessions {
+        // e.g. messages canceled since the last flush
+        transport.resolve_awaited();
+        if config.is_some_and(|config| config.log_undrained) {
+            if let Some(dump) = transport.recv.take_undrained_dump(now) {
+                warn!("{entity} {dump}");
+            }
+        }
+        if let Some(warning) = Undrained::take(&mut transport.recv).warning(entity) {
+            warn!("{warning}");
+        }
+    }
+}
+
+/// Minimum time between two hex dumps of undrained messages.
+///
+/// See [`TransportConfig::log_undrained`].
+const UNDRAINED_DUMP_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Maximum number of undrained messages included in a single hex dump.
+const UNDRAINED_DUMP_MSGS: usize = 4;
+
+/// Maximum number of bytes of each undrained message's payload included in a
+/// hex dump.
+const UNDRAINED_DUMP_BYTES: usize = 64;
+
+impl TransportRecv {
+    /// Builds a hex dump of a sample of the messages left in
+    /// [`TransportRecv::msgs`], or [`None`] if there are none, or if the last
+    /// dump was too recent.
+    fn take_undrained_dump(&mut self, now: Instant) -> Option<String> {
+        if self.msgs.0.is_empty() {
+            return None;
         }
+        if self
+            .last_undrained_dump_at
+            .is_some_and(|last| now.saturating_duration_since(last) < UNDRAINED_DUMP_INTERVAL)
+        {
+            return None;
+        }
+        self.last_undrained_dump_at = Some(now);
 
-        let len = transport.recv.acks.0.len();
-        if len > 0 {
-            warn!(
-                "{entity} has {len} received acks which have not been consumed - this indicates a \
-                 bug in code above the transport layer"
-            );
-            transport.recv.acks.0.clear();
+        let mut dump = format!(
+            "has {} undrained messages, showing up to {UNDRAINED_DUMP_MSGS}:",
+            self.msgs.0.len()
+        );
+        for msg in self.msgs.0.iter().take(UNDRAINED_DUMP_MSGS) {
+            let hex = msg
+                .payload
+                .iter()
+                .take(UNDRAINED_DUMP_BYTES)
+                .map(|byte| format!("{byte:02x}"))
+                .collect::<Vec<_>>()
+                .join(" ");
+            let ellipsis = if msg.payload.len() > UNDRAINED_DUMP_BYTES {
+                " .."
+            } else {
+                ""
+            };
+            write!(
+                dump,
+                "\n  lane {}, {} bytes: {hex}{ellipsis}",
+                msg.lane.0,
+                msg.payload.len()
+            )
+            .expect("writing to a string should not fail");
+        }
+        Some(dump)
+    }
+}
+
+/// How many items were left in each [`TransportRecv`] buffer by the time
+/// the buffers were cleared.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct Undrained {
+    msgs: usize,
+    acks: usize,
+    contexts: usize,
+    pongs: usize,
+    deliveries: usize,
+    late: usize,
+    skipped: usize,
+}
+
+impl Undrained {
+    fn take(recv: &mut TransportRecv) -> Self {
+        let undrained = Self {
+            msgs: recv.msgs.0.len(),
+            acks: recv.acks.0.len(),
+            contexts: recv.contexts.0.len(),
+            pongs: recv.pongs.0.len(),
+            deliveries: recv.deliveries.0.len(),
+            late: recv.late.0.len(),
+            skipped: recv.skipped.0.len(),
+        };
+        recv.msgs.0.clear();
+        recv.acks.0.clear();
+        recv.contexts.0.clear();
+        recv.pongs.0.clear();
+        recv.deliveries.0.clear();
+        recv.late.0.clear();
+        recv.skipped.0.clear();
+        undrained
+    }
+
+    /// Builds a single warning listing every buffer which was not drained,
+    /// or [`None`] if all buffers were drained.
+    fn warning(&self, entity: Entity) -> Option<String> {
+        let leftovers = [
+            ("msgs", self.msgs),
+            ("acks", self.acks),
+            ("contexts", self.contexts),
+            ("pongs", self.pongs),
+            ("deliveries", self.deliveries),
+            ("late", self.late),
+            ("skipped", self.skipped),
+        ]
+        .into_iter()
+        .filter(|(_, len)| *len > 0)
+        .map(|(name, len)| format!("{name}: {len}"))
+        .collect::<Vec<_>>();
+        if leftovers.is_empty() {
+            return None;
         }
+
+        Some(format!(
+            "{entity} has recv buffers which were not drained ({}) - drain them in a system \
+             running after `TransportSet::Poll` and before `TransportSet::Flush`; this indicates \
+             a bug in code above the transport layer",
+            leftovers.join(", ")
+        ))
     }
 }
 
-pub(crate) fn poll(mut sessions: Query<(Entity, &mut Session, &mut Transport, &TransportConfig)>) {
-    for (entity, mut session, mut transport, config) in &mut sessions {
+/// Opts a [`Transport`] session into triggering [`FirstMessageReceived`].
+///
+/// Add this to a session to run some logic exactly once, when the first
+/// message is received on it - for example, to drive a handshake flow. Once
+/// the event has been triggered, this component is removed. You can add it
+/// back to be notified about the next message received.
+#[derive(Debug, Clone, Copy, Default, Component)]
+pub struct NotifyFirstMessage;
+
+/// Triggered on a session with [`NotifyFirstMessage`] the first time a message
+/// is received on it, in [`TransportSet::Poll`].
+///
+/// The message itself is still available in [`TransportRecv::msgs`] as usual.
+///
+/// [`TransportSet::Poll`]: crate::TransportSet::Poll
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Event)]
+pub struct FirstMessageReceived {
+    /// Session which received the message.
+    pub entity: Entity,
+    /// Lane which the message was received on.
+    pub lane: LaneIndex,
+}
+
+pub(crate) fn poll(
+    mut commands: Commands,
+    mut sessions: Query<
+        (
+            Entity,
+            &mut Session,
+            &mut Transport,
+            &TransportConfig,
+            Has<NotifyFirstMessage>,
+        ),
+        Without<DisconnectedRetained>,
+    >,
+) {
+    let now = Instant::now();
+    for (entity, mut session, mut transport, config, notify_first_msg) in &mut sessions {
         let span = trace_span!("poll", %entity);
         let _span = span.enter();
 
+        let peer_mtu_before = transport.peer_mtu;
         for packet in session.recv.drain(..) {
             if let Err(err) = recv_on(&mut transport, config, packet.recv_at, &packet.payload) {
+                transport.recv.errors.record(&err, packet.recv_at);
                 let err = anyhow::Error::new(err);
                 trace!("Received invalid packet: {err:#}");
             }
         }
+
+        detect_lost_packets(&mut transport, config, now);
+        transport.recv.skip_reorder_gaps(now);
+        transport.recv.limit_msgs(config.max_msgs_per_drain);
+
+        if let Some(msg) = transport.recv.msgs.0.first().filter(|_| notify_first_msg) {
+            commands.entity(entity).remove::<NotifyFirstMessage>();
+            commands.trigger_targets(
+                FirstMessageReceived {
+                    entity,
+                    lane: msg.lane,
+                },
+                entity,
+            );
+        }
+
+        let Transport { send, recv, .. } = &mut *transport;
+        for (lane_index, lane) in send.lanes.iter_mut().enumerate() {
+            let lane_index = LaneIndex::try_from(lane_index).expect("lane index too large");
+            recv.contexts
+                .0
+                .extend(lane.take_dropped_contexts(lane_index));
+            recv.deliveries
+                .0
+                .extend(lane.take_dropped_deliveries(lane_index));
+            recv.late.0.extend(lane.take_late(lane_index));
+        }
+
+        if let Some(summary) = transport.recv.errors.take_summary(now) {
+            warn!("{entity} {summary}");
+        }
+
+        let our_mtu = session.mtu();
+        if let Some(peer_mtu) = asymmetric_peer_mtu(our_mtu, peer_mtu_before, transport.peer_mtu) {
+            warn!(
+                "{entity} has asymmetric MTU with its peer - ours: {our_mtu}, peer's: {peer_mtu}"
+            );
+        }
+    }
+}
+
+fn detect_lost_packets(transport: &mut Transport, config: &TransportConfig, now: Instant) {
+    // see `SessionStatsSample::loss`
+    let lost_thresh = Duration::try_from_secs_f64(
+        transport.rtt.pto().as_secs_f64() * transport.packet_lost_threshold_factor(config),
+    )
+    .unwrap_or(Duration::MAX);
+    for (seq, packet) in transport.flushed_packets.iter_mut() {
+        if !packet.lost && now.saturating_duration_since(packet.flushed_at) > lost_thresh {
+            trace!(seq, "Declared packet lost");
+            packet.lost = true;
+            transport.stats.packets_lost += 1;
+
+            // unreliable messages in this packet won't be resent, so we'll
+            // never get a receipt for them
+            // (unless the lane has a retransmit limit, which handles this itself)
+            for path in &packet.frags {
+                let lane = &mut transport.send.lanes[usize::from(path.lane_index)];
+                if !lane.unreliable_receipts
+                    || lane.retransmit_limit.is_some()
+                    || lane.kind.reliability() != LaneReliability::Unreliable
+                {
+                    continue;
+                }
+                if let Some(msg) = lane.sent_msgs.get_mut(&path.msg_seq) {
+                    msg.frags.fill(None);
+                    msg.undelivered = true;
+                }
+            }
+        }
+    }
+}
+
+/// Gets the peer's MTU if it has just changed from `before` to `after`, and is
+/// now very different to our own.
+fn asymmetric_peer_mtu(
+    our_mtu: usize,
+    before: Option<usize>,
+    after: Option<usize>,
+) -> Option<usize> {
+    after.filter(|&peer_mtu| after != before && is_mtu_asymmetric(our_mtu, peer_mtu))
+}
+
+fn is_mtu_asymmetric(our_mtu: usize, peer_mtu: usize) -> bool {
+    let (min, max) = (our_mtu.min(peer_mtu), our_mtu.max(peer_mtu));
+    max >= min.saturating_mul(MTU_ASYMMETRY_WARN_RATIO)
+}
+
+/// How often a summary of [`RecvError`]s is logged, if any errors occurred.
+///
+/// Individual errors are only logged at the `trace` level, since a misbehaving
+/// peer may cause thousands of them per second.
+const ERROR_SUMMARY_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Number of [`RecvError`]s of each kind encountered since the last summary was
+/// logged.
+#[derive(Debug, Clone, Default, TypeSize)]
+struct RecvErrorCounts {
+    since: Option<Instant>,
+    read_header: Saturating<usize>,
+    read_fragment: Saturating<usize>,
+    invalid_lane: Saturating<usize>,
+    too_many_reassemblies: Saturating<usize>,
+    reassemble: Saturating<usize>,
+    seq_desync: Saturating<usize>,
+    protocol_mismatch: Saturating<usize>,
+}
+
+impl RecvErrorCounts {
+    fn record(&mut self, err: &RecvError, now: Instant) {
+        self.since.get_or_insert(now);
+        let count = match err {
+            RecvError::ReadHeader => &mut self.read_header,
+            RecvError::ReadFragment => &mut self.read_fragment,
+            RecvError::InvalidLane { .. } => &mut self.invalid_lane,
+            RecvError::TooManyReassemblies { .. } => &mut self.too_many_reassemblies,
+            RecvError::Reassemble(_) => &mut self.reassemble,
+            RecvError::SequenceDesync { .. } => &mut self.seq_desync,
+            RecvError::ProtocolMismatch { .. } => &mut self.protocol_mismatch,
+        };
+        *count += 1;
+    }
+
+    fn take_summary(&mut self, now: Instant) -> Option<String> {
+        let elapsed = now.saturating_duration_since(self.since?);
+        if elapsed < ERROR_SUMMARY_INTERVAL {
+            return None;
+        }
+
+        let counts = mem::take(self);
+        let parts = [
+            (counts.read_header, "packets with an unreadable header"),
+            (counts.read_fragment, "unreadable fragments"),
+            (counts.invalid_lane, "fragments on an invalid lane"),
+            (
+                counts.too_many_reassemblies,
+                "fragments over the concurrent reassembly limit",
+            ),
+            (counts.reassemble, "fragments which failed to reassemble"),
+            (
+                counts.seq_desync,
+                "packets or fragments with a desynchronized sequence number",
+            ),
+            (
+                counts.protocol_mismatch,
+                "packets with a mismatched protocol version",
+            ),
+        ]
+        .into_iter()
+        .filter(|(count, _)| count.0 > 0)
+        .map(|(count, desc)| format!("{} {desc}", count.0))
+        .collect::<Vec<_>>();
+        Some(format!(
+            "dropped {} in the last {:.1}s",
+            parts.join(", "),
+            elapsed.as_secs_f64()
+        ))
     }
 }
 
+/// Failed to receive a packet, or a fragment within a packet.
+///
+/// See [`Transport::recv_fuzz_input`].
 #[derive(Debug, Display, Error)]
-enum RecvError {
+pub enum RecvError {
+    /// Packet was too short to contain a [`PacketHeader`].
     #[display("not enough bytes to read header")]
     ReadHeader,
+    /// Packet ended partway through a fragment.
     #[display("not enough bytes to read fragment")]
     ReadFragment,
+    /// Fragment was sent on a lane which we do not receive on.
     #[display("invalid lane {lane:?}")]
-    InvalidLane { lane: LaneIndex },
+    InvalidLane {
+        /// Lane which the fragment was sent on.
+        lane: LaneIndex,
+    },
+    /// Fragment started a new message, but we are already reassembling the
+    /// maximum number of messages at once.
+    #[display("already reassembling {max} messages")]
+    TooManyReassemblies {
+        /// Maximum number of messages which may be reassembled at once.
+        max: usize,
+    },
+    /// Fragment could not be reassembled into its message.
     #[display("failed to reassemble fragment")]
     Reassemble(ReassembleError),
+    /// Packet or fragment had a sequence number implausibly far away from the
+    /// one we expected, which means our sequence numbers and the peer's have
+    /// most likely desynchronized.
+    ///
+    /// See [`MAX_SEQ_GAP`].
+    #[display("sequence desync: expected around {expected}, got {received}")]
+    SequenceDesync {
+        /// Lane which the fragment was sent on, or [`None`] if this was the
+        /// sequence number of a packet.
+        lane: Option<LaneIndex>,
+        /// Sequence number which we expected to receive around.
+        expected: u16,
+        /// Sequence number which we received.
+        received: u16,
+    },
+    /// Packet was sent using a different [`PROTOCOL_VERSION`] to ours, so we
+    /// can't interpret the rest of it.
+    ///
+    /// See [`Transport::peer_protocol_version`].
+    #[display("protocol version mismatch: we speak {ours}, peer speaks {peer}")]
+    ProtocolMismatch {
+        /// Our [`PROTOCOL_VERSION`].
+        ours: u32,
+        /// Protocol version which the packet was sent with.
+        peer: u32,
+    },
 }
 
-/// Exposes `recv_on` for fuzz tests.
-#[cfg(fuzzing)]
-pub fn fuzz_recv_on(
-    transport: &mut Transport,
-    packet: &[u8],
-) -> Result<(), Box<dyn core::error::Error>> {
-    recv_on(
-        transport,
-        &TransportConfig::default(),
-        Instant::now(),
-        packet,
-    )
-    .map_err(|err| Box::new(err) as Box<_>)
+/// Largest distance between a received sequence number and the one we expect,
+/// before the sequence numbers are considered desynchronized.
+///
+/// Packet and message sequence numbers wrap around, and are compared relative
+/// to each other, so a sequence number more than half of the sequence space
+/// away is silently treated as being on the wrong side of the one we expect.
+/// Long before that point, a gap this large can't be explained by packet loss
+/// or reordering - it means that the peer's sequence numbers have been
+/// corrupted, or that it has reset its state without us. Instead of acking,
+/// reordering, or dropping data based on a sequence number like this, we
+/// reject it with [`RecvError::SequenceDesync`].
+///
+/// This is a quarter of the sequence space.
+pub const MAX_SEQ_GAP: u16 = 1 << 14;
+
+/// Gets if the distance from the sequence number we expect to the one we
+/// received is too large to be plausible.
+///
+/// See [`MAX_SEQ_GAP`].
+const fn is_seq_desync(dist: i16) -> bool {
+    dist.unsigned_abs() > MAX_SEQ_GAP
 }
 
-fn recv_on(
+/// Number of consecutive packets, or consecutive fragments on a single lane,
+/// which may be rejected with [`RecvError::SequenceDesync`] before the session
+/// is disconnected.
+///
+/// A few stray or corrupted packets are just dropped. But if we keep being
+/// unable to place what the peer sends - e.g. because more than
+/// [`MAX_SEQ_GAP`] packets in a row were lost - the session can never recover,
+/// so instead of staying stuck, it is disconnected in [`TransportSet::Poll`].
+///
+/// [`TransportSet::Poll`]: crate::TransportSet::Poll
+pub const MAX_SEQ_DESYNCS: usize = 16;
+
+/// Number of consecutive packets sent with the same [`PROTOCOL_VERSION`],
+/// different to ours, which must be received before the session is
+/// disconnected.
+///
+/// A single stray or corrupted packet whose version doesn't match is just
+/// dropped with [`RecvError::ProtocolMismatch`]. Only once the peer has
+/// consistently sent a different version is the session disconnected in
+/// [`TransportSet::Poll`].
+///
+/// [`TransportSet::Poll`]: crate::TransportSet::Poll
+pub const MAX_PROTOCOL_MISMATCHES: usize = 8;
+
+pub(crate) fn recv_on(
     transport: &mut Transport,
     config: &TransportConfig,
     recv_at: Instant,
@@ -224,6 +1020,7 @@ fn recv_on(
 ) -> Result<(), RecvError> {
     trace!(len = packet.len(), "Receiving packet");
 
+    check_protocol_version(transport, packet)?;
     let header = packet
         .read::<PacketHeader>()
         .map_err(|_| RecvError::ReadHeader)?;
@@ -233,68 +1030,363 @@ fn recv_on(
 
     trace!("Received packet header");
 
+    // don't ack or process anything from a packet we can't place
+    let last_recv = transport.peer_acks.last_recv;
+    if is_seq_desync(last_recv.0.dist_to(header.seq.0)) {
+        transport.seq_desyncs = transport.seq_desyncs.saturating_add(1);
+        return Err(RecvError::SequenceDesync {
+            lane: None,
+            expected: last_recv.0.0,
+            received: header.seq.0.0,
+        });
+    }
+    transport.seq_desyncs = 0;
+
     transport.peer_acks.ack(header.seq);
+    transport.acks_pending = true;
+    if let Some(mtu) = header.mtu {
+        transport.peer_mtu = Some(usize::try_from(mtu).unwrap_or(usize::MAX));
+    }
+
+    // the peer can't have received a packet that we haven't sent yet,
+    // so if it acks one, ignore it instead of trusting it
+    let next_packet_seq = transport.send.next_packet_seq;
+    let mut unknown_acks = Saturating(0);
+    let acked_seqs = header.acks.seqs().filter(|acked_seq| {
+        let known = *acked_seq < next_packet_seq;
+        if !known {
+            unknown_acks += 1;
+        }
+        known
+    });
+    // every packet we sent since we started advertising our current MTU
+    // carries it, so if the peer has received any of them, it knows our MTU
+    if let Some((_, first_packet)) = transport.sent_mtu {
+        let last_recv = header.acks.last_recv;
+        if header.acks.is_acked(last_recv)
+            && last_recv < next_packet_seq
+            && first_packet.0.dist_to(last_recv.0) >= 0
+        {
+            transport.sent_mtu_acked = true;
+        }
+    }
+    transport
+        .recv
+        .pongs
+        .0
+        .extend(transport.pings.acked(header.acks.seqs(), recv_at));
+    let spurious_losses_before = transport.stats.spurious_losses;
     transport.recv.acks.0.extend(packet_acks_to_msg_keys(
         &mut transport.flushed_packets,
         &mut transport.send.lanes,
         &mut transport.rtt,
         &mut transport.stats.packet_acks_recv,
+        &mut transport.stats.spurious_losses,
         &mut transport.stats.msg_acks_recv,
+        &mut transport.recv.contexts.0,
+        &mut transport.recv.deliveries.0,
         recv_at,
-        header.acks.seqs(),
+        acked_seqs,
     ));
+    transport.pings.forget_lost(&transport.flushed_packets);
+    if let Some(peer_clock) = header.clock {
+        // until we've received an ack, we have no real RTT sample yet
+        let rtt = (transport.stats.packet_acks_recv.0 > 0).then_some(transport.rtt.get());
+        transport.clock.recv(peer_clock, recv_at, rtt);
+    }
+    if let Some(nack) = header.nack {
+        transport.send.resend_nacked(nack, recv_at);
+    }
+    if config.adaptive_loss_threshold {
+        let spurious_losses = transport.stats.spurious_losses - spurious_losses_before;
+        let confirmed_losses = confirm_losses(&mut transport.flushed_packets, header.acks);
+        adapt_lost_threshold_factor(transport, config, spurious_losses.0, confirmed_losses);
+    }
+    if unknown_acks.0 > 0 {
+        debug!(
+            num_acks = unknown_acks.0,
+            ?next_packet_seq,
+            "Peer acked packets which we have not sent yet"
+        );
+        transport.stats.unknown_packet_acks_recv += unknown_acks;
+    }
 
+    let num_frags = recv_frags(transport, config, recv_at, header.seq, packet);
+
+    // packets which only carry acks (or are keep-alives or pings) don't need
+    // an ack of their own straight away - see `AckPolicy`
+    if num_frags > 0 || header.nack.is_some() {
+        transport.ack_eliciting_pending = true;
+    }
+
+    if config.fragment_nacks {
+        queue_nacks(transport, header.seq);
+    } else {
+        transport.nack_candidates.clear();
+    }
+
+    Ok(())
+}
+
+/// Reads the protocol version at the start of `packet`, and tracks how many
+/// consecutive packets the peer has sent with a different version to ours.
+fn check_protocol_version(transport: &mut Transport, mut packet: &[u8]) -> Result<(), RecvError> {
+    // the version always comes first, so we can read it even if we can't
+    // read the rest of the header
+    let protocol_version = packet
+        .read::<VarInt<u32>>()
+        .map_err(|_| RecvError::ReadHeader)?
+        .0;
+    if protocol_version == PROTOCOL_VERSION {
+        transport.protocol_mismatches = 0;
+    } else if transport.peer_protocol_version == Some(protocol_version) {
+        transport.protocol_mismatches = transport.protocol_mismatches.saturating_add(1);
+    } else {
+        transport.protocol_mismatches = 1;
+    }
+    transport.peer_protocol_version = Some(protocol_version);
+    if protocol_version != PROTOCOL_VERSION {
+        return Err(RecvError::ProtocolMismatch {
+            ours: PROTOCOL_VERSION,
+            peer: protocol_version,
+        });
+    }
+    Ok(())
+}
+
+/// Receives all fragments in the rest of `packet`, after its header.
+///
+/// Returns how many fragments the packet carried, including ones which failed
+/// to be received.
+fn recv_frags(
+    transport: &mut Transport,
+    config: &TransportConfig,
+    recv_at: Instant,
+    packet_seq: PacketSeq,
+    mut packet: &[u8],
+) -> usize {
     let mut frag_index = Saturating(0);
     let mut frags_recv = Saturating(0);
     while packet.has_remaining() {
         let span = trace_span!("frag", index = frag_index.0);
         let _span = span.enter();
 
-        match recv_frag(transport, config, recv_at, &mut packet) {
+        #[cfg(feature = "cpu-time")]
+        let reassemble_start = Instant::now();
+        let result = recv_frag(transport, config, recv_at, packet_seq, &mut packet);
+        #[cfg(feature = "cpu-time")]
+        {
+            transport.cpu_time.reassemble += reassemble_start.elapsed();
+        }
+
+        match result {
             Ok(()) => {
                 frags_recv += 1;
             }
             Err(err) => {
+                transport.recv.errors.record(&err, recv_at);
+                // if we couldn't even read the fragment, we don't know where the
+                // next one starts - and the read may not have consumed any bytes
+                let unframed = matches!(err, RecvError::ReadFragment);
                 let err = anyhow::Error::new(err);
                 trace!("Failed to receive fragment: {err:#}");
+                if unframed {
+                    break;
+                }
             }
         }
         frag_index += 1;
     }
 
     trace!(frags_recv = frags_recv.0, "Finished receiving packet");
-
-    Ok(())
+    frag_index.0
 }
 
-fn packet_acks_to_msg_keys<'s, const N: usize>(
-    flushed_packets: &'s mut SeqBuf<FlushedPacket, N>,
-    send_lanes: &'s mut [SendLane],
-    rtt: &'s mut RttEstimator,
-    packet_acks_recv: &'s mut Saturating<usize>,
-    msgs_acks_recv: &'s mut Saturating<usize>,
-    recv_at: Instant,
-    acked_seqs: impl Iterator<Item = PacketSeq> + 's,
-) -> impl Iterator<Item = MessageKey> + 's {
-    acked_seqs
-        // we now know that our packet with sequence `seq` was acked by the peer
-        // let's find what fragments that packet contained when we flushed it out
-        .filter_map(move |acked_seq| {
-            flushed_packets
-                .remove_with(acked_seq.0 .0, FlushedPacket::new(recv_at))
-                .map(|packet| (acked_seq, packet))
-        })
-        .flat_map(move |(acked_seq, packet)| {
-            let span = trace_span!("ack", packet = acked_seq.0 .0);
-            let _span = span.enter();
-
-            let packet_rtt = recv_at.saturating_duration_since(packet.flushed_at);
-            rtt.update(packet_rtt);
+/// Maximum number of [`FragmentNack`]s which may be waiting to be flushed.
+///
+/// Missing fragments which are detected while the queue is full are not
+/// NACKed, and are resent on the peer's usual resend timer instead.
+const MAX_PENDING_NACKS: usize = 256;
 
-            let rtt_now = rtt.get();
+/// Queues NACKs for partially-received reliable messages, which seem to have
+/// lost some of their fragments.
+///
+/// The peer flushes all fragments of a message in consecutive packets, so if
+/// a message received no fragments in this packet, its fragments have stopped
+/// arriving. If we have also not received one of the packets sent after the
+/// one carrying the first fragment of that message, its missing fragments were
+/// most likely in that packet.
+///
+/// Only the messages in `nack_candidates` are checked, and the latest packet
+/// that we missed is read from our acks once for the whole packet, so each
+/// candidate only costs a constant amount of work.
+///
+/// Each message is only NACKed once - if a NACK or the fragments resent because
+/// of it are lost, the peer still resends them later on its usual resend timer.
+///
+/// See [`TransportConfig::fragment_nacks`].
+fn queue_nacks(transport: &mut Transport, packet_seq: PacketSeq) {
+    let latest_missed = latest_missed_packet(transport.peer_acks);
+    let lanes = &mut transport.recv.lanes;
+    let pending_nacks = &mut transport.pending_nacks;
+    transport.nack_candidates.retain(|&(lane_index, msg_seq)| {
+        // if the message is gone, it was either completed or dropped
+        let Some(buf) = lanes[usize::from(lane_index)].frags.msgs.get_mut(&msg_seq) else {
+            return false;
+        };
+        let Some((first_packet, last_packet)) = buf.recv_packets else {
+            return false;
+        };
+        if buf.nacked {
+            return false;
+        }
+        if last_packet == packet_seq {
+            return true;
+        }
+        let missed_packet =
+            latest_missed.is_some_and(|missed| first_packet.0.dist_to(missed.0) > 0);
+        if !missed_packet || pending_nacks.len() >= MAX_PENDING_NACKS {
+            return true;
+        }
+
+        let last_index = buf.last_frag_index.unwrap_or(buf.max_frag_index + 1);
+        let Some(index) = (0..=last_index)
+            .find(|&index| buf.frag_indices_recv.get(index).as_deref() != Some(&true))
+            .and_then(|index| FragmentIndex::try_from(index).ok())
+        else {
+            return false;
+        };
+        buf.nacked = true;
+        trace!(
+            lane = lane_index.0,
+            msg_seq = msg_seq.0.0,
+            index,
+            "Queued NACK"
+        );
+        pending_nacks.push_back(FragmentNack {
+            lane: lane_index,
+            seq: msg_seq,
+            index,
+        });
+        false
+    });
+}
+
+/// Gets the latest packet within the ack window of `acks` which we have not
+/// received.
+fn latest_missed_packet(acks: Acknowledge) -> Option<PacketSeq> {
+    let dist = (!acks.bits).trailing_zeros();
+    u16::try_from(dist)
+        .ok()
+        .filter(|_| dist < u32::BITS)
+        .map(|dist| acks.last_recv - PacketSeq::new(dist))
+}
+
+/// How many packets sent after a lost packet the peer must acknowledge, before
+/// we consider that packet confirmed to be really lost, rather than reordered.
+///
+/// This is the same as QUIC's `kPacketThreshold`.
+const LOSS_CONFIRM_PACKET_THRESHOLD: i16 = 3;
+
+/// Multiplier for the adaptive lost threshold factor when a spurious loss is
+/// detected.
+const ADAPTIVE_LOSS_INCREASE: f64 = 1.25;
+
+/// Multiplier for the adaptive lost threshold factor when a loss is confirmed.
+const ADAPTIVE_LOSS_DECREASE: f64 = 0.95;
+
+/// Maximum value of the adaptive lost threshold factor, relative to
+/// [`TransportConfig::packet_lost_threshold_factor`].
+const ADAPTIVE_LOSS_MAX_RATIO: f64 = 4.0;
+
+/// Marks packets which we declared lost, and which the peer has definitely
+/// not received, as confirmed lost.
+///
+/// Returns how many packets were newly confirmed lost.
+fn confirm_losses<const N: usize>(
+    flushed_packets: &mut SeqBuf<FlushedPacket, N>,
+    acks: Acknowledge,
+) -> usize {
+    let mut confirmed = 0;
+    for (seq, packet) in flushed_packets.iter_mut() {
+        if packet.lost
+            && !packet.loss_confirmed
+            && PacketSeq::new(seq).0.dist_to(acks.last_recv.0) >= LOSS_CONFIRM_PACKET_THRESHOLD
+        {
+            trace!(seq, "Confirmed packet lost");
+            packet.loss_confirmed = true;
+            confirmed += 1;
+        }
+    }
+    confirmed
+}
+
+fn adapt_lost_threshold_factor(
+    transport: &mut Transport,
+    config: &TransportConfig,
+    spurious_losses: usize,
+    confirmed_losses: usize,
+) {
+    if spurious_losses == 0 && confirmed_losses == 0 {
+        return;
+    }
+
+    let min = 1.0_f64.min(config.packet_lost_threshold_factor);
+    let max = config.packet_lost_threshold_factor * ADAPTIVE_LOSS_MAX_RATIO;
+    let mut factor = transport.packet_lost_threshold_factor(config);
+    for _ in 0..spurious_losses {
+        factor *= ADAPTIVE_LOSS_INCREASE;
+    }
+    for _ in 0..confirmed_losses {
+        factor *= ADAPTIVE_LOSS_DECREASE;
+    }
+    let factor = factor.clamp(min, max);
+    trace!(
+        spurious_losses,
+        confirmed_losses, factor, "Adapted packet lost threshold factor"
+    );
+    transport.adapted_lost_threshold_factor = Some(factor);
+}
+
+fn packet_acks_to_msg_keys<'s, const N: usize>(
+    flushed_packets: &'s mut SeqBuf<FlushedPacket, N>,
+    send_lanes: &'s mut [SendLane],
+    rtt: &'s mut RttEstimator,
+    packet_acks_recv: &'s mut Saturating<usize>,
+    spurious_losses: &'s mut Saturating<usize>,
+    msgs_acks_recv: &'s mut Saturating<usize>,
+    contexts: &'s mut Vec<MessageContext>,
+    deliveries: &'s mut Vec<DeliveryReport>,
+    recv_at: Instant,
+    acked_seqs: impl Iterator<Item = PacketSeq> + 's,
+) -> impl Iterator<Item = MessageKey> + 's {
+    acked_seqs
+        // we now know that our packet with sequence `seq` was acked by the peer
+        // let's find what fragments that packet contained when we flushed it out
+        .filter_map(move |acked_seq| {
+            flushed_packets
+                .remove_with(acked_seq.0 .0, FlushedPacket::new(recv_at))
+                .map(|packet| (acked_seq, packet))
+        })
+        .flat_map(move |(acked_seq, packet)| {
+            let span = trace_span!("ack", packet = acked_seq.0 .0);
+            let _span = span.enter();
+
+            // if we already declared this packet lost, then that loss was
+            // spurious - the sample we take here will be larger than our RTT
+            // estimate, which raises the PTO, and therefore the loss threshold,
+            // so that we're less eager to declare packets lost in the future
+            let packet_rtt = recv_at.saturating_duration_since(packet.flushed_at);
+            rtt.update(packet_rtt);
+
+            let rtt_now = rtt.get();
             trace!(acked_seq = acked_seq.0.0, ?packet_rtt, ?rtt_now, "Got peer ack");
 
             *packet_acks_recv += 1;
+            if packet.lost {
+                trace!(acked_seq = acked_seq.0.0, "Packet was spuriously declared lost");
+                *spurious_losses += 1;
+            }
             Box::into_iter(packet.frags)
         })
         .filter_map(|frag_path| {
@@ -306,18 +1398,36 @@ fn packet_acks_to_msg_keys<'s, const N: usize>(
             // fallible instead of panicking, because these messages may have already been
             // removed by a previous ack that we received
             let msg = lane.sent_msgs.get_mut(&frag_path.msg_seq)?;
-            let frag_opt = msg.frags.get_mut(usize::from(frag_path.frag_index))?;
             // take this fragment out so it stops being resent
-            *frag_opt = None;
+            // if it was already taken out (an unreliable frag which we forgot
+            // about, or a duplicate ack), this ack tells us nothing new
+            msg.frags
+                .get_mut(usize::from(frag_path.frag_index))?
+                .take()?;
 
             // if all the fragments are now acked, then we report that
             // the entire message is now acked
             if msg.frags.iter().all(Option::is_none) {
                 *msgs_acks_recv += 1;
-                Some(MessageKey {
+                if let Some(context) = msg.context.take() {
+                    contexts.push(MessageContext::new(
+                        frag_path.lane_index,
+                        frag_path.msg_seq,
+                        context,
+                        MessageOutcome::Acked,
+                    ));
+                }
+                let key = MessageKey {
                     lane: frag_path.lane_index,
-                    seq: frag_path.msg_seq
-                })
+                    seq: frag_path.msg_seq,
+                };
+                if mem::take(&mut msg.track_delivery) {
+                    deliveries.push(DeliveryReport {
+                        key,
+                        delivery: Delivery::Delivered,
+                    });
+                }
+                Some(key)
             } else {
                 None
             }
@@ -328,6 +1438,7 @@ fn recv_frag(
     transport: &mut Transport,
     config: &TransportConfig,
     recv_at: Instant,
+    packet_seq: PacketSeq,
     packet: &mut &[u8],
 ) -> Result<(), RecvError> {
     let frag = packet
@@ -335,24 +1446,68 @@ fn recv_frag(
         .map_err(|_| RecvError::ReadFragment)?;
     let lane_index = frag.header.lane;
 
-    let memory_left = config
-        .max_memory_usage
-        .saturating_sub(transport.memory_used());
     let lane = transport
         .recv
         .lanes
         .get_mut(usize::from(lane_index))
         .ok_or(RecvError::InvalidLane { lane: lane_index })?;
-    let msg = lane
-        .frags
-        .reassemble(
+    if let Some(pending) = lane.state.pending() {
+        if is_seq_desync(pending.0.dist_to(frag.header.seq.0)) {
+            lane.seq_desyncs = lane.seq_desyncs.saturating_add(1);
+            return Err(RecvError::SequenceDesync {
+                lane: Some(lane_index),
+                expected: pending.0.0,
+                received: frag.header.seq.0.0,
+            });
+        }
+    }
+    lane.seq_desyncs = 0;
+
+    make_room_for_reassembly(transport, config, &frag)?;
+    let msg = loop {
+        let memory_left = config
+            .max_memory_usage
+            .saturating_sub(transport.memory_used());
+        let lane = transport
+            .recv
+            .lanes
+            .get_mut(usize::from(lane_index))
+            .ok_or(RecvError::InvalidLane { lane: lane_index })?;
+        match lane.frags.reassemble(
             transport.send.max_frag_len,
             memory_left,
             frag.header.seq,
             frag.header.position,
             &frag.payload,
-        )
-        .map_err(RecvError::Reassemble)?;
+        ) {
+            Err(ReassembleError::OutOfMemory { .. })
+                if evict_unreliable_msg(
+                    &mut transport.recv.lanes,
+                    config.unreliable_drop_policy,
+                    (lane_index, frag.header.seq),
+                ) =>
+            {
+                // try again with the memory we freed up
+            }
+            result => break result.map_err(RecvError::Reassemble)?,
+        }
+    };
+    let lane = &mut transport.recv.lanes[usize::from(lane_index)];
+    let reliable = lane.kind().reliability() == LaneReliability::Reliable;
+    if msg.is_none() {
+        if let Some(buf) = lane.frags.msgs.get_mut(&frag.header.seq) {
+            buf.first_recv_at.get_or_insert(recv_at);
+            if buf.recv_packets.is_none() && config.fragment_nacks && reliable {
+                transport
+                    .nack_candidates
+                    .push((lane_index, frag.header.seq));
+            }
+            let first_packet = buf
+                .recv_packets
+                .map_or(packet_seq, |(first_packet, _)| first_packet);
+            buf.recv_packets = Some((first_packet, packet_seq));
+        }
+    }
 
     trace!(
         lane_index = lane_index.0,
@@ -362,12 +1517,23 @@ fn recv_frag(
     );
 
     if let Some(msg) = msg {
+        let latest_only = lane.kind() == LaneKind::UnreliableLatest;
         let msgs_with_lane =
             recv_on_lane(&mut lane.state, msg, frag.header.seq).map(|msg| RecvMessage {
                 lane: lane_index,
                 recv_at,
                 payload: msg,
             });
+        let mut msgs_with_lane = msgs_with_lane.peekable();
+        if latest_only && msgs_with_lane.peek().is_some() {
+            // only the newest message on this lane is surfaced,
+            // and `recv_on_lane` already dropped anything older than it
+            transport.recv.msgs.0.retain(|msg| msg.lane != lane_index);
+            transport
+                .recv
+                .msgs_backlog
+                .retain(|msg| msg.lane != lane_index);
+        }
         transport.recv.msgs.0.extend(msgs_with_lane);
         trace!("Fragment finished reassembling this message");
     }
@@ -375,6 +1541,98 @@ fn recv_frag(
     Ok(())
 }
 
+/// If `frag` would start reassembling a new message, makes sure that we stay
+/// within [`TransportConfig::max_concurrent_reassemblies`].
+fn make_room_for_reassembly(
+    transport: &mut Transport,
+    config: &TransportConfig,
+    frag: &Fragment,
+) -> Result<(), RecvError> {
+    let (lane_index, msg_seq, position) = (frag.header.lane, frag.header.seq, frag.header.position);
+    let lane = transport
+        .recv
+        .lanes
+        .get(usize::from(lane_index))
+        .ok_or(RecvError::InvalidLane { lane: lane_index })?;
+    // a message made of only one fragment is reassembled immediately
+    let single_frag = position.is_last() && usize::from(position.index()) == 0;
+    if single_frag || lane.frags.msgs.contains_key(&msg_seq) {
+        return Ok(());
+    }
+
+    let max = config.max_concurrent_reassemblies;
+    while num_reassemblies(&transport.recv.lanes) >= max {
+        if !evict_unreliable_msg(
+            &mut transport.recv.lanes,
+            config.unreliable_drop_policy,
+            (lane_index, msg_seq),
+        ) {
+            return Err(RecvError::TooManyReassemblies { max });
+        }
+    }
+    Ok(())
+}
+
+fn num_reassemblies(lanes: &[RecvLane]) -> usize {
+    lanes.iter().map(|lane| lane.frags.len()).sum()
+}
+
+/// Evicts a single partially-received message on an unreliable lane, chosen by
+/// `policy`, to free up memory for reassembling other messages.
+///
+/// The message currently being reassembled, `incoming`, is never evicted.
+///
+/// Returns `true` if a message was evicted.
+fn evict_unreliable_msg(
+    lanes: &mut [RecvLane],
+    policy: UnreliableDropPolicy,
+    incoming: (LaneIndex, MessageSeq),
+) -> bool {
+    let candidates = lanes
+        .iter()
+        .enumerate()
+        .filter(|(_, lane)| lane.kind().reliability() == LaneReliability::Unreliable)
+        .flat_map(|(lane_index, lane)| {
+            let lane_index = LaneIndex::try_from(lane_index).expect("lane index too large");
+            lane.frags
+                .msgs
+                .iter()
+                .map(move |(msg_seq, buf)| (lane_index, *msg_seq, buf))
+        })
+        .filter(|(lane_index, msg_seq, _)| (*lane_index, *msg_seq) != incoming);
+
+    // ties are broken by the raw lane index and message sequence,
+    // so that the choice is deterministic
+    let victim = match policy {
+        UnreliableDropPolicy::DropIncoming => None,
+        UnreliableDropPolicy::DropOldest => candidates.min_by_key(|(lane_index, msg_seq, buf)| {
+            (buf.first_recv_at, lane_index.0, msg_seq.0.0)
+        }),
+        UnreliableDropPolicy::DropLargest => candidates.max_by_key(|(lane_index, msg_seq, buf)| {
+            (buf.payload.capacity(), lane_index.0, msg_seq.0.0)
+        }),
+        UnreliableDropPolicy::DropLowestPriorityLane => {
+            candidates.max_by_key(|(lane_index, msg_seq, buf)| {
+                (
+                    lane_index.0,
+                    core::cmp::Reverse((buf.first_recv_at, msg_seq.0.0)),
+                )
+            })
+        }
+    };
+    let Some((lane_index, msg_seq, _)) = victim else {
+        return false;
+    };
+
+    trace!(
+        lane_index = lane_index.0,
+        msg_seq = msg_seq.0.0,
+        "Evicted partially-received message"
+    );
+    lanes[usize::from(lane_index)].frags.msgs.remove(&msg_seq);
+    true
+}
+
 fn recv_on_lane(
     lane: &mut LaneState,
     msg: Vec<u8>,
@@ -385,7 +1643,7 @@ fn recv_on_lane(
             // always just return the message
             Either::Left(Some(msg))
         }
-        LaneState::UnreliableSequenced { pending } => {
+        LaneState::UnreliableSequenced { pending } | LaneState::UnreliableLatest { pending } => {
             if msg_seq < *pending {
                 // msg is older than the message we're expecting to get next, drop it
                 Either::Left(None)
@@ -431,3 +1689,1545 @@ fn recv_on_lane(
     }
     .into_iter()
 }
+
+#[cfg(test)]
+mod tests {
+    #![expect(clippy::unwrap_used, reason = "testing")]
+
+    use {
+        super::*,
+        crate::{
+            limit::TokenBucket,
+            packet::{Acknowledge, FragmentHeader, FragmentPayload, FragmentPosition},
+            sampling::loss_between,
+            send::{PushError, flush_on},
+        },
+        aeronet_io::packet::{IP_MTU, RecvPacket},
+        bevy_ecs::system::RunSystemOnce,
+        core::fmt,
+        octs::{Bytes, Write},
+        std::sync::{Arc, Mutex},
+    };
+
+    const LANES: [LaneKind; 1] = [LaneKind::ReliableOrdered];
+
+    fn transport(now: Instant) -> Transport {
+        let session = Session::new(now, IP_MTU);
+        Transport::new(&session, LANES, LANES, now).unwrap()
+    }
+
+    fn msg_packet(packet_seq: u16, msg_seq: u16, payload: &'static [u8]) -> Vec<u8> {
+        let mut packet = Vec::new();
+        packet
+            .write(PacketHeader {
+                seq: PacketSeq::new(packet_seq),
+                ..Default::default()
+            })
+            .unwrap();
+        packet
+            .write(Fragment {
+                header: FragmentHeader {
+                    lane: LaneIndex(0),
+                    seq: MessageSeq::new(msg_seq),
+                    position: FragmentPosition::last(0).unwrap(),
+                },
+                payload: FragmentPayload(Bytes::from_static(payload)),
+            })
+            .unwrap();
+        packet
+    }
+
+    fn recv_payloads(transport: &mut Transport) -> Vec<Vec<u8>> {
+        transport.recv.msgs.drain().map(|msg| msg.payload).collect()
+    }
+
+    /// Captures the messages of all `WARN` events logged while it is the
+    /// default subscriber.
+    #[derive(Debug, Clone, Default)]
+    struct CapturedWarnings(Arc<Mutex<Vec<String>>>);
+
+    impl tracing::Subscriber for CapturedWarnings {
+        fn enabled(&self, _: &tracing::Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, _: &tracing::span::Attributes<'_>) -> tracing::span::Id {
+            tracing::span::Id::from_u64(1)
+        }
+
+        fn record(&self, _: &tracing::span::Id, _: &tracing::span::Record<'_>) {}
+
+        fn record_follows_from(&self, _: &tracing::span::Id, _: &tracing::span::Id) {}
+
+        fn event(&self, event: &tracing::Event<'_>) {
+            struct Message(String);
+
+            impl tracing::field::Visit for Message {
+                fn record_debug(&mut self, field: &tracing::field::Field, value: &dyn fmt::Debug) {
+                    if field.name() == "message" {
+                        self.0 = format!("{value:?}");
+                    }
+                }
+            }
+
+            if *event.metadata().level() != tracing::Level::WARN {
+                return;
+            }
+            let mut message = Message(String::new());
+            event.record(&mut message);
+            self.0.lock().unwrap().push(message.0);
+        }
+
+        fn enter(&self, _: &tracing::span::Id) {}
+
+        fn exit(&self, _: &tracing::span::Id) {}
+    }
+
+    #[test]
+    fn summarize_duplicate_frags() {
+        const NUM_DUPLICATES: usize = 1000;
+
+        // received long enough ago that the summary is due on the next poll
+        let recv_at = Instant::now().checked_sub(ERROR_SUMMARY_INTERVAL).unwrap();
+        let mut session = Session::new(recv_at, IP_MTU);
+        let transport = Transport::new(&session, LANES, LANES, recv_at).unwrap();
+
+        let mut packet = Vec::new();
+        packet.write(PacketHeader::default()).unwrap();
+        packet
+            .write(Fragment {
+                header: FragmentHeader {
+                    lane: LaneIndex(0),
+                    seq: MessageSeq::new(0),
+                    position: FragmentPosition::non_last(0).unwrap(),
+                },
+                payload: FragmentPayload(Bytes::from(vec![0; transport.send.max_frag_len])),
+            })
+            .unwrap();
+
+        // the first fragment is valid, every one after that is a duplicate
+        let packet = Bytes::from(packet);
+        session
+            .recv
+            .extend((0..=NUM_DUPLICATES).map(|_| RecvPacket {
+                recv_at,
+                payload: packet.clone(),
+            }));
+        let mut world = World::new();
+        let entity = world
+            .spawn((session, transport, TransportConfig::default()))
+            .id();
+
+        let warnings = CapturedWarnings::default();
+        tracing::subscriber::with_default(warnings.clone(), || {
+            world.run_system_once(poll).unwrap();
+        });
+
+        // all errors are coalesced into a single logged summary..
+        let warnings = warnings.0.lock().unwrap();
+        assert_eq!(1, warnings.len(), "{warnings:?}");
+        assert!(
+            warnings[0].starts_with(&format!("{entity} "))
+                && warnings[0].contains(&format!(
+                    "{NUM_DUPLICATES} fragments which failed to reassemble"
+                )),
+            "{warnings:?}"
+        );
+
+        // ..and the counts are reset after the summary is logged
+        let mut transport = world.get_mut::<Transport>(entity).unwrap();
+        assert_eq!(
+            None,
+            transport
+                .recv
+                .errors
+                .take_summary(recv_at + ERROR_SUMMARY_INTERVAL * 2)
+        );
+    }
+
+    #[test]
+    fn drop_frags_on_invalid_lane() {
+        let now = Instant::now();
+        let mut transport = transport(now);
+        let config = TransportConfig::default();
+
+        // frags on lanes we don't have, followed by a valid frag
+        let mut packet = Vec::new();
+        packet.write(PacketHeader::default()).unwrap();
+        for lane in [LaneIndex(1), LaneIndex(u16::MAX)] {
+            packet
+                .write(Fragment {
+                    header: FragmentHeader {
+                        lane,
+                        seq: MessageSeq::new(0),
+                        position: FragmentPosition::last(0).unwrap(),
+                    },
+                    payload: FragmentPayload(Bytes::from_static(b"invalid")),
+                })
+                .unwrap();
+        }
+        packet
+            .write(Fragment {
+                header: FragmentHeader {
+                    lane: LaneIndex(0),
+                    seq: MessageSeq::new(0),
+                    position: FragmentPosition::last(0).unwrap(),
+                },
+                payload: FragmentPayload(Bytes::from_static(b"valid")),
+            })
+            .unwrap();
+
+        recv_on(&mut transport, &config, now, &packet).unwrap();
+
+        // the invalid frags are dropped, but the rest of the packet is still read
+        assert_eq!(vec![b"valid".to_vec()], recv_payloads(&mut transport));
+        let summary = transport
+            .recv
+            .errors
+            .take_summary(now + ERROR_SUMMARY_INTERVAL)
+            .unwrap();
+        assert!(summary.contains("2 fragments on an invalid lane"));
+    }
+
+    #[test]
+    fn detect_seq_desync() {
+        let now = Instant::now();
+        let mut transport = transport(now);
+        let config = TransportConfig::default();
+
+        recv_on(&mut transport, &config, now, &msg_packet(0, 0, b"a")).unwrap();
+
+        // packet seq is half the sequence space away
+        let err = recv_on(&mut transport, &config, now, &msg_packet(0x8000, 1, b"b"));
+        assert!(matches!(
+            err,
+            Err(RecvError::SequenceDesync {
+                lane: None,
+                expected: 0,
+                received: 0x8000,
+            })
+        ));
+        // we don't ack a packet we can't place
+        assert_eq!(PacketSeq::new(0), transport.peer_acks.last_recv);
+
+        // packet seq is fine, but message seq is half the sequence space away
+        recv_on(&mut transport, &config, now, &msg_packet(1, 0x8001, b"c")).unwrap();
+        let summary = transport
+            .recv
+            .errors
+            .take_summary(now + ERROR_SUMMARY_INTERVAL)
+            .unwrap();
+        assert!(summary.contains("1 packets or fragments with a desynchronized sequence number"));
+
+        // neither message was buffered or received
+        assert_eq!(vec![b"a".to_vec()], recv_payloads(&mut transport));
+        assert!(transport.recv.lanes[0].frags.msgs.is_empty());
+    }
+
+    #[test]
+    fn nack_resends_before_timer() {
+        const MAX_FRAG_LEN: usize = 100;
+        const MTU: usize = crate::FRAG_OVERHEAD + MAX_FRAG_LEN;
+
+        let start = Instant::now();
+        let session = Session::new(start, MTU);
+        let mut sender = Transport::new(&session, LANES, LANES, start).unwrap();
+        let mut receiver = Transport::new(&session, LANES, LANES, start).unwrap();
+        sender.send.bytes_bucket = TokenBucket::new(usize::MAX);
+        receiver.send.bytes_bucket = TokenBucket::new(usize::MAX);
+        let config = TransportConfig {
+            fragment_nacks: true,
+            ..Default::default()
+        };
+
+        // one fragment per packet
+        let msg = Bytes::from(vec![1; 3 * MAX_FRAG_LEN]);
+        sender.send.push(LaneIndex(0), msg.clone(), start).unwrap();
+        let packets = flush_on(&mut sender, start, MTU).collect::<Vec<_>>();
+        assert_eq!(3, packets.len());
+        // the middle packet is lost
+        for packet in [&packets[0], &packets[2]] {
+            recv_on(&mut receiver, &config, start, packet).unwrap();
+        }
+        assert!(recv_payloads(&mut receiver).is_empty());
+
+        // the sender flushes again long before its resend timer fires,
+        // so it has nothing new to send
+        let now = start + Duration::from_millis(1);
+        assert!(now < start + sender.rtt.pto());
+        for packet in flush_on(&mut sender, now, MTU).collect::<Vec<_>>() {
+            recv_on(&mut receiver, &config, now, &packet).unwrap();
+        }
+        assert!(recv_payloads(&mut receiver).is_empty());
+        assert_eq!(1, receiver.pending_nacks.len());
+
+        // the receiver NACKs the missing fragment..
+        for packet in flush_on(&mut receiver, now, MTU).collect::<Vec<_>>() {
+            recv_on(&mut sender, &config, now, &packet).unwrap();
+        }
+        assert!(receiver.pending_nacks.is_empty());
+
+        // ..and the sender resends it straight away
+        for packet in flush_on(&mut sender, now, MTU).collect::<Vec<_>>() {
+            recv_on(&mut receiver, &config, now, &packet).unwrap();
+        }
+        assert_eq!(vec![msg.to_vec()], recv_payloads(&mut receiver));
+    }
+
+    #[test]
+    fn one_nack_covers_all_missing_frags() {
+        const MAX_FRAG_LEN: usize = 100;
+        const MTU: usize = crate::FRAG_OVERHEAD + MAX_FRAG_LEN;
+
+        let start = Instant::now();
+        let session = Session::new(start, MTU);
+        let mut sender = Transport::new(&session, LANES, LANES, start).unwrap();
+        let mut receiver = Transport::new(&session, LANES, LANES, start).unwrap();
+        sender.send.bytes_bucket = TokenBucket::new(usize::MAX);
+        receiver.send.bytes_bucket = TokenBucket::new(usize::MAX);
+        let config = TransportConfig {
+            fragment_nacks: true,
+            ..Default::default()
+        };
+
+        // one fragment per packet, and the 2nd and 4th packets are lost
+        let msg = Bytes::from(vec![1; 5 * MAX_FRAG_LEN]);
+        sender.send.push(LaneIndex(0), msg.clone(), start).unwrap();
+        let packets = flush_on(&mut sender, start, MTU).collect::<Vec<_>>();
+        assert_eq!(5, packets.len());
+        for packet in [&packets[0], &packets[2], &packets[4]] {
+            recv_on(&mut receiver, &config, start, packet).unwrap();
+        }
+        let now = start + Duration::from_millis(1);
+        for packet in flush_on(&mut sender, now, MTU).collect::<Vec<_>>() {
+            recv_on(&mut receiver, &config, now, &packet).unwrap();
+        }
+        assert_eq!(
+            vec![FragmentNack {
+                lane: LaneIndex(0),
+                seq: MessageSeq::new(0),
+                index: 1,
+            }],
+            receiver.pending_nacks.iter().copied().collect::<Vec<_>>()
+        );
+        assert!(receiver.nack_candidates.is_empty());
+
+        // a single round trip resends both missing fragments
+        for packet in flush_on(&mut receiver, now, MTU).collect::<Vec<_>>() {
+            recv_on(&mut sender, &config, now, &packet).unwrap();
+        }
+        for packet in flush_on(&mut sender, now, MTU).collect::<Vec<_>>() {
+            recv_on(&mut receiver, &config, now, &packet).unwrap();
+        }
+        assert_eq!(vec![msg.to_vec()], recv_payloads(&mut receiver));
+    }
+
+    #[test]
+    fn drain_grouped() {
+        const LANES: [LaneKind; 3] = [
+            LaneKind::ReliableOrdered,
+            LaneKind::UnreliableUnordered,
+            LaneKind::ReliableOrdered,
+        ];
+
+        let now = Instant::now();
+        let session = Session::new(now, IP_MTU);
+        let mut sender = Transport::new(&session, LANES, LANES, now).unwrap();
+        sender.send.bytes_bucket = TokenBucket::new(usize::MAX);
+        let mut receiver = Transport::new(&session, LANES, LANES, now).unwrap();
+        let config = TransportConfig::default();
+
+        // interleave messages on lanes 0 and 2, leaving lane 1 empty
+        for (lane, payload) in [(2, b"c1"), (0, b"a1"), (2, b"c2"), (0, b"a2"), (0, b"a3")] {
+            sender
+                .send
+                .push(LaneIndex(lane), Bytes::from_static(payload), now)
+                .unwrap();
+        }
+        for packet in flush_on(&mut sender, now, IP_MTU) {
+            recv_on(&mut receiver, &config, now, &packet).unwrap();
+        }
+
+        let groups = receiver
+            .recv
+            .drain_grouped()
+            .map(|(lane, msgs)| {
+                let payloads = msgs.into_iter().map(|msg| msg.payload).collect::<Vec<_>>();
+                (lane, payloads)
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec![
+                (
+                    LaneIndex(0),
+                    vec![b"a1".to_vec(), b"a2".to_vec(), b"a3".to_vec()]
+                ),
+                (LaneIndex(2), vec![b"c1".to_vec(), b"c2".to_vec()]),
+            ],
+            groups
+        );
+        assert_eq!(0, receiver.recv.msgs.drain().count());
+    }
+
+    #[test]
+    fn msgs_cursor() {
+        const LANES: [LaneKind; 2] = [LaneKind::ReliableOrdered, LaneKind::ReliableOrdered];
+
+        let now = Instant::now();
+        let session = Session::new(now, IP_MTU);
+        let mut sender = Transport::new(&session, LANES, LANES, now).unwrap();
+        sender.send.bytes_bucket = TokenBucket::new(usize::MAX);
+        let mut receiver = Transport::new(&session, LANES, LANES, now).unwrap();
+        let config = TransportConfig::default();
+
+        for (lane, payload) in [(0, b"a1"), (1, b"b1"), (0, b"a2")] {
+            sender
+                .send
+                .push(LaneIndex(lane), Bytes::from_static(payload), now)
+                .unwrap();
+        }
+        for packet in flush_on(&mut sender, now, IP_MTU) {
+            recv_on(&mut receiver, &config, now, &packet).unwrap();
+        }
+
+        // consume everything on lane 0, leaving lane 1 buffered
+        let mut consumed = Vec::new();
+        let mut cursor = receiver.recv.msgs_cursor();
+        assert_eq!(3, cursor.remaining());
+        while let Some((lane, payload)) = cursor.peek() {
+            if lane == LaneIndex(0) {
+                consumed.push(payload.to_vec());
+                assert!(cursor.advance());
+            } else {
+                assert!(cursor.skip());
+            }
+        }
+        assert_eq!(0, cursor.remaining());
+        assert!(!cursor.advance());
+        assert!(!cursor.skip());
+        drop(cursor);
+        assert_eq!(vec![b"a1".to_vec(), b"a2".to_vec()], consumed);
+
+        let left = receiver
+            .recv
+            .msgs
+            .drain()
+            .map(|msg| (msg.lane, msg.payload))
+            .collect::<Vec<_>>();
+        assert_eq!(vec![(LaneIndex(1), b"b1".to_vec())], left);
+    }
+
+    #[test]
+    fn reset_lane_ordering() {
+        let now = Instant::now();
+        let mut transport = transport(now);
+        let config = TransportConfig::default();
+
+        // msg 0 never arrives, so msg 1 is stuck waiting for it
+        recv_on(&mut transport, &config, now, &msg_packet(0, 1, b"before")).unwrap();
+        assert!(recv_payloads(&mut transport).is_empty());
+        assert_eq!(1, transport.recv.lanes()[0].num_unordered_msgs());
+
+        // both sides reset the lane; the peer starts sending from msg 0 again
+        transport.reset_lane_ordering(LaneIndex(0));
+        assert_eq!(0, transport.recv.lanes()[0].num_unordered_msgs());
+
+        recv_on(&mut transport, &config, now, &msg_packet(1, 0, b"after 0")).unwrap();
+        recv_on(&mut transport, &config, now, &msg_packet(2, 1, b"after 1")).unwrap();
+        assert_eq!(
+            vec![b"after 0".to_vec(), b"after 1".to_vec()],
+            recv_payloads(&mut transport)
+        );
+    }
+
+    #[test]
+    fn context_on_ack() {
+        #[derive(Debug, PartialEq)]
+        struct MyContext(u32);
+
+        let now = Instant::now();
+        let mut transport = transport(now);
+        transport.send.bytes_bucket = TokenBucket::new(usize::MAX);
+        let config = TransportConfig::default();
+
+        let key = transport
+            .send
+            .push_with_context(LaneIndex(0), Bytes::from_static(b"hi"), now, MyContext(42))
+            .unwrap();
+        assert_eq!(1, flush_on(&mut transport, now, IP_MTU).count());
+        assert_eq!(0, transport.recv.contexts.drain().count());
+
+        // the peer acks the packet containing our message
+        let mut acks = Acknowledge::default();
+        acks.ack(PacketSeq::new(0));
+        let mut packet = Vec::new();
+        packet
+            .write(PacketHeader {
+                seq: PacketSeq::new(0),
+                acks,
+                ..Default::default()
+            })
+            .unwrap();
+        recv_on(&mut transport, &config, now, &packet).unwrap();
+
+        let mut contexts = transport.recv.contexts.drain().collect::<Vec<_>>();
+        assert_eq!(1, contexts.len());
+        let context = contexts.remove(0);
+        assert_eq!(key, context.key);
+        assert_eq!(MessageOutcome::Acked, context.outcome);
+        assert_eq!(None, context.get::<u32>());
+        assert_eq!(MyContext(42), context.downcast::<MyContext>().unwrap());
+    }
+
+    #[test]
+    fn late_ack_is_spurious_loss() {
+        let now = Instant::now();
+        let mut transport = transport(now);
+        transport.send.bytes_bucket = TokenBucket::new(usize::MAX);
+        let config = TransportConfig::default();
+        let before = transport.stats();
+
+        transport
+            .send
+            .push(LaneIndex(0), Bytes::from_static(b"hi"), now)
+            .unwrap();
+        assert_eq!(1, flush_on(&mut transport, now, IP_MTU).count());
+
+        // no ack has arrived long after we flushed the packet
+        let later = now + transport.rtt().pto() * 10;
+        detect_lost_packets(&mut transport, &config, later);
+        assert_eq!(1, transport.stats().packets_lost.0);
+        assert!((loss_between(before, transport.stats()) - 1.0).abs() < f64::EPSILON);
+
+        // ..but then it does arrive
+        let mut acks = Acknowledge::default();
+        acks.ack(PacketSeq::new(0));
+        let mut packet = Vec::new();
+        packet
+            .write(PacketHeader {
+                seq: PacketSeq::new(0),
+                acks,
+                ..Default::default()
+            })
+            .unwrap();
+        let rtt_before = transport.rtt().get();
+        recv_on(&mut transport, &config, later, &packet).unwrap();
+
+        let stats = transport.stats();
+        assert_eq!(1, stats.packet_acks_recv.0);
+        assert_eq!(1, stats.spurious_losses.0);
+        assert!(loss_between(before, stats).abs() < f64::EPSILON);
+        assert!(transport.rtt().get() > rtt_before);
+
+        // and the packet isn't declared lost again
+        let even_later = later + transport.rtt().pto() * 10;
+        detect_lost_packets(&mut transport, &config, even_later);
+        assert_eq!(1, transport.stats().packets_lost.0);
+    }
+
+    #[test]
+    fn max_msgs_per_drain() {
+        const MAX: usize = 3;
+        const PAYLOADS: [&[u8]; 10] = [b"0", b"1", b"2", b"3", b"4", b"5", b"6", b"7", b"8", b"9"];
+
+        let now = Instant::now();
+        let mut transport = transport(now);
+        let config = TransportConfig::default();
+
+        // the first message is lost, so all the others are held up on the
+        // ordered lane until it's received
+        for (seq, payload) in (0..).zip(PAYLOADS).skip(1) {
+            recv_on(&mut transport, &config, now, &msg_packet(seq, seq, payload)).unwrap();
+        }
+        recv_on(&mut transport, &config, now, &msg_packet(0, 0, PAYLOADS[0])).unwrap();
+        assert_eq!(PAYLOADS.len(), transport.recv.msgs.0.len());
+
+        let mut recv = Vec::new();
+        let mut frames = 0;
+        while recv.len() < PAYLOADS.len() {
+            transport.recv.limit_msgs(MAX);
+            let msgs = transport.recv.collect_msgs();
+            assert!(msgs.len() <= MAX);
+            recv.extend(msgs.into_iter().map(|msg| msg.payload));
+            frames += 1;
+        }
+
+        assert_eq!(4, frames);
+        assert_eq!(0, transport.recv.msgs_backlog_len());
+        assert_eq!(PAYLOADS.map(<[u8]>::to_vec).to_vec(), recv);
+    }
+
+    #[test]
+    fn in_flight_window() {
+        const MAX: usize = 2;
+
+        fn ack_packet(packet_seq: u16) -> Vec<u8> {
+            let mut acks = Acknowledge::default();
+            acks.ack(PacketSeq::new(packet_seq));
+            let mut packet = Vec::new();
+            packet
+                .write(PacketHeader {
+                    seq: PacketSeq::new(packet_seq),
+                    acks,
+                    ..Default::default()
+                })
+                .unwrap();
+            packet
+        }
+
+        let now = Instant::now();
+        let mut transport = transport(now);
+        transport.send.bytes_bucket = TokenBucket::new(usize::MAX);
+        transport
+            .send
+            .set_max_in_flight_msgs(LaneIndex(0), Some(MAX));
+        let config = TransportConfig::default();
+        let msg = Bytes::from_static(b"hi");
+
+        // nothing is in flight yet, so we can push more than `MAX` messages..
+        for _ in 0..=MAX {
+            transport.send.push(LaneIndex(0), msg.clone(), now).unwrap();
+        }
+        // ..but only `MAX` of them are flushed
+        assert_eq!(1, flush_on(&mut transport, now, IP_MTU).count());
+        assert_eq!(MAX, transport.send.lanes()[0].num_in_flight_msgs());
+        assert_eq!(MAX + 1, transport.send.lanes()[0].num_queued_msgs());
+        assert_eq!(
+            PushError::InFlightLimit {
+                in_flight: MAX,
+                max: MAX,
+                msg: msg.clone(),
+            },
+            transport
+                .send
+                .push(LaneIndex(0), msg.clone(), now)
+                .unwrap_err()
+        );
+
+        // the peer acks the packet with both messages in flight,
+        // so the held back message is flushed, and we can push again
+        recv_on(&mut transport, &config, now, &ack_packet(0)).unwrap();
+        assert_eq!(2, transport.recv.acks.drain().count());
+        assert_eq!(0, transport.send.lanes()[0].num_in_flight_msgs());
+        transport.send.push(LaneIndex(0), msg, now).unwrap();
+        assert_eq!(1, flush_on(&mut transport, now, IP_MTU).count());
+        assert_eq!(MAX, transport.send.lanes()[0].num_in_flight_msgs());
+    }
+
+    #[test]
+    fn first_message_received_once() {
+        #[derive(Default, Resource)]
+        struct Received(Vec<FirstMessageReceived>);
+
+        let now = Instant::now();
+        let mut world = World::new();
+        world.init_resource::<Received>();
+        world.add_observer(
+            |trigger: Trigger<FirstMessageReceived>, mut received: ResMut<Received>| {
+                received.0.push(*trigger.event());
+            },
+        );
+        let entity = world
+            .spawn((
+                Session::new(now, IP_MTU),
+                transport(now),
+                NotifyFirstMessage,
+            ))
+            .id();
+
+        let mut schedule = Schedule::default();
+        schedule.add_systems(poll);
+        for (packet_seq, payload) in [(0, b"first"), (1, b"later")] {
+            world
+                .get_mut::<Session>(entity)
+                .unwrap()
+                .recv
+                .push(RecvPacket {
+                    recv_at: now,
+                    payload: Bytes::from(msg_packet(packet_seq, packet_seq, payload)),
+                });
+            schedule.run(&mut world);
+            let msgs = world
+                .get_mut::<Transport>(entity)
+                .unwrap()
+                .recv
+                .collect_msgs();
+            assert_eq!(&payload[..], msgs[0].payload);
+        }
+
+        assert_eq!(
+            vec![FirstMessageReceived {
+                entity,
+                lane: LaneIndex(0),
+            }],
+            world.resource::<Received>().0
+        );
+        assert!(!world.entity(entity).contains::<NotifyFirstMessage>());
+    }
+
+    #[test]
+    fn undrained_buffers_single_warning() {
+        let now = Instant::now();
+        let mut transport = transport(now);
+        let config = TransportConfig::default();
+        let entity = Entity::from_raw(7);
+
+        recv_on(&mut transport, &config, now, &msg_packet(0, 0, b"one")).unwrap();
+        recv_on(&mut transport, &config, now, &msg_packet(1, 1, b"two")).unwrap();
+        for seq in 0..3 {
+            transport.recv.acks.0.push(MessageKey {
+                lane: LaneIndex(0),
+                seq: MessageSeq::new(seq),
+            });
+        }
+
+        let undrained = Undrained::take(&mut transport.recv);
+        assert_eq!(
+            Undrained {
+                msgs: 2,
+                acks: 3,
+                contexts: 0,
+                pongs: 0,
+                deliveries: 0,
+                late: 0,
+                skipped: 0,
+            },
+            undrained
+        );
+        assert_eq!(
+            Some(format!(
+                "{entity} has recv buffers which were not drained (msgs: 2, acks: 3) - drain them \
+                 in a system running after `TransportSet::Poll` and before `TransportSet::Flush`; \
+                 this indicates a bug in code above the transport layer"
+            )),
+            undrained.warning(entity)
+        );
+
+        // buffers were cleared, so there's nothing left to warn about
+        assert_eq!(None, Undrained::take(&mut transport.recv).warning(entity));
+    }
+
+    #[test]
+    fn max_reorder_wait() {
+        const WAIT: Duration = Duration::from_millis(100);
+
+        let now = Instant::now();
+        let mut transport = transport(now);
+        let config = TransportConfig::default();
+        transport
+            .recv
+            .set_max_reorder_wait(LaneIndex(0), Some(WAIT));
+
+        recv_on(&mut transport, &config, now, &msg_packet(0, 0, b"0")).unwrap();
+        assert_eq!(vec![b"0".to_vec()], recv_payloads(&mut transport));
+
+        // msg 1 is never received
+        recv_on(&mut transport, &config, now, &msg_packet(1, 2, b"2")).unwrap();
+        recv_on(&mut transport, &config, now, &msg_packet(2, 3, b"3")).unwrap();
+        transport.recv.skip_reorder_gaps(now);
+        assert!(recv_payloads(&mut transport).is_empty());
+
+        // still within the wait
+        let later = now + WAIT / 2;
+        transport.recv.skip_reorder_gaps(later);
+        assert!(recv_payloads(&mut transport).is_empty());
+        assert_eq!(2, transport.recv.lanes()[0].num_unordered_msgs());
+
+        let later = now + WAIT;
+        transport.recv.skip_reorder_gaps(later);
+        assert_eq!(
+            vec![b"2".to_vec(), b"3".to_vec()],
+            recv_payloads(&mut transport)
+        );
+        assert_eq!(
+            vec![MessageKey {
+                lane: LaneIndex(0),
+                seq: MessageSeq::new(1),
+            }],
+            transport.recv.skipped.drain().collect::<Vec<_>>()
+        );
+        assert_eq!(0, transport.recv.lanes()[0].num_unordered_msgs());
+
+        // if the skipped message arrives after all, it's dropped
+        recv_on(&mut transport, &config, later, &msg_packet(3, 1, b"1")).unwrap();
+        recv_on(&mut transport, &config, later, &msg_packet(4, 4, b"4")).unwrap();
+        assert_eq!(vec![b"4".to_vec()], recv_payloads(&mut transport));
+    }
+
+    #[test]
+    fn undrained_msgs_dump() {
+        let now = Instant::now();
+        let mut transport = transport(now);
+        let config = TransportConfig::default();
+
+        assert_eq!(None, transport.recv.take_undrained_dump(now));
+
+        recv_on(&mut transport, &config, now, &msg_packet(0, 0, b"hi")).unwrap();
+        let long = vec![0xab; UNDRAINED_DUMP_BYTES + 1];
+        transport.recv.msgs.0.push(RecvMessage {
+            lane: LaneIndex(0),
+            recv_at: now,
+            payload: long,
+        });
+        assert_eq!(
+            Some(format!(
+                "has 2 undrained messages, showing up to {UNDRAINED_DUMP_MSGS}:\n  lane 0, 2 \
+                 bytes: 68 69\n  lane 0, {} bytes: {} ..",
+                UNDRAINED_DUMP_BYTES + 1,
+                vec!["ab"; UNDRAINED_DUMP_BYTES].join(" ")
+            )),
+            transport.recv.take_undrained_dump(now)
+        );
+        Undrained::take(&mut transport.recv);
+
+        // rate limited..
+        recv_on(&mut transport, &config, now, &msg_packet(1, 1, b"hi")).unwrap();
+        assert_eq!(None, transport.recv.take_undrained_dump(now));
+        // ..until the interval is up
+        let later = now + UNDRAINED_DUMP_INTERVAL;
+        assert!(
+            transport
+                .recv
+                .take_undrained_dump(later)
+                .unwrap()
+                .starts_with("has 1 undrained messages")
+        );
+    }
+
+    /// Sends a packet every round, which the peer acknowledges after some
+    /// delay. Every few rounds, there is a delay spike.
+    ///
+    /// Returns the number of spurious losses in the first and second halves of
+    /// the rounds, and the final lost threshold factor.
+    fn spurious_losses_with_delay_spikes(adaptive: bool) -> (usize, usize, f64) {
+        const ROUNDS: u16 = 200;
+        const DELAY: Duration = Duration::from_millis(10);
+        const SPIKE_DELAY: Duration = Duration::from_millis(40);
+        const SPIKE_EVERY: u16 = 10;
+
+        let mut now = Instant::now();
+        let mut transport = transport(now);
+        transport.send.bytes_bucket = TokenBucket::new(usize::MAX);
+        let config = TransportConfig {
+            adaptive_loss_threshold: adaptive,
+            ..Default::default()
+        };
+
+        let mut acks = Acknowledge::default();
+        let mut first_half = 0;
+        for round in 0..ROUNDS {
+            if round == ROUNDS / 2 {
+                first_half = transport.stats().spurious_losses.0;
+            }
+
+            transport
+                .send
+                .push(LaneIndex(0), Bytes::from_static(b"hi"), now)
+                .unwrap();
+            assert_eq!(1, flush_on(&mut transport, now, IP_MTU).count());
+
+            let delay = if round % SPIKE_EVERY == SPIKE_EVERY - 1 {
+                SPIKE_DELAY
+            } else {
+                DELAY
+            };
+            let ack_at = now + delay;
+            // right before the ack arrives, we check if the packet is lost
+            detect_lost_packets(&mut transport, &config, ack_at);
+
+            acks.ack(PacketSeq::new(round));
+            let mut packet = Vec::new();
+            packet
+                .write(PacketHeader {
+                    seq: PacketSeq::new(round),
+                    acks,
+                    ..Default::default()
+                })
+                .unwrap();
+            recv_on(&mut transport, &config, ack_at, &packet).unwrap();
+            transport.recv.acks.0.clear();
+            now = ack_at;
+        }
+
+        let total = transport.stats().spurious_losses.0;
+        (
+            first_half,
+            total - first_half,
+            transport.packet_lost_threshold_factor(&config),
+        )
+    }
+
+    #[test]
+    fn adaptive_loss_threshold_raised_by_late_acks() {
+        let config = TransportConfig::default();
+        let (fixed_first, fixed_second, fixed_factor) = spurious_losses_with_delay_spikes(false);
+        let (adaptive_first, adaptive_second, adaptive_factor) =
+            spurious_losses_with_delay_spikes(true);
+
+        // the fixed factor never changes, and keeps misjudging delay spikes
+        assert!((fixed_factor - config.packet_lost_threshold_factor).abs() < f64::EPSILON);
+        assert!(fixed_second > 0);
+
+        // late acks raise the adaptive factor..
+        assert!(adaptive_factor > config.packet_lost_threshold_factor);
+        // ..so that delay spikes stop being declared as losses over time
+        assert!(adaptive_first > 0);
+        assert!(adaptive_second < adaptive_first);
+        assert!(adaptive_first + adaptive_second < fixed_first + fixed_second);
+    }
+
+    #[test]
+    fn adaptive_loss_threshold_lowered_by_confirmed_losses() {
+        let now = Instant::now();
+        let mut transport = transport(now);
+        let config = TransportConfig {
+            adaptive_loss_threshold: true,
+            ..Default::default()
+        };
+        transport.adapted_lost_threshold_factor = Some(3.0);
+
+        for seq in 0..5 {
+            transport
+                .flushed_packets
+                .insert(seq, FlushedPacket::new(now))
+                .lost = true;
+        }
+        // the peer received packets up to 4, but not 0 or 1
+        let mut acks = Acknowledge::default();
+        for seq in 2..5 {
+            acks.ack(PacketSeq::new(seq));
+        }
+        assert_eq!(2, confirm_losses(&mut transport.flushed_packets, acks));
+        // already confirmed losses aren't counted again
+        assert_eq!(0, confirm_losses(&mut transport.flushed_packets, acks));
+
+        adapt_lost_threshold_factor(&mut transport, &config, 0, 2);
+        assert!(transport.packet_lost_threshold_factor(&config) < 3.0);
+    }
+
+    #[test]
+    fn latest_lane_surfaces_only_newest() {
+        let now = Instant::now();
+        let session = Session::new(now, IP_MTU);
+        let lanes = [LaneKind::UnreliableLatest];
+        let mut transport = Transport::new(&session, lanes, lanes, now).unwrap();
+        let config = TransportConfig::default();
+
+        recv_on(&mut transport, &config, now, &msg_packet(0, 0, b"one")).unwrap();
+        recv_on(&mut transport, &config, now, &msg_packet(1, 2, b"three")).unwrap();
+        // older than what we already have, so it's dropped
+        recv_on(&mut transport, &config, now, &msg_packet(2, 1, b"two")).unwrap();
+        assert_eq!(vec![b"three".to_vec()], recv_payloads(&mut transport));
+
+        recv_on(&mut transport, &config, now, &msg_packet(3, 3, b"four")).unwrap();
+        assert_eq!(vec![b"four".to_vec()], recv_payloads(&mut transport));
+    }
+
+    #[test]
+    fn collect_msgs_drains_all() {
+        let now = Instant::now();
+        let mut transport = transport(now);
+        let config = TransportConfig::default();
+
+        recv_on(&mut transport, &config, now, &msg_packet(0, 0, b"one")).unwrap();
+        recv_on(&mut transport, &config, now, &msg_packet(1, 1, b"two")).unwrap();
+
+        let msgs = transport.recv.collect_msgs();
+        assert_eq!(
+            vec![b"one".to_vec(), b"two".to_vec()],
+            msgs.into_iter().map(|msg| msg.payload).collect::<Vec<_>>()
+        );
+        assert!(transport.recv.collect_msgs().is_empty());
+        assert_eq!(0, transport.recv.msgs.drain().count());
+    }
+
+    fn evicted_by(policy: UnreliableDropPolicy) -> Vec<(u8, u16)> {
+        fn frag_packet(lane: u8, msg_seq: u16, frag_index: u8, payload: &[u8]) -> Vec<u8> {
+            let mut packet = Vec::new();
+            packet.write(PacketHeader::default()).unwrap();
+            packet
+                .write(Fragment {
+                    header: FragmentHeader {
+                        lane: LaneIndex(lane.into()),
+                        seq: MessageSeq::new(msg_seq),
+                        position: FragmentPosition::non_last(frag_index.into()).unwrap(),
+                    },
+                    payload: FragmentPayload(Bytes::copy_from_slice(payload)),
+                })
+                .unwrap();
+            packet
+        }
+
+        const PARTIAL_MSGS: [(u8, u16); 3] = [(1, 0), (0, 0), (2, 0)];
+
+        let now = Instant::now();
+        let session = Session::new(now, IP_MTU);
+        let lanes = [LaneKind::UnreliableUnordered; 3];
+        let mut transport = Transport::new(&session, lanes, lanes, now).unwrap();
+        let mut config = TransportConfig {
+            unreliable_drop_policy: policy,
+            ..Default::default()
+        };
+        let payload = vec![0; transport.send.max_frag_len];
+
+        // oldest
+        recv_on(
+            &mut transport,
+            &config,
+            now,
+            &frag_packet(1, 0, 0, &payload),
+        )
+        .unwrap();
+        // largest, since it has to make room for fragments 0 and 1 too
+        let recv_at = now + Duration::from_millis(1);
+        recv_on(
+            &mut transport,
+            &config,
+            recv_at,
+            &frag_packet(0, 0, 2, &payload),
+        )
+        .unwrap();
+        // lowest priority lane
+        let recv_at = now + Duration::from_millis(2);
+        recv_on(
+            &mut transport,
+            &config,
+            recv_at,
+            &frag_packet(2, 0, 0, &payload),
+        )
+        .unwrap();
+
+        // not enough memory for another fragment, but enough after evicting one
+        config.max_memory_usage = transport.memory_used() + payload.len() / 2;
+        let recv_at = now + Duration::from_millis(3);
+        recv_on(
+            &mut transport,
+            &config,
+            recv_at,
+            &frag_packet(0, 1, 0, &payload),
+        )
+        .unwrap();
+        let incoming_buffered = transport.recv.lanes[0]
+            .frags
+            .msgs
+            .get(&MessageSeq::new(1))
+            .is_some_and(|buf| !buf.payload.is_empty());
+        assert_eq!(
+            policy != UnreliableDropPolicy::DropIncoming,
+            incoming_buffered
+        );
+
+        PARTIAL_MSGS
+            .into_iter()
+            .filter(|(lane, msg_seq)| {
+                !transport.recv.lanes[usize::from(*lane)]
+                    .frags
+                    .msgs
+                    .contains_key(&MessageSeq::new(*msg_seq))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn unreliable_drop_incoming() {
+        assert_eq!(
+            Vec::<(u8, u16)>::new(),
+            evicted_by(UnreliableDropPolicy::DropIncoming)
+        );
+    }
+
+    #[test]
+    fn unreliable_drop_oldest() {
+        assert_eq!(vec![(1, 0)], evicted_by(UnreliableDropPolicy::DropOldest));
+    }
+
+    #[test]
+    fn unreliable_drop_largest() {
+        assert_eq!(vec![(0, 0)], evicted_by(UnreliableDropPolicy::DropLargest));
+    }
+
+    #[test]
+    fn unreliable_drop_lowest_priority_lane() {
+        assert_eq!(
+            vec![(2, 0)],
+            evicted_by(UnreliableDropPolicy::DropLowestPriorityLane)
+        );
+    }
+
+    fn reassemblies_after_flood(policy: UnreliableDropPolicy) -> Vec<u16> {
+        const MAX: usize = 4;
+        const NUM_MSGS: u16 = 64;
+
+        let now = Instant::now();
+        let session = Session::new(now, IP_MTU);
+        let lanes = [LaneKind::UnreliableUnordered];
+        let mut transport = Transport::new(&session, lanes, lanes, now).unwrap();
+        let config = TransportConfig {
+            max_concurrent_reassemblies: MAX,
+            unreliable_drop_policy: policy,
+            ..Default::default()
+        };
+        let payload = Bytes::from(vec![0; transport.send.max_frag_len]);
+
+        // only ever send the first fragment of each message
+        for msg_seq in 0..NUM_MSGS {
+            let mut packet = Vec::new();
+            packet.write(PacketHeader::default()).unwrap();
+            packet
+                .write(Fragment {
+                    header: FragmentHeader {
+                        lane: LaneIndex(0),
+                        seq: MessageSeq::new(msg_seq),
+                        position: FragmentPosition::non_last(0).unwrap(),
+                    },
+                    payload: FragmentPayload(payload.clone()),
+                })
+                .unwrap();
+            let recv_at = now + Duration::from_millis(msg_seq.into());
+            recv_on(&mut transport, &config, recv_at, &packet).unwrap();
+            assert!(transport.recv.lanes[0].frags.len() <= MAX);
+        }
+
+        let num_dropped = if policy == UnreliableDropPolicy::DropIncoming {
+            usize::from(NUM_MSGS) - MAX
+        } else {
+            0
+        };
+        assert_eq!(num_dropped, transport.recv.errors.too_many_reassemblies.0);
+
+        // a single-fragment message is still received while at the limit
+        recv_on(
+            &mut transport,
+            &config,
+            now,
+            &msg_packet(0, NUM_MSGS, b"hi"),
+        )
+        .unwrap();
+        assert_eq!(vec![b"hi".to_vec()], recv_payloads(&mut transport));
+
+        let mut msg_seqs = transport.recv.lanes[0]
+            .frags
+            .msgs
+            .keys()
+            .map(|msg_seq| msg_seq.0.0)
+            .collect::<Vec<_>>();
+        msg_seqs.sort_unstable();
+        msg_seqs
+    }
+
+    #[test]
+    fn max_concurrent_reassemblies_drop_incoming() {
+        assert_eq!(
+            vec![0, 1, 2, 3],
+            reassemblies_after_flood(UnreliableDropPolicy::DropIncoming)
+        );
+    }
+
+    #[test]
+    fn max_concurrent_reassemblies_drop_oldest() {
+        assert_eq!(
+            vec![60, 61, 62, 63],
+            reassemblies_after_flood(UnreliableDropPolicy::DropOldest)
+        );
+    }
+
+    #[test]
+    fn ignore_ack_for_unsent_packet() {
+        let now = Instant::now();
+        let config = TransportConfig::default();
+        let mut transport = transport(now);
+        transport.send.bytes_bucket = TokenBucket::new(usize::MAX);
+        let msg_key = transport
+            .send
+            .push(LaneIndex(0), Bytes::from_static(b"hi"), now)
+            .unwrap();
+        assert_eq!(1, flush_on(&mut transport, now, IP_MTU).count());
+
+        // acks our packet 0, which we did send, and packet 10, which we didn't
+        let mut packet = Vec::new();
+        packet
+            .write(PacketHeader {
+                seq: PacketSeq::new(0),
+                acks: Acknowledge {
+                    last_recv: PacketSeq::new(10),
+                    bits: 0b1 | (0b1 << 10),
+                },
+                ..Default::default()
+            })
+            .unwrap();
+        recv_on(&mut transport, &config, now, &packet).unwrap();
+
+        assert_eq!(
+            vec![msg_key],
+            transport.recv.acks.drain().collect::<Vec<_>>()
+        );
+        assert_eq!(1, transport.stats.packet_acks_recv.0);
+        assert_eq!(1, transport.stats.unknown_packet_acks_recv.0);
+        assert_eq!(0, transport.num_unacked_packets());
+        assert_eq!(PacketSeq::new(1), transport.send.next_packet_seq);
+
+        // our acks of the peer's packets are unaffected
+        let mut expected_peer_acks = Acknowledge::default();
+        expected_peer_acks.ack(PacketSeq::new(0));
+        assert_eq!(expected_peer_acks, transport.peer_acks);
+
+        // we can keep sending normally afterwards
+        transport
+            .send
+            .push(LaneIndex(0), Bytes::from_static(b"hi again"), now)
+            .unwrap();
+        assert_eq!(1, flush_on(&mut transport, now, IP_MTU).count());
+        assert_eq!(1, transport.num_unacked_packets());
+    }
+
+    #[test]
+    fn learn_peer_mtu() {
+        const MTU_A: usize = 1200;
+        const MTU_B: usize = 400;
+
+        fn send_all(from: &mut Transport, to: &mut Transport, mtu: usize, now: Instant) {
+            from.send.bytes_bucket = TokenBucket::new(usize::MAX);
+            let config = TransportConfig::default();
+            for packet in flush_on(from, now, mtu) {
+                recv_on(to, &config, now, &packet).unwrap();
+            }
+        }
+
+        let now = Instant::now();
+        let session = Session::new(now, MTU_B);
+        let mut a = Transport::new(&session, LANES, LANES, now).unwrap();
+        let mut b = Transport::new(&session, LANES, LANES, now).unwrap();
+        assert_eq!(None, a.peer_mtu());
+        assert_eq!(None, b.peer_mtu());
+
+        send_all(&mut a, &mut b, MTU_A, now);
+        send_all(&mut b, &mut a, MTU_B, now);
+        assert_eq!(Some(MTU_B), a.peer_mtu());
+        assert_eq!(Some(MTU_A), b.peer_mtu());
+
+        assert!(is_mtu_asymmetric(MTU_A, MTU_B));
+        assert!(is_mtu_asymmetric(MTU_B, MTU_A));
+        assert!(!is_mtu_asymmetric(MTU_A, MTU_A + 100));
+    }
+
+    #[test]
+    fn mtu_only_sent_until_acked() {
+        fn header_mtu(transport: &mut Transport, mtu: usize, now: Instant) -> Option<u32> {
+            let mut packet = flush_on(transport, now, mtu).next().unwrap();
+            packet.read::<PacketHeader>().unwrap().mtu
+        }
+
+        let now = Instant::now();
+        let session = Session::new(now, IP_MTU);
+        let mut a = Transport::new(&session, LANES, LANES, now).unwrap();
+        let mut b = Transport::new(&session, LANES, LANES, now).unwrap();
+        a.send.bytes_bucket = TokenBucket::new(usize::MAX);
+        b.send.bytes_bucket = TokenBucket::new(usize::MAX);
+        let config = TransportConfig::default();
+        let mtu = u32::try_from(IP_MTU).unwrap();
+
+        // sent until the peer acks a packet carrying it..
+        assert_eq!(Some(mtu), header_mtu(&mut a, IP_MTU, now));
+        let packet = flush_on(&mut a, now, IP_MTU).next().unwrap();
+        recv_on(&mut b, &config, now, &packet).unwrap();
+        assert_eq!(Some(IP_MTU), b.peer_mtu());
+        for packet in flush_on(&mut b, now, IP_MTU).collect::<Vec<_>>() {
+            recv_on(&mut a, &config, now, &packet).unwrap();
+        }
+        assert_eq!(None, header_mtu(&mut a, IP_MTU, now));
+
+        // ..and again once it changes
+        assert_eq!(Some(mtu + 1), header_mtu(&mut a, IP_MTU + 1, now));
+    }
+
+    #[test]
+    fn warn_on_mtu_asymmetry() {
+        const OUR_MTU: usize = 1200;
+        const FAR: usize = 400;
+        const NEAR: usize = 1000;
+
+        // only once the peer's MTU is learned or changes
+        assert_eq!(Some(FAR), asymmetric_peer_mtu(OUR_MTU, None, Some(FAR)));
+        assert_eq!(None, asymmetric_peer_mtu(OUR_MTU, Some(FAR), Some(FAR)));
+        assert_eq!(None, asymmetric_peer_mtu(OUR_MTU, Some(FAR), Some(NEAR)));
+        assert_eq!(
+            Some(FAR),
+            asymmetric_peer_mtu(OUR_MTU, Some(NEAR), Some(FAR))
+        );
+        assert_eq!(None, asymmetric_peer_mtu(OUR_MTU, None, None));
+    }
+
+    fn fuzz_sender_packets(now: Instant) -> Vec<Bytes> {
+        let mut sender = transport(now);
+        sender.send.bytes_bucket = TokenBucket::new(usize::MAX);
+        sender
+            .send
+            .push(LaneIndex(0), Bytes::from_static(b"small"), now)
+            .unwrap();
+        sender
+            .send
+            .push(LaneIndex(0), Bytes::from(vec![0xab; IP_MTU * 3]), now)
+            .unwrap();
+        flush_on(&mut sender, now, IP_MTU).collect()
+    }
+
+    #[test]
+    fn fuzz_input_truncated_packets() {
+        let now = Instant::now();
+        let packets = fuzz_sender_packets(now);
+        assert!(packets.len() > 1);
+
+        let mut transport = transport(now);
+        assert!(matches!(
+            transport.recv_fuzz_input(&[], now),
+            Err(RecvError::ReadHeader)
+        ));
+        for packet in &packets {
+            for len in 0..packet.len() {
+                _ = transport.recv_fuzz_input(&packet[..len], now);
+            }
+        }
+
+        // truncated packets must not have stopped the transport from
+        // receiving the real messages
+        let mut transport = self::transport(now);
+        for packet in &packets {
+            for len in 0..packet.len() {
+                _ = transport.recv_fuzz_input(&packet[..len], now);
+            }
+            transport.recv_fuzz_input(packet, now).unwrap();
+        }
+        let payloads = recv_payloads(&mut transport);
+        assert_eq!(2, payloads.len());
+        assert_eq!(b"small".as_slice(), payloads[0]);
+        assert_eq!(vec![0xab; IP_MTU * 3], payloads[1]);
+    }
+
+    #[test]
+    fn fuzz_input_garbage_packets() {
+        // deterministic xorshift, so that failures are reproducible
+        let mut state = 0x2545_f491_4f6c_dd1d_u64;
+        let mut next = move || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        };
+
+        let now = Instant::now();
+        let mut transport = transport(now);
+        for _ in 0..1000 {
+            let len = usize::try_from(next() % 256).unwrap();
+            let packet = (0..len)
+                .map(|_| next().to_le_bytes()[0])
+                .collect::<Vec<_>>();
+            _ = transport.recv_fuzz_input(&packet, now);
+        }
+
+        // corrupt single bytes of otherwise valid packets
+        let packets = fuzz_sender_packets(now);
+        for _ in 0..1000 {
+            let mut packet = packets[usize::try_from(next()).unwrap() % packets.len()].to_vec();
+            let index = usize::try_from(next()).unwrap() % packet.len();
+            packet[index] = next().to_le_bytes()[0];
+            _ = transport.recv_fuzz_input(&packet, now);
+        }
+        _ = recv_payloads(&mut transport);
+    }
+
+    /// Sends `NUM_MSGS` messages on an unreliable lane, with a conditioner
+    /// that drops the packets of every odd message, and gets the keys of the
+    /// messages which were delivered, and the receipts the sender got for
+    /// them.
+    fn unreliable_receipts(receipts: bool) -> (Transport, Vec<MessageKey>, Vec<MessageKey>) {
+        const LANES: [LaneKind; 1] = [LaneKind::UnreliableUnordered];
+        const NUM_MSGS: u8 = 10;
+
+        let config = TransportConfig::default();
+        let now = Instant::now();
+        let session = Session::new(now, IP_MTU);
+        let mut sender = Transport::new(&session, LANES, LANES, now).unwrap();
+        let mut receiver = Transport::new(&session, LANES, LANES, now).unwrap();
+        sender.send.bytes_bucket = TokenBucket::new(usize::MAX);
+        receiver.send.bytes_bucket = TokenBucket::new(usize::MAX);
+        sender.send.set_unreliable_receipts(LaneIndex(0), receipts);
+
+        let mut delivered = Vec::new();
+        for i in 0..NUM_MSGS {
+            let key = sender
+                .send
+                .push(LaneIndex(0), Bytes::from(vec![i]), now)
+                .unwrap();
+            let packets = flush_on(&mut sender, now, IP_MTU).collect::<Vec<_>>();
+            if i % 2 == 0 {
+                for packet in packets {
+                    recv_on(&mut receiver, &config, now, &packet).unwrap();
+                }
+                delivered.push(key);
+            }
+        }
+        assert_eq!(delivered.len(), recv_payloads(&mut receiver).len());
+
+        for packet in flush_on(&mut receiver, now, IP_MTU) {
+            recv_on(&mut sender, &config, now, &packet).unwrap();
+        }
+        let mut acks = sender.recv.acks.drain().collect::<Vec<_>>();
+        acks.sort_unstable_by_key(|key| key.seq.0.0);
+        (sender, delivered, acks)
+    }
+
+    #[test]
+    fn unreliable_receipts_only_for_delivered_msgs() {
+        let (mut sender, delivered, receipts) = unreliable_receipts(true);
+        assert_eq!(delivered, receipts);
+
+        // the dropped messages are still waiting for a receipt...
+        let lane = &sender.send.lanes[0];
+        assert_eq!(5, lane.num_in_flight_msgs());
+
+        // ...until their packets are declared lost - they are never resent
+        let later = Instant::now() + Duration::from_secs(10);
+        detect_lost_packets(&mut sender, &TransportConfig::default(), later);
+        for packet in flush_on(&mut sender, later, IP_MTU) {
+            let mut packet = &packet[..];
+            packet.read::<PacketHeader>().unwrap();
+            assert!(!packet.has_remaining());
+        }
+        assert_eq!(0, sender.send.lanes[0].num_in_flight_msgs());
+        assert_eq!(0, sender.send.lanes[0].num_queued_msgs());
+    }
+
+    #[test]
+    fn unreliable_no_receipts_by_default() {
+        let (sender, delivered, receipts) = unreliable_receipts(false);
+        assert!(!delivered.is_empty());
+        assert!(receipts.is_empty());
+        assert_eq!(0, sender.send.lanes[0].num_in_flight_msgs());
+    }
+
+    #[test]
+    fn delivery_reports() {
+        const LANES: [LaneKind; 1] = [LaneKind::UnreliableUnordered];
+        const NUM_MSGS: u8 = 10;
+
+        let config = TransportConfig::default();
+        let now = Instant::now();
+        let session = Session::new(now, IP_MTU);
+        let mut sender = Transport::new(&session, LANES, LANES, now).unwrap();
+        let mut receiver = Transport::new(&session, LANES, LANES, now).unwrap();
+        sender.send.bytes_bucket = TokenBucket::new(usize::MAX);
+        receiver.send.bytes_bucket = TokenBucket::new(usize::MAX);
+        sender.send.set_unreliable_receipts(LaneIndex(0), true);
+
+        // drop the packets of every odd message
+        let (mut delivered, mut dropped) = (Vec::new(), Vec::new());
+        for i in 0..NUM_MSGS {
+            let key = sender
+                .send
+                .push(LaneIndex(0), Bytes::from(vec![i]), now)
+                .unwrap();
+            sender.track_delivery(key);
+            let packets = flush_on(&mut sender, now, IP_MTU).collect::<Vec<_>>();
+            if i % 2 == 0 {
+                for packet in packets {
+                    recv_on(&mut receiver, &config, now, &packet).unwrap();
+                }
+                delivered.push(key);
+            } else {
+                dropped.push(key);
+            }
+        }
+        _ = recv_payloads(&mut receiver);
+
+        let reports = |sender: &mut Transport, delivery| {
+            let mut keys = sender
+                .recv
+                .deliveries
+                .drain()
+                .map(|report| {
+                    assert_eq!(delivery, report.delivery);
+                    report.key
+                })
+                .collect::<Vec<_>>();
+            keys.sort_unstable_by_key(|key| key.seq.0.0);
+            keys
+        };
+
+        // delivered messages are reported as soon as they're acked...
+        for packet in flush_on(&mut receiver, now, IP_MTU) {
+            recv_on(&mut sender, &config, now, &packet).unwrap();
+        }
+        assert_eq!(delivered, reports(&mut sender, Delivery::Delivered));
+
+        // ...and dropped ones once their packets are declared lost
+        let later = now + Duration::from_secs(10);
+        detect_lost_packets(&mut sender, &config, later);
+        let deliveries = sender.send.lanes[0]
+            .take_dropped_deliveries(LaneIndex(0))
+            .collect::<Vec<_>>();
+        sender.recv.deliveries.0.extend(deliveries);
+        assert_eq!(dropped, reports(&mut sender, Delivery::Lost));
+
+        // every message is only reported once
+        assert_eq!(
+            0,
+            sender.send.lanes[0]
+                .take_dropped_deliveries(LaneIndex(0))
+                .count()
+        );
+
+        // we can't find out what happened to messages we're no longer sending
+        sender.track_delivery(delivered[0]);
+        assert_eq!(vec![delivered[0]], reports(&mut sender, Delivery::Unknown));
+    }
+
+    #[test]
+    fn message_ack_progress() {
+        let config = TransportConfig::default();
+        let now = Instant::now();
+        let mut sender = transport(now);
+        let mut receiver = transport(now);
+        sender.send.bytes_bucket = TokenBucket::new(usize::MAX);
+        receiver.send.bytes_bucket = TokenBucket::new(usize::MAX);
+
+        let key = sender
+            .send
+            .push(LaneIndex(0), Bytes::from(vec![0xab; IP_MTU * 3]), now)
+            .unwrap();
+        assert_eq!(Some(0.0), sender.message_ack_progress(key));
+
+        // flushing alone doesn't count as progress
+        let packets = flush_on(&mut sender, now, IP_MTU).collect::<Vec<_>>();
+        assert!(packets.len() > 1);
+        assert_eq!(Some(0.0), sender.message_ack_progress(key));
+
+        // only the first packet arrives
+        recv_on(&mut receiver, &config, now, &packets[0]).unwrap();
+        for packet in flush_on(&mut receiver, now, IP_MTU) {
+            recv_on(&mut sender, &config, now, &packet).unwrap();
+        }
+        let num_frags = sender.send.lanes[0].sent_msgs[&key.seq].frags.len();
+        let num_acked = num_frags
+            - sender.send.lanes[0].sent_msgs[&key.seq]
+                .frags
+                .iter()
+                .flatten()
+                .count();
+        assert!(num_acked > 0 && num_acked < num_frags);
+        #[expect(clippy::cast_precision_loss, reason = "testing")]
+        let expected = num_acked as f32 / num_frags as f32;
+        assert_eq!(Some(expected), sender.message_ack_progress(key));
+
+        for packet in &packets[1..] {
+            recv_on(&mut receiver, &config, now, packet).unwrap();
+        }
+        for packet in flush_on(&mut receiver, now, IP_MTU) {
+            recv_on(&mut sender, &config, now, &packet).unwrap();
+        }
+        assert_eq!(Some(1.0), sender.message_ack_progress(key));
+
+        // once it's fully acked, we forget about it on the next flush
+        _ = flush_on(&mut sender, now, IP_MTU).count();
+        assert_eq!(None, sender.message_ack_progress(key));
+    }
+}
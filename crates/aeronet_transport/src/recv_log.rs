@@ -0,0 +1,271 @@
+//! Logging metadata of every received message into a writer.
+//!
+//! See [`RecvLog`].
+
+use {
+    crate::{RecvMessage, Transport, lane::LaneIndex},
+    bevy_ecs::prelude::*,
+    core::{fmt, time::Duration},
+    std::io::{self, Read},
+    tracing::warn,
+    web_time::Instant,
+};
+
+/// Magic bytes written at the start of a [`RecvLog`].
+///
+/// The last byte is the version of the log format.
+pub const RECV_LOG_MAGIC: [u8; 8] = *b"AERNMSG1";
+
+/// Records metadata of every message received by a [`Transport`] into an
+/// append-only writer.
+///
+/// When this component is present on a session, every message in
+/// [`TransportRecv::msgs`] is recorded in [`TransportSet::Poll`], right after
+/// the transport has delivered it. Each record stores the lane the message was
+/// received on, when it was received, its length, and a hash of its contents.
+/// The payload itself is only recorded if enabled via
+/// [`RecvLog::with_payloads`].
+///
+/// This is the message-level counterpart to [`PacketTrace`], intended for
+/// debugging and replaying sessions: the hashes let you check that two runs
+/// received the same messages, without the cost of storing every payload.
+/// Writes are performed synchronously in the system which records the
+/// messages, so you should use a buffered writer such as [`io::BufWriter`]. If
+/// writing fails, a warning is logged and no more messages are recorded.
+///
+/// # Format
+///
+/// The log starts with [`RECV_LOG_MAGIC`], followed by one record per message.
+/// Each record consists of:
+/// - lane index: 2 bytes, little-endian
+/// - timestamp: 8 bytes, little-endian microseconds since the log was created
+/// - payload length: 4 bytes, little-endian
+/// - payload hash: 8 bytes, little-endian 64-bit FNV-1a hash of the payload
+/// - payload flag: 1 byte, `1` if the payload follows, otherwise `0`
+/// - payload: the raw bytes of the message, only if the payload flag is `1`
+///
+/// Use [`read_recv_log`] to parse a log back into records.
+///
+/// # Examples
+///
+/// ```
+/// use {
+///     aeronet_transport::recv_log::RecvLog,
+///     bevy_ecs::prelude::*,
+///     std::{fs::File, io::BufWriter},
+/// };
+///
+/// # fn run(mut commands: Commands, session: Entity) -> std::io::Result<()> {
+/// let file = File::create("session.msglog")?;
+/// commands
+///     .entity(session)
+///     .insert(RecvLog::new(BufWriter::new(file))?);
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`TransportRecv::msgs`]: crate::recv::TransportRecv::msgs
+/// [`TransportSet::Poll`]: crate::TransportSet::Poll
+/// [`PacketTrace`]: aeronet_io::packet::PacketTrace
+#[derive(Component)]
+pub struct RecvLog {
+    writer: Option<Box<dyn io::Write + Send + Sync>>,
+    started_at: Instant,
+    payloads: bool,
+}
+
+/// Message read from a [`RecvLog`] by [`read_recv_log`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoggedMessage {
+    /// Lane index on which this message was received.
+    pub lane: LaneIndex,
+    /// Time since the log was created until the message was received.
+    pub elapsed: Duration,
+    /// Length of this message's payload in bytes.
+    pub len: usize,
+    /// Hash of this message's payload.
+    ///
+    /// See [`payload_hash`].
+    pub hash: u64,
+    /// Raw byte data of this message, if the log was recording payloads.
+    pub payload: Option<Vec<u8>>,
+}
+
+impl RecvLog {
+    /// Creates a new log which records message metadata into the given
+    /// writer.
+    ///
+    /// Payloads are not recorded by default - see [`RecvLog::with_payloads`].
+    ///
+    /// # Errors
+    ///
+    /// Errors if [`RECV_LOG_MAGIC`] could not be written.
+    pub fn new(mut writer: impl io::Write + Send + Sync + 'static) -> io::Result<Self> {
+        writer.write_all(&RECV_LOG_MAGIC)?;
+        Ok(Self {
+            writer: Some(Box::new(writer)),
+            started_at: Instant::now(),
+            payloads: false,
+        })
+    }
+
+    /// Makes this log also record the full payload of every message.
+    #[must_use]
+    pub const fn with_payloads(mut self) -> Self {
+        self.payloads = true;
+        self
+    }
+
+    /// Gets the instant at which this log was created.
+    ///
+    /// Timestamps in the log are relative to this instant.
+    #[must_use]
+    pub const fn started_at(&self) -> Instant {
+        self.started_at
+    }
+
+    /// Gets if this log records the full payload of every message.
+    #[must_use]
+    pub const fn records_payloads(&self) -> bool {
+        self.payloads
+    }
+
+    /// Gets if this log is still recording messages.
+    ///
+    /// This is `false` if writing to the writer has failed.
+    #[must_use]
+    pub const fn is_recording(&self) -> bool {
+        self.writer.is_some()
+    }
+
+    fn record(&mut self, entity: Entity, msg: &RecvMessage) {
+        let Some(writer) = &mut self.writer else {
+            return;
+        };
+        let elapsed = msg.recv_at.saturating_duration_since(self.started_at);
+        if let Err(err) = write_record(writer, msg, elapsed, self.payloads) {
+            warn!("{entity} failed to write to recv log, no longer recording: {err:?}");
+            self.writer = None;
+        }
+    }
+}
+
+impl fmt::Debug for RecvLog {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RecvLog")
+            .field("recording", &self.is_recording())
+            .field("started_at", &self.started_at)
+            .field("payloads", &self.payloads)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Computes the hash of a message payload which is stored in a [`RecvLog`].
+///
+/// This is the 64-bit FNV-1a hash, which is stable across platforms and
+/// versions, so logs from different runs can be compared.
+#[must_use]
+pub fn payload_hash(payload: &[u8]) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+
+    payload.iter().fold(OFFSET_BASIS, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(PRIME)
+    })
+}
+
+fn write_record(
+    writer: &mut dyn io::Write,
+    msg: &RecvMessage,
+    elapsed: Duration,
+    payloads: bool,
+) -> io::Result<()> {
+    let timestamp = u64::try_from(elapsed.as_micros()).unwrap_or(u64::MAX);
+    let len = u32::try_from(msg.payload.len())
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "message too large"))?;
+    writer.write_all(&msg.lane.0.to_le_bytes())?;
+    writer.write_all(&timestamp.to_le_bytes())?;
+    writer.write_all(&len.to_le_bytes())?;
+    writer.write_all(&payload_hash(&msg.payload).to_le_bytes())?;
+    writer.write_all(&[u8::from(payloads)])?;
+    if payloads {
+        writer.write_all(&msg.payload)?;
+    }
+    Ok(())
+}
+
+/// Reads all messages recorded by a [`RecvLog`].
+///
+/// # Errors
+///
+/// Errors if the reader fails, or if the data is not a valid log.
+pub fn read_recv_log(mut reader: impl Read) -> io::Result<Vec<LoggedMessage>> {
+    let invalid = |msg| io::Error::new(io::ErrorKind::InvalidData, msg);
+
+    let mut magic = [0; RECV_LOG_MAGIC.len()];
+    reader.read_exact(&mut magic)?;
+    if magic != RECV_LOG_MAGIC {
+        return Err(invalid("invalid recv log magic"));
+    }
+
+    let mut msgs = Vec::new();
+    loop {
+        let mut lane = [0; 2];
+        match reader.read_exact(&mut lane) {
+            Ok(()) => {}
+            Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => return Ok(msgs),
+            Err(err) => return Err(err),
+        }
+        let lane = LaneIndex(u16::from_le_bytes(lane));
+
+        let mut timestamp = [0; 8];
+        reader.read_exact(&mut timestamp)?;
+        let elapsed = Duration::from_micros(u64::from_le_bytes(timestamp));
+
+        let mut len = [0; 4];
+        reader.read_exact(&mut len)?;
+        let len =
+            usize::try_from(u32::from_le_bytes(len)).map_err(|_| invalid("message too large"))?;
+
+        let mut hash = [0; 8];
+        reader.read_exact(&mut hash)?;
+        let hash = u64::from_le_bytes(hash);
+
+        let mut flag = [0; 1];
+        reader.read_exact(&mut flag)?;
+        let payload = match flag[0] {
+            0 => None,
+            1 => {
+                // don't trust `len` for the allocation, since a corrupt log
+                // could make us allocate gigabytes before failing to read
+                let mut payload = Vec::new();
+                let max_len = u64::try_from(len).map_err(|_| invalid("message too large"))?;
+                reader.by_ref().take(max_len).read_to_end(&mut payload)?;
+                if payload.len() != len {
+                    return Err(io::Error::new(
+                        io::ErrorKind::UnexpectedEof,
+                        "recv log ended in the middle of a payload",
+                    ));
+                }
+                Some(payload)
+            }
+            _ => return Err(invalid("invalid payload flag")),
+        };
+
+        msgs.push(LoggedMessage {
+            lane,
+            elapsed,
+            len,
+            hash,
+            payload,
+        });
+    }
+}
+
+pub(crate) fn log_recv_msgs(mut sessions: Query<(Entity, &Transport, &mut RecvLog)>) {
+    for (entity, transport, mut log) in &mut sessions {
+        for msg in &transport.recv.msgs.0 {
+            log.record(entity, msg);
+        }
+    }
+}
@@ -6,6 +6,7 @@ use {core::time::Duration, typesize::derive::TypeSize};
 ///
 /// This is based on [`quinn-proto`'s `RttEstimator`](https://github.com/quinn-rs/quinn/blob/411abe9/quinn-proto/src/connection/paths.rs#L151).
 #[derive(Debug, Clone, TypeSize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct RttEstimator {
     latest: Duration,
     smoothed: Duration,
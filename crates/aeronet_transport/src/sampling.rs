@@ -161,39 +161,28 @@ pub struct SessionStatsSample {
     ///
     /// # Algorithm
     ///
-    /// We want to figure out how many packets have been lost during this
-    /// sample. To do this, we find out how many packets, that we sent out
-    /// earlier, should have been acknowledged by our peer by now; and how many
-    /// of those have actually been acknowledged. "By now" is defined as a
-    /// function of the current RTT estimate. Currently it is [the PTO]
-    /// multiplied by [`TransportConfig::packet_lost_threshold_factor`],
-    /// however the implementation may change this in the future.
+    /// A packet is declared lost once it has gone unacknowledged for longer
+    /// than [the PTO] multiplied by
+    /// [`TransportConfig::packet_lost_threshold_factor`] (see
+    /// [`MessageStats::packets_lost`]). However, the acknowledgement may have
+    /// just been late - if the peer acknowledges a packet after we declared it
+    /// lost, the loss was spurious, and is not counted (see
+    /// [`MessageStats::spurious_losses`]).
     ///
-    /// Let's assume that we are calculating sample 100, and our RTT is such
-    /// that we expect to have received acknowledgements for all packets sent up
-    /// to sample 90 by now.
+    /// We look at a recent window of samples, roughly as long as that loss
+    /// threshold, and figure out how many packets we know the fate of - either
+    /// they were acknowledged, or they were really lost.
     ///
-    /// Up to sample 90, we received 950 acks and sent 1000 messages total.
-    /// Therefore, at sample 100, we expect to have 1000 acks - we expect to
-    /// have 50 more acks than we had at sample 90.
+    /// Let's assume that in this window, we received 90 acks and declared 15
+    /// packets lost, but 5 of those acks were for packets which we had already
+    /// declared lost. Then:
+    /// - we have really lost 10 (15 - 5) packets
+    /// - we know the fate of 100 (90 + 10) packets
+    /// - we have 10% packet loss
     ///
-    /// - If by now we have received 1000 acks, then:
-    ///   - we have received 50 (1000 - 950) extra acks
-    ///   - we have lost 0 (50 - 50) packets
-    ///   - we have 0% packet loss
-    ///   - we have a very accurate RTT estimate
-    /// - If we have more than 1000 acks, then:
-    ///   - we have received more than 50 extra acks
-    ///   - we have lost 0 packets
-    ///   - we have 0% packet loss
-    ///   - our RTT estimate is too high - the peer actually acknowledges
-    ///     packets faster than we think
-    /// - If we have between 950 and 1000 acknowledgements, we have some
-    ///   percentage of packet loss. If we have 960 acks, then:
-    ///   - we have received 10 extra acks
-    ///   - we have lost 40 (50 - 10) packets
-    ///   - we have 90% packet loss
-    /// - If we still only have 950 acks, we have 100% packet loss.
+    /// A late ack may arrive in a later window than the one in which its packet
+    /// was declared lost. In that case, the later window will have fewer
+    /// losses, but this value is never negative.
     ///
     /// [the PTO]: crate::rtt::RttEstimator::pto
     pub loss: f64,
@@ -250,7 +239,7 @@ fn update_stats(
     }
 
     for (mut stats, session, packet_rtt, transport, transport_config) in &mut sessions {
-        let loss = compute_loss(session, transport, transport_config, &sampling, &stats);
+        let loss = compute_loss(transport, transport_config, &sampling, &stats);
         let last_sample = stats.iter().next_back().copied().unwrap_or_default();
         let sample = SessionStatsSample {
             packet_rtt: packet_rtt.map(|rtt| **rtt),
@@ -269,7 +258,6 @@ fn update_stats(
 }
 
 fn compute_loss(
-    session: &Session,
     transport: &Transport,
     transport_config: &TransportConfig,
     sampling: &SessionStatsSampling,
@@ -284,7 +272,7 @@ fn compute_loss(
 
     let sampling_rate = sampling.rate();
     let pto = transport.rtt().pto();
-    let lost_thresh = pto.as_secs_f64() * transport_config.packet_lost_threshold_factor;
+    let lost_thresh = pto.as_secs_f64() * transport.packet_lost_threshold_factor(transport_config);
 
     // Convert lost threshold to sample index
     #[expect(
@@ -305,21 +293,28 @@ fn compute_loss(
             |(_, sample)| *sample,
         );
 
-    // Calculate total packets sent and acked in the window
-    let total_packets_sent =
-        session.stats.packets_sent.0 - lost_thresh_sample.packets_total.packets_sent.0;
-    let total_packets_acked =
-        transport.stats().packet_acks_recv.0 - lost_thresh_sample.msgs_total.packet_acks_recv.0;
+    loss_between(lost_thresh_sample.msgs_total, transport.stats())
+}
+
+/// Computes the proportion of packets lost between two snapshots of
+/// [`Transport::stats`], as described in [`SessionStatsSample::loss`].
+pub(crate) fn loss_between(from: MessageStats, to: MessageStats) -> f64 {
+    let delta = to - from;
+    #[expect(clippy::cast_precision_loss, reason = "precision loss is acceptable")]
+    let (acked, lost, spurious) = (
+        delta.packet_acks_recv.0 as f64,
+        delta.packets_lost.0 as f64,
+        delta.spurious_losses.0 as f64,
+    );
+    // spurious losses may have been declared lost before this window
+    let really_lost = (lost - spurious).max(0.0);
 
     // Avoid division by zero and handle edge cases
-    if total_packets_sent == 0 {
+    let known = acked + really_lost;
+    if known <= 0.0 {
         return 0.0;
     }
 
-    // Calculate packet loss percentage
-    #[expect(clippy::cast_precision_loss, reason = "precision loss is acceptable")]
-    let packet_loss = 1.0 - (total_packets_acked as f64 / total_packets_sent as f64);
-
     // Clamp to ensure it's between 0 and 1
-    packet_loss.clamp(0.0, 1.0)
+    (really_lost / known).clamp(0.0, 1.0)
 }
@@ -2,23 +2,26 @@
 
 use {
     crate::{
-        FlushedPacket, FragmentPath, MessageKey, Transport, TransportConfig, frag,
+        AckPolicy, FlushedPacket, FragmentPath, MessageKey, Transport, TransportConfig, frag,
+        hash::{HashMap, HashSet},
+        hysteresis,
         lane::{LaneIndex, LaneKind, LaneReliability},
         limit::{Limit, TokenBucket},
         packet::{
-            Fragment, FragmentHeader, FragmentIndex, FragmentPayload, FragmentPosition, MessageSeq,
-            PacketHeader, PacketSeq,
+            Fragment, FragmentHeader, FragmentIndex, FragmentNack, FragmentPayload,
+            FragmentPosition, MessageSeq, PROTOCOL_VERSION, PacketHeader, PacketSeq,
         },
         rtt::RttEstimator,
     },
-    aeronet_io::Session,
-    ahash::HashMap,
+    aeronet_io::{Session, connection::DisconnectedRetained},
+    alloc::collections::VecDeque,
     bevy_ecs::prelude::*,
     bevy_time::{Real, Time},
-    core::iter,
+    core::{any::Any, cmp::Reverse, iter, time::Duration},
+    derive_more::{Display, Error},
     octs::{Bytes, EncodeLen, Write},
     std::collections::hash_map::Entry,
-    tracing::{trace, trace_span},
+    tracing::{trace, trace_span, warn},
     typesize::derive::TypeSize,
     web_time::Instant,
 };
@@ -28,33 +31,470 @@ use {
 pub struct TransportSend {
     pub(crate) max_frag_len: usize,
     pub(crate) lanes: Box<[SendLane]>,
-    bytes_bucket: TokenBucket,
-    next_packet_seq: PacketSeq,
+    pub(crate) bytes_bucket: TokenBucket,
+    pub(crate) next_packet_seq: PacketSeq,
     too_many_msgs: bool,
+    retransmit_priority: bool,
+    min_flush_interval: Duration,
+    ack_policy: AckPolicy,
+    max_packets_per_flush: usize,
+    pub(crate) last_flush_outcome: FlushOutcome,
+    last_flush_at: Option<Instant>,
+    recent_flushes: VecDeque<Instant>,
+    slow_start: Option<SlowStart>,
+    wfq_vtime: u64,
 }
 
-/// State of a lane used for sending outgoing messages on a [`Transport`].
+/// Why the last flush of a [`Transport`] stopped sending packets.
+///
+/// See [`Transport::last_flush_outcome`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, TypeSize)]
+pub enum FlushOutcome {
+    /// Everything which was due to be sent was flushed.
+    Sent,
+    /// The flush was skipped, but there was nothing queued to send anyway.
+    ///
+    /// This is also the outcome before the transport is first flushed.
+    #[default]
+    NothingToSend,
+    /// The flush was skipped while fragments were queued, because
+    /// [`TransportConfig::min_flush_interval`] had not elapsed since the last
+    /// flush.
+    Paused,
+    /// Fragments were left queued because there were not enough bytes left to
+    /// send them, either under [`TransportConfig::send_bytes_per_sec`] or
+    /// under the [`FlushBudget`].
+    BudgetExhausted,
+    /// Fragments were left queued because some capacity limit was reached -
+    /// either [`TransportConfig::max_packets_per_flush`], or the
+    /// [maximum number of messages in flight] on a lane.
+    ///
+    /// [maximum number of messages in flight]: TransportSend::set_max_in_flight_msgs
+    CapacityFull,
+}
+
+/// State of [`TransportConfig::slow_start_bytes_per_sec`].
 #[derive(Debug, Clone, TypeSize)]
+struct SlowStart {
+    bytes_per_sec: usize,
+    next_increase_at: Instant,
+    packets_lost: usize,
+}
+
+/// Fixed-point scale of the virtual time used for weighted fair queuing, so
+/// that a fragment's cost (its length divided by its lane's weight) doesn't
+/// round down to zero.
+///
+/// See [`TransportSend::set_lane_weight`].
+const WFQ_SCALE: u64 = 1 << 16;
+
+/// How far back [`TransportSend::recent_flushes`] keeps track of flushed
+/// packets.
+pub const FLUSH_HISTORY: Duration = Duration::from_secs(1);
+
+/// State of a lane used for sending outgoing messages on a [`Transport`].
+#[derive(Debug, TypeSize)]
 pub struct SendLane {
-    kind: LaneKind,
+    pub(crate) kind: LaneKind,
     pub(crate) sent_msgs: HashMap<MessageSeq, SentMessage>,
-    next_msg_seq: MessageSeq,
+    pub(crate) next_msg_seq: MessageSeq,
+    pub(crate) backpressure_threshold: Option<usize>,
+    pub(crate) max_in_flight_msgs: Option<usize>,
+    pub(crate) unreliable_receipts: bool,
+    pub(crate) retransmit_limit: Option<RetransmitLimit>,
+    pub(crate) congestion_watermarks: Option<CongestionWatermarks>,
+    pub(crate) congested: bool,
+    pub(crate) weight: Option<u32>,
+    wfq_finish: u64,
 }
 
-#[derive(Debug, Clone, TypeSize)]
+/// Limits on how long a message on an unreliable lane is resent for, before
+/// it is abandoned.
+///
+/// If both limits are set, the message is abandoned as soon as either one is
+/// reached.
+///
+/// See [`TransportSend::set_retransmit_limit`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, TypeSize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RetransmitLimit {
+    /// Maximum number of times that a fragment of a message may be resent
+    /// after it is first flushed.
+    ///
+    /// Once a fragment has been resent this many times, and the peer has still
+    /// not acknowledged it by the time it would be resent again, its message
+    /// is abandoned.
+    pub max_retransmits: Option<usize>,
+    /// Maximum time after a message is pushed that it may still be sent for.
+    ///
+    /// Once this much time has passed, and the peer has still not acknowledged
+    /// the whole message, it is abandoned - even if it was never flushed.
+    pub max_lifetime: Option<Duration>,
+}
+
+/// Backlog sizes at which a [`SendLane`] is considered congested, or no longer
+/// congested.
+///
+/// See [`TransportSend::set_congestion_watermarks`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, TypeSize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CongestionWatermarks {
+    /// Once a congested lane's backlog drops to this many messages or fewer,
+    /// the lane is no longer congested.
+    pub low: usize,
+    /// Once a lane's backlog reaches this many messages or more, the lane is
+    /// congested.
+    ///
+    /// This must be greater than [`CongestionWatermarks::low`].
+    pub high: usize,
+}
+
+/// Triggered on a session when one of its send lanes becomes congested, or
+/// stops being congested, in [`TransportSet::Flush`].
+///
+/// See [`TransportSend::set_congestion_watermarks`].
+///
+/// [`TransportSet::Flush`]: crate::TransportSet::Flush
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Event)]
+pub struct LaneCongestion {
+    /// Session which owns the lane.
+    pub entity: Entity,
+    /// Lane whose congestion state changed.
+    pub lane: LaneIndex,
+    /// Whether the lane is now congested.
+    pub congested: bool,
+}
+
+/// Failed to enqueue a message on a [`TransportSend`].
+#[derive(Debug, Clone, PartialEq, Eq, Display, Error)]
+pub enum PushError {
+    /// There are already too many messages buffered for sending on this lane.
+    ///
+    /// The transport will be forcibly disconnected on the next update.
+    #[display("too many messages buffered")]
+    TooManyMessages,
+    /// The message is longer than [`Transport::max_message_size`], so it
+    /// can't be split into fragments, and was not enqueued.
+    #[display("message of {len} bytes is larger than maximum of {max} bytes")]
+    TooLarge {
+        /// Length of the message in bytes.
+        len: usize,
+        /// Maximum message length in bytes.
+        max: usize,
+        /// Message which was not enqueued.
+        #[error(not(source))]
+        msg: Bytes,
+    },
+    /// This lane has more messages buffered than its
+    /// [backpressure threshold], so the message was not enqueued.
+    ///
+    /// The connection is still healthy, and you may decide to drop the
+    /// message, or send it on a different lane (e.g. an unreliable one).
+    ///
+    /// [backpressure threshold]: TransportSend::set_backpressure_threshold
+    #[display("lane backlog of {backlog} messages is over threshold of {threshold}")]
+    Backpressured {
+        /// Number of messages buffered on this lane.
+        backlog: usize,
+        /// Backpressure threshold configured on this lane.
+        threshold: usize,
+        /// Message which was not enqueued.
+        #[error(not(source))]
+        msg: Bytes,
+    },
+    /// This lane already has its [maximum number of messages in flight], so
+    /// the message was not enqueued.
+    ///
+    /// The connection is still healthy - once the peer acknowledges some of
+    /// the messages in flight, you will be able to push onto this lane again.
+    ///
+    /// [maximum number of messages in flight]: TransportSend::set_max_in_flight_msgs
+    #[display("lane has {in_flight} messages in flight, at maximum of {max}")]
+    InFlightLimit {
+        /// Number of messages in flight on this lane.
+        in_flight: usize,
+        /// Maximum number of messages in flight configured on this lane.
+        max: usize,
+        /// Message which was not enqueued.
+        #[error(not(source))]
+        msg: Bytes,
+    },
+}
+
+#[derive(Debug, TypeSize)]
 pub(crate) struct SentMessage {
+    /// Whole message which was pushed, if this is on a reliable lane.
+    ///
+    /// This is only kept so that it can be given back by
+    /// [`SendLane::unacked_msgs`]. The fragment payloads are slices of this,
+    /// so it shares their allocation - see [`SentMessage::retained_len`].
+    #[typesize(skip)]
+    pub(crate) msg: Option<Bytes>,
     pub(crate) frags: Box<[Option<SentFragment>]>,
+    #[typesize(skip)]
+    pub(crate) context: Option<Box<dyn Any + Send + Sync>>,
+    /// Whether to report this message's [`Delivery`] once it is known.
+    pub(crate) track_delivery: bool,
+    /// Whether we know that this message was not delivered, because it was
+    /// dropped before it could be.
+    pub(crate) undelivered: bool,
+    /// Instant by which this message should be fully flushed.
+    ///
+    /// See [`TransportSend::push_with_deadline`].
+    pub(crate) deadline: Option<Instant>,
+    /// Whether this message missed its deadline, and this has not been
+    /// reported in [`TransportRecv::late`] yet.
+    ///
+    /// [`TransportRecv::late`]: crate::recv::TransportRecv::late
+    pub(crate) late: bool,
+}
+
+impl SentMessage {
+    /// Gets if any part of this message has been flushed, and the message is
+    /// now waiting for the peer to acknowledge the rest of it.
+    pub(crate) fn is_in_flight(&self) -> bool {
+        // acked frags are taken out of their slots
+        let fully_acked = self.frags.iter().all(Option::is_none);
+        let any_flushed = self
+            .frags
+            .iter()
+            .any(|frag| frag.as_ref().is_none_or(|frag| frag.flushed));
+        !fully_acked && any_flushed
+    }
+
+    fn pushed_at(&self) -> Option<Instant> {
+        self.frags.iter().flatten().map(|frag| frag.sent_at).min()
+    }
+
+    /// Gets how many bytes of [`SentMessage::msg`] are not also referenced by
+    /// a fragment which is still waiting to be acked.
+    ///
+    /// These bytes are only kept alive by the whole message, so they are not
+    /// counted by the fragments' own size.
+    pub(crate) fn retained_len(&self) -> usize {
+        let msg_len = self.msg.as_ref().map_or(0, Bytes::len);
+        let frags_len = self
+            .frags
+            .iter()
+            .flatten()
+            .map(|frag| frag.payload.len())
+            .sum::<usize>();
+        msg_len.saturating_sub(frags_len)
+    }
+}
+
+/// Failed to enqueue a batch of messages via [`Transport::send_batch`].
+///
+/// If this is returned, none of the messages in the batch were enqueued.
+#[derive(Debug, Clone, PartialEq, Eq, Display, Error)]
+pub enum SendError {
+    /// A message in the batch is on a lane which does not exist.
+    #[display("message {index} is on invalid lane {lane:?}")]
+    InvalidLane {
+        /// Index of the message in the batch.
+        index: usize,
+        /// Lane index of the message.
+        lane: LaneIndex,
+    },
+    /// Enqueuing a message in the batch would put its lane over its
+    /// [backpressure threshold].
+    ///
+    /// [backpressure threshold]: TransportSend::set_backpressure_threshold
+    #[display("message {index} would put lane {lane:?} over backpressure threshold of {threshold}")]
+    Backpressured {
+        /// Index of the message in the batch.
+        index: usize,
+        /// Lane index of the message.
+        lane: LaneIndex,
+        /// Backpressure threshold configured on this lane.
+        threshold: usize,
+    },
+    /// A message in the batch is on a lane which already has its
+    /// [maximum number of messages in flight].
+    ///
+    /// [maximum number of messages in flight]: TransportSend::set_max_in_flight_msgs
+    #[display("message {index} is on lane {lane:?}, which has maximum of {max} messages in flight")]
+    InFlightLimit {
+        /// Index of the message in the batch.
+        index: usize,
+        /// Lane index of the message.
+        lane: LaneIndex,
+        /// Maximum number of messages in flight configured on this lane.
+        max: usize,
+    },
+    /// A message in the batch is longer than [`Transport::max_message_size`].
+    #[display("message {index} of {len} bytes is larger than maximum of {max} bytes")]
+    TooLarge {
+        /// Index of the message in the batch.
+        index: usize,
+        /// Length of the message in bytes.
+        len: usize,
+        /// Maximum message length in bytes.
+        max: usize,
+    },
+    /// Enqueuing a message in the batch would put too many messages on its
+    /// lane.
+    ///
+    /// Unlike [`PushError::TooManyMessages`], this does not cause the transport
+    /// to be disconnected, since nothing was enqueued.
+    #[display("message {index} would put too many messages on lane {lane:?}")]
+    TooManyMessages {
+        /// Index of the message in the batch.
+        index: usize,
+        /// Lane index of the message.
+        lane: LaneIndex,
+    },
+}
+
+/// User-provided context of a message sent via
+/// [`TransportSend::push_with_context`], given back once the transport is done
+/// with the message.
+///
+/// These are drained from [`TransportRecv::contexts`].
+///
+/// [`TransportRecv::contexts`]: crate::recv::TransportRecv::contexts
+#[derive(Debug, TypeSize)]
+pub struct MessageContext {
+    /// Key of the message that this context was attached to.
+    pub key: MessageKey,
+    /// What happened to the message.
+    pub outcome: MessageOutcome,
+    #[typesize(skip)]
+    context: Box<dyn Any + Send + Sync>,
+}
+
+/// What happened to a message sent via [`TransportSend::push_with_context`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, TypeSize)]
+pub enum MessageOutcome {
+    /// The peer acknowledged that it received the message.
+    Acked,
+    /// The transport stopped tracking the message before the peer acknowledged
+    /// it.
+    ///
+    /// On unreliable lanes, this happens as soon as all of the message's
+    /// fragments have been flushed, since they are never resent - the message
+    /// may or may not still reach the peer. On a [`LaneKind::UnreliableLatest`]
+    /// lane, this also happens when a newer message is pushed before this one
+    /// was fully flushed. On a lane with a [retransmit limit], this happens
+    /// once the message is abandoned instead. On any lane, this also happens
+    /// if the message is [canceled], or the lane's ordering is [reset].
+    ///
+    /// [retransmit limit]: TransportSend::set_retransmit_limit
+    /// [canceled]: Transport::cancel
+    /// [reset]: Transport::reset_lane_ordering
+    Dropped,
+}
+
+/// Final delivery status of a message tracked via
+/// [`Transport::track_delivery`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, TypeSize)]
+pub enum Delivery {
+    /// The peer acknowledged that it received the whole message.
+    Delivered,
+    /// The message was not delivered, and never will be.
+    ///
+    /// This happens if a packet carrying the message is declared lost on an
+    /// unreliable lane with [receipts], since the message is never resent, or
+    /// if the message is abandoned after reaching its lane's
+    /// [retransmit limit]. It also happens if the message is dropped before it
+    /// is fully sent - if it is [canceled], superseded on a
+    /// [`LaneKind::UnreliableLatest`] lane, or its lane's ordering is [reset].
+    ///
+    /// Like receipts, this is best-effort: if a packet is spuriously declared
+    /// lost, the peer may have received the message after all.
+    ///
+    /// [receipts]: TransportSend::set_unreliable_receipts
+    /// [retransmit limit]: TransportSend::set_retransmit_limit
+    /// [canceled]: Transport::cancel
+    /// [reset]: Transport::reset_lane_ordering
+    Lost,
+    /// The transport does not know if the message was delivered.
+    ///
+    /// This happens on unreliable lanes without [receipts], where a message is
+    /// forgotten as soon as it is flushed. It also happens if the key being
+    /// tracked did not refer to a message which was still being sent.
+    ///
+    /// [receipts]: TransportSend::set_unreliable_receipts
+    Unknown,
+}
+
+/// Final [`Delivery`] status of a message tracked via
+/// [`Transport::track_delivery`].
+///
+/// These are drained from [`TransportRecv::deliveries`].
+///
+/// [`TransportRecv::deliveries`]: crate::recv::TransportRecv::deliveries
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, TypeSize)]
+pub struct DeliveryReport {
+    /// Key of the tracked message.
+    pub key: MessageKey,
+    /// What happened to the message.
+    pub delivery: Delivery,
 }
 
 #[derive(Debug, Clone, TypeSize)]
 pub(crate) struct SentFragment {
-    position: FragmentPosition,
+    pub(crate) position: FragmentPosition,
     #[typesize(with = Bytes::len)]
-    payload: Bytes,
-    sent_at: Instant,
-    next_flush_at: Instant,
+    pub(crate) payload: Bytes,
+    pub(crate) sent_at: Instant,
+    pub(crate) next_flush_at: Instant,
+    pub(crate) flushed: bool,
+    /// How many times this fragment has been resent on an unreliable lane.
+    ///
+    /// See [`TransportSend::set_retransmit_limit`].
+    pub(crate) retransmits: usize,
 }
 
+/// Limit on how many bytes [`TransportSet::Flush`] may flush out across all
+/// [`Transport`]s in a single update.
+///
+/// On a server with many clients, flushing every session in the same update
+/// can cause a spike in frame time. Insert this resource to spread the work
+/// out over multiple updates instead: sessions are flushed in a round-robin
+/// order, and once the budget is used up, the remaining sessions (and the
+/// remaining packets of a partially-flushed session) are flushed in a later
+/// update, starting where this update left off.
+///
+/// Deferring a flush does not drop anything - fragments which were not
+/// flushed stay queued, and reliable fragments are still tracked until they
+/// are acknowledged. However, deferred sessions do not send out acks or
+/// keep-alives until they are flushed, so the budget should be large enough to
+/// flush every session within their peers' timeouts.
+///
+/// A packet is only flushed if it fits into the remaining budget, so the
+/// budget must be at least the largest [`Session::mtu`] of any session,
+/// otherwise that session will never be flushed.
+///
+/// Sessions with a higher [`SessionPriority`] are flushed before any sessions
+/// with a lower priority, and the round-robin order only applies between
+/// sessions of the same priority.
+///
+/// By default, this resource is not present, so all sessions are fully flushed
+/// every update.
+///
+/// [`TransportSet::Flush`]: crate::TransportSet::Flush
+#[derive(Debug, Clone, Copy, Resource)]
+pub struct FlushBudget {
+    /// Maximum number of bytes, summed over all packets of all sessions, which
+    /// may be flushed in a single update.
+    pub max_bytes_per_update: usize,
+}
+
+/// Priority of a [`Transport`] when flushing under a [`FlushBudget`].
+///
+/// Insert this into a session entity to have it flushed before sessions with a
+/// lower priority, e.g. for admins on a server under load. Sessions without
+/// this component have the default priority of 0.
+///
+/// If the budget is used up by higher-priority sessions, lower-priority
+/// sessions are not flushed at all in that update, so be careful not to starve
+/// them for longer than their peers' timeouts.
+///
+/// This has no effect if there is no [`FlushBudget`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Component)]
+pub struct SessionPriority(pub i32);
+
 impl TransportSend {
     pub(crate) fn new(
         max_frag_len: usize,
@@ -69,11 +509,28 @@ impl TransportSend {
                     kind,
                     sent_msgs: HashMap::default(),
                     next_msg_seq: MessageSeq::default(),
+                    backpressure_threshold: None,
+                    max_in_flight_msgs: None,
+                    unreliable_receipts: false,
+                    retransmit_limit: None,
+                    congestion_watermarks: None,
+                    congested: false,
+                    weight: None,
+                    wfq_finish: 0,
                 })
                 .collect(),
             bytes_bucket: TokenBucket::new(0),
             next_packet_seq: PacketSeq::default(),
             too_many_msgs: false,
+            retransmit_priority: false,
+            min_flush_interval: Duration::ZERO,
+            ack_policy: AckPolicy::default(),
+            max_packets_per_flush: usize::MAX,
+            last_flush_outcome: FlushOutcome::default(),
+            last_flush_at: None,
+            recent_flushes: VecDeque::new(),
+            slow_start: None,
+            wfq_vtime: 0,
         }
     }
 
@@ -90,6 +547,29 @@ impl TransportSend {
         &self.bytes_bucket
     }
 
+    /// Gets the instants at which packets were flushed out over the last
+    /// [`FLUSH_HISTORY`], from oldest to newest.
+    ///
+    /// Every fragment written into a packet is flushed at the same instant as
+    /// that packet, so this also gives the flush times of individual
+    /// fragments. This is useful for diagnosing pacing issues, e.g. if packets
+    /// are being sent out in bursts because
+    /// [`TransportConfig::send_bytes_per_sec`] is too low.
+    pub fn recent_flushes(&self) -> impl ExactSizeIterator<Item = Instant> + '_ {
+        self.recent_flushes.iter().copied()
+    }
+
+    /// Gets the spacing between consecutive packet flushes over the last
+    /// [`FLUSH_HISTORY`], from oldest to newest.
+    ///
+    /// See [`TransportSend::recent_flushes`].
+    pub fn flush_spacing(&self) -> impl Iterator<Item = Duration> + '_ {
+        self.recent_flushes
+            .iter()
+            .zip(self.recent_flushes.iter().skip(1))
+            .map(|(prev, next)| next.saturating_duration_since(*prev))
+    }
+
     /// Attempts to enqueue a message on this transport for sending.
     ///
     /// This will not send out a message immediately - that happens during
@@ -100,15 +580,35 @@ impl TransportSend {
     /// [`TransportRecv::acks`], you can compare message keys to tell if the
     /// message you are pushing right now was the one that was acknowledged.
     ///
-    /// If the message could not be enqueued (if e.g. there are already too many
-    /// messages buffered for sending), this returns [`None`], and the transport
-    /// will be forcibly disconnected on the next update. This is considered a
-    /// fatal connection condition, because you may have sent a message along a
-    /// reliable lane, and those [`LaneKind`]s provide strong guarantees that
-    /// messages will be received by the peer.
-    ///
     /// [^1]: See [`MessageKey`] for uniqueness guarantees.
     ///
+    /// # Errors
+    ///
+    /// If this lane has a [backpressure threshold], and there are already at
+    /// least that many messages buffered on this lane, this returns
+    /// [`PushError::Backpressured`] and gives the message back. This is not a
+    /// fatal error, and lets you decide what to do with the message before the
+    /// backlog grows large enough to hit [`TransportConfig::max_memory_usage`].
+    ///
+    /// If this lane has a [maximum number of messages in flight], and there
+    /// are already that many messages in flight on this lane, this returns
+    /// [`PushError::InFlightLimit`] and gives the message back. This is also
+    /// not a fatal error.
+    ///
+    /// If the message is longer than [`Transport::max_message_size`], this
+    /// returns [`PushError::TooLarge`] and gives the message back.
+    ///
+    /// If the message could not be enqueued because there are already too many
+    /// messages buffered for sending, this returns
+    /// [`PushError::TooManyMessages`], and the transport will be forcibly
+    /// disconnected on the next update. This is considered a fatal connection
+    /// condition, because you may have sent a message along a reliable lane,
+    /// and those [`LaneKind`]s provide strong guarantees that messages will be
+    /// received by the peer.
+    ///
+    /// [backpressure threshold]: TransportSend::set_backpressure_threshold
+    /// [maximum number of messages in flight]: TransportSend::set_max_in_flight_msgs
+    ///
     /// # Panics
     ///
     /// Panics if the `lane_index` is outside the range of send lanes configured
@@ -121,18 +621,17 @@ impl TransportSend {
     ///
     /// # Examples
     ///
+    /// Use [`aeronet_io::now`] to get the instant to pass as `now`.
+    ///
     /// ```
-    /// use {
-    ///     aeronet_transport::{Transport, lane::LaneIndex},
-    ///     web_time::Instant,
-    /// };
+    /// use aeronet_transport::{Transport, lane::LaneIndex};
     ///
     /// const SEND_LANE: LaneIndex = LaneIndex(0);
     ///
     /// fn send_msgs(transport: &mut Transport) {
     ///     let msg_key = transport
     ///         .send
-    ///         .push(SEND_LANE, b"hello world".to_vec().into(), Instant::now())
+    ///         .push(SEND_LANE, b"hello world".to_vec().into(), aeronet_io::now())
     ///         .unwrap();
     ///
     ///     // later...
@@ -146,16 +645,160 @@ impl TransportSend {
     /// ```
     ///
     /// [`TransportRecv::acks`]: crate::recv::TransportRecv::acks
-    pub fn push(&mut self, lane_index: LaneIndex, msg: Bytes, now: Instant) -> Option<MessageKey> {
+    pub fn push(
+        &mut self,
+        lane_index: LaneIndex,
+        msg: Bytes,
+        now: Instant,
+    ) -> Result<MessageKey, PushError> {
+        self.push_inner(lane_index, msg, now, None, None)
+    }
+
+    /// Attempts to enqueue a message on this transport for sending, attaching
+    /// some user-defined context to it.
+    ///
+    /// This behaves the same as [`TransportSend::push`], but the transport also
+    /// keeps hold of `context` until it is done with the message. Once the
+    /// peer acknowledges the message, or the transport stops tracking it, the
+    /// context is given back in [`TransportRecv::contexts`] along with the
+    /// [`MessageOutcome`]. This avoids having to keep a separate map from
+    /// [`MessageKey`]s to your own data, which is fragile since message keys
+    /// are [reused].
+    ///
+    /// # Memory
+    ///
+    /// The context is stored for as long as the message is tracked - on a
+    /// reliable lane, this is until the peer acknowledges every fragment of the
+    /// message, which may take a while on a bad connection. The memory used by
+    /// the context is *not* counted towards [`Transport::memory_used`], so it
+    /// will not count towards [`TransportConfig::max_memory_usage`] either.
+    /// Prefer small contexts, such as IDs or handles into your own storage.
+    ///
+    /// If the message could not be enqueued, the context is dropped.
+    ///
+    /// # Errors
+    ///
+    /// See [`TransportSend::push`].
+    ///
+    /// # Panics
+    ///
+    /// See [`TransportSend::push`].
+    ///
+    /// [`TransportRecv::contexts`]: crate::recv::TransportRecv::contexts
+    /// [reused]: MessageKey#uniqueness
+    pub fn push_with_context<T: Send + Sync + 'static>(
+        &mut self,
+        lane_index: LaneIndex,
+        msg: Bytes,
+        now: Instant,
+        context: T,
+    ) -> Result<MessageKey, PushError> {
+        self.push_inner(lane_index, msg, now, Some(Box::new(context)), None)
+    }
+
+    /// Attempts to enqueue a message on this transport for sending, which
+    /// should be fully flushed by `deadline`.
+    ///
+    /// This behaves the same as [`TransportSend::push`], but when flushing,
+    /// fragments of messages with a deadline are sent before any other
+    /// fragments, earliest deadline first. If the deadline passes before the
+    /// message has been fully flushed:
+    /// - on an unreliable lane, the rest of the message is dropped, since it
+    ///   would arrive too late to be useful
+    /// - on a reliable lane, the message is still sent, but its key is given in
+    ///   [`TransportRecv::late`], and it is no longer prioritized
+    ///
+    /// Use this for messages which are only useful if they arrive in time,
+    /// e.g. frames of interactive media.
+    ///
+    /// # Errors
+    ///
+    /// See [`TransportSend::push`].
+    ///
+    /// # Panics
+    ///
+    /// See [`TransportSend::push`].
+    ///
+    /// [`TransportRecv::late`]: crate::recv::TransportRecv::late
+    pub fn push_with_deadline(
+        &mut self,
+        lane_index: LaneIndex,
+        msg: Bytes,
+        now: Instant,
+        deadline: Instant,
+    ) -> Result<MessageKey, PushError> {
+        self.push_inner(lane_index, msg, now, None, Some(deadline))
+    }
+
+    fn push_inner(
+        &mut self,
+        lane_index: LaneIndex,
+        msg: Bytes,
+        now: Instant,
+        context: Option<Box<dyn Any + Send + Sync>>,
+        deadline: Option<Instant>,
+    ) -> Result<MessageKey, PushError> {
+        let max = self.max_msg_len();
+        if msg.len() > max {
+            return Err(PushError::TooLarge {
+                len: msg.len(),
+                max,
+                msg,
+            });
+        }
+
         let lane = &mut self.lanes[usize::from(lane_index)];
+        if let Some(threshold) = lane.backpressure_threshold {
+            let backlog = lane.sent_msgs.len();
+            if backlog >= threshold {
+                return Err(PushError::Backpressured {
+                    backlog,
+                    threshold,
+                    msg,
+                });
+            }
+        }
+
+        if let Some((in_flight, max)) = lane.full_in_flight_window() {
+            return Err(PushError::InFlightLimit {
+                in_flight,
+                max,
+                msg,
+            });
+        }
+
+        if lane.kind == LaneKind::UnreliableLatest {
+            // the new message supersedes any which haven't been fully flushed;
+            // they'll be dropped on the next flush, like any other unreliable
+            // message with no frags left to send
+            // (fully flushed messages may still be waiting for a receipt, but
+            // there's no point resending them if they have a retransmit limit)
+            let resends = lane.retransmit_limit.is_some();
+            for msg in lane.sent_msgs.values_mut() {
+                if msg
+                    .frags
+                    .iter()
+                    .flatten()
+                    .any(|frag| resends || !frag.flushed)
+                {
+                    msg.frags.fill(None);
+                    msg.undelivered = true;
+                }
+            }
+        }
+
         let msg_seq = lane.next_msg_seq;
         let Entry::Vacant(entry) = lane.sent_msgs.entry(msg_seq) else {
             self.too_many_msgs = true;
-            return None;
+            return Err(PushError::TooManyMessages);
         };
 
-        let frags = frag::split(self.max_frag_len, msg);
+        // on unreliable lanes, acked or dropped fragments should free their
+        // part of the message, so we don't keep the whole message around
+        let reliable = lane.kind.reliability() == LaneReliability::Reliable;
+        let frags = frag::split(self.max_frag_len, msg.clone());
         entry.insert(SentMessage {
+            msg: reliable.then_some(msg),
             frags: frags
                 .map(|(position, payload)| {
                     Some(SentFragment {
@@ -163,17 +806,341 @@ impl TransportSend {
                         payload,
                         sent_at: now,
                         next_flush_at: now,
+                        flushed: false,
+                        retransmits: 0,
                     })
                 })
                 .collect(),
+            context,
+            track_delivery: false,
+            undelivered: false,
+            deadline,
+            late: false,
         });
 
         lane.next_msg_seq += MessageSeq::new(1);
-        Some(MessageKey {
+        Ok(MessageKey {
             lane: lane_index,
             seq: msg_seq,
         })
     }
+
+    /// Gets the maximum length of a single message in bytes.
+    ///
+    /// See [`Transport::max_message_size`].
+    #[must_use]
+    pub const fn max_msg_len(&self) -> usize {
+        self.max_frag_len.saturating_mul(frag::MAX_FRAGS)
+    }
+
+    /// Sets how many messages may be buffered on a lane before
+    /// [`TransportSend::push`] starts returning [`PushError::Backpressured`].
+    ///
+    /// Buffered messages include messages which have not been flushed yet, and
+    /// messages on reliable lanes which have not been acknowledged by the peer
+    /// yet. If the peer stops acknowledging messages, or the link is saturated,
+    /// this backlog will keep growing until the transport runs out of memory
+    /// and is disconnected. Setting a threshold lets you find out about this
+    /// early, and drop or downgrade messages instead.
+    ///
+    /// Pass [`None`] to remove the threshold, which is the default.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `lane_index` is outside the range of send lanes configured
+    /// on this [`Transport`] when it was created.
+    pub fn set_backpressure_threshold(&mut self, lane_index: LaneIndex, threshold: Option<usize>) {
+        self.lanes[usize::from(lane_index)].backpressure_threshold = threshold;
+    }
+
+    /// Sets how many messages may be in flight on a reliable lane at once.
+    ///
+    /// A message is in flight once any of its fragments have been flushed,
+    /// until the peer has acknowledged all of its fragments. While a lane has
+    /// this many messages in flight:
+    /// - [`TransportSend::push`] returns [`PushError::InFlightLimit`]
+    /// - messages which were already pushed, but not flushed yet, are held back
+    ///   until the peer acknowledges some of the messages in flight
+    ///
+    /// Messages in flight are still resent as usual. This bounds how much
+    /// memory, and how much of the link, a single lane can take up with
+    /// unacknowledged messages, independently of
+    /// [`TransportConfig::max_memory_usage`]. To also bound the number of
+    /// messages waiting to be flushed, use
+    /// [`TransportSend::set_backpressure_threshold`].
+    ///
+    /// This has no effect on unreliable lanes, since messages on those lanes
+    /// are never resent.
+    ///
+    /// Pass [`None`] to remove the limit, which is the default.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `lane_index` is outside the range of send lanes configured
+    /// on this [`Transport`] when it was created.
+    pub fn set_max_in_flight_msgs(&mut self, lane_index: LaneIndex, max: Option<usize>) {
+        self.lanes[usize::from(lane_index)].max_in_flight_msgs = max;
+    }
+
+    /// Sets whether messages sent on an unreliable lane are reported in
+    /// [`TransportRecv::acks`] when the peer receives them.
+    ///
+    /// Normally, a message on an unreliable lane is forgotten as soon as it is
+    /// flushed, so you never find out whether it arrived. With receipts
+    /// enabled, the message is still only sent once, and is never resent - but
+    /// we keep track of it until either:
+    /// - the peer acknowledges all of its fragments, and its key is reported in
+    ///   [`TransportRecv::acks`] (and its context is given back as
+    ///   [`MessageOutcome::Acked`])
+    /// - a packet carrying one of its fragments is declared lost, and the
+    ///   message is dropped (and its context is given back as
+    ///   [`MessageOutcome::Dropped`])
+    ///
+    /// This lets you measure how many of your unreliable messages actually
+    /// reach the peer. Receipts are best-effort: if a packet is spuriously
+    /// declared lost, but the peer did receive it, no receipt is reported for
+    /// the messages in it.
+    ///
+    /// This has no effect on reliable lanes, since their messages are always
+    /// reported once acknowledged.
+    ///
+    /// Receipts are disabled by default.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `lane_index` is outside the range of send lanes configured
+    /// on this [`Transport`] when it was created.
+    ///
+    /// [`TransportRecv::acks`]: crate::recv::TransportRecv::acks
+    pub fn set_unreliable_receipts(&mut self, lane_index: LaneIndex, enabled: bool) {
+        self.lanes[usize::from(lane_index)].unreliable_receipts = enabled;
+    }
+
+    /// Makes an unreliable lane *partially reliable*, resending its messages
+    /// until the peer acknowledges them, or until they reach a
+    /// [`RetransmitLimit`].
+    ///
+    /// This is useful for data which is worth a retry or two, but which goes
+    /// stale quickly, such as media. Fragments on this lane are resent on the
+    /// same schedule as on a reliable lane, but once a message reaches its
+    /// limit, it is abandoned instead of being resent forever: its context is
+    /// given back as [`MessageOutcome::Dropped`], and if it is
+    /// [tracked](Transport::track_delivery), it is reported as
+    /// [`Delivery::Lost`]. If the peer does acknowledge a message in time, its
+    /// key is reported in [`TransportRecv::acks`], as if [receipts] were
+    /// enabled.
+    ///
+    /// Since this is still an unreliable lane, the peer may receive a message
+    /// more than once, if it receives a resent fragment after the original.
+    ///
+    /// This has no effect on reliable lanes. Their peer can't tell an abandoned
+    /// message apart from one which has not arrived yet, so on an ordered lane,
+    /// the peer would wait for it forever.
+    ///
+    /// Pass [`None`] to never resend messages on this lane, which is the
+    /// default.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `lane_index` is outside the range of send lanes configured
+    /// on this [`Transport`] when it was created.
+    ///
+    /// [receipts]: TransportSend::set_unreliable_receipts
+    /// [`TransportRecv::acks`]: crate::recv::TransportRecv::acks
+    pub fn set_retransmit_limit(&mut self, lane_index: LaneIndex, limit: Option<RetransmitLimit>) {
+        self.lanes[usize::from(lane_index)].retransmit_limit = limit;
+    }
+
+    /// Sets the backlog sizes at which a lane is considered congested.
+    ///
+    /// The backlog is the same as for
+    /// [`TransportSend::set_backpressure_threshold`]. After each flush, if the
+    /// lane's backlog has reached [`CongestionWatermarks::high`], the lane
+    /// becomes congested; once a congested lane's backlog drops to
+    /// [`CongestionWatermarks::low`], it is no longer congested. Each time this
+    /// state changes, [`LaneCongestion`] is triggered on the session, so you
+    /// can e.g. reduce your send rate while the lane is congested, without
+    /// having to check its backlog every update. The gap between the two
+    /// watermarks stops a backlog which hovers around a single value from
+    /// triggering an event on every update.
+    ///
+    /// Pass [`None`] to stop tracking congestion on this lane, which is the
+    /// default. This resets the lane to not congested, without triggering
+    /// [`LaneCongestion`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `lane_index` is outside the range of send lanes configured
+    /// on this [`Transport`] when it was created, or if
+    /// [`CongestionWatermarks::low`] is not less than
+    /// [`CongestionWatermarks::high`].
+    pub fn set_congestion_watermarks(
+        &mut self,
+        lane_index: LaneIndex,
+        watermarks: Option<CongestionWatermarks>,
+    ) {
+        if let Some(CongestionWatermarks { low, high }) = watermarks {
+            assert!(
+                low < high,
+                "low congestion watermark {low} must be less than high watermark {high}"
+            );
+        }
+        let lane = &mut self.lanes[usize::from(lane_index)];
+        lane.congestion_watermarks = watermarks;
+        if watermarks.is_none() {
+            lane.congested = false;
+        }
+    }
+
+    /// Sets the weight of a lane, to share out the send bytes budget between
+    /// lanes using weighted fair queuing.
+    ///
+    /// When several weighted lanes have more to send than the budget allows
+    /// (see [`TransportConfig::send_bytes_per_sec`]), each one gets a share of
+    /// the bytes flushed proportional to its weight, no matter how large each
+    /// lane's backlog is. For example, a game state lane with weight `4` and a
+    /// bulk transfer lane with weight `1` get 80% and 20% of the bandwidth
+    /// respectively.
+    ///
+    /// Weighted fair queuing only decides which lane's fragments go first out
+    /// of the fragments which are ready to be flushed:
+    /// - fragments of messages with a deadline (see
+    ///   [`TransportSend::push_with_deadline`]) still go before all others
+    /// - if [`TransportConfig::retransmit_priority`] is enabled,
+    ///   retransmissions go next, on any lane
+    /// - lanes without a weight, which is the default, go before weighted lanes
+    /// - a weight is a share of the budget, not a cap on it. If a weighted lane
+    ///   has nothing to send, its share goes to the other lanes, and it doesn't
+    ///   build up credit while idle
+    /// - per-lane caps, such as [`TransportSend::set_max_in_flight_msgs`], are
+    ///   applied before weighting. A lane held back by its cap gets less than
+    ///   its weighted share, and the rest goes to the other lanes
+    ///
+    /// Pass [`None`] to remove the weight of this lane.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `lane_index` is outside the range of send lanes configured
+    /// on this [`Transport`] when it was created, or if `weight` is `Some(0)`.
+    pub fn set_lane_weight(&mut self, lane_index: LaneIndex, weight: Option<u32>) {
+        assert!(weight != Some(0), "lane weight must be greater than zero");
+        self.lanes[usize::from(lane_index)].weight = weight;
+    }
+
+    /// Makes the fragments of a reliable message which the peer NACKed due to
+    /// be resent on the next flush.
+    ///
+    /// See [`TransportConfig::fragment_nacks`].
+    pub(crate) fn resend_nacked(&mut self, nack: FragmentNack, now: Instant) {
+        let Some(msg) = self
+            .lanes
+            .get_mut(usize::from(nack.lane))
+            .filter(|lane| lane.kind.reliability() == LaneReliability::Reliable)
+            .and_then(|lane| lane.sent_msgs.get_mut(&nack.seq))
+        else {
+            // the peer may have received the message after all, and acked it
+            return;
+        };
+
+        trace!(
+            lane = nack.lane.0,
+            msg_seq = nack.seq.0.0,
+            index = nack.index,
+            "Peer NACKed message"
+        );
+        // frags which the peer has acked are already gone
+        for frag in msg
+            .frags
+            .iter_mut()
+            .skip(usize::from(nack.index))
+            .flatten()
+            .filter(|frag| frag.flushed)
+        {
+            frag.next_flush_at = frag.next_flush_at.min(now);
+        }
+    }
+
+    /// Gets if any fragments are queued which have never been flushed.
+    fn has_unflushed_frags(&self) -> bool {
+        self.lanes.iter().any(|lane| {
+            lane.sent_msgs
+                .values()
+                .flat_map(|msg| msg.frags.iter().flatten())
+                .any(|frag| !frag.flushed)
+        })
+    }
+
+    /// Forgets about flushes which are too old to be useful for diagnostics.
+    fn forget_old_flushes(&mut self, now: Instant) {
+        while self
+            .recent_flushes
+            .front()
+            .is_some_and(|flushed_at| now.saturating_duration_since(*flushed_at) > FLUSH_HISTORY)
+        {
+            self.recent_flushes.pop_front();
+        }
+    }
+
+    /// Gets the outcome of a flush which didn't stop for any particular
+    /// reason while building packets.
+    fn finished_flush_outcome(&self) -> FlushOutcome {
+        // the only frags which we didn't even try to send are the ones
+        // held back by a lane's in-flight window
+        if self.has_unflushed_frags() {
+            FlushOutcome::CapacityFull
+        } else {
+            FlushOutcome::Sent
+        }
+    }
+
+    /// Gets the outcome of a flush which the [`FlushBudget`] cut short, where
+    /// `flushed` is whether any packets were flushed before it ran out.
+    fn budget_flush_outcome(&self, now: Instant, flushed: bool) -> FlushOutcome {
+        let frags_left =
+            self.has_unflushed_frags() || self.lanes.iter().any(|lane| lane.retransmit_due(now));
+        if frags_left {
+            FlushOutcome::BudgetExhausted
+        } else if flushed {
+            FlushOutcome::Sent
+        } else {
+            FlushOutcome::NothingToSend
+        }
+    }
+
+    /// Gets the outcome of a flush which was skipped.
+    fn skipped_flush_outcome(&self) -> FlushOutcome {
+        if self.has_unflushed_frags() {
+            FlushOutcome::Paused
+        } else {
+            FlushOutcome::NothingToSend
+        }
+    }
+
+    /// Gets if this transport should be flushed now, and if so, records that it
+    /// was flushed.
+    ///
+    /// See [`TransportConfig::min_flush_interval`].
+    fn flush_due(&mut self, now: Instant) -> bool {
+        let due = self.last_flush_at.is_none_or(|last_flush_at| {
+            now.saturating_duration_since(last_flush_at) >= self.min_flush_interval
+        }) || self.lanes.iter().any(|lane| lane.retransmit_due(now));
+        if due {
+            self.last_flush_at = Some(now);
+        }
+        due
+    }
+
+    /// Gets if a flush carrying fragments will happen by `at`, assuming that
+    /// nothing else is pushed until then.
+    ///
+    /// See [`AckPolicy::Hybrid`].
+    fn frags_due_by(&self, at: Instant) -> bool {
+        let flush_due = self.last_flush_at.is_none_or(|last_flush_at| {
+            at.saturating_duration_since(last_flush_at) >= self.min_flush_interval
+        });
+        (flush_due && self.has_unflushed_frags())
+            || self.lanes.iter().any(|lane| lane.retransmit_due(at))
+    }
 }
 
 impl SendLane {
@@ -189,71 +1156,606 @@ impl SendLane {
     pub fn num_queued_msgs(&self) -> usize {
         self.sent_msgs.len()
     }
-}
 
-pub(crate) fn update_send_bytes_config(
-    mut sessions: Query<
-        (&mut Transport, &TransportConfig),
-        Or<(Added<Transport>, Changed<TransportConfig>)>,
-    >,
-) {
-    for (mut transport, config) in &mut sessions {
-        transport
-            .send
-            .bytes_bucket
-            .set_cap(config.send_bytes_per_sec);
+    /// Gets the backpressure threshold of this lane.
+    ///
+    /// See [`TransportSend::set_backpressure_threshold`].
+    #[must_use]
+    pub const fn backpressure_threshold(&self) -> Option<usize> {
+        self.backpressure_threshold
     }
-}
 
-pub(crate) fn refill_send_bytes(time: Res<Time<Real>>, mut sessions: Query<&mut Transport>) {
-    for mut transport in &mut sessions {
-        transport
-            .send
-            .bytes_bucket
-            .refill_portion(time.delta_secs_f64());
+    /// Gets the congestion watermarks of this lane.
+    ///
+    /// See [`TransportSend::set_congestion_watermarks`].
+    #[must_use]
+    pub const fn congestion_watermarks(&self) -> Option<CongestionWatermarks> {
+        self.congestion_watermarks
     }
-}
 
-pub(crate) fn flush(mut sessions: Query<(&mut Session, &mut Transport)>) {
-    let now = Instant::now();
-    for (mut session, mut transport) in &mut sessions {
-        let packet_mtu = session.mtu();
-        session
-            .send
-            .extend(flush_on(&mut transport, now, packet_mtu));
+    /// Gets if this lane is currently congested.
+    ///
+    /// See [`TransportSend::set_congestion_watermarks`].
+    #[must_use]
+    pub const fn is_congested(&self) -> bool {
+        self.congested
     }
-}
-
-/// Exposes `flush_on` for fuzz tests.
-#[cfg(fuzzing)]
-pub fn fuzz_flush_on(transport: &mut Transport, mtu: usize) -> impl Iterator<Item = Bytes> + '_ {
-    flush_on(transport, Instant::now(), mtu)
-}
 
-fn flush_on(
-    transport: &mut Transport,
-    now: Instant,
-    mtu: usize,
-) -> impl Iterator<Item = Bytes> + '_ {
-    // collect the paths of the frags to send, along with how old they are
-    let mut frag_paths = transport
-        .send
-        .lanes
+    /// Gets the number of messages which have been flushed on this lane, but
+    /// which the peer has not fully acknowledged yet.
+    ///
+    /// This is always 0 on unreliable lanes, unless
+    /// [receipts](TransportSend::set_unreliable_receipts) are enabled, or the
+    /// lane has a [retransmit limit](TransportSend::set_retransmit_limit).
+    #[must_use]
+    pub fn num_in_flight_msgs(&self) -> usize {
+        self.sent_msgs
+            .values()
+            .filter(|msg| msg.is_in_flight())
+            .count()
+    }
+
+    /// Gets the messages on this lane which the peer has not fully
+    /// acknowledged yet, from oldest to newest.
+    ///
+    /// This includes messages which have not been flushed at all yet. This is
+    /// always empty on unreliable lanes.
+    ///
+    /// This is useful if you are moving away from this session (see
+    /// [`handoff`]), and want to re-send these messages on another session.
+    ///
+    /// [`handoff`]: crate::handoff
+    pub fn unacked_msgs(&self) -> impl Iterator<Item = (MessageSeq, Bytes)> + '_ {
+        let mut msgs = Vec::new();
+        if self.kind.reliability() == LaneReliability::Reliable {
+            msgs.extend(
+                self.sent_msgs
+                    .iter()
+                    // acked frags are taken out of their slots
+                    .filter(|(_, msg)| msg.frags.iter().any(Option::is_some))
+                    .filter_map(|(msg_seq, msg)| Some((*msg_seq, msg.msg.clone()?))),
+            );
+        }
+        msgs.sort_unstable_by_key(|(msg_seq, _)| self.next_msg_seq.dist_to(**msg_seq));
+        msgs.into_iter()
+    }
+
+    /// Gets the maximum number of messages in flight on this lane.
+    ///
+    /// See [`TransportSend::set_max_in_flight_msgs`].
+    #[must_use]
+    pub const fn max_in_flight_msgs(&self) -> Option<usize> {
+        self.max_in_flight_msgs
+    }
+
+    /// Gets if messages on this lane are reported once the peer receives them,
+    /// even if this lane is unreliable.
+    ///
+    /// See [`TransportSend::set_unreliable_receipts`].
+    #[must_use]
+    pub const fn unreliable_receipts(&self) -> bool {
+        self.unreliable_receipts
+    }
+
+    /// Gets the retransmit limit of this lane.
+    ///
+    /// See [`TransportSend::set_retransmit_limit`].
+    #[must_use]
+    pub const fn retransmit_limit(&self) -> Option<RetransmitLimit> {
+        self.retransmit_limit
+    }
+
+    /// Gets the weight of this lane for weighted fair queuing.
+    ///
+    /// See [`TransportSend::set_lane_weight`].
+    #[must_use]
+    pub const fn weight(&self) -> Option<u32> {
+        self.weight
+    }
+
+    /// If this lane can't have any more messages in flight, gets the number of
+    /// messages in flight, and the maximum.
+    pub(crate) fn full_in_flight_window(&self) -> Option<(usize, usize)> {
+        if self.kind.reliability() != LaneReliability::Reliable {
+            return None;
+        }
+        let max = self.max_in_flight_msgs?;
+        let in_flight = self.num_in_flight_msgs();
+        (in_flight >= max).then_some((in_flight, max))
+    }
+
+    /// Gets if any fragment on this lane has been flushed before, and is now
+    /// due to be resent.
+    fn retransmit_due(&self, now: Instant) -> bool {
+        let resends =
+            self.kind.reliability() == LaneReliability::Reliable || self.retransmit_limit.is_some();
+        resends
+            && self
+                .sent_msgs
+                .values()
+                .flat_map(|msg| msg.frags.iter().flatten())
+                .any(|frag| frag.flushed && now >= frag.next_flush_at)
+    }
+
+    /// Drops all messages queued for sending, and restarts the message
+    /// sequence numbers of this lane from the beginning.
+    ///
+    /// Gives back the contexts and delivery reports of all dropped messages.
+    ///
+    /// See [`Transport::reset_lane_ordering`].
+    pub(crate) fn reset(
+        &mut self,
+        lane_index: LaneIndex,
+        contexts: &mut Vec<MessageContext>,
+        deliveries: &mut Vec<DeliveryReport>,
+    ) {
+        for (msg_seq, msg) in self.sent_msgs.drain() {
+            if let Some(context) = msg.context {
+                contexts.push(MessageContext::new(
+                    lane_index,
+                    msg_seq,
+                    context,
+                    MessageOutcome::Dropped,
+                ));
+            }
+            if msg.track_delivery {
+                deliveries.push(DeliveryReport {
+                    key: MessageKey {
+                        lane: lane_index,
+                        seq: msg_seq,
+                    },
+                    delivery: Delivery::Lost,
+                });
+            }
+        }
+        self.next_msg_seq = MessageSeq::default();
+    }
+
+    /// Handles messages whose deadline has passed before they could be fully
+    /// flushed.
+    ///
+    /// See [`TransportSend::push_with_deadline`].
+    fn expire_deadlines(&mut self, now: Instant) {
+        let reliable = self.kind.reliability() == LaneReliability::Reliable;
+        for msg in self.sent_msgs.values_mut() {
+            if msg.deadline.is_none_or(|deadline| now <= deadline) {
+                continue;
+            }
+            // a late message no longer gets priority over the others
+            msg.deadline = None;
+            if !msg.frags.iter().flatten().any(|frag| !frag.flushed) {
+                continue;
+            }
+
+            if reliable {
+                msg.late = true;
+            } else {
+                // it'll be dropped on the next flush, like any other unreliable
+                // message with no frags left to send
+                msg.frags.fill(None);
+                msg.undelivered = true;
+            }
+        }
+    }
+
+    /// Abandons messages which have reached this lane's retransmit limit.
+    ///
+    /// See [`TransportSend::set_retransmit_limit`].
+    fn abandon_retransmits(&mut self, now: Instant) {
+        if self.kind.reliability() != LaneReliability::Unreliable {
+            return;
+        }
+        let Some(limit) = self.retransmit_limit else {
+            return;
+        };
+        for msg in self.sent_msgs.values_mut() {
+            let abandon = msg.frags.iter().flatten().any(|frag| {
+                let out_of_retransmits = frag.flushed
+                    && now >= frag.next_flush_at
+                    && limit
+                        .max_retransmits
+                        .is_some_and(|max| frag.retransmits >= max);
+                let out_of_lifetime = limit
+                    .max_lifetime
+                    .is_some_and(|max| now.saturating_duration_since(frag.sent_at) > max);
+                out_of_retransmits || out_of_lifetime
+            });
+            if abandon {
+                // it'll be dropped on the next flush, like any other unreliable
+                // message with no frags left to send
+                msg.frags.fill(None);
+                msg.undelivered = true;
+            }
+        }
+    }
+
+    /// Takes the keys of all messages which missed their deadline, and have not
+    /// been reported yet.
+    pub(crate) fn take_late(
+        &mut self,
+        lane_index: LaneIndex,
+    ) -> impl Iterator<Item = MessageKey> + '_ {
+        self.sent_msgs
+            .iter_mut()
+            .filter(|(_, msg)| msg.late)
+            .map(move |(msg_seq, msg)| {
+                msg.late = false;
+                MessageKey {
+                    lane: lane_index,
+                    seq: *msg_seq,
+                }
+            })
+    }
+
+    /// Takes the contexts of all messages which have no more fragments left to
+    /// send, but were not acknowledged by the peer.
+    pub(crate) fn take_dropped_contexts(
+        &mut self,
+        lane_index: LaneIndex,
+    ) -> impl Iterator<Item = MessageContext> + '_ {
+        self.sent_msgs
+            .iter_mut()
+            .filter(|(_, msg)| msg.frags.iter().all(Option::is_none))
+            .filter_map(move |(msg_seq, msg)| {
+                msg.context.take().map(|context| {
+                    MessageContext::new(lane_index, *msg_seq, context, MessageOutcome::Dropped)
+                })
+            })
+    }
+
+    /// Takes the delivery reports of all tracked messages which have no more
+    /// fragments left to send, but were not acknowledged by the peer.
+    pub(crate) fn take_dropped_deliveries(
+        &mut self,
+        lane_index: LaneIndex,
+    ) -> impl Iterator<Item = DeliveryReport> + '_ {
+        self.sent_msgs
+            .iter_mut()
+            .filter(|(_, msg)| msg.track_delivery && msg.frags.iter().all(Option::is_none))
+            .map(move |(msg_seq, msg)| {
+                msg.track_delivery = false;
+                DeliveryReport {
+                    key: MessageKey {
+                        lane: lane_index,
+                        seq: *msg_seq,
+                    },
+                    delivery: if msg.undelivered {
+                        Delivery::Lost
+                    } else {
+                        Delivery::Unknown
+                    },
+                }
+            })
+    }
+}
+
+impl MessageContext {
+    pub(crate) fn new(
+        lane_index: LaneIndex,
+        msg_seq: MessageSeq,
+        context: Box<dyn Any + Send + Sync>,
+        outcome: MessageOutcome,
+    ) -> Self {
+        Self {
+            key: MessageKey {
+                lane: lane_index,
+                seq: msg_seq,
+            },
+            outcome,
+            context,
+        }
+    }
+
+    /// Gets a reference to the context, if it is of type `T`.
+    #[must_use]
+    pub fn get<T: 'static>(&self) -> Option<&T> {
+        self.context.downcast_ref()
+    }
+
+    /// Takes ownership of the context, if it is of type `T`.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the context is not of type `T`, giving back `self`.
+    pub fn downcast<T: 'static>(self) -> Result<T, Self> {
+        match self.context.downcast() {
+            Ok(context) => Ok(*context),
+            Err(context) => Err(Self { context, ..self }),
+        }
+    }
+}
+
+pub(crate) fn update_send_config(
+    mut sessions: Query<
+        (Entity, &mut Transport, &TransportConfig),
+        Or<(Added<Transport>, Changed<TransportConfig>)>,
+    >,
+) {
+    let now = Instant::now();
+    for (entity, mut transport, config) in &mut sessions {
+        if transport.is_added() {
+            start_slow_start(&mut transport, config, now);
+        } else if config.slow_start_bytes_per_sec.is_none() {
+            transport.send.slow_start = None;
+        }
+
+        let send_bytes_per_sec = send_bytes_per_sec(&mut transport, config, now);
+        transport.send.bytes_bucket.set_cap(send_bytes_per_sec);
+        transport.send.retransmit_priority = config.retransmit_priority;
+        transport.send.min_flush_interval = config.min_flush_interval;
+        transport.send.ack_policy = config.ack_policy;
+        if config.max_packets_per_flush == 0 {
+            warn!(
+                "{entity} has `max_packets_per_flush` set to 0, which would never send anything; \
+                 using 1 instead"
+            );
+        }
+        transport.send.max_packets_per_flush = config.max_packets_per_flush.max(1);
+        transport.clock.set_interval(config.clock_sync_interval);
+    }
+}
+
+pub(crate) fn refill_send_bytes(
+    time: Res<Time<Real>>,
+    mut sessions: Query<(&mut Transport, &TransportConfig)>,
+) {
+    let now = Instant::now();
+    for (mut transport, config) in &mut sessions {
+        if transport.send.slow_start.is_some() {
+            let send_bytes_per_sec = send_bytes_per_sec(&mut transport, config, now);
+            transport.send.bytes_bucket.set_cap(send_bytes_per_sec);
+        }
+
+        transport
+            .send
+            .bytes_bucket
+            .refill_portion(time.delta_secs_f64());
+    }
+}
+
+fn start_slow_start(transport: &mut Transport, config: &TransportConfig, now: Instant) {
+    transport.send.slow_start = config
+        .slow_start_bytes_per_sec
+        .map(|bytes_per_sec| SlowStart {
+            bytes_per_sec,
+            next_increase_at: now + transport.rtt.get(),
+            packets_lost: transport.stats.packets_lost.0,
+        });
+}
+
+/// Gets how many bytes per second we may currently send, advancing slow start
+/// if it is in progress.
+fn send_bytes_per_sec(transport: &mut Transport, config: &TransportConfig, now: Instant) -> usize {
+    let cap = config.send_bytes_per_sec;
+    let Some(slow_start) = &mut transport.send.slow_start else {
+        return cap;
+    };
+
+    if now >= slow_start.next_increase_at {
+        let packets_lost = transport.stats.packets_lost.0;
+        if packets_lost == slow_start.packets_lost {
+            slow_start.bytes_per_sec = slow_start.bytes_per_sec.saturating_mul(2);
+        }
+        slow_start.packets_lost = packets_lost;
+        slow_start.next_increase_at = now + transport.rtt.get();
+    }
+
+    if slow_start.bytes_per_sec >= cap {
+        transport.send.slow_start = None;
+        cap
+    } else {
+        slow_start.bytes_per_sec
+    }
+}
+
+pub(crate) fn detect_congestion(
+    mut sessions: Query<(Entity, &mut Transport)>,
+    mut commands: Commands,
+) {
+    for (entity, mut transport) in &mut sessions {
+        for (lane_index, lane) in transport.send.lanes.iter_mut().enumerate() {
+            let Some(watermarks) = lane.congestion_watermarks else {
+                continue;
+            };
+            let backlog = lane.sent_msgs.len();
+            let congested = hysteresis(lane.congested, backlog, watermarks.low, watermarks.high);
+            if congested == lane.congested {
+                continue;
+            }
+
+            lane.congested = congested;
+            let lane = LaneIndex::try_from(lane_index).expect("lane index too large");
+            trace!(%entity, lane = lane.0, congested, backlog, "Lane congestion changed");
+            commands.trigger_targets(
+                LaneCongestion {
+                    entity,
+                    lane,
+                    congested,
+                },
+                entity,
+            );
+        }
+    }
+}
+
+pub(crate) fn flush(
+    mut sessions: Query<
+        (
+            Entity,
+            &mut Session,
+            &mut Transport,
+            Option<&SessionPriority>,
+        ),
+        Without<DisconnectedRetained>,
+    >,
+    budget: Option<Res<FlushBudget>>,
+    mut next_session: Local<HashMap<SessionPriority, usize>>,
+) {
+    let now = Instant::now();
+    let Some(budget) = budget else {
+        for (_, mut session, mut transport, _) in &mut sessions {
+            let packet_mtu = session.mtu();
+            if !transport.send.flush_due(now) {
+                transport.send.last_flush_outcome = transport.send.skipped_flush_outcome();
+                session
+                    .send
+                    .extend(flush_standalone_acks(&mut transport, now, packet_mtu));
+                continue;
+            }
+            session
+                .send
+                .extend(flush_on(&mut transport, now, packet_mtu));
+        }
+        return;
+    };
+
+    // sort so that higher priorities come first,
+    // and the round-robin order is stable across updates
+    let mut sessions = sessions
         .iter_mut()
-        .enumerate()
-        .flat_map(|(lane_index, lane)| frag_paths_in_lane(now, lane_index, lane))
+        .map(|(entity, session, transport, priority)| {
+            (
+                priority.copied().unwrap_or_default(),
+                entity,
+                session,
+                transport,
+            )
+        })
         .collect::<Vec<_>>();
+    sessions.sort_unstable_by_key(|(priority, entity, _, _)| (Reverse(*priority), *entity));
+
+    let mut bytes_left = budget.max_bytes_per_update;
+    let mut deferred = false;
+    // each priority has its own round-robin order
+    for class in sessions.chunk_by_mut(|(a, ..), (b, ..)| a == b) {
+        if deferred {
+            // this whole class is deferred, and keeps its round-robin order
+            for (_, _, _, transport) in class {
+                transport.send.last_flush_outcome = transport.send.budget_flush_outcome(now, false);
+            }
+            continue;
+        }
 
-    // sort by time sent, oldest to newest
-    frag_paths.sort_unstable_by(|(_, sent_at_a), (_, sent_at_b)| sent_at_a.cmp(sent_at_b));
+        let next_session = next_session.entry(class[0].0).or_default();
+        let num_sessions = class.len();
+        let start = *next_session % num_sessions;
+        *next_session = start;
+        for offset in 0..num_sessions {
+            let index = (start + offset) % num_sessions;
+            let (_, entity, session, transport) = &mut class[index];
+            let packet_mtu = session.mtu();
+            if !deferred && bytes_left < packet_mtu {
+                trace!(
+                    deferred = num_sessions - offset,
+                    "Flush budget used up, deferring until next update"
+                );
+                *next_session = index;
+                deferred = true;
+            }
+            if deferred {
+                transport.send.last_flush_outcome = transport.send.budget_flush_outcome(now, false);
+                continue;
+            }
+            if !transport.send.flush_due(now) {
+                transport.send.last_flush_outcome = transport.send.skipped_flush_outcome();
+                if let Some(packet) = flush_standalone_acks(transport, now, packet_mtu) {
+                    bytes_left = bytes_left.saturating_sub(packet.len());
+                    session.send.push(packet);
+                }
+                continue;
+            }
 
-    let mut frag_paths = frag_paths
+            // `flush_on` only takes frags out of the lanes as it builds packets,
+            // so any frags we don't get to stay queued for the next flush
+            let mut packets = flush_on(transport, now, packet_mtu);
+            let mut out_of_budget = true;
+            while bytes_left >= packet_mtu {
+                let Some(packet) = packets.next() else {
+                    out_of_budget = false;
+                    break;
+                };
+                bytes_left -= packet.len();
+                session.send.push(packet);
+            }
+            drop(packets);
+            // `flush_on` only records its outcome once it runs out of packets
+            // to build, so if we stopped taking packets before then, we have
+            // to record it ourselves
+            if out_of_budget {
+                transport.send.last_flush_outcome = transport.send.budget_flush_outcome(now, true);
+            }
+            trace!(%entity, bytes_left, "Flushed session within budget");
+        }
+    }
+}
+
+/// Exposes `flush_on` for fuzz tests.
+#[cfg(fuzzing)]
+pub fn fuzz_flush_on(transport: &mut Transport, mtu: usize) -> impl Iterator<Item = Bytes> + '_ {
+    flush_on(transport, Instant::now(), mtu)
+}
+
+/// Builds up packets out of the fragments which are due to be flushed.
+///
+/// Acknowledgements are always written into the header of every packet, so
+/// they piggyback on any fragments being sent out in the same flush. If there
+/// are no fragments to send, we still send a single packet containing only the
+/// header, which acts as both an ack and a keep-alive for the peer. This means
+/// a single flush never emits a separate ack-only packet alongside packets
+/// carrying messages.
+///
+/// At most [`TransportConfig::max_packets_per_flush`] packets are produced.
+///
+/// No packet is ever longer than `mtu`, which is the current MTU of the
+/// session. Fragments are sized for the session's [`Session::min_mtu`], which
+/// the current MTU never drops below, so if the MTU shrinks while messages are
+/// still being sent, their fragments still fit into packets - they are just
+/// spread across more of them.
+///
+/// Once the iterator is exhausted, [`Transport::last_flush_outcome`] is
+/// updated with why the flush stopped.
+pub(crate) fn flush_on(
+    transport: &mut Transport,
+    now: Instant,
+    mtu: usize,
+) -> impl Iterator<Item = Bytes> + '_ {
+    let mut frag_paths = frag_paths_to_flush(&mut transport.send, now)
         .into_iter()
-        .map(|(path, _)| Some(path))
+        .map(Some)
         .collect::<Vec<_>>();
 
+    transport.send.forget_old_flushes(now);
+
+    // packets are only built as the iterator is advanced, so stopping early
+    // leaves any frags we didn't get to queued for the next flush
+    let max_packets = transport.send.max_packets_per_flush;
+    let mut num_packets = 0;
     let mut sent_packet_yet = false;
     iter::from_fn(move || {
+        let frags_left = frag_paths.iter().any(Option::is_some);
+        if num_packets >= max_packets {
+            transport.send.last_flush_outcome = if frags_left || transport.pings.is_queued() {
+                FlushOutcome::CapacityFull
+            } else {
+                transport.send.finished_flush_outcome()
+            };
+            return None;
+        }
+        num_packets += 1;
+
+        // pings get their own dedicated probe packets, so that the RTT sample
+        // isn't skewed by how long it takes to build a full packet
+        if transport.pings.is_queued() {
+            let Some((packet_seq, packet)) = flush_header_only(transport, now, mtu) else {
+                transport.send.last_flush_outcome = FlushOutcome::BudgetExhausted;
+                return None;
+            };
+            trace!(packet = packet_seq.0.0, "Flushed ping probe packet");
+            transport.pings.flushed(packet_seq, now);
+            sent_packet_yet = true;
+            return Some(packet);
+        }
+
         // this iteration, we want to build up one full packet
 
         // make a buffer for the packet
@@ -264,15 +1766,22 @@ fn flush_on(
         // ourselves, leading to very large `mtu`s (~512KiB)
         let mut packet = Vec::<u8>::new();
 
+        let header = next_header(transport, now, mtu);
+        let packet_seq = header.seq;
+
         // we can't put more than either `mtu` or `bytes_left`
         // bytes into this packet, so we track this as well
         let mut bytes_left = (&mut transport.send.bytes_bucket).min_of(mtu);
-        let packet_seq = transport.send.next_packet_seq;
-        let header = PacketHeader {
-            seq: packet_seq,
-            acks: transport.peer_acks,
-        };
-        bytes_left.consume(header.encode_len()).ok()?;
+        if bytes_left.consume(header.encode_len()).is_err() {
+            // if we have nothing left to send, we just couldn't fit the
+            // (optional) trailing ack-only packet
+            transport.send.last_flush_outcome = if frags_left || !sent_packet_yet {
+                FlushOutcome::BudgetExhausted
+            } else {
+                transport.send.finished_flush_outcome()
+            };
+            return None;
+        }
         packet
             .write(&header)
             .expect("should grow the buffer when writing over capacity");
@@ -283,11 +1792,13 @@ fn flush_on(
         // collect the paths of the frags we want to put into this packet
         // so that we can track which ones have been acked later
         let mut packet_frags = Vec::new();
+        #[cfg(feature = "cpu-time")]
+        let fragment_start = Instant::now();
         for path_opt in &mut frag_paths {
-            let Some(path) = path_opt else {
+            let Some((path, tag)) = path_opt else {
                 continue;
             };
-            let path = *path;
+            let (path, tag) = (*path, *tag);
 
             if write_frag_at_path(
                 now,
@@ -304,48 +1815,333 @@ fn flush_on(
                 // and track that this frag has been sent out in this packet
                 *path_opt = None;
                 packet_frags.push(path);
+                if let Some(tag) = tag {
+                    wfq_served(
+                        &mut transport.send.lanes,
+                        &mut transport.send.wfq_vtime,
+                        path.lane_index,
+                        tag,
+                    );
+                }
             }
         }
+        #[cfg(feature = "cpu-time")]
+        {
+            transport.cpu_time.fragment += fragment_start.elapsed();
+        }
 
+        // only send an empty (ack/keep-alive only) packet if we haven't sent
+        // anything else yet - otherwise, the acks already rode along in the
+        // packets we sent before
+        // NACKs never get a packet of their own, and wait for the next flush
         let should_send = !packet_frags.is_empty() || !sent_packet_yet;
         if !should_send {
+            // if we couldn't fit any of the remaining frags into an empty
+            // packet, we must be out of send bytes
+            transport.send.last_flush_outcome = if frag_paths.iter().any(Option::is_some) {
+                FlushOutcome::BudgetExhausted
+            } else {
+                transport.send.finished_flush_outcome()
+            };
             return None;
         }
 
         trace!(num_frags = packet_frags.len(), "Flushed packet");
-        transport
-            .flushed_packets
-            .insert(packet_seq.0.0, FlushedPacket {
-                flushed_at: now,
-                frags: packet_frags.into_boxed_slice(),
-            });
-
-        transport.send.next_packet_seq += PacketSeq::new(1);
+        packet_flushed(transport, now, &header, packet_frags.into_boxed_slice());
         sent_packet_yet = true;
+        debug_assert!(packet.len() <= mtu, "packet must fit into the MTU");
         Some(Bytes::from(packet))
     })
 }
 
+/// Collects the paths of all frags which are ready to be flushed, in the order
+/// that they should be sent in.
+fn frag_paths_to_flush(
+    send: &mut TransportSend,
+    now: Instant,
+) -> Vec<(FragmentPath, Option<WfqTag>)> {
+    // collect the paths of the frags to send, along with their message's
+    // deadline, how old they are, if they've been sent before, and their length
+    let mut frag_paths = send
+        .lanes
+        .iter_mut()
+        .enumerate()
+        .flat_map(|(lane_index, lane)| frag_paths_in_lane(now, lane_index, lane))
+        .collect::<Vec<_>>();
+
+    // messages with a deadline always go first, earliest deadline first
+    if send.retransmit_priority {
+        // sort retransmissions first, then by time sent, oldest to newest
+        frag_paths.sort_unstable_by_key(|(_, deadline, sent_at, flushed, _)| {
+            (deadline.is_none(), *deadline, !flushed, *sent_at)
+        });
+    } else {
+        // sort by time sent, oldest to newest
+        frag_paths.sort_unstable_by_key(|(_, deadline, sent_at, _, _)| {
+            (deadline.is_none(), *deadline, *sent_at)
+        });
+    }
+
+    wfq_order(send, frag_paths)
+}
+
+/// Builds a packet containing only the header, if there are acknowledgements
+/// which have not been flushed out yet.
+///
+/// See [`Transport::flush_acks_now`].
+pub(crate) fn flush_acks_on(transport: &mut Transport, now: Instant, mtu: usize) -> Option<Bytes> {
+    if !transport.acks_pending {
+        return None;
+    }
+
+    let (packet_seq, packet) = flush_header_only(transport, now, mtu)?;
+    trace!(packet = packet_seq.0.0, "Flushed ack-only packet");
+    Some(packet)
+}
+
+/// Builds a packet containing only the header for a transport which is not
+/// being flushed this update, if its [`AckPolicy`] says that its pending
+/// acknowledgements should not wait for the next flush.
+///
+/// See [`TransportConfig::ack_policy`].
+fn flush_standalone_acks(transport: &mut Transport, now: Instant, mtu: usize) -> Option<Bytes> {
+    // otherwise, two peers would keep acking each other's ack packets forever
+    if !transport.ack_eliciting_pending {
+        return None;
+    }
+
+    let standalone = match transport.send.ack_policy {
+        AckPolicy::Piggyback => false,
+        AckPolicy::Standalone => true,
+        AckPolicy::Hybrid { max_delay } => now
+            .checked_add(max_delay)
+            .is_some_and(|at| !transport.send.frags_due_by(at)),
+    };
+    if standalone {
+        flush_acks_on(transport, now, mtu)
+    } else {
+        None
+    }
+}
+
+/// Builds a packet containing only the header, which is tracked like any
+/// other flushed packet, so that we know when the peer acknowledges it.
+fn flush_header_only(
+    transport: &mut Transport,
+    now: Instant,
+    mtu: usize,
+) -> Option<(PacketSeq, Bytes)> {
+    let header = next_header(transport, now, mtu);
+    let packet_seq = header.seq;
+    (&mut transport.send.bytes_bucket)
+        .min_of(mtu)
+        .consume(header.encode_len())
+        .ok()?;
+    let mut packet = Vec::<u8>::with_capacity(header.encode_len());
+    packet
+        .write(&header)
+        .expect("should grow the buffer when writing over capacity");
+    packet_flushed(transport, now, &header, Box::default());
+    Some((packet_seq, Bytes::from(packet)))
+}
+
+/// Builds the header for the next packet we flush.
+fn next_header(transport: &mut Transport, now: Instant, mtu: usize) -> PacketHeader {
+    let packet_seq = transport.send.next_packet_seq;
+    PacketHeader {
+        protocol_version: PROTOCOL_VERSION,
+        seq: packet_seq,
+        acks: transport.peer_acks,
+        mtu: mtu_to_send(transport, mtu, packet_seq),
+        clock: transport.clock.timestamp_to_send(now),
+        nack: transport.pending_nacks.front().copied(),
+    }
+}
+
+/// Tracks a packet with the given header, carrying `frags`, as flushed.
+fn packet_flushed(
+    transport: &mut Transport,
+    now: Instant,
+    header: &PacketHeader,
+    frags: Box<[FragmentPath]>,
+) {
+    if header.clock.is_some() {
+        transport.clock.sent(now);
+    }
+    if header.nack.is_some() {
+        transport.pending_nacks.pop_front();
+    }
+
+    transport.flushed_packets.insert(
+        header.seq.0.0,
+        FlushedPacket {
+            flushed_at: now,
+            lost: false,
+            loss_confirmed: false,
+            frags,
+        },
+    );
+    transport.track_unacked_packets();
+
+    transport.send.recent_flushes.push_back(now);
+    transport.send.next_packet_seq += PacketSeq::new(1);
+    transport.acks_pending = false;
+    transport.ack_eliciting_pending = false;
+}
+
+/// Gets the MTU to advertise to the peer in the header of `packet_seq`.
+///
+/// Our MTU is only sent until the peer has received a packet carrying it, and
+/// again whenever it changes.
+fn mtu_to_send(transport: &mut Transport, mtu: usize, packet_seq: PacketSeq) -> Option<u32> {
+    if transport
+        .sent_mtu
+        .is_none_or(|(sent_mtu, _)| sent_mtu != mtu)
+    {
+        transport.sent_mtu = Some((mtu, packet_seq));
+        transport.sent_mtu_acked = false;
+    }
+    (!transport.sent_mtu_acked).then(|| u32::try_from(mtu).unwrap_or(u32::MAX))
+}
+
+/// Virtual start and finish times of a fragment on a weighted lane.
+///
+/// See [`TransportSend::set_lane_weight`].
+#[derive(Debug, Clone, Copy)]
+struct WfqTag {
+    start: u64,
+    finish: u64,
+}
+
+/// Advances the virtual time of weighted fair queuing after a frag on a
+/// weighted lane has been written into a packet.
+fn wfq_served(lanes: &mut [SendLane], wfq_vtime: &mut u64, lane_index: LaneIndex, tag: WfqTag) {
+    let lane = &mut lanes[usize::from(lane_index)];
+    lane.wfq_finish = lane.wfq_finish.max(tag.finish);
+    *wfq_vtime = (*wfq_vtime).max(tag.start);
+}
+
+/// Orders already-sorted frag paths by weighted fair queuing, if any lanes are
+/// weighted.
+///
+/// Each frag on a weighted lane is tagged with the virtual times at which it
+/// would start and finish being sent, if every lane was served at a rate
+/// proportional to its weight, and frags are sent in order of their start tags.
+/// A lane which was idle starts from the current virtual time, so it can't
+/// build up credit.
+///
+/// Since frags are re-tagged on every flush, we order by start tags rather
+/// than finish tags: the virtual time only ever advances to the start tag of a
+/// frag which was sent, so it never overtakes a lane which is still backlogged,
+/// and that lane keeps its place across flushes.
+/// Frags with a deadline, retransmissions if
+/// [`TransportConfig::retransmit_priority`] is enabled, and frags on unweighted
+/// lanes, keep their order and go first.
+fn wfq_order(
+    send: &TransportSend,
+    frag_paths: Vec<(FragmentPath, Option<Instant>, Instant, bool, usize)>,
+) -> Vec<(FragmentPath, Option<WfqTag>)> {
+    if send.lanes.iter().all(|lane| lane.weight.is_none()) {
+        return frag_paths
+            .into_iter()
+            .map(|(path, ..)| (path, None))
+            .collect();
+    }
+
+    let mut lane_finish = send
+        .lanes
+        .iter()
+        .map(|lane| lane.wfq_finish.max(send.wfq_vtime))
+        .collect::<Vec<_>>();
+    let mut frag_paths = frag_paths
+        .into_iter()
+        .map(|(path, deadline, _, flushed, len)| {
+            let lane_index = usize::from(path.lane_index);
+            let tag = send.lanes[lane_index].weight.map(|weight| {
+                let finish = &mut lane_finish[lane_index];
+                let start = *finish;
+                let len = u64::try_from(len).unwrap_or(u64::MAX);
+                let cost = len.saturating_mul(WFQ_SCALE) / u64::from(weight);
+                *finish = start.saturating_add(cost);
+                WfqTag {
+                    start,
+                    finish: *finish,
+                }
+            });
+            let retransmit = send.retransmit_priority && flushed;
+            (path, deadline, retransmit, tag)
+        })
+        .collect::<Vec<_>>();
+
+    // stable, so that frags with the same tag keep their previous order
+    frag_paths.sort_by_key(|(_, deadline, retransmit, tag)| {
+        (
+            deadline.is_none(),
+            *deadline,
+            !retransmit,
+            tag.map(|tag| tag.start),
+        )
+    });
+    frag_paths
+        .into_iter()
+        .map(|(path, _, _, tag)| (path, tag))
+        .collect()
+}
+
 fn frag_paths_in_lane(
     now: Instant,
     lane_index: usize,
     lane: &mut SendLane,
-) -> impl Iterator<Item = (FragmentPath, Instant)> + '_ {
+) -> impl Iterator<Item = (FragmentPath, Option<Instant>, Instant, bool, usize)> + '_ {
     let lane_index = LaneIndex::try_from(lane_index).expect("lane index too large");
 
     // drop any messages which have no frags to send
     lane.sent_msgs
         .retain(|_, msg| msg.frags.iter().any(Option::is_some));
+    lane.expire_deadlines(now);
+    lane.abandon_retransmits(now);
+
+    // if this lane limits how many messages it has in flight,
+    // only the oldest messages which fit into the window may start being sent
+    let new_msgs_allowed = lane
+        .max_in_flight_msgs
+        .filter(|_| lane.kind.reliability() == LaneReliability::Reliable)
+        .map(|max| {
+            let free = max.saturating_sub(lane.num_in_flight_msgs());
+            let mut new_msgs = lane
+                .sent_msgs
+                .iter()
+                .filter(|(_, msg)| !msg.is_in_flight())
+                .map(|(msg_seq, msg)| (*msg_seq, msg.pushed_at()))
+                .collect::<Vec<_>>();
+            new_msgs.sort_unstable_by_key(|(msg_seq, pushed_at)| (*pushed_at, msg_seq.0.0));
+            new_msgs
+                .into_iter()
+                .take(free)
+                .map(|(msg_seq, _)| msg_seq)
+                .collect::<HashSet<_>>()
+        });
 
     // grab the frag paths from this lane's messages
-    lane.sent_msgs.iter().flat_map(move |(msg_seq, msg)| {
-        msg.frags
+    let resends =
+        lane.kind.reliability() == LaneReliability::Reliable || lane.retransmit_limit.is_some();
+    lane.sent_msgs
+        .iter()
+        .filter(move |(msg_seq, msg)| {
+            new_msgs_allowed
+                .as_ref()
+                .is_none_or(|allowed| msg.is_in_flight() || allowed.contains(msg_seq))
+        })
+        .flat_map(move |(msg_seq, msg)| {
+            msg.frags
             .iter()
             // we have to enumerate here specifically, since we use the index
             // when building up the `FragmentPath`, and that path has to point
             // back to this exact `Option<..>`
             .enumerate()
             .filter_map(|(i, frag)| frag.as_ref().map(|frag| (i, frag)))
+            // unreliable frags which are only waiting for a receipt are never resent
+            .filter(move |(_, frag)| resends || !frag.flushed)
             .filter(move |(_, frag)| now >= frag.next_flush_at)
             .map(move |(frag_index, frag)| {
                 let frag_index = FragmentIndex::try_from(frag_index)
@@ -356,10 +2152,13 @@ fn frag_paths_in_lane(
                         msg_seq: *msg_seq,
                         frag_index,
                     },
+                    msg.deadline,
                     frag.sent_at,
+                    frag.flushed,
+                    frag.payload.len(),
                 )
             })
-    })
+        })
 }
 
 fn write_frag_at_path(
@@ -403,6 +2202,21 @@ fn write_frag_at_path(
 
     // what does the lane do with this after sending?
     match &lane.kind.reliability() {
+        LaneReliability::Unreliable if lane.retransmit_limit.is_some() => {
+            // like a reliable frag, but count how many times we've resent it,
+            // so that we can give up on it
+            if sent_frag.flushed {
+                sent_frag.retransmits += 1;
+            }
+            sent_frag.next_flush_at = now + rtt.pto();
+            sent_frag.flushed = true;
+        }
+        LaneReliability::Unreliable if lane.unreliable_receipts => {
+            // keep the frag around without its payload, so that we can report
+            // the message once the peer acks it - but never resend it
+            sent_frag.payload = Bytes::new();
+            sent_frag.flushed = true;
+        }
         LaneReliability::Unreliable => {
             // drop the frag
             // if we've dropped all frags of this message, then
@@ -413,8 +2227,1105 @@ fn write_frag_at_path(
             // don't drop the frag, just attempt to resend it later
             // it'll be dropped when the peer acks it
             sent_frag.next_flush_at = now + rtt.pto();
+            sent_frag.flushed = true;
         }
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    #![expect(clippy::unwrap_used, reason = "testing")]
+
+    use {
+        super::*,
+        crate::{packet::Acknowledge, recv::recv_on},
+        aeronet_io::packet::IP_MTU,
+        octs::{Buf, Read},
+    };
+
+    const LANES: [LaneKind; 1] = [LaneKind::ReliableOrdered];
+
+    fn transport(now: Instant) -> Transport {
+        let session = Session::new(now, IP_MTU);
+        let mut transport = Transport::new(&session, LANES, LANES, now).unwrap();
+        transport.send.bytes_bucket = TokenBucket::new(usize::MAX);
+        transport
+    }
+
+    #[test]
+    fn ack_only_flush_sends_one_packet() {
+        let now = Instant::now();
+        let mut transport = transport(now);
+        transport.peer_acks.ack(PacketSeq::new(0));
+
+        assert_eq!(1, flush_on(&mut transport, now, IP_MTU).count());
+    }
+
+    #[test]
+    fn flush_budget() {
+        const NUM_SESSIONS: usize = 32;
+        const BUDGET: usize = 4 * IP_MTU;
+
+        fn all_flushed(world: &mut World) -> bool {
+            world.query::<&Transport>().iter(world).all(|transport| {
+                transport.send.lanes.iter().all(|lane| {
+                    lane.sent_msgs
+                        .values()
+                        .flat_map(|msg| msg.frags.iter().flatten())
+                        .all(|frag| frag.flushed)
+                })
+            })
+        }
+
+        let now = Instant::now();
+        let mut world = World::new();
+        world.insert_resource(FlushBudget {
+            max_bytes_per_update: BUDGET,
+        });
+        for _ in 0..NUM_SESSIONS {
+            let mut transport = transport(now);
+            transport
+                .send
+                .push(LaneIndex(0), Bytes::from(vec![0; 3 * IP_MTU]), now)
+                .unwrap();
+            world.spawn((Session::new(now, IP_MTU), transport));
+        }
+
+        let mut schedule = Schedule::default();
+        schedule.add_systems(flush);
+
+        let mut updates = 0;
+        while !all_flushed(&mut world) {
+            schedule.run(&mut world);
+            updates += 1;
+            assert!(updates < 100, "sessions were starved");
+
+            let flushed = world
+                .query::<&mut Session>()
+                .iter_mut(&mut world)
+                .flat_map(|mut session| session.send.drain(..).collect::<Vec<_>>())
+                .map(|packet| packet.len())
+                .sum::<usize>();
+            assert!(flushed <= BUDGET, "flushed {flushed} / {BUDGET} bytes");
+        }
+        // this much work can't fit into a single update's budget
+        assert!(updates >= NUM_SESSIONS * 3 / 4);
+    }
+
+    #[test]
+    fn flush_budget_priority() {
+        const BUDGET: usize = 2 * IP_MTU;
+
+        fn num_unflushed(world: &World, entity: Entity) -> usize {
+            world
+                .get::<Transport>(entity)
+                .unwrap()
+                .send
+                .lanes
+                .iter()
+                .flat_map(|lane| lane.sent_msgs.values())
+                .flat_map(|msg| msg.frags.iter().flatten())
+                .filter(|frag| !frag.flushed)
+                .count()
+        }
+
+        let now = Instant::now();
+        let mut world = World::new();
+        world.insert_resource(FlushBudget {
+            max_bytes_per_update: BUDGET,
+        });
+
+        // spawned first, so it would be flushed first without priorities
+        let mut low_transport = transport(now);
+        low_transport
+            .send
+            .push(LaneIndex(0), Bytes::from(vec![0; 8 * IP_MTU]), now)
+            .unwrap();
+        let low = world.spawn((Session::new(now, IP_MTU), low_transport)).id();
+
+        let mut high_transport = transport(now);
+        high_transport
+            .send
+            .push(LaneIndex(0), Bytes::from_static(b"urgent"), now)
+            .unwrap();
+        let high = world
+            .spawn((
+                Session::new(now, IP_MTU),
+                high_transport,
+                SessionPriority(1),
+            ))
+            .id();
+
+        let mut schedule = Schedule::default();
+        schedule.add_systems(flush);
+        schedule.run(&mut world);
+
+        assert_eq!(0, num_unflushed(&world, high));
+        assert_eq!(1, world.get::<Session>(high).unwrap().send.len());
+        // the rest of the budget goes to the lower priority session
+        assert!(num_unflushed(&world, low) > 0);
+        assert!(!world.get::<Session>(low).unwrap().send.is_empty());
+    }
+
+    #[test]
+    fn flush_acks_now() {
+        let now = Instant::now();
+        let mut sender = transport(now);
+        let mut receiver = transport(now);
+        let mut receiver_session = Session::new(now, IP_MTU);
+        let config = TransportConfig::default();
+
+        // nothing to acknowledge yet
+        assert!(!receiver.flush_acks_now(&mut receiver_session, now));
+        assert!(receiver_session.send.is_empty());
+
+        sender
+            .send
+            .push(LaneIndex(0), Bytes::from_static(b"request"), now)
+            .unwrap();
+        for packet in flush_on(&mut sender, now, IP_MTU) {
+            recv_on(&mut receiver, &config, now, &packet).unwrap();
+        }
+
+        assert!(receiver.flush_acks_now(&mut receiver_session, now));
+        assert_eq!(1, receiver_session.send.len());
+        let mut packet = receiver_session.send[0].clone();
+        let header = packet.read::<PacketHeader>().unwrap();
+        let mut expected_acks = Acknowledge::default();
+        expected_acks.ack(PacketSeq::new(0));
+        assert_eq!(expected_acks, header.acks);
+        assert!(!packet.has_remaining());
+
+        // acks have already been flushed
+        assert!(!receiver.flush_acks_now(&mut receiver_session, now));
+        assert_eq!(1, receiver_session.send.len());
+    }
+
+    #[test]
+    fn ack_policy() {
+        // the receiver has received a packet, but isn't due to flush for a
+        // long time, so its acks can only go out in a dedicated packet
+        fn received(policy: AckPolicy, queue_msg: bool) -> (World, Schedule, Entity) {
+            let now = Instant::now();
+            let mut sender = transport(now);
+            let mut receiver = transport(now);
+            receiver.send.min_flush_interval = Duration::from_secs(3600);
+            receiver.send.ack_policy = policy;
+
+            let mut world = World::new();
+            let entity = world.spawn((Session::new(now, IP_MTU), receiver)).id();
+            let mut schedule = Schedule::default();
+            schedule.add_systems(flush);
+
+            // the first flush isn't held back, and has nothing to acknowledge
+            schedule.run(&mut world);
+            world.get_mut::<Session>(entity).unwrap().send.clear();
+
+            sender
+                .send
+                .push(LaneIndex(0), Bytes::from_static(b"request"), now)
+                .unwrap();
+            let mut receiver = world.get_mut::<Transport>(entity).unwrap();
+            for packet in flush_on(&mut sender, now, IP_MTU) {
+                recv_on(&mut receiver, &TransportConfig::default(), now, &packet).unwrap();
+            }
+            if queue_msg {
+                receiver
+                    .send
+                    .push(LaneIndex(0), Bytes::from_static(b"response"), now)
+                    .unwrap();
+            }
+            (world, schedule, entity)
+        }
+
+        fn flushed(world: &mut World, entity: Entity) -> Vec<Bytes> {
+            world
+                .get_mut::<Session>(entity)
+                .unwrap()
+                .send
+                .drain(..)
+                .collect()
+        }
+
+        let mut expected_acks = Acknowledge::default();
+        expected_acks.ack(PacketSeq::new(0));
+
+        // standalone: acks go out promptly, on their own
+        let (mut world, mut schedule, entity) = received(AckPolicy::Standalone, false);
+        schedule.run(&mut world);
+        let packets = flushed(&mut world, entity);
+        assert_eq!(1, packets.len());
+        let mut packet = packets[0].clone();
+        assert_eq!(expected_acks, packet.read::<PacketHeader>().unwrap().acks);
+        assert!(!packet.has_remaining());
+        // and only once
+        schedule.run(&mut world);
+        assert!(flushed(&mut world, entity).is_empty());
+
+        // piggyback: acks wait for the next flush
+        let (mut world, mut schedule, entity) = received(AckPolicy::Piggyback, false);
+        schedule.run(&mut world);
+        assert!(flushed(&mut world, entity).is_empty());
+        world
+            .get_mut::<Transport>(entity)
+            .unwrap()
+            .send
+            .min_flush_interval = Duration::ZERO;
+        schedule.run(&mut world);
+        let packets = flushed(&mut world, entity);
+        assert_eq!(1, packets.len());
+        let mut packet = packets[0].clone();
+        assert_eq!(expected_acks, packet.read::<PacketHeader>().unwrap().acks);
+
+        // hybrid: acks go out on their own if no messages are due soon...
+        let max_delay = Duration::from_secs(1);
+        let (mut world, mut schedule, entity) = received(AckPolicy::Hybrid { max_delay }, true);
+        schedule.run(&mut world);
+        assert_eq!(1, flushed(&mut world, entity).len());
+
+        // ...and wait for them otherwise
+        let max_delay = Duration::from_secs(7200);
+        let (mut world, mut schedule, entity) = received(AckPolicy::Hybrid { max_delay }, true);
+        schedule.run(&mut world);
+        assert!(flushed(&mut world, entity).is_empty());
+    }
+
+    #[test]
+    fn standalone_acks_settle_when_idle() {
+        let now = Instant::now();
+        let mut world = World::new();
+        let [a, b] = [(); 2].map(|()| {
+            let mut transport = transport(now);
+            transport.send.min_flush_interval = Duration::from_secs(3600);
+            transport.send.ack_policy = AckPolicy::Standalone;
+            world.spawn((Session::new(now, IP_MTU), transport)).id()
+        });
+        world
+            .get_mut::<Transport>(a)
+            .unwrap()
+            .send
+            .push(LaneIndex(0), Bytes::from_static(b"hello"), now)
+            .unwrap();
+
+        let mut schedule = Schedule::default();
+        schedule.add_systems(flush);
+        let config = TransportConfig::default();
+
+        let mut exchanged = Vec::new();
+        for _ in 0..8 {
+            schedule.run(&mut world);
+            let mut num_packets = 0;
+            for (from, to) in [(a, b), (b, a)] {
+                let packets = world
+                    .get_mut::<Session>(from)
+                    .unwrap()
+                    .send
+                    .drain(..)
+                    .collect::<Vec<_>>();
+                num_packets += packets.len();
+                let mut to = world.get_mut::<Transport>(to).unwrap();
+                for packet in packets {
+                    recv_on(&mut to, &config, Instant::now(), &packet).unwrap();
+                }
+            }
+            exchanged.push(num_packets);
+        }
+
+        // first, the message and a keep-alive from each side, then the ack for
+        // the message - but nobody acks the acks
+        assert_eq!(vec![2, 1, 0, 0, 0, 0, 0, 0], exchanged);
+    }
+
+    #[test]
+    fn max_message_size() {
+        let now = Instant::now();
+        // each fragment carries a single byte of payload
+        let session = Session::new(now, crate::FRAG_OVERHEAD + 1);
+        let mut transport = Transport::new(&session, LANES, LANES, now).unwrap();
+        let max = transport.max_message_size();
+        assert_eq!(frag::MAX_FRAGS, max);
+
+        transport
+            .send
+            .push(LaneIndex(0), Bytes::from(vec![0; max]), now)
+            .unwrap();
+        assert_eq!(
+            PushError::TooLarge {
+                len: max + 1,
+                max,
+                msg: Bytes::from(vec![0; max + 1]),
+            },
+            transport
+                .send
+                .push(LaneIndex(0), Bytes::from(vec![0; max + 1]), now)
+                .unwrap_err()
+        );
+        assert_eq!(1, transport.send.lanes[0].num_queued_msgs());
+    }
+
+    #[test]
+    fn coalesce_ack_and_msg() {
+        let now = Instant::now();
+        let mut transport = transport(now);
+        transport.peer_acks.ack(PacketSeq::new(0));
+        transport
+            .send
+            .push(LaneIndex(0), Bytes::from_static(b"hi"), now)
+            .unwrap();
+
+        let packets = flush_on(&mut transport, now, IP_MTU).collect::<Vec<_>>();
+        assert_eq!(1, packets.len());
+
+        let mut packet = packets[0].clone();
+        let header = packet.read::<PacketHeader>().unwrap();
+        let mut expected_acks = Acknowledge::default();
+        expected_acks.ack(PacketSeq::new(0));
+        assert_eq!(expected_acks, header.acks);
+
+        let frag = packet.read::<Fragment>().unwrap();
+        assert_eq!(LaneIndex(0), frag.header.lane);
+        assert_eq!(b"hi", &*frag.payload.0);
+        assert!(!packet.has_remaining());
+    }
+
+    #[test]
+    fn latest_lane_sends_only_newest() {
+        let now = Instant::now();
+        let session = Session::new(now, IP_MTU);
+        let lanes = [LaneKind::UnreliableLatest];
+        let mut transport = Transport::new(&session, lanes, lanes, now).unwrap();
+        transport.send.bytes_bucket = TokenBucket::new(usize::MAX);
+        for msg in [b"one", b"two", b"six"] {
+            transport
+                .send
+                .push(LaneIndex(0), Bytes::from_static(msg), now)
+                .unwrap();
+        }
+
+        let packets = flush_on(&mut transport, now, IP_MTU).collect::<Vec<_>>();
+        assert_eq!(1, packets.len());
+
+        let mut packet = packets[0].clone();
+        packet.read::<PacketHeader>().unwrap();
+        let frag = packet.read::<Fragment>().unwrap();
+        assert_eq!(MessageSeq::new(2), frag.header.seq);
+        assert_eq!(b"six", &*frag.payload.0);
+        assert!(!packet.has_remaining());
+
+        // superseded messages are gone, and aren't sent later either
+        for mut packet in flush_on(&mut transport, now, IP_MTU) {
+            packet.read::<PacketHeader>().unwrap();
+            assert!(!packet.has_remaining());
+        }
+        assert_eq!(0, transport.send.lanes()[0].num_queued_msgs());
+    }
+
+    #[test]
+    fn flush_times_follow_pacing() {
+        const PACING: Duration = Duration::from_millis(50);
+        const NUM_MSGS: u32 = 4;
+
+        let start = Instant::now();
+        let session = Session::new(start, IP_MTU);
+        let lanes = [LaneKind::UnreliableUnordered];
+        let mut transport = Transport::new(&session, lanes, lanes, start).unwrap();
+        // only enough bytes to send one message per refill
+        transport.send.bytes_bucket = TokenBucket::new(100);
+        for _ in 0..NUM_MSGS {
+            transport
+                .send
+                .push(LaneIndex(0), Bytes::from_static(&[0; 60]), start)
+                .unwrap();
+        }
+
+        for i in 0..NUM_MSGS {
+            let now = start + PACING * i;
+            transport.send.bytes_bucket.refill();
+            assert_eq!(1, flush_on(&mut transport, now, IP_MTU).count());
+        }
+
+        let flushes = transport.send.recent_flushes().collect::<Vec<_>>();
+        assert_eq!(NUM_MSGS as usize, flushes.len());
+        assert!(flushes.is_sorted());
+        assert!(
+            transport
+                .send
+                .flush_spacing()
+                .all(|spacing| spacing == PACING)
+        );
+
+        // old flushes are forgotten once they fall out of the history window
+        let now = start + FLUSH_HISTORY + PACING * NUM_MSGS;
+        transport.send.bytes_bucket.refill();
+        assert_eq!(1, flush_on(&mut transport, now, IP_MTU).count());
+        assert_eq!(1, transport.send.recent_flushes().len());
+    }
+
+    #[test]
+    fn backpressure_before_memory_limit() {
+        const THRESHOLD: usize = 64;
+
+        let now = Instant::now();
+        let mut transport = transport(now);
+        transport
+            .send
+            .set_backpressure_threshold(LaneIndex(0), Some(THRESHOLD));
+        let max_memory_usage = TransportConfig::default().max_memory_usage;
+
+        // the peer never acks anything, so the reliable backlog keeps growing
+        let msg = Bytes::from(vec![0; 1024]);
+        let mut num_pushed = 0;
+        let err = loop {
+            match transport.send.push(LaneIndex(0), msg.clone(), now) {
+                Ok(_) => num_pushed += 1,
+                Err(err) => break err,
+            }
+            _ = flush_on(&mut transport, now, IP_MTU).count();
+        };
+
+        assert_eq!(THRESHOLD, num_pushed);
+        assert_eq!(
+            PushError::Backpressured {
+                backlog: THRESHOLD,
+                threshold: THRESHOLD,
+                msg,
+            },
+            err
+        );
+        assert!(transport.memory_used() < max_memory_usage);
+        assert!(!transport.send.too_many_msgs);
+    }
+
+    #[test]
+    fn cancel_partially_flushed() {
+        let now = Instant::now();
+        let mut transport = transport(now);
+        let max_frag_len = transport.send.max_frag_len;
+
+        let msg = Bytes::from(vec![0; max_frag_len * 4]);
+        let key = transport.send.push(LaneIndex(0), msg, now).unwrap();
+
+        // only enough bytes to flush out a single fragment
+        transport.send.bytes_bucket = TokenBucket::new(max_frag_len + 32);
+        let packets = flush_on(&mut transport, now, IP_MTU).collect::<Vec<_>>();
+        assert_eq!(1, packets.len());
+        let mut packet = packets[0].clone();
+        packet.read::<PacketHeader>().unwrap();
+        assert_eq!(key.seq, packet.read::<Fragment>().unwrap().header.seq);
+
+        assert!(transport.cancel(key));
+        assert!(!transport.cancel(key));
+        assert_eq!(0, transport.send.lanes()[0].num_queued_msgs());
+
+        // even once the fragments are due to be resent, nothing is flushed
+        // apart from an empty packet
+        transport.send.bytes_bucket = TokenBucket::new(usize::MAX);
+        let later = now + transport.rtt().pto() * 2;
+        let packets = flush_on(&mut transport, later, IP_MTU).collect::<Vec<_>>();
+        assert_eq!(1, packets.len());
+        let mut packet = packets[0].clone();
+        packet.read::<PacketHeader>().unwrap();
+        assert!(!packet.has_remaining());
+    }
+
+    #[test]
+    fn whole_msg_only_kept_on_reliable_lanes() {
+        const LANES: [LaneKind; 2] = [LaneKind::ReliableOrdered, LaneKind::UnreliableUnordered];
+
+        let now = Instant::now();
+        let session = Session::new(now, IP_MTU);
+        let mut transport = Transport::new(&session, LANES, LANES, now).unwrap();
+        let max_frag_len = transport.send.max_frag_len;
+
+        let msg = Bytes::from(vec![0; max_frag_len * 4]);
+        let reliable = transport.send.push(LaneIndex(0), msg.clone(), now).unwrap();
+        let unreliable = transport.send.push(LaneIndex(1), msg, now).unwrap();
+        assert!(
+            transport.send.lanes[0].sent_msgs[&reliable.seq]
+                .msg
+                .is_some()
+        );
+        assert!(
+            transport.send.lanes[1].sent_msgs[&unreliable.seq]
+                .msg
+                .is_none()
+        );
+
+        // the whole message shares its allocation with the fragments,
+        // so its bytes are only counted once the fragments are gone
+        let before_ack = transport.memory_used();
+        let sent_msg = transport.send.lanes[0]
+            .sent_msgs
+            .get_mut(&reliable.seq)
+            .unwrap();
+        assert_eq!(0, sent_msg.retained_len());
+        sent_msg.frags[0] = None;
+        assert_eq!(max_frag_len, sent_msg.retained_len());
+        assert!(transport.memory_used() >= before_ack);
+    }
+
+    #[test]
+    fn nacks_only_ride_on_sent_packets() {
+        let now = Instant::now();
+        let mut transport = transport(now);
+        for seq in 0..2 {
+            transport.pending_nacks.push_back(FragmentNack {
+                lane: LaneIndex(0),
+                seq: MessageSeq::new(seq),
+                index: 0,
+            });
+        }
+
+        // the ack-only packet carries one NACK, and the other waits
+        let mut packets = flush_on(&mut transport, now, IP_MTU).collect::<Vec<_>>();
+        assert_eq!(1, packets.len());
+        let header = packets[0].read::<PacketHeader>().unwrap();
+        assert_eq!(Some(MessageSeq::new(0)), header.nack.map(|nack| nack.seq));
+        assert_eq!(1, transport.pending_nacks.len());
+    }
+
+    #[test]
+    fn retransmit_priority() {
+        fn first_frag_seq(transport: &mut Transport, now: Instant) -> MessageSeq {
+            let mut packet = flush_on(transport, now, IP_MTU).next().unwrap();
+            packet.read::<PacketHeader>().unwrap();
+            packet.read::<Fragment>().unwrap().header.seq
+        }
+
+        fn flush_order(retransmit_priority: bool) -> (MessageKey, MessageKey, MessageSeq) {
+            let now = Instant::now();
+            let mut transport = transport(now);
+            let max_frag_len = transport.send.max_frag_len;
+
+            // `old` is too large to be flushed with our byte budget,
+            // so only `new` is flushed, and is now waiting to be retransmitted
+            let old = transport
+                .send
+                .push(LaneIndex(0), Bytes::from(vec![0; max_frag_len]), now)
+                .unwrap();
+            let now = now + Duration::from_millis(1);
+            let new = transport
+                .send
+                .push(LaneIndex(0), Bytes::from_static(b"new"), now)
+                .unwrap();
+            transport.send.bytes_bucket = TokenBucket::new(64);
+            assert_eq!(new.seq, first_frag_seq(&mut transport, now));
+
+            let later = now + transport.rtt().pto() * 2;
+            transport.send.bytes_bucket = TokenBucket::new(usize::MAX);
+            transport.send.retransmit_priority = retransmit_priority;
+            (old, new, first_frag_seq(&mut transport, later))
+        }
+
+        // by default, the older message goes first, even though it has never
+        // been sent..
+        let (old, _, first) = flush_order(false);
+        assert_eq!(old.seq, first);
+
+        // ..but the retransmission of the newer message can go first instead
+        let (_, new, first) = flush_order(true);
+        assert_eq!(new.seq, first);
+    }
+
+    #[test]
+    fn retransmit_priority_with_weights() {
+        const LANES: [LaneKind; 2] = [LaneKind::ReliableOrdered; 2];
+
+        let now = Instant::now();
+        let session = Session::new(now, IP_MTU);
+        let mut transport = Transport::new(&session, LANES, LANES, now).unwrap();
+        transport.send.bytes_bucket = TokenBucket::new(usize::MAX);
+        transport.send.retransmit_priority = true;
+        transport.send.set_lane_weight(LaneIndex(0), Some(1));
+        transport.send.set_lane_weight(LaneIndex(1), Some(1));
+
+        // `resend` uses up some of its lane's fair share..
+        let resend = transport
+            .send
+            .push(LaneIndex(0), Bytes::from_static(b"resend"), now)
+            .unwrap();
+        assert_eq!(1, flush_on(&mut transport, now, IP_MTU).count());
+
+        // ..so `fresh` has an earlier start tag, since its lane was idle..
+        let fresh = transport
+            .send
+            .push(LaneIndex(1), Bytes::from_static(b"fresh"), now)
+            .unwrap();
+        let later = now + transport.rtt().pto() * 2;
+        let mut packet = flush_on(&mut transport, later, IP_MTU).next().unwrap();
+        packet.read::<PacketHeader>().unwrap();
+
+        // ..but the retransmission still goes first
+        let first = packet.read::<Fragment>().unwrap().header;
+        assert_eq!((LaneIndex(0), resend.seq), (first.lane, first.seq));
+        let second = packet.read::<Fragment>().unwrap().header;
+        assert_eq!((LaneIndex(1), fresh.seq), (second.lane, second.seq));
+    }
+
+    #[test]
+    fn weighted_fair_queuing() {
+        const LANES: [LaneKind; 2] = [LaneKind::UnreliableUnordered; 2];
+        const NUM_FLUSHES: usize = 10;
+
+        let now = Instant::now();
+        let session = Session::new(now, IP_MTU);
+        let mut transport = Transport::new(&session, LANES, LANES, now).unwrap();
+        transport.send.set_lane_weight(LaneIndex(0), Some(4));
+        transport.send.set_lane_weight(LaneIndex(1), Some(1));
+
+        // both lanes have far more queued up than we can flush
+        let max_frag_len = transport.send.max_frag_len;
+        for _ in 0..100 {
+            for lane in [LaneIndex(0), LaneIndex(1)] {
+                transport
+                    .send
+                    .push(lane, Bytes::from(vec![0; max_frag_len]), now)
+                    .unwrap();
+            }
+        }
+
+        let mut lane_bytes = [0usize; 2];
+        for _ in 0..NUM_FLUSHES {
+            transport.send.bytes_bucket = TokenBucket::new(8 * IP_MTU);
+            for mut packet in flush_on(&mut transport, now, IP_MTU) {
+                packet.read::<PacketHeader>().unwrap();
+                while packet.has_remaining() {
+                    let frag = packet.read::<Fragment>().unwrap();
+                    lane_bytes[usize::from(frag.header.lane)] += frag.payload.0.len();
+                }
+            }
+        }
+
+        let total = lane_bytes[0] + lane_bytes[1];
+        #[expect(clippy::cast_precision_loss, reason = "testing")]
+        let share = lane_bytes[0] as f64 / total as f64;
+        assert!(
+            (0.75..=0.85).contains(&share),
+            "lane 0 got {share} of bytes"
+        );
+    }
+
+    #[test]
+    fn deadline_scheduling() {
+        fn flushed_frags(transport: &mut Transport, now: Instant) -> Vec<(LaneIndex, MessageSeq)> {
+            flush_on(transport, now, IP_MTU)
+                .flat_map(|mut packet| {
+                    packet.read::<PacketHeader>().unwrap();
+                    iter::from_fn(move || {
+                        packet.has_remaining().then(|| {
+                            let frag = packet.read::<Fragment>().unwrap();
+                            (frag.header.lane, frag.header.seq)
+                        })
+                    })
+                })
+                .collect()
+        }
+
+        const RELIABLE: LaneIndex = LaneIndex(0);
+        const UNRELIABLE: LaneIndex = LaneIndex(1);
+
+        let now = Instant::now();
+        let session = Session::new(now, IP_MTU);
+        let lanes = [LaneKind::ReliableOrdered, LaneKind::UnreliableUnordered];
+        let mut transport = Transport::new(&session, lanes, lanes, now).unwrap();
+        let max_frag_len = transport.send.max_frag_len;
+
+        // a backlog of older messages, which would normally be sent first
+        for _ in 0..4 {
+            transport
+                .send
+                .push(RELIABLE, Bytes::from(vec![0; max_frag_len]), now)
+                .unwrap();
+        }
+        let now = now + Duration::from_millis(1);
+        let urgent = transport
+            .send
+            .push_with_deadline(
+                UNRELIABLE,
+                Bytes::from_static(b"urgent"),
+                now,
+                now + Duration::from_millis(10),
+            )
+            .unwrap();
+
+        // only enough bytes to flush out a single packet
+        transport.send.bytes_bucket = TokenBucket::new(max_frag_len + 64);
+        let frags = flushed_frags(&mut transport, now);
+        assert_eq!((UNRELIABLE, urgent.seq), frags[0]);
+
+        // messages whose deadline passes before they are flushed..
+        let missed = transport
+            .send
+            .push_with_deadline(
+                UNRELIABLE,
+                Bytes::from_static(b"missed"),
+                now,
+                now + Duration::from_millis(2),
+            )
+            .unwrap();
+        let late = transport
+            .send
+            .push_with_deadline(
+                RELIABLE,
+                Bytes::from_static(b"late"),
+                now,
+                now + Duration::from_millis(2),
+            )
+            .unwrap();
+
+        transport.send.bytes_bucket = TokenBucket::new(usize::MAX);
+        let later = now + Duration::from_millis(5);
+        let frags = flushed_frags(&mut transport, later);
+        // ..are dropped on unreliable lanes..
+        assert!(!frags.contains(&(UNRELIABLE, missed.seq)));
+        // ..and are still sent, but reported as late, on reliable lanes
+        assert!(frags.contains(&(RELIABLE, late.seq)));
+        let reported = transport.send.lanes[0]
+            .take_late(RELIABLE)
+            .collect::<Vec<_>>();
+        assert_eq!(vec![late], reported);
+        assert_eq!(0, transport.send.lanes[0].take_late(RELIABLE).count());
+    }
+
+    #[test]
+    fn slow_start() {
+        let now = Instant::now();
+        let mut transport = transport(now);
+        let config = TransportConfig {
+            send_bytes_per_sec: 100_000,
+            slow_start_bytes_per_sec: Some(10_000),
+            ..Default::default()
+        };
+        start_slow_start(&mut transport, &config, now);
+        let rtt = transport.rtt().get();
+
+        // starts out low..
+        assert_eq!(10_000, send_bytes_per_sec(&mut transport, &config, now));
+        let now = now + rtt / 2;
+        assert_eq!(10_000, send_bytes_per_sec(&mut transport, &config, now));
+
+        // ..doubles every RTT..
+        let now = now + rtt / 2;
+        assert_eq!(20_000, send_bytes_per_sec(&mut transport, &config, now));
+        let now = now + rtt;
+        assert_eq!(40_000, send_bytes_per_sec(&mut transport, &config, now));
+
+        // ..is held while packets are being lost..
+        transport.stats.packets_lost.0 += 1;
+        let now = now + rtt;
+        assert_eq!(40_000, send_bytes_per_sec(&mut transport, &config, now));
+        let now = now + rtt;
+        assert_eq!(80_000, send_bytes_per_sec(&mut transport, &config, now));
+
+        // ..and ends once it reaches the configured rate
+        let now = now + rtt;
+        assert_eq!(100_000, send_bytes_per_sec(&mut transport, &config, now));
+        assert!(transport.send.slow_start.is_none());
+    }
+
+    #[test]
+    fn congestion_transitions() {
+        #[derive(Default, Resource)]
+        struct Events(Vec<LaneCongestion>);
+
+        fn set_backlog(world: &mut World, entity: Entity, backlog: usize, now: Instant) {
+            let mut transport = world.get_mut::<Transport>(entity).unwrap();
+            transport.send.lanes[0].sent_msgs.clear();
+            for _ in 0..backlog {
+                transport
+                    .send
+                    .push(LaneIndex(0), Bytes::new(), now)
+                    .unwrap();
+            }
+        }
+
+        let now = Instant::now();
+        let mut world = World::new();
+        world.init_resource::<Events>();
+        world.add_observer(
+            |trigger: Trigger<LaneCongestion>, mut events: ResMut<Events>| {
+                events.0.push(*trigger.event());
+            },
+        );
+        let mut transport = transport(now);
+        transport.send.set_congestion_watermarks(
+            LaneIndex(0),
+            Some(CongestionWatermarks { low: 1, high: 3 }),
+        );
+        let entity = world.spawn((Session::new(now, IP_MTU), transport)).id();
+
+        let mut schedule = Schedule::default();
+        schedule.add_systems(detect_congestion);
+        let mut run = |world: &mut World, backlog: usize| {
+            set_backlog(world, entity, backlog, now);
+            schedule.run(world);
+            let events = core::mem::take(&mut world.resource_mut::<Events>().0);
+            let congested = world.get::<Transport>(entity).unwrap().send.lanes[0].is_congested();
+            (
+                events
+                    .into_iter()
+                    .map(|event| event.congested)
+                    .collect::<Vec<_>>(),
+                congested,
+            )
+        };
+
+        assert_eq!((vec![], false), run(&mut world, 2));
+        assert_eq!((vec![true], true), run(&mut world, 3));
+        assert_eq!((vec![], true), run(&mut world, 4));
+        // between the watermarks, stays congested
+        assert_eq!((vec![], true), run(&mut world, 2));
+        assert_eq!((vec![false], false), run(&mut world, 1));
+        // between the watermarks, stays not congested
+        assert_eq!((vec![], false), run(&mut world, 2));
+        assert_eq!((vec![true], true), run(&mut world, 3));
+
+        let mut transport = world.get_mut::<Transport>(entity).unwrap();
+        transport.send.set_congestion_watermarks(LaneIndex(0), None);
+        assert!(!transport.send.lanes[0].is_congested());
+        assert_eq!((vec![], false), run(&mut world, 4));
+    }
+
+    #[test]
+    fn retransmit_limit() {
+        /// Gets how many times the message was resent, and its delivery.
+        fn abandoned_after(limit: RetransmitLimit) -> (usize, Vec<Delivery>) {
+            const LANE: LaneIndex = LaneIndex(0);
+
+            let mut now = Instant::now();
+            let session = Session::new(now, IP_MTU);
+            let lanes = [LaneKind::UnreliableUnordered];
+            let mut transport = Transport::new(&session, lanes, lanes, now).unwrap();
+            transport.send.bytes_bucket = TokenBucket::new(usize::MAX);
+            transport.send.set_retransmit_limit(LANE, Some(limit));
+
+            let key = transport
+                .send
+                .push(LANE, Bytes::from_static(b"hi"), now)
+                .unwrap();
+            transport.track_delivery(key);
+
+            // the peer never acks anything
+            let mut retransmits = 0;
+            for _ in 0..100 {
+                _ = flush_on(&mut transport, now, IP_MTU).count();
+                let lane = &mut transport.send.lanes[0];
+                let Some(frag) = &lane.sent_msgs[&key.seq].frags[0] else {
+                    let deliveries = lane
+                        .take_dropped_deliveries(LANE)
+                        .map(|report| report.delivery)
+                        .collect();
+                    return (retransmits, deliveries);
+                };
+                retransmits = frag.retransmits;
+                now += transport.rtt().pto();
+            }
+            panic!("message was never abandoned");
+        }
+
+        assert_eq!(
+            (2, vec![Delivery::Lost]),
+            abandoned_after(RetransmitLimit {
+                max_retransmits: Some(2),
+                ..Default::default()
+            })
+        );
+        assert_eq!(
+            (0, vec![Delivery::Lost]),
+            abandoned_after(RetransmitLimit {
+                max_retransmits: Some(0),
+                ..Default::default()
+            })
+        );
+        // each resend happens one PTO after the last
+        let pto = RttEstimator::default().pto();
+        assert_eq!(
+            (3, vec![Delivery::Lost]),
+            abandoned_after(RetransmitLimit {
+                max_lifetime: Some(pto * 3),
+                ..Default::default()
+            })
+        );
+    }
+
+    #[test]
+    fn min_flush_interval() {
+        const INTERVAL: Duration = Duration::from_millis(16);
+
+        let start = Instant::now();
+        let mut transport = transport(start);
+        transport.send.min_flush_interval = INTERVAL;
+
+        // updating at 1000 Hz
+        let mut flushed_at = Vec::new();
+        for update in 0..100 {
+            let now = start + Duration::from_millis(update);
+            transport
+                .send
+                .push(LaneIndex(0), Bytes::from_static(b"hi"), now)
+                .unwrap();
+            if transport.send.flush_due(now) {
+                assert_eq!(1, flush_on(&mut transport, now, IP_MTU).count());
+                flushed_at.push(now);
+            }
+        }
+        assert_eq!(7, flushed_at.len());
+        assert!(
+            flushed_at
+                .windows(2)
+                .all(|window| window[1] - window[0] >= INTERVAL)
+        );
+
+        // none of those messages were acked, so they're due to be resent
+        // well before the interval is up
+        transport.send.min_flush_interval = Duration::from_secs(3600);
+        let now = *flushed_at.last().unwrap();
+        assert!(!transport.send.flush_due(now));
+        let now = now + transport.rtt().pto();
+        assert!(transport.send.flush_due(now));
+    }
+
+    #[test]
+    fn mtu_shrink() {
+        const MIN_MTU: usize = 600;
+
+        let now = Instant::now();
+        let mut session = Session::new(now, MIN_MTU);
+        session.set_mtu(IP_MTU).unwrap();
+        let mut transport = Transport::new(&session, LANES, LANES, now).unwrap();
+        transport.send.bytes_bucket = TokenBucket::new(usize::MAX);
+
+        for _ in 0..4 {
+            transport
+                .send
+                .push(LaneIndex(0), Bytes::from(vec![0; 4 * IP_MTU]), now)
+                .unwrap();
+        }
+
+        // some packets go out while the MTU is large...
+        let packets = flush_on(&mut transport, now, session.mtu())
+            .take(2)
+            .collect::<Vec<_>>();
+        assert!(packets.iter().all(|packet| packet.len() <= IP_MTU));
+        assert!(packets.iter().any(|packet| packet.len() > MIN_MTU));
+
+        // ...then the path MTU drops, e.g. because a tunnel was added
+        session.set_mtu(MIN_MTU).unwrap();
+        let packets = flush_on(&mut transport, now, session.mtu()).collect::<Vec<_>>();
+        assert!(packets.len() > 2);
+        assert!(packets.iter().all(|packet| packet.len() <= MIN_MTU));
+        // and the frags which were split before the drop still all went out
+        assert!(!transport.send.has_unflushed_frags());
+    }
+
+    #[test]
+    #[should_panic = "must be less than high watermark"]
+    fn congestion_watermarks_must_be_ordered() {
+        let mut transport = transport(Instant::now());
+        transport.send.set_congestion_watermarks(
+            LaneIndex(0),
+            Some(CongestionWatermarks { low: 3, high: 3 }),
+        );
+    }
+
+    #[test]
+    fn max_packets_per_flush() {
+        const MAX_PACKETS: usize = 3;
+        const NUM_MSGS: usize = 10;
+
+        let now = Instant::now();
+        let mut transport = transport(now);
+        transport.send.max_packets_per_flush = MAX_PACKETS;
+
+        // each message only fits into its own packet
+        for _ in 0..NUM_MSGS {
+            transport
+                .send
+                .push(LaneIndex(0), Bytes::from(vec![0; IP_MTU / 2]), now)
+                .unwrap();
+        }
+
+        let mut flushed = Vec::new();
+        while flushed.iter().sum::<usize>() < NUM_MSGS {
+            let num_packets = flush_on(&mut transport, now, IP_MTU).count();
+            assert!(num_packets <= MAX_PACKETS);
+            flushed.push(num_packets);
+        }
+        assert_eq!(vec![3, 3, 3, 1], flushed);
+    }
+
+    #[test]
+    fn flush_outcome_budget_exhausted() {
+        let now = Instant::now();
+        let mut transport = transport(now);
+        assert_eq!(FlushOutcome::NothingToSend, transport.last_flush_outcome());
+
+        transport.send.bytes_bucket = TokenBucket::new(2 * IP_MTU);
+        transport
+            .send
+            .push(LaneIndex(0), Bytes::from(vec![0; 4 * IP_MTU]), now)
+            .unwrap();
+
+        // the budget runs out partway through the message..
+        assert!(flush_on(&mut transport, now, IP_MTU).count() > 0);
+        assert_eq!(
+            FlushOutcome::BudgetExhausted,
+            transport.last_flush_outcome()
+        );
+        // ..and once it's fully drained, nothing can be sent at all
+        transport.send.bytes_bucket = TokenBucket::new(0);
+        assert_eq!(0, flush_on(&mut transport, now, IP_MTU).count());
+        assert_eq!(
+            FlushOutcome::BudgetExhausted,
+            transport.last_flush_outcome()
+        );
+        assert!(transport.send.has_unflushed_frags());
+        assert_eq!(1, transport.send.lanes[0].num_queued_msgs());
+
+        transport.send.bytes_bucket = TokenBucket::new(usize::MAX);
+        assert!(flush_on(&mut transport, now, IP_MTU).count() > 0);
+        assert_eq!(FlushOutcome::Sent, transport.last_flush_outcome());
+        assert!(!transport.send.has_unflushed_frags());
+    }
+
+    #[test]
+    fn flush_outcome_with_budget() {
+        let now = Instant::now();
+        let mut world = World::new();
+        world.insert_resource(FlushBudget {
+            max_bytes_per_update: IP_MTU,
+        });
+        let mut transport = transport(now);
+        transport
+            .send
+            .push(LaneIndex(0), Bytes::from_static(b"hello"), now)
+            .unwrap();
+        transport.send.last_flush_outcome = FlushOutcome::Paused;
+        let entity = world.spawn((Session::new(now, IP_MTU), transport)).id();
+
+        let mut schedule = Schedule::default();
+        schedule.add_systems(flush);
+        // the budget runs out after the first packet, before `flush_on` finds
+        // that it has nothing left to send
+        schedule.run(&mut world);
+        let transport = world.get::<Transport>(entity).unwrap();
+        assert!(!transport.send.has_unflushed_frags());
+        assert_eq!(FlushOutcome::Sent, transport.last_flush_outcome());
+    }
+
+    #[test]
+    fn flush_outcome_capacity_full() {
+        let now = Instant::now();
+        let mut transport = transport(now);
+        transport.send.max_packets_per_flush = 1;
+        transport
+            .send
+            .push(LaneIndex(0), Bytes::from(vec![0; 2 * IP_MTU]), now)
+            .unwrap();
+
+        assert_eq!(1, flush_on(&mut transport, now, IP_MTU).count());
+        assert_eq!(FlushOutcome::CapacityFull, transport.last_flush_outcome());
+    }
+}
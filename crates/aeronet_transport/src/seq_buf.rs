@@ -294,6 +294,56 @@ impl<T, const N: usize> SeqBuf<T, N> {
             None
         }
     }
+
+    /// Iterates over all values currently stored in this buffer, along with
+    /// the key they are stored at.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use aeronet_transport::seq_buf::SeqBuf;
+    ///
+    /// let mut buf = SeqBuf::<String, 16>::new();
+    /// buf.insert(4, "hello".into());
+    /// buf.insert(5, "world".into());
+    ///
+    /// let mut keys = buf.iter().map(|(key, _)| key).collect::<Vec<_>>();
+    /// keys.sort_unstable();
+    /// assert_eq!([4, 5], keys.as_slice());
+    /// ```
+    pub fn iter(&self) -> impl Iterator<Item = (u16, &T)> {
+        self.indices
+            .iter()
+            .zip(self.data.iter())
+            .filter(|(key, _)| **key != EMPTY)
+            .map(|(key, value)| (*key, value))
+    }
+
+    /// Iterates over all values currently stored in this buffer, along with
+    /// the key they are stored at.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use aeronet_transport::seq_buf::SeqBuf;
+    ///
+    /// let mut buf = SeqBuf::<String, 16>::new();
+    /// buf.insert(4, "hello".into());
+    /// buf.insert(5, "world".into());
+    ///
+    /// for (_, value) in buf.iter_mut() {
+    ///     value.push('!');
+    /// }
+    /// assert_eq!("hello!", buf.get(4).unwrap());
+    /// assert_eq!("world!", buf.get(5).unwrap());
+    /// ```
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (u16, &mut T)> {
+        self.indices
+            .iter()
+            .zip(self.data.iter_mut())
+            .filter(|(key, _)| **key != EMPTY)
+            .map(|(key, value)| (*key, value))
+    }
 }
 
 impl<T: TypeSize, const N: usize> TypeSize for SeqBuf<T, N> {
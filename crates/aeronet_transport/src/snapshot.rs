@@ -0,0 +1,629 @@
+//! See [`TransportSnapshot`].
+
+use {
+    crate::{
+        FRAG_OVERHEAD, FlushedPacket, MessageStats, RecvMessage, Transport,
+        awaitable::DeliveryWaiters,
+        clock::ClockSync,
+        frag::{FragmentReceiver, MessageBuf},
+        lane::{LaneIndex, LaneKind, LaneReliability},
+        packet::{Acknowledge, FragmentPosition, MessageSeq, PacketSeq},
+        ping::Pings,
+        recv::{LaneState, RecvLane, TransportRecv},
+        rtt::RttEstimator,
+        send::{
+            CongestionWatermarks, RetransmitLimit, SendLane, SentFragment, SentMessage,
+            TransportSend,
+        },
+        seq_buf::SeqBuf,
+    },
+    aeronet_io::{Session, packet::MtuTooSmall},
+    alloc::collections::VecDeque,
+    core::time::Duration,
+    octs::Bytes,
+    web_time::Instant,
+};
+
+/// State of a [`Transport`] which can be moved into another process, and
+/// restored there to continue the same connection.
+///
+/// This is intended for live-upgrading a server: the old process snapshots the
+/// transports of its sessions via [`Transport::snapshot`], hands the snapshots
+/// and the underlying sockets over to the new process, and the new process
+/// restores the transports via [`Transport::restore`]. Since sequence numbers,
+/// acknowledgements, and unacknowledged messages are carried over, the peer
+/// does not notice that anything happened.
+///
+/// With the `serde` feature, this can be serialized and deserialized.
+///
+/// # Captured state
+///
+/// - packet and message sequence numbers on all lanes
+/// - which of the peer's packets we have acknowledged
+/// - sent messages which the peer has not acknowledged yet, including any
+///   per-lane [backpressure threshold], [in-flight limit], [retransmit limit],
+///   [congestion watermarks], [lane weight], and whether [unreliable receipts]
+///   are enabled
+/// - received messages which are still being reassembled or ordered, and the
+///   per-lane [max reorder wait]
+/// - received messages which have not been drained from [`TransportRecv::msgs`]
+///   yet
+/// - the RTT estimate, and the peer's lanes and MTU
+///
+/// Everything else starts from scratch on the restored transport. Notably:
+///
+/// - [`Transport::stats`] and [`Transport::peak_memory_used`] are reset
+/// - contexts attached via [`TransportSend::push_with_context`] are not
+///   captured, so they are never given back for restored messages
+/// - acknowledgements for packets flushed before the snapshot are ignored, so
+///   any unacknowledged fragments are resent as soon as the restored transport
+///   flushes
+/// - unreliable messages which are only waiting for a receipt, or to be resent,
+///   are not captured, so no receipt is ever reported for them
+/// - messages tracked via [`Transport::track_delivery`] are not tracked on the
+///   restored transport, so their delivery is never reported
+/// - the transport starts out as not under memory pressure, so
+///   [`MemoryPressureHigh`] is triggered again on the first update if the
+///   restored transport is over [`TransportConfig::soft_memory_limit`]
+/// - lanes start out as not congested, so [`LaneCongestion`] is triggered again
+///   on the first flush if a restored lane's backlog is over its high watermark
+/// - deadlines set via [`TransportSend::push_with_deadline`] are not captured,
+///   so restored messages are never prioritized, dropped, or reported as late
+///
+/// # IO layer support
+///
+/// A snapshot is only useful if the restored transport can keep talking to the
+/// same peer over the same connection, which means the IO layer must be able
+/// to hand its socket (and any encryption state on top of it) over to another
+/// process. **None of the IO layers provided by `aeronet` support this**:
+///
+/// - `aeronet_channel` only connects sessions within a single process
+/// - `aeronet_tcp` does not support adopting an existing TCP stream
+/// - `aeronet_websocket` and `aeronet_webtransport` keep TLS and QUIC state
+///   inside of their backend tasks, which cannot be moved between processes
+///
+/// To use this, you will need a custom IO layer which supports socket passing
+/// (e.g. a UDP layer without encryption, or one whose encryption state can be
+/// serialized).
+///
+/// [backpressure threshold]: TransportSend::set_backpressure_threshold
+/// [in-flight limit]: TransportSend::set_max_in_flight_msgs
+/// [retransmit limit]: TransportSend::set_retransmit_limit
+/// [congestion watermarks]: TransportSend::set_congestion_watermarks
+/// [lane weight]: TransportSend::set_lane_weight
+/// [max reorder wait]: TransportRecv::set_max_reorder_wait
+/// [`LaneCongestion`]: crate::send::LaneCongestion
+/// [`MemoryPressureHigh`]: crate::MemoryPressureHigh
+/// [`TransportConfig::soft_memory_limit`]: crate::TransportConfig::soft_memory_limit
+/// [unreliable receipts]: TransportSend::set_unreliable_receipts
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TransportSnapshot {
+    max_frag_len: usize,
+    next_packet_seq: PacketSeq,
+    peer_acks: Acknowledge,
+    rtt: RttEstimator,
+    peer_lanes: Option<Box<[LaneKind]>>,
+    peer_mtu: Option<usize>,
+    send_lanes: Vec<SendLaneSnapshot>,
+    recv_lanes: Vec<RecvLaneSnapshot>,
+    recv_msgs: Vec<(LaneIndex, Vec<u8>)>,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct SendLaneSnapshot {
+    kind: LaneKind,
+    next_msg_seq: MessageSeq,
+    backpressure_threshold: Option<usize>,
+    max_in_flight_msgs: Option<usize>,
+    unreliable_receipts: bool,
+    retransmit_limit: Option<RetransmitLimit>,
+    congestion_watermarks: Option<CongestionWatermarks>,
+    weight: Option<u32>,
+    sent_msgs: Vec<(MessageSeq, SentMessageSnapshot)>,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct SentMessageSnapshot {
+    // only present on reliable lanes, in which case the fragment payloads are
+    // not stored, and are sliced out of this on restore instead
+    msg: Option<Vec<u8>>,
+    frags: Vec<Option<SentFragmentSnapshot>>,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct SentFragmentSnapshot {
+    position: FragmentPosition,
+    payload: Option<Vec<u8>>,
+    // `Instant`s can't be moved between processes, so we store how long ago
+    // the fragment was pushed instead, to keep the flush order the same
+    age: Duration,
+    flushed: bool,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct RecvLaneSnapshot {
+    state: LaneState,
+    max_reorder_wait: Option<Duration>,
+    partial_msgs: Vec<(MessageSeq, PartialMessageSnapshot)>,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct PartialMessageSnapshot {
+    last_frag_index: Option<usize>,
+    max_frag_index: usize,
+    num_frags_recv: usize,
+    frag_indices_recv: Vec<bool>,
+    payload: Vec<u8>,
+}
+
+impl Transport {
+    /// Takes a snapshot of the state of this transport, which can be restored
+    /// later via [`Transport::restore`], potentially in another process.
+    ///
+    /// See [`TransportSnapshot`].
+    #[must_use]
+    pub fn snapshot(&self, now: Instant) -> TransportSnapshot {
+        TransportSnapshot {
+            max_frag_len: self.send.max_frag_len,
+            next_packet_seq: self.send.next_packet_seq,
+            peer_acks: self.peer_acks,
+            rtt: self.rtt.clone(),
+            peer_lanes: self.peer_lanes.clone(),
+            peer_mtu: self.peer_mtu,
+            send_lanes: self
+                .send
+                .lanes
+                .iter()
+                .map(|lane| SendLaneSnapshot::new(lane, now))
+                .collect(),
+            recv_lanes: self.recv.lanes.iter().map(RecvLaneSnapshot::new).collect(),
+            // messages in `msgs` were received before the ones still in the
+            // backlog, so they must come first
+            recv_msgs: self
+                .recv
+                .msgs
+                .0
+                .iter()
+                .chain(&self.recv.msgs_backlog)
+                .map(|msg| (msg.lane, msg.payload.clone()))
+                .collect(),
+        }
+    }
+
+    /// Restores a transport from a snapshot taken via [`Transport::snapshot`].
+    ///
+    /// The restored transport has the same lanes as the transport that the
+    /// snapshot was taken from.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the [`Session::min_mtu`] is too small to fit the fragments
+    /// that the snapshotted transport created.
+    pub fn restore(
+        session: &Session,
+        snapshot: TransportSnapshot,
+        now: Instant,
+    ) -> Result<Self, MtuTooSmall> {
+        let min_mtu = session.min_mtu();
+        let required_mtu = snapshot.max_frag_len + FRAG_OVERHEAD;
+        if min_mtu < required_mtu {
+            return Err(MtuTooSmall {
+                mtu: min_mtu,
+                min: required_mtu,
+            });
+        }
+
+        let mut recv = TransportRecv::new(snapshot.recv_lanes.iter().map(RecvLaneSnapshot::kind));
+        let mut send = TransportSend::new(
+            snapshot.max_frag_len,
+            snapshot.send_lanes.iter().map(|lane| lane.kind),
+        );
+
+        send.next_packet_seq = snapshot.next_packet_seq;
+        for (lane, lane_snapshot) in send.lanes.iter_mut().zip(snapshot.send_lanes) {
+            lane_snapshot.restore_into(lane, snapshot.max_frag_len, now);
+        }
+        for (lane, lane_snapshot) in recv.lanes.iter_mut().zip(snapshot.recv_lanes) {
+            lane_snapshot.restore_into(lane);
+        }
+        recv.msgs_backlog
+            .extend(
+                snapshot
+                    .recv_msgs
+                    .into_iter()
+                    .map(|(lane, payload)| RecvMessage {
+                        lane,
+                        recv_at: now,
+                        payload,
+                    }),
+            );
+
+        Ok(Self {
+            flushed_packets: SeqBuf::new_from_fn(|_| FlushedPacket::new(now)),
+            unacked_packets_warned_at: None,
+            unacked_packets_warning_pending: false,
+            peak_memory_used: 0,
+            memory_pressure_high: false,
+            stats: MessageStats::default(),
+            peer_acks: snapshot.peer_acks,
+            acks_pending: false,
+            ack_eliciting_pending: false,
+            pending_nacks: VecDeque::new(),
+            nack_candidates: Vec::new(),
+            pings: Pings::default(),
+            rtt: snapshot.rtt,
+            peer_lanes: snapshot.peer_lanes,
+            peer_lanes_checked: false,
+            peer_mtu: snapshot.peer_mtu,
+            sent_mtu: None,
+            sent_mtu_acked: false,
+            peer_protocol_version: None,
+            protocol_mismatches: 0,
+            protocol_mismatch_checked: false,
+            seq_desyncs: 0,
+            seq_desync_checked: false,
+            adapted_lost_threshold_factor: None,
+            clock: ClockSync::default(),
+            #[cfg(feature = "cpu-time")]
+            cpu_time: crate::CpuTime::default(),
+            awaiting: DeliveryWaiters::default(),
+            recv,
+            send,
+        })
+    }
+}
+
+impl SendLaneSnapshot {
+    fn new(lane: &SendLane, now: Instant) -> Self {
+        let reliable = lane.kind.reliability() == LaneReliability::Reliable;
+        Self {
+            kind: lane.kind,
+            next_msg_seq: lane.next_msg_seq,
+            backpressure_threshold: lane.backpressure_threshold,
+            max_in_flight_msgs: lane.max_in_flight_msgs,
+            unreliable_receipts: lane.unreliable_receipts,
+            retransmit_limit: lane.retransmit_limit,
+            congestion_watermarks: lane.congestion_watermarks,
+            weight: lane.weight,
+            sent_msgs: lane
+                .sent_msgs
+                .iter()
+                // acks for packets flushed before the snapshot are ignored,
+                // so these would never get a receipt, and never be dropped
+                .filter(|(_, msg)| reliable || !msg.is_in_flight())
+                .map(|(msg_seq, msg)| {
+                    let frags = msg
+                        .frags
+                        .iter()
+                        .map(|frag| {
+                            frag.as_ref().map(|frag| SentFragmentSnapshot {
+                                position: frag.position,
+                                payload: msg.msg.is_none().then(|| frag.payload.to_vec()),
+                                age: now.saturating_duration_since(frag.sent_at),
+                                flushed: frag.flushed,
+                            })
+                        })
+                        .collect();
+                    let msg_snapshot = SentMessageSnapshot {
+                        msg: msg.msg.as_deref().map(<[u8]>::to_vec),
+                        frags,
+                    };
+                    (*msg_seq, msg_snapshot)
+                })
+                .collect(),
+        }
+    }
+
+    fn restore_into(self, lane: &mut SendLane, max_frag_len: usize, now: Instant) {
+        lane.next_msg_seq = self.next_msg_seq;
+        lane.backpressure_threshold = self.backpressure_threshold;
+        lane.max_in_flight_msgs = self.max_in_flight_msgs;
+        lane.unreliable_receipts = self.unreliable_receipts;
+        lane.retransmit_limit = self.retransmit_limit;
+        lane.congestion_watermarks = self.congestion_watermarks;
+        lane.weight = self.weight;
+        lane.sent_msgs = self
+            .sent_msgs
+            .into_iter()
+            .map(|(msg_seq, msg)| {
+                let whole_msg = msg.msg.map(Bytes::from);
+                let frags = msg
+                    .frags
+                    .into_iter()
+                    .map(|frag| {
+                        frag.map(|frag| SentFragment {
+                            position: frag.position,
+                            payload: frag.payload.map_or_else(
+                                || slice_frag(whole_msg.as_ref(), frag.position, max_frag_len),
+                                Bytes::from,
+                            ),
+                            sent_at: now.checked_sub(frag.age).unwrap_or(now),
+                            next_flush_at: now,
+                            flushed: frag.flushed,
+                            retransmits: 0,
+                        })
+                    })
+                    .collect();
+                let msg = SentMessage {
+                    msg: whole_msg,
+                    frags,
+                    context: None,
+                    track_delivery: false,
+                    undelivered: false,
+                    deadline: None,
+                    late: false,
+                };
+                (msg_seq, msg)
+            })
+            .collect();
+    }
+}
+
+/// Slices the payload of a fragment out of the whole message that it was split
+/// from, in the same way as [`frag::split`].
+///
+/// [`frag::split`]: crate::frag::split
+fn slice_frag(msg: Option<&Bytes>, position: FragmentPosition, max_frag_len: usize) -> Bytes {
+    let Some(msg) = msg else {
+        return Bytes::new();
+    };
+    let start = usize::from(position.index())
+        .saturating_mul(max_frag_len)
+        .min(msg.len());
+    let end = start.saturating_add(max_frag_len).min(msg.len());
+    msg.slice(start..end)
+}
+
+impl RecvLaneSnapshot {
+    fn new(lane: &RecvLane) -> Self {
+        Self {
+            state: lane.state.clone(),
+            max_reorder_wait: lane.max_reorder_wait,
+            partial_msgs: lane
+                .frags
+                .msgs
+                .iter()
+                .map(|(msg_seq, buf)| {
+                    let partial = PartialMessageSnapshot {
+                        last_frag_index: buf.last_frag_index,
+                        max_frag_index: buf.max_frag_index,
+                        num_frags_recv: buf.num_frags_recv,
+                        frag_indices_recv: buf.frag_indices_recv.iter().map(|bit| *bit).collect(),
+                        payload: buf.payload.clone(),
+                    };
+                    (*msg_seq, partial)
+                })
+                .collect(),
+        }
+    }
+
+    const fn kind(&self) -> LaneKind {
+        match self.state {
+            LaneState::UnreliableUnordered => LaneKind::UnreliableUnordered,
+            LaneState::UnreliableSequenced { .. } => LaneKind::UnreliableSequenced,
+            LaneState::UnreliableLatest { .. } => LaneKind::UnreliableLatest,
+            LaneState::ReliableUnordered { .. } => LaneKind::ReliableUnordered,
+            LaneState::ReliableOrdered { .. } => LaneKind::ReliableOrdered,
+        }
+    }
+
+    fn restore_into(self, lane: &mut RecvLane) {
+        lane.state = self.state;
+        lane.max_reorder_wait = self.max_reorder_wait;
+        lane.frags = FragmentReceiver {
+            msgs: self
+                .partial_msgs
+                .into_iter()
+                .map(|(msg_seq, partial)| {
+                    let buf = MessageBuf {
+                        last_frag_index: partial.last_frag_index,
+                        max_frag_index: partial.max_frag_index,
+                        num_frags_recv: partial.num_frags_recv,
+                        frag_indices_recv: partial.frag_indices_recv.into_iter().collect(),
+                        payload: partial.payload,
+                        // receive times don't carry over, so these messages
+                        // will be treated as the oldest ones
+                        first_recv_at: None,
+                        recv_packets: None,
+                        nacked: false,
+                    };
+                    (msg_seq, buf)
+                })
+                .collect(),
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![expect(clippy::unwrap_used, reason = "testing")]
+
+    use {
+        super::*,
+        crate::{
+            TransportConfig, limit::TokenBucket, packet::PacketHeader, recv::recv_on,
+            send::flush_on,
+        },
+        aeronet_io::packet::IP_MTU,
+        octs::Read,
+    };
+
+    const LANES: [LaneKind; 1] = [LaneKind::ReliableOrdered];
+
+    fn transport(session: &Session, now: Instant) -> Transport {
+        let mut transport = Transport::new(session, LANES, LANES, now).unwrap();
+        transport.send.bytes_bucket = TokenBucket::new(usize::MAX);
+        transport
+    }
+
+    fn flush(transport: &mut Transport, now: Instant) -> Vec<Bytes> {
+        flush_on(transport, now, IP_MTU).collect()
+    }
+
+    #[test]
+    fn restore_continues_sequences() {
+        let now = Instant::now();
+        let session = Session::new(now, IP_MTU);
+        let config = TransportConfig::default();
+        let mut sender = transport(&session, now);
+        let mut receiver = transport(&session, now);
+
+        sender
+            .send
+            .push(LaneIndex(0), Bytes::from_static(b"one"), now)
+            .unwrap();
+        let delivered = flush(&mut sender, now);
+        sender
+            .send
+            .push(LaneIndex(0), Bytes::from_static(b"two"), now)
+            .unwrap();
+        // this packet is lost
+        _ = flush(&mut sender, now);
+
+        for packet in &delivered {
+            recv_on(&mut receiver, &config, now, packet).unwrap();
+        }
+        assert_eq!(
+            vec![b"one".to_vec()],
+            receiver
+                .recv
+                .msgs
+                .drain()
+                .map(|msg| msg.payload)
+                .collect::<Vec<_>>()
+        );
+
+        let sender_snapshot = sender.snapshot(now);
+        let receiver_snapshot = receiver.snapshot(now);
+        let mut sender = Transport::restore(&session, sender_snapshot, now).unwrap();
+        let mut receiver = Transport::restore(&session, receiver_snapshot, now).unwrap();
+        sender.send.bytes_bucket = TokenBucket::new(usize::MAX);
+
+        let key = sender
+            .send
+            .push(LaneIndex(0), Bytes::from_static(b"three"), now)
+            .unwrap();
+        assert_eq!(MessageSeq::new(2), key.seq);
+
+        // the lost message is resent alongside the new one,
+        // continuing on from the packet sequence before the snapshot
+        let packets = flush(&mut sender, now);
+        let header = (&mut &packets[0][..]).read::<PacketHeader>().unwrap();
+        assert_eq!(PacketSeq::new(2), header.seq);
+        for packet in &packets {
+            recv_on(&mut receiver, &config, now, packet).unwrap();
+        }
+        assert_eq!(
+            vec![b"two".to_vec(), b"three".to_vec()],
+            receiver
+                .recv
+                .msgs
+                .drain()
+                .map(|msg| msg.payload)
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn restore_slices_frags_from_whole_msg() {
+        let now = Instant::now();
+        let session = Session::new(now, IP_MTU);
+        let config = TransportConfig::default();
+        let mut sender = transport(&session, now);
+        let mut receiver = transport(&session, now);
+
+        let max_frag_len = sender.send.max_frag_len;
+        let msg = (0..max_frag_len * 3 + 1)
+            .map(|i| u8::try_from(i % 256).unwrap())
+            .collect::<Vec<_>>();
+        let key = sender
+            .send
+            .push(LaneIndex(0), Bytes::from(msg.clone()), now)
+            .unwrap();
+
+        let snapshot = sender.snapshot(now);
+        let snapshot_msg = &snapshot.send_lanes[0].sent_msgs[0].1;
+        assert!(
+            snapshot_msg
+                .frags
+                .iter()
+                .flatten()
+                .all(|frag| frag.payload.is_none())
+        );
+        let mut sender = Transport::restore(&session, snapshot, now).unwrap();
+        sender.send.bytes_bucket = TokenBucket::new(usize::MAX);
+
+        assert_eq!(
+            vec![(key.seq, Bytes::from(msg.clone()))],
+            sender.send.lanes()[0].unacked_msgs().collect::<Vec<_>>()
+        );
+        for packet in &flush(&mut sender, now) {
+            recv_on(&mut receiver, &config, now, packet).unwrap();
+        }
+        assert_eq!(
+            vec![msg],
+            receiver
+                .recv
+                .msgs
+                .drain()
+                .map(|msg| msg.payload)
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn restore_keeps_recv_msg_order() {
+        const MAX: usize = 2;
+        const PAYLOADS: [&[u8]; 5] = [b"0", b"1", b"2", b"3", b"4"];
+
+        let now = Instant::now();
+        let session = Session::new(now, IP_MTU);
+        let config = TransportConfig::default();
+        let mut sender = transport(&session, now);
+        let mut receiver = transport(&session, now);
+
+        for payload in PAYLOADS {
+            sender
+                .send
+                .push(LaneIndex(0), Bytes::from_static(payload), now)
+                .unwrap();
+        }
+        for packet in &flush(&mut sender, now) {
+            recv_on(&mut receiver, &config, now, packet).unwrap();
+        }
+        // some messages are ready to be drained, the rest are held back
+        receiver.recv.limit_msgs(MAX);
+        assert_eq!(MAX, receiver.recv.msgs.0.len());
+        assert_eq!(PAYLOADS.len() - MAX, receiver.recv.msgs_backlog_len());
+
+        let mut receiver = Transport::restore(&session, receiver.snapshot(now), now).unwrap();
+        let mut recv = Vec::new();
+        while recv.len() < PAYLOADS.len() {
+            receiver.recv.limit_msgs(MAX);
+            recv.extend(receiver.recv.msgs.drain().map(|msg| msg.payload));
+        }
+        assert_eq!(PAYLOADS.map(<[u8]>::to_vec).to_vec(), recv);
+    }
+
+    #[test]
+    fn restore_requires_large_enough_mtu() {
+        let now = Instant::now();
+        let session = Session::new(now, IP_MTU);
+        let snapshot = transport(&session, now).snapshot(now);
+
+        let small_session = Session::new(now, IP_MTU - 1);
+        assert_eq!(
+            MtuTooSmall {
+                mtu: IP_MTU - 1,
+                min: IP_MTU,
+            },
+            Transport::restore(&small_session, snapshot, now).unwrap_err()
+        );
+    }
+}
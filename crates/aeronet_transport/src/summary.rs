@@ -0,0 +1,129 @@
+//! See [`SessionSummaries`].
+
+use {
+    crate::{Transport, sampling::SessionStats},
+    aeronet_io::{
+        Session, SessionEndpoint,
+        packet::{PacketRtt, PacketStats},
+    },
+    bevy_core::Name,
+    bevy_ecs::{prelude::*, system::SystemParam},
+    core::time::Duration,
+};
+
+/// Provides a [`SessionSummary`] for every session in the world.
+///
+/// This is an ergonomics layer over [`Session`], [`Transport`],
+/// [`SessionStats`], and other session components, so that admin overlays and
+/// dashboards don't each have to write the same query. All sessions with a
+/// [`SessionEndpoint`] are included, even if they are still connecting.
+///
+/// # Examples
+///
+/// ```
+/// use {aeronet_transport::summary::SessionSummaries, bevy_ecs::prelude::*};
+///
+/// fn list_sessions(summaries: SessionSummaries) {
+///     for summary in summaries.iter() {
+///         let name = summary.name.map_or("(unnamed)", |name| name.as_str());
+///         println!(
+///             "{name}: {:?}, RTT {:?}, sent {} bytes",
+///             summary.state, summary.msg_rtt, summary.packets.bytes_sent
+///         );
+///     }
+/// }
+/// ```
+#[derive(SystemParam)]
+pub struct SessionSummaries<'w, 's> {
+    sessions: Query<
+        'w,
+        's,
+        (
+            Entity,
+            Option<&'static Name>,
+            Option<&'static Session>,
+            Option<&'static PacketRtt>,
+            Option<&'static Transport>,
+            Option<&'static SessionStats>,
+        ),
+        With<SessionEndpoint>,
+    >,
+}
+
+/// Snapshot of the state and statistics of a single session.
+///
+/// See [`SessionSummaries`].
+#[derive(Debug, Clone, Copy)]
+pub struct SessionSummary<'a> {
+    /// Entity of the session.
+    pub entity: Entity,
+    /// [`Name`] of the session, if it has one.
+    pub name: Option<&'a Name>,
+    /// Connection state of the session.
+    pub state: SessionState,
+    /// [`PacketRtt`] of the session, if the IO layer provides one.
+    pub packet_rtt: Option<Duration>,
+    /// [`Transport::rtt`]'s [`RttEstimator::get`], if the session has a
+    /// [`Transport`].
+    ///
+    /// [`RttEstimator::get`]: crate::rtt::RttEstimator::get
+    pub msg_rtt: Option<Duration>,
+    /// Packet loss of the last [`SessionStatsSample`], if the session has any
+    /// [`SessionStats`].
+    ///
+    /// [`SessionStatsSample`]: crate::sampling::SessionStatsSample
+    pub loss: Option<f64>,
+    /// [`Session::stats`] of the session, including byte totals.
+    ///
+    /// This is zeroed if the session is still connecting.
+    pub packets: PacketStats,
+}
+
+/// Connection state of a session in a [`SessionSummary`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SessionState {
+    /// The session has a [`SessionEndpoint`], but no [`Session`] yet.
+    Connecting,
+    /// The session has a [`Session`].
+    Connected,
+}
+
+impl SessionSummaries<'_, '_> {
+    /// Gets a summary of every session.
+    pub fn iter(&self) -> impl Iterator<Item = SessionSummary<'_>> {
+        self.sessions.iter().map(summarize)
+    }
+
+    /// Gets a summary of the given session.
+    ///
+    /// Returns [`None`] if `entity` is not a session.
+    #[must_use]
+    pub fn get(&self, entity: Entity) -> Option<SessionSummary<'_>> {
+        self.sessions.get(entity).ok().map(summarize)
+    }
+}
+
+fn summarize<'a>(
+    (entity, name, session, packet_rtt, transport, stats): (
+        Entity,
+        Option<&'a Name>,
+        Option<&'a Session>,
+        Option<&'a PacketRtt>,
+        Option<&'a Transport>,
+        Option<&'a SessionStats>,
+    ),
+) -> SessionSummary<'a> {
+    SessionSummary {
+        entity,
+        name,
+        state: if session.is_some() {
+            SessionState::Connected
+        } else {
+            SessionState::Connecting
+        },
+        packet_rtt: packet_rtt.map(|rtt| rtt.0),
+        msg_rtt: transport.map(|transport| transport.rtt().get()),
+        loss: stats.and_then(SessionStats::last).map(|sample| sample.loss),
+        packets: session.map(|session| session.stats).unwrap_or_default(),
+    }
+}
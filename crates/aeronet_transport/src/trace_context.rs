@@ -0,0 +1,232 @@
+//! Propagates distributed tracing contexts alongside messages.
+//!
+//! When a client sends a request to a server, you may want the spans that the
+//! server creates while handling that request to be linked to the client-side
+//! span which sent it. To do this, the client attaches its current trace
+//! context to the message via [`TransportSend::push_traced`], and the server
+//! extracts it via [`RecvMessage::split_trace_context`].
+//!
+//! The trace context is an opaque blob of bytes - `aeronet` does not depend on
+//! any specific tracing system. For OpenTelemetry, this would typically be the
+//! [W3C `traceparent`] header of the current span.
+//!
+//! A message with a trace context is encoded as a [`VarInt`] length prefix, the
+//! trace context itself, and then the message payload. Both sides must agree
+//! on which lanes carry traced messages, since a receiver can't tell apart a
+//! traced message from an untraced one.
+//!
+//! # Examples
+//!
+//! Bridging to OpenTelemetry via [`tracing-opentelemetry`]:
+//!
+//! ```ignore
+//! use {
+//!     aeronet_transport::{Transport, lane::LaneIndex},
+//!     octs::Bytes,
+//!     opentelemetry::global,
+//!     std::collections::HashMap,
+//!     tracing_opentelemetry::OpenTelemetrySpanExt,
+//!     web_time::Instant,
+//! };
+//!
+//! const TRACED_LANE: LaneIndex = LaneIndex(0);
+//!
+//! // client
+//! fn send_request(transport: &mut Transport, request: Bytes) {
+//!     let mut carrier = HashMap::new();
+//!     global::get_text_map_propagator(|propagator| {
+//!         propagator.inject_context(&tracing::Span::current().context(), &mut carrier);
+//!     });
+//!     let traceparent = carrier.get("traceparent").cloned().unwrap_or_default();
+//!     transport
+//!         .send
+//!         .push_traced(TRACED_LANE, request, traceparent.as_bytes(), Instant::now())
+//!         .unwrap();
+//! }
+//!
+//! // server
+//! fn recv_requests(transport: &mut Transport) {
+//!     for msg in transport.recv.msgs.drain() {
+//!         let Ok((trace_context, request)) = msg.split_trace_context() else {
+//!             continue;
+//!         };
+//!         let span = aeronet_transport::trace_context::recv_span(trace_context);
+//!         let carrier = HashMap::from([(
+//!             "traceparent".to_string(),
+//!             String::from_utf8_lossy(trace_context).into_owned(),
+//!         )]);
+//!         span.set_parent(global::get_text_map_propagator(|propagator| {
+//!             propagator.extract(&carrier)
+//!         }));
+//!         let _span = span.enter();
+//!         handle_request(request);
+//!     }
+//! }
+//! ```
+//!
+//! [W3C `traceparent`]: https://www.w3.org/TR/trace-context/#traceparent-header
+//! [`tracing-opentelemetry`]: https://docs.rs/tracing-opentelemetry
+
+use {
+    crate::{
+        MessageKey, RecvMessage,
+        lane::LaneIndex,
+        send::{PushError, TransportSend},
+    },
+    derive_more::{Display, Error},
+    octs::{Bytes, EncodeLen, Read, VarInt, Write},
+    tracing::{Span, info_span},
+    web_time::Instant,
+};
+
+/// Received a message which does not start with a valid trace context.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display, Error)]
+#[display("message does not start with a valid trace context")]
+pub struct InvalidTraceContext;
+
+/// Encodes a message payload with a trace context attached to it.
+///
+/// This is the format used by [`TransportSend::push_traced`], and can be
+/// decoded by [`decode`].
+#[must_use]
+#[expect(clippy::missing_panics_doc, reason = "shouldn't panic")]
+pub fn encode(trace_context: &[u8], msg: &[u8]) -> Bytes {
+    let len = VarInt(trace_context.len());
+    let mut buf = Vec::with_capacity(len.encode_len() + trace_context.len() + msg.len());
+    buf.write(len)
+        .expect("should grow the buffer when writing over capacity");
+    buf.extend_from_slice(trace_context);
+    buf.extend_from_slice(msg);
+    Bytes::from(buf)
+}
+
+/// Splits a payload created by [`encode`] into its trace context and the
+/// message payload.
+///
+/// # Errors
+///
+/// Errors if the payload does not start with a valid trace context.
+pub fn decode(mut payload: &[u8]) -> Result<(&[u8], &[u8]), InvalidTraceContext> {
+    let len = payload
+        .read::<VarInt<usize>>()
+        .map_err(|_| InvalidTraceContext)?
+        .0;
+    if len > payload.len() {
+        return Err(InvalidTraceContext);
+    }
+    Ok(payload.split_at(len))
+}
+
+/// Creates a span for handling a message received with the given trace
+/// context.
+///
+/// The trace context is recorded in the `trace_context` field, lossily
+/// converted to a UTF-8 string. If you use OpenTelemetry, you can then set
+/// this span's parent to the extracted context - see the [module docs].
+///
+/// [module docs]: crate::trace_context
+#[must_use]
+pub fn recv_span(trace_context: &[u8]) -> Span {
+    info_span!(
+        "recv_traced",
+        trace_context = %String::from_utf8_lossy(trace_context)
+    )
+}
+
+impl TransportSend {
+    /// Attempts to enqueue a message on this transport for sending, with a
+    /// trace context attached to it.
+    ///
+    /// The peer must extract the trace context from the message via
+    /// [`RecvMessage::split_trace_context`]. See [`trace_context`].
+    ///
+    /// # Errors
+    ///
+    /// See [`TransportSend::push`].
+    ///
+    /// # Panics
+    ///
+    /// See [`TransportSend::push`].
+    ///
+    /// [`trace_context`]: crate::trace_context
+    pub fn push_traced(
+        &mut self,
+        lane_index: LaneIndex,
+        msg: Bytes,
+        trace_context: &[u8],
+        now: Instant,
+    ) -> Result<MessageKey, PushError> {
+        self.push(lane_index, encode(trace_context, &msg), now)
+    }
+}
+
+impl RecvMessage {
+    /// Splits this message into the trace context attached to it by
+    /// [`TransportSend::push_traced`], and the actual message payload.
+    ///
+    /// See [`trace_context`].
+    ///
+    /// # Errors
+    ///
+    /// Errors if this message does not start with a valid trace context, e.g.
+    /// if the peer sent it via [`TransportSend::push`] instead.
+    ///
+    /// [`trace_context`]: crate::trace_context
+    pub fn split_trace_context(&self) -> Result<(&[u8], &[u8]), InvalidTraceContext> {
+        decode(&self.payload)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![expect(clippy::unwrap_used, reason = "testing")]
+
+    use {
+        super::*,
+        crate::{
+            Transport, TransportConfig, lane::LaneKind, limit::TokenBucket, recv::recv_on,
+            send::flush_on,
+        },
+        aeronet_io::{Session, packet::IP_MTU},
+    };
+
+    const LANES: [LaneKind; 1] = [LaneKind::ReliableOrdered];
+    const TRACEPARENT: &[u8] = b"00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01";
+
+    #[test]
+    fn round_trip() {
+        let now = Instant::now();
+        let session = Session::new(now, IP_MTU);
+        let config = TransportConfig::default();
+        let mut sender = Transport::new(&session, LANES, LANES, now).unwrap();
+        let mut receiver = Transport::new(&session, LANES, LANES, now).unwrap();
+        sender.send.bytes_bucket = TokenBucket::new(usize::MAX);
+
+        sender
+            .send
+            .push_traced(LaneIndex(0), Bytes::from_static(b"hello"), TRACEPARENT, now)
+            .unwrap();
+        for packet in flush_on(&mut sender, now, IP_MTU) {
+            recv_on(&mut receiver, &config, now, &packet).unwrap();
+        }
+
+        let msgs = receiver.recv.collect_msgs();
+        assert_eq!(1, msgs.len());
+        let (trace_context, payload) = msgs[0].split_trace_context().unwrap();
+        assert_eq!(TRACEPARENT, trace_context);
+        assert_eq!(b"hello", payload);
+    }
+
+    #[test]
+    fn empty_trace_context() {
+        let encoded = encode(&[], b"hello");
+        assert_eq!((&[][..], &b"hello"[..]), decode(&encoded).unwrap());
+    }
+
+    #[test]
+    fn invalid_trace_context() {
+        assert_eq!(InvalidTraceContext, decode(&[]).unwrap_err());
+        // claims to have a 16-byte context, but only has 5 bytes
+        assert_eq!(InvalidTraceContext, decode(b"\x10hello").unwrap_err());
+    }
+}
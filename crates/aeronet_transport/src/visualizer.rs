@@ -288,11 +288,13 @@ impl SessionVisualizer {
         ui.horizontal(|ui| {
             ui.with_layout(egui::Layout::left_to_right(egui::Align::Center), |ui| {
                 show_connected_status(ui, session, now);
-                show_mtu_status(ui, session);
+                show_mtu_status(ui, session, transport);
                 show_mem_status(ui, transport, transport_config);
                 show_tx_cap_status(ui, transport);
                 show_msg_buf_status(ui, transport);
                 show_rtt_status(ui, packet_rtt, transport);
+                #[cfg(feature = "cpu-time")]
+                show_cpu_time_status(ui, transport);
 
                 ui.label("hover for details");
             });
@@ -323,7 +325,7 @@ fn show_connected_status(ui: &mut egui::Ui, session: &Session, now: Instant) {
     });
 }
 
-fn show_mtu_status(ui: &mut egui::Ui, session: &Session) {
+fn show_mtu_status(ui: &mut egui::Ui, session: &Session, transport: &Transport) {
     ui.group(|ui| {
         ui.label("MTU");
         ui.label(format!("{}", session.mtu()));
@@ -340,6 +342,14 @@ fn show_mtu_status(ui: &mut egui::Ui, session: &Session) {
                 ui.label("Min");
                 ui.label(format!("{}", session.min_mtu()));
                 ui.end_row();
+
+                ui.label("Peer");
+                ui.label(
+                    transport
+                        .peer_mtu()
+                        .map_or_else(|| "?".into(), |mtu| format!("{mtu}")),
+                );
+                ui.end_row();
             });
 
         #[rustfmt::skip]
@@ -388,7 +398,7 @@ fn show_tx_cap_status(ui: &mut egui::Ui, transport: &Transport) {
         ui.label("TX CAP");
         ui.label(format!(
             "{} / {}",
-            fmt_bytes(transport.send.bytes_bucket().rem()),
+            fmt_bytes(transport.send_budget_remaining()),
             fmt_bytes(transport.send.bytes_bucket().cap()),
         ));
     })
@@ -398,18 +408,71 @@ fn show_tx_cap_status(ui: &mut egui::Ui, transport: &Transport) {
             .num_columns(2)
             .show(ui, |ui| {
                 ui.label("Remaining");
-                ui.label(fmt_thousands(transport.send.bytes_bucket().rem()));
+                ui.label(fmt_thousands(transport.send_budget_remaining()));
                 ui.end_row();
 
                 ui.label("Capacity");
                 ui.label(fmt_thousands(transport.send.bytes_bucket().cap()));
                 ui.end_row();
+
+                let fmt_spacing = |spacing: Option<Duration>| {
+                    spacing.map_or_else(|| "?".into(), |spacing| format!("{spacing:.1?}"))
+                };
+
+                ui.label("Packets / sec");
+                ui.label(format!("{}", transport.send.recent_flushes().len()));
+                ui.end_row();
+
+                ui.label("Min spacing");
+                ui.label(fmt_spacing(transport.send.flush_spacing().min()));
+                ui.end_row();
+
+                ui.label("Max spacing");
+                ui.label(fmt_spacing(transport.send.flush_spacing().max()));
+                ui.end_row();
             });
 
         #[rustfmt::skip]
         ui.label(
             "How many bytes this session is \n\
-            allowed to use to send out packets.",
+            allowed to use to send out packets, \n\
+            and how far apart packets were flushed \n\
+            over the last second.",
+        );
+    });
+}
+
+#[cfg(feature = "cpu-time")]
+fn show_cpu_time_status(ui: &mut egui::Ui, transport: &Transport) {
+    let cpu_time = transport.cpu_time();
+
+    ui.group(|ui| {
+        ui.label("CPU");
+        ui.label(format!(
+            "{:.1?} frag / {:.1?} reasm",
+            cpu_time.fragment, cpu_time.reassemble
+        ));
+    })
+    .response
+    .on_hover_ui(|ui| {
+        egui::Grid::new("cpu_time_details")
+            .num_columns(2)
+            .show(ui, |ui| {
+                ui.label("Fragment");
+                ui.label(format!("{:?}", cpu_time.fragment));
+                ui.end_row();
+
+                ui.label("Reassemble");
+                ui.label(format!("{:?}", cpu_time.reassemble));
+                ui.end_row();
+            });
+
+        #[rustfmt::skip]
+        ui.label(
+            "Total time spent splitting messages \n\
+            into fragments when flushing, and \n\
+            reassembling fragments into messages \n\
+            when receiving.",
         );
     });
 }
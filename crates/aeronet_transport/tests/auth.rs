@@ -0,0 +1,117 @@
+#![expect(missing_docs, clippy::unwrap_used, reason = "testing")]
+
+use {
+    aeronet_channel::{ChannelIo, ChannelIoPlugin},
+    aeronet_io::{
+        Session,
+        connection::{DisconnectReason, Disconnected},
+    },
+    aeronet_transport::{
+        AeronetTransportPlugin, Transport,
+        auth::{AuthChallenger, AuthError, AuthFailed, AuthPlugin, AuthResponder, Authenticated},
+        lane::LaneKind,
+    },
+    bevy_app::prelude::*,
+    bevy_ecs::prelude::*,
+    octs::Bytes,
+    web_time::Instant,
+};
+
+const LANES: [LaneKind; 1] = [LaneKind::ReliableOrdered];
+
+const TOKEN: &[u8] = b"secret token";
+
+fn add_transport(
+    trigger: Trigger<OnAdd, Authenticated>,
+    sessions: Query<&Session>,
+    mut commands: Commands,
+) {
+    let entity = trigger.entity();
+    let session = sessions.get(entity).unwrap();
+    let transport = Transport::new(session, LANES, LANES, Instant::now()).unwrap();
+    commands.entity(entity).insert(transport);
+}
+
+fn setup(token: &'static [u8]) -> (App, Entity, Entity) {
+    let mut app = App::new();
+    app.add_plugins((ChannelIoPlugin, AeronetTransportPlugin, AuthPlugin))
+        .add_observer(add_transport);
+
+    let world = app.world_mut();
+    let challenger = world
+        .spawn(AuthChallenger::new(
+            || Bytes::from_static(b"nonce"),
+            |challenge, response| {
+                assert_eq!(b"nonce", challenge);
+                if response == TOKEN {
+                    Ok(())
+                } else {
+                    Err("invalid token".into())
+                }
+            },
+        ))
+        .id();
+    let responder = world
+        .spawn(AuthResponder::new(move |_| Bytes::from_static(token)))
+        .id();
+    world
+        .commands()
+        .queue(ChannelIo::open(challenger, responder));
+    (app, challenger, responder)
+}
+
+#[test]
+fn valid_response_authenticates() {
+    let (mut app, challenger, responder) = setup(TOKEN);
+    for _ in 0..5 {
+        app.update();
+    }
+
+    let world = app.world();
+    for entity in [challenger, responder] {
+        assert!(world.get::<Authenticated>(entity).is_some());
+        assert!(world.get::<Transport>(entity).is_some());
+    }
+}
+
+#[test]
+fn invalid_response_disconnects() {
+    #[derive(Default, Resource)]
+    struct Results {
+        failed: Vec<(Entity, AuthError)>,
+        peer_reasons: Vec<(Entity, String)>,
+    }
+
+    let (mut app, challenger, responder) = setup(b"wrong token");
+    app.init_resource::<Results>()
+        .add_observer(
+            |trigger: Trigger<AuthFailed>, mut results: ResMut<Results>| {
+                results
+                    .failed
+                    .push((trigger.entity(), trigger.event().error.clone()));
+            },
+        )
+        .add_observer(
+            |trigger: Trigger<Disconnected>, mut results: ResMut<Results>| {
+                if let DisconnectReason::Peer(reason) = &trigger.event().reason {
+                    results
+                        .peer_reasons
+                        .push((trigger.entity(), reason.clone()));
+                }
+            },
+        );
+    for _ in 0..5 {
+        app.update();
+    }
+
+    let error = AuthError::Rejected {
+        reason: "invalid token".into(),
+    };
+    let results = app.world().resource::<Results>();
+    assert_eq!(vec![(challenger, error.clone())], results.failed);
+    assert_eq!(vec![(responder, error.to_string())], results.peer_reasons);
+    for entity in [challenger, responder] {
+        assert!(app.world().get::<Authenticated>(entity).is_none());
+        assert!(app.world().get::<Transport>(entity).is_none());
+    }
+}
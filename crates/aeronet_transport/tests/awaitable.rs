@@ -0,0 +1,125 @@
+#![expect(missing_docs, clippy::unwrap_used, reason = "testing")]
+
+extern crate alloc;
+
+use {
+    aeronet_channel::{ChannelIo, ChannelIoPlugin},
+    aeronet_io::Session,
+    aeronet_transport::{
+        AeronetTransportPlugin, Transport,
+        awaitable::{DeliveryFuture, DeliveryOutcome},
+        lane::{LaneIndex, LaneKind},
+    },
+    alloc::{sync::Arc, task::Wake},
+    bevy_app::prelude::*,
+    bevy_ecs::prelude::*,
+    bevy_time::TimePlugin,
+    core::{
+        future::Future,
+        pin::Pin,
+        sync::atomic::{AtomicBool, Ordering},
+        task::{Context, Poll, Waker},
+    },
+    octs::Bytes,
+    web_time::Instant,
+};
+
+const LANES: [LaneKind; 1] = [LaneKind::ReliableOrdered];
+
+fn add_transport(
+    trigger: Trigger<OnAdd, Session>,
+    sessions: Query<&Session>,
+    mut commands: Commands,
+) {
+    let entity = trigger.entity();
+    let session = sessions.get(entity).unwrap();
+    let transport = Transport::new(session, LANES, LANES, Instant::now()).unwrap();
+    commands.entity(entity).insert(transport);
+}
+
+fn drain(mut transports: Query<&mut Transport>) {
+    for mut transport in &mut transports {
+        transport.recv.msgs.drain().for_each(drop);
+        transport.recv.acks.drain().for_each(drop);
+    }
+}
+
+#[derive(Debug, Default)]
+struct WakeFlag(AtomicBool);
+
+impl Wake for WakeFlag {
+    fn wake(self: Arc<Self>) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+}
+
+fn setup() -> (App, Entity, Entity) {
+    let mut app = App::new();
+    app.add_plugins((TimePlugin, ChannelIoPlugin, AeronetTransportPlugin))
+        .add_systems(Update, drain)
+        .add_observer(add_transport);
+
+    let world = app.world_mut();
+    let a = world.spawn_empty().id();
+    let b = world.spawn_empty().id();
+    world.commands().queue(ChannelIo::open(a, b));
+    app.update();
+    (app, a, b)
+}
+
+fn poll(future: &mut DeliveryFuture, waker: &Waker) -> Poll<DeliveryOutcome> {
+    Pin::new(future).poll(&mut Context::from_waker(waker))
+}
+
+#[test]
+fn resolves_delivered() {
+    let (mut app, a, _) = setup();
+
+    let mut future = app
+        .world_mut()
+        .get_mut::<Transport>(a)
+        .unwrap()
+        .send_awaitable(LaneIndex(0), Bytes::from_static(b"hello"), Instant::now())
+        .unwrap();
+    let flag = Arc::new(WakeFlag::default());
+    let waker = Waker::from(flag.clone());
+    assert_eq!(Poll::Pending, poll(&mut future, &waker));
+
+    for _ in 0..10 {
+        if flag.0.load(Ordering::SeqCst) {
+            break;
+        }
+        app.update();
+    }
+
+    assert!(flag.0.load(Ordering::SeqCst), "future was never woken");
+    assert_eq!(
+        Poll::Ready(DeliveryOutcome::Delivered),
+        poll(&mut future, &waker)
+    );
+    // the report was consumed by the future
+    let mut transport = app.world_mut().get_mut::<Transport>(a).unwrap();
+    assert_eq!(0, transport.recv.deliveries.drain().count());
+}
+
+#[test]
+fn resolves_closed_on_despawn() {
+    let (mut app, a, b) = setup();
+
+    // the peer never receives anything, so this can never be acked
+    app.world_mut().despawn(b);
+    let mut future = app
+        .world_mut()
+        .get_mut::<Transport>(a)
+        .unwrap()
+        .send_awaitable(LaneIndex(0), Bytes::from_static(b"hello"), Instant::now())
+        .unwrap();
+    let waker = Waker::from(Arc::new(WakeFlag::default()));
+    assert_eq!(Poll::Pending, poll(&mut future, &waker));
+
+    app.world_mut().despawn(a);
+    assert_eq!(
+        Poll::Ready(DeliveryOutcome::Closed),
+        poll(&mut future, &waker)
+    );
+}
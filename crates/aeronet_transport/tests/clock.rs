@@ -0,0 +1,126 @@
+#![expect(missing_docs, clippy::unwrap_used, reason = "testing")]
+
+use {
+    aeronet_channel::{ChannelIo, ChannelIoPlugin},
+    aeronet_io::{IoSet, Session},
+    aeronet_transport::{
+        AeronetTransportPlugin, Transport, TransportConfig, TransportSet,
+        lane::{LaneIndex, LaneKind},
+    },
+    bevy_app::prelude::*,
+    bevy_ecs::prelude::*,
+    bevy_time::TimePlugin,
+    core::time::Duration,
+    octs::Bytes,
+    std::thread,
+    web_time::Instant,
+};
+
+const LANES: [LaneKind; 1] = [LaneKind::UnreliableUnordered];
+
+/// One-way delay applied to every packet sent, in both directions.
+const LATENCY: Duration = Duration::from_millis(50);
+
+fn add_transport(
+    trigger: Trigger<OnAdd, Session>,
+    sessions: Query<&Session>,
+    mut commands: Commands,
+) {
+    let entity = trigger.entity();
+    let session = sessions.get(entity).unwrap();
+    let transport = Transport::new(session, LANES, LANES, Instant::now()).unwrap();
+    commands.entity(entity).insert((
+        transport,
+        TransportConfig {
+            clock_sync_interval: Some(Duration::from_millis(20)),
+            ..Default::default()
+        },
+    ));
+}
+
+#[derive(Debug, Default, Resource)]
+struct DelayedPackets(Vec<(Entity, Instant, Bytes)>);
+
+fn delay_packets(mut sessions: Query<(Entity, &mut Session)>, mut delayed: ResMut<DelayedPackets>) {
+    let now = Instant::now();
+    for (entity, mut session) in &mut sessions {
+        delayed.0.extend(
+            session
+                .send
+                .drain(..)
+                .map(|packet| (entity, now + LATENCY, packet)),
+        );
+    }
+
+    delayed.0.retain(|(entity, send_at, packet)| {
+        if *send_at > now {
+            return true;
+        }
+        sessions
+            .get_mut(*entity)
+            .unwrap()
+            .1
+            .send
+            .push(packet.clone());
+        false
+    });
+}
+
+fn exchange_msgs(mut transports: Query<&mut Transport>) {
+    let now = Instant::now();
+    for mut transport in &mut transports {
+        transport.recv.msgs.drain().for_each(drop);
+        transport.recv.acks.drain().for_each(drop);
+        transport
+            .send
+            .push(LaneIndex(0), Bytes::from_static(b"tick"), now)
+            .unwrap();
+    }
+}
+
+#[test]
+fn co_located_clocks_have_no_offset() {
+    let mut app = App::new();
+    app.add_plugins((TimePlugin, ChannelIoPlugin, AeronetTransportPlugin))
+        .init_resource::<DelayedPackets>()
+        .add_systems(Update, exchange_msgs)
+        .add_systems(
+            PostUpdate,
+            delay_packets
+                .after(TransportSet::Flush)
+                .before(IoSet::Flush),
+        )
+        .add_observer(add_transport);
+
+    let world = app.world_mut();
+    let a = world.spawn_empty().id();
+    let b = world.spawn_empty().id();
+    world.commands().queue(ChannelIo::open(a, b));
+    app.update();
+    assert_eq!(
+        None,
+        app.world().get::<Transport>(a).unwrap().peer_clock_offset()
+    );
+
+    // let the RTT estimate and clock offset settle
+    let start = Instant::now();
+    while start.elapsed() < LATENCY * 20 {
+        app.update();
+        thread::sleep(Duration::from_millis(1));
+    }
+
+    // both transports share the same clock, so the offset should be tiny,
+    // apart from the time spent between flushing and receiving packets
+    for entity in [a, b] {
+        let offset = app
+            .world()
+            .get::<Transport>(entity)
+            .unwrap()
+            .peer_clock_offset()
+            .unwrap();
+        assert!(
+            offset < Duration::from_millis(15),
+            "offset {offset:?} too high"
+        );
+    }
+}
@@ -0,0 +1,83 @@
+#![expect(missing_docs, clippy::unwrap_used, reason = "testing")]
+
+use {
+    aeronet_channel::{ChannelIo, ChannelIoPlugin},
+    aeronet_io::{
+        Session,
+        connection::{AppExitTimeout, Unflushed},
+    },
+    aeronet_transport::{
+        AeronetTransportPlugin, Transport,
+        lane::{LaneIndex, LaneKind},
+    },
+    bevy_app::prelude::*,
+    bevy_ecs::prelude::*,
+    bevy_time::TimePlugin,
+    core::time::Duration,
+    octs::Bytes,
+    web_time::Instant,
+};
+
+const LANES: [LaneKind; 1] = [LaneKind::ReliableOrdered];
+const MSG: &[u8] = b"goodbye";
+
+#[derive(Debug, Default, Resource)]
+struct Received(Vec<Vec<u8>>);
+
+fn add_transport(
+    trigger: Trigger<OnAdd, Session>,
+    sessions: Query<&Session>,
+    mut commands: Commands,
+) {
+    let entity = trigger.entity();
+    let session = sessions.get(entity).unwrap();
+    let transport = Transport::new(session, LANES, LANES, Instant::now()).unwrap();
+    commands.entity(entity).insert(transport);
+}
+
+fn drain(mut transports: Query<&mut Transport>, mut received: ResMut<Received>) {
+    for mut transport in &mut transports {
+        received
+            .0
+            .extend(transport.recv.msgs.drain().map(|msg| msg.payload));
+        transport.recv.acks.drain().for_each(drop);
+    }
+}
+
+#[test]
+fn exit_waits_for_acks() {
+    let mut app = App::new();
+    app.add_plugins((TimePlugin, ChannelIoPlugin, AeronetTransportPlugin))
+        .insert_resource(AppExitTimeout(Duration::from_secs(60)))
+        .init_resource::<Received>()
+        .add_systems(Update, drain)
+        .add_observer(add_transport);
+
+    let world = app.world_mut();
+    let a = world.spawn_empty().id();
+    let b = world.spawn_empty().id();
+    world.commands().queue(ChannelIo::open(a, b));
+    app.update();
+
+    app.world_mut()
+        .get_mut::<Transport>(a)
+        .unwrap()
+        .send
+        .push(LaneIndex(0), Bytes::from_static(MSG), Instant::now())
+        .unwrap();
+    app.world_mut().send_event(AppExit::Success);
+
+    // the message is flushed, but not acked yet
+    app.update();
+    assert!(app.world().get::<Unflushed>(a).is_some());
+    assert_eq!(None, app.should_exit());
+
+    let mut updates = 0;
+    while app.should_exit().is_none() {
+        app.update();
+        updates += 1;
+        assert!(updates < 8, "app never exited");
+    }
+    assert_eq!(vec![MSG.to_vec()], app.world().resource::<Received>().0);
+    assert!(app.world().get_entity(a).is_err());
+}
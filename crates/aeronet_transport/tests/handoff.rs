@@ -0,0 +1,210 @@
+#![expect(missing_docs, clippy::unwrap_used, reason = "testing")]
+
+use {
+    aeronet_channel::{ChannelIo, ChannelIoPlugin},
+    aeronet_io::{
+        Session,
+        connection::{DisconnectReason, Disconnected},
+    },
+    aeronet_transport::{
+        AeronetTransportPlugin, Transport,
+        handoff::{
+            HANDOFF_DISCONNECT_REASON, HandoffComplete, SessionHandoff, SessionHandoffPlugin,
+        },
+        lane::{LaneIndex, LaneKind},
+    },
+    bevy_app::prelude::*,
+    bevy_ecs::prelude::*,
+    bevy_time::TimePlugin,
+    core::time::Duration,
+    octs::Bytes,
+    web_time::Instant,
+};
+
+const LANES: [LaneKind; 1] = [LaneKind::ReliableOrdered];
+const LANE: LaneIndex = LaneIndex(0);
+const MAX_UPDATES: usize = 100;
+
+fn add_transport(
+    trigger: Trigger<OnAdd, Session>,
+    sessions: Query<&Session>,
+    mut commands: Commands,
+) {
+    let entity = trigger.entity();
+    let session = sessions.get(entity).unwrap();
+    let transport = Transport::new(session, LANES, LANES, Instant::now()).unwrap();
+    commands.entity(entity).insert(transport);
+}
+
+#[derive(Debug, Default, Resource)]
+struct Received(Vec<(Entity, Vec<u8>)>);
+
+fn drain_transports(mut transports: Query<(Entity, &mut Transport)>, mut recv: ResMut<Received>) {
+    for (entity, mut transport) in &mut transports {
+        recv.0
+            .extend(transport.recv.msgs.drain().map(|msg| (entity, msg.payload)));
+        transport.recv.acks.drain().for_each(drop);
+    }
+}
+
+#[derive(Debug, Default, Resource)]
+struct Completed(Vec<HandoffComplete>);
+
+fn resend_unacked(
+    trigger: Trigger<HandoffComplete>,
+    mut transports: Query<&mut Transport>,
+    mut completed: ResMut<Completed>,
+) {
+    let handoff = trigger.event();
+    let mut transport = transports.get_mut(handoff.target).unwrap();
+    for (lane_index, msg) in handoff.unacked.iter().cloned() {
+        transport
+            .send
+            .push(lane_index, msg, Instant::now())
+            .unwrap();
+    }
+    completed.0.push(handoff.clone());
+}
+
+#[derive(Debug, Default, Resource)]
+struct PeerDisconnected(Vec<Entity>);
+
+fn peer_disconnected(trigger: Trigger<Disconnected>, mut disconnected: ResMut<PeerDisconnected>) {
+    let DisconnectReason::Peer(reason) = &trigger.event().reason else {
+        panic!("unexpected disconnect reason");
+    };
+    assert_eq!(HANDOFF_DISCONNECT_REASON, reason);
+    disconnected.0.push(trigger.entity());
+}
+
+fn app() -> App {
+    let mut app = App::new();
+    app.add_plugins((
+        TimePlugin,
+        ChannelIoPlugin,
+        AeronetTransportPlugin,
+        SessionHandoffPlugin,
+    ))
+    .init_resource::<Received>()
+    .init_resource::<Completed>()
+    .init_resource::<PeerDisconnected>()
+    .add_systems(Update, drain_transports)
+    .add_observer(add_transport);
+    app
+}
+
+/// Connects a client to a server, returning `(client, server)`.
+fn connect(app: &mut App) -> (Entity, Entity) {
+    let world = app.world_mut();
+    let client = world.spawn_empty().id();
+    let server = world.spawn_empty().observe(peer_disconnected).id();
+    world.commands().queue(ChannelIo::open(client, server));
+    app.update();
+    assert!(app.world().get::<Transport>(client).is_some());
+    assert!(app.world().get::<Transport>(server).is_some());
+    (client, server)
+}
+
+fn push(app: &mut App, session: Entity, msg: &'static [u8]) {
+    app.world_mut()
+        .get_mut::<Transport>(session)
+        .unwrap()
+        .send
+        .push(LANE, Bytes::from_static(msg), Instant::now())
+        .unwrap();
+}
+
+fn update_until(app: &mut App, mut f: impl FnMut(&World) -> bool) {
+    for _ in 0..MAX_UPDATES {
+        app.update();
+        if f(app.world()) {
+            return;
+        }
+    }
+    panic!("condition not met after {MAX_UPDATES} updates");
+}
+
+fn received(world: &World, session: Entity, msg: &[u8]) -> bool {
+    world
+        .resource::<Received>()
+        .0
+        .iter()
+        .any(|(entity, recv)| *entity == session && recv == msg)
+}
+
+#[test]
+fn handoff_resends_unacked() {
+    let mut app = app();
+    let (old_client, old_server) = connect(&mut app);
+
+    push(&mut app, old_client, b"acked");
+    update_until(&mut app, |world| received(world, old_server, b"acked"));
+
+    let (new_client, new_server) = connect(&mut app);
+    app.world_mut()
+        .entity_mut(old_client)
+        .insert(SessionHandoff::new(new_client).with_drain_timeout(Duration::ZERO))
+        .observe(resend_unacked);
+    // cutover happens before this is ever flushed
+    push(&mut app, old_client, b"unacked");
+    app.update();
+
+    let completed = &app.world().resource::<Completed>().0;
+    assert_eq!(1, completed.len());
+    assert_eq!(new_client, completed[0].target);
+    assert_eq!(
+        vec![(LANE, Bytes::from_static(b"unacked"))],
+        completed[0].unacked
+    );
+
+    update_until(&mut app, |world| {
+        received(world, new_server, b"unacked")
+            && world.resource::<PeerDisconnected>().0.contains(&old_server)
+    });
+    assert!(!received(app.world(), old_server, b"unacked"));
+    assert!(app.world().get_entity(old_client).is_err());
+}
+
+#[test]
+fn handoff_drains_old_session() {
+    let mut app = app();
+    let (old_client, old_server) = connect(&mut app);
+    let (new_client, _) = connect(&mut app);
+
+    push(&mut app, old_client, b"draining");
+    app.world_mut()
+        .entity_mut(old_client)
+        .insert(SessionHandoff::new(new_client))
+        .observe(resend_unacked);
+    app.update();
+    assert!(
+        app.world()
+            .get::<SessionHandoff>(old_client)
+            .unwrap()
+            .is_draining()
+    );
+
+    update_until(&mut app, |world| {
+        !world.resource::<Completed>().0.is_empty()
+    });
+    let completed = &app.world().resource::<Completed>().0;
+    assert!(completed[0].unacked.is_empty());
+    assert!(received(app.world(), old_server, b"draining"));
+}
+
+#[test]
+fn handoff_cancelled_without_target() {
+    let mut app = app();
+    let (old_client, _) = connect(&mut app);
+    let new_client = app.world_mut().spawn_empty().id();
+
+    app.world_mut()
+        .entity_mut(old_client)
+        .insert(SessionHandoff::new(new_client));
+    app.world_mut().despawn(new_client);
+    app.update();
+
+    assert!(app.world().get::<SessionHandoff>(old_client).is_none());
+    assert!(app.world().get::<Session>(old_client).is_some());
+    assert!(app.world().resource::<Completed>().0.is_empty());
+}
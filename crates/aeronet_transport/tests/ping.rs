@@ -0,0 +1,105 @@
+#![expect(missing_docs, clippy::unwrap_used, reason = "testing")]
+
+use {
+    aeronet_channel::{ChannelIo, ChannelIoPlugin},
+    aeronet_io::{IoSet, Session},
+    aeronet_transport::{AeronetTransportPlugin, Transport, TransportSet, lane::LaneKind},
+    bevy_app::prelude::*,
+    bevy_ecs::prelude::*,
+    bevy_time::TimePlugin,
+    core::time::Duration,
+    octs::Bytes,
+    std::thread,
+    web_time::Instant,
+};
+
+const LANES: [LaneKind; 1] = [LaneKind::ReliableOrdered];
+
+/// One-way delay applied to every packet sent.
+const LATENCY: Duration = Duration::from_millis(50);
+
+fn add_transport(
+    trigger: Trigger<OnAdd, Session>,
+    sessions: Query<&Session>,
+    mut commands: Commands,
+) {
+    let entity = trigger.entity();
+    let session = sessions.get(entity).unwrap();
+    let transport = Transport::new(session, LANES, LANES, Instant::now()).unwrap();
+    commands.entity(entity).insert(transport);
+}
+
+#[derive(Debug, Default, Resource)]
+struct DelayedPackets(Vec<(Entity, Instant, Bytes)>);
+
+fn delay_packets(mut sessions: Query<(Entity, &mut Session)>, mut delayed: ResMut<DelayedPackets>) {
+    let now = Instant::now();
+    for (entity, mut session) in &mut sessions {
+        delayed.0.extend(
+            session
+                .send
+                .drain(..)
+                .map(|packet| (entity, now + LATENCY, packet)),
+        );
+    }
+
+    delayed.0.retain(|(entity, send_at, packet)| {
+        if *send_at > now {
+            return true;
+        }
+        sessions
+            .get_mut(*entity)
+            .unwrap()
+            .1
+            .send
+            .push(packet.clone());
+        false
+    });
+}
+
+#[test]
+fn ping_measures_rtt() {
+    let mut app = App::new();
+    app.add_plugins((TimePlugin, ChannelIoPlugin, AeronetTransportPlugin))
+        .init_resource::<DelayedPackets>()
+        .add_systems(
+            PostUpdate,
+            delay_packets
+                .after(TransportSet::Flush)
+                .before(IoSet::Flush),
+        )
+        .add_observer(add_transport);
+
+    let world = app.world_mut();
+    let a = world.spawn_empty().id();
+    let b = world.spawn_empty().id();
+    world.commands().queue(ChannelIo::open(a, b));
+    app.update();
+
+    let token = app
+        .world_mut()
+        .get_mut::<Transport>(a)
+        .unwrap()
+        .ping(Instant::now());
+
+    let start = Instant::now();
+    let pong = loop {
+        app.update();
+        let mut transport = app.world_mut().get_mut::<Transport>(a).unwrap();
+        transport.recv.msgs.drain().for_each(drop);
+        transport.recv.acks.drain().for_each(drop);
+        if let Some(pong) = transport.recv.pongs.drain().next() {
+            break pong;
+        }
+        assert!(start.elapsed() < LATENCY * 20, "never received a pong");
+        thread::sleep(Duration::from_millis(1));
+    };
+
+    assert_eq!(token, pong.token);
+    assert!(pong.rtt >= LATENCY * 2, "rtt {:?} too low", pong.rtt);
+    assert!(
+        pong.rtt < LATENCY * 2 + Duration::from_millis(40),
+        "rtt {:?} too high",
+        pong.rtt
+    );
+}
@@ -0,0 +1,139 @@
+#![expect(missing_docs, clippy::unwrap_used, reason = "testing")]
+
+extern crate alloc;
+
+use {
+    aeronet_channel::{ChannelIo, ChannelIoPlugin},
+    aeronet_io::Session,
+    aeronet_transport::{
+        AeronetTransportPlugin, Transport,
+        lane::{LaneIndex, LaneKind},
+        recv_log::{RECV_LOG_MAGIC, RecvLog, payload_hash, read_recv_log},
+    },
+    alloc::sync::Arc,
+    bevy_app::prelude::*,
+    bevy_ecs::prelude::*,
+    bevy_time::TimePlugin,
+    octs::Bytes,
+    std::{
+        io,
+        sync::{Mutex, PoisonError},
+    },
+    web_time::Instant,
+};
+
+const LANES: [LaneKind; 2] = [LaneKind::ReliableOrdered, LaneKind::UnreliableUnordered];
+
+fn add_transport(
+    trigger: Trigger<OnAdd, Session>,
+    sessions: Query<&Session>,
+    mut commands: Commands,
+) {
+    let entity = trigger.entity();
+    let session = sessions.get(entity).unwrap();
+    let transport = Transport::new(session, LANES, LANES, Instant::now()).unwrap();
+    commands.entity(entity).insert(transport);
+}
+
+fn drain(mut transports: Query<&mut Transport>) {
+    for mut transport in &mut transports {
+        transport.recv.msgs.drain().for_each(drop);
+        transport.recv.acks.drain().for_each(drop);
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct SharedBuf(Arc<Mutex<Vec<u8>>>);
+
+impl io::Write for SharedBuf {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+fn setup(log: RecvLog) -> (App, Entity) {
+    let mut app = App::new();
+    app.add_plugins((TimePlugin, ChannelIoPlugin, AeronetTransportPlugin))
+        .add_systems(Update, drain)
+        .add_observer(add_transport);
+
+    let world = app.world_mut();
+    let a = world.spawn_empty().id();
+    let b = world.spawn(log).id();
+    world.commands().queue(ChannelIo::open(a, b));
+    app.update();
+    (app, a)
+}
+
+fn send(app: &mut App, from: Entity, msgs: &[(LaneIndex, &'static [u8])]) {
+    let mut transport = app.world_mut().get_mut::<Transport>(from).unwrap();
+    for (lane, msg) in msgs {
+        transport
+            .send
+            .push(*lane, Bytes::from_static(msg), Instant::now())
+            .unwrap();
+    }
+    for _ in 0..4 {
+        app.update();
+    }
+}
+
+const MSGS: [(LaneIndex, &[u8]); 3] = [
+    (LaneIndex(0), b"hello"),
+    (LaneIndex(1), b"world!"),
+    (LaneIndex(0), b"!"),
+];
+
+#[test]
+fn logs_one_entry_per_message() {
+    let buf = SharedBuf::default();
+    let (mut app, a) = setup(RecvLog::new(buf.clone()).unwrap());
+    send(&mut app, a, &MSGS);
+
+    let logged = read_recv_log(buf.0.lock().unwrap().as_slice()).unwrap();
+    assert_eq!(MSGS.len(), logged.len());
+    // messages on different lanes may be delivered in any order
+    for (lane, msg) in MSGS {
+        assert!(
+            logged.iter().any(|logged| logged.lane == lane
+                && logged.len == msg.len()
+                && logged.hash == payload_hash(msg)
+                && logged.payload.is_none()),
+            "no entry for {msg:?} on {lane:?} in {logged:?}"
+        );
+    }
+}
+
+#[test]
+fn logs_payloads() {
+    let buf = SharedBuf::default();
+    let (mut app, a) = setup(RecvLog::new(buf.clone()).unwrap().with_payloads());
+    send(&mut app, a, &MSGS[..1]);
+
+    let logged = read_recv_log(buf.0.lock().unwrap().as_slice()).unwrap();
+    assert_eq!(1, logged.len());
+    assert_eq!(LaneIndex(0), logged[0].lane);
+    assert_eq!(Some(b"hello".to_vec()), logged[0].payload);
+}
+
+#[test]
+fn truncated_payload() {
+    let mut log = RECV_LOG_MAGIC.to_vec();
+    log.extend_from_slice(&0u16.to_le_bytes());
+    log.extend_from_slice(&0u64.to_le_bytes());
+    // claims a payload far larger than the rest of the log
+    log.extend_from_slice(&u32::MAX.to_le_bytes());
+    log.extend_from_slice(&payload_hash(b"").to_le_bytes());
+    log.push(1);
+    log.extend_from_slice(b"short");
+
+    let err = read_recv_log(log.as_slice()).unwrap_err();
+    assert_eq!(io::ErrorKind::UnexpectedEof, err.kind());
+}
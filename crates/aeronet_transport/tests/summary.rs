@@ -0,0 +1,80 @@
+#![expect(missing_docs, clippy::unwrap_used, reason = "testing")]
+
+use {
+    aeronet_channel::{ChannelIo, ChannelIoPlugin},
+    aeronet_io::{Session, SessionEndpoint},
+    aeronet_transport::{
+        AeronetTransportPlugin, Transport,
+        lane::LaneKind,
+        sampling::{SessionSamplingPlugin, SessionStatsSampling},
+        summary::{SessionState, SessionSummaries},
+    },
+    bevy_app::prelude::*,
+    bevy_core::Name,
+    bevy_ecs::{prelude::*, system::SystemState},
+    bevy_time::TimePlugin,
+    core::time::Duration,
+    std::thread,
+    web_time::Instant,
+};
+
+const LANES: [LaneKind; 1] = [LaneKind::ReliableOrdered];
+
+fn add_transport(
+    trigger: Trigger<OnAdd, Session>,
+    sessions: Query<&Session>,
+    mut commands: Commands,
+) {
+    let entity = trigger.entity();
+    let session = sessions.get(entity).unwrap();
+    let transport = Transport::new(session, LANES, LANES, Instant::now()).unwrap();
+    commands.entity(entity).insert(transport);
+}
+
+#[test]
+fn summarize_channel_sessions() {
+    let mut app = App::new();
+    app.add_plugins((
+        TimePlugin,
+        ChannelIoPlugin,
+        AeronetTransportPlugin,
+        SessionSamplingPlugin,
+    ))
+    .insert_resource(SessionStatsSampling::new(1000.0, 1.0))
+    .add_observer(add_transport);
+
+    let world = app.world_mut();
+    let a = world.spawn(Name::new("a")).id();
+    let b = world.spawn(Name::new("b")).id();
+    let connecting = world.spawn((Name::new("connecting"), SessionEndpoint)).id();
+    world.commands().queue(ChannelIo::open(a, b));
+    for _ in 0..10 {
+        app.update();
+        thread::sleep(Duration::from_millis(2));
+    }
+
+    let world = app.world_mut();
+    let mut state = SystemState::<SessionSummaries>::new(world);
+    let summaries = state.get(world);
+    assert_eq!(3, summaries.iter().count());
+
+    for (entity, name) in [(a, "a"), (b, "b")] {
+        let summary = summaries.get(entity).unwrap();
+        let session = world.get::<Session>(entity).unwrap();
+        let transport = world.get::<Transport>(entity).unwrap();
+
+        assert_eq!(name, summary.name.unwrap().as_str());
+        assert_eq!(SessionState::Connected, summary.state);
+        assert_eq!(Some(transport.rtt().get()), summary.msg_rtt);
+        assert!(summary.loss.is_some());
+        assert_eq!(session.stats.bytes_sent, summary.packets.bytes_sent);
+        assert_eq!(session.stats.bytes_recv, summary.packets.bytes_recv);
+        assert!(summary.packets.bytes_sent.0 > 0);
+    }
+
+    let summary = summaries.get(connecting).unwrap();
+    assert_eq!(SessionState::Connecting, summary.state);
+    assert_eq!(None, summary.msg_rtt);
+    assert_eq!(None, summary.loss);
+    assert_eq!(0, summary.packets.bytes_sent.0);
+}
@@ -66,7 +66,7 @@ fn on_disconnected(
     mut ui_state: ResMut<GlobalUi>,
 ) {
     let session = trigger.entity();
-    let Disconnected { reason } = trigger.event();
+    let Disconnected { reason, .. } = trigger.event();
     let name = names
         .get(session)
         .expect("our session entity should have a name");
@@ -14,18 +14,24 @@ pub async fn start(
     let (connected, backend) = {
         #[cfg(target_family = "wasm")]
         {
-            // suppress `unused_variables`
-            _ = config;
-
             debug!("Spawning backend task to connect to {target:?}");
 
+            let started_at = web_time::Instant::now();
+
             let socket = web_sys::WebSocket::new(&target)
                 .map_err(crate::JsError::from)
                 .map_err(ClientError::CreateSocket)?;
             debug!("Created socket");
 
-            let (frontend, backend) = crate::session::backend::wasm::split(socket);
-            (ToConnected { frontend }, backend)
+            let (frontend, backend) =
+                crate::session::backend::wasm::split(socket, config.frame_kind);
+            (
+                ToConnected {
+                    started_at,
+                    frontend,
+                },
+                backend,
+            )
         }
 
         #[cfg(not(target_family = "wasm"))]
@@ -36,19 +42,10 @@ pub async fn start(
 
             debug!("Spawning backend task to connect to {:?}", target.uri());
 
-            let (stream, _) = {
-                let socket_config = Some(config.socket);
-                let disable_nagle = !config.nagle;
-
-                tokio_tungstenite::connect_async_tls_with_config(
-                    target,
-                    socket_config,
-                    disable_nagle,
-                    Some(config.connector),
-                )
-            }
-            .await
-            .map_err(ClientError::Connect)?;
+            let (stream, response_headers, timing) =
+                native::connect(config.socket, config.nagle, config.connector, target)
+                    .await
+                    .map_err(ClientError::Connect)?;
 
             let socket = match stream.get_ref() {
                 MaybeTlsStream::Plain(socket) => socket,
@@ -65,11 +62,14 @@ pub async fn start(
                 .map_err(ClientError::Session)?;
             debug!("Created stream");
 
-            let (frontend, backend) = crate::session::backend::native::split(stream);
+            let (frontend, backend) =
+                crate::session::backend::native::split(stream, config.frame_kind);
             (
                 ToConnected {
                     local_addr,
                     peer_addr,
+                    response_headers,
+                    timing,
                     frontend,
                 },
                 backend,
@@ -88,3 +88,84 @@ pub async fn start(
         .await
         .map_err(|reason| reason.map_err(ClientError::Session))
 }
+
+#[cfg(not(target_family = "wasm"))]
+mod native {
+    use {
+        crate::tungstenite::{
+            self,
+            client::uri_mode,
+            error::{TlsError, UrlError},
+            handshake::client::Request,
+            http::HeaderMap,
+            protocol::WebSocketConfig,
+            stream::Mode,
+        },
+        aeronet_io::connection::ConnectTiming,
+        rustls::pki_types::ServerName,
+        tokio::net::TcpStream,
+        tokio_rustls::TlsConnector,
+        tokio_tungstenite::{Connector, MaybeTlsStream, WebSocketStream},
+        tracing::debug,
+        web_time::Instant,
+    };
+
+    type Stream = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+    // equivalent to `tokio_tungstenite::connect_async_tls_with_config`,
+    // but split up so that we can time each phase
+    pub async fn connect(
+        socket_config: WebSocketConfig,
+        nagle: bool,
+        connector: Connector,
+        target: Request,
+    ) -> Result<(Stream, HeaderMap, ConnectTiming), tungstenite::Error> {
+        let started_at = Instant::now();
+
+        let uri = target.uri();
+        let mode = uri_mode(uri)?;
+        let domain = uri
+            .host()
+            .map(|host| host.trim_start_matches('[').trim_end_matches(']'))
+            .ok_or(UrlError::NoHostName)?
+            .to_owned();
+        let port = uri.port_u16().unwrap_or(match mode {
+            Mode::Plain => 80,
+            Mode::Tls => 443,
+        });
+
+        let addrs = tokio::net::lookup_host((domain.as_str(), port))
+            .await?
+            .collect::<Vec<_>>();
+        let resolved_at = Instant::now();
+        debug!("Resolved {domain} to {addrs:?}");
+
+        let socket = TcpStream::connect(addrs.as_slice()).await?;
+        socket.set_nodelay(!nagle)?;
+        let stream = match (mode, connector) {
+            (Mode::Plain, _) => MaybeTlsStream::Plain(socket),
+            (Mode::Tls, Connector::Rustls(tls_config)) => {
+                let server_name =
+                    ServerName::try_from(domain).map_err(|_| TlsError::InvalidDnsName)?;
+                let stream = TlsConnector::from(tls_config)
+                    .connect(server_name, socket)
+                    .await?;
+                MaybeTlsStream::Rustls(stream)
+            }
+            (Mode::Tls, _) => return Err(UrlError::TlsFeatureNotEnabled.into()),
+        };
+        let handshake_at = Instant::now();
+        debug!("Established stream");
+
+        let (stream, response) =
+            tokio_tungstenite::client_async_with_config(target, stream, Some(socket_config))
+                .await?;
+        let timing = ConnectTiming {
+            started_at,
+            resolved_at: Some(resolved_at),
+            handshake_at: Some(handshake_at),
+            connected_at: Instant::now(),
+        };
+        Ok((stream, response.into_parts().0.headers, timing))
+    }
+}
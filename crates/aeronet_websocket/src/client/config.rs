@@ -1,4 +1,5 @@
 use {
+    crate::session::FrameKind,
     alloc::sync::Arc,
     rustls::{
         RootCertStore,
@@ -20,6 +21,7 @@ pub struct ClientConfig {
     pub(crate) connector: Connector,
     pub(crate) socket: WebSocketConfig,
     pub(crate) nagle: bool,
+    pub(crate) frame_kind: FrameKind,
 }
 
 impl ClientConfig {
@@ -99,6 +101,7 @@ impl ClientConfigBuilder<WantsConnector> {
             connector,
             socket: WebSocketConfig::default(),
             nagle: true,
+            frame_kind: FrameKind::default(),
         }
     }
 }
@@ -122,6 +125,13 @@ impl ClientConfig {
     pub fn disable_nagle(self) -> Self {
         self.with_nagle(false)
     }
+
+    /// Sets what kind of WebSocket frame outgoing packets are sent as.
+    ///
+    /// By default, this is [`FrameKind::Binary`].
+    pub fn with_frame_kind(self, frame_kind: FrameKind) -> Self {
+        Self { frame_kind, ..self }
+    }
 }
 
 /// Helper function for creating a [`RootCertStore`] with
@@ -9,7 +9,7 @@ use {
     },
     aeronet_io::{
         IoSet, Session, SessionEndpoint,
-        connection::{DisconnectReason, Disconnected},
+        connection::{ConnectTiming, DisconnectReason, Disconnected},
     },
     bevy_app::prelude::*,
     bevy_ecs::{prelude::*, system::EntityCommand},
@@ -27,10 +27,24 @@ cfg_if::cfg_if! {
         type CreateSocketError = crate::JsError;
         type ConnectError = crate::JsError;
 
-        /// Dummy configuration type for [`WebSocketClient`], used to keep
-        /// parity between native and WASM APIs.
+        /// Configuration for a [`WebSocketClient`] using the browser's
+        /// WebSocket API.
         #[derive(Debug, Clone, Default)]
-        pub struct ClientConfig;
+        pub struct ClientConfig {
+            frame_kind: session::FrameKind,
+        }
+
+        impl ClientConfig {
+            /// Sets what kind of WebSocket frame outgoing packets are sent as.
+            ///
+            /// By default, this is [`FrameKind::Binary`].
+            ///
+            /// [`FrameKind::Binary`]: session::FrameKind::Binary
+            #[must_use]
+            pub fn with_frame_kind(self, frame_kind: session::FrameKind) -> Self {
+                Self { frame_kind }
+            }
+        }
     } else {
         mod config;
         pub use config::*;
@@ -140,6 +154,20 @@ fn connect(session: Entity, world: &mut World, config: ClientConfig, target: Con
         }));
 }
 
+/// Headers which the server sent in its response to the WebSocket handshake of
+/// a [`WebSocketClient`].
+///
+/// This is inserted alongside [`Session`] once the client connects, and can be
+/// used to adapt to capabilities which the server advertises (e.g. its
+/// version). A server using this crate can send these via
+/// `server::ServerConfig::with_response_header`.
+///
+/// This is not available on WASM, since the browser's WebSocket API does not
+/// expose the handshake response.
+#[cfg(not(target_family = "wasm"))]
+#[derive(Debug, Clone, Component)]
+pub struct ResponseHeaders(pub tungstenite::http::HeaderMap);
+
 /// [`WebSocketClient`] error.
 #[derive(Debug, Display, Error, From)]
 #[non_exhaustive]
@@ -173,10 +201,16 @@ enum ClientFrontend {
 
 #[derive(Debug)]
 struct ToConnected {
+    #[cfg(target_family = "wasm")]
+    started_at: Instant,
     #[cfg(not(target_family = "wasm"))]
     local_addr: core::net::SocketAddr,
     #[cfg(not(target_family = "wasm"))]
     peer_addr: core::net::SocketAddr,
+    #[cfg(not(target_family = "wasm"))]
+    response_headers: tungstenite::http::HeaderMap,
+    #[cfg(not(target_family = "wasm"))]
+    timing: ConnectTiming,
     frontend: SessionFrontend,
 }
 
@@ -212,17 +246,32 @@ fn poll_connecting(
         return ClientFrontend::Connecting { recv_dc, recv_next };
     };
 
+    let now = Instant::now();
+    // the browser doesn't expose any of the intermediate phases
+    #[cfg(target_family = "wasm")]
+    let timing = ConnectTiming {
+        started_at: next.started_at,
+        resolved_at: None,
+        handshake_at: None,
+        connected_at: now,
+    };
+    #[cfg(not(target_family = "wasm"))]
+    let timing = next.timing;
+
     commands.entity(session).insert((
         WebSocketIo {
             recv_packet_b2f: next.frontend.recv_packet_b2f,
             send_packet_f2b: next.frontend.send_packet_f2b,
             send_user_dc: Some(next.frontend.send_user_dc),
         },
-        Session::new(Instant::now(), MTU),
+        Session::new(now, MTU),
+        timing,
         #[cfg(not(target_family = "wasm"))]
         aeronet_io::connection::LocalAddr(next.local_addr),
         #[cfg(not(target_family = "wasm"))]
         aeronet_io::connection::PeerAddr(next.peer_addr),
+        #[cfg(not(target_family = "wasm"))]
+        ResponseHeaders(next.response_headers),
     ));
     ClientFrontend::Connected { recv_dc }
 }
@@ -239,7 +288,7 @@ fn should_disconnect(
     };
     dc_reason.map_or(false, |reason| {
         let reason = reason.map_err(anyhow::Error::new);
-        commands.trigger_targets(Disconnected { reason }, session);
+        commands.trigger_targets(Disconnected { reason, code: None }, session);
         true
     })
 }
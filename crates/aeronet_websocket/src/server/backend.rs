@@ -1,35 +1,52 @@
 use {
-    super::{ServerConfig, ServerError, ToConnected, ToOpen},
-    crate::{server::ToConnecting, session::SessionError},
-    aeronet_io::connection::DisconnectReason,
+    super::{AcceptRateLimit, RateLimitOverflow, ServerConfig, ServerError, ToConnected, ToOpen},
+    crate::{
+        server::ToConnecting,
+        session::{FrameKind, SessionError},
+    },
+    aeronet_io::{
+        connection::{ConnectTiming, DisconnectReason},
+        server::{PeerRateLimit, PeerRateLimiter},
+    },
+    alloc::collections::VecDeque,
     bevy_ecs::prelude::*,
     core::{
         net::SocketAddr,
         pin::Pin,
         task::{Context, Poll},
+        time::Duration,
     },
     futures::{
         SinkExt,
         channel::{mpsc, oneshot},
         never::Never,
     },
+    socket2::{Domain, Protocol, Socket, Type},
+    std::io,
     tokio::{
         io::{AsyncRead, AsyncWrite, ReadBuf},
         net::{TcpListener, TcpStream},
     },
     tokio_rustls::TlsAcceptor,
-    tokio_tungstenite::tungstenite::protocol::WebSocketConfig,
-    tracing::{Instrument, debug, debug_span},
+    tokio_tungstenite::tungstenite::{
+        handshake::server::{Request, Response},
+        http::HeaderMap,
+        protocol::WebSocketConfig,
+    },
+    tracing::{Instrument, debug, debug_span, trace},
+    web_time::Instant,
 };
 
 pub async fn start(
     config: ServerConfig,
+    peer_rate_limit: Option<PeerRateLimit>,
+    accept_rate_limit: Option<AcceptRateLimit>,
     send_next: oneshot::Sender<ToOpen>,
 ) -> Result<Never, ServerError> {
+    let listener = bind(&config).map_err(ServerError::BindSocket)?;
     let tls_acceptor = config.tls.map(TlsAcceptor::from);
-    let listener = TcpListener::bind(config.bind_address)
-        .await
-        .map_err(ServerError::BindSocket)?;
+    let mut peer_rate_limiter = peer_rate_limit.map(PeerRateLimiter::new);
+    let mut accept_rate_limiter = accept_rate_limit.map(AcceptRateLimiter::new);
     debug!("Listening on {}", config.bind_address);
 
     let (send_connecting, recv_connecting) = mpsc::channel::<ToConnecting>(1);
@@ -49,14 +66,30 @@ pub async fn start(
             .accept()
             .await
             .map_err(ServerError::AcceptConnection)?;
+        if let Some(limiter) = &mut peer_rate_limiter {
+            if !limiter.try_acquire(peer_addr.ip(), Instant::now()) {
+                trace!("Dropping connection from {peer_addr} over peer rate limit");
+                drop(stream);
+                continue;
+            }
+        }
+        if let Some(limiter) = &mut accept_rate_limiter {
+            if !limiter.acquire(peer_addr).await {
+                drop(stream);
+                continue;
+            }
+        }
         tokio::spawn({
             let send_connecting = send_connecting.clone();
             let tls_acceptor = tls_acceptor.clone();
+            let response_headers = config.response_headers.clone();
             async move {
                 if let Err(err) = accept_session(
                     stream,
                     peer_addr,
                     config.socket,
+                    config.frame_kind,
+                    response_headers,
                     tls_acceptor,
                     send_connecting,
                 )
@@ -69,13 +102,98 @@ pub async fn start(
     }
 }
 
+/// Enforces an [`AcceptRateLimit`] over a sliding window.
+#[derive(Debug)]
+struct AcceptRateLimiter {
+    limit: AcceptRateLimit,
+    recent: VecDeque<Instant>,
+}
+
+impl AcceptRateLimiter {
+    const fn new(limit: AcceptRateLimit) -> Self {
+        Self {
+            limit,
+            recent: VecDeque::new(),
+        }
+    }
+
+    /// Records a connection from `peer_addr`, waiting until the limit allows it
+    /// if it is over the limit and should be queued.
+    ///
+    /// Returns `false` if the connection should be dropped.
+    async fn acquire(&mut self, peer_addr: SocketAddr) -> bool {
+        loop {
+            let wait = match self.try_acquire(Instant::now()) {
+                Ok(()) => return true,
+                Err(wait) => wait,
+            };
+            match self.limit.overflow {
+                RateLimitOverflow::Queue => {
+                    trace!(
+                        "Delaying connection from {peer_addr} by {wait:?} over accept rate limit"
+                    );
+                    tokio::time::sleep(wait).await;
+                }
+                RateLimitOverflow::Reject => {
+                    trace!("Dropping connection from {peer_addr} over accept rate limit");
+                    return false;
+                }
+            }
+        }
+    }
+
+    /// Records a connection at `now` if the limit allows it, otherwise returns
+    /// how long until it will allow one.
+    fn try_acquire(&mut self, now: Instant) -> Result<(), Duration> {
+        let interval = self.limit.interval;
+        while self
+            .recent
+            .front()
+            .is_some_and(|at| now.saturating_duration_since(*at) >= interval)
+        {
+            self.recent.pop_front();
+        }
+
+        if self.recent.len() < self.limit.max_connections {
+            self.recent.push_back(now);
+            return Ok(());
+        }
+        let wait = self.recent.front().map_or(interval, |oldest| {
+            interval.saturating_sub(now.saturating_duration_since(*oldest))
+        });
+        Err(wait)
+    }
+}
+
+fn bind(config: &ServerConfig) -> io::Result<TcpListener> {
+    // same options as `TcpListener::bind`
+    let socket = Socket::new(
+        Domain::for_address(config.bind_address),
+        Type::STREAM,
+        Some(Protocol::TCP),
+    )?;
+    socket.set_nonblocking(true)?;
+    #[cfg(not(windows))]
+    socket.set_reuse_address(true)?;
+    #[cfg(any(target_os = "android", target_os = "fuchsia", target_os = "linux"))]
+    if let Some(device) = &config.bind_device {
+        socket.bind_device(Some(device.as_bytes()))?;
+    }
+    socket.bind(&config.bind_address.into())?;
+    socket.listen(1024)?;
+    TcpListener::from_std(socket.into())
+}
+
 async fn accept_session(
     stream: TcpStream,
     peer_addr: SocketAddr,
     socket_config: WebSocketConfig,
+    frame_kind: FrameKind,
+    response_headers: HeaderMap,
     tls_acceptor: Option<TlsAcceptor>,
     mut send_connecting: mpsc::Sender<ToConnecting>,
 ) -> Result<(), DisconnectReason<ServerError>> {
+    let started_at = Instant::now();
     let (send_session_entity, recv_session_entity) = oneshot::channel::<Entity>();
     let (send_dc, recv_dc) = oneshot::channel::<DisconnectReason<ServerError>>();
     let (send_next, recv_next) = oneshot::channel::<ToConnected>();
@@ -94,17 +212,29 @@ async fn accept_session(
         .map_err(|_| SessionError::FrontendClosed)
         .map_err(ServerError::Session)?;
 
-    let Err(dc_reason) = handle_session(stream, peer_addr, socket_config, tls_acceptor, send_next)
-        .instrument(debug_span!("session", %session))
-        .await;
+    let Err(dc_reason) = handle_session(
+        stream,
+        started_at,
+        peer_addr,
+        socket_config,
+        frame_kind,
+        response_headers,
+        tls_acceptor,
+        send_next,
+    )
+    .instrument(debug_span!("session", %session))
+    .await;
     _ = send_dc.send(dc_reason);
     Ok(())
 }
 
 async fn handle_session(
     stream: TcpStream,
+    started_at: Instant,
     peer_addr: SocketAddr,
     socket_config: WebSocketConfig,
+    frame_kind: FrameKind,
+    response_headers: HeaderMap,
     tls_acceptor: Option<TlsAcceptor>,
     send_next: oneshot::Sender<ToConnected>,
 ) -> Result<Never, DisconnectReason<ServerError>> {
@@ -117,14 +247,26 @@ async fn handle_session(
     } else {
         MaybeTlsStream::Plain(stream)
     };
-    // TODO accept hdr: find some way to pass control of headers over to user
-    let stream = tokio_tungstenite::accept_async_with_config(stream, Some(socket_config))
-        .await
-        .map_err(ServerError::AcceptClient)?;
+    let handshake_at = Instant::now();
+    let add_headers = |_: &Request, mut response: Response| {
+        response.headers_mut().extend(response_headers);
+        Ok(response)
+    };
+    let stream =
+        tokio_tungstenite::accept_hdr_async_with_config(stream, add_headers, Some(socket_config))
+            .await
+            .map_err(ServerError::AcceptClient)?;
+    let timing = ConnectTiming {
+        started_at,
+        resolved_at: None,
+        handshake_at: Some(handshake_at),
+        connected_at: Instant::now(),
+    };
 
-    let (frontend, backend) = crate::session::backend::native::split(stream);
+    let (frontend, backend) = crate::session::backend::native::split(stream, frame_kind);
     let connected = ToConnected {
         peer_addr,
+        timing,
         frontend,
     };
     debug!("Connected");
@@ -190,3 +332,38 @@ impl<S: AsyncRead + AsyncWrite + Unpin> AsyncWrite for MaybeTlsStream<S> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accept_rate_window() {
+        const INTERVAL: Duration = Duration::from_millis(100);
+
+        let mut limiter = AcceptRateLimiter::new(AcceptRateLimit::new(2, INTERVAL));
+        let start = Instant::now();
+        assert_eq!(Ok(()), limiter.try_acquire(start));
+        assert_eq!(
+            Ok(()),
+            limiter.try_acquire(start + Duration::from_millis(50))
+        );
+        assert_eq!(
+            Err(Duration::from_millis(40)),
+            limiter.try_acquire(start + Duration::from_millis(60))
+        );
+
+        // the first connection leaves the window
+        assert_eq!(Ok(()), limiter.try_acquire(start + INTERVAL));
+        assert_eq!(
+            Err(Duration::from_millis(50)),
+            limiter.try_acquire(start + INTERVAL)
+        );
+
+        // the second connection leaves the window
+        assert_eq!(
+            Ok(()),
+            limiter.try_acquire(start + Duration::from_millis(150))
+        );
+    }
+}
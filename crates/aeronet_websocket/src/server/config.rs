@@ -2,11 +2,15 @@
 // https://github.com/BiagioFesta/wtransport/blob/bf3a5401c2b3671e6611bd093d7666f4660b2119/wtransport/src/tls.rs
 
 use {
+    crate::session::FrameKind,
     alloc::sync::Arc,
     core::net::{Ipv6Addr, SocketAddr},
     derive_more::{Display, Error},
     rustls::pki_types::{CertificateDer, PrivateKeyDer},
-    tokio_tungstenite::tungstenite::protocol::WebSocketConfig,
+    tokio_tungstenite::tungstenite::{
+        http::{HeaderMap, HeaderName, HeaderValue},
+        protocol::WebSocketConfig,
+    },
 };
 
 /// Configuration for a [`WebSocketServer`].
@@ -19,8 +23,12 @@ use {
 #[must_use]
 pub struct ServerConfig {
     pub(crate) bind_address: SocketAddr,
+    #[cfg(any(target_os = "android", target_os = "fuchsia", target_os = "linux"))]
+    pub(crate) bind_device: Option<String>,
     pub(crate) tls: Option<Arc<rustls::ServerConfig>>,
     pub(crate) socket: WebSocketConfig,
+    pub(crate) frame_kind: FrameKind,
+    pub(crate) response_headers: HeaderMap,
 }
 
 impl ServerConfig {
@@ -55,6 +63,12 @@ impl ServerConfigBuilder<WantsBindAddress> {
     }
 
     /// Configures this to listen on the given socket address.
+    ///
+    /// On a machine with multiple network interfaces, you can pass the IP
+    /// address of a specific interface to only listen on that interface. The
+    /// server's [`LocalAddr`] will reflect this address.
+    ///
+    /// [`LocalAddr`]: aeronet_io::connection::LocalAddr
     pub const fn with_bind_address(
         self,
         bind_address: SocketAddr,
@@ -103,8 +117,12 @@ impl ServerConfigBuilder<WantsTlsConfig> {
     fn with_tls(self, tls: Option<Arc<rustls::ServerConfig>>) -> ServerConfig {
         ServerConfig {
             bind_address: self.0.bind_address,
+            #[cfg(any(target_os = "android", target_os = "fuchsia", target_os = "linux"))]
+            bind_device: None,
             tls,
             socket: WebSocketConfig::default(),
+            frame_kind: FrameKind::default(),
+            response_headers: HeaderMap::new(),
         }
     }
 }
@@ -114,6 +132,44 @@ impl ServerConfig {
     pub fn with_socket_config(self, socket: WebSocketConfig) -> Self {
         Self { socket, ..self }
     }
+
+    /// Sets what kind of WebSocket frame outgoing packets are sent as.
+    ///
+    /// By default, this is [`FrameKind::Binary`].
+    pub fn with_frame_kind(self, frame_kind: FrameKind) -> Self {
+        Self { frame_kind, ..self }
+    }
+
+    /// Adds a header which is sent to every client in the response to its
+    /// WebSocket handshake.
+    ///
+    /// Native clients using this crate can read these via the
+    /// `client::ResponseHeaders` component, e.g. to find out what version of
+    /// your protocol this server speaks.
+    pub fn with_response_header(mut self, name: HeaderName, value: HeaderValue) -> Self {
+        self.response_headers.append(name, value);
+        self
+    }
+
+    /// Configures this to only listen on the network interface with the given
+    /// name, such as `eth0`.
+    ///
+    /// This binds the listen socket to the interface using `SO_BINDTODEVICE`,
+    /// so only connections arriving on this interface are accepted, even if
+    /// the bind address is [`Ipv6Addr::UNSPECIFIED`]. Depending on the OS and
+    /// its version, this may require elevated privileges (e.g.
+    /// `CAP_NET_RAW`) - if it fails, the server will fail to open with
+    /// [`ServerError::BindSocket`].
+    ///
+    /// [`Ipv6Addr::UNSPECIFIED`]: core::net::Ipv6Addr::UNSPECIFIED
+    /// [`ServerError::BindSocket`]: crate::server::ServerError::BindSocket
+    #[cfg(any(target_os = "android", target_os = "fuchsia", target_os = "linux"))]
+    pub fn with_bind_device(self, interface: impl Into<String>) -> Self {
+        Self {
+            bind_device: Some(interface.into()),
+            ..self
+        }
+    }
 }
 
 /// Single pair of certificate chain and private key used for configuring a
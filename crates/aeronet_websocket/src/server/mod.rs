@@ -12,13 +12,13 @@ use {
     },
     aeronet_io::{
         IoSet, SessionEndpoint,
-        connection::{DisconnectReason, Disconnected, LocalAddr, PeerAddr},
-        server::{CloseReason, Closed, Server, ServerEndpoint},
+        connection::{ConnectTiming, DisconnectReason, Disconnected, LocalAddr, PeerAddr},
+        server::{CloseReason, Closed, PeerRateLimit, Server, ServerEndpoint},
     },
     bevy_app::prelude::*,
     bevy_ecs::{prelude::*, system::EntityCommand},
     bevy_hierarchy::BuildChildren,
-    core::net::SocketAddr,
+    core::{net::SocketAddr, time::Duration},
     derive_more::{Display, Error, derive::From},
     futures::channel::{mpsc, oneshot},
     std::io,
@@ -57,6 +57,16 @@ impl WebSocketServer {
     /// Creates an [`EntityCommand`] to set up a server and have it start
     /// listening for connections.
     ///
+    /// To limit how many connection attempts are accepted from a single IP
+    /// address before the TLS or WebSocket handshake, insert a
+    /// [`PeerRateLimit`] on the server entity before this command is applied.
+    /// Attempts over the limit are refused without spawning a session entity
+    /// for them.
+    ///
+    /// To limit how quickly the server accepts new connections from all
+    /// clients combined, insert an [`AcceptRateLimit`] on the server entity
+    /// before this command is applied.
+    ///
     /// # Examples
     ///
     /// ```
@@ -91,12 +101,14 @@ impl WebSocketServer {
 
 fn open(server: Entity, world: &mut World, config: ServerConfig) {
     let runtime = world.resource::<WebSocketRuntime>().clone();
-
+    let peer_rate_limit = world.get::<PeerRateLimit>(server).copied();
+    let accept_rate_limit = world.get::<AcceptRateLimit>(server).copied();
     let (send_closed, recv_closed) = oneshot::channel::<CloseReason<ServerError>>();
     let (send_next, recv_next) = oneshot::channel::<ToOpen>();
     runtime.spawn_on_self(
         async move {
-            let Err(err) = backend::start(config, send_next).await;
+            let Err(err) =
+                backend::start(config, peer_rate_limit, accept_rate_limit, send_next).await;
             _ = send_closed.send(CloseReason::Error(err));
         }
         .instrument(debug_span!("server", %server)),
@@ -110,6 +122,91 @@ fn open(server: Entity, world: &mut World, config: ServerConfig) {
         }));
 }
 
+/// Limits how quickly a [`WebSocketServer`] accepts new connections, to
+/// mitigate floods of connection attempts.
+///
+/// Only up to [`AcceptRateLimit::max_connections`] connections are accepted in
+/// any window of [`AcceptRateLimit::interval`]. Connections beyond that are
+/// handled according to [`AcceptRateLimit::overflow`]. The limit applies to
+/// all clients combined, and is independent of how many clients are connected
+/// to the server - it only limits how quickly new clients can start
+/// connecting. Use [`PeerRateLimit`] to limit connection attempts from a
+/// single IP address.
+///
+/// To use this, insert it on a server entity before opening the server. The
+/// limit is read once when the server starts opening, so changing or removing
+/// this component afterwards has no effect.
+///
+/// # Examples
+///
+/// Accept at most 10 connections per second, delaying the rest:
+///
+/// ```
+/// use {
+///     aeronet_websocket::server::{AcceptRateLimit, RateLimitOverflow},
+///     bevy_ecs::prelude::*,
+///     core::time::Duration,
+/// };
+///
+/// # fn run(mut commands: Commands, server: Entity) {
+/// commands
+///     .entity(server)
+///     .insert(AcceptRateLimit::new(10, Duration::from_secs(1)));
+///
+/// // or drop the rest instead
+/// commands.entity(server).insert(
+///     AcceptRateLimit::new(10, Duration::from_secs(1)).with_overflow(RateLimitOverflow::Reject),
+/// );
+/// # }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Component)]
+pub struct AcceptRateLimit {
+    /// Maximum number of connections accepted within any
+    /// [`AcceptRateLimit::interval`].
+    pub max_connections: usize,
+    /// Length of the window which [`AcceptRateLimit::max_connections`] applies
+    /// to.
+    pub interval: Duration,
+    /// What to do with connections which go over the limit.
+    ///
+    /// Defaults to [`RateLimitOverflow::Queue`].
+    pub overflow: RateLimitOverflow,
+}
+
+/// What [`AcceptRateLimit`] does with connections which go over the limit.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum RateLimitOverflow {
+    /// Stop accepting connections until the rate limit allows it.
+    ///
+    /// Connections are accepted in the order they arrived, and wait in the OS
+    /// listen backlog until then. [`PeerRateLimit`] is also only checked once
+    /// a connection is accepted.
+    #[default]
+    Queue,
+    /// Drop the connection immediately, before any handshake is performed.
+    Reject,
+}
+
+impl AcceptRateLimit {
+    /// Creates an [`AcceptRateLimit`] which accepts up to `max_connections`
+    /// connections in any window of `interval`.
+    #[must_use]
+    pub const fn new(max_connections: usize, interval: Duration) -> Self {
+        Self {
+            max_connections,
+            interval,
+            overflow: RateLimitOverflow::Queue,
+        }
+    }
+
+    /// Sets the [`AcceptRateLimit::overflow`] behavior.
+    #[must_use]
+    pub const fn with_overflow(mut self, overflow: RateLimitOverflow) -> Self {
+        self.overflow = overflow;
+        self
+    }
+}
+
 /// [`WebSocketServer`] error.
 #[derive(Debug, Display, Error, From)]
 #[non_exhaustive]
@@ -174,6 +271,7 @@ struct ToConnecting {
 #[derive(Debug)]
 struct ToConnected {
     peer_addr: SocketAddr,
+    timing: ConnectTiming,
     frontend: SessionFrontend,
 }
 
@@ -309,6 +407,7 @@ fn poll_connecting(
             send_user_dc: Some(next.frontend.send_user_dc),
         },
         PeerAddr(next.peer_addr),
+        next.timing,
     ));
     ClientFrontend::Connected { recv_dc }
 }
@@ -325,7 +424,32 @@ fn should_disconnect(
     };
     dc_reason.map_or(false, |reason| {
         let reason = reason.map_err(anyhow::Error::new);
-        commands.trigger_targets(Disconnected { reason }, client);
+        commands.trigger_targets(Disconnected { reason, code: None }, client);
         true
     })
 }
+
+#[cfg(test)]
+mod tests {
+    #![expect(clippy::unwrap_used, reason = "testing")]
+
+    use {
+        super::*,
+        core::net::{IpAddr, Ipv4Addr},
+    };
+
+    #[test]
+    fn local_addr_honors_bind_address() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let config = ServerConfig::builder()
+            .with_bind_address(SocketAddr::new(Ipv4Addr::LOCALHOST.into(), 0))
+            .with_no_encryption();
+
+        let (send_next, recv_next) = oneshot::channel();
+        runtime.spawn(backend::start(config, None, None, send_next));
+        let next = runtime.block_on(recv_next).unwrap();
+
+        assert_eq!(IpAddr::from(Ipv4Addr::LOCALHOST), next.local_addr.ip());
+        assert_ne!(0, next.local_addr.port());
+    }
+}
@@ -1,11 +1,57 @@
+use {
+    crate::session::FrameKind,
+    base64::{Engine, engine::general_purpose::STANDARD as BASE64},
+    tracing::{debug, warn},
+};
+
+/// Turns incoming text frames back into packets.
+///
+/// Text frames are only accepted if we use [`FrameKind::Text`] ourselves, since
+/// otherwise the peer has no reason to send them. Every text frame which is
+/// rejected, or is not valid base64, is counted and dropped.
+#[derive(Debug)]
+struct TextFrames {
+    frame_kind: FrameKind,
+    rejected: usize,
+}
+
+impl TextFrames {
+    const fn new(frame_kind: FrameKind) -> Self {
+        Self {
+            frame_kind,
+            rejected: 0,
+        }
+    }
+
+    fn decode(&mut self, text: &str) -> Option<Vec<u8>> {
+        let err = match self.frame_kind {
+            FrameKind::Text => match BASE64.decode(text) {
+                Ok(packet) => return Some(packet),
+                Err(err) => format!("text frame is not valid base64: {err:?}"),
+            },
+            FrameKind::Binary => "received text frame, but text frames are not enabled".into(),
+        };
+
+        self.rejected += 1;
+        if self.rejected == 1 {
+            warn!("Dropping invalid frame: {err}");
+        } else {
+            debug!("Dropping invalid frame ({} so far): {err}", self.rejected);
+        }
+        None
+    }
+}
+
 #[cfg(target_family = "wasm")]
 pub mod wasm {
     use {
+        super::TextFrames,
         crate::{
             JsError,
-            session::{SessionError, SessionFrontend},
+            session::{FrameKind, SessionError, SessionFrontend},
         },
         aeronet_io::{connection::DisconnectReason, packet::RecvPacket},
+        base64::{Engine, engine::general_purpose::STANDARD as BASE64},
         bytes::Bytes,
         futures::{
             SinkExt, StreamExt,
@@ -28,7 +74,7 @@ pub mod wasm {
     // https://www.rfc-editor.org/rfc/rfc6455.html#section-7.4.1
     const NORMAL_CLOSE_CODE: u16 = 1000;
 
-    pub fn split(socket: WebSocket) -> (SessionFrontend, SessionBackend) {
+    pub fn split(socket: WebSocket, frame_kind: FrameKind) -> (SessionFrontend, SessionBackend) {
         socket.set_binary_type(BinaryType::Arraybuffer);
 
         let (send_packet_b2f, recv_packet_b2f) = mpsc::unbounded::<RecvPacket>();
@@ -44,17 +90,24 @@ pub mod wasm {
             let mut send_dc_reason = send_dc_reason.clone();
             || {
                 wasm_bindgen_futures::spawn_local(async move {
-                    let Err(err) = send_loop(socket, recv_packet_f2b, recv_dropped).await;
+                    let Err(err) =
+                        send_loop(socket, frame_kind, recv_packet_f2b, recv_dropped).await;
                     _ = send_dc_reason.send(err.into());
                 });
             }
         });
 
+        let mut text_frames = TextFrames::new(frame_kind);
         let on_message = Closure::<dyn FnMut(_)>::new(move |event: MessageEvent| {
             let data = event.data();
-            let packet = data
-                .as_string()
-                .map_or_else(|| Uint8Array::new(&data).to_vec(), String::into_bytes);
+            let packet = if let Some(text) = data.as_string() {
+                let Some(packet) = text_frames.decode(&text) else {
+                    return;
+                };
+                packet
+            } else {
+                Uint8Array::new(&data).to_vec()
+            };
             let packet = Bytes::from(packet);
             let now = Instant::now();
 
@@ -116,6 +169,7 @@ pub mod wasm {
 
     async fn send_loop(
         socket: WebSocket,
+        frame_kind: FrameKind,
         mut recv_packet_f2b: mpsc::UnboundedReceiver<Bytes>,
         mut recv_dropped: oneshot::Receiver<()>,
     ) -> Result<Never, SessionError> {
@@ -126,10 +180,12 @@ pub mod wasm {
             }
             .ok_or(SessionError::FrontendClosed)?;
 
-            socket
-                .send_with_u8_array(&packet)
-                .map_err(JsError::from)
-                .map_err(SessionError::Send)?;
+            match frame_kind {
+                FrameKind::Binary => socket.send_with_u8_array(&packet),
+                FrameKind::Text => socket.send_with_str(&BASE64.encode(packet)),
+            }
+            .map_err(JsError::from)
+            .map_err(SessionError::Send)?;
         }
     }
 
@@ -159,9 +215,11 @@ pub mod wasm {
 #[cfg(not(target_family = "wasm"))]
 pub mod native {
     use {
-        crate::session::{SessionError, SessionFrontend},
+        super::TextFrames,
+        crate::session::{FrameKind, SessionError, SessionFrontend},
         aeronet_io::{connection::DisconnectReason, packet::RecvPacket},
         alloc::borrow::Cow,
+        base64::{Engine, engine::general_purpose::STANDARD as BASE64},
         bytes::Bytes,
         futures::{
             SinkExt, StreamExt,
@@ -182,6 +240,7 @@ pub mod native {
     #[derive(Debug)]
     pub struct SessionBackend<S> {
         stream: WebSocketStream<S>,
+        frame_kind: FrameKind,
         send_packet_b2f: mpsc::UnboundedSender<RecvPacket>,
         recv_packet_f2b: mpsc::UnboundedReceiver<Bytes>,
         recv_user_dc: oneshot::Receiver<String>,
@@ -189,6 +248,7 @@ pub mod native {
 
     pub fn split<S: AsyncRead + AsyncWrite + Unpin>(
         stream: WebSocketStream<S>,
+        frame_kind: FrameKind,
     ) -> (SessionFrontend, SessionBackend<S>) {
         let (send_packet_b2f, recv_packet_b2f) = mpsc::unbounded::<RecvPacket>();
         let (send_packet_f2b, recv_packet_f2b) = mpsc::unbounded::<Bytes>();
@@ -202,6 +262,7 @@ pub mod native {
             },
             SessionBackend {
                 stream,
+                frame_kind,
                 send_packet_b2f,
                 recv_packet_f2b,
                 recv_user_dc,
@@ -213,25 +274,32 @@ pub mod native {
         pub async fn start(self) -> Result<Never, DisconnectReason<SessionError>> {
             let Self {
                 mut stream,
+                frame_kind,
                 send_packet_b2f,
                 mut recv_packet_f2b,
                 mut recv_user_dc,
             } = self;
 
+            let mut text_frames = TextFrames::new(frame_kind);
             loop {
                 futures::select! {
                     msg = stream.next() => {
                         let msg = msg
                             .ok_or(SessionError::RecvStreamClosed)?
                             .map_err(SessionError::Connection)?;
-                        Self::recv(&send_packet_b2f, msg)?;
+                        Self::recv(&send_packet_b2f, &mut text_frames, msg)?;
                     }
                     packet = recv_packet_f2b.next() => {
                         let packet = packet.ok_or(SessionError::FrontendClosed)?;
-                        Self::send(&mut stream, packet).await?;
+                        Self::send(&mut stream, frame_kind, packet).await?;
                     }
                     reason = recv_user_dc => {
                         let reason = reason.map_err(|_| SessionError::FrontendClosed)?;
+                        // send out packets which were flushed before the user
+                        // disconnected, so they arrive before the close frame
+                        while let Ok(Some(packet)) = recv_packet_f2b.try_next() {
+                            Self::send(&mut stream, frame_kind, packet).await?;
+                        }
                         Self::close(&mut stream, reason.clone()).await?;
                         return Err(DisconnectReason::User(reason));
                     }
@@ -241,6 +309,7 @@ pub mod native {
 
         fn recv(
             send_packet_b2f: &mpsc::UnboundedSender<RecvPacket>,
+            text_frames: &mut TextFrames,
             msg: Message,
         ) -> Result<(), DisconnectReason<SessionError>> {
             let packet = match msg {
@@ -250,6 +319,10 @@ pub mod native {
                 Message::Close(Some(frame)) => {
                     return Err(DisconnectReason::Peer(frame.reason.into_owned()));
                 }
+                Message::Text(text) => match text_frames.decode(&text) {
+                    Some(packet) => Bytes::from(packet),
+                    None => return Ok(()),
+                },
                 msg => Bytes::from(msg.into_data()),
             };
             let now = Instant::now();
@@ -265,9 +338,13 @@ pub mod native {
 
         async fn send(
             stream: &mut WebSocketStream<S>,
+            frame_kind: FrameKind,
             packet: Bytes,
         ) -> Result<(), DisconnectReason<SessionError>> {
-            let msg = Message::binary(packet);
+            let msg = match frame_kind {
+                FrameKind::Binary => Message::binary(packet),
+                FrameKind::Text => Message::text(BASE64.encode(packet)),
+            };
             stream.send(msg).await.map_err(SessionError::Connection)?;
             Ok(())
         }
@@ -8,7 +8,7 @@ use {
     crate::WebSocketRuntime,
     aeronet_io::{
         AeronetIoPlugin, IoSet, Session,
-        connection::{DROP_DISCONNECT_REASON, Disconnect},
+        connection::{DROP_DISCONNECT_REASON, Disconnect, DisconnectedRetained},
         packet::{IP_MTU, RecvPacket},
     },
     bevy_app::prelude::*,
@@ -139,6 +139,32 @@ impl Drop for WebSocketIo {
 ///   - <https://en.wikipedia.org/wiki/WebSocket#Frame_structure>
 pub const MTU: usize = IP_MTU - 60 - 40 - 14;
 
+/// What kind of WebSocket frame outgoing packets are sent as.
+///
+/// A [`WebSocketIo`] always accepts packets in binary frames, but only accepts
+/// text frames if it uses [`FrameKind::Text`] itself, so if one side sends
+/// text frames, the other side must use text frames too. Text frames which are
+/// not accepted, or which are not valid base64, are dropped and logged.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum FrameKind {
+    /// Packets are sent as-is in binary frames.
+    ///
+    /// This is the most efficient option, and should be used unless you have
+    /// a specific reason to use [`FrameKind::Text`].
+    #[default]
+    Binary,
+    /// Packets are [base64]-encoded and sent in text frames.
+    ///
+    /// Some proxies and middleboxes handle text frames differently to binary
+    /// frames, or some peers may only accept text frames. However, base64
+    /// encoding makes every packet take up 4/3 as many bytes on the wire
+    /// (rounded up to a multiple of 4), and costs some CPU time to encode and
+    /// decode. The [`Session::mtu`] does not account for this overhead.
+    ///
+    /// [base64]: https://en.wikipedia.org/wiki/Base64
+    Text,
+}
+
 #[derive(Debug)]
 pub(crate) struct SessionFrontend {
     pub recv_packet_b2f: mpsc::UnboundedReceiver<RecvPacket>,
@@ -148,7 +174,7 @@ pub(crate) struct SessionFrontend {
 
 fn on_disconnect(trigger: Trigger<Disconnect>, mut sessions: Query<&mut WebSocketIo>) {
     let session = trigger.entity();
-    let Disconnect { reason } = trigger.event();
+    let Disconnect { reason, .. } = trigger.event();
     let Ok(mut io) = sessions.get_mut(session) else {
         return;
     };
@@ -158,7 +184,9 @@ fn on_disconnect(trigger: Trigger<Disconnect>, mut sessions: Query<&mut WebSocke
     }
 }
 
-pub(crate) fn poll(mut sessions: Query<(Entity, &mut Session, &mut WebSocketIo)>) {
+pub(crate) fn poll(
+    mut sessions: Query<(Entity, &mut Session, &mut WebSocketIo), Without<DisconnectedRetained>>,
+) {
     for (entity, mut session, mut io) in &mut sessions {
         let span = trace_span!("poll", %entity);
         let _span = span.enter();
@@ -183,7 +211,7 @@ pub(crate) fn poll(mut sessions: Query<(Entity, &mut Session, &mut WebSocketIo)>
     }
 }
 
-fn flush(mut sessions: Query<(Entity, &mut Session, &WebSocketIo)>) {
+fn flush(mut sessions: Query<(Entity, &mut Session, &WebSocketIo), Without<DisconnectedRetained>>) {
     for (entity, mut session, io) in &mut sessions {
         let span = trace_span!("flush", %entity);
         let _span = span.enter();
@@ -0,0 +1,121 @@
+#![expect(missing_docs, clippy::unwrap_used, reason = "testing")]
+
+mod common;
+
+use {
+    aeronet_io::{
+        Session,
+        connection::{DisconnectReason, Disconnected, LocalAddr},
+    },
+    aeronet_websocket::{
+        client::{ClientConfig, WebSocketClient, WebSocketClientPlugin},
+        server::{
+            AcceptRateLimit, RateLimitOverflow, ServerConfig, WebSocketServer,
+            WebSocketServerPlugin,
+        },
+    },
+    bevy_app::prelude::*,
+    bevy_ecs::prelude::*,
+    common::update_until,
+    core::{
+        net::{Ipv4Addr, SocketAddr},
+        time::Duration,
+    },
+    web_time::Instant,
+};
+
+const NUM_CLIENTS: usize = 3;
+
+#[derive(Debug, Default, Resource)]
+struct Connected(Vec<Instant>);
+
+#[derive(Debug, Default, Resource)]
+struct Rejected(usize);
+
+/// Opens a server with `limit`, and starts connecting [`NUM_CLIENTS`] clients
+/// to it all at once.
+fn setup(limit: AcceptRateLimit) -> (App, Vec<Entity>) {
+    let mut app = App::new();
+    app.add_plugins((WebSocketClientPlugin, WebSocketServerPlugin))
+        .init_resource::<Connected>()
+        .init_resource::<Rejected>();
+
+    let server_config = ServerConfig::builder()
+        .with_bind_address(SocketAddr::new(Ipv4Addr::LOCALHOST.into(), 0))
+        .with_no_encryption();
+    let server = app
+        .world_mut()
+        .commands()
+        .spawn(limit)
+        .queue(WebSocketServer::open(server_config))
+        .id();
+    app.world_mut().flush();
+    let server_port = update_until(&mut app, |world| {
+        world.get::<LocalAddr>(server).map(|a| a.0.port())
+    });
+
+    let clients = (0..NUM_CLIENTS)
+        .map(|_| {
+            app.world_mut()
+                .commands()
+                .spawn_empty()
+                .queue(WebSocketClient::connect(
+                    ClientConfig::builder().with_no_encryption(),
+                    format!("ws://127.0.0.1:{server_port}"),
+                ))
+                .observe(
+                    |_: Trigger<OnAdd, Session>, mut connected: ResMut<Connected>| {
+                        connected.0.push(Instant::now());
+                    },
+                )
+                .observe(
+                    |trigger: Trigger<Disconnected>, mut rejected: ResMut<Rejected>| {
+                        assert!(matches!(trigger.event().reason, DisconnectReason::Error(_)));
+                        rejected.0 += 1;
+                    },
+                )
+                .id()
+        })
+        .collect::<Vec<_>>();
+    app.world_mut().flush();
+    (app, clients)
+}
+
+#[test]
+fn queue_over_limit() {
+    const INTERVAL: Duration = Duration::from_millis(300);
+    // allow for the time between the limiter letting a connection through,
+    // and the client noticing that it is connected
+    const TOLERANCE: Duration = Duration::from_millis(50);
+
+    let (mut app, clients) = setup(AcceptRateLimit::new(1, INTERVAL));
+    update_until(&mut app, |world| {
+        clients
+            .iter()
+            .all(|client| world.get::<Session>(*client).is_some())
+            .then_some(())
+    });
+
+    let connected = &app.world().resource::<Connected>().0;
+    assert_eq!(NUM_CLIENTS, connected.len());
+    for pair in connected.windows(2) {
+        let gap = pair[1].saturating_duration_since(pair[0]);
+        assert!(
+            gap + TOLERANCE >= INTERVAL,
+            "connections only {gap:?} apart"
+        );
+    }
+}
+
+#[test]
+fn reject_over_limit() {
+    let (mut app, _) = setup(
+        AcceptRateLimit::new(1, Duration::from_secs(60)).with_overflow(RateLimitOverflow::Reject),
+    );
+    update_until(&mut app, |world| {
+        (world.resource::<Rejected>().0 == NUM_CLIENTS - 1).then_some(())
+    });
+    update_until(&mut app, |world| {
+        (world.resource::<Connected>().0.len() == 1).then_some(())
+    });
+}
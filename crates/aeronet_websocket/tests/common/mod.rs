@@ -0,0 +1,19 @@
+//! Helpers shared between the integration tests of this crate.
+
+use {bevy_app::prelude::*, bevy_ecs::prelude::*, core::time::Duration, std::thread};
+
+/// Maximum number of updates that [`update_until`] waits for.
+pub const MAX_UPDATES: usize = 1000;
+
+/// Updates the app until `f` returns [`Some`], panicking if this takes more
+/// than [`MAX_UPDATES`] updates.
+pub fn update_until<T>(app: &mut App, mut f: impl FnMut(&mut World) -> Option<T>) -> T {
+    for _ in 0..MAX_UPDATES {
+        app.update();
+        if let Some(value) = f(app.world_mut()) {
+            return value;
+        }
+        thread::sleep(Duration::from_millis(5));
+    }
+    panic!("condition not met after {MAX_UPDATES} updates");
+}
@@ -0,0 +1,146 @@
+#![expect(missing_docs, clippy::unwrap_used, reason = "testing")]
+
+mod common;
+
+use {
+    aeronet_io::{Session, conformance, connection::LocalAddr},
+    aeronet_websocket::{
+        client::{ClientConfig, WebSocketClient, WebSocketClientPlugin},
+        server::{ServerConfig, WebSocketServer, WebSocketServerPlugin},
+        session::FrameKind,
+    },
+    bevy_app::prelude::*,
+    bevy_ecs::prelude::*,
+    bevy_hierarchy::Parent,
+    bytes::Bytes,
+    common::update_until,
+    core::net::{Ipv4Addr, SocketAddr},
+};
+
+fn setup(server_frame_kind: FrameKind, client_frame_kind: FrameKind) -> (App, Entity, Entity) {
+    let mut app = App::new();
+    app.add_plugins((WebSocketClientPlugin, WebSocketServerPlugin));
+
+    let server_config = ServerConfig::builder()
+        .with_bind_address(SocketAddr::new(Ipv4Addr::LOCALHOST.into(), 0))
+        .with_no_encryption()
+        .with_frame_kind(server_frame_kind);
+    let server = app
+        .world_mut()
+        .commands()
+        .spawn_empty()
+        .queue(WebSocketServer::open(server_config))
+        .id();
+    app.world_mut().flush();
+    let server_port = update_until(&mut app, |world| {
+        world.get::<LocalAddr>(server).map(|a| a.0.port())
+    });
+
+    let client_config = ClientConfig::builder()
+        .with_no_encryption()
+        .with_frame_kind(client_frame_kind);
+    let client = app
+        .world_mut()
+        .commands()
+        .spawn_empty()
+        .queue(WebSocketClient::connect(
+            client_config,
+            format!("ws://127.0.0.1:{server_port}"),
+        ))
+        .id();
+    app.world_mut().flush();
+    update_until(&mut app, |world| world.get::<Session>(client).map(drop));
+
+    let server_session = update_until(&mut app, |world| {
+        world
+            .query_filtered::<(Entity, &Parent), With<Session>>()
+            .iter(world)
+            .find(|(_, parent)| parent.get() == server)
+            .map(|(entity, _)| entity)
+    });
+    (app, client, server_session)
+}
+
+// not valid UTF-8, so text frames must encode it
+const PACKET: &[u8] = b"packet \xff\x00\x80";
+
+fn round_trip(frame_kind: FrameKind) {
+    let (mut app, client, server_session) = setup(frame_kind, frame_kind);
+
+    for (from, to) in [(client, server_session), (server_session, client)] {
+        app.world_mut()
+            .get_mut::<Session>(from)
+            .unwrap()
+            .send
+            .push(Bytes::from_static(PACKET));
+
+        let packet = update_until(&mut app, |world| {
+            world
+                .get_mut::<Session>(to)
+                .unwrap()
+                .recv
+                .drain(..)
+                .next()
+                .map(|packet| packet.payload)
+        });
+        assert_eq!(PACKET, packet);
+    }
+}
+
+#[test]
+fn binary_conformance() {
+    conformance::run(|| setup(FrameKind::Binary, FrameKind::Binary));
+}
+
+#[test]
+fn text_conformance() {
+    conformance::run(|| setup(FrameKind::Text, FrameKind::Text));
+}
+
+#[test]
+fn binary_round_trip() {
+    round_trip(FrameKind::Binary);
+}
+
+#[test]
+fn text_round_trip() {
+    round_trip(FrameKind::Text);
+}
+
+#[test]
+fn text_rejected_without_text_frames() {
+    let (mut app, client, server_session) = setup(FrameKind::Binary, FrameKind::Text);
+
+    app.world_mut()
+        .get_mut::<Session>(client)
+        .unwrap()
+        .send
+        .push(Bytes::from_static(PACKET));
+    for _ in 0..10 {
+        app.update();
+    }
+    assert!(
+        app.world()
+            .get::<Session>(server_session)
+            .unwrap()
+            .recv
+            .is_empty()
+    );
+
+    // the session stays usable for binary frames
+    app.world_mut()
+        .get_mut::<Session>(server_session)
+        .unwrap()
+        .send
+        .push(Bytes::from_static(PACKET));
+    let packet = update_until(&mut app, |world| {
+        world
+            .get_mut::<Session>(client)
+            .unwrap()
+            .recv
+            .drain(..)
+            .next()
+            .map(|packet| packet.payload)
+    });
+    assert_eq!(PACKET, packet);
+}
@@ -0,0 +1,82 @@
+#![expect(missing_docs, clippy::unwrap_used, reason = "testing")]
+
+mod common;
+
+use {
+    aeronet_io::{connection::LocalAddr, server::PeerRateLimit},
+    aeronet_websocket::server::{ServerConfig, WebSocketServer, WebSocketServerPlugin},
+    bevy_app::prelude::*,
+    bevy_ecs::prelude::*,
+    bevy_hierarchy::Children,
+    common::update_until,
+    core::{
+        net::{Ipv4Addr, SocketAddr},
+        time::Duration,
+    },
+    std::{
+        io::{self, Read},
+        net::TcpStream,
+    },
+};
+
+const MAX_ATTEMPTS: usize = 2;
+const NUM_ATTEMPTS: usize = 8;
+
+fn num_sessions(world: &World, server: Entity) -> usize {
+    world
+        .get::<Children>(server)
+        .map_or(0, |children| children.len())
+}
+
+/// Checks if the server has closed its end of `stream`.
+fn is_dropped(stream: &mut TcpStream) -> bool {
+    stream
+        .set_read_timeout(Some(Duration::from_millis(500)))
+        .unwrap();
+    match stream.read(&mut [0; 1]) {
+        Ok(0) => true,
+        Ok(_) => false,
+        Err(err) => !matches!(
+            err.kind(),
+            io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+        ),
+    }
+}
+
+#[test]
+fn drop_over_limit() {
+    let mut app = App::new();
+    app.add_plugins(WebSocketServerPlugin);
+
+    let server_config = ServerConfig::builder()
+        .with_bind_address(SocketAddr::new(Ipv4Addr::LOCALHOST.into(), 0))
+        .with_no_encryption();
+    let server = app
+        .world_mut()
+        .commands()
+        .spawn(PeerRateLimit::new(MAX_ATTEMPTS, Duration::from_secs(60)))
+        .queue(WebSocketServer::open(server_config))
+        .id();
+    app.world_mut().flush();
+    let server_port = update_until(&mut app, |world| {
+        world.get::<LocalAddr>(server).map(|a| a.0.port())
+    });
+
+    // connect without ever starting a handshake
+    let mut streams = (0..NUM_ATTEMPTS)
+        .map(|_| TcpStream::connect((Ipv4Addr::LOCALHOST, server_port)).unwrap())
+        .collect::<Vec<_>>();
+    update_until(&mut app, |world| {
+        (num_sessions(world, server) >= MAX_ATTEMPTS).then_some(())
+    });
+
+    let dropped = streams.iter_mut().map(is_dropped).collect::<Vec<_>>();
+    let mut expected = vec![false; MAX_ATTEMPTS];
+    expected.resize(NUM_ATTEMPTS, true);
+    assert_eq!(expected, dropped);
+
+    for _ in 0..10 {
+        app.update();
+    }
+    assert_eq!(MAX_ATTEMPTS, num_sessions(app.world(), server));
+}
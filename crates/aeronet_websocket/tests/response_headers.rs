@@ -0,0 +1,55 @@
+#![expect(missing_docs, clippy::unwrap_used, reason = "testing")]
+
+mod common;
+
+use {
+    aeronet_io::{Session, connection::LocalAddr},
+    aeronet_websocket::{
+        client::{ClientConfig, ResponseHeaders, WebSocketClient, WebSocketClientPlugin},
+        server::{ServerConfig, WebSocketServer, WebSocketServerPlugin},
+        tungstenite::http::{HeaderName, HeaderValue},
+    },
+    bevy_app::prelude::*,
+    bevy_ecs::prelude::*,
+    common::update_until,
+    core::net::{Ipv4Addr, SocketAddr},
+};
+
+#[test]
+fn client_reads_response_headers() {
+    let mut app = App::new();
+    app.add_plugins((WebSocketClientPlugin, WebSocketServerPlugin));
+
+    let server_config = ServerConfig::builder()
+        .with_bind_address(SocketAddr::new(Ipv4Addr::LOCALHOST.into(), 0))
+        .with_no_encryption()
+        .with_response_header(
+            HeaderName::from_static("x-server-version"),
+            HeaderValue::from_static("1.2.3"),
+        );
+    let server = app
+        .world_mut()
+        .commands()
+        .spawn_empty()
+        .queue(WebSocketServer::open(server_config))
+        .id();
+    app.world_mut().flush();
+    let server_port = update_until(&mut app, |world| {
+        world.get::<LocalAddr>(server).map(|a| a.0.port())
+    });
+
+    let client = app
+        .world_mut()
+        .commands()
+        .spawn_empty()
+        .queue(WebSocketClient::connect(
+            ClientConfig::builder().with_no_encryption(),
+            format!("ws://localhost:{server_port}"),
+        ))
+        .id();
+    app.world_mut().flush();
+    update_until(&mut app, |world| world.get::<Session>(client).map(drop));
+
+    let headers = &app.world().get::<ResponseHeaders>(client).unwrap().0;
+    assert_eq!("1.2.3", headers.get("x-server-version").unwrap());
+}
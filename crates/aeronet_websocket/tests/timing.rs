@@ -0,0 +1,87 @@
+#![expect(missing_docs, clippy::unwrap_used, reason = "testing")]
+
+mod common;
+
+use {
+    aeronet_io::{
+        Session,
+        connection::{ConnectTiming, LocalAddr},
+    },
+    aeronet_websocket::{
+        client::{ClientConfig, WebSocketClient, WebSocketClientPlugin},
+        server::{ServerConfig, WebSocketServer, WebSocketServerPlugin},
+    },
+    bevy_app::prelude::*,
+    bevy_ecs::prelude::*,
+    bevy_hierarchy::Parent,
+    common::update_until,
+    core::net::{Ipv4Addr, SocketAddr},
+};
+
+fn assert_ordered(timing: &ConnectTiming) {
+    let phases = [
+        Some(timing.started_at),
+        timing.resolved_at,
+        timing.handshake_at,
+        Some(timing.connected_at),
+    ];
+    let phases = phases.into_iter().flatten().collect::<Vec<_>>();
+    assert!(phases.is_sorted(), "phases out of order: {timing:?}");
+
+    let observed = timing.dns().unwrap_or_default()
+        + timing.handshake().unwrap_or_default()
+        + timing.negotiation();
+    assert_eq!(timing.total(), observed);
+}
+
+#[test]
+fn connect_timing() {
+    let mut app = App::new();
+    app.add_plugins((WebSocketClientPlugin, WebSocketServerPlugin));
+
+    let server_config = ServerConfig::builder()
+        .with_bind_address(SocketAddr::new(Ipv4Addr::LOCALHOST.into(), 0))
+        .with_no_encryption();
+    let server = app
+        .world_mut()
+        .commands()
+        .spawn_empty()
+        .queue(WebSocketServer::open(server_config))
+        .id();
+    app.world_mut().flush();
+    let server_port = update_until(&mut app, |world| {
+        world.get::<LocalAddr>(server).map(|a| a.0.port())
+    });
+
+    let client = app
+        .world_mut()
+        .commands()
+        .spawn_empty()
+        .queue(WebSocketClient::connect(
+            ClientConfig::builder().with_no_encryption(),
+            format!("ws://localhost:{server_port}"),
+        ))
+        .id();
+    app.world_mut().flush();
+    update_until(&mut app, |world| world.get::<Session>(client).map(drop));
+
+    let server_session = update_until(&mut app, |world| {
+        world
+            .query_filtered::<(Entity, &Parent), With<Session>>()
+            .iter(world)
+            .find(|(_, parent)| parent.get() == server)
+            .map(|(entity, _)| entity)
+    });
+
+    // the client observes every phase
+    let timing = *app.world().get::<ConnectTiming>(client).unwrap();
+    assert!(timing.resolved_at.is_some());
+    assert!(timing.handshake_at.is_some());
+    assert_ordered(&timing);
+
+    // the server doesn't resolve anything
+    let timing = *app.world().get::<ConnectTiming>(server_session).unwrap();
+    assert!(timing.resolved_at.is_none());
+    assert!(timing.handshake_at.is_some());
+    assert_ordered(&timing);
+}
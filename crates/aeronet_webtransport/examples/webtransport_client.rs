@@ -71,7 +71,7 @@ fn on_disconnected(
     mut ui_state: ResMut<GlobalUi>,
 ) {
     let entity = trigger.entity();
-    let Disconnected { reason } = trigger.event();
+    let Disconnected { reason, .. } = trigger.event();
     let name = names
         .get(entity)
         .expect("our session entity should have a name");
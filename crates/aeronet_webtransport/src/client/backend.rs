@@ -1,21 +1,28 @@
+#[cfg(target_family = "wasm")]
+use xwt_core::prelude::*;
 use {
     super::{ClientConfig, ClientError, ConnectTarget, ToConnected},
-    crate::session::{SessionBackend, SessionError, SessionMeta},
-    aeronet_io::{connection::DisconnectReason, packet::RecvPacket},
+    crate::session::{self, BackendDisconnect, SessionBackend, SessionError, SessionMeta},
+    aeronet_io::{
+        connection::{ConnectTiming, Disconnect},
+        packet::RecvPacket,
+    },
     bytes::Bytes,
     futures::{
         channel::{mpsc, oneshot},
         never::Never,
     },
     tracing::debug,
-    xwt_core::prelude::*,
+    web_time::Instant,
 };
 
 pub async fn start(
     config: ClientConfig,
     target: ConnectTarget,
     send_next: oneshot::Sender<ToConnected>,
-) -> Result<Never, DisconnectReason<ClientError>> {
+) -> Result<Never, BackendDisconnect<ClientError>> {
+    let started_at = Instant::now();
+
     // TODO: On native, debug log the target after this is merged:
     // https://github.com/BiagioFesta/wtransport/pull/226
     #[cfg(target_family = "wasm")]
@@ -61,12 +68,21 @@ pub async fn start(
                 .map_err(ClientError::Connect)?
         }
     };
+    // DNS resolution and the QUIC handshake both happen inside `connect`,
+    // so we can't observe them separately
+    let timing = ConnectTiming {
+        started_at,
+        resolved_at: None,
+        handshake_at: None,
+        connected_at: Instant::now(),
+    };
     debug!("Connected");
 
     let (send_meta, recv_meta) = mpsc::channel::<SessionMeta>(1);
     let (send_packet_b2f, recv_packet_b2f) = mpsc::unbounded::<RecvPacket>();
     let (send_packet_f2b, recv_packet_f2b) = mpsc::unbounded::<Bytes>();
-    let (send_user_dc, recv_user_dc) = oneshot::channel::<String>();
+    let (uni_streams_frontend, uni_streams_backend) = session::uni_stream_channels();
+    let (send_user_dc, recv_user_dc) = oneshot::channel::<Disconnect>();
     let next = ToConnected {
         #[cfg(not(target_family = "wasm"))]
         local_addr: endpoint
@@ -77,13 +93,12 @@ pub async fn start(
         initial_peer_addr: conn.0.remote_address(),
         #[cfg(not(target_family = "wasm"))]
         initial_rtt: conn.0.rtt(),
-        initial_mtu: conn
-            .max_datagram_size()
-            .ok_or(SessionError::DatagramsNotSupported)
-            .map_err(ClientError::Session)?,
+        initial_mtu: session::packet_mtu(&conn).map_err(ClientError::Session)?,
+        timing,
         recv_meta,
         recv_packet_b2f,
         send_packet_f2b,
+        uni_streams: uni_streams_frontend,
         send_user_dc,
     };
     let backend = SessionBackend {
@@ -91,6 +106,7 @@ pub async fn start(
         send_meta,
         send_packet_b2f,
         recv_packet_f2b,
+        uni_streams: uni_streams_backend,
         recv_user_dc,
     };
     send_next
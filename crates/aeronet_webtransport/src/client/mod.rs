@@ -6,12 +6,13 @@ use {
     crate::{
         runtime::WebTransportRuntime,
         session::{
-            self, MIN_MTU, SessionError, SessionMeta, WebTransportIo, WebTransportSessionPlugin,
+            self, BackendDisconnect, MIN_MTU, SessionError, SessionMeta, UniStreamsFrontend,
+            WebTransportIo, WebTransportSessionPlugin,
         },
     },
     aeronet_io::{
         IoSet, Session, SessionEndpoint,
-        connection::{DisconnectReason, Disconnected},
+        connection::{ConnectTiming, Disconnect, DisconnectReason, Disconnected},
         packet::RecvPacket,
     },
     bevy_app::prelude::*,
@@ -123,7 +124,7 @@ impl WebTransportClient {
 
 fn connect(session: Entity, world: &mut World, config: ClientConfig, target: ConnectTarget) {
     let runtime = world.resource::<WebTransportRuntime>().clone();
-    let (send_dc, recv_dc) = oneshot::channel::<DisconnectReason<ClientError>>();
+    let (send_dc, recv_dc) = oneshot::channel::<BackendDisconnect<ClientError>>();
     let (send_next, recv_next) = oneshot::channel::<ToConnected>();
     runtime.spawn_on_self(
         async move {
@@ -159,11 +160,11 @@ pub enum ClientError {
 #[derive(Debug)]
 enum ClientFrontend {
     Connecting {
-        recv_dc: oneshot::Receiver<DisconnectReason<ClientError>>,
+        recv_dc: oneshot::Receiver<BackendDisconnect<ClientError>>,
         recv_next: oneshot::Receiver<ToConnected>,
     },
     Connected {
-        recv_dc: oneshot::Receiver<DisconnectReason<ClientError>>,
+        recv_dc: oneshot::Receiver<BackendDisconnect<ClientError>>,
     },
     Disconnected,
 }
@@ -177,10 +178,12 @@ struct ToConnected {
     #[cfg(not(target_family = "wasm"))]
     initial_rtt: core::time::Duration,
     initial_mtu: usize,
+    timing: ConnectTiming,
     recv_meta: mpsc::Receiver<SessionMeta>,
     recv_packet_b2f: mpsc::UnboundedReceiver<RecvPacket>,
     send_packet_f2b: mpsc::UnboundedSender<Bytes>,
-    send_user_dc: oneshot::Sender<String>,
+    uni_streams: UniStreamsFrontend,
+    send_user_dc: oneshot::Sender<Disconnect>,
 }
 
 fn poll_clients(mut commands: Commands, mut frontends: Query<(Entity, &mut WebTransportClient)>) {
@@ -204,7 +207,7 @@ fn poll_clients(mut commands: Commands, mut frontends: Query<(Entity, &mut WebTr
 fn poll_connecting(
     commands: &mut Commands,
     entity: Entity,
-    mut recv_dc: oneshot::Receiver<DisconnectReason<ClientError>>,
+    mut recv_dc: oneshot::Receiver<BackendDisconnect<ClientError>>,
     mut recv_next: oneshot::Receiver<ToConnected>,
 ) -> ClientFrontend {
     if should_disconnect(commands, entity, &mut recv_dc) {
@@ -220,6 +223,7 @@ fn poll_connecting(
         commands.trigger_targets(
             Disconnected {
                 reason: DisconnectReason::Error(SessionError::MtuTooSmall(err).into()),
+                code: None,
             },
             entity,
         );
@@ -231,9 +235,11 @@ fn poll_connecting(
             recv_meta: next.recv_meta,
             recv_packet_b2f: next.recv_packet_b2f,
             send_packet_f2b: next.send_packet_f2b,
+            uni_streams: next.uni_streams,
             send_user_dc: Some(next.send_user_dc),
         },
         session,
+        next.timing,
         #[cfg(not(target_family = "wasm"))]
         aeronet_io::connection::LocalAddr(next.local_addr),
         #[cfg(not(target_family = "wasm"))]
@@ -247,16 +253,16 @@ fn poll_connecting(
 fn should_disconnect(
     commands: &mut Commands,
     session: Entity,
-    recv_dc: &mut oneshot::Receiver<DisconnectReason<ClientError>>,
+    recv_dc: &mut oneshot::Receiver<BackendDisconnect<ClientError>>,
 ) -> bool {
     let dc_reason = match recv_dc.try_recv() {
         Ok(None) => None,
         Ok(Some(dc_reason)) => Some(dc_reason),
         Err(_) => Some(ClientError::Session(SessionError::BackendClosed).into()),
     };
-    dc_reason.map_or(false, |reason| {
-        let reason = reason.map_err(anyhow::Error::new);
-        commands.trigger_targets(Disconnected { reason }, session);
+    dc_reason.map_or(false, |dc| {
+        let BackendDisconnect { reason, code } = dc.map_err(anyhow::Error::new);
+        commands.trigger_targets(Disconnected { reason, code }, session);
         true
     })
 }
@@ -2,9 +2,13 @@ use {
     super::{ServerError, SessionResponse, ToConnected, ToConnecting, ToOpen},
     crate::{
         WebTransportRuntime,
-        session::{SessionBackend, SessionError, SessionMeta},
+        session::{self, BackendDisconnect, SessionBackend, SessionError, SessionMeta},
+    },
+    aeronet_io::{
+        connection::{ConnectTiming, Disconnect},
+        packet::RecvPacket,
+        server::{PeerRateLimit, PeerRateLimiter},
     },
-    aeronet_io::{connection::DisconnectReason, packet::RecvPacket},
     bevy_ecs::prelude::*,
     bytes::Bytes,
     futures::{
@@ -12,22 +16,24 @@ use {
         channel::{mpsc, oneshot},
         never::Never,
     },
-    tracing::{Instrument, debug, debug_span},
+    tracing::{Instrument, debug, debug_span, trace},
+    web_time::Instant,
     wtransport::{
         Endpoint, ServerConfig,
         endpoint::{IncomingSession, SessionRequest},
     },
-    xwt_core::prelude::*,
 };
 
 pub async fn start(
     config: ServerConfig,
+    peer_rate_limit: Option<PeerRateLimit>,
     send_next: oneshot::Sender<ToOpen>,
 ) -> Result<Never, ServerError> {
     debug!("Spawning backend task to open server");
 
     let endpoint = Endpoint::server(config).map_err(SessionError::CreateEndpoint)?;
     debug!("Created endpoint");
+    let mut peer_rate_limiter = peer_rate_limit.map(PeerRateLimiter::new);
 
     let (send_connecting, recv_connecting) = mpsc::channel(1);
 
@@ -43,6 +49,14 @@ pub async fn start(
     debug!("Starting server loop");
     loop {
         let session = endpoint.accept().await;
+        if let Some(limiter) = &mut peer_rate_limiter {
+            let peer_addr = session.remote_address();
+            if !limiter.try_acquire(peer_addr.ip(), Instant::now()) {
+                trace!("Refusing connection from {peer_addr} over peer rate limit");
+                session.refuse();
+                continue;
+            }
+        }
 
         WebTransportRuntime::spawn({
             let send_connecting = send_connecting.clone();
@@ -59,11 +73,13 @@ async fn accept_session(
     session: IncomingSession,
     mut send_connecting: mpsc::Sender<ToConnecting>,
 ) -> Result<(), ServerError> {
+    let started_at = Instant::now();
     let request = session.await.map_err(ServerError::AwaitSessionRequest)?;
+    let handshake_at = Instant::now();
 
     let (send_session_entity, recv_session_entity) = oneshot::channel::<Entity>();
     let (send_session_response, recv_session_response) = oneshot::channel::<SessionResponse>();
-    let (send_dc, recv_dc) = oneshot::channel::<DisconnectReason<ServerError>>();
+    let (send_dc, recv_dc) = oneshot::channel::<BackendDisconnect<ServerError>>();
     let (send_next, recv_next) = oneshot::channel::<ToConnected>();
     send_connecting
         .send(ToConnecting {
@@ -83,18 +99,26 @@ async fn accept_session(
         .await
         .map_err(|_| SessionError::FrontendClosed)?;
 
-    let Err(dc_reason) = handle_session(request, recv_session_response, send_next)
-        .instrument(debug_span!("session", %session))
-        .await;
+    let Err(dc_reason) = handle_session(
+        request,
+        started_at,
+        handshake_at,
+        recv_session_response,
+        send_next,
+    )
+    .instrument(debug_span!("session", %session))
+    .await;
     _ = send_dc.send(dc_reason);
     Ok(())
 }
 
 async fn handle_session(
     request: SessionRequest,
+    started_at: Instant,
+    handshake_at: Instant,
     recv_session_response: oneshot::Receiver<SessionResponse>,
     send_connected: oneshot::Sender<ToConnected>,
-) -> Result<Never, DisconnectReason<ServerError>> {
+) -> Result<Never, BackendDisconnect<ServerError>> {
     debug!(
         "New session request from {}{}",
         request.authority(),
@@ -117,26 +141,37 @@ async fn handle_session(
             request.not_found().await;
             return Err(ServerError::Rejected.into());
         }
+        SessionResponse::Draining | SessionResponse::RateLimited => {
+            request.too_many_requests().await;
+            return Err(ServerError::Rejected.into());
+        }
     }
     .await
     .map(xwt_wtransport::Connection)
     .map_err(ServerError::AcceptSessionRequest)?;
+    // this includes the time that the frontend took to respond to the request
+    let timing = ConnectTiming {
+        started_at,
+        resolved_at: None,
+        handshake_at: Some(handshake_at),
+        connected_at: Instant::now(),
+    };
     debug!("Connected");
 
     let (send_meta, recv_meta) = mpsc::channel::<SessionMeta>(1);
     let (send_packet_b2f, recv_packet_b2f) = mpsc::unbounded::<RecvPacket>();
     let (send_packet_f2b, recv_packet_f2b) = mpsc::unbounded::<Bytes>();
-    let (send_user_dc, recv_user_dc) = oneshot::channel::<String>();
+    let (uni_streams_frontend, uni_streams_backend) = session::uni_stream_channels();
+    let (send_user_dc, recv_user_dc) = oneshot::channel::<Disconnect>();
     let next = ToConnected {
         initial_peer_addr: conn.0.remote_address(),
         initial_rtt: conn.0.rtt(),
-        initial_mtu: conn
-            .max_datagram_size()
-            .ok_or(SessionError::DatagramsNotSupported)
-            .map_err(ServerError::Session)?,
+        initial_mtu: session::packet_mtu(&conn).map_err(ServerError::Session)?,
+        timing,
         recv_meta,
         recv_packet_b2f,
         send_packet_f2b,
+        uni_streams: uni_streams_frontend,
         send_user_dc,
     };
     let backend = SessionBackend {
@@ -144,6 +179,7 @@ async fn handle_session(
         send_meta,
         send_packet_b2f,
         recv_packet_f2b,
+        uni_streams: uni_streams_backend,
         recv_user_dc,
     };
     send_connected
@@ -6,24 +6,29 @@ use {
     crate::{
         runtime::WebTransportRuntime,
         session::{
-            self, MIN_MTU, SessionError, SessionMeta, WebTransportIo, WebTransportSessionPlugin,
+            self, BackendDisconnect, MIN_MTU, SessionError, SessionMeta, UniStreamsFrontend,
+            WebTransportIo, WebTransportSessionPlugin,
         },
     },
     aeronet_io::{
         IoSet, Session, SessionEndpoint,
-        connection::{DisconnectReason, Disconnected, LocalAddr, PeerAddr},
+        connection::{
+            ConnectTiming, Disconnect, DisconnectReason, Disconnected, LocalAddr, PeerAddr,
+        },
         packet::{PacketRtt, RecvPacket},
-        server::{CloseReason, Closed, Server, ServerEndpoint},
+        server::{CloseReason, Closed, Draining, PeerRateLimit, Server, ServerEndpoint},
     },
+    alloc::collections::VecDeque,
     bevy_app::prelude::*,
     bevy_ecs::{prelude::*, system::EntityCommand},
     bevy_hierarchy::BuildChildren,
     bevy_reflect::prelude::*,
     bytes::Bytes,
-    core::{any::type_name, net::SocketAddr, time::Duration},
+    core::{any::type_name, fmt, net::SocketAddr, time::Duration},
     derive_more::{Display, Error, From},
     futures::channel::{mpsc, oneshot},
-    tracing::{Instrument, debug_span, warn},
+    std::collections::HashMap,
+    tracing::{Instrument, debug, debug_span, warn},
     web_time::Instant,
     wtransport::error::ConnectionError,
 };
@@ -40,9 +45,10 @@ impl Plugin for WebTransportServerPlugin {
 
         app.register_type::<SessionRequest>()
             .register_type::<SessionResponse>()
+            .add_observer(reject_pending_on_drain)
             .add_systems(
                 PreUpdate,
-                (poll_servers, poll_clients)
+                (poll_servers, release_queued_requests, poll_clients)
                     .in_set(IoSet::Poll)
                     .before(session::poll),
             );
@@ -54,7 +60,7 @@ impl Plugin for WebTransportServerPlugin {
 ///
 /// Use [`WebTransportServer::open`] to start opening a server.
 #[derive(Debug, Component)]
-#[require(ServerEndpoint)]
+#[require(ServerEndpoint, PendingSessionRequests)]
 pub struct WebTransportServer(Frontend);
 
 /// Configuration for the [`WebTransportServer`].
@@ -64,6 +70,19 @@ impl WebTransportServer {
     /// Creates an [`EntityCommand`] to set up a server and have it start
     /// listening for connections.
     ///
+    /// To only listen on a specific network interface, configure the server
+    /// with that interface's IP address using
+    /// [`ServerConfigBuilder::with_bind_address`]. The server's [`LocalAddr`]
+    /// will reflect this address. Binding to an interface by name is not
+    /// supported, since [`wtransport`] creates the socket itself.
+    ///
+    /// To limit how many connection attempts are accepted from a single IP
+    /// address before the QUIC handshake, insert a [`PeerRateLimit`] on the
+    /// server entity before this command is applied. Attempts over the limit
+    /// are refused without spawning a session entity for them.
+    ///
+    /// [`ServerConfigBuilder::with_bind_address`]: wtransport::config::ServerConfigBuilder::with_bind_address
+    ///
     /// # Examples
     ///
     /// ```
@@ -101,11 +120,12 @@ impl WebTransportServer {
 
 fn open(server: Entity, world: &mut World, config: ServerConfig) {
     let runtime = world.resource::<WebTransportRuntime>().clone();
+    let peer_rate_limit = world.get::<PeerRateLimit>(server).copied();
     let (send_closed, recv_closed) = oneshot::channel::<CloseReason<ServerError>>();
     let (send_next, recv_next) = oneshot::channel::<ToOpen>();
     runtime.spawn_on_self(
         async move {
-            let Err(err) = backend::start(config, send_next).await;
+            let Err(err) = backend::start(config, peer_rate_limit, send_next).await;
             _ = send_closed.send(CloseReason::Error(err));
         }
         .instrument(debug_span!("server", %server)),
@@ -131,6 +151,21 @@ pub enum SessionResponse {
     Forbidden,
     /// Reject the client with a `404 Not Found`.
     NotFound,
+    /// Reject the client with a `429 Too Many Requests`, since the server is
+    /// [`Draining`].
+    ///
+    /// While a server is draining, all session requests are automatically
+    /// responded to with this, without triggering [`SessionRequest`].
+    ///
+    /// [`Draining`]: aeronet_io::server::Draining
+    Draining,
+    /// Reject the client with a `429 Too Many Requests`, since the server is
+    /// receiving session requests faster than its [`AcceptRateLimit`] allows.
+    ///
+    /// Session requests which go over the rate limit are automatically
+    /// responded to with this if the limit uses [`RateLimitOverflow::Reject`],
+    /// without triggering [`SessionRequest`].
+    RateLimited,
 }
 
 /// Triggered when a client requests to connect to a [`WebTransportServer`].
@@ -140,7 +175,10 @@ pub enum SessionResponse {
 /// [`SessionRequest::respond`].
 ///
 /// At least one of your observers must `respond` to this request, otherwise
-/// the server will default to [`SessionResponse::NotFound`].
+/// the server will default to [`SessionResponse::NotFound`]. If you want to
+/// decide on a response later, e.g. to batch decisions together, use
+/// [`SessionRequest::defer`] to move this request into the server's
+/// [`PendingSessionRequests`] instead.
 ///
 /// # Examples
 ///
@@ -192,9 +230,10 @@ pub struct SessionRequest {
     /// `user-agent` header.
     pub user_agent: Option<String>,
     /// Full map of request headers.
-    pub headers: std::collections::HashMap<String, String>,
+    pub headers: HashMap<String, String>,
     /// How should the server respond to this request?
     pub response: Option<SessionResponse>,
+    deferred: bool,
 }
 
 impl SessionRequest {
@@ -202,6 +241,264 @@ impl SessionRequest {
     pub fn respond(&mut self, response: SessionResponse) {
         self.response = Some(response);
     }
+
+    /// Marks that no response will be given to this request right now, and the
+    /// request should be kept in the server's [`PendingSessionRequests`] until
+    /// you respond to it there.
+    ///
+    /// If any observer [`respond`]s to this request, that response takes
+    /// priority, and the request will not be deferred.
+    ///
+    /// [`respond`]: SessionRequest::respond
+    pub fn defer(&mut self) {
+        self.deferred = true;
+    }
+}
+
+/// Session requests on a [`WebTransportServer`] which have not been responded
+/// to yet.
+///
+/// This component is automatically added to all [`WebTransportServer`]s.
+///
+/// A request is pending while either:
+/// - it has been [deferred] by your observers
+/// - it is queued by the server's [`AcceptRateLimit`], and [`SessionRequest`]
+///   has not been triggered for it yet - see [`PendingSessionRequest::queued`]
+///
+/// The client will not be able to connect until its request is responded to,
+/// so you should regularly check this queue and implement a policy for
+/// handling old requests, such as rejecting all requests which have been
+/// pending for longer than a few seconds. Responding to a queued request using
+/// [`PendingSessionRequests::respond`] removes it from the rate limit queue
+/// without ever triggering [`SessionRequest`] for it.
+///
+/// Requests are removed from this queue automatically if the client's backend
+/// task stops waiting for a response.
+///
+/// # Examples
+///
+/// Reject requests which have been pending for too long:
+///
+/// ```
+/// use {
+///     aeronet_webtransport::server::{PendingSessionRequests, SessionResponse},
+///     bevy_ecs::prelude::*,
+///     core::time::Duration,
+///     web_time::Instant,
+/// };
+///
+/// const MAX_AGE: Duration = Duration::from_secs(5);
+///
+/// fn reject_old_requests(mut servers: Query<&mut PendingSessionRequests>) {
+///     let now = Instant::now();
+///     for mut pending in &mut servers {
+///         let old = pending
+///             .iter()
+///             .filter(|(_, request)| request.age(now) > MAX_AGE)
+///             .map(|(session, _)| session)
+///             .collect::<Vec<_>>();
+///         for session in old {
+///             pending.respond(session, SessionResponse::Forbidden);
+///         }
+///     }
+/// }
+/// ```
+///
+/// [deferred]: SessionRequest::defer
+#[derive(Debug, Default, Component)]
+pub struct PendingSessionRequests(HashMap<Entity, PendingSessionRequest>);
+
+/// [`SessionRequest`] which is waiting for a response in
+/// [`PendingSessionRequests`].
+pub struct PendingSessionRequest {
+    /// Original request sent by the client.
+    pub request: SessionRequest,
+    /// Instant at which the request was received by the server.
+    pub requested_at: Instant,
+    /// Whether this request is queued by [`AcceptRateLimit`], and
+    /// [`SessionRequest`] has not been triggered for it yet.
+    pub queued: bool,
+    send_response: oneshot::Sender<SessionResponse>,
+}
+
+impl fmt::Debug for PendingSessionRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PendingSessionRequest")
+            .field("request", &self.request)
+            .field("requested_at", &self.requested_at)
+            .field("queued", &self.queued)
+            .finish_non_exhaustive()
+    }
+}
+
+impl PendingSessionRequest {
+    /// Gets how long this request has been pending for.
+    #[must_use]
+    pub fn age(&self, now: Instant) -> Duration {
+        now.saturating_duration_since(self.requested_at)
+    }
+}
+
+impl PendingSessionRequests {
+    /// Gets the number of requests which are still pending.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Returns `true` if there are no pending requests.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Gets the pending request for the given session entity.
+    #[must_use]
+    pub fn get(&self, session: Entity) -> Option<&PendingSessionRequest> {
+        self.0.get(&session)
+    }
+
+    /// Iterates over all pending requests, along with the session entity which
+    /// made the request.
+    pub fn iter(&self) -> impl Iterator<Item = (Entity, &PendingSessionRequest)> {
+        self.0.iter().map(|(session, request)| (*session, request))
+    }
+
+    /// Responds to the pending request made by `session`, removing it from this
+    /// queue.
+    ///
+    /// Returns `false` if there was no pending request for this session.
+    pub fn respond(&mut self, session: Entity, response: SessionResponse) -> bool {
+        let Some(pending) = self.0.remove(&session) else {
+            return false;
+        };
+        _ = pending.send_response.send(response);
+        true
+    }
+
+    fn remove_canceled(&mut self) {
+        if self
+            .0
+            .values()
+            .any(|pending| pending.send_response.is_canceled())
+        {
+            self.0
+                .retain(|_, pending| !pending.send_response.is_canceled());
+        }
+    }
+}
+
+/// Limits how quickly a [`WebTransportServer`] processes session requests, to
+/// mitigate floods of connection attempts.
+///
+/// Insert this component into a server entity to limit it. Only up to
+/// [`AcceptRateLimit::max_requests`] session requests are processed - that is,
+/// have [`SessionRequest`] triggered for them - in any window of
+/// [`AcceptRateLimit::interval`]. Requests beyond that are handled according to
+/// [`AcceptRateLimit::overflow`].
+///
+/// This is independent of how many clients are connected to the server - it
+/// only limits how quickly new clients can start connecting.
+///
+/// # Examples
+///
+/// Process at most 10 session requests per second, queuing the rest:
+///
+/// ```
+/// use {
+///     aeronet_webtransport::server::{AcceptRateLimit, RateLimitOverflow},
+///     bevy_ecs::prelude::*,
+///     core::time::Duration,
+/// };
+///
+/// # fn run(mut commands: Commands, server: Entity) {
+/// commands
+///     .entity(server)
+///     .insert(AcceptRateLimit::new(10, Duration::from_secs(1)));
+///
+/// // or reject the rest instead
+/// commands.entity(server).insert(
+///     AcceptRateLimit::new(10, Duration::from_secs(1)).with_overflow(RateLimitOverflow::Reject),
+/// );
+/// # }
+/// ```
+#[derive(Debug, Component)]
+pub struct AcceptRateLimit {
+    /// Maximum number of session requests processed within any
+    /// [`AcceptRateLimit::interval`].
+    pub max_requests: usize,
+    /// Length of the window which [`AcceptRateLimit::max_requests`] applies
+    /// to.
+    pub interval: Duration,
+    /// What to do with session requests which go over the limit.
+    ///
+    /// Defaults to [`RateLimitOverflow::Queue`].
+    pub overflow: RateLimitOverflow,
+    recent: VecDeque<Instant>,
+    queued: VecDeque<Entity>,
+}
+
+/// What [`AcceptRateLimit`] does with session requests which go over the
+/// limit.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Reflect)]
+pub enum RateLimitOverflow {
+    /// Keep the request in a queue, and process it once the rate limit allows
+    /// it.
+    ///
+    /// Requests are processed in the order they were received. While queued,
+    /// the request is in the server's [`PendingSessionRequests`]. If it is
+    /// responded to there, or the client stops waiting for a response, it is
+    /// removed from the queue.
+    #[default]
+    Queue,
+    /// Respond to the request with [`SessionResponse::RateLimited`].
+    Reject,
+}
+
+impl AcceptRateLimit {
+    /// Creates an [`AcceptRateLimit`] which processes up to `max_requests`
+    /// session requests in any window of `interval`.
+    #[must_use]
+    pub fn new(max_requests: usize, interval: Duration) -> Self {
+        Self {
+            max_requests,
+            interval,
+            overflow: RateLimitOverflow::default(),
+            recent: VecDeque::new(),
+            queued: VecDeque::new(),
+        }
+    }
+
+    /// Sets the [`AcceptRateLimit::overflow`] behavior.
+    #[must_use]
+    pub const fn with_overflow(mut self, overflow: RateLimitOverflow) -> Self {
+        self.overflow = overflow;
+        self
+    }
+
+    /// Gets the number of session requests which are queued, waiting for the
+    /// rate limit to allow them to be processed.
+    #[must_use]
+    pub fn num_queued(&self) -> usize {
+        self.queued.len()
+    }
+
+    fn try_acquire(&mut self, now: Instant) -> bool {
+        while self
+            .recent
+            .front()
+            .is_some_and(|at| now.saturating_duration_since(*at) >= self.interval)
+        {
+            self.recent.pop_front();
+        }
+
+        if self.recent.len() < self.max_requests {
+            self.recent.push_back(now);
+            true
+        } else {
+            false
+        }
+    }
 }
 
 /// [`WebTransportServer`] error.
@@ -239,11 +536,11 @@ enum Frontend {
 #[require(SessionEndpoint)]
 enum ClientFrontend {
     Connecting {
-        recv_dc: oneshot::Receiver<DisconnectReason<ServerError>>,
+        recv_dc: oneshot::Receiver<BackendDisconnect<ServerError>>,
         recv_next: oneshot::Receiver<ToConnected>,
     },
     Connected {
-        recv_dc: oneshot::Receiver<DisconnectReason<ServerError>>,
+        recv_dc: oneshot::Receiver<BackendDisconnect<ServerError>>,
     },
     Disconnected,
 }
@@ -260,10 +557,10 @@ struct ToConnecting {
     path: String,
     origin: Option<String>,
     user_agent: Option<String>,
-    headers: std::collections::HashMap<String, String>,
+    headers: HashMap<String, String>,
     send_session_entity: oneshot::Sender<Entity>,
     send_session_response: oneshot::Sender<SessionResponse>,
-    recv_dc: oneshot::Receiver<DisconnectReason<ServerError>>,
+    recv_dc: oneshot::Receiver<BackendDisconnect<ServerError>>,
     recv_next: oneshot::Receiver<ToConnected>,
 }
 
@@ -272,14 +569,21 @@ struct ToConnected {
     initial_peer_addr: SocketAddr,
     initial_rtt: Duration,
     initial_mtu: usize,
+    timing: ConnectTiming,
     recv_meta: mpsc::Receiver<SessionMeta>,
     recv_packet_b2f: mpsc::UnboundedReceiver<RecvPacket>,
     send_packet_f2b: mpsc::UnboundedSender<Bytes>,
-    send_user_dc: oneshot::Sender<String>,
+    uni_streams: UniStreamsFrontend,
+    send_user_dc: oneshot::Sender<Disconnect>,
 }
 
-fn poll_servers(mut commands: Commands, mut servers: Query<(Entity, &mut WebTransportServer)>) {
-    for (server, mut frontend) in &mut servers {
+fn poll_servers(
+    mut commands: Commands,
+    mut servers: Query<(Entity, &mut WebTransportServer, &mut PendingSessionRequests)>,
+) {
+    for (server, mut frontend, mut pending) in &mut servers {
+        pending.remove_canceled();
+
         replace_with::replace_with_or_abort(&mut frontend.0, |state| match state {
             Frontend::Opening {
                 recv_closed,
@@ -347,25 +651,58 @@ fn poll_open(
         _ = connecting.send_session_entity.send(session);
 
         commands.queue(move |world: &mut World| {
-            let mut request = SessionRequest {
-                authority: connecting.authority,
-                path: connecting.path,
-                origin: connecting.origin,
-                user_agent: connecting.user_agent,
-                headers: connecting.headers,
-                response: None,
+            if world.get::<Draining>(server).is_some() {
+                debug!("Rejecting session {session} since server {server} is draining");
+                _ = connecting
+                    .send_session_response
+                    .send(SessionResponse::Draining);
+                return;
+            }
+
+            let now = Instant::now();
+            let mut pending = PendingSessionRequest {
+                request: SessionRequest {
+                    authority: connecting.authority,
+                    path: connecting.path,
+                    origin: connecting.origin,
+                    user_agent: connecting.user_agent,
+                    headers: connecting.headers,
+                    response: None,
+                    deferred: false,
+                },
+                requested_at: now,
+                queued: false,
+                send_response: connecting.send_session_response,
             };
-            world.trigger_targets_ref(&mut request, session);
-
-            let response = request.response.unwrap_or_else(|| {
-                warn!(
-                    "Session {session} created on server {server} but no response was given, will \
-                     not allow this client to connect; you must `respond` to `{}`",
-                    type_name::<SessionRequest>()
-                );
-                SessionResponse::NotFound
-            });
-            _ = connecting.send_session_response.send(response);
+
+            if let Some(mut limit) = world.get_mut::<AcceptRateLimit>(server) {
+                // don't let new requests skip ahead of queued ones
+                if !limit.queued.is_empty() || !limit.try_acquire(now) {
+                    match limit.overflow {
+                        RateLimitOverflow::Queue => {
+                            debug!(
+                                "Queueing session {session} since server {server} is rate limited"
+                            );
+                            limit.queued.push_back(session);
+                            pending.queued = true;
+                            if let Some(mut requests) =
+                                world.get_mut::<PendingSessionRequests>(server)
+                            {
+                                requests.0.insert(session, pending);
+                            }
+                        }
+                        RateLimitOverflow::Reject => {
+                            debug!(
+                                "Rejecting session {session} since server {server} is rate limited"
+                            );
+                            _ = pending.send_response.send(SessionResponse::RateLimited);
+                        }
+                    }
+                    return;
+                }
+            }
+
+            process_request(world, server, session, pending);
         });
     }
 
@@ -375,6 +712,82 @@ fn poll_open(
     }
 }
 
+fn process_request(
+    world: &mut World,
+    server: Entity,
+    session: Entity,
+    mut pending: PendingSessionRequest,
+) {
+    pending.queued = false;
+    world.trigger_targets_ref(&mut pending.request, session);
+
+    if pending.request.response.is_none() && pending.request.deferred {
+        if let Some(mut requests) = world.get_mut::<PendingSessionRequests>(server) {
+            requests.0.insert(session, pending);
+            return;
+        }
+    }
+
+    let response = pending.request.response.unwrap_or_else(|| {
+        warn!(
+            "Session {session} created on server {server} but no response was given, will not \
+             allow this client to connect; you must `respond` to `{}`",
+            type_name::<SessionRequest>()
+        );
+        SessionResponse::NotFound
+    });
+    _ = pending.send_response.send(response);
+}
+
+fn release_queued_requests(
+    mut commands: Commands,
+    mut servers: Query<(Entity, &mut AcceptRateLimit, &mut PendingSessionRequests)>,
+) {
+    let now = Instant::now();
+    for (server, mut limit, mut requests) in &mut servers {
+        // requests which were responded to, or whose client stopped waiting,
+        // have already been removed from the pending requests
+        limit
+            .queued
+            .retain(|session| requests.get(*session).is_some_and(|pending| pending.queued));
+
+        while !limit.queued.is_empty() && limit.try_acquire(now) {
+            let session = limit
+                .queued
+                .pop_front()
+                .expect("checked that the queue is not empty");
+            let pending = requests
+                .0
+                .remove(&session)
+                .expect("checked that the request is pending");
+            commands.queue(move |world: &mut World| {
+                process_request(world, server, session, pending);
+            });
+        }
+    }
+}
+
+fn reject_pending_on_drain(
+    trigger: Trigger<OnAdd, Draining>,
+    mut servers: Query<(&mut PendingSessionRequests, Option<&mut AcceptRateLimit>)>,
+) {
+    let server = trigger.entity();
+    let Ok((mut pending, limit)) = servers.get_mut(server) else {
+        return;
+    };
+    // queued requests are also pending, so are rejected below
+    if let Some(mut limit) = limit {
+        limit.queued.clear();
+    }
+    let sessions = pending
+        .iter()
+        .map(|(session, _)| session)
+        .collect::<Vec<_>>();
+    for session in sessions {
+        pending.respond(session, SessionResponse::Draining);
+    }
+}
+
 fn should_close(
     commands: &mut Commands,
     server: Entity,
@@ -413,7 +826,7 @@ fn poll_clients(mut commands: Commands, mut clients: Query<(Entity, &mut ClientF
 fn poll_connecting(
     commands: &mut Commands,
     entity: Entity,
-    mut recv_dc: oneshot::Receiver<DisconnectReason<ServerError>>,
+    mut recv_dc: oneshot::Receiver<BackendDisconnect<ServerError>>,
     mut recv_next: oneshot::Receiver<ToConnected>,
 ) -> ClientFrontend {
     if should_disconnect(commands, entity, &mut recv_dc) {
@@ -429,6 +842,7 @@ fn poll_connecting(
         commands.trigger_targets(
             Disconnected {
                 reason: DisconnectReason::Error(SessionError::MtuTooSmall(err).into()),
+                code: None,
             },
             entity,
         );
@@ -440,11 +854,13 @@ fn poll_connecting(
             recv_meta: next.recv_meta,
             recv_packet_b2f: next.recv_packet_b2f,
             send_packet_f2b: next.send_packet_f2b,
+            uni_streams: next.uni_streams,
             send_user_dc: Some(next.send_user_dc),
         },
         session,
         PeerAddr(next.initial_peer_addr),
         PacketRtt(next.initial_rtt),
+        next.timing,
     ));
     ClientFrontend::Connected { recv_dc }
 }
@@ -452,16 +868,92 @@ fn poll_connecting(
 fn should_disconnect(
     commands: &mut Commands,
     client: Entity,
-    recv_dc: &mut oneshot::Receiver<DisconnectReason<ServerError>>,
+    recv_dc: &mut oneshot::Receiver<BackendDisconnect<ServerError>>,
 ) -> bool {
     let dc_reason = match recv_dc.try_recv() {
         Ok(None) => None,
         Ok(Some(dc_reason)) => Some(dc_reason),
         Err(_) => Some(ServerError::Session(SessionError::BackendClosed).into()),
     };
-    dc_reason.map_or(false, |reason| {
-        let reason = reason.map_err(anyhow::Error::new);
-        commands.trigger_targets(Disconnected { reason }, client);
+    dc_reason.map_or(false, |dc| {
+        let BackendDisconnect { reason, code } = dc.map_err(anyhow::Error::new);
+        commands.trigger_targets(Disconnected { reason, code }, client);
         true
     })
 }
+
+#[cfg(test)]
+mod tests {
+    #![expect(clippy::unwrap_used, reason = "testing")]
+
+    use super::*;
+
+    fn request() -> SessionRequest {
+        SessionRequest {
+            authority: "localhost".into(),
+            path: "/".into(),
+            origin: None,
+            user_agent: None,
+            headers: HashMap::new(),
+            response: None,
+            deferred: true,
+        }
+    }
+
+    #[test]
+    fn pending_until_responded() {
+        let mut world = World::new();
+        let sessions = [world.spawn_empty().id(), world.spawn_empty().id()];
+
+        let mut pending = PendingSessionRequests::default();
+        let mut recv_responses = Vec::new();
+        for session in sessions {
+            let (send_response, recv_response) = oneshot::channel();
+            pending.0.insert(
+                session,
+                PendingSessionRequest {
+                    request: request(),
+                    requested_at: Instant::now(),
+                    queued: false,
+                    send_response,
+                },
+            );
+            recv_responses.push(recv_response);
+        }
+        assert_eq!(2, pending.len());
+
+        assert!(pending.respond(sessions[0], SessionResponse::Accepted));
+        assert!(!pending.respond(sessions[0], SessionResponse::Accepted));
+        assert_eq!(1, pending.len());
+        assert!(pending.get(sessions[0]).is_none());
+        assert!(pending.get(sessions[1]).is_some());
+        assert_eq!(
+            Some(SessionResponse::Accepted),
+            recv_responses[0].try_recv().unwrap()
+        );
+        assert_eq!(None, recv_responses[1].try_recv().unwrap());
+
+        // backend stops waiting for a response
+        recv_responses.pop();
+        pending.remove_canceled();
+        assert!(pending.is_empty());
+    }
+
+    #[test]
+    fn rate_limit_window() {
+        const INTERVAL: Duration = Duration::from_millis(100);
+
+        let mut limit = AcceptRateLimit::new(2, INTERVAL);
+        let start = Instant::now();
+        assert!(limit.try_acquire(start));
+        assert!(limit.try_acquire(start + Duration::from_millis(50)));
+        assert!(!limit.try_acquire(start + Duration::from_millis(60)));
+
+        // the first request leaves the window
+        assert!(limit.try_acquire(start + INTERVAL));
+        assert!(!limit.try_acquire(start + INTERVAL));
+
+        // the second request leaves the window
+        assert!(limit.try_acquire(start + Duration::from_millis(150)));
+    }
+}
@@ -6,13 +6,15 @@ use {
     crate::runtime::WebTransportRuntime,
     aeronet_io::{
         AeronetIoPlugin, IoSet, Session,
-        connection::{DROP_DISCONNECT_REASON, Disconnect, DisconnectReason, PeerAddr},
+        connection::{
+            DROP_DISCONNECT_REASON, Disconnect, DisconnectReason, DisconnectedRetained, PeerAddr,
+        },
         packet::{IP_MTU, MtuTooSmall, PacketRtt, RecvPacket},
     },
     alloc::sync::Arc,
     bevy_app::prelude::*,
     bevy_ecs::prelude::*,
-    bytes::Bytes,
+    bytes::{BufMut, Bytes, BytesMut},
     core::{num::Saturating, time::Duration},
     derive_more::{Display, Error},
     futures::{
@@ -20,8 +22,8 @@ use {
         channel::{mpsc, oneshot},
         never::Never,
     },
-    std::io,
-    tracing::{trace, trace_span},
+    std::{collections::HashMap, io},
+    tracing::{debug, trace, trace_span},
     web_time::Instant,
     xwt_core::prelude::*,
 };
@@ -73,18 +75,264 @@ impl Plugin for WebTransportSessionPlugin {
 /// You should not add or remove this component directly - it is managed
 /// entirely by the client and server implementations.
 #[derive(Debug, Component)]
-#[require(Session(new_session))]
+#[require(Session(new_session), RawDatagrams, UniStreams)]
 pub struct WebTransportIo {
     pub(crate) recv_meta: mpsc::Receiver<SessionMeta>,
     pub(crate) recv_packet_b2f: mpsc::UnboundedReceiver<RecvPacket>,
     pub(crate) send_packet_f2b: mpsc::UnboundedSender<Bytes>,
-    pub(crate) send_user_dc: Option<oneshot::Sender<String>>,
+    pub(crate) uni_streams: UniStreamsFrontend,
+    pub(crate) send_user_dc: Option<oneshot::Sender<Disconnect>>,
 }
 
 fn new_session() -> Session {
     Session::new(Instant::now(), IP_MTU)
 }
 
+/// Raw WebTransport datagrams sent and received on a [`WebTransportIo`]
+/// session, in parallel with the packets in [`Session`].
+///
+/// All packets of a [`WebTransportIo`] session are already sent as
+/// WebTransport datagrams, which the transport layer (if you use one) then
+/// builds messages on top of. However, if you have small, unreliable messages
+/// which are sent very frequently, you may want to send them as datagrams
+/// directly, bypassing the transport layer's fragmentation, acknowledgement,
+/// and framing overhead. Datagrams sent here are never seen by the transport
+/// layer, and vice versa.
+///
+/// This component is automatically added to all [`WebTransportIo`] sessions.
+///
+/// # Size limits
+///
+/// A raw datagram may be at most [`Session::mtu`] bytes long - the same limit
+/// as for packets. This is guaranteed to be at least [`MIN_MTU`], but may
+/// change over the lifetime of the session as the path MTU updates. Datagrams
+/// which are too large to be sent are dropped.
+///
+/// Raw datagrams are unreliable and unordered, exactly like the underlying
+/// QUIC datagrams: they may be lost, duplicated, or arrive out of order.
+///
+/// # Usage
+///
+/// - Push into [`RawDatagrams::send`] to send a datagram to the peer. These are
+///   sent in [`IoSet::Flush`].
+/// - Drain [`RawDatagrams::recv`] to read datagrams received from the peer.
+///   These are received in [`IoSet::Poll`], and any datagrams not drained by
+///   the next [`IoSet::Poll`] are discarded.
+///
+/// Both peers must be using a [`WebTransportIo`] session for raw datagrams to
+/// be distinguished from packets.
+#[derive(Debug, Clone, Default, Component)]
+pub struct RawDatagrams {
+    /// Datagrams received from the peer.
+    pub recv: Vec<RecvPacket>,
+    /// Datagrams to send to the peer.
+    pub send: Vec<Bytes>,
+}
+
+/// Identifies a lane of [`UniStreams`].
+///
+/// Each lane is sent over its own dedicated unidirectional QUIC stream, so
+/// data within a lane is reliable and ordered, but lanes do not block each
+/// other.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct StreamLane(pub u32);
+
+/// Chunk of data received on a [`UniStreams`] lane.
+#[derive(Debug, Clone)]
+pub struct StreamChunk {
+    /// Lane that this data was received on.
+    pub lane: StreamLane,
+    /// Instant at which the backend read this data from the stream.
+    pub recv_at: Instant,
+    /// Data read from the stream.
+    ///
+    /// Chunk boundaries are not preserved from the sender: this may contain
+    /// part of, or several of, the [`Bytes`] passed to [`UniStreams::send`].
+    pub payload: Bytes,
+}
+
+/// Unidirectional QUIC streams sent and received on a [`WebTransportIo`]
+/// session, in parallel with the packets in [`Session`].
+///
+/// Packets are sent as datagrams, and the transport layer (if you use one)
+/// builds reliable messages on top of them, buffering every message until it
+/// is acknowledged. For large one-way transfers such as asset downloads, this
+/// means the whole transfer sits in memory on the sender. Instead, you can send
+/// the data over a [`StreamLane`], which uses a dedicated unidirectional QUIC
+/// stream and is subject to QUIC's flow control.
+///
+/// This component is automatically added to all [`WebTransportIo`] sessions.
+///
+/// # Backpressure
+///
+/// Each lane may have at most [`UniStreams::max_buffered`] bytes which have
+/// been sent by the user, but not yet accepted by the QUIC stream. Once the
+/// peer stops reading (or the network can't keep up), the stream stops
+/// accepting data, and [`UniStreams::writable`] drops to 0. Data sent past this
+/// limit is rejected and handed back to you in [`StreamFull`], so you can
+/// slow down instead of buffering the data in the IO layer.
+///
+/// On the receiving side, data is only read from the stream as fast as
+/// [`IoSet::Poll`] drains it, so a slow receiver propagates backpressure to
+/// the sender.
+///
+/// # Usage
+///
+/// - Call [`UniStreams::send`] to send data on a lane. Check
+///   [`UniStreams::writable`] first to find out how much data the lane can
+///   currently accept. Data is sent in [`IoSet::Flush`].
+/// - Drain [`UniStreams::recv`] to read data received from the peer. This is
+///   received in [`IoSet::Poll`], and any chunks not drained by the next
+///   [`IoSet::Poll`] are discarded.
+///
+/// The stream for a lane is opened when data is first sent on it, and stays
+/// open for the lifetime of the session. If a stream fails, its lane stops
+/// sending and receiving data - this is usually because the connection itself
+/// was lost, which is reported as a disconnect.
+#[derive(Debug, Clone, Component)]
+pub struct UniStreams {
+    /// Data received from the peer.
+    pub recv: Vec<StreamChunk>,
+    /// Maximum number of bytes which may be buffered on a single lane, before
+    /// the lane stops accepting new data.
+    ///
+    /// Defaults to [`UniStreams::DEFAULT_MAX_BUFFERED`].
+    pub max_buffered: usize,
+    send: Vec<(StreamLane, Bytes)>,
+    buffered: HashMap<StreamLane, usize>,
+}
+
+impl Default for UniStreams {
+    fn default() -> Self {
+        Self {
+            recv: Vec::new(),
+            max_buffered: Self::DEFAULT_MAX_BUFFERED,
+            send: Vec::new(),
+            buffered: HashMap::new(),
+        }
+    }
+}
+
+impl UniStreams {
+    /// Default value of [`UniStreams::max_buffered`].
+    pub const DEFAULT_MAX_BUFFERED: usize = 256 * 1024;
+
+    /// Gets how many bytes are buffered on `lane`, waiting to be accepted by
+    /// the QUIC stream.
+    #[must_use]
+    pub fn buffered(&self, lane: StreamLane) -> usize {
+        self.buffered.get(&lane).copied().unwrap_or(0)
+    }
+
+    /// Gets how many more bytes `lane` can currently accept.
+    #[must_use]
+    pub fn writable(&self, lane: StreamLane) -> usize {
+        self.max_buffered.saturating_sub(self.buffered(lane))
+    }
+
+    /// Sends data on `lane`.
+    ///
+    /// # Errors
+    ///
+    /// Errors if `data` is longer than [`UniStreams::writable`] for this lane.
+    /// In this case, none of `data` is sent.
+    pub fn send(&mut self, lane: StreamLane, data: Bytes) -> Result<(), StreamFull> {
+        let writable = self.writable(lane);
+        if data.len() > writable {
+            return Err(StreamFull { writable, data });
+        }
+
+        *self.buffered.entry(lane).or_default() += data.len();
+        self.send.push((lane, data));
+        Ok(())
+    }
+
+    fn written(&mut self, lane: StreamLane, len: usize) {
+        if let Some(buffered) = self.buffered.get_mut(&lane) {
+            *buffered = buffered.saturating_sub(len);
+            if *buffered == 0 {
+                self.buffered.remove(&lane);
+            }
+        }
+    }
+}
+
+/// Attempted to send more data on a [`UniStreams`] lane than it can currently
+/// accept.
+#[derive(Debug, Clone, Display, Error)]
+#[display("stream lane is full - {} bytes sent, {writable} bytes writable", data.len())]
+pub struct StreamFull {
+    /// How many bytes the lane could accept at the time of sending.
+    pub writable: usize,
+    /// Data which was not sent.
+    pub data: Bytes,
+}
+
+#[derive(Debug)]
+pub(crate) struct UniStreamsFrontend {
+    send_data_f2b: mpsc::UnboundedSender<(StreamLane, Bytes)>,
+    recv_written_b2f: mpsc::UnboundedReceiver<(StreamLane, usize)>,
+    recv_chunk_b2f: mpsc::Receiver<StreamChunk>,
+}
+
+#[derive(Debug)]
+pub(crate) struct UniStreamsBackend {
+    recv_data_f2b: mpsc::UnboundedReceiver<(StreamLane, Bytes)>,
+    send_written_b2f: mpsc::UnboundedSender<(StreamLane, usize)>,
+    send_chunk_b2f: mpsc::Sender<StreamChunk>,
+}
+
+/// Maximum number of received [`StreamChunk`]s which may be waiting for the
+/// frontend, before the backend stops reading from streams.
+const UNI_STREAM_CHUNK_CAPACITY: usize = 64;
+
+/// Maximum length of a single [`StreamChunk`] read from a stream.
+const UNI_STREAM_READ_LEN: usize = 16 * 1024;
+
+pub(crate) fn uni_stream_channels() -> (UniStreamsFrontend, UniStreamsBackend) {
+    let (send_data_f2b, recv_data_f2b) = mpsc::unbounded();
+    let (send_written_b2f, recv_written_b2f) = mpsc::unbounded();
+    let (send_chunk_b2f, recv_chunk_b2f) = mpsc::channel(UNI_STREAM_CHUNK_CAPACITY);
+    (
+        UniStreamsFrontend {
+            send_data_f2b,
+            recv_written_b2f,
+            recv_chunk_b2f,
+        },
+        UniStreamsBackend {
+            recv_data_f2b,
+            send_written_b2f,
+            send_chunk_b2f,
+        },
+    )
+}
+
+/// Length of the header prepended to every datagram sent by a
+/// [`WebTransportIo`], which identifies if the datagram carries a packet or a
+/// [`RawDatagrams`] datagram.
+const DATAGRAM_HEADER_LEN: usize = 1;
+
+/// Header of a datagram which carries a [`Session`] packet.
+const PACKET_TAG: u8 = 0;
+
+/// Header of a datagram which carries a [`RawDatagrams`] datagram.
+const RAW_DATAGRAM_TAG: u8 = 1;
+
+/// Gets the maximum length of a packet which can be sent over this connection,
+/// accounting for the datagram header.
+pub(crate) fn packet_mtu(conn: &Connection) -> Result<usize, SessionError> {
+    conn.max_datagram_size()
+        .map(|len| len.saturating_sub(DATAGRAM_HEADER_LEN))
+        .ok_or(SessionError::DatagramsNotSupported)
+}
+
+fn frame_datagram(tag: u8, payload: &[u8]) -> Bytes {
+    let mut buf = BytesMut::with_capacity(DATAGRAM_HEADER_LEN + payload.len());
+    buf.put_u8(tag);
+    buf.put_slice(payload);
+    buf.freeze()
+}
+
 /// Error that occurs when polling a session using the [`WebTransportIo`]
 /// IO layer.
 #[derive(Debug, Display, Error)]
@@ -122,7 +370,7 @@ pub const MIN_MTU: usize = IP_MTU;
 impl Drop for WebTransportIo {
     fn drop(&mut self) {
         if let Some(send_dc) = self.send_user_dc.take() {
-            _ = send_dc.send(DROP_DISCONNECT_REASON.to_owned());
+            _ = send_dc.send(Disconnect::new(DROP_DISCONNECT_REASON));
         }
     }
 }
@@ -138,27 +386,40 @@ pub(crate) struct SessionMeta {
 
 fn on_disconnect(trigger: Trigger<Disconnect>, mut sessions: Query<&mut WebTransportIo>) {
     let session = trigger.entity();
-    let Disconnect { reason } = trigger.event();
     let Ok(mut io) = sessions.get_mut(session) else {
         return;
     };
 
     if let Some(send_dc) = io.send_user_dc.take() {
-        _ = send_dc.send(reason.clone());
+        _ = send_dc.send(trigger.event().clone());
     }
 }
 
 pub(crate) fn poll(
-    mut sessions: Query<(
-        Entity,
-        &mut Session,
-        &mut WebTransportIo,
-        Option<&mut PeerAddr>,
-        Option<&mut PacketRtt>,
-    )>,
+    mut sessions: Query<
+        (
+            Entity,
+            &mut Session,
+            &mut WebTransportIo,
+            &mut RawDatagrams,
+            &mut UniStreams,
+            Option<&mut PeerAddr>,
+            Option<&mut PacketRtt>,
+        ),
+        Without<DisconnectedRetained>,
+    >,
     mut commands: Commands,
 ) {
-    'sessions: for (entity, mut session, mut io, mut peer_addr, mut packet_rtt) in &mut sessions {
+    'sessions: for (
+        entity,
+        mut session,
+        mut io,
+        mut datagrams,
+        mut streams,
+        mut peer_addr,
+        mut packet_rtt,
+    ) in &mut sessions
+    {
         #[cfg(target_family = "wasm")]
         {
             // suppress `unused_variables`, `unused_mut`
@@ -186,28 +447,73 @@ pub(crate) fn poll(
             }
         }
 
+        datagrams.recv.clear();
         let mut num_packets = Saturating(0);
         let mut num_bytes = Saturating(0);
-        while let Ok(Some(packet)) = io.recv_packet_b2f.try_next() {
-            num_packets += 1;
-            session.stats.packets_recv += 1;
-
-            num_bytes += packet.payload.len();
-            session.stats.bytes_recv += packet.payload.len();
+        let mut num_datagrams = Saturating(0);
+        while let Ok(Some(datagram)) = io.recv_packet_b2f.try_next() {
+            let Some(&tag) = datagram.payload.first() else {
+                trace!("Received datagram with no header");
+                continue;
+            };
+            let packet = RecvPacket {
+                recv_at: datagram.recv_at,
+                payload: datagram.payload.slice(DATAGRAM_HEADER_LEN..),
+            };
+
+            match tag {
+                PACKET_TAG => {
+                    num_packets += 1;
+                    session.stats.packets_recv += 1;
+
+                    num_bytes += packet.payload.len();
+                    session.stats.bytes_recv += packet.payload.len();
+
+                    session.recv.push(packet);
+                }
+                RAW_DATAGRAM_TAG => {
+                    num_datagrams += 1;
+                    datagrams.recv.push(packet);
+                }
+                tag => {
+                    trace!(tag, "Received datagram with unknown header");
+                }
+            }
+        }
 
-            session.recv.push(packet);
+        streams.recv.clear();
+        while let Ok(Some((lane, len))) = io.uni_streams.recv_written_b2f.try_next() {
+            streams.written(lane, len);
+        }
+        let mut num_stream_bytes = Saturating(0);
+        while let Ok(Some(chunk)) = io.uni_streams.recv_chunk_b2f.try_next() {
+            num_stream_bytes += chunk.payload.len();
+            streams.recv.push(chunk);
         }
 
         trace!(
             num_packets = num_packets.0,
             num_bytes = num_bytes.0,
+            num_datagrams = num_datagrams.0,
+            num_stream_bytes = num_stream_bytes.0,
             "Received packets",
         );
     }
 }
 
-fn flush(mut sessions: Query<(Entity, &mut Session, &WebTransportIo)>) {
-    for (entity, mut session, io) in &mut sessions {
+fn flush(
+    mut sessions: Query<
+        (
+            Entity,
+            &mut Session,
+            &WebTransportIo,
+            &mut RawDatagrams,
+            &mut UniStreams,
+        ),
+        Without<DisconnectedRetained>,
+    >,
+) {
+    for (entity, mut session, io, mut datagrams, mut streams) in &mut sessions {
         let span = trace_span!("flush", %entity);
         let _span = span.enter();
 
@@ -223,33 +529,81 @@ fn flush(mut sessions: Query<(Entity, &mut Session, &WebTransportIo)>) {
             session.stats.bytes_sent += packet.len();
 
             // handle connection errors in `poll`
-            _ = io.send_packet_f2b.unbounded_send(packet);
+            _ = io
+                .send_packet_f2b
+                .unbounded_send(frame_datagram(PACKET_TAG, &packet));
+        }
+
+        let mut num_datagrams = Saturating(0);
+        for datagram in datagrams.send.drain(..) {
+            num_datagrams += 1;
+            _ = io
+                .send_packet_f2b
+                .unbounded_send(frame_datagram(RAW_DATAGRAM_TAG, &datagram));
+        }
+
+        let mut num_stream_bytes = Saturating(0);
+        for (lane, data) in streams.send.drain(..) {
+            num_stream_bytes += data.len();
+            _ = io.uni_streams.send_data_f2b.unbounded_send((lane, data));
         }
 
         trace!(
             num_packets = num_packets.0,
             num_bytes = num_bytes.0,
+            num_datagrams = num_datagrams.0,
+            num_stream_bytes = num_stream_bytes.0,
             "Flushed packets",
         );
     }
 }
 
+/// Why a session's backend task stopped running.
+#[derive(Debug)]
+pub(crate) struct BackendDisconnect<E> {
+    pub reason: DisconnectReason<E>,
+    /// See [`Disconnected::code`].
+    ///
+    /// [`Disconnected::code`]: aeronet_io::connection::Disconnected::code
+    pub code: Option<u32>,
+}
+
+impl<E> BackendDisconnect<E> {
+    pub fn map_err<F>(self, f: impl FnOnce(E) -> F) -> BackendDisconnect<F> {
+        BackendDisconnect {
+            reason: self.reason.map_err(f),
+            code: self.code,
+        }
+    }
+}
+
+impl<E> From<E> for BackendDisconnect<E> {
+    fn from(value: E) -> Self {
+        Self {
+            reason: DisconnectReason::Error(value),
+            code: None,
+        }
+    }
+}
+
 #[derive(Debug)]
 pub(crate) struct SessionBackend {
     pub conn: Connection,
     pub send_meta: mpsc::Sender<SessionMeta>,
     pub send_packet_b2f: mpsc::UnboundedSender<RecvPacket>,
     pub recv_packet_f2b: mpsc::UnboundedReceiver<Bytes>,
-    pub recv_user_dc: oneshot::Receiver<String>,
+    pub uni_streams: UniStreamsBackend,
+    pub recv_user_dc: oneshot::Receiver<Disconnect>,
 }
 
 impl SessionBackend {
-    pub async fn start(self) -> DisconnectReason<SessionError> {
+    pub async fn start(self) -> BackendDisconnect<SessionError> {
         let Self {
             conn,
             send_meta,
             send_packet_b2f,
             recv_packet_f2b,
+            uni_streams,
             mut recv_user_dc,
         } = self;
 
@@ -286,17 +640,37 @@ impl SessionBackend {
             }
         });
 
+        // stream failures are not reported as session errors;
+        // if the connection was lost, we'll pick that up in the recv loop
+        let (_send_uni_sending_closed, recv_uni_sending_closed) = oneshot::channel();
+        WebTransportRuntime::spawn(uni_send_loop(
+            conn.clone(),
+            recv_uni_sending_closed,
+            uni_streams.recv_data_f2b,
+            uni_streams.send_written_b2f,
+        ));
+
+        let (_send_uni_receiving_closed, recv_uni_receiving_closed) = oneshot::channel();
+        WebTransportRuntime::spawn(uni_recv_loop(
+            conn.clone(),
+            recv_uni_receiving_closed,
+            uni_streams.send_chunk_b2f,
+        ));
+
         futures::select! {
             err = recv_err.next() => {
                 let err = err.unwrap_or(SessionError::BackendClosed);
                 get_disconnect_reason(err)
             }
-            reason = recv_user_dc => {
-                if let Ok(reason) = reason {
-                    disconnect(conn, &reason).await;
-                    DisconnectReason::User(reason)
+            dc = recv_user_dc => {
+                if let Ok(Disconnect { reason, code }) = dc {
+                    disconnect(conn, &reason, code).await;
+                    BackendDisconnect {
+                        reason: DisconnectReason::User(reason),
+                        code,
+                    }
                 } else {
-                    DisconnectReason::Error(SessionError::FrontendClosed)
+                    SessionError::FrontendClosed.into()
                 }
             }
         }
@@ -321,9 +695,7 @@ async fn meta_loop(
             peer_addr: conn.0.remote_address(),
             #[cfg(not(target_family = "wasm"))]
             packet_rtt: conn.0.rtt(),
-            mtu: conn
-                .max_datagram_size()
-                .ok_or(SessionError::DatagramsNotSupported)?,
+            mtu: packet_mtu(&conn)?,
         };
         match send_meta.try_send(meta) {
             Ok(()) => {}
@@ -427,6 +799,151 @@ async fn send_loop(
     }
 }
 
+async fn uni_send_loop(
+    conn: Arc<Connection>,
+    mut recv_closed: oneshot::Receiver<()>,
+    mut recv_data_f2b: mpsc::UnboundedReceiver<(StreamLane, Bytes)>,
+    send_written_b2f: mpsc::UnboundedSender<(StreamLane, usize)>,
+) {
+    let mut lanes = HashMap::<StreamLane, mpsc::UnboundedSender<Bytes>>::new();
+    loop {
+        let next = futures::select! {
+            x = recv_data_f2b.next() => x,
+            _ = recv_closed => return,
+        };
+        let Some((lane, data)) = next else {
+            return;
+        };
+
+        let send_lane_data = lanes.entry(lane).or_insert_with(|| {
+            let (send_lane_data, recv_lane_data) = mpsc::unbounded();
+            WebTransportRuntime::spawn(send_uni_stream(
+                conn.clone(),
+                lane,
+                recv_lane_data,
+                send_written_b2f.clone(),
+            ));
+            send_lane_data
+        });
+        _ = send_lane_data.unbounded_send(data);
+    }
+}
+
+async fn send_uni_stream(
+    conn: Arc<Connection>,
+    lane: StreamLane,
+    mut recv_lane_data: mpsc::UnboundedReceiver<Bytes>,
+    send_written_b2f: mpsc::UnboundedSender<(StreamLane, usize)>,
+) {
+    let opening = match conn.open_uni().await {
+        Ok(opening) => opening,
+        Err(err) => {
+            debug!(lane = lane.0, "Failed to open uni stream: {err}");
+            return;
+        }
+    };
+    let mut stream = match opening.wait_uni().await {
+        Ok(stream) => stream,
+        Err(err) => {
+            debug!(lane = lane.0, "Failed to open uni stream: {err}");
+            return;
+        }
+    };
+
+    // the peer doesn't know which lane this stream is for until we tell it
+    let mut data = Bytes::copy_from_slice(&lane.0.to_be_bytes());
+    let mut header_len = data.len();
+    loop {
+        while !data.is_empty() {
+            // this is where QUIC flow control applies backpressure
+            let written = match stream.write(&data).await {
+                Ok(written) => written,
+                Err(err) => {
+                    debug!(lane = lane.0, "Failed to write to uni stream: {err}");
+                    return;
+                }
+            };
+            data = data.slice(written..);
+
+            let header_written = written.min(header_len);
+            header_len -= header_written;
+            let written = written - header_written;
+            if written > 0 && send_written_b2f.unbounded_send((lane, written)).is_err() {
+                return;
+            }
+        }
+
+        let Some(next) = recv_lane_data.next().await else {
+            return;
+        };
+        data = next;
+    }
+}
+
+async fn uni_recv_loop(
+    conn: Arc<Connection>,
+    mut recv_closed: oneshot::Receiver<()>,
+    send_chunk_b2f: mpsc::Sender<StreamChunk>,
+) {
+    loop {
+        let stream = futures::select! {
+            x = conn.accept_uni().fuse() => x,
+            _ = recv_closed => return,
+        };
+        let Ok(stream) = stream else {
+            return;
+        };
+
+        WebTransportRuntime::spawn(recv_uni_stream(stream, send_chunk_b2f.clone()));
+    }
+}
+
+async fn recv_uni_stream(
+    mut stream: RecvStreamFor<Connection>,
+    mut send_chunk_b2f: mpsc::Sender<StreamChunk>,
+) {
+    let mut buf = vec![0; UNI_STREAM_READ_LEN];
+
+    let mut header = [0; 4];
+    let mut header_read = 0;
+    while header_read < header.len() {
+        match stream.read(&mut header[header_read..]).await {
+            Ok(Some(read)) => header_read += read,
+            Ok(None) => {
+                debug!("Uni stream closed before sending its lane");
+                return;
+            }
+            Err(err) => {
+                debug!("Failed to read from uni stream: {err}");
+                return;
+            }
+        }
+    }
+    let lane = StreamLane(u32::from_be_bytes(header));
+
+    loop {
+        let read = match stream.read(&mut buf).await {
+            Ok(Some(read)) => read,
+            Ok(None) => return,
+            Err(err) => {
+                debug!(lane = lane.0, "Failed to read from uni stream: {err}");
+                return;
+            }
+        };
+
+        let chunk = StreamChunk {
+            lane,
+            recv_at: Instant::now(),
+            payload: Bytes::copy_from_slice(&buf[..read]),
+        };
+        // if the frontend isn't draining chunks fast enough, we stop reading
+        // from the stream, which applies backpressure to the sender
+        if send_chunk_b2f.send(chunk).await.is_err() {
+            return;
+        }
+    }
+}
+
 #[cfg_attr(
     target_family = "wasm",
     expect(
@@ -434,7 +951,7 @@ async fn send_loop(
         reason = "the current implementation is temporary"
     )
 )]
-fn get_disconnect_reason(err: SessionError) -> DisconnectReason<SessionError> {
+fn get_disconnect_reason(err: SessionError) -> BackendDisconnect<SessionError> {
     #[cfg(target_family = "wasm")]
     {
         // TODO: I don't know how the app-initiated disconnect message looks
@@ -442,7 +959,7 @@ fn get_disconnect_reason(err: SessionError) -> DisconnectReason<SessionError> {
         // https://github.com/BiagioFesta/wtransport/issues/182
         //
         // Tested: when the server disconnects us, all we get is "Connection lost."
-        DisconnectReason::Error(err)
+        err.into()
     }
 
     #[cfg(not(target_family = "wasm"))]
@@ -452,22 +969,27 @@ fn get_disconnect_reason(err: SessionError) -> DisconnectReason<SessionError> {
         match err {
             SessionError::Connection(ConnectionError::ApplicationClosed(err)) => {
                 let reason = String::from_utf8_lossy(err.reason()).into_owned();
-                DisconnectReason::Peer(reason)
+                BackendDisconnect {
+                    reason: DisconnectReason::Peer(reason),
+                    code: u32::try_from(err.code().into_inner()).ok(),
+                }
             }
-            err => DisconnectReason::Error(err),
+            err => err.into(),
         }
     }
 }
 
-async fn disconnect(conn: Arc<Connection>, reason: &str) {
-    const DISCONNECT_ERROR_CODE: u32 = 0;
+async fn disconnect(conn: Arc<Connection>, reason: &str, code: Option<u32>) {
+    const DEFAULT_ERROR_CODE: u32 = 0;
+
+    let code = code.unwrap_or(DEFAULT_ERROR_CODE);
 
     #[cfg(target_family = "wasm")]
     {
         use {wasm_bindgen_futures::JsFuture, xwt_web_sys::sys::WebTransportCloseInfo};
 
         let mut close_info = WebTransportCloseInfo::new();
-        close_info.close_code(DISCONNECT_ERROR_CODE);
+        close_info.close_code(code);
         close_info.reason(reason);
 
         // TODO: This seems to not close the connection properly
@@ -484,9 +1006,7 @@ async fn disconnect(conn: Arc<Connection>, reason: &str) {
     {
         use wtransport::VarInt;
 
-        const ERROR_CODE: VarInt = VarInt::from_u32(DISCONNECT_ERROR_CODE);
-
-        conn.0.close(ERROR_CODE, reason.as_bytes());
+        conn.0.close(VarInt::from_u32(code), reason.as_bytes());
         conn.0.closed().await;
     }
 }
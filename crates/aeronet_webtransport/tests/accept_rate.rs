@@ -0,0 +1,129 @@
+#![expect(missing_docs, clippy::unwrap_used, reason = "testing")]
+
+mod common;
+
+use {
+    aeronet_io::{
+        Session,
+        connection::{DisconnectReason, Disconnected, LocalAddr},
+    },
+    aeronet_webtransport::{
+        client::{ClientConfig, WebTransportClient, WebTransportClientPlugin},
+        server::{
+            AcceptRateLimit, RateLimitOverflow, ServerConfig, SessionRequest, SessionResponse,
+            WebTransportServer, WebTransportServerPlugin,
+        },
+        wtransport::Identity,
+    },
+    bevy_app::prelude::*,
+    bevy_ecs::prelude::*,
+    common::update_until,
+    core::time::Duration,
+    web_time::Instant,
+};
+
+const NUM_CLIENTS: usize = 3;
+
+#[derive(Debug, Default, Resource)]
+struct Requests(Vec<Instant>);
+
+#[derive(Debug, Default, Resource)]
+struct Rejected(usize);
+
+/// Opens a server with `limit`, and starts connecting [`NUM_CLIENTS`] clients
+/// to it all at once.
+fn setup(limit: AcceptRateLimit) -> (App, Vec<Entity>) {
+    let mut app = App::new();
+    app.add_plugins((WebTransportClientPlugin, WebTransportServerPlugin))
+        .init_resource::<Requests>()
+        .init_resource::<Rejected>()
+        .add_observer(
+            |mut trigger: Trigger<SessionRequest>, mut requests: ResMut<Requests>| {
+                requests.0.push(Instant::now());
+                trigger.event_mut().respond(SessionResponse::Accepted);
+            },
+        );
+
+    let identity = Identity::self_signed(["localhost", "127.0.0.1"]).unwrap();
+    let cert_hash = identity.certificate_chain().as_slice()[0].hash();
+    let server_config: ServerConfig = ServerConfig::builder()
+        .with_bind_default(0)
+        .with_identity(&identity)
+        .build();
+    let server = app
+        .world_mut()
+        .commands()
+        .spawn(limit)
+        .queue(WebTransportServer::open(server_config))
+        .id();
+    app.world_mut().flush();
+    let server_port = update_until(&mut app, |world| {
+        world.get::<LocalAddr>(server).map(|a| a.0.port())
+    });
+
+    let clients = (0..NUM_CLIENTS)
+        .map(|_| {
+            let client_config: ClientConfig = ClientConfig::builder()
+                .with_bind_default()
+                .with_server_certificate_hashes([cert_hash.clone()])
+                .build();
+            app.world_mut()
+                .commands()
+                .spawn_empty()
+                .queue(WebTransportClient::connect(
+                    client_config,
+                    format!("https://127.0.0.1:{server_port}"),
+                ))
+                .observe(
+                    |trigger: Trigger<Disconnected>, mut rejected: ResMut<Rejected>| {
+                        assert!(matches!(trigger.event().reason, DisconnectReason::Error(_)));
+                        rejected.0 += 1;
+                    },
+                )
+                .id()
+        })
+        .collect::<Vec<_>>();
+    app.world_mut().flush();
+    (app, clients)
+}
+
+#[test]
+fn queue_over_limit() {
+    const INTERVAL: Duration = Duration::from_millis(300);
+    // allow for the time between the limiter letting a request through, and
+    // the request actually being triggered
+    const TOLERANCE: Duration = Duration::from_millis(50);
+
+    let (mut app, clients) = setup(AcceptRateLimit::new(1, INTERVAL));
+    update_until(&mut app, |world| {
+        clients
+            .iter()
+            .all(|client| world.get::<Session>(*client).is_some())
+            .then_some(())
+    });
+
+    let requests = &app.world().resource::<Requests>().0;
+    assert_eq!(NUM_CLIENTS, requests.len());
+    for pair in requests.windows(2) {
+        let gap = pair[1].saturating_duration_since(pair[0]);
+        assert!(gap + TOLERANCE >= INTERVAL, "requests only {gap:?} apart");
+    }
+}
+
+#[test]
+fn reject_over_limit() {
+    let (mut app, clients) = setup(
+        AcceptRateLimit::new(1, Duration::from_secs(60)).with_overflow(RateLimitOverflow::Reject),
+    );
+    update_until(&mut app, |world| {
+        (world.resource::<Rejected>().0 == NUM_CLIENTS - 1).then_some(())
+    });
+    update_until(&mut app, |world| {
+        clients
+            .iter()
+            .any(|client| world.get::<Session>(*client).is_some())
+            .then_some(())
+    });
+
+    assert_eq!(1, app.world().resource::<Requests>().0.len());
+}
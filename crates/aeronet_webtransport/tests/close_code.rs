@@ -0,0 +1,37 @@
+#![expect(missing_docs, clippy::unwrap_used, reason = "testing")]
+
+mod common;
+
+use {
+    aeronet_io::connection::{Disconnect, DisconnectReason, Disconnected},
+    bevy_ecs::prelude::*,
+    common::{setup, update_until},
+};
+
+#[derive(Debug, Default, Resource)]
+struct ClientDisconnected(Option<(String, Option<u32>)>);
+
+#[test]
+fn peer_observes_close_code() {
+    const REASON: &str = "banned";
+    const CODE: u32 = 403;
+
+    let (mut app, client, server_session) = setup();
+    app.init_resource::<ClientDisconnected>();
+    app.world_mut().entity_mut(client).observe(
+        |trigger: Trigger<Disconnected>, mut dc: ResMut<ClientDisconnected>| {
+            let Disconnected { reason, code } = trigger.event();
+            let DisconnectReason::Peer(reason) = reason else {
+                panic!("expected peer disconnect, got {reason:?}");
+            };
+            dc.0 = Some((reason.clone(), *code));
+        },
+    );
+
+    app.world_mut()
+        .trigger_targets(Disconnect::new(REASON).with_code(CODE), server_session);
+    let dc = update_until(&mut app, |world| {
+        world.resource_mut::<ClientDisconnected>().0.take()
+    });
+    assert_eq!((REASON.to_owned(), Some(CODE)), dc);
+}
@@ -0,0 +1,89 @@
+//! Helpers shared between the integration tests of this crate.
+
+#![allow(dead_code, reason = "not every test uses every helper")]
+
+use {
+    aeronet_io::{Session, connection::LocalAddr},
+    aeronet_webtransport::{
+        client::{ClientConfig, WebTransportClient, WebTransportClientPlugin},
+        server::{
+            ServerConfig, SessionRequest, SessionResponse, WebTransportServer,
+            WebTransportServerPlugin,
+        },
+        wtransport::Identity,
+    },
+    bevy_app::prelude::*,
+    bevy_ecs::prelude::*,
+    bevy_hierarchy::Parent,
+    core::time::Duration,
+    std::thread,
+};
+
+/// Maximum number of updates that [`update_until`] waits for.
+pub const MAX_UPDATES: usize = 1000;
+
+/// Updates the app until `f` returns [`Some`], panicking if this takes more
+/// than [`MAX_UPDATES`] updates.
+pub fn update_until<T>(app: &mut App, mut f: impl FnMut(&mut World) -> Option<T>) -> T {
+    for _ in 0..MAX_UPDATES {
+        app.update();
+        if let Some(value) = f(app.world_mut()) {
+            return value;
+        }
+        thread::sleep(Duration::from_millis(5));
+    }
+    panic!("condition not met after {MAX_UPDATES} updates");
+}
+
+/// Opens a server, and connects a client to it which the server accepts.
+///
+/// Returns the app, the client, and the server's session for that client.
+pub fn setup() -> (App, Entity, Entity) {
+    let mut app = App::new();
+    app.add_plugins((WebTransportClientPlugin, WebTransportServerPlugin))
+        .add_observer(|mut trigger: Trigger<SessionRequest>| {
+            trigger.event_mut().respond(SessionResponse::Accepted);
+        });
+
+    let identity = Identity::self_signed(["localhost", "127.0.0.1"]).unwrap();
+    let cert_hash = identity.certificate_chain().as_slice()[0].hash();
+    let server_config: ServerConfig = ServerConfig::builder()
+        .with_bind_default(0)
+        .with_identity(&identity)
+        .build();
+    let server = app
+        .world_mut()
+        .commands()
+        .spawn_empty()
+        .queue(WebTransportServer::open(server_config))
+        .id();
+    app.world_mut().flush();
+    let server_port = update_until(&mut app, |world| {
+        world.get::<LocalAddr>(server).map(|a| a.0.port())
+    });
+
+    let client_config: ClientConfig = ClientConfig::builder()
+        .with_bind_default()
+        .with_server_certificate_hashes([cert_hash])
+        .build();
+    let client = app
+        .world_mut()
+        .commands()
+        .spawn_empty()
+        .queue(WebTransportClient::connect(
+            client_config,
+            format!("https://127.0.0.1:{server_port}"),
+        ))
+        .id();
+    app.world_mut().flush();
+    update_until(&mut app, |world| world.get::<Session>(client).map(drop));
+
+    let server_session = update_until(&mut app, |world| {
+        world
+            .query_filtered::<(Entity, &Parent), With<Session>>()
+            .iter(world)
+            .find(|(_, parent)| parent.get() == server)
+            .map(|(entity, _)| entity)
+    });
+    (app, client, server_session)
+}
@@ -0,0 +1,10 @@
+#![expect(missing_docs, reason = "testing")]
+
+mod common;
+
+use {aeronet_io::conformance, common::setup};
+
+#[test]
+fn conformance() {
+    conformance::run(setup);
+}
@@ -0,0 +1,56 @@
+#![expect(missing_docs, clippy::unwrap_used, reason = "testing")]
+
+mod common;
+
+use {
+    aeronet_io::Session,
+    aeronet_webtransport::session::RawDatagrams,
+    bytes::Bytes,
+    common::{setup, update_until},
+};
+
+#[test]
+fn raw_datagram_round_trip() {
+    const DATAGRAM: &[u8] = b"raw datagram";
+    const PACKET: &[u8] = b"packet";
+
+    let (mut app, client, server_session) = setup();
+
+    let mut client_ref = app.world_mut().entity_mut(client);
+    client_ref
+        .get_mut::<RawDatagrams>()
+        .unwrap()
+        .send
+        .push(Bytes::from_static(DATAGRAM));
+    client_ref
+        .get_mut::<Session>()
+        .unwrap()
+        .send
+        .push(Bytes::from_static(PACKET));
+
+    let (mut datagrams, mut packets) = (Vec::new(), Vec::new());
+    update_until(&mut app, |world| {
+        let mut session = world.entity_mut(server_session);
+        datagrams.extend(
+            session
+                .get_mut::<RawDatagrams>()
+                .unwrap()
+                .recv
+                .drain(..)
+                .map(|datagram| datagram.payload),
+        );
+        packets.extend(
+            session
+                .get_mut::<Session>()
+                .unwrap()
+                .recv
+                .drain(..)
+                .map(|packet| packet.payload),
+        );
+        (!datagrams.is_empty() && !packets.is_empty()).then_some(())
+    });
+
+    // datagrams and packets are kept separate
+    assert_eq!(vec![DATAGRAM], datagrams);
+    assert_eq!(vec![PACKET], packets);
+}
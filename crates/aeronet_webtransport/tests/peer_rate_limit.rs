@@ -0,0 +1,105 @@
+#![expect(missing_docs, clippy::unwrap_used, reason = "testing")]
+
+mod common;
+
+use {
+    aeronet_io::{
+        Session,
+        connection::{DisconnectReason, Disconnected, LocalAddr},
+        server::PeerRateLimit,
+    },
+    aeronet_webtransport::{
+        client::{ClientConfig, WebTransportClient, WebTransportClientPlugin},
+        server::{
+            ServerConfig, SessionRequest, SessionResponse, WebTransportServer,
+            WebTransportServerPlugin,
+        },
+        wtransport::Identity,
+    },
+    bevy_app::prelude::*,
+    bevy_ecs::prelude::*,
+    bevy_hierarchy::Children,
+    common::update_until,
+    core::time::Duration,
+};
+
+const MAX_ATTEMPTS: usize = 2;
+const NUM_CLIENTS: usize = 5;
+
+#[derive(Debug, Default, Resource)]
+struct Requests(usize);
+
+#[derive(Debug, Default, Resource)]
+struct Refused(usize);
+
+#[test]
+fn refuse_over_limit() {
+    let mut app = App::new();
+    app.add_plugins((WebTransportClientPlugin, WebTransportServerPlugin))
+        .init_resource::<Requests>()
+        .init_resource::<Refused>()
+        .add_observer(
+            |mut trigger: Trigger<SessionRequest>, mut requests: ResMut<Requests>| {
+                requests.0 += 1;
+                trigger.event_mut().respond(SessionResponse::Accepted);
+            },
+        );
+
+    let identity = Identity::self_signed(["localhost", "127.0.0.1"]).unwrap();
+    let cert_hash = identity.certificate_chain().as_slice()[0].hash();
+    let server_config: ServerConfig = ServerConfig::builder()
+        .with_bind_default(0)
+        .with_identity(&identity)
+        .build();
+    let server = app
+        .world_mut()
+        .commands()
+        .spawn(PeerRateLimit::new(MAX_ATTEMPTS, Duration::from_secs(60)))
+        .queue(WebTransportServer::open(server_config))
+        .id();
+    app.world_mut().flush();
+    let server_port = update_until(&mut app, |world| {
+        world.get::<LocalAddr>(server).map(|a| a.0.port())
+    });
+
+    // every client connects from the same IP
+    let clients = (0..NUM_CLIENTS)
+        .map(|_| {
+            let client_config: ClientConfig = ClientConfig::builder()
+                .with_bind_default()
+                .with_server_certificate_hashes([cert_hash.clone()])
+                .build();
+            app.world_mut()
+                .commands()
+                .spawn_empty()
+                .queue(WebTransportClient::connect(
+                    client_config,
+                    format!("https://127.0.0.1:{server_port}"),
+                ))
+                .observe(
+                    |trigger: Trigger<Disconnected>, mut refused: ResMut<Refused>| {
+                        assert!(matches!(trigger.event().reason, DisconnectReason::Error(_)));
+                        refused.0 += 1;
+                    },
+                )
+                .id()
+        })
+        .collect::<Vec<_>>();
+    app.world_mut().flush();
+
+    update_until(&mut app, |world| {
+        let connected = clients
+            .iter()
+            .filter(|client| world.get::<Session>(**client).is_some())
+            .count();
+        (connected == MAX_ATTEMPTS && world.resource::<Refused>().0 == NUM_CLIENTS - MAX_ATTEMPTS)
+            .then_some(())
+    });
+
+    assert_eq!(MAX_ATTEMPTS, app.world().resource::<Requests>().0);
+    let num_sessions = app
+        .world()
+        .get::<Children>(server)
+        .map_or(0, |children| children.len());
+    assert_eq!(MAX_ATTEMPTS, num_sessions);
+}
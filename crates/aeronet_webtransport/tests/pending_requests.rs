@@ -0,0 +1,173 @@
+#![expect(missing_docs, clippy::unwrap_used, reason = "testing")]
+
+mod common;
+
+use {
+    aeronet_io::{
+        Session,
+        connection::{DisconnectReason, Disconnected, LocalAddr},
+    },
+    aeronet_webtransport::{
+        client::{ClientConfig, WebTransportClient, WebTransportClientPlugin},
+        server::{
+            AcceptRateLimit, PendingSessionRequests, ServerConfig, SessionRequest, SessionResponse,
+            WebTransportServer, WebTransportServerPlugin,
+        },
+        wtransport::Identity,
+    },
+    bevy_app::prelude::*,
+    bevy_ecs::prelude::*,
+    common::update_until,
+    core::time::Duration,
+};
+
+const NUM_CLIENTS: usize = 3;
+
+#[derive(Debug, Default, Resource)]
+struct Triggered(usize);
+
+#[derive(Debug, Default, Resource)]
+struct Rejected(usize);
+
+/// Opens a server which defers every session request, and starts connecting
+/// [`NUM_CLIENTS`] clients to it all at once.
+fn setup(limit: Option<AcceptRateLimit>) -> (App, Entity, Vec<Entity>) {
+    let mut app = App::new();
+    app.add_plugins((WebTransportClientPlugin, WebTransportServerPlugin))
+        .init_resource::<Triggered>()
+        .init_resource::<Rejected>()
+        .add_observer(
+            |mut trigger: Trigger<SessionRequest>, mut triggered: ResMut<Triggered>| {
+                triggered.0 += 1;
+                trigger.event_mut().defer();
+            },
+        );
+
+    let identity = Identity::self_signed(["localhost", "127.0.0.1"]).unwrap();
+    let cert_hash = identity.certificate_chain().as_slice()[0].hash();
+    let server_config: ServerConfig = ServerConfig::builder()
+        .with_bind_default(0)
+        .with_identity(&identity)
+        .build();
+    let mut server = app.world_mut().spawn_empty();
+    if let Some(limit) = limit {
+        server.insert(limit);
+    }
+    let server = server.id();
+    app.world_mut()
+        .commands()
+        .entity(server)
+        .queue(WebTransportServer::open(server_config));
+    app.world_mut().flush();
+    let server_port = update_until(&mut app, |world| {
+        world.get::<LocalAddr>(server).map(|a| a.0.port())
+    });
+
+    let clients = (0..NUM_CLIENTS)
+        .map(|_| {
+            let client_config: ClientConfig = ClientConfig::builder()
+                .with_bind_default()
+                .with_server_certificate_hashes([cert_hash.clone()])
+                .build();
+            app.world_mut()
+                .commands()
+                .spawn_empty()
+                .queue(WebTransportClient::connect(
+                    client_config,
+                    format!("https://127.0.0.1:{server_port}"),
+                ))
+                .observe(
+                    |trigger: Trigger<Disconnected>, mut rejected: ResMut<Rejected>| {
+                        assert!(matches!(trigger.event().reason, DisconnectReason::Error(_)));
+                        rejected.0 += 1;
+                    },
+                )
+                .id()
+        })
+        .collect::<Vec<_>>();
+    app.world_mut().flush();
+    (app, server, clients)
+}
+
+fn wait_for_pending(app: &mut App, server: Entity) {
+    update_until(app, |world| {
+        (world.get::<PendingSessionRequests>(server).unwrap().len() == NUM_CLIENTS).then_some(())
+    });
+}
+
+fn num_connected(world: &World, clients: &[Entity]) -> usize {
+    clients
+        .iter()
+        .filter(|client| world.get::<Session>(**client).is_some())
+        .count()
+}
+
+#[test]
+fn deferred_pending_until_responded() {
+    let (mut app, server, clients) = setup(None);
+    wait_for_pending(&mut app, server);
+
+    let pending = app.world().get::<PendingSessionRequests>(server).unwrap();
+    assert!(pending.iter().all(|(_, request)| !request.queued));
+    assert_eq!(NUM_CLIENTS, app.world().resource::<Triggered>().0);
+    assert_eq!(0, num_connected(app.world(), &clients));
+
+    let mut pending = app
+        .world_mut()
+        .get_mut::<PendingSessionRequests>(server)
+        .unwrap();
+    let sessions = pending
+        .iter()
+        .map(|(session, _)| session)
+        .collect::<Vec<_>>();
+    assert!(pending.respond(sessions[0], SessionResponse::Accepted));
+    for session in &sessions[1..] {
+        assert!(pending.respond(*session, SessionResponse::Forbidden));
+    }
+    assert!(pending.is_empty());
+
+    update_until(&mut app, |world| {
+        (world.resource::<Rejected>().0 == NUM_CLIENTS - 1).then_some(())
+    });
+    update_until(&mut app, |world| {
+        (num_connected(world, &clients) == 1).then_some(())
+    });
+}
+
+#[test]
+fn queued_pending_until_responded() {
+    let (mut app, server, clients) = setup(Some(AcceptRateLimit::new(1, Duration::from_secs(60))));
+    wait_for_pending(&mut app, server);
+
+    // only the first request got through the rate limit
+    assert_eq!(1, app.world().resource::<Triggered>().0);
+    let pending = app.world().get::<PendingSessionRequests>(server).unwrap();
+    let queued = pending
+        .iter()
+        .filter(|(_, request)| request.queued)
+        .map(|(session, _)| session)
+        .collect::<Vec<_>>();
+    assert_eq!(NUM_CLIENTS - 1, queued.len());
+
+    let mut pending = app
+        .world_mut()
+        .get_mut::<PendingSessionRequests>(server)
+        .unwrap();
+    for session in queued {
+        assert!(pending.respond(session, SessionResponse::Forbidden));
+    }
+    assert_eq!(1, pending.len());
+
+    update_until(&mut app, |world| {
+        (world.resource::<Rejected>().0 == NUM_CLIENTS - 1).then_some(())
+    });
+    assert_eq!(
+        0,
+        app.world()
+            .get::<AcceptRateLimit>(server)
+            .unwrap()
+            .num_queued()
+    );
+    assert_eq!(1, app.world().resource::<Triggered>().0);
+    assert_eq!(0, num_connected(app.world(), &clients));
+}
@@ -0,0 +1,57 @@
+#![expect(missing_docs, clippy::unwrap_used, reason = "testing")]
+
+mod common;
+
+use {
+    aeronet_webtransport::session::{StreamLane, UniStreams},
+    bytes::Bytes,
+    common::{setup, update_until},
+};
+
+#[test]
+fn large_transfer_with_backpressure() {
+    const LANE: StreamLane = StreamLane(3);
+    const FILE_LEN: usize = 4 * 1024 * 1024;
+    const MAX_BUFFERED: usize = 64 * 1024;
+
+    let file = (0..FILE_LEN)
+        .map(|i| u8::try_from(i % 251).unwrap())
+        .collect::<Vec<_>>();
+    let file = Bytes::from(file);
+
+    let (mut app, client, server_session) = setup();
+    app.world_mut()
+        .get_mut::<UniStreams>(server_session)
+        .unwrap()
+        .max_buffered = MAX_BUFFERED;
+
+    let mut sent = 0;
+    let mut received = Vec::with_capacity(FILE_LEN);
+    update_until(&mut app, |world| {
+        let mut streams = world.get_mut::<UniStreams>(server_session).unwrap();
+        assert!(streams.buffered(LANE) <= MAX_BUFFERED);
+        let writable = streams.writable(LANE).min(FILE_LEN - sent);
+        if writable > 0 {
+            streams
+                .send(LANE, file.slice(sent..sent + writable))
+                .unwrap();
+            sent += writable;
+        }
+        assert!(streams.buffered(LANE) <= MAX_BUFFERED);
+        if sent < FILE_LEN {
+            // the lane is full, so we're told to slow down
+            let err = streams.send(LANE, Bytes::from_static(&[0])).unwrap_err();
+            assert_eq!(0, err.writable);
+        }
+
+        let mut streams = world.get_mut::<UniStreams>(client).unwrap();
+        for chunk in streams.recv.drain(..) {
+            assert_eq!(LANE, chunk.lane);
+            received.extend_from_slice(&chunk.payload);
+        }
+        (received.len() >= FILE_LEN).then_some(())
+    });
+
+    assert_eq!(FILE_LEN, received.len());
+    assert_eq!(file, received);
+}
@@ -19,7 +19,6 @@ use {
             Session, SessionEndpoint,
             bytes::Bytes,
             connection::{Disconnect, DisconnectReason, Disconnected},
-            web_time,
         },
         transport::{
             AeronetTransportPlugin, Transport, TransportConfig,
@@ -161,8 +160,8 @@ fn on_connected(
         LANES,
         // Don't use `std::time::Instant::now`!
         // On WASM that function will panic.
-        // Instead, use the re-exported `web_time`.
-        web_time::Instant::now(),
+        // Instead, use `aeronet::now`, which uses the right clock.
+        aeronet::now(),
     )
     .expect("packet MTU should be large enough to support transport");
     commands.entity(entity).insert(transport);
@@ -228,9 +227,7 @@ fn ui(
 
                 let msg = Bytes::from(msg);
                 // We ignore the resulting `MessageKey`, since we don't need it.
-                _ = transport
-                    .send
-                    .push(SEND_LANE, msg, web_time::Instant::now());
+                _ = transport.send.push(SEND_LANE, msg, aeronet::now());
             }
 
             if ui.button("Disconnect").clicked() {
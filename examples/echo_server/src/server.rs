@@ -5,7 +5,6 @@ use {
             bytes::Bytes,
             connection::{DisconnectReason, Disconnected, LocalAddr},
             server::Server,
-            web_time,
         },
         transport::{AeronetTransportPlugin, Transport, lane::LaneKind},
     },
@@ -106,10 +105,10 @@ fn on_connected(
         LANES,
         // Don't use `std::time::Instant::now`!
         // On WASM that function will panic.
-        // Instead, use the re-exported `web_time`.
+        // Instead, use `aeronet::now`, which uses the right clock.
         // (We're writing a server, so we'll never run it on WASM.
-        // But still, use `web_time` for consistency with the client code.)
-        web_time::Instant::now(),
+        // But still, use `aeronet::now` for consistency with the client code.)
+        aeronet::now(),
     )
     .expect("packet MTU should be large enough to support transport");
     commands.entity(client).insert(transport);
@@ -168,7 +167,7 @@ fn echo_messages(
             // We ignore the resulting `MessageKey`, since we don't need it.
             _ = transport
                 .send
-                .push(msg.lane, Bytes::from(reply), web_time::Instant::now());
+                .push(msg.lane, Bytes::from(reply), aeronet::now());
         }
 
         for _ in transport.recv.acks.drain() {
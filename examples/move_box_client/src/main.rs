@@ -119,7 +119,7 @@ fn on_disconnected(
     mut game_state: ResMut<NextState<GameState>>,
 ) {
     let session = trigger.entity();
-    let Disconnected { reason } = trigger.event();
+    let Disconnected { reason, .. } = trigger.event();
     let name = names
         .get(session)
         .expect("our session entity should have a name");